@@ -21,7 +21,7 @@ import {
 /**
  * Supported database types.
  */
-export type DatabaseType = "PostgreSQL" | "MySQL" | "SQLite" | "MongoDB";
+export type DatabaseType = "PostgreSQL" | "MySQL" | "SQLite" | "MongoDB" | "SurrealDB";
 
 /**
  * Configuration for database connection.
@@ -71,6 +71,7 @@ const defaultPorts: Record<DatabaseType, number> = {
   MySQL: 3306,
   PostgreSQL: 5432,
   MongoDB: 27017,
+  SurrealDB: 8000,
   SQLite: 0, // Port is not typically used for SQLite file-based connections
 };
 
@@ -118,6 +119,7 @@ const DatabaseConnection: React.FC<DatabaseConnectionProps> = ({
     
     let protocol = dbType.toLowerCase();
     if (dbType === 'PostgreSQL') protocol = 'postgresql'; // Common alias
+    if (dbType === 'SurrealDB') protocol = 'http'; // Reached through its HTTP API
 
     return `${protocol}://${creds}${host}${portNumber}${dbSuffix}`;
   }, [host, port, username, password, databaseName, dbType]);
@@ -299,6 +301,7 @@ const DatabaseConnection: React.FC<DatabaseConnectionProps> = ({
                 <MenuItem value="MySQL">MySQL</MenuItem>
                 <MenuItem value="PostgreSQL">PostgreSQL</MenuItem>
                 <MenuItem value="MongoDB">MongoDB</MenuItem>
+                <MenuItem value="SurrealDB">SurrealDB</MenuItem>
                 <MenuItem value="SQLite">SQLite</MenuItem>
               </Select>
             </FormControl>
@@ -46,6 +46,8 @@ interface Role {
 interface Permission {
   action: string;
   subject: string;
+  roles?: string[];
+  effect?: 'Allow' | 'Deny';
 }
 
 interface Authorization {
@@ -13,7 +13,7 @@ export interface DbConfig {
   username: string;
   password: string;
   database_name: string;
-  db_type: "PostgreSQL" | "MySQL" | "SQLite" | "MongoDB";
+  db_type: "PostgreSQL" | "MySQL" | "SQLite" | "MongoDB" | "SurrealDB";
   connection_string: string;
   ssl_enabled: boolean;
   max_connections?: number;
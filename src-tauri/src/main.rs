@@ -271,6 +271,7 @@ async fn save_configuration(config: ApiConfig) -> Result<String, String> {
         max_payload_size_mb: config.server.max_payload_size_mb,
         rate_limiting: config.server.rate_limiting,
         logging_level: config.server.logging_level,
+        ..config.server
     };
 
     // Create a new config with all required fields
@@ -436,6 +437,7 @@ async fn start_api_server() -> Result<String, String> {
             max_payload_size_mb: config.server.max_payload_size_mb,
             rate_limiting: config.server.rate_limiting,
             logging_level: config.server.logging_level,
+            ..config.server
         },
         database: DatabaseConfig {
             db_type: config.database.db_type.clone(),
@@ -454,6 +456,8 @@ async fn start_api_server() -> Result<String, String> {
         auth: None,
         cors: CorsConfig::default(),
         documentation: DocumentationConfig::default(),
+        backup: None,
+        health: None,
     };
 
     // Set server as starting and reset metrics
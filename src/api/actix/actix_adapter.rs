@@ -0,0 +1,714 @@
+use crate::api::adapters::api_adapter::{ApiAdapter, ApiAdapterTrait, ApiRequest, ApiResponse, ApiResponseBody};
+use crate::api::adapters::change_access::ChangeAccess;
+use crate::api::adapters::maintenance::{MaintenanceMode, MAINTENANCE_PATH};
+use crate::api::adapters::oauth::{OAuthProviders, OAUTH_LOGIN_PATH};
+use crate::api::adapters::proxy::ProxyTrust;
+use crate::api::adapters::router::ROUTES_PATH;
+use crate::api::adapters::request_timeout::RequestTimeouts;
+use crate::api::adapters::schema_drift::SchemaDriftMonitor;
+use crate::api::adapters::subscriptions::subscription_stream;
+use crate::api::adapters::serving::{self, change_stream, listeners, max_payload_size_mb, render_body, ServedApi};
+use crate::api::adapters::uploads::{UploadStore, UPLOADS_PATH};
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::docs::admin_panel::{AdminPanel, ADMIN_PANEL_PATH};
+use crate::api::docs::openapi::{ApiDocs, OPENAPI_FILE};
+use crate::api::handlers::common::request_id::{request_id, tag_response};
+use crate::api::handlers::common::utils::{default_headers, error_response};
+use crate::api::rocket::rate_limit::RateLimiter;
+use crate::config::configuration::Config;
+use crate::config::schema::CONFIG_SCHEMA_FILE;
+use crate::config::specific::entity_config::HttpMethod;
+use crate::config::specific::server_config::{ErrorFormat, ResponseFormat};
+use crate::error::{Result, RusterApiError};
+use crate::events::event_bus::EventBus;
+#[cfg(feature = "graphql")]
+use crate::api::graphql::schema::{graphiql, GraphqlEndpoint, GRAPHQL_PATH};
+use crate::health::health_monitor::HealthRegistry;
+use actix_web::http::{KeepAlive, Method, StatusCode};
+use actix_web::web::{self, BytesMut};
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer};
+use actix_ws::Message as WsMessage;
+use futures::future::{ready, try_join_all};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// Address the server binds to when `server.host` is empty
+const DEFAULT_HOST: &str = "127.0.0.1";
+/// Port the server binds to when `server.port` is 0
+const DEFAULT_PORT: u16 = 8000;
+
+/// State of the Actix routes serving an API, registered as app data
+pub struct ActixApiState<T: ApiEntity> {
+    pub api_adapter: Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+    pub health: HealthRegistry,
+    pub schema_drift: Option<SchemaDriftMonitor>,
+    /// Bus the changes of the entities are streamed from
+    pub events: EventBus,
+    /// GraphQL endpoint, when configured
+    #[cfg(feature = "graphql")]
+    pub graphql: Option<GraphqlEndpoint>,
+    /// OpenAPI document and its viewer, when enabled
+    pub docs: Option<ApiDocs>,
+    /// Admin panel of the entities, when enabled
+    pub admin_panel: Option<AdminPanel>,
+    /// Files uploaded to the Binary fields of the entities
+    pub uploads: UploadStore,
+    /// Trust of the forwarding headers of the reverse proxies in front of the server
+    pub proxy: ProxyTrust,
+    /// Maintenance mode rejecting the changes of the entities
+    pub maintenance: MaintenanceMode,
+    /// OAuth providers signing users in, when enabled
+    pub oauth: Option<OAuthProviders>,
+    /// Access of the callers to the streamed changes of the entities
+    pub changes: ChangeAccess,
+    pub error_format: ErrorFormat,
+    pub response_format: ResponseFormat,
+    pub rate_limiter: RateLimiter,
+    /// Timeouts of the requests to the endpoints
+    pub timeouts: RequestTimeouts,
+    /// Maximum size of request bodies in megabytes
+    pub max_payload_size_mb: u32,
+}
+
+impl<T: ApiEntity> ActixApiState<T> {
+    /// Prepares an adapter to be served by Actix, starting the backup scheduler and the health monitor
+    pub fn new(api_adapter: ApiAdapter<T>) -> Result<web::Data<Self>> {
+        let config = api_adapter.config.clone();
+        Ok(Self::from_served(ServedApi::new(api_adapter)?, &config))
+    }
+
+    fn from_served(served: ServedApi<T>, config: &Config) -> web::Data<Self> {
+        web::Data::new(Self {
+            api_adapter: served.api_adapter,
+            health: served.health,
+            schema_drift: served.schema_drift,
+            events: served.events,
+            #[cfg(feature = "graphql")]
+            graphql: served.graphql,
+            docs: served.docs,
+            admin_panel: served.admin_panel,
+            uploads: served.uploads,
+            proxy: served.proxy,
+            maintenance: served.maintenance,
+            oauth: served.oauth,
+            changes: served.changes,
+            error_format: config.server.error_format,
+            response_format: config.server.response_format,
+            rate_limiter: RateLimiter::new(config),
+            timeouts: RequestTimeouts::new(config),
+            max_payload_size_mb: max_payload_size_mb(config),
+        })
+    }
+}
+
+/// Registers the API routes under `/api`, along with `/metrics`, `/health`, `/admin/schema-drift`,
+/// `/admin/maintenance`, `/_routes`, the `/admin` panel, the `/ws` subscriptions WebSocket, the `/uploads` downloads and, with the graphql feature, `/graphql`.
+/// Embedding applications register an `ActixApiState` as app data and call this from `App::configure`.
+/// CORS is left to the application, like with the `actix-cors` middleware.
+pub fn configure(config: &mut web::ServiceConfig) {
+    configure_admin(config);
+    configure_api(config);
+}
+
+/// Registers the admin routes: `/metrics`, `/health`, `/admin/schema-drift`, `/admin/maintenance`,
+/// `/_routes` and the `/admin` panel.
+/// Embedding applications serving them on another listener call this and `configure_api` instead
+/// of `configure`.
+pub fn configure_admin(config: &mut web::ServiceConfig) {
+    config
+        .route("/metrics", web::get().to(metrics_handler))
+        .route("/health", web::get().to(health_handler))
+        .route("/admin/schema-drift", web::get().to(schema_drift_handler))
+        .route(MAINTENANCE_PATH, web::get().to(maintenance_handler))
+        .route(MAINTENANCE_PATH, web::put().to(toggle_maintenance_handler))
+        .route(ROUTES_PATH, web::get().to(routes_handler))
+        .route(ADMIN_PANEL_PATH, web::get().to(admin_panel_handler));
+}
+
+/// Registers the routes of `configure` other than the admin ones
+pub fn configure_api(config: &mut web::ServiceConfig) {
+    config
+        .route("/ws", web::get().to(subscriptions_handler))
+        .route(&format!("{}/{{folder}}/{{file}}", UPLOADS_PATH), web::get().to(download_handler));
+    #[cfg(feature = "graphql")]
+    config
+        .route(GRAPHQL_PATH, web::post().to(graphql_handler))
+        .route(GRAPHQL_PATH, web::get().to(graphiql_handler));
+    config
+        .route("/api/{entity}/events", web::get().to(events_handler))
+        .route("/api/{path:.*}", web::route().to(api_handler));
+}
+
+/// Registers the documentation page at its configured path, and the OpenAPI document and the
+/// schema of the configuration under it. Embedding applications call this from `App::configure`
+/// as well when documentation is enabled.
+pub fn configure_docs(config: &mut web::ServiceConfig, docs: &ApiDocs) {
+    let spec_path = format!("{}/{}", docs.path.trim_end_matches('/'), OPENAPI_FILE);
+    let schema_path = format!("{}/{}", docs.path.trim_end_matches('/'), CONFIG_SCHEMA_FILE);
+    config
+        .route(&docs.path, web::get().to(docs_handler))
+        .route(&spec_path, web::get().to(openapi_handler))
+        .route(&schema_path, web::get().to(config_schema_handler));
+}
+
+/// Registers the OAuth sign in at `/auth/oauth/{provider}` and the path of the callback URL.
+/// Embedding applications call this from `App::configure` as well when OAuth is enabled.
+pub fn configure_oauth(config: &mut web::ServiceConfig, oauth: &OAuthProviders) {
+    config
+        .route(&format!("{}/{{provider}}", OAUTH_LOGIN_PATH), web::get().to(oauth_login_handler))
+        .route(&oauth.callback_path(), web::get().to(oauth_callback_handler));
+}
+
+// Main function to start the Actix server, binding to `server.host` and `server.port`
+pub async fn start_server<T: ApiEntity>(api_adapter: ApiAdapter<T>) -> Result<()> {
+    let config = api_adapter.config.clone();
+    launch(ActixApiState::new(api_adapter)?, &config).await
+}
+
+// Starts a stub server replaying the recording configured in `ServerConfig.record_replay`,
+// without connecting to any datasource
+pub async fn start_replay_server(config: &Config) -> Result<()> {
+    launch(ActixApiState::from_served(ServedApi::replay(config)?, config), config).await
+}
+
+// Binds and runs the Actix servers serving the given state: one per listener of `server.listeners`,
+// or a single one at `server.socket_path`, or else at `server.host` and `server.port`
+async fn launch<T: ApiEntity>(state: web::Data<ActixApiState<T>>, config: &Config) -> Result<()> {
+    if config.server.tls.is_some() {
+        return Err(RusterApiError::ConfigError("server.tls is only supported by the Rocket framework".to_string()));
+    }
+    if !config.server.static_dirs.is_empty() {
+        return Err(RusterApiError::ConfigError("server.static_dirs is only supported by the Rocket framework".to_string()));
+    }
+    if let Some(path) = &config.server.socket_path {
+        return serve(state, config, Binding::Unix(PathBuf::from(path)), true).await;
+    }
+    let listeners = listeners(config)?;
+    if listeners.is_empty() {
+        let host = match config.server.host.trim() {
+            "" => DEFAULT_HOST.to_string(),
+            host => host.to_string(),
+        };
+        let port = match u16::try_from(config.server.port) {
+            Ok(0) => DEFAULT_PORT,
+            Ok(port) => port,
+            Err(_) => return Err(RusterApiError::ConfigError(format!("Invalid server port {}", config.server.port))),
+        };
+        return serve(state, config, Binding::Tcp(host, port), true).await;
+    }
+    let servers = listeners.into_iter()
+        .map(|listener| serve(state.clone(), config, Binding::Tcp(listener.host, listener.port), listener.admin));
+    try_join_all(servers).await.map(|_| ())
+}
+
+// Address an Actix server binds to
+enum Binding {
+    Tcp(String, u16),
+    Unix(PathBuf),
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Tcp(host, port) => write!(f, "{}:{}", host, port),
+            Binding::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+// Removes the Unix socket left at a path by a previous run, so the server can bind to it again
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
+// Binds and runs an Actix server with the tuning of the configuration, serving the admin routes,
+// including the documentation, only when asked to
+async fn serve<T: ApiEntity>(state: web::Data<ActixApiState<T>>, config: &Config, binding: Binding, admin: bool)
+-> Result<()> {
+    info!("Starting Actix server on {}{}", binding, if admin { "" } else { " without the admin routes" });
+    let mut server = HttpServer::new(move || {
+        let docs = state.docs.clone().filter(|_| admin);
+        let oauth = state.oauth.clone();
+        App::new()
+            .app_data(state.clone())
+            .configure(|config| if admin { configure_admin(config) })
+            .configure(|config| if let Some(oauth) = &oauth { configure_oauth(config, oauth) })
+            .configure(configure_api)
+            .configure(|config| if let Some(docs) = &docs { configure_docs(config, docs) })
+    });
+    if let Some(tuning) = &config.server.tuning {
+        if let Some(seconds) = tuning.keep_alive_seconds {
+            server = server.keep_alive(match seconds {
+                0 => KeepAlive::Disabled,
+                seconds => KeepAlive::Timeout(Duration::from_secs(u64::from(seconds))),
+            });
+        }
+        if let Some(workers) = tuning.workers {
+            server = server.workers(workers);
+        }
+        if let Some(connections) = tuning.max_connections {
+            server = server.max_connections(connections);
+        }
+    }
+    let server = match &binding {
+        Binding::Tcp(host, port) => server.bind((host.as_str(), *port)),
+        #[cfg(unix)]
+        Binding::Unix(path) => remove_stale_socket(path).and_then(|()| server.bind_uds(path)),
+        #[cfg(not(unix))]
+        Binding::Unix(_) => Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Unix sockets require a Unix system")),
+    };
+    server
+        .map_err(|e| RusterApiError::ServerError(format!("Failed to bind Actix server to {}: {}", binding, e)))?
+        .run()
+        .await
+        .map_err(|e| RusterApiError::ServerError(format!("Failed to run Actix server: {}", e)))
+}
+
+/// Converts an API response into an Actix response, with the body in the configured response format
+fn http_response(api_response: ApiResponse<Value>, format: ResponseFormat) -> HttpResponse {
+    let status = StatusCode::from_u16(api_response.status).unwrap_or(StatusCode::OK);
+    let body = render_body(&api_response, format);
+
+    let mut response = HttpResponse::build(status);
+    let has_content_type = api_response.headers.keys().any(|key| key.eq_ignore_ascii_case("Content-Type"));
+    for (key, value) in api_response.headers {
+        response.append_header((key, value));
+    }
+    // If we have a body without a content type, set it to JSON
+    if !body.is_empty() && !has_content_type {
+        response.content_type("application/json");
+    }
+    response.body(body)
+}
+
+/// Headers of a request, keyed by lowercase name. Repeated headers are joined with commas.
+fn request_headers(request: &HttpRequest) -> HashMap<String, String> {
+    let mut headers: HashMap<String, String> = HashMap::new();
+    for (name, value) in request.headers() {
+        let value = value.to_str().unwrap_or_default();
+        headers.entry(name.as_str().to_lowercase())
+            .and_modify(|joined| {
+                joined.push_str(", ");
+                joined.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    headers
+}
+
+/// Address of the client of a request, behind the trusted proxies of `server.proxy`
+fn client_ip(request: &HttpRequest, headers: &HashMap<String, String>, state: &ActixApiState<Value>) -> Option<IpAddr> {
+    state.proxy.client(request.peer_addr().map(|address| address.ip()), headers)
+}
+
+/// Reads a request body, failing when it is larger than the limit in megabytes
+async fn read_bytes(mut payload: web::Payload, limit_mb: u32) -> Result<Vec<u8>> {
+    let limit = limit_mb as usize * 1024 * 1024;
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| RusterApiError::BadRequest(format!("Failed to read the request body: {}", e)))?;
+        if body.len() + chunk.len() > limit {
+            return Err(RusterApiError::PayloadTooLarge(format!("The request body exceeds the limit of {} MB", limit_mb)));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.to_vec())
+}
+
+/// Reads a request body, failing when it is larger than the limit in megabytes.
+/// Bodies that are not UTF-8 are treated as missing.
+async fn read_body(payload: web::Payload, limit_mb: u32) -> Result<Option<String>> {
+    Ok(String::from_utf8(read_bytes(payload, limit_mb).await?).ok())
+}
+
+/// Reads the body of a request to the endpoints, with the files uploaded by `multipart/form-data`
+/// forms, which are converted to the JSON body of the entity
+async fn request_body(payload: web::Payload, path: &str, headers: &HashMap<String, String>, state: &ActixApiState<Value>)
+-> Result<(Option<String>, Vec<PathBuf>)> {
+    if !UploadStore::is_form(headers) {
+        return Ok((read_body(payload, state.max_payload_size_mb).await?, Vec::new()));
+    }
+    let bytes = read_bytes(payload, state.max_payload_size_mb).await?;
+    let form = state.uploads.form_body(path, headers, bytes).await?;
+    Ok((Some(form.body), form.files))
+}
+
+/// Catch-all handler of the API routes. HEAD requests are processed as GET requests, and OPTIONS
+/// requests list the methods of the endpoints matching the path in the `Allow` header.
+async fn api_handler(
+    request: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Payload,
+    state: web::Data<ActixApiState<Value>>,
+) -> HttpResponse {
+    let path = path.into_inner();
+    let instance = format!("/api/{}", path);
+    let method = match *request.method() {
+        Method::GET | Method::HEAD => HttpMethod::GET,
+        Method::POST => HttpMethod::POST,
+        Method::PUT => HttpMethod::PUT,
+        Method::PATCH => HttpMethod::PATCH,
+        Method::DELETE => HttpMethod::DELETE,
+        Method::OPTIONS => return http_response(options_response(&state, &path), state.response_format),
+        _ => return HttpResponse::MethodNotAllowed().finish(),
+    };
+
+    let headers = request_headers(&request);
+    let request_id = request_id(&headers);
+    let (body, files) = match method {
+        HttpMethod::POST | HttpMethod::PUT | HttpMethod::PATCH => match request_body(payload, &path, &headers, &state).await {
+            Ok(request_body) => request_body,
+            Err(err) => {
+                let mut api_response = error_response(&err, state.error_format, &instance);
+                tag_response(&mut api_response, &request_id);
+                return http_response(api_response, state.response_format);
+            }
+        },
+        _ => (None, Vec::new()),
+    };
+    let query = web::Query::<HashMap<String, String>>::from_query(request.query_string())
+        .map(|query| query.into_inner())
+        .unwrap_or_default();
+
+    let api_request = ApiRequest {
+        method,
+        path,
+        params: HashMap::new(),
+        query,
+        headers,
+        body,
+        request_id,
+        auth: None,
+        tenant: None,
+    };
+    let client = client_ip(&request, &api_request.headers, &state);
+    let api_response = serving::process_request(state.api_adapter.clone(), &state.rate_limiter, &state.timeouts, state.error_format, api_request, client).await;
+    if api_response.status >= 400 {
+        state.uploads.discard(&files).await;
+    }
+    http_response(api_response, state.response_format)
+}
+
+/// Streams the changes of an entity as Server-Sent Events, until the client disconnects
+async fn events_handler(
+    request: HttpRequest,
+    entity: web::Path<String>,
+    state: web::Data<ActixApiState<Value>>,
+) -> HttpResponse {
+    let entity = entity.into_inner();
+    if state.api_adapter.route_for(&HttpMethod::GET, &entity).is_none() {
+        let err = RusterApiError::EntityNotFound(entity.to_string());
+        let mut api_response = error_response(&err, state.error_format, &format!("/api/{}/events", entity));
+        tag_response(&mut api_response, &request_id(&request_headers(&request)));
+        return http_response(api_response, state.response_format);
+    }
+    let headers = request_headers(&request);
+    let path = format!("{}/events", entity);
+    let reader = state.changes.admit(&path, headers.clone())
+        .and_then(|reader| reader.check(&entity).map(|_| reader));
+    let reader = match reader {
+        Ok(reader) => reader,
+        Err(err) => {
+            let mut api_response = error_response(&err, state.error_format, &format!("/api/{}", path));
+            tag_response(&mut api_response, &request_id(&headers));
+            return http_response(api_response, state.response_format);
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(change_stream(&state.events, &entity, reader).map(|frame| Ok::<_, actix_web::Error>(web::Bytes::from(frame))))
+}
+
+/// WebSocket where clients subscribe to the changes of entities
+async fn subscriptions_handler(
+    request: HttpRequest,
+    payload: web::Payload,
+    state: web::Data<ActixApiState<Value>>,
+) -> HttpResponse {
+    let headers = request_headers(&request);
+    let reader = match state.changes.admit("ws", headers.clone()) {
+        Ok(reader) => reader,
+        Err(err) => {
+            let mut api_response = error_response(&err, state.error_format, "/ws");
+            tag_response(&mut api_response, &request_id(&headers));
+            return http_response(api_response, state.response_format);
+        }
+    };
+    let (response, mut session, messages) = match actix_ws::handle(&request, payload) {
+        Ok(socket) => socket,
+        Err(_) => {
+            let err = RusterApiError::BadRequest("Expected a WebSocket handshake".to_string());
+            let mut api_response = error_response(&err, state.error_format, "/ws");
+            tag_response(&mut api_response, &request_id(&headers));
+            return http_response(api_response, state.response_format);
+        }
+    };
+
+    let pong_session = session.clone();
+    let incoming = messages
+        .take_while(|message| ready(matches!(message, Ok(message) if !matches!(message, WsMessage::Close(_)))))
+        .filter_map(move |message| {
+            let mut pong_session = pong_session.clone();
+            async move {
+                match message {
+                    Ok(WsMessage::Text(text)) => Some(text.to_string()),
+                    Ok(WsMessage::Ping(bytes)) => {
+                        let _ = pong_session.pong(&bytes).await;
+                        None
+                    }
+                    _ => None,
+                }
+            }
+        });
+    let api_adapter = state.api_adapter.clone();
+    let serves = move |entity: &str| api_adapter.route_for(&HttpMethod::GET, entity).is_some();
+    let events = state.events.clone();
+
+    actix_web::rt::spawn(async move {
+        let mut outgoing = Box::pin(subscription_stream(&events, Box::pin(incoming), serves, reader));
+        while let Some(text) = outgoing.next().await {
+            if session.text(text).await.is_err() {
+                return;
+            }
+        }
+        let _ = session.close(None).await;
+    });
+    response
+}
+
+/// Runs the GraphQL operations of a request
+#[cfg(feature = "graphql")]
+async fn graphql_handler(request: HttpRequest, payload: web::Payload, state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    let headers = request_headers(&request);
+    let result = match &state.graphql {
+        Some(graphql) => read_body(payload, state.max_payload_size_mb).await.map(|body| (graphql, body)),
+        None => Err(RusterApiError::NotFound("GraphQL is not enabled".to_string())),
+    };
+    let api_response = match result {
+        Ok((graphql, body)) => {
+            let client = client_ip(&request, &headers, &state);
+            serving::process_graphql(&graphql.schema, &state.rate_limiter, state.error_format, body, headers, client).await
+        }
+        Err(err) => {
+            let mut api_response = error_response(&err, state.error_format, GRAPHQL_PATH);
+            tag_response(&mut api_response, &request_id(&headers));
+            api_response
+        }
+    };
+    http_response(api_response, state.response_format)
+}
+
+/// Serves the GraphiQL IDE, when enabled
+#[cfg(feature = "graphql")]
+async fn graphiql_handler(request: HttpRequest, state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    if state.graphql.as_ref().is_some_and(|graphql| graphql.graphiql) {
+        return HttpResponse::Ok().content_type("text/html; charset=utf-8").body(graphiql());
+    }
+    let err = RusterApiError::NotFound("GraphiQL is not enabled".to_string());
+    let mut api_response = error_response(&err, state.error_format, GRAPHQL_PATH);
+    tag_response(&mut api_response, &request_id(&request_headers(&request)));
+    http_response(api_response, state.response_format)
+}
+
+/// Serves the page browsing the OpenAPI document
+async fn docs_handler(state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    match &state.docs {
+        Some(docs) => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(docs.page.clone()),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+/// Lists the endpoints of the API with their method, entity and whether they require authentication
+async fn routes_handler(state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    http_response(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(json!({ "routes": state.api_adapter.routes() }))),
+    }, state.response_format)
+}
+
+/// Reports whether the maintenance mode is on
+async fn maintenance_handler(state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    http_response(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(state.maintenance.status())),
+    }, state.response_format)
+}
+
+/// Turns the maintenance mode on or off with a `{"enabled": true}` body
+async fn toggle_maintenance_handler(payload: web::Payload, state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    let result = read_body(payload, state.max_payload_size_mb).await
+        .and_then(|body| state.maintenance.toggle(&body.unwrap_or_default()));
+    let api_response = match result {
+        Ok(status) => ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Json(status)),
+        },
+        Err(err) => error_response(&err, state.error_format, MAINTENANCE_PATH),
+    };
+    http_response(api_response, state.response_format)
+}
+
+/// Redirects to the authorization page of a provider to sign in with it
+async fn oauth_login_handler(provider: web::Path<String>, state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    let provider = provider.into_inner();
+    let result = match &state.oauth {
+        Some(oauth) => oauth.authorize_url(&provider),
+        None => Err(RusterApiError::NotFound("OAuth is not enabled".to_string())),
+    };
+    match result {
+        Ok(url) => HttpResponse::Found().insert_header(("Location", url)).finish(),
+        Err(err) => http_response(error_response(&err, state.error_format, &format!("{}/{}", OAUTH_LOGIN_PATH, provider)), state.response_format),
+    }
+}
+
+/// Exchanges the authorization code sent back by a provider for its tokens
+async fn oauth_callback_handler(request: HttpRequest, state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    let Some(oauth) = &state.oauth else {
+        return HttpResponse::NotFound().finish();
+    };
+    let query = web::Query::<HashMap<String, String>>::from_query(request.query_string())
+        .map(|query| query.into_inner())
+        .unwrap_or_default();
+    let api_response = match oauth.exchange(&query).await {
+        Ok(tokens) => ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Json(tokens)),
+        },
+        Err(err) => error_response(&err, state.error_format, &oauth.callback_path()),
+    };
+    http_response(api_response, state.response_format)
+}
+
+/// Serves the admin panel of the entities
+async fn admin_panel_handler(state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    match &state.admin_panel {
+        Some(panel) => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(panel.page.clone()),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+/// Serves the OpenAPI document of the API, with the public origin of the request as its server
+async fn openapi_handler(request: HttpRequest, state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    match &state.docs {
+        Some(docs) => {
+            let origin = state.proxy.origin(request.peer_addr().map(|address| address.ip()), &request_headers(&request));
+            HttpResponse::Ok().json(docs.spec_at(origin.as_deref()))
+        }
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+/// Serves the JSON Schema of the configuration files
+async fn config_schema_handler(state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    match &state.docs {
+        Some(docs) => HttpResponse::Ok().json(&docs.config_schema),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+/// Serves a file uploaded to a Binary field
+async fn download_handler(
+    request: HttpRequest,
+    path: web::Path<(String, String)>,
+    state: web::Data<ActixApiState<Value>>,
+) -> HttpResponse {
+    let (folder, file) = path.into_inner();
+    match state.uploads.download(&folder, &file).await {
+        Ok((bytes, content_type)) => HttpResponse::Ok().content_type(content_type).body(bytes),
+        Err(err) => {
+            let mut api_response = error_response(&err, state.error_format, &format!("{}/{}/{}", UPLOADS_PATH, folder, file));
+            tag_response(&mut api_response, &request_id(&request_headers(&request)));
+            http_response(api_response, state.response_format)
+        }
+    }
+}
+
+/// Lists the methods of the endpoints matching a path in the `Allow` header
+fn options_response(state: &ActixApiState<Value>, path: &str) -> ApiResponse<Value> {
+    let methods: Vec<HttpMethod> = [HttpMethod::GET, HttpMethod::POST, HttpMethod::PUT, HttpMethod::PATCH, HttpMethod::DELETE]
+        .into_iter()
+        .filter(|method| state.api_adapter.route_for(method, path).is_some())
+        .collect();
+    if methods.is_empty() {
+        let err = RusterApiError::NotFound(format!("No endpoint matches {}", path));
+        return error_response(&err, state.error_format, &format!("/api/{}", path));
+    }
+
+    let mut allowed: Vec<String> = Vec::new();
+    for method in methods {
+        allowed.push(format!("{:?}", method));
+        if matches!(method, HttpMethod::GET) {
+            allowed.push("HEAD".to_string());
+        }
+    }
+    allowed.push("OPTIONS".to_string());
+
+    let mut headers = HashMap::new();
+    headers.insert("Allow".to_string(), allowed.join(", "));
+    ApiResponse {
+        status: 204,
+        headers,
+        body: None,
+    }
+}
+
+/// Reports runtime metrics of the API, such as the datasource connection pool statistics
+async fn metrics_handler(state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    let pool_stats = state.api_adapter.pool_stats();
+
+    http_response(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(json!({ "pool": pool_stats }))),
+    }, state.response_format)
+}
+
+/// Reports the reachability of the configured webhooks and upstream proxies.
+/// Responds with 503 when any of them is down.
+async fn health_handler(state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    let healthy = state.health.is_healthy();
+
+    http_response(ApiResponse {
+        status: if healthy { 200 } else { 503 },
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(json!({
+            "status": if healthy { "up" } else { "degraded" },
+            "targets": state.health.snapshot(),
+        }))),
+    }, state.response_format)
+}
+
+/// Reports the endpoints whose response shape drifted from the stored snapshots
+async fn schema_drift_handler(state: web::Data<ActixApiState<Value>>) -> HttpResponse {
+    let body = match &state.schema_drift {
+        Some(monitor) => json!({
+            "enabled": true,
+            "snapshots": monitor.snapshot_count(),
+            "drifts": monitor.reports(),
+        }),
+        None => json!({ "enabled": false, "snapshots": 0, "drifts": [] }),
+    };
+
+    http_response(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(body)),
+    }, state.response_format)
+}
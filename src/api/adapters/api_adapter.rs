@@ -1,15 +1,42 @@
 use serde::{Serialize, Deserialize};
 
 // Actualizar la importación del ApiHandlerManager a la nueva ruta
+#[cfg(feature = "native")]
+use crate::api::adapters::jwt::{EntityRevocations, JwtTokens};
+#[cfg(feature = "native")]
+use crate::api::adapters::ldap::LdapDirectory;
+#[cfg(feature = "native")]
+use crate::api::adapters::session::Sessions;
+use crate::api::adapters::middleware::Middleware;
+use crate::api::adapters::router::{RouteInfo, RouteTable};
+#[cfg(feature = "native")]
+use crate::api::handlers::auth::{self, Accounts, SignIn, AUTH_PATH, DEFAULT_USERS_ENTITY};
+use crate::api::handlers::crud::batch::{self, BatchEntity, BATCH_PATH};
+use crate::api::handlers::hooks::HookRegistry;
 use crate::api::handlers::manager::ApiHandlerManager;
+#[cfg(feature = "actix")]
+use crate::api::actix::actix_adapter;
+#[cfg(feature = "native")]
 use crate::api::rocket::rocket_adapter;
 use crate::config::configuration::Config;
-use crate::config::specific::entity_config::{Entity, HttpMethod};
-use crate::data::datasource::base::DataSource;
+use crate::config::specific::entity_config::HttpMethod;
+use crate::config::specific::server_config::ResponseFormat;
+#[cfg(feature = "native")]
+use crate::config::specific::server_config::ServerFramework;
+#[cfg(feature = "native")]
+use crate::logging::logger;
+use crate::data::datasource::base::{DataSource, PoolStats};
+use crate::events::event_bus::EventBus;
+#[cfg(feature = "native")]
+use crate::events::outbox::Outbox;
 use crate::error::{Result, RusterApiError};
 use crate::api::common::api_entity::ApiEntity;
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::{debug, info, info_span, warn};
+#[cfg(feature = "native")]
+use tracing::error;
 
 pub type EndpointHandler<T> = Arc<dyn Fn(ApiRequest) -> Result<ApiResponse<T>> + Send + Sync>;
 
@@ -18,19 +45,125 @@ pub struct ApiRequest {
     pub method: HttpMethod,
     pub path: String,
     pub params: HashMap<String, String>,
+    /// Query string parameters
+    pub query: HashMap<String, String>,
+    /// Request headers, keyed by lowercase name as the servers forward them
     pub headers: HashMap<String, String>,
     pub body: Option<String>,
+    /// Identifier of the request, from its `X-Request-Id` header or generated, echoed in the response
+    pub request_id: String,
+    /// Caller of the request, set by the authentication layer once its credentials are verified
+    pub auth: Option<AuthContext>,
+    /// Tenant of the request, set by the tenancy layer
+    pub tenant: Option<TenantContext>,
+}
+
+/// Tenant a request is scoped to, as the tenancy layer resolved it
+#[derive(Serialize, Debug, Clone)]
+pub struct TenantContext {
+    /// Identifier of the tenant
+    pub id: String,
+    /// Field of the entities holding their tenant
+    pub field: String,
+}
+
+/// Identity of the caller of a request, as the authentication layer verified it
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AuthContext {
+    /// Subject of the credentials, like the `sub` claim of a token
+    pub subject: String,
+    /// Roles of the caller, checked by the authorization of the entities
+    pub roles: Vec<String>,
+    /// Claims of the verified token
+    pub claims: Value,
+}
+
+impl ApiRequest {
+    /// Value of a request header. Names are compared ignoring case, so requests built
+    /// by hand with headers like `Authorization` are found too.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+            .or_else(|| self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value))
+            .map(String::as_str)
+    }
 }
 
 /// Represents an API response with typed data payload
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub enum ApiResponseBody<T> {
     Single(T),
     List(Vec<T>),
-    Json(T)
+    Json(T),
+    /// One page of a list with the pagination metadata
+    Page {
+        items: Vec<T>,
+        page: u64,
+        size: u64,
+        total: u64,
+        total_pages: u64,
+    },
+    /// One page of a list in primary key order, with the cursor of the next page
+    Cursor {
+        items: Vec<T>,
+        limit: u64,
+        next_cursor: Option<String>,
+    },
+    /// Text written as it is, like CSV rows. The handler sets its `Content-Type`.
+    Text(String),
 }
 
-#[derive(Serialize)]
+impl<T: Serialize> ApiResponseBody<T> {
+    /// Renders the body in a response format. `Json` bodies are documents built by the handlers,
+    /// like errors or counts, and are rendered as they are in every format.
+    pub fn render(&self, format: ResponseFormat) -> serde_json::Result<Value> {
+        match self {
+            ApiResponseBody::Json(value) => return serde_json::to_value(value),
+            ApiResponseBody::Text(text) => return Ok(Value::String(text.clone())),
+            _ => {}
+        }
+        if format == ResponseFormat::Tagged {
+            return serde_json::to_value(self);
+        }
+
+        let (data, meta) = match self {
+            ApiResponseBody::Single(item) | ApiResponseBody::Json(item) => (serde_json::to_value(item)?, Map::new()),
+            ApiResponseBody::Text(text) => (Value::String(text.clone()), Map::new()),
+            ApiResponseBody::List(items) => {
+                let meta = Map::from_iter([("count".to_string(), Value::from(items.len()))]);
+                (serde_json::to_value(items)?, meta)
+            }
+            ApiResponseBody::Page { items, page, size, total, total_pages } => {
+                let meta = Map::from_iter([
+                    ("page".to_string(), Value::from(*page)),
+                    ("size".to_string(), Value::from(*size)),
+                    ("total".to_string(), Value::from(*total)),
+                    ("total_pages".to_string(), Value::from(*total_pages)),
+                ]);
+                (serde_json::to_value(items)?, meta)
+            }
+            ApiResponseBody::Cursor { items, limit, next_cursor } => {
+                let meta = Map::from_iter([
+                    ("limit".to_string(), Value::from(*limit)),
+                    ("next_cursor".to_string(), Value::from(next_cursor.clone())),
+                ]);
+                (serde_json::to_value(items)?, meta)
+            }
+        };
+
+        match (format, self) {
+            (ResponseFormat::Envelope, _) => Ok(json!({ "data": data, "meta": meta })),
+            // Bare pages keep their pagination fields next to the items
+            (_, ApiResponseBody::Page { .. } | ApiResponseBody::Cursor { .. }) => {
+                let mut page = Map::from_iter([("items".to_string(), data)]);
+                page.extend(meta);
+                Ok(Value::Object(page))
+            }
+            _ => Ok(data),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
 pub struct ApiResponse<T> {
     pub status: u16,
     pub headers: HashMap<String, String>,
@@ -38,7 +171,6 @@ pub struct ApiResponse<T> {
 }
 
 /// Represents a single entity's API configuration
-
 pub struct EntityApi<T> {
     pub datasource: Box<dyn DataSource<T>>,
     pub endpoints: HashMap<String, EndpointHandler<T>>,
@@ -49,6 +181,21 @@ pub struct EntityApi<T> {
 pub trait ApiAdapterTrait<T> {
     /// Handles an API request and returns a response
     fn handle_request(&self, request: ApiRequest) -> Result<ApiResponse<T>>;
+
+    /// Returns the connection pool statistics of the underlying datasources, if any
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+
+    /// Returns the key of the endpoint (like `GET:users/:id`) handling a request, if any
+    fn route_for(&self, _method: &HttpMethod, _path: &str) -> Option<String> {
+        None
+    }
+
+    /// Returns the endpoints the adapter serves, if it knows them
+    fn routes(&self) -> Vec<RouteInfo> {
+        Vec::new()
+    }
 }
 
 /// ApiAdapter serves as the main interface for handling API operations.
@@ -56,136 +203,198 @@ pub trait ApiAdapterTrait<T> {
 pub struct ApiAdapter<T> {
     pub config: Config,
     pub entities: HashMap<String, EntityApi<T>>,
+    /// Routes of the endpoints of the entities
+    routes: RouteTable<T>,
+    /// Bus the endpoints publish the changes of the entities to
+    pub events: EventBus,
+    /// Outbox the endpoints write the changes to publish to the broker of `events` to
+    #[cfg(feature = "native")]
+    pub outbox: Option<Arc<Outbox>>,
+    /// Tokens of the JWT authentication, when `auth.auth_type` is JWT
+    #[cfg(feature = "native")]
+    pub jwt: Option<JwtTokens>,
+    /// Sessions of the cookie authentication, when `auth.auth_type` is Session
+    #[cfg(feature = "native")]
+    pub sessions: Option<Sessions>,
+    /// Layers the served requests run through, outermost first
+    pub middleware: Vec<Arc<dyn Middleware<T>>>,
 }
 
 impl<T: ApiEntity> ApiAdapter<T> {
     /// Creates a new ApiAdapter with the provided configuration and data sources
     pub fn new(config: Config, datasources: HashMap<String, Box<dyn DataSource<T>>>) -> Self {
+        Self::with_hooks(config, datasources, HookRegistry::new())
+    }
+
+    /// Creates a new ApiAdapter whose endpoints run the given lifecycle hooks. With `events`, they
+    /// also write the changes to its outbox.
+    pub fn with_hooks(config: Config, datasources: HashMap<String, Box<dyn DataSource<T>>>, hooks: HookRegistry<T>) -> Self {
         let mut entities = HashMap::new(); 
-        entity_mapper(&config, datasources, &mut entities);
-        Self { config, entities }
+        let events = EventBus::new();
+        #[cfg(feature = "native")]
+        let outbox = Outbox::from_config(&config).unwrap_or_else(|e| {
+            error!("Changes will not be published: {}", e);
+            None
+        });
+        #[cfg(feature = "native")]
+        let hooks = match &outbox {
+            Some(outbox) => outbox.register(hooks, &config),
+            None => hooks,
+        };
+        // Each version maps its entities on its own, and serves them under its name
+        for version in &config.versions {
+            let mut version_entities = HashMap::new();
+            entity_mapper(&config.version_config(version), datasources.clone(), &hooks, &events, &mut version_entities);
+            for (name, mut entity_api) in version_entities {
+                entity_api.endpoints = entity_api.endpoints.into_iter()
+                    .map(|(key, handler)| (versioned_key(&version.name, &key), handler))
+                    .collect();
+                entities.insert(format!("{}/{}", version.name, name), entity_api);
+            }
+        }
+        entity_mapper(&config, datasources, &hooks, &events, &mut entities);
+        #[cfg(feature = "native")]
+        let jwt = JwtTokens::from_config(&config).map(|jwt| with_revocation_store(&config, jwt, &entities));
+        #[cfg(feature = "native")]
+        let sessions = Sessions::from_config(&config);
+        #[cfg(feature = "native")]
+        if let Some(sign_in) = jwt.clone().map(SignIn::Jwt).or_else(|| sessions.clone().map(SignIn::Session)) {
+            map_auth_endpoints(&config, sign_in, &mut entities);
+        }
+        let routes = RouteTable::new(&entities);
+        Self {
+            config,
+            entities,
+            routes,
+            events,
+            #[cfg(feature = "native")]
+            outbox,
+            #[cfg(feature = "native")]
+            jwt,
+            #[cfg(feature = "native")]
+            sessions,
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Adds a layer the served requests run through, inside the layers already added and the
+    /// ones of the configuration, like the maintenance mode
+    pub fn with_middleware(mut self, layer: impl Middleware<T> + 'static) -> Self {
+        self.middleware.push(Arc::new(layer));
+        self
     }
 
-    /// Starts the API server based on the configuration
+    /// Starts the API server with the web framework selected in `server.framework`
+    #[cfg(feature = "native")]
     pub async fn start_server(&self) -> Result<()> {
-        // Use the Rocket adapter for server implementation
-        rocket_adapter::start_server(self.clone()).await
+        logger::init(&self.config.server)?;
+        let result = match self.config.server.framework {
+            ServerFramework::Rocket => rocket_adapter::start_server(self.clone()).await,
+            #[cfg(feature = "actix")]
+            ServerFramework::Actix => actix_adapter::start_server(self.clone()).await,
+            #[cfg(not(feature = "actix"))]
+            ServerFramework::Actix => Err(RusterApiError::ConfigError(
+                "server.framework Actix requires Rawst to be built with the actix feature".to_string()
+            )),
+        };
+        logger::shutdown();
+        result
     }
 }
 
 // Implement the ApiAdapterTrait for the ApiAdapter struct
 impl<T: ApiEntity> ApiAdapterTrait<T> for ApiAdapter<T> {
-    /// Handles an API request and returns a response
-    fn handle_request(&self, request: ApiRequest) -> Result<ApiResponse<T>> {
-
-        // Split the path into components
-        let path_parts: Vec<&str> = request.path.split('/').filter(|s| !s.is_empty()).collect();
-        
-        eprintln!("Debug: Path parts: {:?}", path_parts);
-        eprintln!("Debug: API prefix: {:?}", self.config.api_prefix);
-        eprintln!("Debug: Available entities: {:?}", self.entities.keys().collect::<Vec<_>>());
-        
-        // Extract entity name accounting for API prefix
-        let entity_name;
-
-        // Check if the API prefix is set and adjust the entity name accordingly
-        if let Some(api_prefix) = &self.config.api_prefix {
-            let prefix = api_prefix.trim_start_matches('/').trim_end_matches('/');
-            
-            if !path_parts.is_empty() && path_parts[0] == prefix {
-                if path_parts.len() < 2 {
-                    return Err(RusterApiError::ValidationError("Invalid path: missing entity name".to_string()));
-                }
-                // Normilize the entity name to lowercase
-                entity_name = path_parts[1].to_lowercase();
-            } else if !path_parts.is_empty() {
-                // If the prefix is not present, use the first part of the path
-                entity_name = path_parts[0].to_lowercase();
-            } else {
-                return Err(RusterApiError::ValidationError("Invalid path: empty path".to_string()));
-            }
-        } else if !path_parts.is_empty() {
-            // If no prefix is set, use the first part of the path
-            entity_name = path_parts[0].to_lowercase();
-        } else {
+    /// Handles an API request, dispatching it to the handler of the route matching its method and
+    /// path. The parameters captured by the route, like `:id`, are added to the request.
+    fn handle_request(&self, mut request: ApiRequest) -> Result<ApiResponse<T>> {
+        let path = request.path.clone();
+        let path_parts = self.route_parts(&path);
+        let Some(first) = path_parts.first() else {
             return Err(RusterApiError::ValidationError("Invalid path: empty path".to_string()));
         };
-        
-        // Add more debug logging
-        eprintln!("Debug: Extracted entity name: {}", entity_name);
-        
-        // Entity name search is case insensitive
-        let entity_api = self.entities.iter()
-            .find_map(|(key, value)| {
-                if key.to_lowercase() == entity_name {
-                    Some(value)
-                } else {
-                    None
-                }
+
+        let Some(route) = self.routes.find(&request.method, &path_parts) else {
+            return Err(if self.routes.serves(first) {
+                RusterApiError::EntityNotFound(format!("Endpoint not found for {:?} {}", request.method, request.path))
+            } else {
+                RusterApiError::EntityNotFound(format!(
+                    "Entity not found: {}. Available entities: {:?}",
+                    first.to_lowercase(),
+                    self.entities.keys().collect::<Vec<_>>()
+                ))
             });
-        
-        if let Some(entity_api) = entity_api {
-            // Generate possible keys based on the request method and entity name
-            let possible_keys = vec![
-                // Without prefix
-                format!("{:?}:{}", request.method, entity_name),
-                // Key with ID without prefix
-                format!("{:?}:{}/:id", request.method, entity_name),
-                // Key with API prefix
-                format!("{:?}:api/{}", request.method, entity_name),
-                // Key with API prefix and ID
-                format!("{:?}:api/{}/:id", request.method, entity_name),
-            ];
-            
-            eprintln!("Debug: Trying keys: {:?}", possible_keys);
-            eprintln!("Debug: Available endpoints: {:?}", entity_api.endpoints.keys().collect::<Vec<_>>());
-
-            // Try all possible keys
-            for key in &possible_keys {
-                if let Some(handler) = entity_api.endpoints.get(key) {
-                    eprintln!("Debug: Found handler with key: {}", key);
-                    return match handler(request) {
-                        Ok(response) => Ok(response),
-                        Err(RusterApiError::EndpointGenerationError(msg)) => {
-                            eprintln!("Debug: Entity mapping error: {}", msg);
-                            Ok(ApiResponse {
-                                status: 500,
-                                headers: HashMap::new(),
-                                body: None,
-                            })
-                        }
-                        Err(e) => Err(e)
-                    };
-                }
+        };
+
+        request.params.extend(route.params);
+        let _span = info_span!("handler", endpoint = route.key).entered();
+        match (route.handler)(request) {
+            Err(RusterApiError::EndpointGenerationError(msg)) => {
+                debug!("Entity mapping error: {}", msg);
+                Ok(ApiResponse {
+                    status: 500,
+                    headers: HashMap::new(),
+                    body: None,
+                })
             }
+            result => result,
+        }
+    }
+
+    /// Returns the pool statistics of the shared datasource connection
+    fn pool_stats(&self) -> Option<PoolStats> {
+        self.entities.values().find_map(|entity_api| entity_api.datasource.pool_stats())
+    }
 
-            // If no exact key is found, look for a partial match
-            let mut found_handler = None;
-            for (key, handler) in &entity_api.endpoints {
-                if key.contains(&entity_name) && key.starts_with(&format!("{:?}", request.method)) {
-                    found_handler = Some(handler);
-                    eprintln!("Debug: Found handler with partial match: {}", key);
-                    break;
+    /// Resolves the endpoint pattern matching a path, skipping the API prefix
+    fn route_for(&self, method: &HttpMethod, path: &str) -> Option<String> {
+        let path_parts = self.route_parts(path);
+        self.routes.find(method, &path_parts).map(|route| route.key.to_string())
+    }
+
+    /// Lists the endpoints of every entity, once each, by path then method
+    fn routes(&self) -> Vec<RouteInfo> {
+        let served = self.config.served_entities();
+        let mut routes: Vec<RouteInfo> = Vec::new();
+        for (name, entity_api) in &self.entities {
+            let authentication = served.iter()
+                .find(|(path, _)| path.eq_ignore_ascii_case(name))
+                .is_some_and(|(_, entity)| entity.authentication);
+            // Unprefixed keys sort first, so they name the routes
+            let mut keys: Vec<&String> = entity_api.endpoints.keys().collect();
+            keys.sort_by_key(|key| (key.contains(":api/"), key.to_string()));
+            for key in keys {
+                let Some((method, pattern)) = key.split_once(':') else {
+                    continue;
+                };
+                let path = format!("/api/{}", pattern.strip_prefix("api/").unwrap_or(pattern));
+                if routes.iter().any(|route| route.method == method && route.path == path) {
+                    continue;
                 }
+                routes.push(RouteInfo {
+                    method: method.to_string(),
+                    path,
+                    entity: name.clone(),
+                    key: key.clone(),
+                    authentication,
+                });
             }
-            
-            if let Some(handler) = found_handler {
-                handler(request)
-            } else {
-                Err(RusterApiError::EntityNotFound(format!(
-                    "Endpoint not found for {:?} {}. Available endpoints: {:?}",
-                    request.method, 
-                    request.path, 
-                    entity_api.endpoints.keys().collect::<Vec<_>>()
-                )))
-            }
+        }
+        routes.sort_by(|a, b| (&a.path, &a.method).cmp(&(&b.path, &b.method)));
+        routes
+    }
+}
+
+impl<T> ApiAdapter<T> {
+    /// Segments of a request path, without the API prefix when the path starts with it
+    fn route_parts<'a>(&self, path: &'a str) -> Vec<&'a str> {
+        let path_parts: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let prefix: Vec<&str> = self.config.api_prefix.as_deref()
+            .map(|prefix| prefix.split('/').filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        if !prefix.is_empty() && path_parts.starts_with(&prefix) {
+            path_parts[prefix.len()..].to_vec()
         } else {
-            // If the entity is not found, return an error
-            Err(RusterApiError::EntityNotFound(format!(
-                "Entity not found: {}. Available entities: {:?}",
-                entity_name,
-                self.entities.keys().collect::<Vec<_>>()
-            )))
+            path_parts
         }
     }
 }
@@ -198,20 +407,39 @@ impl<T: ApiEntity> Clone for ApiAdapter<T> {
         Self {
             config: self.config.clone(),
             entities: self.entities.clone(),
+            routes: self.routes.clone(),
+            events: self.events.clone(),
+            #[cfg(feature = "native")]
+            outbox: self.outbox.clone(),
+            #[cfg(feature = "native")]
+            jwt: self.jwt.clone(),
+            #[cfg(feature = "native")]
+            sessions: self.sessions.clone(),
+            middleware: self.middleware.clone(),
         }
     }
 }
 
+/// Key of an endpoint of a version of the API, like `GET:v2/users/:id` for `GET:users/:id`
+fn versioned_key(version: &str, key: &str) -> String {
+    match key.split_once(':') {
+        Some((method, pattern)) => format!("{}:{}/{}", method, version, pattern.strip_prefix("api/").unwrap_or(pattern)),
+        None => key.to_string(),
+    }
+}
+
 /// Maps the entities from the configuration to their respective data sources and handlers
 fn entity_mapper<T: ApiEntity>(
     config: &Config,
     datasources: HashMap<String, Box<dyn DataSource<T>>>,
+    hooks: &HookRegistry<T>,
+    events: &EventBus,
     entities: &mut HashMap<String, EntityApi<T>>,
 ) {
-    println!("Mapping entities to handlers...");
-    println!("Available datasources: {:?}", datasources.keys().collect::<Vec<_>>());
-    println!("Advanced entities count: {}", config.entities_advanced.len());
-    println!("Basic entities count: {}", config.entities_basic.len());
+    debug!("Mapping entities to handlers...");
+    debug!("Available datasources: {:?}", datasources.keys().collect::<Vec<_>>());
+    debug!("Advanced entities count: {}", config.entities_advanced.len());
+    debug!("Basic entities count: {}", config.entities_basic.len());
     
     // Normalize all datasource keys for better matching
     let normalized_datasources: HashMap<String, &Box<dyn DataSource<T>>> = datasources.iter()
@@ -220,10 +448,11 @@ fn entity_mapper<T: ApiEntity>(
 
     // Process all entities (both advanced and basic)
     let mut processed_entities = std::collections::HashSet::new();
+    let mut batch_entities = Vec::new();
 
     // First process advanced entities
     for entity in &config.entities_advanced {
-        println!("Processing advanced entity: {}", entity.name);
+        debug!("Processing advanced entity: {}", entity.name);
         let normalized_name = entity.name.to_lowercase();
         if !processed_entities.contains(&normalized_name) {
             // Search for the datasource by both normalized and exact name
@@ -233,10 +462,13 @@ fn entity_mapper<T: ApiEntity>(
             
             if let Some(datasource) = datasource {
                 // Initialize the handler manager for the entity
-                let handler_manager = ApiHandlerManager::new(config.clone(), (*datasource).clone());
+                let handler_manager = ApiHandlerManager::new(config.clone(), (*datasource).clone())
+                    .with_hooks(hooks.clone())
+                    .with_events(events.clone());
 
                 // Get the initialized endpoints for the entity
                 let endpoints = handler_manager.initialize_endpoints(entity);
+                batch_entities.push(BatchEntity::new(entity, handler_manager.entity_hooks(entity)));
 
                 // Add the entity's API configuration to the HashMap
                 entities.insert(
@@ -247,16 +479,16 @@ fn entity_mapper<T: ApiEntity>(
                     },
                 );
                 processed_entities.insert(entity.name.clone());
-                println!("Successfully mapped advanced entity: {}", entity.name);
+                info!("Successfully mapped advanced entity: {}", entity.name);
             } else {
-                println!("No datasource found for advanced entity: {}", entity.name);
+                warn!("No datasource found for advanced entity: {}", entity.name);
             }
         }
     }
 
     // Then process basic entities
     for entity_basic in &config.entities_basic {
-        println!("Processing basic entity: {}", entity_basic.name);
+        debug!("Processing basic entity: {}", entity_basic.name);
 
         // Normalize the entity name to lowercase for case-insensitive matching
         let normalized_name = entity_basic.name.to_lowercase();
@@ -265,59 +497,28 @@ fn entity_mapper<T: ApiEntity>(
             // Search for the datasource by both normalized and exact name
             let datasource = normalized_datasources.get(&normalized_name)
                 .or_else(|| normalized_datasources.get(&entity_basic.name.to_lowercase()))
-                .map(|ds| *ds)
+                .copied()
                 .or_else(|| {
                     datasources.get(&entity_basic.name)
                 });
            
             if let Some(datasource) = datasource {
-                println!("Found datasource for basic entity: {}", entity_basic.name);
+                debug!("Found datasource for basic entity: {}", entity_basic.name);
                 
-                // Prepare the entity configuration
-                // Use the entity_basic fields to create the Entity struct
-                let entity = Entity {
-                    name: entity_basic.name.clone(),
-
-                    table_name: entity_basic.table_name.clone(),
-                    fields: entity_basic.fields.iter().map(|f| {
-                        crate::config::specific::entity_config::Field {
-                            name: f.name.clone(),
-                            column_name: Some(f.name.clone()),
-                            data_type: f.data_type.clone(),
-                            required: f.required,
-                            unique: false,
-                            searchable: true,
-                            default_value: None,
-                            description: None,
-                        }
-                    }).collect(),
-                    relationships: Vec::new(),
-                    endpoints: crate::config::specific::entity_config::EndpointConfig {
-                        generate_create: true,
-                        generate_read: true,
-                        generate_update: true,
-                        generate_delete: true,
-                        generate_list: true,
-                        custom_routes: Vec::new(),
-                    },
-                    authentication: entity_basic.authentication,
-                    authorization: crate::config::specific::entity_config::Authorization {
-                        active: false,
-                        roles: Vec::new(),
-                        permissions: Vec::new(),
-                    },
-                    validations: Vec::new(),
-                    pagination: None,
-                };
+                // Prepare the entity configuration from the basic entity
+                let entity = entity_basic.to_entity();
 
                 // Initialize the handler manager for the entity
-                let handler_manager = ApiHandlerManager::new(config.clone(), (*datasource).clone());
+                let handler_manager = ApiHandlerManager::new(config.clone(), (*datasource).clone())
+                    .with_hooks(hooks.clone())
+                    .with_events(events.clone());
 
                 // Get the initialized endpoints for the entity
                 let endpoints = handler_manager.initialize_endpoints(&entity);
+                batch_entities.push(BatchEntity::new(&entity, handler_manager.entity_hooks(&entity)));
 
                 // Register the available endpoints
-                println!("Registered endpoints for {}: {:?}", 
+                debug!("Registered endpoints for {}: {:?}", 
                          entity_basic.name, 
                          endpoints.keys().collect::<Vec<_>>());
 
@@ -331,17 +532,73 @@ fn entity_mapper<T: ApiEntity>(
                     },
                 );
                 processed_entities.insert(normalized_name);
-                println!("Successfully mapped basic entity: {}", entity_basic.name);
+                info!("Successfully mapped basic entity: {}", entity_basic.name);
             } else {
-                println!("No datasource found for basic entity: {}. Available datasources: {:?}", 
+                warn!("No datasource found for basic entity: {}. Available datasources: {:?}", 
                          entity_basic.name, 
                          datasources.keys().collect::<Vec<_>>());
             }
         }
     }
 
-    println!("Total entities mapped: {}", entities.len());
-    println!("Mapped entities: {:?}", entities.keys().collect::<Vec<_>>());
+    // Serve the batch endpoint over the datasource the entities share, like an entity of its own
+    if let Some(datasource) = entities.values().next().map(|entity_api| entity_api.datasource.clone()) {
+        let mut endpoints = HashMap::new();
+        batch::register_batch_endpoint(datasource.clone(), batch_entities, &mut endpoints);
+        entities.insert(BATCH_PATH.to_string(), EntityApi { datasource, endpoints });
+    }
+
+    info!("Total entities mapped: {}", entities.len());
+    debug!("Mapped entities: {:?}", entities.keys().collect::<Vec<_>>());
+}
+
+/// Keeps the tokens revoked in the `revocation_entity` of the JWT authentication. Adapters whose
+/// revocation entity is not served keep them in memory, and fail to be served.
+#[cfg(feature = "native")]
+fn with_revocation_store<T: ApiEntity>(config: &Config, jwt: JwtTokens, entities: &HashMap<String, EntityApi<T>>) -> JwtTokens {
+    let Some(name) = jwt.config().revocation_entity.clone() else {
+        return jwt;
+    };
+    let entity = config.all_entities().into_iter().find(|entity| entity.name.eq_ignore_ascii_case(&name));
+    let (Some(entity), Some(entity_api)) = (entity, entities.get(&name.to_lowercase())) else {
+        return jwt;
+    };
+    match EntityRevocations::new(entity_api.datasource.clone(), &entity) {
+        Ok(store) => jwt.with_store(Arc::new(store)),
+        Err(_) => jwt,
+    }
+}
+
+/// Serves the sign in endpoints of the JWT or session authentication, verifying the users against
+/// the directory of `ldap_config` or else its users entity, like an entity of its own
+#[cfg(feature = "native")]
+fn map_auth_endpoints<T: ApiEntity>(config: &Config, sign_in: SignIn, entities: &mut HashMap<String, EntityApi<T>>) {
+    let (datasource, accounts) = match LdapDirectory::from_config(config) {
+        // Directory users need no entity: the endpoints are served over the datasource the entities share
+        Some(directory) => {
+            let Some(datasource) = entities.values().next().map(|entity_api| entity_api.datasource.clone()) else {
+                return;
+            };
+            (datasource, Accounts::Directory(directory))
+        }
+        None => {
+            let users_config = sign_in.users().clone();
+            let users_entity = users_config.users_entity.as_deref().unwrap_or(DEFAULT_USERS_ENTITY);
+            let users = config.all_entities().into_iter().find(|entity| entity.name.eq_ignore_ascii_case(users_entity));
+            let (Some(users), Some(entity_api)) = (users, entities.get(&users_entity.to_lowercase())) else {
+                warn!("Skipping the sign in endpoints: no users entity {} is served", users_entity);
+                return;
+            };
+            let datasource = entity_api.datasource.clone();
+            let Some(accounts) = Accounts::users_entity(datasource.clone(), &users, &users_config, config.tenancy.as_ref()) else {
+                return;
+            };
+            (datasource, accounts)
+        }
+    };
+    let mut endpoints = HashMap::new();
+    auth::register_auth_endpoints(accounts, sign_in, &mut endpoints);
+    entities.insert(AUTH_PATH.to_string(), EntityApi { datasource, endpoints });
 }
 
 impl<T> Clone for Box<dyn DataSource<T>> {
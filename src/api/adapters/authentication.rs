@@ -0,0 +1,69 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, AuthContext};
+use crate::api::adapters::middleware::{Middleware, Next};
+use crate::api::adapters::jwt::JwtTokens;
+use crate::api::adapters::oauth::OAuthProviders;
+use crate::api::adapters::router::RouteInfo;
+use crate::api::adapters::session::{request_cookie, Sessions};
+use crate::api::handlers::crud::batch::BATCH_PATH;
+use crate::error::{Result, RusterApiError};
+use std::collections::HashSet;
+
+/// Bearer token of the `Authorization` header of a request, if any
+pub fn bearer_token(request: &ApiRequest) -> Option<&str> {
+    let value = request.header("authorization")?.trim();
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("Bearer").then(|| token.trim()).filter(|token| !token.is_empty())
+}
+
+/// Layer verifying the credentials of the requests with the authentication of `auth`, and
+/// rejecting with 401 the requests to the endpoints of entities with `authentication` that carry
+/// none. Verified callers are set on the requests, for the handlers to authorize them.
+pub struct Authenticator {
+    /// Keys of the endpoints requiring authentication, like `GET:users/:id`
+    protected: HashSet<String>,
+    oauth: Option<OAuthProviders>,
+    jwt: Option<JwtTokens>,
+    sessions: Option<Sessions>,
+}
+
+impl Authenticator {
+    /// Authenticator of the endpoints of an adapter. The batch endpoint writes the entities
+    /// directly, so it requires authentication as soon as any of them does.
+    pub fn new(oauth: Option<OAuthProviders>, jwt: Option<JwtTokens>, sessions: Option<Sessions>, routes: &[RouteInfo]) -> Self {
+        let any_protected = routes.iter().any(|route| route.authentication);
+        let protected = routes.iter()
+            .filter(|route| route.authentication || (any_protected && route.entity == BATCH_PATH))
+            .map(|route| route.key.clone())
+            .collect();
+        Self { protected, oauth, jwt, sessions }
+    }
+
+    /// Caller of a request, once its credentials are verified. Invalid credentials are rejected
+    /// even on the endpoints not requiring any, except session cookies: browsers keep sending
+    /// them once expired, so those requests are only anonymous.
+    pub fn authenticate(&self, request: &ApiRequest) -> Result<Option<AuthContext>> {
+        let Some(token) = bearer_token(request) else {
+            return Ok(self.sessions.as_ref().and_then(|sessions| {
+                request_cookie(request, sessions.cookie_name()).and_then(|cookie| sessions.verify(cookie).ok())
+            }));
+        };
+        match (&self.jwt, &self.oauth) {
+            (Some(jwt), _) => jwt.verify(token).map(Some),
+            (None, Some(oauth)) => oauth.verify(token).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+impl<T> Middleware<T> for Authenticator {
+    fn handle(&self, mut request: ApiRequest, next: Next<'_, T>) -> Result<ApiResponse<T>> {
+        let auth = self.authenticate(&request)?;
+        let protected = next.route_for(&request.method, &request.path)
+            .is_some_and(|key| self.protected.contains(&key));
+        if protected && auth.is_none() {
+            return Err(RusterApiError::AuthError("Authentication required".to_string()));
+        }
+        request.auth = auth;
+        next.run(request)
+    }
+}
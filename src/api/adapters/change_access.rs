@@ -0,0 +1,122 @@
+use crate::api::adapters::api_adapter::ApiRequest;
+use crate::api::adapters::authentication::Authenticator;
+use crate::api::adapters::tenancy::TenantResolver;
+use crate::api::handlers::common::authorization::{CrudAction, EntityAuthorization};
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::request_id::request_id;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::config::configuration::Config;
+use crate::config::specific::entity_config::HttpMethod;
+use crate::error::{Result, RusterApiError};
+use crate::events::event_bus::EntityChange;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Read access of an entity, as its endpoints check it
+#[derive(Clone)]
+struct EntityAccess {
+    authentication: bool,
+    authorization: EntityAuthorization,
+    ownership: Ownership,
+    tenancy: Tenancy,
+    field_access: FieldAccess,
+}
+
+/// Access of the callers to the changes streamed over Server-Sent Events and the subscriptions
+/// WebSocket, which are served outside of the endpoints. Streams are opened by requests
+/// authenticated like the ones to the endpoints, and the changes of an entity only reach the
+/// callers allowed to read it, without the rows of other owners or tenants nor the fields the
+/// callers may not read.
+#[derive(Clone)]
+pub struct ChangeAccess {
+    authenticator: Option<Arc<Authenticator>>,
+    tenants: Option<Arc<TenantResolver>>,
+    /// Access of the served entities by lowercase path, like `users` or `v2/users`
+    entities: Arc<HashMap<String, EntityAccess>>,
+}
+
+impl ChangeAccess {
+    pub fn new(config: &Config, authenticator: Option<Arc<Authenticator>>, tenants: Option<Arc<TenantResolver>>) -> Self {
+        let entities = config.served_entities().into_iter()
+            .map(|(path, entity)| (path.to_lowercase(), EntityAccess {
+                authentication: entity.authentication,
+                authorization: EntityAuthorization::new(&entity),
+                ownership: Ownership::new(&entity),
+                tenancy: Tenancy::new(&entity),
+                field_access: FieldAccess::new(&entity),
+            }))
+            .collect();
+        Self { authenticator, tenants, entities: Arc::new(entities) }
+    }
+
+    /// Caller of the request opening a stream at a path, with its tenant. Invalid credentials are
+    /// rejected, like on the endpoints.
+    pub fn admit(&self, path: &str, headers: HashMap<String, String>) -> Result<ChangeReader> {
+        let mut request = ApiRequest {
+            method: HttpMethod::GET,
+            path: path.to_string(),
+            params: HashMap::new(),
+            query: HashMap::new(),
+            request_id: request_id(&headers),
+            headers,
+            body: None,
+            auth: None,
+            tenant: None,
+        };
+        if let Some(authenticator) = &self.authenticator {
+            request.auth = authenticator.authenticate(&request)?;
+        }
+        if let Some(tenants) = &self.tenants {
+            request.tenant = tenants.tenant_of(&request, true)?;
+        }
+        Ok(ChangeReader { request, access: self.clone() })
+    }
+}
+
+/// A caller streaming changes, once admitted
+pub struct ChangeReader {
+    /// Request that opened the stream, with its caller and tenant
+    request: ApiRequest,
+    access: ChangeAccess,
+}
+
+impl ChangeReader {
+    /// Checks that the caller may read the changes of the entity served at a path, like its list
+    /// endpoint: anonymous callers are answered with 401, and callers lacking the roles or
+    /// permissions to read it with 403
+    pub fn check(&self, entity: &str) -> Result<()> {
+        let Some(access) = self.access.entities.get(&entity.to_lowercase()) else {
+            return Ok(());
+        };
+        let auth = self.request.auth.as_ref();
+        if access.authentication && auth.is_none() {
+            return Err(RusterApiError::AuthError("Authentication required".to_string()));
+        }
+        access.authorization.check(auth, CrudAction::Read)?;
+        access.ownership.owner(auth)?;
+        Ok(())
+    }
+
+    /// A change of the entity served at a path as the caller may read it, without the fields it
+    /// may not read. Changes of the rows of other owners or tenants are left out, and so are the
+    /// deletions of the entities whose rows are scoped to the caller, which carry no row to tell.
+    pub fn filter(&self, entity: &str, change: &EntityChange) -> Option<EntityChange> {
+        let Some(access) = self.access.entities.get(&entity.to_lowercase()) else {
+            return Some(change.clone());
+        };
+        let auth = self.request.auth.as_ref();
+        let owner = access.ownership.owner(auth).ok()?;
+        match &change.data {
+            None if owner.is_some() || access.tenancy.is_scoped(&self.request) => return None,
+            None => {}
+            Some(data) => {
+                let visible = access.tenancy.visible(&self.request, Some(data.clone())).ok()?;
+                access.ownership.visible(auth, visible).ok()??;
+            }
+        }
+        let mut change = change.clone();
+        change.hide(&access.field_access.hidden(auth));
+        Some(change)
+    }
+}
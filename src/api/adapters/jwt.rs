@@ -0,0 +1,294 @@
+use crate::api::adapters::api_adapter::AuthContext;
+use crate::api::handlers::common::utils::handle_datasource_error;
+use crate::config::configuration::Config;
+use crate::config::secrets::resolve_secret;
+use crate::config::specific::auth_config::{AuthType, JWTConfig};
+use crate::config::specific::entity_config::Entity;
+use crate::data::datasource::base::DataSource;
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+/// Lifetime of the refresh tokens when `refresh_expiration_hours` is not set
+const DEFAULT_REFRESH_EXPIRATION_HOURS: u32 = 24 * 7;
+/// Field of the `revocation_entity` holding the expiry of the revoked tokens, in seconds since the epoch
+pub const REVOCATION_EXPIRY_FIELD: &str = "expires_at";
+
+/// Kinds of the tokens issued, so refresh tokens cannot authenticate requests and access tokens
+/// cannot be refreshed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum TokenKind {
+    Access,
+    Refresh,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    #[serde(default)]
+    roles: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+    iat: u64,
+    exp: u64,
+    jti: String,
+    typ: TokenKind,
+    /// Other claims of the user, like its tenant
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// Deny-list of the tokens revoked before they expire, by token id. Refresh tokens already
+/// exchanged are revoked as well, so each is used once.
+pub trait RevocationStore: Send + Sync {
+    /// Revokes a token until it expires, returning whether it was not revoked yet
+    fn revoke(&self, token_id: &str, expires_at: u64) -> Result<bool>;
+    /// Whether a token was revoked
+    fn is_revoked(&self, token_id: &str) -> Result<bool>;
+}
+
+/// Revoked tokens kept in memory until they expire, lost on restart
+#[derive(Default)]
+pub struct MemoryRevocations {
+    /// Expiry of the revoked tokens, by token id
+    revoked: Mutex<HashMap<String, u64>>,
+}
+
+impl RevocationStore for MemoryRevocations {
+    fn revoke(&self, token_id: &str, expires_at: u64) -> Result<bool> {
+        let mut revoked = self.revoked.lock().unwrap_or_else(|e| e.into_inner());
+        let now = now_seconds();
+        revoked.retain(|_, expiry| *expiry > now);
+        Ok(revoked.insert(token_id.to_string(), expires_at).is_none())
+    }
+
+    fn is_revoked(&self, token_id: &str) -> Result<bool> {
+        let revoked = self.revoked.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(revoked.contains_key(token_id))
+    }
+}
+
+/// Revoked tokens stored as the rows of an entity, keyed by token id with their expiry in
+/// `expires_at`, so every instance of the API shares them and they survive restarts. Rows of
+/// expired tokens are ignored, and can be deleted.
+pub struct EntityRevocations<T> {
+    datasource: Box<dyn DataSource<T>>,
+    entity: String,
+    key_field: String,
+}
+
+impl<T: ApiEntity> EntityRevocations<T> {
+    /// Store over the datasource of an entity, which needs a key and an `expires_at` field
+    pub fn new(datasource: Box<dyn DataSource<T>>, entity: &Entity) -> Result<Self> {
+        let key_field = revocation_key(entity)?;
+        Ok(Self { datasource, entity: entity.name.clone(), key_field })
+    }
+
+    /// Expiry of a stored revocation, if the token was revoked
+    fn expiry(&self, token_id: &str) -> Result<Option<u64>> {
+        let row = self.datasource.get_by_id(token_id, Some(&self.entity)).map_err(handle_datasource_error)?;
+        Ok(row.map(|row| serde_json::to_value(&row)).transpose()?
+            .map(|row| row.get(REVOCATION_EXPIRY_FIELD).and_then(Value::as_u64).unwrap_or(u64::MAX)))
+    }
+}
+
+impl<T: ApiEntity> RevocationStore for EntityRevocations<T> {
+    fn revoke(&self, token_id: &str, expires_at: u64) -> Result<bool> {
+        let expiry = self.expiry(token_id)?;
+        if expiry.is_some_and(|expiry| expiry > now_seconds()) {
+            return Ok(false);
+        }
+        let row = serde_json::from_value(json!({ &self.key_field: token_id, REVOCATION_EXPIRY_FIELD: expires_at }))?;
+        // The expired row of a former revocation is replaced
+        let written = match expiry {
+            Some(_) => self.datasource.update(token_id, row, Some(&self.entity)),
+            None => self.datasource.create(row, Some(&self.entity)),
+        };
+        written.map_err(handle_datasource_error)?;
+        Ok(true)
+    }
+
+    fn is_revoked(&self, token_id: &str) -> Result<bool> {
+        Ok(self.expiry(token_id)?.is_some_and(|expiry| expiry > now_seconds()))
+    }
+}
+
+/// Key field of a `revocation_entity`, which needs one and an `expires_at` field
+fn revocation_key(entity: &Entity) -> Result<String> {
+    let key_field = entity.key_field().ok_or_else(|| {
+        RusterApiError::ConfigError(format!("auth.jwt_config.revocation_entity {} has no key field", entity.name))
+    })?;
+    if !entity.fields.iter().any(|field| field.name == REVOCATION_EXPIRY_FIELD) {
+        return Err(RusterApiError::ConfigError(format!(
+            "auth.jwt_config.revocation_entity {} has no field {}", entity.name, REVOCATION_EXPIRY_FIELD
+        )));
+    }
+    Ok(key_field.name.clone())
+}
+
+/// Tokens of the JWT authentication of `auth.jwt_config`, signed with HS256. Access tokens
+/// authenticate the requests, and refresh tokens, when enabled, are exchanged once for new tokens.
+/// Tokens can be revoked before they expire. Clones share the revoked tokens.
+#[derive(Clone)]
+pub struct JwtTokens {
+    config: JWTConfig,
+    revoked: Arc<dyn RevocationStore>,
+}
+
+impl JwtTokens {
+    /// Fails when `auth.auth_type` is JWT without a usable `auth.jwt_config`
+    pub fn validate(config: &Config) -> Result<()> {
+        let Some(auth) = config.auth.as_ref().filter(|auth| matches!(auth.auth_type, AuthType::JWT)) else {
+            return Ok(());
+        };
+        let jwt = auth.jwt_config.as_ref().ok_or_else(|| {
+            RusterApiError::ConfigError("auth.auth_type JWT requires auth.jwt_config".to_string())
+        })?;
+        if jwt.secret.trim().is_empty() {
+            return Err(RusterApiError::ConfigError("auth.jwt_config.secret cannot be empty".to_string()));
+        }
+        if jwt.expiration_hours == 0 {
+            return Err(RusterApiError::ConfigError("auth.jwt_config.expiration_hours must be positive".to_string()));
+        }
+        if let Some(name) = &jwt.revocation_entity {
+            let entity = config.all_entities().into_iter().find(|entity| entity.name.eq_ignore_ascii_case(name)).ok_or_else(|| {
+                RusterApiError::ConfigError(format!("auth.jwt_config.revocation_entity {} is not an entity", name))
+            })?;
+            revocation_key(&entity)?;
+            // Its endpoints would otherwise let anyone read the revoked tokens, or delete them to reuse them
+            if !entity.authorization.active {
+                return Err(RusterApiError::ConfigError(format!(
+                    "auth.jwt_config.revocation_entity {} requires an active authorization", entity.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Tokens of the configuration, None unless `auth.auth_type` is JWT. Invalid configurations
+    /// are reported and leave JWT authentication disabled.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if let Err(e) = Self::validate(config) {
+            error!("JWT authentication is disabled: {}", e);
+            return None;
+        }
+        let auth = config.auth.as_ref().filter(|auth| matches!(auth.auth_type, AuthType::JWT))?;
+        let mut jwt = auth.jwt_config.clone()?;
+        jwt.secret = match resolve_secret(&jwt.secret) {
+            Ok(secret) => secret,
+            Err(e) => {
+                error!("JWT authentication is disabled: {}", e);
+                return None;
+            }
+        };
+        info!("Issuing JWT tokens valid for {} hour(s)", jwt.expiration_hours);
+        Some(Self { config: jwt, revoked: Arc::new(MemoryRevocations::default()) })
+    }
+
+    /// Keeps the revoked tokens in another store
+    pub fn with_store(self, revoked: Arc<dyn RevocationStore>) -> Self {
+        Self { revoked, ..self }
+    }
+
+    pub fn config(&self) -> &JWTConfig {
+        &self.config
+    }
+
+    /// Body answering a sign in: an access token and, when enabled, a refresh token, both
+    /// carrying the other claims of the user
+    pub fn issue(&self, subject: &str, roles: &[String], claims: &Map<String, Value>) -> Result<Value> {
+        let expires_in = u64::from(self.config.expiration_hours) * 3600;
+        let mut body = json!({
+            "access_token": self.sign(subject, roles, claims, TokenKind::Access, expires_in)?,
+            "token_type": "Bearer",
+            "expires_in": expires_in,
+        });
+        if self.config.refresh_token_enabled {
+            let hours = self.config.refresh_expiration_hours.unwrap_or(DEFAULT_REFRESH_EXPIRATION_HOURS);
+            body["refresh_token"] = Value::String(self.sign(subject, roles, claims, TokenKind::Refresh, u64::from(hours) * 3600)?);
+        }
+        Ok(body)
+    }
+
+    /// Verifies an access token that was not revoked, returning the caller with the roles it was issued with
+    pub fn verify(&self, token: &str) -> Result<AuthContext> {
+        let claims = self.decode(token, TokenKind::Access)?;
+        if self.revoked.is_revoked(&claims.jti)? {
+            return Err(RusterApiError::AuthError("The token was revoked".to_string()));
+        }
+        Ok(AuthContext {
+            subject: claims.sub.clone(),
+            roles: claims.roles.clone(),
+            claims: serde_json::to_value(&claims)?,
+        })
+    }
+
+    /// Spends a refresh token, returning the subject it was issued to. Refresh tokens are rotated,
+    /// so one already used or revoked is rejected.
+    pub fn redeem(&self, refresh_token: &str) -> Result<String> {
+        let claims = self.decode(refresh_token, TokenKind::Refresh)?;
+        if !self.revoked.revoke(&claims.jti, claims.exp)? {
+            return Err(RusterApiError::AuthError("The refresh token was already used".to_string()));
+        }
+        Ok(claims.sub)
+    }
+
+    /// Revokes an access or refresh token until it expires, so it cannot authenticate requests or
+    /// be exchanged any more. Tokens that are invalid or expired are rejected.
+    pub fn revoke(&self, token: &str) -> Result<()> {
+        let claims = self.claims(token)?;
+        self.revoked.revoke(&claims.jti, claims.exp)?;
+        Ok(())
+    }
+
+    fn sign(&self, subject: &str, roles: &[String], claims: &Map<String, Value>, kind: TokenKind, lifetime: u64) -> Result<String> {
+        let now = now_seconds();
+        let claims = Claims {
+            sub: subject.to_string(),
+            roles: roles.to_vec(),
+            iss: self.config.issuer.clone(),
+            iat: now,
+            exp: now + lifetime,
+            jti: uuid::Uuid::new_v4().to_string(),
+            typ: kind,
+            extra: claims.clone(),
+        };
+        encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(self.config.secret.as_bytes()))
+            .map_err(|e| RusterApiError::ServerError(format!("Failed to sign the token: {}", e)))
+    }
+
+    /// Checks the signature, expiry and issuer of a token, returning its claims
+    fn claims(&self, token: &str) -> Result<Claims> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.validate_aud = false;
+        if let Some(issuer) = &self.config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        decode::<Claims>(token, &DecodingKey::from_secret(self.config.secret.as_bytes()), &validation)
+            .map(|data| data.claims)
+            .map_err(|e| RusterApiError::AuthError(format!("Invalid token: {}", e)))
+    }
+
+    /// Checks the signature, expiry, issuer and kind of a token, returning its claims
+    fn decode(&self, token: &str, kind: TokenKind) -> Result<Claims> {
+        let claims = self.claims(token)?;
+        if claims.typ != kind {
+            return Err(RusterApiError::AuthError(match kind {
+                TokenKind::Access => "Refresh tokens cannot authenticate requests".to_string(),
+                TokenKind::Refresh => "Expected a refresh token".to_string(),
+            }));
+        }
+        Ok(claims)
+    }
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default()
+}
@@ -0,0 +1,402 @@
+use crate::config::configuration::Config;
+use crate::config::secrets::resolve_secret;
+use crate::config::specific::auth_config::{AuthType, LdapConfig};
+use crate::error::{Result, RusterApiError};
+use ldap3::{dn_escape, drive, ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, LdapError, Scope, SearchEntry, SearchOptions};
+use rustls::{Certificate, ClientConfig, RootCertStore};
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tracing::{error, info};
+
+/// Seconds the directory has to answer when `timeout_seconds` is not set
+const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+const DEFAULT_USER_FILTER: &str = "(uid={username})";
+const DEFAULT_GROUP_ATTRIBUTE: &str = "memberOf";
+/// Placeholder of the user name in `bind_dn` and `user_filter`
+const USERNAME: &str = "{username}";
+
+// Result codes of the directory
+const SUCCESS: u32 = 0;
+const NO_SUCH_OBJECT: u32 = 32;
+const INVALID_CREDENTIALS: u32 = 49;
+
+/// Host and port of the directory
+#[derive(Clone)]
+struct Address {
+    host: String,
+    port: u16,
+    tls: bool,
+}
+
+impl Address {
+    fn parse(url: &str) -> Result<Self> {
+        let (tls, rest) = match (url.strip_prefix("ldaps://"), url.strip_prefix("ldap://")) {
+            (Some(rest), _) => (true, rest),
+            (_, Some(rest)) => (false, rest),
+            _ => return Err(RusterApiError::ConfigError(format!("auth.ldap_config.url {} must start with ldap:// or ldaps://", url))),
+        };
+        let rest = rest.trim_end_matches('/');
+        let (host, port) = match rest.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+                let port = port.parse().map_err(|_| RusterApiError::ConfigError(format!("Invalid port in auth.ldap_config.url {}", url)))?;
+                (host, port)
+            }
+            _ => (rest, if tls { 636 } else { 389 }),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(RusterApiError::ConfigError(format!("auth.ldap_config.url {} has no host", url)));
+        }
+        Ok(Self { host: host.to_string(), port, tls })
+    }
+}
+
+/// Equality filter of `user_filter`, with the user name still to be replaced in its value
+#[derive(Clone)]
+struct UserFilter {
+    attribute: String,
+    value: String,
+}
+
+impl UserFilter {
+    /// Reads a filter like `(uid={username})`
+    fn parse(filter: &str) -> Result<Self> {
+        let invalid = || RusterApiError::ConfigError(format!("auth.ldap_config.user_filter {} must be an equality filter like (uid={{username}})", filter));
+        let inner = filter.trim().strip_prefix('(').and_then(|inner| inner.strip_suffix(')')).ok_or_else(invalid)?;
+        let (attribute, value) = inner.split_once('=').ok_or_else(invalid)?;
+        let valid_attribute = !attribute.is_empty() && attribute.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == ';' || c == '.');
+        if !valid_attribute || value.is_empty() || value.contains(['*', '(', ')']) || attribute.ends_with(['~', '>', '<', ':']) {
+            return Err(invalid());
+        }
+        Ok(Self { attribute: attribute.to_string(), value: value.to_string() })
+    }
+
+    /// The filter of a user. The user name is escaped, so it cannot change the filter.
+    fn filter(&self, username: &str) -> String {
+        format!("({}={})", self.attribute, self.value.replace(USERNAME, &ldap_escape(username)))
+    }
+}
+
+/// LDAP / Active Directory of `auth.ldap_config`, verifying the credentials of the users by
+/// binding to it as them and reading the roles of their groups
+#[derive(Clone)]
+pub struct LdapDirectory {
+    config: LdapConfig,
+    address: Address,
+    user_filter: UserFilter,
+    settings: LdapConnSettings,
+    timeout: Duration,
+}
+
+impl LdapDirectory {
+    /// Fails when `auth.ldap_config` is set but unusable
+    pub fn validate(config: &Config) -> Result<()> {
+        let Some(auth) = config.auth.as_ref() else {
+            return Ok(());
+        };
+        let Some(ldap) = auth.ldap_config.as_ref() else {
+            return Ok(());
+        };
+        if !matches!(auth.auth_type, AuthType::JWT | AuthType::Session) {
+            return Err(RusterApiError::ConfigError("auth.ldap_config requires auth.auth_type JWT or Session".to_string()));
+        }
+        Address::parse(&ldap.url)?;
+        UserFilter::parse(ldap.user_filter.as_deref().unwrap_or(DEFAULT_USER_FILTER))?;
+        if !ldap.bind_dn.contains(USERNAME) {
+            return Err(RusterApiError::ConfigError(format!("auth.ldap_config.bind_dn must contain {}", USERNAME)));
+        }
+        if ldap.search_base.is_none() && !ldap.bind_dn.contains('=') {
+            return Err(RusterApiError::ConfigError(
+                "auth.ldap_config.search_base is required when bind_dn is not a DN".to_string()
+            ));
+        }
+        if ldap.service_dn.is_some() != ldap.service_password.is_some() {
+            return Err(RusterApiError::ConfigError(
+                "auth.ldap_config.service_dn and service_password must be set together".to_string()
+            ));
+        }
+        if ldap.timeout_seconds == Some(0) {
+            return Err(RusterApiError::ConfigError("auth.ldap_config.timeout_seconds must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Directory of the configuration, None without `auth.ldap_config`. Invalid configurations
+    /// are reported and leave LDAP sign ins disabled.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if let Err(e) = Self::validate(config) {
+            error!("LDAP sign ins are disabled: {}", e);
+            return None;
+        }
+        let mut ldap = config.auth.as_ref()?.ldap_config.clone()?;
+        if let Some(service_password) = &ldap.service_password {
+            match resolve_secret(service_password) {
+                Ok(password) => ldap.service_password = Some(password),
+                Err(e) => {
+                    error!("LDAP sign ins are disabled: {}", e);
+                    return None;
+                }
+            }
+        }
+        let address = Address::parse(&ldap.url).ok()?;
+        let user_filter = UserFilter::parse(ldap.user_filter.as_deref().unwrap_or(DEFAULT_USER_FILTER)).ok()?;
+        let timeout = Duration::from_secs(ldap.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS));
+        let mut settings = LdapConnSettings::new().set_conn_timeout(timeout);
+        if let Some(ca_file) = ldap.ca_file.as_deref().filter(|_| address.tls) {
+            match tls_config(ca_file) {
+                Ok(tls) => settings = settings.set_config(tls),
+                Err(e) => {
+                    error!("LDAP sign ins are disabled: {}", e);
+                    return None;
+                }
+            }
+        }
+        info!("Verifying sign ins against the directory {}:{}", address.host, address.port);
+        Some(Self { config: ldap, address, user_filter, settings, timeout })
+    }
+
+    pub fn config(&self) -> &LdapConfig {
+        &self.config
+    }
+
+    /// Whether the groups of the users can be read without their password, to refresh their tokens
+    pub fn can_look_up(&self) -> bool {
+        self.config.service_dn.is_some()
+    }
+
+    /// Roles of the user with the given credentials, None when the directory refuses them
+    pub fn authenticate(&self, username: &str, password: &str) -> Result<Option<Vec<String>>> {
+        // Binds without a password are anonymous, and succeed whatever the user name
+        if !valid_username(username) || password.is_empty() {
+            return Ok(None);
+        }
+        self.with_connection(|mut ldap| async move {
+            if !self.bind(&mut ldap, &self.user_dn(username), password).await? {
+                return Ok((ldap, None));
+            }
+            let groups = self.groups(&mut ldap, username).await?.unwrap_or_default();
+            Ok((ldap, Some(self.roles(groups))))
+        })
+    }
+
+    /// Roles of a user, read with the service account, None when the user no longer exists
+    pub fn roles_of(&self, username: &str) -> Result<Option<Vec<String>>> {
+        let (Some(service_dn), Some(service_password)) = (&self.config.service_dn, &self.config.service_password) else {
+            return Err(RusterApiError::ServerError("The directory has no service account to read users with".to_string()));
+        };
+        if !valid_username(username) {
+            return Ok(None);
+        }
+        self.with_connection(|mut ldap| async move {
+            if !self.bind(&mut ldap, service_dn, service_password).await? {
+                return Err(RusterApiError::ServerError("The directory refused the service account".to_string()));
+            }
+            let groups = self.groups(&mut ldap, username).await?;
+            Ok((ldap, groups.map(|groups| self.roles(groups))))
+        })
+    }
+
+    /// Name a user binds with
+    fn user_dn(&self, username: &str) -> String {
+        self.config.bind_dn.replace(USERNAME, &dn_escape(username))
+    }
+
+    /// Runs operations on a new connection to the directory, unbinding it afterwards. The
+    /// operations hand the connection back along with their result.
+    fn with_connection<R, F, Fut>(&self, operations: F) -> Result<R>
+    where
+        F: FnOnce(Ldap) -> Fut,
+        Fut: Future<Output = Result<(Ldap, R)>>,
+    {
+        let handle = Handle::try_current()
+            .map_err(|_| RusterApiError::ServerError("The directory is read outside of a Tokio runtime".to_string()))?;
+        handle.block_on(async {
+            let (connection, ldap) = LdapConnAsync::with_settings(self.settings.clone(), &self.config.url).await
+                .map_err(|e| self.unavailable(e))?;
+            drive!(connection);
+            let (mut ldap, result) = operations(ldap).await?;
+            let _ = ldap.unbind().await;
+            Ok(result)
+        })
+    }
+
+    /// Binds with a name and password, returning whether the directory accepted them
+    async fn bind(&self, ldap: &mut Ldap, name: &str, password: &str) -> Result<bool> {
+        let result = ldap.with_timeout(self.timeout).simple_bind(name, password).await.map_err(|e| self.unavailable(e))?;
+        match result.rc {
+            SUCCESS => Ok(true),
+            INVALID_CREDENTIALS => Ok(false),
+            code => Err(RusterApiError::ServerError(format!("The directory refused the bind with code {}: {}", code, result.text))),
+        }
+    }
+
+    /// Groups of the entry of a user, None when the entry is not found
+    async fn groups(&self, ldap: &mut Ldap, username: &str) -> Result<Option<Vec<String>>> {
+        let attribute = self.config.group_attribute.as_deref().unwrap_or(DEFAULT_GROUP_ATTRIBUTE);
+        let (base, scope, filter) = match &self.config.search_base {
+            Some(base) => (base.clone(), Scope::Subtree, self.user_filter.filter(username)),
+            None => (self.user_dn(username), Scope::Base, "(objectClass=*)".to_string()),
+        };
+        // A user name is expected to match one entry
+        let options = SearchOptions::new().sizelimit(2).timelimit(self.timeout.as_secs() as i32);
+        let search = ldap.with_search_options(options).with_timeout(self.timeout)
+            .search(&base, scope, &filter, vec![attribute]).await
+            .map_err(|e| self.unavailable(e))?;
+        let (entries, result) = (search.0, search.1);
+        if !matches!(result.rc, SUCCESS | NO_SUCH_OBJECT) {
+            return Err(RusterApiError::ServerError(format!("The directory search failed with code {}: {}", result.rc, result.text)));
+        }
+        Ok(entries.into_iter().next().map(|entry| attribute_values(SearchEntry::construct(entry), attribute)))
+    }
+
+    /// Roles of some groups: their mapped roles, or their names when they are not mapped
+    fn roles(&self, groups: Vec<String>) -> Vec<String> {
+        let mapped = |key: &str| {
+            self.config.group_mapping.iter().find(|(group, _)| group.eq_ignore_ascii_case(key)).map(|(_, role)| role.clone())
+        };
+        let mut roles: Vec<String> = Vec::new();
+        for group in groups {
+            let name = group_name(&group);
+            let role = mapped(&group).or_else(|| mapped(&name)).unwrap_or(name);
+            if !roles.contains(&role) {
+                roles.push(role);
+            }
+        }
+        roles
+    }
+
+    fn unavailable(&self, e: LdapError) -> RusterApiError {
+        RusterApiError::ServerError(format!("The directory {} is unavailable: {}", self.address.host, e))
+    }
+}
+
+/// TLS configuration trusting the certificate authorities of `ca_file`. Without it, the
+/// authorities of the system are trusted.
+fn tls_config(ca_file: &str) -> Result<Arc<ClientConfig>> {
+    let invalid = |e: String| RusterApiError::ConfigError(format!("Invalid auth.ldap_config.ca_file {}: {}", ca_file, e));
+    let file = File::open(ca_file).map_err(|e| invalid(e.to_string()))?;
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut BufReader::new(file)).map_err(|e| invalid(e.to_string()))? {
+        roots.add(&Certificate(cert)).map_err(|e| invalid(e.to_string()))?;
+    }
+    if roots.is_empty() {
+        return Err(invalid("no certificate found".to_string()));
+    }
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+/// Whether a user name can be sent to the directory
+fn valid_username(username: &str) -> bool {
+    !username.trim().is_empty() && !username.chars().any(char::is_control)
+}
+
+/// Values of an attribute of a search entry, whatever the case the directory names it with
+fn attribute_values(entry: SearchEntry, attribute: &str) -> Vec<String> {
+    entry.attrs.into_iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case(attribute))
+        .flat_map(|(_, values)| values)
+        .collect()
+}
+
+/// Name of a group: the value of the first component of its DN, like `admins` for
+/// `cn=admins,ou=groups,dc=example,dc=com`, or the value itself when it is not a DN
+fn group_name(group: &str) -> String {
+    let mut escaped = false;
+    let end = group.char_indices().find(|(_, c)| {
+        let separator = *c == ',' && !escaped;
+        escaped = *c == '\\' && !escaped;
+        separator
+    });
+    let first = &group[..end.map_or(group.len(), |(i, _)| i)];
+    match first.split_once('=') {
+        Some((_, value)) => value.replace('\\', ""),
+        None => group.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn directory(group_mapping: &[(&str, &str)]) -> LdapDirectory {
+        let config = LdapConfig {
+            url: "ldap://ldap.example.com".to_string(),
+            bind_dn: "uid={username},ou=people,dc=example,dc=com".to_string(),
+            search_base: None,
+            user_filter: None,
+            group_attribute: None,
+            group_mapping: group_mapping.iter().map(|(group, role)| (group.to_string(), role.to_string())).collect(),
+            service_dn: None,
+            service_password: None,
+            ca_file: None,
+            timeout_seconds: None,
+        };
+        LdapDirectory {
+            address: Address::parse(&config.url).unwrap(),
+            user_filter: UserFilter::parse(DEFAULT_USER_FILTER).unwrap(),
+            settings: LdapConnSettings::new(),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECONDS),
+            config,
+        }
+    }
+
+    #[test]
+    fn maps_groups_by_dn_or_name() {
+        let directory = directory(&[("cn=rawst-admins,ou=groups,dc=example,dc=com", "admin"), ("Editors", "editor")]);
+        let roles = directory.roles(vec![
+            "cn=rawst-admins,ou=groups,dc=example,dc=com".to_string(),
+            "cn=editors,ou=groups,dc=example,dc=com".to_string(),
+        ]);
+        assert_eq!(roles, vec!["admin", "editor"]);
+    }
+
+    #[test]
+    fn keeps_unmapped_groups_by_name_once() {
+        let directory = directory(&[("staff", "employee")]);
+        let roles = directory.roles(vec![
+            "cn=ops,ou=groups,dc=example,dc=com".to_string(),
+            "cn=staff,ou=groups,dc=example,dc=com".to_string(),
+            "cn=staff,ou=legacy,dc=example,dc=com".to_string(),
+            "auditors".to_string(),
+        ]);
+        assert_eq!(roles, vec!["ops", "employee", "auditors"]);
+    }
+
+    #[test]
+    fn names_groups_with_escaped_commas() {
+        assert_eq!(group_name("cn=Sales\\, EMEA,ou=groups,dc=example,dc=com"), "Sales, EMEA");
+    }
+
+    #[test]
+    fn escapes_user_names_in_dns_and_filters() {
+        let directory = directory(&[]);
+        assert_eq!(directory.user_dn("ana,ou=admins"), "uid=ana\\2cou\\3dadmins,ou=people,dc=example,dc=com");
+        assert_eq!(directory.user_filter.filter("*)(uid=*"), "(uid=\\2a\\29\\28uid=\\2a)");
+    }
+
+    #[test]
+    fn refuses_empty_passwords_without_binding() {
+        // No runtime nor directory is needed, as the credentials are refused before connecting
+        assert_eq!(directory(&[]).authenticate("ana", "").unwrap(), None);
+        assert_eq!(directory(&[]).authenticate(" ", "secret").unwrap(), None);
+    }
+
+    #[test]
+    fn reads_group_attributes_whatever_their_case() {
+        let entry = SearchEntry {
+            dn: "uid=ana,ou=people,dc=example,dc=com".to_string(),
+            attrs: HashMap::from([("memberof".to_string(), vec!["cn=ops".to_string()])]),
+            bin_attrs: HashMap::new(),
+        };
+        assert_eq!(attribute_values(entry, DEFAULT_GROUP_ATTRIBUTE), vec!["cn=ops"]);
+    }
+}
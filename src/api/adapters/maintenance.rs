@@ -0,0 +1,72 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse};
+use crate::api::adapters::middleware::{Middleware, Next};
+use crate::config::configuration::Config;
+use crate::config::specific::entity_config::HttpMethod;
+use crate::error::{Result, RusterApiError};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::info;
+
+/// Path of the admin route reading and toggling the maintenance mode
+pub const MAINTENANCE_PATH: &str = "/admin/maintenance";
+
+/// Body of the requests toggling the maintenance mode
+#[derive(Deserialize)]
+struct MaintenanceToggle {
+    enabled: bool,
+}
+
+/// Maintenance mode of `server.maintenance`, toggled at runtime. Clones share the same mode.
+#[derive(Clone)]
+pub struct MaintenanceMode {
+    enabled: Arc<AtomicBool>,
+    retry_after_seconds: u64,
+}
+
+impl MaintenanceMode {
+    pub fn new(config: &Config) -> Self {
+        let maintenance = config.server.maintenance.clone().unwrap_or_default();
+        Self {
+            enabled: Arc::new(AtomicBool::new(maintenance.enabled)),
+            retry_after_seconds: maintenance.retry_after_seconds,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Whether the maintenance mode is on, and the seconds clients wait before retrying
+    pub fn status(&self) -> Value {
+        json!({ "enabled": self.is_enabled(), "retry_after_seconds": self.retry_after_seconds })
+    }
+
+    /// Toggles the maintenance mode from a `{"enabled": true}` body, returning the new status
+    pub fn toggle(&self, body: &str) -> Result<Value> {
+        let toggle: MaintenanceToggle = serde_json::from_str(body)
+            .map_err(|e| RusterApiError::BadRequest(format!("Expected {{\"enabled\": true|false}}: {}", e)))?;
+        self.enabled.store(toggle.enabled, Ordering::Relaxed);
+        info!("Maintenance mode {}", if toggle.enabled { "enabled" } else { "disabled" });
+        Ok(self.status())
+    }
+
+    /// Rejects the requests changing the entities while the maintenance mode is on. Every POST,
+    /// PUT, PATCH and DELETE is taken for a change, custom routes included.
+    pub fn check(&self, method: &HttpMethod) -> Result<()> {
+        if self.is_enabled() && !matches!(method, HttpMethod::GET) {
+            return Err(RusterApiError::Maintenance(self.retry_after_seconds));
+        }
+        Ok(())
+    }
+}
+
+/// Layer rejecting the changes while the maintenance mode is on, so the endpoints, GraphQL and
+/// gRPC stay readable
+impl<T> Middleware<T> for MaintenanceMode {
+    fn handle(&self, request: ApiRequest, next: Next<'_, T>) -> Result<ApiResponse<T>> {
+        self.check(&request.method)?;
+        next.run(request)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::api::adapters::api_adapter::{ApiAdapterTrait, ApiRequest, ApiResponse};
+use crate::api::adapters::router::RouteInfo;
+use crate::config::specific::entity_config::HttpMethod;
+use crate::data::datasource::base::PoolStats;
+use crate::error::Result;
+use std::sync::Arc;
+
+/// A layer around the handling of the requests, for cross-cutting concerns like authentication,
+/// tenancy or auditing. A layer may answer a request itself, change it before passing it to the
+/// next layers, or change their response. Layers run off the async runtime, inside the span of the
+/// request, like the endpoints.
+///
+/// Closures taking the request and the next layers are layers as well, like
+/// `|request: ApiRequest, next: Next<'_, Value>| { ...; next.run(request) }`.
+pub trait Middleware<T>: Send + Sync {
+    fn handle(&self, request: ApiRequest, next: Next<'_, T>) -> Result<ApiResponse<T>>;
+}
+
+impl<T, F> Middleware<T> for F
+where
+    F: Fn(ApiRequest, Next<'_, T>) -> Result<ApiResponse<T>> + Send + Sync,
+{
+    fn handle(&self, request: ApiRequest, next: Next<'_, T>) -> Result<ApiResponse<T>> {
+        self(request, next)
+    }
+}
+
+/// The layers following a layer in its pipeline, down to the adapter answering the requests
+pub struct Next<'a, T> {
+    layers: &'a [Arc<dyn Middleware<T>>],
+    adapter: &'a (dyn ApiAdapterTrait<T> + Send + Sync),
+}
+
+impl<T> Next<'_, T> {
+    /// Passes a request to the next layer, or to the adapter after the last one
+    pub fn run(self, request: ApiRequest) -> Result<ApiResponse<T>> {
+        match self.layers.split_first() {
+            Some((layer, layers)) => layer.handle(request, Next { layers, adapter: self.adapter }),
+            None => self.adapter.handle_request(request),
+        }
+    }
+
+    /// Key of the endpoint (like `GET:users/:id`) handling a request, if any
+    pub fn route_for(&self, method: &HttpMethod, path: &str) -> Option<String> {
+        self.adapter.route_for(method, path)
+    }
+}
+
+/// Adapter running the requests through ordered layers before another adapter. The first layer
+/// added is the outermost one: it sees the requests first and their responses last.
+pub struct Pipeline<T> {
+    inner: Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+    layers: Vec<Arc<dyn Middleware<T>>>,
+}
+
+impl<T: 'static> Pipeline<T> {
+    pub fn new(inner: Arc<dyn ApiAdapterTrait<T> + Send + Sync>) -> Self {
+        Self { inner, layers: Vec::new() }
+    }
+
+    /// Adds a layer inside the ones already added
+    pub fn layer(mut self, layer: Arc<dyn Middleware<T>>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Adds layers inside the ones already added, in order
+    pub fn layers(mut self, layers: impl IntoIterator<Item = Arc<dyn Middleware<T>>>) -> Self {
+        self.layers.extend(layers);
+        self
+    }
+
+    /// The pipeline as an adapter, which is the inner adapter itself without any layer
+    pub fn build(self) -> Arc<dyn ApiAdapterTrait<T> + Send + Sync> {
+        if self.layers.is_empty() {
+            return self.inner;
+        }
+        Arc::new(self)
+    }
+}
+
+impl<T> ApiAdapterTrait<T> for Pipeline<T> {
+    fn handle_request(&self, request: ApiRequest) -> Result<ApiResponse<T>> {
+        Next { layers: &self.layers, adapter: self.inner.as_ref() }.run(request)
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        self.inner.pool_stats()
+    }
+
+    fn route_for(&self, method: &HttpMethod, path: &str) -> Option<String> {
+        self.inner.route_for(method, path)
+    }
+
+    fn routes(&self) -> Vec<RouteInfo> {
+        self.inner.routes()
+    }
+}
@@ -0,0 +1,426 @@
+use crate::api::adapters::api_adapter::AuthContext;
+use crate::config::configuration::Config;
+use crate::config::secrets::resolve_secret;
+use crate::config::specific::auth_config::{AuthType, OAuthProvider};
+use crate::error::{Result, RusterApiError};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::runtime::Handle;
+use tracing::{debug, error, info};
+
+/// Prefix of the routes signing in with a provider, like `/auth/oauth/google`
+pub const OAUTH_LOGIN_PATH: &str = "/auth/oauth";
+/// Claim holding the roles when `roles_claim` is not set
+const DEFAULT_ROLES_CLAIM: &str = "roles";
+/// Scopes asked for when a provider sets none
+const DEFAULT_SCOPES: &str = "openid";
+/// Minimum time between two fetches of the keys of a provider, so tokens with unknown key ids
+/// cannot flood it
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Seconds a user has to sign in at the provider before the state of the flow expires
+const STATE_TTL_SECONDS: u64 = 600;
+/// Timeout of the requests to the providers
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Signing keys of a provider, fetched from its JWKS
+#[derive(Default)]
+struct KeyCache {
+    keys: Vec<Jwk>,
+    fetched_at: Option<Instant>,
+}
+
+struct Provider {
+    config: OAuthProvider,
+    keys: RwLock<KeyCache>,
+}
+
+/// State of an authorization-code flow, sent to the provider and back to the callback. It is
+/// signed with the secret of the provider, so the callback only accepts the flows it started.
+#[derive(Serialize, Deserialize)]
+struct FlowState {
+    provider: String,
+    issued_at: u64,
+    nonce: String,
+}
+
+/// OAuth2 / OpenID Connect providers of `auth.oauth_config`, validating bearer tokens against
+/// their signing keys and running the authorization-code flow. Clones share the cached keys.
+#[derive(Clone)]
+pub struct OAuthProviders {
+    providers: Arc<Vec<Provider>>,
+    callback_url: String,
+    client: reqwest::Client,
+}
+
+impl OAuthProviders {
+    /// Providers of the configuration, None unless `auth.auth_type` is OAuth
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let Some(auth) = config.auth.as_ref().filter(|auth| matches!(auth.auth_type, AuthType::OAuth)) else {
+            return Ok(None);
+        };
+        let oauth = auth.oauth_config.as_ref().ok_or_else(|| {
+            RusterApiError::ConfigError("auth.auth_type OAuth requires auth.oauth_config".to_string())
+        })?;
+        if oauth.providers.is_empty() {
+            return Err(RusterApiError::ConfigError("auth.oauth_config needs at least one provider".to_string()));
+        }
+        reqwest::Url::parse(&oauth.callback_url).map_err(|e| {
+            RusterApiError::ConfigError(format!("Invalid auth.oauth_config.callback_url {}: {}", oauth.callback_url, e))
+        })?;
+        for provider in &oauth.providers {
+            if provider.jwks_url.is_none() && provider.issuer.is_none() {
+                return Err(RusterApiError::ConfigError(format!(
+                    "OAuth provider {} needs a jwks_url or an issuer", provider.name
+                )));
+            }
+        }
+        let client = reqwest::Client::builder().timeout(PROVIDER_TIMEOUT).build().map_err(|e| {
+            RusterApiError::ConfigError(format!("Failed to build the OAuth client: {}", e))
+        })?;
+        let providers = oauth.providers.iter()
+            .map(|provider| {
+                let client_secret = resolve_secret(&provider.client_secret)
+                    .map_err(|e| RusterApiError::ConfigError(e.to_string()))?;
+                let config = OAuthProvider { client_secret, ..provider.clone() };
+                Ok(Provider { config, keys: RwLock::new(KeyCache::default()) })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        info!("Validating bearer tokens of {} OAuth provider(s)", oauth.providers.len());
+        Ok(Some(Self {
+            providers: Arc::new(providers),
+            callback_url: oauth.callback_url.clone(),
+            client,
+        }))
+    }
+
+    /// Path of the callback URL, served by the API
+    pub fn callback_path(&self) -> String {
+        reqwest::Url::parse(&self.callback_url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| "/".to_string())
+    }
+
+    /// Verifies a bearer token against the signing keys of the provider that issued it, returning
+    /// the caller with the roles of its claims. Runs off the async runtime, like the endpoints, as
+    /// the keys are fetched when the token names one that is not cached yet.
+    pub fn verify(&self, token: &str) -> Result<AuthContext> {
+        let header = decode_header(token)
+            .map_err(|e| RusterApiError::AuthError(format!("Invalid bearer token: {}", e)))?;
+        let kid = header.kid.as_deref()
+            .ok_or_else(|| RusterApiError::AuthError("The bearer token does not name its signing key".to_string()))?;
+        let issuer = unverified_issuer(token);
+
+        let mut failure = RusterApiError::AuthError("The bearer token was not issued by a configured provider".to_string());
+        let candidates = self.providers.iter().filter(|provider| match (&provider.config.issuer, &issuer) {
+            (Some(expected), Some(issuer)) => expected == issuer,
+            (Some(_), None) => false,
+            (None, _) => true,
+        });
+        for provider in candidates {
+            let Some(jwk) = self.signing_key(provider, kid)? else {
+                continue;
+            };
+            match validate(&provider.config, token, header.alg, &jwk) {
+                Ok(claims) => return Ok(auth_context(&provider.config, claims)),
+                Err(err) => failure = err,
+            }
+        }
+        Err(failure)
+    }
+
+    /// Key of a provider with the given id, fetching the keys again when it is not cached and
+    /// they were not fetched recently
+    fn signing_key(&self, provider: &Provider, kid: &str) -> Result<Option<Jwk>> {
+        {
+            let mut cache = provider.keys.write().unwrap_or_else(|e| e.into_inner());
+            if let Some(jwk) = find_key(&cache.keys, kid) {
+                return Ok(Some(jwk));
+            }
+            if cache.fetched_at.is_some_and(|fetched_at| fetched_at.elapsed() < JWKS_REFRESH_INTERVAL) {
+                return Ok(None);
+            }
+            // Marked before fetching, so concurrent requests do not fetch the keys as well
+            cache.fetched_at = Some(Instant::now());
+        }
+
+        let handle = Handle::try_current()
+            .map_err(|_| RusterApiError::ServerError("OAuth keys are fetched outside of a Tokio runtime".to_string()))?;
+        let keys = handle.block_on(self.fetch_keys(&provider.config)).map_err(|e| {
+            error!("Failed to fetch the signing keys of OAuth provider {}: {}", provider.config.name, e);
+            RusterApiError::ServerError(format!("The signing keys of OAuth provider {} are unavailable", provider.config.name))
+        })?;
+        debug!("Fetched {} signing key(s) of OAuth provider {}", keys.len(), provider.config.name);
+        let mut cache = provider.keys.write().unwrap_or_else(|e| e.into_inner());
+        cache.keys = keys;
+        Ok(find_key(&cache.keys, kid))
+    }
+
+    /// Fetches the JWKS of a provider, discovering its URL from the issuer when it is not configured
+    async fn fetch_keys(&self, provider: &OAuthProvider) -> reqwest::Result<Vec<Jwk>> {
+        let jwks_url = match (&provider.jwks_url, &provider.issuer) {
+            (Some(jwks_url), _) => jwks_url.clone(),
+            (None, Some(issuer)) => {
+                let discovery = format!("{}/.well-known/openid-configuration", issuer.trim_end_matches('/'));
+                let document: Value = self.client.get(discovery).send().await?.error_for_status()?.json().await?;
+                document["jwks_uri"].as_str().unwrap_or_default().to_string()
+            }
+            (None, None) => String::new(),
+        };
+        let jwks: Value = self.client.get(jwks_url).send().await?.error_for_status()?.json().await?;
+        Ok(supported_keys(&jwks))
+    }
+
+    /// URL of the authorization page of a provider the sign in redirects to, asking for a code
+    /// sent to the callback URL
+    pub fn authorize_url(&self, name: &str) -> Result<String> {
+        let provider = self.provider(name)?;
+        let state = FlowState { provider: provider.name.clone(), issued_at: now_seconds(), nonce: uuid::Uuid::new_v4().to_string() };
+        let scopes = match provider.scopes.is_empty() {
+            true => DEFAULT_SCOPES.to_string(),
+            false => provider.scopes.join(" "),
+        };
+        let url = reqwest::Url::parse_with_params(&provider.auth_url, [
+            ("response_type", "code"),
+            ("client_id", provider.client_id.as_str()),
+            ("redirect_uri", self.callback_url.as_str()),
+            ("scope", scopes.as_str()),
+            ("state", sign_state(provider, &state).as_str()),
+        ]).map_err(|e| RusterApiError::ConfigError(format!("Invalid auth_url of OAuth provider {}: {}", provider.name, e)))?;
+        Ok(url.to_string())
+    }
+
+    /// Exchanges the authorization code of a callback for the tokens of the provider that started
+    /// the flow, returned as the provider sent them
+    pub async fn exchange(&self, query: &HashMap<String, String>) -> Result<Value> {
+        if let Some(error) = query.get("error") {
+            let description = query.get("error_description").map(|text| format!(": {}", text)).unwrap_or_default();
+            return Err(RusterApiError::AuthError(format!("The provider refused the sign in ({}){}", error, description)));
+        }
+        let (Some(code), Some(state)) = (query.get("code"), query.get("state")) else {
+            return Err(RusterApiError::BadRequest("The callback expects the code and state parameters".to_string()));
+        };
+        let provider = self.verify_state(state)?;
+
+        let response = self.client.post(&provider.token_url)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code.as_str()),
+                ("redirect_uri", self.callback_url.as_str()),
+                ("client_id", provider.client_id.as_str()),
+                ("client_secret", provider.client_secret.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| RusterApiError::ServerError(format!("Failed to reach OAuth provider {}: {}", provider.name, e)))?;
+        let status = response.status();
+        let tokens: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let reason = tokens["error_description"].as_str().or(tokens["error"].as_str()).unwrap_or("unknown error");
+            return Err(RusterApiError::AuthError(format!("OAuth provider {} refused the code: {}", provider.name, reason)));
+        }
+        Ok(tokens)
+    }
+
+    fn provider(&self, name: &str) -> Result<&OAuthProvider> {
+        self.providers.iter()
+            .map(|provider| &provider.config)
+            .find(|provider| provider.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| RusterApiError::NotFound(format!("Unknown OAuth provider {}", name)))
+    }
+
+    /// Provider of a state sent back to the callback, once its signature and age are checked
+    fn verify_state(&self, state: &str) -> Result<&OAuthProvider> {
+        let invalid = || RusterApiError::AuthError("Invalid or expired OAuth state".to_string());
+        let (payload, _) = state.split_once('.').ok_or_else(invalid)?;
+        let flow: FlowState = URL_SAFE_NO_PAD.decode(payload).ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(invalid)?;
+        let provider = self.provider(&flow.provider).map_err(|_| invalid())?;
+        if sign_state(provider, &flow) != state || now_seconds().saturating_sub(flow.issued_at) > STATE_TTL_SECONDS {
+            return Err(invalid());
+        }
+        Ok(provider)
+    }
+}
+
+/// Keys of a JWKS the validation supports. Symmetric keys are skipped, as a published secret
+/// would let anyone sign tokens.
+fn supported_keys(jwks: &Value) -> Vec<Jwk> {
+    jwks["keys"].as_array().into_iter().flatten()
+        .filter_map(|key| serde_json::from_value::<Jwk>(key.clone()).ok())
+        .filter(|jwk| !matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_)))
+        .collect()
+}
+
+/// Key with the given id, if any
+fn find_key(keys: &[Jwk], kid: &str) -> Option<Jwk> {
+    keys.iter().find(|jwk| jwk.common.key_id.as_deref() == Some(kid)).cloned()
+}
+
+/// Checks the signature, expiry, issuer and audience of a token, returning its claims
+fn validate(provider: &OAuthProvider, token: &str, algorithm: Algorithm, jwk: &Jwk) -> Result<Value> {
+    let invalid = |e: jsonwebtoken::errors::Error| RusterApiError::AuthError(format!("Invalid bearer token: {}", e));
+    // Keys published for an algorithm only verify tokens signed with it
+    if let Some(key_algorithm) = jwk.common.key_algorithm {
+        if key_algorithm.to_string().parse::<Algorithm>().ok() != Some(algorithm) {
+            return Err(RusterApiError::AuthError("The bearer token is not signed with the algorithm of its key".to_string()));
+        }
+    }
+    let key = DecodingKey::from_jwk(jwk).map_err(invalid)?;
+    let mut validation = Validation::new(algorithm);
+    if let Some(issuer) = &provider.issuer {
+        validation.set_issuer(&[issuer]);
+    }
+    match &provider.audience {
+        Some(audience) => validation.set_audience(&[audience]),
+        None => validation.validate_aud = false,
+    }
+    decode::<Value>(token, &key, &validation).map(|data| data.claims).map_err(invalid)
+}
+
+/// The caller a provider vouches for with the claims of a token
+fn auth_context(provider: &OAuthProvider, claims: Value) -> AuthContext {
+    let claim = provider.roles_claim.as_deref().unwrap_or(DEFAULT_ROLES_CLAIM);
+    let values: Vec<&str> = match claim.split('.').try_fold(&claims, |value, key| value.get(key)) {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+        Some(Value::String(text)) => text.split_whitespace().collect(),
+        _ => Vec::new(),
+    };
+    let mut roles: Vec<String> = Vec::new();
+    for value in values {
+        let role = provider.role_mapping.get(value).cloned().unwrap_or_else(|| value.to_string());
+        if !roles.contains(&role) {
+            roles.push(role);
+        }
+    }
+    AuthContext {
+        subject: claims["sub"].as_str().unwrap_or_default().to_string(),
+        roles,
+        claims,
+    }
+}
+
+/// `iss` claim of a token, read before its signature is verified to pick the provider checking it
+fn unverified_issuer(token: &str) -> Option<String> {
+    let payload = token.split('.').nth(1)?;
+    let claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    claims["iss"].as_str().map(str::to_string)
+}
+
+/// State of a flow followed by the hex HMAC-SHA256 of it, keyed with the secret of its provider
+fn sign_state(provider: &OAuthProvider, state: &FlowState) -> String {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(state).unwrap_or_default());
+    let mut mac = Hmac::<Sha256>::new_from_slice(provider.client_secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    let signature: String = mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}.{}", payload, signature)
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Modulus of an RSA public key published in the JWKS
+    const RSA_MODULUS: &str = "sXchDaQebHnPiGvyDOAT4saGEUetSyo9MKLOoWFsueri23bOdgWp4Dy1WlUzewbgBHod5pcM9H95GQRV3JDXboIRROSBigeC5yjU1hGzHHyXss8UDprecbAYxknTcQkhslANGRUZmdTOQ5qTRsLAt6BTYuyvVRdhS8exSZEy_c4gs_7svlJJQ4H9_NxsiIoLwAEk7-Q3UXERGYw_75IDrGA84-lA_-Ct4eTlXHBIY2EaV7t7LjJaynVJCpkv4LKjTTAumiGUIuQhrNhZLuF_RJLqHpM2kgWFLU7-VTdL1VbC2tejvcI2BlMkEpk1BzBZI0KQB0GaDWFLN-aEAw3vRw";
+
+    fn provider(name: &str, client_secret: &str) -> OAuthProvider {
+        OAuthProvider {
+            name: name.to_string(),
+            client_id: format!("{}-client", name),
+            client_secret: client_secret.to_string(),
+            auth_url: format!("https://{}.example.com/authorize", name),
+            token_url: format!("https://{}.example.com/token", name),
+            issuer: None,
+            jwks_url: Some(format!("https://{}.example.com/jwks", name)),
+            audience: None,
+            scopes: Vec::new(),
+            roles_claim: None,
+            role_mapping: HashMap::new(),
+        }
+    }
+
+    fn providers() -> OAuthProviders {
+        let providers = [provider("google", "google-secret"), provider("github", "github-secret")].into_iter()
+            .map(|config| Provider { config, keys: RwLock::new(KeyCache::default()) })
+            .collect();
+        OAuthProviders {
+            providers: Arc::new(providers),
+            callback_url: "https://api.example.com/auth/oauth/callback".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn state(provider: &str, issued_at: u64) -> FlowState {
+        FlowState { provider: provider.to_string(), issued_at, nonce: "nonce".to_string() }
+    }
+
+    #[test]
+    fn accepts_the_states_it_signed() {
+        let providers = providers();
+        let url = reqwest::Url::parse(&providers.authorize_url("github").unwrap()).unwrap();
+        let (_, state) = url.query_pairs().find(|(name, _)| name == "state").unwrap();
+        assert_eq!(providers.verify_state(&state).unwrap().name, "github");
+    }
+
+    #[test]
+    fn refuses_tampered_states() {
+        let providers = providers();
+        let signed = sign_state(&provider("google", "google-secret"), &state("google", now_seconds()));
+        let (payload, signature) = signed.split_once('.').unwrap();
+        let flipped = if signature.starts_with('0') { "1" } else { "0" };
+        assert!(providers.verify_state(&format!("{}.{}{}", payload, flipped, &signature[1..])).is_err());
+        assert!(providers.verify_state(payload).is_err());
+
+        // A payload naming another provider keeps the signature of the first one
+        let other = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&state("github", now_seconds())).unwrap());
+        assert!(providers.verify_state(&format!("{}.{}", other, signature)).is_err());
+    }
+
+    #[test]
+    fn refuses_states_signed_with_the_secret_of_another_provider() {
+        let forged = sign_state(&provider("google", "github-secret"), &state("google", now_seconds()));
+        assert!(providers().verify_state(&forged).is_err());
+    }
+
+    #[test]
+    fn refuses_expired_states() {
+        let issued_at = now_seconds() - STATE_TTL_SECONDS - 1;
+        let expired = sign_state(&provider("google", "google-secret"), &state("google", issued_at));
+        assert!(providers().verify_state(&expired).is_err());
+    }
+
+    #[test]
+    fn skips_symmetric_keys_of_the_jwks() {
+        let jwks = json!({ "keys": [
+            { "kty": "oct", "kid": "shared", "alg": "HS256", "k": "c2VjcmV0" },
+            { "kty": "RSA", "kid": "rsa", "alg": "RS256", "n": RSA_MODULUS, "e": "AQAB" },
+            { "kty": "unknown" },
+        ] });
+        let keys = supported_keys(&jwks);
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].common.key_id.as_deref(), Some("rsa"));
+    }
+
+    #[test]
+    fn refuses_tokens_signed_with_another_algorithm_than_their_key() {
+        let jwk: Jwk = serde_json::from_value(json!({
+            "kty": "RSA", "kid": "rsa", "alg": "RS256", "n": RSA_MODULUS, "e": "AQAB",
+        })).unwrap();
+        let error = validate(&provider("google", "google-secret"), "a.b.c", Algorithm::RS384, &jwk).unwrap_err();
+        assert!(error.to_string().contains("algorithm of its key"));
+    }
+}
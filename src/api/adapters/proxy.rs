@@ -0,0 +1,137 @@
+use crate::config::configuration::Config;
+use crate::error::{Result, RusterApiError};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Request header listing the client and the proxies a request went through, lowercase as captured
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+/// Request header holding the scheme the client used, lowercase as captured
+pub const FORWARDED_PROTO_HEADER: &str = "x-forwarded-proto";
+/// Request header holding the host the client asked for, lowercase as captured
+pub const FORWARDED_HOST_HEADER: &str = "x-forwarded-host";
+
+/// A range of addresses, like `10.0.0.0/8`
+#[derive(Clone)]
+struct Network {
+    address: IpAddr,
+    prefix: u32,
+}
+
+impl Network {
+    /// Parses an address or a CIDR range
+    fn parse(text: &str) -> Option<Self> {
+        let (address, prefix) = match text.trim().split_once('/') {
+            Some((address, prefix)) => (address.parse::<IpAddr>().ok()?, Some(prefix.parse::<u32>().ok()?)),
+            None => (text.trim().parse::<IpAddr>().ok()?, None),
+        };
+        let bits = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(bits);
+        (prefix <= bits).then_some(Self { address, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.address, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Trust of the forwarding headers of `server.proxy`. Without it, the headers are ignored and the
+/// peer of the connection is the client.
+#[derive(Clone, Default)]
+pub struct ProxyTrust {
+    enabled: bool,
+    /// Whether every peer is a trusted proxy
+    any: bool,
+    networks: Vec<Network>,
+    /// Scheme of the requests not forwarded by a trusted proxy
+    https: bool,
+}
+
+/// First value of a header listing several, like `https, http`
+fn first_value<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers.get(name)
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+impl ProxyTrust {
+    pub fn new(config: &Config) -> Result<Self> {
+        let https = config.server.tls.is_some();
+        let Some(proxy) = &config.server.proxy else {
+            return Ok(Self { https, ..Self::default() });
+        };
+        let mut networks = Vec::new();
+        let mut any = false;
+        for entry in &proxy.trusted_proxies {
+            if entry.trim() == "*" {
+                any = true;
+                continue;
+            }
+            let network = Network::parse(entry).ok_or_else(|| RusterApiError::ConfigError(format!(
+                "Invalid server.proxy.trusted_proxies entry {}: expected an address, a CIDR range or *", entry
+            )))?;
+            networks.push(network);
+        }
+        Ok(Self { enabled: true, any, networks, https })
+    }
+
+    /// Whether `server.proxy` is configured
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn trusts(&self, ip: IpAddr) -> bool {
+        self.any || self.networks.iter().any(|network| network.contains(ip))
+    }
+
+    /// Whether the forwarding headers of a request sent by a peer are trusted
+    fn forwarded_by_proxy(&self, peer: Option<IpAddr>) -> bool {
+        self.enabled && peer.is_some_and(|peer| self.trusts(peer))
+    }
+
+    /// Address of the client of a request sent by a peer. Behind trusted proxies, it is the last
+    /// address of `X-Forwarded-For` that is not a trusted proxy, as earlier ones may be forged.
+    pub fn client(&self, peer: Option<IpAddr>, headers: &HashMap<String, String>) -> Option<IpAddr> {
+        if !self.forwarded_by_proxy(peer) {
+            return peer;
+        }
+        let Some(forwarded) = headers.get(FORWARDED_FOR_HEADER) else {
+            return peer;
+        };
+        let mut client = peer;
+        for address in forwarded.rsplit(',') {
+            let Ok(address) = address.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = Some(address);
+            if !self.trusts(address) {
+                break;
+            }
+        }
+        client
+    }
+
+    /// Public origin of the API as the client of a request sent by a peer reached it, like
+    /// `https://api.example.com`, when the request names its host
+    pub fn origin(&self, peer: Option<IpAddr>, headers: &HashMap<String, String>) -> Option<String> {
+        let forwarded = self.forwarded_by_proxy(peer);
+        let scheme = forwarded.then(|| first_value(headers, FORWARDED_PROTO_HEADER)).flatten()
+            .map(str::to_lowercase)
+            .filter(|scheme| scheme == "http" || scheme == "https")
+            .unwrap_or_else(|| if self.https { "https" } else { "http" }.to_string());
+        let host = forwarded.then(|| first_value(headers, FORWARDED_HOST_HEADER)).flatten()
+            .or_else(|| first_value(headers, "host"))
+            .filter(|host| host.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']')))?;
+        Some(format!("{}://{}", scheme, host))
+    }
+}
@@ -0,0 +1,262 @@
+use crate::api::adapters::api_adapter::{ApiAdapterTrait, ApiRequest, ApiResponse, ApiResponseBody};
+use crate::api::adapters::router::RouteInfo;
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::handlers::common::utils::{default_headers, status_for_error};
+use crate::config::specific::entity_config::HttpMethod;
+use crate::config::specific::server_config::RecordReplayConfig;
+use crate::data::datasource::base::PoolStats;
+use crate::error::{Result, RusterApiError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// A recorded request/response pair, stored as one JSON line in the recording file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedExchange {
+    pub method: HttpMethod,
+    pub path: String,
+    pub params: BTreeMap<String, String>,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<Value>,
+    pub status: u16,
+    pub response_headers: HashMap<String, String>,
+    pub response_body: Option<Value>,
+    pub error: Option<String>,
+}
+
+impl RecordedExchange {
+    /// Key used to find the recorded exchange matching a request
+    fn request_key(method: &HttpMethod, path: &str, params: &BTreeMap<String, String>, body: &Option<Value>) -> String {
+        let body = body.as_ref().map(|b| b.to_string()).unwrap_or_default();
+        format!("{:?} {} {:?} {}", method, path.trim_matches('/'), params, body)
+    }
+
+    /// Key used as a fallback when no exchange matches the request body
+    fn route_key(method: &HttpMethod, path: &str, params: &BTreeMap<String, String>) -> String {
+        format!("{:?} {} {:?}", method, path.trim_matches('/'), params)
+    }
+}
+
+/// Removes sensitive values from the request and response headers and bodies before they are
+/// written to disk
+struct Sanitizer {
+    headers: Vec<String>,
+    fields: Vec<String>,
+}
+
+impl Sanitizer {
+    fn new(config: &RecordReplayConfig) -> Self {
+        Self {
+            headers: config.sanitize_headers.iter().map(|h| h.to_lowercase()).collect(),
+            fields: config.sanitize_fields.iter().map(|f| f.to_lowercase()).collect(),
+        }
+    }
+
+    fn headers(&self, headers: &HashMap<String, String>) -> BTreeMap<String, String> {
+        headers.iter()
+            .map(|(key, value)| {
+                if self.headers.contains(&key.to_lowercase()) {
+                    (key.clone(), REDACTED.to_string())
+                } else {
+                    (key.clone(), value.clone())
+                }
+            })
+            .collect()
+    }
+
+    fn value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, field) in map.iter_mut() {
+                    if self.fields.contains(&key.to_lowercase()) {
+                        *field = Value::String(REDACTED.to_string());
+                    } else {
+                        self.value(field);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.value(item)),
+            _ => {}
+        }
+    }
+
+    fn body(&self, body: &Option<String>) -> Option<Value> {
+        body.as_ref().filter(|b| !b.is_empty()).map(|b| {
+            let mut value = serde_json::from_str(b).unwrap_or_else(|_| Value::String(b.clone()));
+            self.value(&mut value);
+            value
+        })
+    }
+}
+
+/// Adapter wrapper that forwards requests to the inner adapter and appends every exchange to the recording file
+pub struct RecordingAdapter<T: ApiEntity> {
+    inner: Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+    file: Mutex<File>,
+    sanitizer: Sanitizer,
+}
+
+impl<T: ApiEntity> RecordingAdapter<T> {
+    /// Creates a recording adapter, appending to the configured recording file
+    pub fn new(inner: Arc<dyn ApiAdapterTrait<T> + Send + Sync>, config: &RecordReplayConfig) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        Ok(Self {
+            inner,
+            file: Mutex::new(file),
+            sanitizer: Sanitizer::new(config),
+        })
+    }
+
+    fn write(&self, exchange: &RecordedExchange) {
+        let line = match serde_json::to_string(exchange) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to serialize recorded exchange: {}", e);
+                return;
+            }
+        };
+        if let Ok(mut file) = self.file.lock() {
+            if let Err(e) = writeln!(file, "{}", line) {
+                warn!("Failed to write recorded exchange: {}", e);
+            }
+        }
+    }
+}
+
+impl<T: ApiEntity> ApiAdapterTrait<T> for RecordingAdapter<T> {
+    fn handle_request(&self, request: ApiRequest) -> Result<ApiResponse<T>> {
+        let method = request.method.clone();
+        let path = request.path.clone();
+        // Query parameters are recorded with the path parameters so that, for instance, pages do not collide
+        let params: BTreeMap<String, String> = request.params.iter().chain(&request.query)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let headers = self.sanitizer.headers(&request.headers);
+        let body = self.sanitizer.body(&request.body);
+
+        let result = self.inner.handle_request(request);
+
+        let (status, response_headers, response_body, error) = match &result {
+            Ok(response) => {
+                let mut response_body = response.body.as_ref().and_then(|b| serde_json::to_value(b).ok());
+                if let Some(value) = response_body.as_mut() {
+                    self.sanitizer.value(value);
+                }
+                let response_headers = self.sanitizer.headers(&response.headers).into_iter().collect();
+                (response.status, response_headers, response_body, None)
+            }
+            Err(err) => (status_for_error(err), default_headers(), None, Some(err.to_string())),
+        };
+
+        self.write(&RecordedExchange {
+            method,
+            path,
+            params,
+            headers,
+            body,
+            status,
+            response_headers,
+            response_body,
+            error,
+        });
+
+        result
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        self.inner.pool_stats()
+    }
+
+    fn route_for(&self, method: &HttpMethod, path: &str) -> Option<String> {
+        self.inner.route_for(method, path)
+    }
+
+    fn routes(&self) -> Vec<RouteInfo> {
+        self.inner.routes()
+    }
+}
+
+/// Adapter that serves previously recorded exchanges without any datasource
+pub struct ReplayAdapter<T: ApiEntity> {
+    exchanges: HashMap<String, RecordedExchange>,
+    routes: HashMap<String, RecordedExchange>,
+    sanitizer: Sanitizer,
+    _entity: PhantomData<T>,
+}
+
+impl<T: ApiEntity> ReplayAdapter<T> {
+    /// Loads the recording file; later exchanges for the same request replace earlier ones
+    pub fn new(config: &RecordReplayConfig) -> Result<Self> {
+        let contents = fs::read_to_string(&config.path)?;
+        let mut exchanges = HashMap::new();
+        let mut routes = HashMap::new();
+
+        for (line_number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let exchange: RecordedExchange = serde_json::from_str(line).map_err(|e| {
+                RusterApiError::ConfigError(format!(
+                    "Invalid recording at {}:{}: {}", config.path, line_number + 1, e
+                ))
+            })?;
+            let key = RecordedExchange::request_key(&exchange.method, &exchange.path, &exchange.params, &exchange.body);
+            let route = RecordedExchange::route_key(&exchange.method, &exchange.path, &exchange.params);
+            routes.insert(route, exchange.clone());
+            exchanges.insert(key, exchange);
+        }
+
+        info!("Loaded {} recorded exchanges from {}", exchanges.len(), config.path);
+        Ok(Self {
+            exchanges,
+            routes,
+            sanitizer: Sanitizer::new(config),
+            _entity: PhantomData,
+        })
+    }
+}
+
+impl<T: ApiEntity> ApiAdapterTrait<T> for ReplayAdapter<T> {
+    fn handle_request(&self, request: ApiRequest) -> Result<ApiResponse<T>> {
+        let params: BTreeMap<String, String> = request.params.into_iter().chain(request.query).collect();
+        let body = self.sanitizer.body(&request.body);
+        let key = RecordedExchange::request_key(&request.method, &request.path, &params, &body);
+        let route = RecordedExchange::route_key(&request.method, &request.path, &params);
+
+        let exchange = self.exchanges.get(&key)
+            .or_else(|| self.routes.get(&route))
+            .ok_or_else(|| RusterApiError::EntityNotFound(format!(
+                "No recorded exchange for {:?} {}", request.method, request.path
+            )))?;
+
+        if let Some(error) = &exchange.error {
+            return Err(match exchange.status {
+                404 => RusterApiError::EntityNotFound(error.clone()),
+                400 => RusterApiError::BadRequest(error.clone()),
+                _ => RusterApiError::ServerError(error.clone()),
+            });
+        }
+
+        let body = match &exchange.response_body {
+            Some(value) => Some(serde_json::from_value::<ApiResponseBody<T>>(value.clone())?),
+            None => None,
+        };
+
+        Ok(ApiResponse {
+            status: exchange.status,
+            // Redacted headers, like the session cookies, are left out rather than replayed
+            headers: exchange.response_headers.iter()
+                .filter(|(_, value)| value.as_str() != REDACTED)
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            body,
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use crate::api::handlers::common::utils::error_response;
+use crate::config::configuration::Config;
+use crate::config::specific::server_config::{ErrorFormat, DEFAULT_REQUEST_TIMEOUT_SECONDS};
+use crate::error::RusterApiError;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Timeout of the requests to the endpoints of an entity
+#[derive(Clone)]
+struct EntityTimeout {
+    /// Segments of the path the entity is served at, like `["v2", "users"]`
+    segments: Vec<String>,
+    name: String,
+    seconds: u64,
+}
+
+/// Timeout of a request, with the entity it was sent to if any
+pub struct RequestTimeout {
+    pub entity: Option<String>,
+    pub duration: Duration,
+}
+
+/// Timeouts of the requests to the endpoints: `server.request_timeout_seconds`, overridden by the
+/// `request_timeout_seconds` of each entity. Timeouts of 0 fall back to the server one, or 30 seconds.
+#[derive(Clone)]
+pub struct RequestTimeouts {
+    server: u64,
+    entities: Vec<EntityTimeout>,
+}
+
+impl RequestTimeouts {
+    pub fn new(config: &Config) -> Self {
+        let server = match config.server.request_timeout_seconds {
+            0 => DEFAULT_REQUEST_TIMEOUT_SECONDS,
+            seconds => seconds,
+        };
+        let entities = config.served_entities().into_iter()
+            .map(|(path, entity)| EntityTimeout {
+                segments: path.split('/').map(str::to_lowercase).collect(),
+                seconds: u64::from(entity.request_timeout_seconds.filter(|seconds| *seconds > 0).unwrap_or(server)),
+                name: entity.name,
+            })
+            .collect();
+        Self { server: u64::from(server), entities }
+    }
+
+    /// Timeout of a request to a path, from the entity served at the start of the path
+    pub fn for_path(&self, path: &str) -> RequestTimeout {
+        let segments: Vec<String> = path.split('/').filter(|s| !s.is_empty()).map(str::to_lowercase).collect();
+        let entity = self.entities.iter()
+            .filter(|entity| segments.starts_with(&entity.segments))
+            .max_by_key(|entity| entity.segments.len());
+        RequestTimeout {
+            entity: entity.map(|entity| entity.name.clone()),
+            duration: Duration::from_secs(entity.map_or(self.server, |entity| entity.seconds)),
+        }
+    }
+}
+
+/// Builds the 504 response of a request that timed out, in an error format. The body names the
+/// entity and the operation, like `GET /books/:id`, with the timeout they exceeded.
+pub fn timeout_response(timeout: &RequestTimeout, operation: &str, format: ErrorFormat, instance: &str) -> ApiResponse<Value> {
+    let seconds = timeout.duration.as_secs();
+    let err = RusterApiError::Timeout(format!("{} did not complete within {} seconds", operation, seconds));
+    let mut response = error_response(&err, format, instance);
+    if let Some(ApiResponseBody::Json(Value::Object(body))) = &mut response.body {
+        body.insert("entity".to_string(), json!(timeout.entity));
+        body.insert("operation".to_string(), json!(operation));
+        body.insert("timeout_seconds".to_string(), json!(seconds));
+    }
+    response
+}
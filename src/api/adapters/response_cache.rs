@@ -0,0 +1,179 @@
+use crate::api::adapters::api_adapter::{ApiAdapterTrait, ApiRequest, ApiResponse};
+use crate::api::adapters::router::RouteInfo;
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::handlers::common::etag::IF_NONE_MATCH_HEADER;
+use crate::api::handlers::common::http_cache::IF_MODIFIED_SINCE_HEADER;
+use crate::config::configuration::Config;
+use crate::config::specific::entity_config::{HttpMethod, ResponseCacheConfig};
+use crate::data::datasource::base::PoolStats;
+use crate::error::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Number of responses an entity caches when its configuration does not set a maximum
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+/// Header telling whether a response was served from the cache
+const CACHE_HEADER: &str = "X-Cache";
+
+/// An entity served by the API, with the cache of its responses if configured
+struct CachedEntity {
+    /// Segments of the path the entity is served at, like `["v2", "users"]`
+    segments: Vec<String>,
+    /// Name of the entity, shared by the versions serving it
+    name: String,
+    cache: Option<ResponseCacheConfig>,
+}
+
+struct CacheEntry<T> {
+    entity: String,
+    expires_at: Instant,
+    response: ApiResponse<T>,
+}
+
+/// Key of a cached response: the path and query of the request, its credentials and its tenant,
+/// so responses of authenticated requests are only served to the same credentials, be they a
+/// bearer token or a session cookie, and responses of a tenant only to that tenant
+fn cache_key(request: &ApiRequest) -> String {
+    let query: BTreeMap<&String, &String> = request.query.iter().collect();
+    let authorization = request.header("authorization").unwrap_or_default();
+    let cookie = request.header("cookie").unwrap_or_default();
+    let tenant = request.tenant.as_ref().map(|tenant| tenant.id.as_str()).unwrap_or_default();
+    format!("{}?{:?}#{}#{}#{}", request.path.trim_matches('/').to_lowercase(), query, authorization, cookie, tenant)
+}
+
+/// Number of cached responses of an entity
+fn entity_entries<T>(responses: &HashMap<String, CacheEntry<T>>, entity: &str) -> usize {
+    responses.values().filter(|entry| entry.entity == entity).count()
+}
+
+/// Adapter decorator caching the responses of the GET endpoints of the entities configuring
+/// `cache`. Successful mutations of an entity clear its cached responses, in every version of
+/// the API; mutations of paths not belonging to an entity, like batches, clear them all.
+pub struct ResponseCacheAdapter<T: ApiEntity> {
+    inner: Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+    entities: Vec<CachedEntity>,
+    responses: Mutex<HashMap<String, CacheEntry<T>>>,
+}
+
+impl<T: ApiEntity> ResponseCacheAdapter<T> {
+    /// Wraps an adapter when an entity of the configuration caches its responses
+    pub fn wrap(inner: Arc<dyn ApiAdapterTrait<T> + Send + Sync>, config: &Config) -> Arc<dyn ApiAdapterTrait<T> + Send + Sync> {
+        let entities: Vec<CachedEntity> = config.served_entities().into_iter()
+            .map(|(path, entity)| CachedEntity {
+                segments: path.split('/').map(str::to_lowercase).collect(),
+                name: entity.name.to_lowercase(),
+                cache: entity.cache,
+            })
+            .collect();
+        if entities.iter().all(|entity| entity.cache.is_none()) {
+            return inner;
+        }
+        Arc::new(Self { inner, entities, responses: Mutex::new(HashMap::new()) })
+    }
+
+    /// Entity served at the start of a path
+    fn entity_of(&self, path: &str) -> Option<&CachedEntity> {
+        let segments: Vec<String> = path.split('/').filter(|s| !s.is_empty()).map(str::to_lowercase).collect();
+        self.entities.iter()
+            .filter(|entity| segments.starts_with(&entity.segments))
+            .max_by_key(|entity| entity.segments.len())
+    }
+
+    fn lookup(&self, key: &str) -> Option<ApiResponse<T>> {
+        let mut responses = self.responses.lock().unwrap();
+        match responses.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.response.clone()),
+            Some(_) => {
+                responses.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn store(&self, key: String, entity: &str, cache: &ResponseCacheConfig, response: &ApiResponse<T>) {
+        let max_entries = cache.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES);
+        if max_entries == 0 {
+            return;
+        }
+        let now = Instant::now();
+        let mut responses = self.responses.lock().unwrap();
+        if entity_entries(&responses, entity) >= max_entries {
+            responses.retain(|_, entry| entry.entity != entity || entry.expires_at > now);
+        }
+        if entity_entries(&responses, entity) >= max_entries {
+            // Still full of live responses: the one expiring first makes room
+            let oldest = responses.iter()
+                .filter(|(_, entry)| entry.entity == entity)
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                responses.remove(&oldest);
+            }
+        }
+        let expires_at = now + Duration::from_secs(cache.ttl_seconds);
+        responses.insert(key, CacheEntry { entity: entity.to_string(), expires_at, response: response.clone() });
+    }
+
+    /// Clears the cached responses of an entity, or of every entity
+    fn invalidate(&self, entity: Option<&str>) {
+        let mut responses = self.responses.lock().unwrap();
+        match entity {
+            Some(entity) => responses.retain(|_, entry| entry.entity != entity),
+            None => responses.clear(),
+        }
+        debug!("Cleared the cached responses of {}", entity.unwrap_or("every entity"));
+    }
+}
+
+impl<T: ApiEntity> ApiAdapterTrait<T> for ResponseCacheAdapter<T> {
+    fn handle_request(&self, request: ApiRequest) -> Result<ApiResponse<T>> {
+        let entity = self.entity_of(&request.path);
+        if !matches!(request.method, HttpMethod::GET) {
+            let name = entity.map(|entity| entity.name.clone());
+            let result = self.inner.handle_request(request);
+            if result.as_ref().is_ok_and(|response| response.status < 400) {
+                self.invalidate(name.as_deref());
+            }
+            return result;
+        }
+
+        // Conditional requests are answered by the endpoints, which know the current ETag
+        let conditional = request.headers.keys()
+            .any(|name| name.eq_ignore_ascii_case(IF_NONE_MATCH_HEADER) || name.eq_ignore_ascii_case(IF_MODIFIED_SINCE_HEADER));
+        let Some((name, cache)) = entity.and_then(|entity| Some((&entity.name, entity.cache.as_ref()?))) else {
+            return self.inner.handle_request(request);
+        };
+        if conditional {
+            return self.inner.handle_request(request);
+        }
+
+        let key = cache_key(&request);
+        if let Some(mut response) = self.lookup(&key) {
+            response.headers.insert(CACHE_HEADER.to_string(), "HIT".to_string());
+            return Ok(response);
+        }
+        let mut result = self.inner.handle_request(request);
+        if let Ok(response) = &mut result {
+            if response.status == 200 {
+                self.store(key, name, cache, response);
+            }
+            response.headers.insert(CACHE_HEADER.to_string(), "MISS".to_string());
+        }
+        result
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        self.inner.pool_stats()
+    }
+
+    fn route_for(&self, method: &HttpMethod, path: &str) -> Option<String> {
+        self.inner.route_for(method, path)
+    }
+
+    fn routes(&self) -> Vec<RouteInfo> {
+        self.inner.routes()
+    }
+}
@@ -0,0 +1,230 @@
+use crate::api::adapters::api_adapter::{EndpointHandler, EntityApi};
+use crate::config::specific::entity_config::HttpMethod;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Path of the admin route listing the endpoints
+pub const ROUTES_PATH: &str = "/_routes";
+
+/// Description of an endpoint of the API, as listed by `/_routes`
+#[derive(Serialize, Debug, Clone)]
+pub struct RouteInfo {
+    pub method: String,
+    /// Path of the endpoint, like `/api/users/:id`
+    pub path: String,
+    /// Entity serving the endpoint, like `users` or `v2/users`
+    pub entity: String,
+    /// Key the endpoint was registered with, like `GET:users/:id`
+    pub key: String,
+    /// Whether the entity requires authentication
+    pub authentication: bool,
+}
+
+/// A segment of a route pattern
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// Text the path segment must equal, ignoring case
+    Literal(String),
+    /// Named parameter capturing the path segment, written `:name`
+    Param(String),
+}
+
+impl Segment {
+    fn parse(segment: &str) -> Self {
+        match segment.strip_prefix(':') {
+            Some(name) => Segment::Param(name.to_string()),
+            None => Segment::Literal(segment.to_string()),
+        }
+    }
+
+    /// Whether both segments match the same path segments, whatever the names of their parameters
+    fn overlaps(&self, other: &Segment) -> bool {
+        match (self, other) {
+            (Segment::Literal(left), Segment::Literal(right)) => left.eq_ignore_ascii_case(right),
+            (Segment::Param(_), Segment::Param(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A route of the table: an endpoint pattern and the handler serving it
+struct Route<T> {
+    /// Key the endpoint was registered with, like `GET:users/:id`
+    key: String,
+    segments: Vec<Segment>,
+    handler: EndpointHandler<T>,
+}
+
+impl<T> Clone for Route<T> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            segments: self.segments.clone(),
+            handler: self.handler.clone(),
+        }
+    }
+}
+
+/// Orders the routes of a method so the most specific one is tried first: at the first
+/// segment where two patterns differ, a literal comes before a parameter
+fn specificity<T>(a: &Route<T>, b: &Route<T>) -> Ordering {
+    for (left, right) in a.segments.iter().zip(&b.segments) {
+        match (left, right) {
+            (Segment::Literal(_), Segment::Param(_)) => return Ordering::Less,
+            (Segment::Param(_), Segment::Literal(_)) => return Ordering::Greater,
+            _ => {}
+        }
+    }
+    a.segments.len().cmp(&b.segments.len()).then_with(|| a.key.cmp(&b.key))
+}
+
+/// Endpoint key without the `api/` prefix of its pattern
+fn unprefixed(key: &str) -> String {
+    key.replacen(":api/", ":", 1)
+}
+
+/// A route matching a request, with the path parameters it captured
+pub struct RouteMatch<'a, T> {
+    /// Key the endpoint was registered with, like `GET:users/:id`
+    pub key: &'a str,
+    pub handler: &'a EndpointHandler<T>,
+    pub params: HashMap<String, String>,
+}
+
+/// Table of the endpoints of every entity, resolving a method and path to a single handler.
+/// Endpoint keys are `METHOD:pattern`, where the pattern may start with `api/` and its
+/// `:name` segments capture path parameters.
+pub struct RouteTable<T> {
+    /// Routes by method name, most specific first
+    routes: HashMap<String, Vec<Route<T>>>,
+}
+
+impl<T> Clone for RouteTable<T> {
+    fn clone(&self) -> Self {
+        Self { routes: self.routes.clone() }
+    }
+}
+
+impl<T> RouteTable<T> {
+    /// Builds the table from the endpoints of the entities. Endpoints registered both with and
+    /// without the `api/` prefix are routed once; of other endpoints matching the same paths,
+    /// the first key in order is routed and the rest are reported.
+    pub fn new(entities: &HashMap<String, EntityApi<T>>) -> Self {
+        let mut routes: HashMap<String, Vec<Route<T>>> = HashMap::new();
+        let mut keys: Vec<(&String, &EndpointHandler<T>)> = entities.values()
+            .flat_map(|entity_api| entity_api.endpoints.iter())
+            .collect();
+        // Unprefixed keys sort first, so they name the routes
+        keys.sort_by_key(|(key, _)| (key.contains(":api/"), key.to_string()));
+
+        for (key, handler) in keys {
+            let Some((method, pattern)) = key.split_once(':') else {
+                warn!("Ignoring endpoint key without a method: {}", key);
+                continue;
+            };
+            let pattern = pattern.strip_prefix("api/").unwrap_or(pattern);
+            let segments: Vec<Segment> = pattern.split('/').filter(|s| !s.is_empty()).map(Segment::parse).collect();
+            let method_routes = routes.entry(method.to_string()).or_default();
+            let routed = method_routes.iter().find(|route| {
+                route.segments.len() == segments.len() && route.segments.iter().zip(&segments).all(|(a, b)| a.overlaps(b))
+            });
+            if let Some(route) = routed {
+                if unprefixed(&route.key) != unprefixed(key) {
+                    warn!("Ignoring endpoint key {}, its paths are already routed to {}", key, route.key);
+                }
+                continue;
+            }
+            method_routes.push(Route { key: key.clone(), segments, handler: handler.clone() });
+        }
+
+        for method_routes in routes.values_mut() {
+            method_routes.sort_by(specificity);
+        }
+        Self { routes }
+    }
+
+    /// Finds the most specific route of a method matching the path segments
+    pub fn find(&self, method: &HttpMethod, path_parts: &[&str]) -> Option<RouteMatch<'_, T>> {
+        self.routes.get(&format!("{:?}", method))?
+            .iter()
+            .find_map(|route| {
+                if route.segments.len() != path_parts.len() {
+                    return None;
+                }
+                let mut params = HashMap::new();
+                for (segment, part) in route.segments.iter().zip(path_parts) {
+                    match segment {
+                        Segment::Param(name) => {
+                            params.insert(name.clone(), part.to_string());
+                        }
+                        Segment::Literal(text) if text.eq_ignore_ascii_case(part) => {}
+                        Segment::Literal(_) => return None,
+                    }
+                }
+                Some(RouteMatch { key: &route.key, handler: &route.handler, params })
+            })
+    }
+
+    /// Whether any route of any method starts with the given literal segment, like an entity name
+    pub fn serves(&self, first_segment: &str) -> bool {
+        self.routes.values().flatten().any(|route| {
+            matches!(route.segments.first(), Some(Segment::Literal(text)) if text.eq_ignore_ascii_case(first_segment))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::adapters::api_adapter::ApiResponse;
+    use crate::data::datasource::base::DataSource;
+    use crate::data::datasource::memory::in_memory::InMemoryDatasource;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    fn table(keys: &[&str]) -> RouteTable<Value> {
+        let handler: EndpointHandler<Value> = Arc::new(|_| Ok(ApiResponse { status: 200, headers: HashMap::new(), body: None }));
+        let endpoints = keys.iter().map(|key| (key.to_string(), handler.clone())).collect();
+        let datasource: Box<dyn DataSource<Value>> = Box::new(InMemoryDatasource::new());
+        RouteTable::new(&HashMap::from([("users".to_string(), EntityApi { datasource, endpoints })]))
+    }
+
+    /// Key of the route serving a path, with the parameters it captured
+    fn route(table: &RouteTable<Value>, method: HttpMethod, path: &str) -> Option<(String, HashMap<String, String>)> {
+        let parts: Vec<&str> = path.split('/').collect();
+        table.find(&method, &parts).map(|found| (found.key.to_string(), found.params))
+    }
+
+    #[test]
+    fn literals_win_over_parameters() {
+        let table = table(&["GET:users/:id", "GET:users/count", "GET:users/:id/export"]);
+        assert_eq!(route(&table, HttpMethod::GET, "users/count").unwrap().0, "GET:users/count");
+        let (key, params) = route(&table, HttpMethod::GET, "users/7").unwrap();
+        assert_eq!((key.as_str(), params.get("id").map(String::as_str)), ("GET:users/:id", Some("7")));
+        assert_eq!(route(&table, HttpMethod::GET, "users/7/export").unwrap().0, "GET:users/:id/export");
+        assert!(route(&table, HttpMethod::POST, "users/7").is_none());
+    }
+
+    #[test]
+    fn prefixed_twins_are_routed_once() {
+        let table = table(&["GET:api/users", "GET:users"]);
+        assert_eq!(table.routes["GET"].len(), 1);
+        assert_eq!(route(&table, HttpMethod::GET, "users").unwrap().0, "GET:users");
+    }
+
+    #[test]
+    fn matches_literals_ignoring_case() {
+        let table = table(&["GET:users/count"]);
+        assert_eq!(route(&table, HttpMethod::GET, "Users/COUNT").unwrap().0, "GET:users/count");
+        assert!(table.serves("USERS"));
+    }
+
+    #[test]
+    fn routes_the_first_of_colliding_keys() {
+        let table = table(&["GET:users/:user_id", "GET:users/:id", "GET:api/users/:uid"]);
+        assert_eq!(table.routes["GET"].len(), 1);
+        assert_eq!(route(&table, HttpMethod::GET, "users/1").unwrap().0, "GET:users/:id");
+    }
+}
@@ -0,0 +1,262 @@
+use crate::api::adapters::api_adapter::{ApiAdapterTrait, ApiRequest, ApiResponse, ApiResponseBody};
+use crate::api::adapters::router::RouteInfo;
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::handlers::common::fieldset::FIELDS_PARAM;
+use crate::api::handlers::common::include::INCLUDE_PARAM;
+use crate::config::specific::entity_config::HttpMethod;
+use crate::config::specific::server_config::SchemaSnapshotConfig;
+use crate::data::datasource::base::PoolStats;
+use crate::error::Result;
+use crate::events::event_bus::{EventBus, RawstEvent};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Drift of an endpoint response from its snapshot
+#[derive(Serialize, Debug, Clone)]
+pub struct DriftReport {
+    pub endpoint: String,
+    /// Differences between the snapshot and the live shape, like `added field items[].email`
+    pub changes: Vec<String>,
+    /// Time of the last drifted response in seconds since the Unix epoch
+    pub detected_at: u64,
+    /// Number of drifted responses seen
+    pub occurrences: u64,
+}
+
+/// Describes the JSON shape of a value: scalars become their type name, objects keep their keys
+/// and arrays hold the merged shape of their elements
+pub fn shape_of(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::from("null"),
+        Value::Bool(_) => Value::from("boolean"),
+        Value::Number(_) => Value::from("number"),
+        Value::String(_) => Value::from("string"),
+        Value::Array(items) => {
+            let element = items.iter().map(shape_of).reduce(merge_shapes);
+            Value::Array(element.into_iter().collect())
+        }
+        Value::Object(map) => Value::Object(map.iter().map(|(key, value)| (key.clone(), shape_of(value))).collect()),
+    }
+}
+
+/// Merges the shapes of two array elements. Null merges into any shape.
+fn merge_shapes(left: Value, right: Value) -> Value {
+    match (left, right) {
+        (left, right) if left == right => left,
+        (Value::String(null), other) | (other, Value::String(null)) if null == "null" => other,
+        (Value::Object(mut left), Value::Object(right)) => {
+            for (key, shape) in right {
+                let merged = match left.remove(&key) {
+                    Some(existing) => merge_shapes(existing, shape),
+                    None => shape,
+                };
+                left.insert(key, merged);
+            }
+            Value::Object(left)
+        }
+        (Value::Array(left), Value::Array(right)) => {
+            Value::Array(left.into_iter().chain(right).reduce(merge_shapes).into_iter().collect())
+        }
+        _ => Value::from("mixed"),
+    }
+}
+
+/// Name of a shape used in change descriptions
+fn shape_name(shape: &Value) -> &str {
+    match shape {
+        Value::String(name) => name,
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+        _ => "unknown",
+    }
+}
+
+/// Collects the differences between an expected and an actual shape.
+/// Nulls and empty arrays carry no shape information, so they never drift.
+fn diff_shapes(expected: &Value, actual: &Value, path: &str, changes: &mut Vec<String>) {
+    let field = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match (expected, actual) {
+        (Value::String(null), _) | (_, Value::String(null)) if null == "null" => {}
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, shape) in expected {
+                match actual.get(key) {
+                    Some(actual_shape) => diff_shapes(shape, actual_shape, &field(key), changes),
+                    None => changes.push(format!("removed field {}", field(key))),
+                }
+            }
+            for key in actual.keys().filter(|key| !expected.contains_key(*key)) {
+                changes.push(format!("added field {}", field(key)));
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if let (Some(expected), Some(actual)) = (expected.first(), actual.first()) {
+                diff_shapes(expected, actual, &format!("{}[]", path), changes);
+            }
+        }
+        (expected, actual) if expected != actual => {
+            let location = if path.is_empty() { "the response" } else { path };
+            changes.push(format!("type of {} changed from {} to {}", location, shape_name(expected), shape_name(actual)));
+        }
+        _ => {}
+    }
+}
+
+#[derive(Default)]
+struct DriftState {
+    snapshots: BTreeMap<String, Value>,
+    reports: HashMap<String, DriftReport>,
+}
+
+/// Compares response shapes with the stored snapshots, recording the shape of endpoints seen
+/// for the first time. Clones share the same snapshots and reports.
+#[derive(Clone)]
+pub struct SchemaDriftMonitor {
+    config: SchemaSnapshotConfig,
+    state: Arc<Mutex<DriftState>>,
+    events: EventBus,
+}
+
+impl SchemaDriftMonitor {
+    /// Loads the snapshot file, starting without snapshots if it does not exist yet
+    pub fn new(config: &SchemaSnapshotConfig, events: EventBus) -> Result<Self> {
+        let snapshots = if Path::new(&config.path).exists() {
+            serde_json::from_str(&fs::read_to_string(&config.path)?)?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            config: config.clone(),
+            state: Arc::new(Mutex::new(DriftState { snapshots, reports: HashMap::new() })),
+            events,
+        })
+    }
+
+    /// Checks the shape of a response body against the snapshot of its endpoint
+    pub fn observe(&self, endpoint: &str, body: &Value) {
+        let shape = shape_of(body);
+        let mut state = self.state.lock().unwrap();
+
+        let expected = match state.snapshots.get(endpoint) {
+            Some(expected) => expected.clone(),
+            None => {
+                info!("Schema snapshot recorded for {}", endpoint);
+                state.snapshots.insert(endpoint.to_string(), shape);
+                self.save(&state.snapshots);
+                return;
+            }
+        };
+
+        let mut changes = Vec::new();
+        diff_shapes(&expected, &shape, "", &mut changes);
+        if changes.is_empty() {
+            // Fill in the parts the snapshot could not describe, like empty lists
+            let refined = merge_shapes(expected.clone(), shape);
+            if refined != expected {
+                state.snapshots.insert(endpoint.to_string(), refined);
+                self.save(&state.snapshots);
+            }
+            return;
+        }
+
+        if self.config.update {
+            info!("Schema snapshot updated for {}: {}", endpoint, changes.join(", "));
+            state.snapshots.insert(endpoint.to_string(), shape);
+            self.save(&state.snapshots);
+            return;
+        }
+
+        let detected_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let report = state.reports.entry(endpoint.to_string()).or_insert_with(|| DriftReport {
+            endpoint: endpoint.to_string(),
+            changes: Vec::new(),
+            detected_at,
+            occurrences: 0,
+        });
+        report.detected_at = detected_at;
+        report.occurrences += 1;
+
+        // Only new changes are logged, so a drifted endpoint does not flood the log
+        if report.changes != changes {
+            warn!("Schema drift on {}: {}", endpoint, changes.join(", "));
+            report.changes = changes.clone();
+            self.events.publish(RawstEvent::SchemaDrift {
+                endpoint: endpoint.to_string(),
+                changes,
+            });
+        }
+    }
+
+    /// Returns the drift reports, sorted by endpoint
+    pub fn reports(&self) -> Vec<DriftReport> {
+        let mut reports: Vec<DriftReport> = self.state.lock().unwrap().reports.values().cloned().collect();
+        reports.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+        reports
+    }
+
+    /// Number of endpoints with a snapshot
+    pub fn snapshot_count(&self) -> usize {
+        self.state.lock().unwrap().snapshots.len()
+    }
+
+    fn save(&self, snapshots: &BTreeMap<String, Value>) {
+        let result = serde_json::to_string_pretty(snapshots)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&self.config.path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Failed to write schema snapshots to {}: {}", self.config.path, e);
+        }
+    }
+}
+
+/// Adapter wrapper checking the shape of every successful response for drift
+pub struct SchemaDriftAdapter<T: ApiEntity> {
+    inner: Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+    monitor: SchemaDriftMonitor,
+}
+
+impl<T: ApiEntity> SchemaDriftAdapter<T> {
+    pub fn new(inner: Arc<dyn ApiAdapterTrait<T> + Send + Sync>, monitor: SchemaDriftMonitor) -> Self {
+        Self { inner, monitor }
+    }
+}
+
+impl<T: ApiEntity> ApiAdapterTrait<T> for SchemaDriftAdapter<T> {
+    fn handle_request(&self, request: ApiRequest) -> Result<ApiResponse<T>> {
+        let route = self.inner.route_for(&request.method, &request.path)
+            .unwrap_or_else(|| format!("{:?}:{}", request.method, request.path.trim_matches('/')));
+        // Sparse fieldsets and included relationships change the shape on purpose,
+        // so they are not compared with the snapshot
+        let reshaped = request.query.contains_key(FIELDS_PARAM) || request.query.contains_key(INCLUDE_PARAM);
+        let result = self.inner.handle_request(request);
+
+        if let Ok(response) = &result {
+            let text = matches!(response.body, Some(ApiResponseBody::Text(_)));
+            if !reshaped && !text && (200..300).contains(&response.status) {
+                if let Some(Ok(body)) = response.body.as_ref().map(serde_json::to_value) {
+                    self.monitor.observe(&format!("{} {}", route, response.status), &body);
+                }
+            }
+        }
+        result
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        self.inner.pool_stats()
+    }
+
+    fn route_for(&self, method: &HttpMethod, path: &str) -> Option<String> {
+        self.inner.route_for(method, path)
+    }
+
+    fn routes(&self) -> Vec<RouteInfo> {
+        self.inner.routes()
+    }
+}
+
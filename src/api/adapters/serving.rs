@@ -0,0 +1,605 @@
+use crate::api::adapters::api_adapter::{ApiAdapter, ApiAdapterTrait, ApiRequest, ApiResponse, ApiResponseBody, EntityApi};
+use crate::api::adapters::authentication::Authenticator;
+use crate::api::adapters::change_access::{ChangeAccess, ChangeReader};
+use crate::api::adapters::jwt::JwtTokens;
+use crate::api::adapters::ldap::LdapDirectory;
+use crate::api::adapters::maintenance::MaintenanceMode;
+use crate::api::adapters::middleware::{Middleware, Pipeline};
+use crate::api::adapters::oauth::OAuthProviders;
+use crate::api::adapters::proxy::ProxyTrust;
+use crate::api::adapters::record_replay::{RecordingAdapter, ReplayAdapter};
+use crate::api::adapters::request_timeout::{timeout_response, RequestTimeouts};
+use crate::api::adapters::response_cache::ResponseCacheAdapter;
+use crate::api::adapters::schema_drift::{SchemaDriftAdapter, SchemaDriftMonitor};
+use crate::api::adapters::session::Sessions;
+use crate::api::adapters::tenancy::TenantResolver;
+use crate::api::adapters::uploads::UploadStore;
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::docs::admin_panel::AdminPanel;
+use crate::api::docs::openapi::ApiDocs;
+use crate::api::handlers::common::request_id::tag_response;
+#[cfg(feature = "graphql")]
+use crate::api::handlers::common::request_id::request_id;
+#[cfg(feature = "graphql")]
+use crate::api::graphql::schema::{build_schema, execute, GraphqlEndpoint, GraphqlRequest, GRAPHQL_PATH};
+#[cfg(feature = "grpc")]
+use crate::api::grpc::server::spawn_server as spawn_grpc_server;
+#[cfg(feature = "graphql")]
+use async_graphql::dynamic::Schema;
+use crate::api::handlers::common::utils::{error_response, status_for_error};
+use crate::api::rocket::rate_limit::RateLimiter;
+use crate::backup::backup_manager::BackupManager;
+use crate::config::configuration::Config;
+use crate::config::specific::auth_config::AuthType;
+use crate::config::specific::events_config::BrokerType;
+use crate::config::specific::server_config::{ErrorFormat, RecordMode, ResponseFormat, DEFAULT_MAX_PAYLOAD_SIZE_MB};
+use crate::config::validation::restricts_callers;
+use crate::error::{Result, RusterApiError};
+use crate::events::event_bus::{EventBus, RawstEvent};
+#[cfg(any(feature = "kafka", feature = "rabbitmq", feature = "nats"))]
+use crate::events::broker::OutboxRelay;
+#[cfg(any(feature = "kafka", feature = "rabbitmq", feature = "nats"))]
+use crate::events::outbox::Outbox;
+use crate::events::webhooks::WebhookDispatcher;
+use crate::health::health_monitor::{HealthMonitor, HealthRegistry};
+use crate::logging::logger::log_request;
+use futures::stream::{self, Stream};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, field, info, info_span, Instrument, Span};
+
+/// What a web framework serves: the adapter wrapped in the record-and-replay, schema drift and
+/// response cache decorators the configuration asks for, then in the pipeline of the maintenance
+/// mode, the authentication, the tenancy and the middleware of the adapter, with the health of the
+/// monitored targets
+pub struct ServedApi<T> {
+    pub api_adapter: Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+    pub health: HealthRegistry,
+    pub schema_drift: Option<SchemaDriftMonitor>,
+    /// Bus of the changes of the entities and of the health and schema drift events
+    pub events: EventBus,
+    /// GraphQL endpoint, when `server.graphql` is configured
+    #[cfg(feature = "graphql")]
+    pub graphql: Option<GraphqlEndpoint>,
+    /// OpenAPI document and its viewer, when `documentation.generate_openapi` is set
+    pub docs: Option<ApiDocs>,
+    /// Admin panel of the entities, when `server.admin_panel` is set
+    pub admin_panel: Option<AdminPanel>,
+    /// Files uploaded to the Binary fields of the entities
+    pub uploads: UploadStore,
+    /// Trust of the forwarding headers of the reverse proxies in front of the server
+    pub proxy: ProxyTrust,
+    /// Maintenance mode rejecting the changes of the entities
+    pub maintenance: MaintenanceMode,
+    /// OAuth providers signing users in, when `auth.auth_type` is OAuth
+    pub oauth: Option<OAuthProviders>,
+    /// Access of the callers to the streamed changes of the entities
+    pub changes: ChangeAccess,
+}
+
+impl<T: ApiEntity> ServedApi<T> {
+    /// Prepares an adapter to be served, starting the backup scheduler, the health monitor, the
+    /// webhook dispatcher, the relay of the outbox to the broker and the gRPC server
+    pub fn new(api_adapter: ApiAdapter<T>) -> Result<Self> {
+        let config = api_adapter.config.clone();
+        let middleware = api_adapter.middleware.clone();
+
+        if let Some(backup_manager) = BackupManager::from_config(&config) {
+            backup_manager.spawn_scheduler();
+        }
+
+        let events = api_adapter.events.clone();
+        let health = HealthRegistry::new(config.health.as_ref());
+        if let Some(health_config) = config.health.clone() {
+            HealthMonitor::new(health_config, health.clone(), events.clone()).spawn();
+        }
+        if let Some(webhooks) = WebhookDispatcher::new(&config, events.clone()) {
+            webhooks.spawn();
+        }
+        broker_unavailable(&config)?;
+        #[cfg(any(feature = "kafka", feature = "rabbitmq", feature = "nats"))]
+        if config.events.is_some() {
+            Outbox::validate(&config)?;
+            let outbox = api_adapter.outbox.clone().ok_or_else(|| {
+                RusterApiError::ConfigError("events could not open its outbox table".to_string())
+            })?;
+            OutboxRelay::new(outbox).spawn();
+        }
+
+        JwtTokens::validate(&config)?;
+        revocations_unavailable(&config, &api_adapter.entities)?;
+        Sessions::validate(&config)?;
+        LdapDirectory::validate(&config)?;
+        TenantResolver::validate(&config)?;
+        let jwt = api_adapter.jwt.clone();
+        let sessions = api_adapter.sessions.clone();
+
+        let api_adapter: Arc<dyn ApiAdapterTrait<T> + Send + Sync> = match config.server.record_replay.clone() {
+            Some(record_config) if record_config.mode == RecordMode::Replay => {
+                Arc::new(ReplayAdapter::new(&record_config)?)
+            }
+            Some(record_config) => {
+                info!("Recording API exchanges to {}", record_config.path);
+                Arc::new(RecordingAdapter::new(Arc::new(api_adapter), &record_config)?)
+            }
+            None => Arc::new(api_adapter),
+        };
+
+        let schema_drift = match &config.server.schema_snapshots {
+            Some(snapshot_config) => Some(SchemaDriftMonitor::new(snapshot_config, events.clone())?),
+            None => None,
+        };
+        let api_adapter: Arc<dyn ApiAdapterTrait<T> + Send + Sync> = match &schema_drift {
+            Some(monitor) => Arc::new(SchemaDriftAdapter::new(api_adapter, monitor.clone())),
+            None => api_adapter,
+        };
+        let api_adapter = ResponseCacheAdapter::wrap(api_adapter, &config);
+        // Layers run before the decorators, so cached responses go through them as well
+        let maintenance = MaintenanceMode::new(&config);
+        let oauth = OAuthProviders::from_config(&config)?;
+        let authenticator = authenticator(&oauth, jwt, sessions, &api_adapter);
+        authentication_unavailable(&config, &authenticator)?;
+        let tenants = TenantResolver::from_config(&config).map(Arc::new);
+        let changes = ChangeAccess::new(&config, authenticator.clone(), tenants.clone());
+        let api_adapter = Pipeline::new(api_adapter)
+            .layer(Arc::new(maintenance.clone()))
+            .layers(authentication_layer(authenticator))
+            .layers(tenancy_layer(tenants))
+            .layers(middleware)
+            .build();
+
+        #[cfg(not(feature = "graphql"))]
+        graphql_unavailable(&config)?;
+        #[cfg(feature = "grpc")]
+        spawn_grpc_server(&config, api_adapter.clone())?;
+        #[cfg(not(feature = "grpc"))]
+        grpc_unavailable(&config)?;
+        Ok(Self {
+            #[cfg(feature = "graphql")]
+            graphql: graphql_endpoint(&config, &api_adapter)?,
+            api_adapter,
+            health,
+            schema_drift,
+            events,
+            docs: ApiDocs::from_config(&config)?,
+            admin_panel: AdminPanel::from_config(&config),
+            uploads: UploadStore::new(&config),
+            proxy: ProxyTrust::new(&config)?,
+            maintenance,
+            oauth,
+            changes,
+        })
+    }
+}
+
+impl ServedApi<Value> {
+    /// Prepares the recording configured in `ServerConfig.record_replay` to be replayed,
+    /// without connecting to any datasource
+    pub fn replay(config: &Config) -> Result<Self> {
+        let record_config = config.server.record_replay.as_ref().ok_or_else(|| {
+            RusterApiError::ConfigError("Replay requires server.record_replay to be configured".to_string())
+        })?;
+
+        let maintenance = MaintenanceMode::new(config);
+        let oauth = OAuthProviders::from_config(config)?;
+        JwtTokens::validate(config)?;
+        Sessions::validate(config)?;
+        LdapDirectory::validate(config)?;
+        TenantResolver::validate(config)?;
+        let api_adapter: Arc<dyn ApiAdapterTrait<Value> + Send + Sync> = Arc::new(ReplayAdapter::new(record_config)?);
+        let authenticator = authenticator(&oauth, JwtTokens::from_config(config), Sessions::from_config(config), &api_adapter);
+        authentication_unavailable(config, &authenticator)?;
+        let tenants = TenantResolver::from_config(config).map(Arc::new);
+        let changes = ChangeAccess::new(config, authenticator.clone(), tenants.clone());
+        let api_adapter = Pipeline::new(api_adapter)
+            .layer(Arc::new(maintenance.clone()))
+            .layers(authentication_layer(authenticator))
+            .layers(tenancy_layer(tenants))
+            .build();
+        #[cfg(not(feature = "graphql"))]
+        graphql_unavailable(config)?;
+        #[cfg(feature = "grpc")]
+        spawn_grpc_server(config, api_adapter.clone())?;
+        #[cfg(not(feature = "grpc"))]
+        grpc_unavailable(config)?;
+        Ok(Self {
+            #[cfg(feature = "graphql")]
+            graphql: graphql_endpoint(config, &api_adapter)?,
+            api_adapter,
+            health: HealthRegistry::new(None),
+            schema_drift: None,
+            events: EventBus::new(),
+            docs: ApiDocs::from_config(config)?,
+            admin_panel: AdminPanel::from_config(config),
+            uploads: UploadStore::new(config),
+            proxy: ProxyTrust::new(config)?,
+            maintenance,
+            oauth,
+            changes,
+        })
+    }
+}
+
+/// Authenticator of the requests to the endpoints of an adapter, when authentication is configured
+fn authenticator<T>(
+    oauth: &Option<OAuthProviders>,
+    jwt: Option<JwtTokens>,
+    sessions: Option<Sessions>,
+    api_adapter: &Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+) -> Option<Arc<Authenticator>> {
+    if oauth.is_none() && jwt.is_none() && sessions.is_none() {
+        return None;
+    }
+    Some(Arc::new(Authenticator::new(oauth.clone(), jwt, sessions, &api_adapter.routes())))
+}
+
+/// Layer authenticating the requests to the endpoints, when authentication is configured
+fn authentication_layer<T>(authenticator: Option<Arc<Authenticator>>) -> Option<Arc<dyn Middleware<T>>> {
+    authenticator.map(|authenticator| authenticator as Arc<dyn Middleware<T>>)
+}
+
+/// Layer resolving the tenant of the requests, when `tenancy` is configured. It runs after the
+/// authentication, as tenants may come from the claims of the caller.
+fn tenancy_layer<T>(tenants: Option<Arc<TenantResolver>>) -> Option<Arc<dyn Middleware<T>>> {
+    tenants.map(|resolver| resolver as Arc<dyn Middleware<T>>)
+}
+
+/// Generates the GraphQL endpoint of an adapter when `server.graphql` is configured
+#[cfg(feature = "graphql")]
+fn graphql_endpoint<T: ApiEntity>(config: &Config, api_adapter: &Arc<dyn ApiAdapterTrait<T> + Send + Sync>) -> Result<Option<GraphqlEndpoint>> {
+    let Some(graphql) = &config.server.graphql else {
+        return Ok(None);
+    };
+    Ok(Some(GraphqlEndpoint { schema: build_schema(config, api_adapter.clone())?, graphiql: graphql.graphiql }))
+}
+
+/// Fails when entities restricted to some callers are served without an authenticator telling
+/// who the callers are, which would leave them open to anonymous requests
+fn authentication_unavailable(config: &Config, authenticator: &Option<Arc<Authenticator>>) -> Result<()> {
+    if authenticator.is_some() {
+        return Ok(());
+    }
+    match config.served_entities().into_iter().find(|(_, entity)| restricts_callers(entity)) {
+        Some((path, _)) => Err(RusterApiError::ConfigError(format!(
+            "Entity {} requires authenticated callers, but auth configures no JWT, OAuth or Session authentication", path
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Fails when the `revocation_entity` of the JWT authentication is not served, which would keep
+/// the revoked tokens in memory, lost on restart and unknown to the other instances
+fn revocations_unavailable<T: ApiEntity>(config: &Config, entities: &HashMap<String, EntityApi<T>>) -> Result<()> {
+    let revocation_entity = config.auth.as_ref()
+        .filter(|auth| matches!(auth.auth_type, AuthType::JWT))
+        .and_then(|auth| auth.jwt_config.as_ref())
+        .and_then(|jwt| jwt.revocation_entity.as_ref());
+    match revocation_entity {
+        Some(name) if !entities.contains_key(&name.to_lowercase()) => Err(RusterApiError::ConfigError(format!(
+            "auth.jwt_config.revocation_entity {} is not served: no datasource stores it", name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Fails when `server.graphql` is configured in a build without the graphql feature
+#[cfg(not(feature = "graphql"))]
+fn graphql_unavailable(config: &Config) -> Result<()> {
+    match config.server.graphql {
+        Some(_) => Err(RusterApiError::ConfigError(
+            "server.graphql requires Rawst to be built with the graphql feature".to_string()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Fails when the broker of `events` is not built in, as each one requires its feature
+fn broker_unavailable(config: &Config) -> Result<()> {
+    let Some(events) = &config.events else {
+        return Ok(());
+    };
+    let feature = match events.broker {
+        BrokerType::Kafka if !cfg!(feature = "kafka") => "kafka",
+        BrokerType::RabbitMQ if !cfg!(feature = "rabbitmq") => "rabbitmq",
+        BrokerType::NATS if !cfg!(feature = "nats") => "nats",
+        _ => return Ok(()),
+    };
+    Err(RusterApiError::ConfigError(format!(
+        "events.broker {:?} requires Rawst to be built with the {} feature", events.broker, feature
+    )))
+}
+
+/// Fails when `server.grpc` is configured in a build without the grpc feature
+#[cfg(not(feature = "grpc"))]
+fn grpc_unavailable(config: &Config) -> Result<()> {
+    match config.server.grpc {
+        Some(_) => Err(RusterApiError::ConfigError(
+            "server.grpc requires Rawst to be built with the grpc feature".to_string()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Runs a GraphQL request once the rate limits let it through, logging it like the API requests.
+/// The response carries the identifier of the request.
+#[cfg(feature = "graphql")]
+pub async fn process_graphql(
+    schema: &Schema,
+    rate_limiter: &RateLimiter,
+    error_format: ErrorFormat,
+    body: Option<String>,
+    headers: HashMap<String, String>,
+    client: Option<IpAddr>,
+) -> ApiResponse<Value> {
+    let started = Instant::now();
+    let path = GRAPHQL_PATH.trim_start_matches('/');
+    let request_id = request_id(&headers);
+    let span = info_span!("graphql", otel.name = "POST /graphql", otel.kind = "server", request_id);
+    #[cfg(feature = "otel")]
+    crate::logging::telemetry::continue_trace(&span, &headers);
+
+    let mut api_response = match rate_limiter.check(path, client) {
+        Err(err) => error_response(&err, error_format, GRAPHQL_PATH),
+        Ok(()) => {
+            let http = GraphqlRequest { headers, request_id: request_id.clone() };
+            execute(schema, body.as_deref(), http).instrument(span).await
+        }
+    };
+    tag_response(&mut api_response, &request_id);
+    log_request("POST", path, None, &request_id, client, api_response.status, started.elapsed());
+    api_response
+}
+
+/// Runs a request through the endpoints off the async runtime, for the GraphQL and gRPC
+/// operations. Failures are reported with the status the endpoint answered and its message.
+pub async fn call_endpoint<T: ApiEntity>(
+    api_adapter: Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+    request: ApiRequest,
+) -> std::result::Result<ApiResponse<T>, (u16, String)> {
+    let result = tokio::task::spawn_blocking(move || api_adapter.handle_request(request))
+        .await
+        .map_err(|e| (500, format!("Request processing failed: {}", e)))?;
+    match result {
+        Ok(response) if response.status < 400 => Ok(response),
+        Ok(response) => {
+            let message = match &response.body {
+                Some(ApiResponseBody::Json(body)) => serde_json::to_value(body).ok().and_then(|body| {
+                    body.get("error").or_else(|| body.get("detail")).and_then(Value::as_str).map(str::to_string)
+                }),
+                _ => None,
+            };
+            Err((response.status, message.unwrap_or_else(|| format!("Request failed with status {}", response.status))))
+        }
+        Err(err) => Err((status_for_error(&err), err.to_string())),
+    }
+}
+
+/// Entities of a response body, as JSON
+pub fn response_items<T: ApiEntity>(body: Option<ApiResponseBody<T>>) -> Vec<Value> {
+    let items = match body {
+        Some(ApiResponseBody::List(items) | ApiResponseBody::Page { items, .. } | ApiResponseBody::Cursor { items, .. }) => items,
+        Some(ApiResponseBody::Single(item) | ApiResponseBody::Json(item)) => vec![item],
+        Some(ApiResponseBody::Text(_)) | None => Vec::new(),
+    };
+    items.iter().filter_map(|item| serde_json::to_value(item).ok()).collect()
+}
+
+/// Interval of the comments keeping idle change streams open through proxies
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Server-Sent Events frames of the changes of an entity the reader may read: `created`, `updated`
+/// and `deleted` events with the change as data, a `lagged` event when the client fell behind and
+/// missed some changes, and comments while there are none. Ends when the bus is dropped.
+pub fn change_stream(events: &EventBus, entity: &str, reader: ChangeReader) -> impl Stream<Item = String> + Send + 'static {
+    let receiver = events.subscribe();
+    let entity = entity.to_string();
+    let reader = Arc::new(reader);
+    stream::unfold(receiver, move |mut receiver| {
+        let entity = entity.clone();
+        let reader = reader.clone();
+        async move {
+            loop {
+                let frame = match tokio::time::timeout(HEARTBEAT_INTERVAL, receiver.recv()).await {
+                    Err(_) => ": keep-alive\n\n".to_string(),
+                    Ok(Ok(RawstEvent::EntityChanged(change))) if change.entity.eq_ignore_ascii_case(&entity) => {
+                        let Some(change) = reader.filter(&entity, &change) else {
+                            continue;
+                        };
+                        let data = serde_json::to_string(&change).unwrap_or_default();
+                        format!("event: {}\ndata: {}\n\n", change.action.name(), data)
+                    }
+                    Ok(Ok(_)) => continue,
+                    Ok(Err(RecvError::Lagged(missed))) => format!("event: lagged\ndata: {{\"missed\":{}}}\n\n", missed),
+                    Ok(Err(RecvError::Closed)) => return None,
+                };
+                return Some((frame, receiver));
+            }
+        }
+    })
+}
+
+/// Maximum size of request bodies in megabytes, as configured
+pub fn max_payload_size_mb(config: &Config) -> u32 {
+    match config.server.max_payload_size_mb {
+        0 => DEFAULT_MAX_PAYLOAD_SIZE_MB,
+        size => size,
+    }
+}
+
+/// Host of the listeners configuring neither their own host nor the server one
+const DEFAULT_LISTENER_HOST: &str = "127.0.0.1";
+
+/// An address the server listens on, from `server.listeners`
+pub struct Listener {
+    pub host: String,
+    pub port: u16,
+    /// Whether the admin routes are served on this listener
+    pub admin: bool,
+}
+
+/// Listeners of `server.listeners`. Without a listener marked admin, every listener serves the
+/// admin routes.
+pub fn listeners(config: &Config) -> Result<Vec<Listener>> {
+    let any_admin = config.server.listeners.iter().any(|listener| listener.admin);
+    config.server.listeners.iter()
+        .map(|listener| {
+            let host = [listener.host.trim(), config.server.host.trim()].into_iter()
+                .find(|host| !host.is_empty())
+                .unwrap_or(DEFAULT_LISTENER_HOST);
+            let port = u16::try_from(listener.port)
+                .map_err(|_| RusterApiError::ConfigError(format!("Invalid listener port {}", listener.port)))?;
+            Ok(Listener { host: host.to_string(), port, admin: listener.admin || !any_admin })
+        })
+        .collect()
+}
+
+/// Builds the multi-threaded Tokio runtime of a server, with the worker and blocking threads of
+/// `server.tuning`. Requests to the endpoints run on the blocking threads.
+pub fn server_runtime(config: &Config) -> Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(tuning) = &config.server.tuning {
+        if let Some(workers) = tuning.workers {
+            builder.worker_threads(workers);
+        }
+        if let Some(threads) = tuning.max_blocking_threads {
+            builder.max_blocking_threads(threads);
+        }
+    }
+    builder.build().map_err(|e| RusterApiError::ServerError(format!("Failed to start the server runtime: {}", e)))
+}
+
+/// PEM certificate chain and private key of an HTTPS server
+pub struct TlsCertificate {
+    pub cert: Vec<u8>,
+    pub key: Vec<u8>,
+}
+
+/// Reads the certificate configured in `server.tls`, or generates a self-signed one for the
+/// server host and `localhost` when asked to. None when TLS is not configured.
+pub fn tls_certificate(config: &Config) -> Result<Option<TlsCertificate>> {
+    let Some(tls) = &config.server.tls else {
+        return Ok(None);
+    };
+    let read = |path: &str| {
+        std::fs::read(path).map_err(|e| RusterApiError::ConfigError(format!("Failed to read TLS file {}: {}", path, e)))
+    };
+
+    match (&tls.cert_path, &tls.key_path) {
+        (Some(cert_path), Some(key_path)) => Ok(Some(TlsCertificate { cert: read(cert_path)?, key: read(key_path)? })),
+        (Some(_), None) | (None, Some(_)) => Err(RusterApiError::ConfigError(
+            "server.tls needs both cert_path and key_path".to_string()
+        )),
+        (None, None) if tls.self_signed => {
+            let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+            let host = config.server.host.trim();
+            if !host.is_empty() && !names.iter().any(|name| name == host) {
+                names.push(host.to_string());
+            }
+            let generated = rcgen::generate_simple_self_signed(names).map_err(|e| {
+                RusterApiError::ConfigError(format!("Failed to generate a self-signed certificate: {}", e))
+            })?;
+            info!("Serving HTTPS with a self-signed certificate; clients will not trust it");
+            Ok(Some(TlsCertificate {
+                cert: generated.cert.pem().into_bytes(),
+                key: generated.key_pair.serialize_pem().into_bytes(),
+            }))
+        }
+        (None, None) => Err(RusterApiError::ConfigError(
+            "server.tls needs cert_path and key_path, or self_signed".to_string()
+        )),
+    }
+}
+
+/// Serializes the body of a response in the configured response format. Text is written as it is.
+pub fn render_body<T: Serialize>(api_response: &ApiResponse<T>, format: ResponseFormat) -> String {
+    match api_response.body.as_ref().map(|body| body.render(format)) {
+        Some(Ok(Value::String(text))) if matches!(api_response.body, Some(ApiResponseBody::Text(_))) => text,
+        Some(Ok(json)) => json.to_string(),
+        Some(Err(_)) => r#"{"error": "Failed to serialize response"}"#.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Runs a request through an adapter off the async runtime, once the rate limits let it through.
+/// Errors are answered in the configured error format, and every request is logged with its
+/// route, status and latency. Responses carry the identifier of the request.
+pub async fn process_request(
+    api_adapter: Arc<dyn ApiAdapterTrait<Value> + Send + Sync>,
+    rate_limiter: &RateLimiter,
+    timeouts: &RequestTimeouts,
+    error_format: ErrorFormat,
+    api_request: ApiRequest,
+    client: Option<IpAddr>,
+) -> ApiResponse<Value> {
+    let started = Instant::now();
+    let method = format!("{:?}", api_request.method);
+    let path = api_request.path.clone();
+    let request_id = api_request.request_id.clone();
+    let route = api_adapter.route_for(&api_request.method, &api_request.path);
+    let pattern = route.as_deref().and_then(|key| key.split_once(':')).map(|(_, pattern)| pattern);
+    let operation = format!("{} /{}", method, pattern.unwrap_or(&path));
+
+    let span = info_span!(
+        "request",
+        otel.name = operation,
+        otel.kind = "server",
+        http.request.method = method,
+        url.path = path,
+        http.route = pattern,
+        http.response.status_code = field::Empty,
+        request_id,
+    );
+    #[cfg(feature = "otel")]
+    crate::logging::telemetry::continue_trace(&span, &api_request.headers);
+
+    let mut api_response = respond(api_adapter, rate_limiter, timeouts, &operation, error_format, api_request, client)
+        .instrument(span.clone())
+        .await;
+    tag_response(&mut api_response, &request_id);
+    span.record("http.response.status_code", api_response.status);
+    log_request(&method, &path, route.as_deref(), &request_id, client, api_response.status, started.elapsed());
+    api_response
+}
+
+async fn respond(
+    api_adapter: Arc<dyn ApiAdapterTrait<Value> + Send + Sync>,
+    rate_limiter: &RateLimiter,
+    timeouts: &RequestTimeouts,
+    operation: &str,
+    error_format: ErrorFormat,
+    api_request: ApiRequest,
+    client: Option<IpAddr>,
+) -> ApiResponse<Value> {
+    let instance = format!("/api/{}", api_request.path);
+    if let Err(err) = rate_limiter.check(&api_request.path, client) {
+        return error_response(&err, error_format, &instance);
+    }
+    let timeout = timeouts.for_path(&api_request.path);
+    // The handler runs on another thread, inside the span of the request
+    let span = Span::current();
+    let result = match tokio::time::timeout(
+        timeout.duration,
+        tokio::task::spawn_blocking(move || span.in_scope(|| api_adapter.handle_request(api_request)))
+    ).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(join_err)) => {
+            error!("Task join error: {:?}", join_err);
+            Err(RusterApiError::ServerError("Internal server error: Request processing failed".to_string()))
+        }
+        Err(_) => {
+            error!("{} timed out after {} seconds", operation, timeout.duration.as_secs());
+            return timeout_response(&timeout, operation, error_format, &instance);
+        }
+    };
+
+    result.unwrap_or_else(|err| {
+        debug!("API Error: {:?}", err);
+        error_response(&err, error_format, &instance)
+    })
+}
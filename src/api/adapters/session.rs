@@ -0,0 +1,199 @@
+use crate::api::adapters::api_adapter::{ApiRequest, AuthContext};
+use crate::config::configuration::Config;
+use crate::config::secrets::resolve_secret;
+use crate::config::specific::auth_config::{AuthType, SameSite, SessionConfig};
+use crate::error::{Result, RusterApiError};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+/// Lifetime of the sessions when `ttl_minutes` is not set
+const DEFAULT_TTL_MINUTES: u32 = 24 * 60;
+/// Length of the AES-GCM nonce in bytes
+const NONCE_LEN: usize = 12;
+
+/// What the session cookie holds, encrypted
+#[derive(Serialize, Deserialize)]
+struct Session {
+    sid: String,
+    sub: String,
+    #[serde(default)]
+    roles: Vec<String>,
+    exp: u64,
+    /// Other claims of the user, like its tenant
+    #[serde(flatten)]
+    claims: Map<String, Value>,
+}
+
+/// Value of a cookie of a request, if it sent it
+pub fn request_cookie<'a>(request: &'a ApiRequest, name: &str) -> Option<&'a str> {
+    request.header("cookie")?
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| key.trim() == name)
+        .map(|(_, value)| value.trim())
+        .filter(|value| !value.is_empty())
+}
+
+/// Sessions of the cookie authentication of `auth.session_config`. The cookie holds the session
+/// encrypted and authenticated with AES-256-GCM under a key derived from the secret, so clients
+/// can neither read nor forge it. Clones share the sessions already closed.
+#[derive(Clone)]
+pub struct Sessions {
+    config: SessionConfig,
+    cipher: Arc<Aes256Gcm>,
+    /// Expiry of the sessions signed out, by session id
+    closed: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl Sessions {
+    /// Fails when `auth.auth_type` is Session without a usable `auth.session_config`
+    pub fn validate(config: &Config) -> Result<()> {
+        let Some(auth) = config.auth.as_ref().filter(|auth| matches!(auth.auth_type, AuthType::Session)) else {
+            return Ok(());
+        };
+        let session = auth.session_config.as_ref().ok_or_else(|| {
+            RusterApiError::ConfigError("auth.auth_type Session requires auth.session_config".to_string())
+        })?;
+        if session.secret.trim().is_empty() {
+            return Err(RusterApiError::ConfigError("auth.session_config.secret cannot be empty".to_string()));
+        }
+        if session.ttl_minutes == Some(0) {
+            return Err(RusterApiError::ConfigError("auth.session_config.ttl_minutes must be positive".to_string()));
+        }
+        if session.same_site == SameSite::None && session.secure == Some(false) {
+            return Err(RusterApiError::ConfigError(
+                "auth.session_config.same_site None requires a secure cookie".to_string()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sessions of the configuration, None unless `auth.auth_type` is Session. Invalid
+    /// configurations are reported and leave session authentication disabled.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if let Err(e) = Self::validate(config) {
+            error!("Session authentication is disabled: {}", e);
+            return None;
+        }
+        let auth = config.auth.as_ref().filter(|auth| matches!(auth.auth_type, AuthType::Session))?;
+        let session = auth.session_config.clone()?;
+        let secret = match resolve_secret(&session.secret) {
+            Ok(secret) => secret,
+            Err(e) => {
+                error!("Session authentication is disabled: {}", e);
+                return None;
+            }
+        };
+        let key = Sha256::digest(secret.as_bytes());
+        let sessions = Self {
+            cipher: Arc::new(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))),
+            config: session,
+            closed: Arc::new(Mutex::new(HashMap::new())),
+        };
+        info!("Opening sessions valid for {} minute(s) in cookie {}", sessions.ttl_minutes(), sessions.cookie_name());
+        Some(sessions)
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    pub fn cookie_name(&self) -> &str {
+        self.config.cookie_name()
+    }
+
+    fn ttl_minutes(&self) -> u32 {
+        self.config.ttl_minutes.unwrap_or(DEFAULT_TTL_MINUTES)
+    }
+
+    /// `Set-Cookie` header of a cookie, expiring after a number of seconds
+    fn set_cookie(&self, value: &str, max_age: u64) -> String {
+        let same_site = match self.config.same_site {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        };
+        let mut cookie = format!(
+            "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite={}",
+            self.cookie_name(), value, self.config.cookie_path.as_deref().unwrap_or("/"), max_age, same_site
+        );
+        if self.config.secure.unwrap_or(true) {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+
+    /// Opens a session for a user, carrying its other claims, returning the `Set-Cookie` header
+    /// carrying it and the body answering the sign in
+    pub fn open(&self, subject: &str, roles: &[String], claims: &Map<String, Value>) -> Result<(String, Value)> {
+        let lifetime = u64::from(self.ttl_minutes()) * 60;
+        let session = Session {
+            sid: uuid::Uuid::new_v4().to_string(),
+            sub: subject.to_string(),
+            roles: roles.to_vec(),
+            exp: now_seconds() + lifetime,
+            claims: claims.clone(),
+        };
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, serde_json::to_vec(&session)?.as_slice())
+            .map_err(|_| RusterApiError::ServerError("Failed to encrypt the session".to_string()))?;
+        let value = URL_SAFE_NO_PAD.encode([nonce.as_slice(), &ciphertext].concat());
+        let body = json!({ "subject": subject, "roles": roles, "expires_in": lifetime });
+        Ok((self.set_cookie(&value, lifetime), body))
+    }
+
+    /// Verifies a session cookie, returning the caller with the roles they signed in with
+    pub fn verify(&self, cookie: &str) -> Result<AuthContext> {
+        let session = self.decode(cookie)?;
+        let closed = self.closed.lock().unwrap_or_else(|e| e.into_inner());
+        if closed.contains_key(&session.sid) {
+            return Err(RusterApiError::AuthError("The session was signed out".to_string()));
+        }
+        Ok(AuthContext {
+            subject: session.sub.clone(),
+            roles: session.roles.clone(),
+            claims: serde_json::to_value(&session)?,
+        })
+    }
+
+    /// Signs a session out, returning the `Set-Cookie` header removing its cookie. Invalid
+    /// cookies are removed as well.
+    pub fn close(&self, cookie: Option<&str>) -> String {
+        if let Some(session) = cookie.and_then(|cookie| self.decode(cookie).ok()) {
+            let mut closed = self.closed.lock().unwrap_or_else(|e| e.into_inner());
+            let now = now_seconds();
+            closed.retain(|_, expiry| *expiry > now);
+            closed.insert(session.sid, session.exp);
+        }
+        self.set_cookie("", 0)
+    }
+
+    /// Decrypts a session cookie, checking it is not expired
+    fn decode(&self, cookie: &str) -> Result<Session> {
+        let invalid = || RusterApiError::AuthError("Invalid session cookie".to_string());
+        let data = URL_SAFE_NO_PAD.decode(cookie).map_err(|_| invalid())?;
+        if data.len() <= NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| invalid())?;
+        let session: Session = serde_json::from_slice(&plaintext).map_err(|_| invalid())?;
+        if session.exp <= now_seconds() {
+            return Err(RusterApiError::AuthError("The session expired".to_string()));
+        }
+        Ok(session)
+    }
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default()
+}
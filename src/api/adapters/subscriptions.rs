@@ -0,0 +1,114 @@
+use crate::api::adapters::change_access::ChangeReader;
+use crate::events::event_bus::{EntityChange, EventBus, RawstEvent};
+use futures::stream::{self, Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Messages clients send over the subscriptions WebSocket
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ClientMessage {
+    /// Subscribes to the changes of an entity whose fields have the values of the filter.
+    /// Subscribing again to an entity replaces its filter.
+    Subscribe {
+        entity: String,
+        #[serde(default)]
+        filter: Map<String, Value>,
+    },
+    Unsubscribe {
+        entity: String,
+    },
+}
+
+/// Entities a WebSocket client subscribed to, keyed by lowercase name, with their filters
+pub struct Subscriptions {
+    entities: HashMap<String, Map<String, Value>>,
+    /// Caller of the WebSocket, whose access limits the entities and changes it gets
+    reader: ChangeReader,
+}
+
+impl Subscriptions {
+    pub fn new(reader: ChangeReader) -> Self {
+        Self { entities: HashMap::new(), reader }
+    }
+
+    /// Applies a message of the client, returning the reply to send back
+    pub fn handle(&mut self, text: &str, serves: impl Fn(&str) -> bool) -> Value {
+        match serde_json::from_str::<ClientMessage>(text) {
+            Ok(ClientMessage::Subscribe { entity, filter }) if serves(&entity) => {
+                if let Err(e) = self.reader.check(&entity) {
+                    return json!({ "type": "error", "message": e.to_string() });
+                }
+                let reply = json!({ "type": "subscribed", "entity": entity, "filter": filter });
+                self.entities.insert(entity.to_lowercase(), filter);
+                reply
+            }
+            Ok(ClientMessage::Subscribe { entity, .. }) => {
+                json!({ "type": "error", "message": format!("Entity not found: {}", entity) })
+            }
+            Ok(ClientMessage::Unsubscribe { entity }) => {
+                self.entities.remove(&entity.to_lowercase());
+                json!({ "type": "unsubscribed", "entity": entity })
+            }
+            Err(e) => json!({ "type": "error", "message": format!("Invalid message: {}", e) }),
+        }
+    }
+
+    /// Message telling the client about a change, if it subscribed to it and may read it. Filters
+    /// are checked against the stored entity, so deletions, which carry none, reach every
+    /// subscriber of the entity allowed to see them.
+    pub fn message(&self, change: &EntityChange) -> Option<Value> {
+        let filter = self.entities.get(&change.entity.to_lowercase())?;
+        let change = &self.reader.filter(&change.entity, change)?;
+        if let Some(data) = &change.data {
+            if !filter.iter().all(|(field, expected)| data.get(field).is_some_and(|actual| matches(expected, actual))) {
+                return None;
+            }
+        }
+        let mut message = serde_json::to_value(change).ok()?;
+        message.as_object_mut()?.insert("type".to_string(), Value::String("change".to_string()));
+        Some(message)
+    }
+}
+
+/// Whether a field has the value of a filter. Strings match the text of numbers and booleans,
+/// as clients may not know how a field is stored.
+fn matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::String(expected), Value::Number(_) | Value::Bool(_)) => {
+            serde_json::from_str::<Value>(expected).is_ok_and(|expected| expected == *actual)
+        }
+        _ => expected == actual,
+    }
+}
+
+/// Messages to send to a WebSocket client: the replies to its messages and the changes of the
+/// entities it subscribed to, with a `lagged` message when it fell behind and missed some.
+/// `serves` tells whether an entity can be subscribed to, and the reader whether the client may
+/// read it. Ends when the client stops sending.
+pub fn subscription_stream<S, F>(events: &EventBus, incoming: S, serves: F, reader: ChangeReader) -> impl Stream<Item = String>
+where
+    S: Stream<Item = String> + Unpin,
+    F: Fn(&str) -> bool,
+{
+    let state = (Subscriptions::new(reader), incoming, events.subscribe(), serves);
+    stream::unfold(state, |(mut subscriptions, mut incoming, mut receiver, serves)| async move {
+        loop {
+            let message = tokio::select! {
+                text = incoming.next() => subscriptions.handle(&text?, &serves),
+                event = receiver.recv() => match event {
+                    Ok(RawstEvent::EntityChanged(change)) => match subscriptions.message(&change) {
+                        Some(message) => message,
+                        None => continue,
+                    },
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => json!({ "type": "lagged", "missed": missed }),
+                    Err(RecvError::Closed) => return None,
+                },
+            };
+            return Some((message.to_string(), (subscriptions, incoming, receiver, serves)));
+        }
+    })
+}
@@ -0,0 +1,215 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, AuthContext, TenantContext};
+use crate::api::adapters::middleware::{Middleware, Next};
+use crate::api::handlers::auth::{AUTH_PATH, DEFAULT_USERS_ENTITY};
+use crate::config::configuration::Config;
+use crate::config::specific::auth_config::AuthType;
+use crate::config::specific::tenancy_config::{TenancyConfig, TenantSource};
+use crate::error::{Result, RusterApiError};
+use serde_json::Value;
+use tracing::info;
+
+/// Longest tenant identifier accepted
+const MAX_TENANT_LENGTH: usize = 128;
+
+/// Whether an endpoint signs users in, which they do before they have a tenant
+fn signs_in(key: &str) -> bool {
+    let path = key.split_once(':').map_or(key, |(_, path)| path);
+    path.strip_prefix("api/").unwrap_or(path).starts_with(&format!("{}/", AUTH_PATH))
+}
+
+/// Layer resolving the tenant of the requests with the tenancy of `tenancy`, and rejecting the
+/// requests to the endpoints that name none. The sign in endpoints are the only ones served
+/// without a tenant. Authenticated callers naming a tenant in a header or subdomain must belong
+/// to it by their tenant claim.
+pub struct TenantResolver {
+    config: TenancyConfig,
+}
+
+impl TenantResolver {
+    /// Fails when the Subdomain source has no `domain`, or when the users signing in with the JWT
+    /// or session authentication cannot carry the tenant claim every authenticated caller needs
+    pub fn validate(config: &Config) -> Result<()> {
+        let Some(tenancy) = &config.tenancy else {
+            return Ok(());
+        };
+        if tenancy.source == TenantSource::Subdomain && tenancy.domain.as_deref().is_none_or(|domain| domain.trim().is_empty()) {
+            return Err(RusterApiError::ConfigError("tenancy.source Subdomain requires tenancy.domain".to_string()));
+        }
+        let Some(auth) = &config.auth else {
+            return Ok(());
+        };
+        let users = match auth.auth_type {
+            AuthType::JWT => auth.jwt_config.as_ref().map(|jwt| &jwt.users),
+            AuthType::Session => auth.session_config.as_ref().map(|session| &session.users),
+            _ => None,
+        };
+        let Some(users) = users else {
+            return Ok(());
+        };
+        if auth.ldap_config.is_some() {
+            return Err(RusterApiError::ConfigError(format!(
+                "tenancy requires a {} claim, which the users of auth.ldap_config do not have", tenancy.claim()
+            )));
+        }
+        // Users get their tenant claim from the tenant field of the users entity
+        let users_entity = users.users_entity.as_deref().unwrap_or(DEFAULT_USERS_ENTITY);
+        let lacks_tenant = config.all_entities().iter()
+            .find(|entity| entity.name.eq_ignore_ascii_case(users_entity))
+            .is_some_and(|entity| !entity.fields.iter().any(|field| field.name == tenancy.field()));
+        if lacks_tenant {
+            return Err(RusterApiError::ConfigError(format!(
+                "tenancy requires the users entity {} to have a {} field, which signed in users carry in their {} claim",
+                users_entity, tenancy.field(), tenancy.claim()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Resolver of the configuration, None without `tenancy`
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let tenancy = config.tenancy.clone()?;
+        info!("Scoping the entities with a {} field to the tenant of each request", tenancy.field());
+        Some(Self { config: tenancy })
+    }
+
+    /// Tenant a request names, if any
+    fn resolve(&self, request: &ApiRequest) -> Result<Option<String>> {
+        let tenant = match self.config.source {
+            TenantSource::Header => request.header(self.config.header_name()).map(str::to_string),
+            TenantSource::Subdomain => {
+                let domain = self.config.domain.as_deref().unwrap_or_default().trim_matches('.').to_lowercase();
+                request.header("host").and_then(|host| {
+                    let host = host.rsplit_once(':').map_or(host, |(name, _)| name).to_lowercase();
+                    let subdomain = host.strip_suffix(&domain)?.strip_suffix('.')?;
+                    (!subdomain.contains('.')).then(|| subdomain.to_string())
+                })
+            }
+            TenantSource::Claim => request.auth.as_ref().and_then(|auth| self.claimed(auth)),
+        };
+        let Some(tenant) = tenant.map(|tenant| tenant.trim().to_string()).filter(|tenant| !tenant.is_empty()) else {
+            return Ok(None);
+        };
+        let valid = tenant.len() <= MAX_TENANT_LENGTH
+            && tenant.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(RusterApiError::BadRequest(format!("Invalid tenant '{}'", tenant)));
+        }
+        Ok(Some(tenant))
+    }
+
+    /// Tenant in the claim of a caller, if any
+    fn claimed(&self, auth: &AuthContext) -> Option<String> {
+        match self.config.claim().split('.').try_fold(&auth.claims, |value, key| value.get(key)) {
+            Some(Value::String(text)) => Some(text.clone()),
+            Some(Value::Number(number)) => Some(number.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Checks that an authenticated caller belongs to the tenant a header or subdomain names, so
+    /// callers cannot reach the rows of other tenants by naming them
+    fn check_claim(&self, auth: &AuthContext, tenant: &str) -> Result<()> {
+        match self.claimed(auth) {
+            Some(claimed) if claimed.trim() == tenant => Ok(()),
+            Some(_) => Err(RusterApiError::Forbidden(format!("The caller does not belong to tenant '{}'", tenant))),
+            None => Err(RusterApiError::Forbidden(format!("The caller has no {} claim", self.config.claim()))),
+        }
+    }
+
+    /// Error answering a request naming no tenant
+    fn missing(&self, request: &ApiRequest) -> RusterApiError {
+        match self.config.source {
+            TenantSource::Header => RusterApiError::BadRequest(format!("Requests must name their tenant in header {}", self.config.header_name())),
+            TenantSource::Subdomain => RusterApiError::BadRequest("Requests must be sent to the subdomain of their tenant".to_string()),
+            TenantSource::Claim if request.auth.is_none() => RusterApiError::AuthError("Authentication required".to_string()),
+            TenantSource::Claim => RusterApiError::Forbidden(format!("The caller has no {} claim", self.config.claim())),
+        }
+    }
+
+    /// Tenant of a request, failing when it names none although it is `required`
+    pub fn tenant_of(&self, request: &ApiRequest, required: bool) -> Result<Option<TenantContext>> {
+        let tenant = self.resolve(request)?;
+        if required && tenant.is_none() {
+            return Err(self.missing(request));
+        }
+        if let (Some(tenant), Some(auth)) = (&tenant, &request.auth) {
+            if self.config.source != TenantSource::Claim {
+                self.check_claim(auth, tenant)?;
+            }
+        }
+        Ok(tenant.map(|id| TenantContext { id, field: self.config.field().to_string() }))
+    }
+}
+
+impl<T> Middleware<T> for TenantResolver {
+    fn handle(&self, mut request: ApiRequest, next: Next<'_, T>) -> Result<ApiResponse<T>> {
+        let served = next.route_for(&request.method, &request.path)
+            .is_some_and(|key| !signs_in(&key));
+        request.tenant = self.tenant_of(&request, served)?;
+        next.run(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::specific::entity_config::HttpMethod;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn resolver(source: TenantSource) -> TenantResolver {
+        TenantResolver {
+            config: TenancyConfig { source, header_name: None, domain: Some("example.com".to_string()), claim: None, field: None },
+        }
+    }
+
+    fn request(headers: &[(&str, &str)], claims: Option<Value>) -> ApiRequest {
+        ApiRequest {
+            method: HttpMethod::GET,
+            path: "invoices".to_string(),
+            params: HashMap::new(),
+            query: HashMap::new(),
+            headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            body: None,
+            request_id: "test".to_string(),
+            auth: claims.map(|claims| AuthContext { subject: "alice".to_string(), roles: Vec::new(), claims }),
+            tenant: None,
+        }
+    }
+
+    fn tenant(resolver: &TenantResolver, request: &ApiRequest) -> Result<Option<String>> {
+        Ok(resolver.tenant_of(request, true)?.map(|tenant| tenant.id))
+    }
+
+    #[test]
+    fn resolves_the_tenant_of_anonymous_callers_from_the_header() {
+        let resolver = resolver(TenantSource::Header);
+        assert_eq!(tenant(&resolver, &request(&[("X-Tenant-Id", "acme")], None)).unwrap(), Some("acme".to_string()));
+        assert!(matches!(tenant(&resolver, &request(&[], None)), Err(RusterApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn admits_authenticated_callers_to_the_tenant_of_their_claim() {
+        let resolver = resolver(TenantSource::Header);
+        let request = request(&[("X-Tenant-Id", "acme")], Some(json!({ "tenant_id": "acme" })));
+        assert_eq!(tenant(&resolver, &request).unwrap(), Some("acme".to_string()));
+    }
+
+    #[test]
+    fn refuses_authenticated_callers_naming_another_tenant() {
+        let resolver = resolver(TenantSource::Header);
+        let other = request(&[("X-Tenant-Id", "globex")], Some(json!({ "tenant_id": "acme" })));
+        assert!(matches!(tenant(&resolver, &other), Err(RusterApiError::Forbidden(_))));
+        let unclaimed = request(&[("X-Tenant-Id", "acme")], Some(json!({})));
+        assert!(matches!(tenant(&resolver, &unclaimed), Err(RusterApiError::Forbidden(_))));
+    }
+
+    #[test]
+    fn checks_the_subdomain_against_the_claim() {
+        let resolver = resolver(TenantSource::Subdomain);
+        let own = request(&[("host", "acme.example.com:8000")], Some(json!({ "tenant_id": "acme" })));
+        assert_eq!(tenant(&resolver, &own).unwrap(), Some("acme".to_string()));
+        let other = request(&[("host", "globex.example.com")], Some(json!({ "tenant_id": "acme" })));
+        assert!(matches!(tenant(&resolver, &other), Err(RusterApiError::Forbidden(_))));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::config::configuration::Config;
+use crate::config::specific::entity_config::{DataType, Entity, Field};
+use crate::config::specific::server_config::DEFAULT_UPLOADS_DIR;
+use crate::error::{Result, RusterApiError};
+use multer::Multipart;
+use rocket::http::ContentType;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Path the uploaded files are downloaded from, followed by the entity and the file name
+pub const UPLOADS_PATH: &str = "/uploads";
+/// Maximum length of the extension kept from the name of an uploaded file
+const MAX_EXTENSION_LENGTH: usize = 10;
+
+/// Body of a `multipart/form-data` request converted to JSON, with the files it stored
+pub struct FormBody {
+    pub body: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Files uploaded to the Binary fields of the entities with `multipart/form-data` requests.
+/// They are stored in the uploads directory, one folder per entity, and the fields hold the
+/// URL they are downloaded from.
+#[derive(Clone)]
+pub struct UploadStore {
+    directory: PathBuf,
+    /// Entities with Binary fields, the only ones accepting forms, by the path they are served at
+    entities: Arc<Vec<(String, Entity)>>,
+}
+
+/// Whether a segment of an upload URL is a plain file or folder name
+fn safe_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && !segment.starts_with('.')
+        && segment.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Folder of the files of an entity
+fn entity_folder(entity: &Entity) -> String {
+    entity.name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// Value of a text part for the field it is sent to. Text that does not parse as the type of
+/// the field is kept as a string, for the validation to report.
+fn form_value(field: Option<&Field>, text: String) -> Value {
+    let parsed = match field.map(|field| &field.data_type) {
+        Some(DataType::Integer) => text.parse::<i64>().ok().map(Value::from),
+        Some(DataType::Float) => text.parse::<f64>().ok().map(Value::from),
+        Some(DataType::Boolean) => match text.as_str() {
+            "true" | "on" | "1" => Some(Value::Bool(true)),
+            "false" | "off" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Some(DataType::JSON) => serde_json::from_str(&text).ok(),
+        _ => None,
+    };
+    parsed.unwrap_or(Value::String(text))
+}
+
+fn invalid_form(e: multer::Error) -> RusterApiError {
+    RusterApiError::BadRequest(format!("Invalid multipart/form-data body: {}", e))
+}
+
+impl UploadStore {
+    pub fn new(config: &Config) -> Self {
+        let directory = config.server.uploads_dir.as_deref().unwrap_or(DEFAULT_UPLOADS_DIR);
+        let entities = config.served_entities().into_iter()
+            .filter(|(_, entity)| entity.fields.iter().any(|field| matches!(field.data_type, DataType::Binary)))
+            .collect();
+        Self { directory: PathBuf::from(directory), entities: Arc::new(entities) }
+    }
+
+    /// Whether the body of a request is a `multipart/form-data` form
+    pub fn is_form(headers: &HashMap<String, String>) -> bool {
+        headers.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case("content-type") && value.trim_start().to_ascii_lowercase().starts_with("multipart/form-data")
+        })
+    }
+
+    /// Converts a form sent to the endpoints of an entity into the JSON body they expect. Files of
+    /// Binary fields are stored and replaced by their download URL, and text parts are parsed as the
+    /// type of their field. Stored files are removed when the form turns out to be invalid.
+    pub async fn form_body(&self, path: &str, headers: &HashMap<String, String>, body: Vec<u8>) -> Result<FormBody> {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let entity = self.entities.iter()
+            .find(|(entity_path, _)| {
+                let entity_segments: Vec<&str> = entity_path.split('/').collect();
+                entity_segments.len() <= segments.len()
+                    && entity_segments.iter().zip(&segments).all(|(a, b)| a.eq_ignore_ascii_case(b))
+            })
+            .map(|(_, entity)| entity)
+            .ok_or_else(|| RusterApiError::BadRequest(
+                "multipart/form-data bodies are only accepted by entities with Binary fields".to_string()
+            ))?;
+        let content_type = headers.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.as_str())
+            .unwrap_or_default();
+        let boundary = multer::parse_boundary(content_type).map_err(invalid_form)?;
+
+        let mut multipart = Multipart::with_reader(Cursor::new(body), boundary);
+        let mut fields = Map::new();
+        let mut files = Vec::new();
+        let result = self.read_form(&mut multipart, entity, &mut fields, &mut files).await;
+        if let Err(err) = result {
+            self.discard(&files).await;
+            return Err(err);
+        }
+        Ok(FormBody { body: Value::Object(fields).to_string(), files })
+    }
+
+    async fn read_form(&self, multipart: &mut Multipart<'_>, entity: &Entity, fields: &mut Map<String, Value>, files: &mut Vec<PathBuf>)
+    -> Result<()> {
+        while let Some(part) = multipart.next_field().await.map_err(invalid_form)? {
+            let name = part.name().unwrap_or_default().to_string();
+            let field = entity.fields.iter().find(|field| field.name == name);
+            let value = match part.file_name().map(str::to_string) {
+                Some(file_name) if field.is_some_and(|field| matches!(field.data_type, DataType::Binary)) => {
+                    let bytes = part.bytes().await.map_err(invalid_form)?;
+                    Value::String(self.store(entity, &file_name, &bytes, files).await?)
+                }
+                Some(_) => return Err(RusterApiError::BadRequest(format!("Field '{}' does not accept files", name))),
+                None => form_value(field, part.text().await.map_err(invalid_form)?),
+            };
+            fields.insert(name, value);
+        }
+        Ok(())
+    }
+
+    /// Stores a file of an entity under a new name keeping its extension, returning its download URL
+    async fn store(&self, entity: &Entity, file_name: &str, bytes: &[u8], files: &mut Vec<PathBuf>) -> Result<String> {
+        let extension = Path::new(file_name).extension()
+            .and_then(|extension| extension.to_str())
+            .filter(|extension| extension.len() <= MAX_EXTENSION_LENGTH && extension.chars().all(|c| c.is_ascii_alphanumeric()))
+            .map(|extension| format!(".{}", extension.to_ascii_lowercase()))
+            .unwrap_or_default();
+        let folder = entity_folder(entity);
+        let stored_name = format!("{}{}", uuid::Uuid::new_v4(), extension);
+
+        let directory = self.directory.join(&folder);
+        tokio::fs::create_dir_all(&directory).await?;
+        let path = directory.join(&stored_name);
+        tokio::fs::write(&path, bytes).await?;
+        files.push(path);
+        Ok(format!("{}/{}/{}", UPLOADS_PATH, folder, stored_name))
+    }
+
+    /// Removes the files of a request the endpoints rejected
+    pub async fn discard(&self, files: &[PathBuf]) {
+        for file in files {
+            if let Err(e) = tokio::fs::remove_file(file).await {
+                warn!("Failed to remove the upload {}: {}", file.display(), e);
+            }
+        }
+    }
+
+    /// Content and media type of an uploaded file, from the entity folder and file name of its URL
+    pub async fn download(&self, folder: &str, file: &str) -> Result<(Vec<u8>, String)> {
+        let not_found = || RusterApiError::NotFound(format!("File not found: {}/{}", folder, file));
+        if !safe_segment(folder) || !safe_segment(file) {
+            return Err(not_found());
+        }
+        let bytes = tokio::fs::read(self.directory.join(folder).join(file)).await.map_err(|_| not_found())?;
+        let content_type = Path::new(file).extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(ContentType::from_extension)
+            .unwrap_or(ContentType::Binary);
+        Ok((bytes, content_type.to_string()))
+    }
+}
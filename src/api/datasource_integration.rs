@@ -1,10 +1,15 @@
 use std::collections::HashMap;
 use serde::Serialize;
-use crate::config::configuration::Config;
 use crate::data::datasource::base::DataSource;
+#[cfg(feature = "native")]
+use crate::config::configuration::Config;
+#[cfg(feature = "native")]
 use crate::data::datasource_factory::DataSourceFactory;
+#[cfg(feature = "native")]
 use crate::api::adapters::api_adapter::EntityApi;
+#[cfg(feature = "native")]
 use crate::api::handlers::manager::ApiHandlerManager;
+#[cfg(feature = "native")]
 use crate::api::common::api_entity::ApiEntity;
 
 /// This module integrates datasources with the API system
@@ -17,6 +22,7 @@ impl DatasourceIntegration {
     /// 1. Creating datasources for all entities based on configuration
     /// 2. Mapping entities to their datasources
     /// 3. Preparing entity APIs with proper endpoints
+    #[cfg(feature = "native")]
     pub fn setup_entity_datasources<T: ApiEntity>(
         config: &Config
     ) -> HashMap<String, EntityApi<T>> {
@@ -54,8 +60,8 @@ impl DatasourceIntegration {
     pub fn get_entity_datasource<'a, T: 'static + Serialize + Send + Sync>(
         entity_name: &str,
         datasources: &'a HashMap<String, Box<dyn DataSource<T>>>
-    ) -> Option<&'a Box<dyn DataSource<T>>> {
-        datasources.get(entity_name)
+    ) -> Option<&'a dyn DataSource<T>> {
+        datasources.get(entity_name).map(|ds| ds.as_ref())
     }
 }
 
@@ -0,0 +1,276 @@
+use crate::config::configuration::Config;
+use crate::config::specific::auth_config::AuthType;
+use crate::config::specific::entity_config::{DataType, Entity, Field};
+use crate::config::specific::server_config::ResponseFormat;
+use serde_json::{json, Value};
+
+/// Path the admin panel is served at
+pub const ADMIN_PANEL_PATH: &str = "/admin";
+
+/// HTML admin panel generated from the entities, listing, creating, editing and deleting them
+/// through the endpoints of the API. The panel sends the credentials of the configured
+/// authentication with its requests, so the API decides what it may do.
+#[derive(Clone)]
+pub struct AdminPanel {
+    pub page: String,
+}
+
+impl AdminPanel {
+    /// Admin panel of the entities of a configuration, when `server.admin_panel` is set
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.server.admin_panel.then(|| Self { page: admin_page(config) })
+    }
+}
+
+/// Kind of form input of a field
+fn input_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Integer => "integer",
+        DataType::Float => "number",
+        DataType::Boolean => "checkbox",
+        DataType::Date => "date",
+        DataType::DateTime => "datetime",
+        DataType::JSON => "json",
+        DataType::String | DataType::Binary => "text",
+    }
+}
+
+/// Description of a field for the panel: how it is entered and when it can be written
+fn field_metadata(field: &Field) -> Value {
+    let writable = !field.generated && !field.read_only && field.is_stored() && !matches!(field.data_type, DataType::Binary);
+    json!({
+        "name": field.name,
+        "input": input_type(&field.data_type),
+        "required": field.required && field.default_value.is_none(),
+        "description": field.description,
+        "create": writable && field.insertable,
+        "update": writable && field.updatable && !field.immutable,
+    })
+}
+
+/// Description of an entity for the panel: its fields, key and generated endpoints
+fn entity_metadata(entity: &Entity) -> Value {
+    let endpoints = &entity.endpoints;
+    json!({
+        "name": entity.name,
+        "key": entity.key_field().map(|field| field.name.as_str()),
+        "fields": entity.fields.iter().map(field_metadata).collect::<Vec<_>>(),
+        "list": endpoints.generate_list,
+        "create": endpoints.generate_create,
+        "read": endpoints.generate_read,
+        "update": endpoints.generate_update,
+        "delete": endpoints.generate_delete,
+        "page_param": entity.pagination.as_ref().map(|pagination| pagination.page_param_name.as_str()),
+    })
+}
+
+/// How the panel sends the credentials of the configured authentication
+fn auth_metadata(config: &Config) -> Value {
+    let Some(auth) = &config.auth else {
+        return Value::Null;
+    };
+    match auth.auth_type {
+        AuthType::JWT | AuthType::OAuth => json!({ "header": "Authorization", "prefix": "Bearer ", "hint": "Bearer token" }),
+        // The browser sends the session cookie of the sign in by itself, and the other types are
+        // not implemented by the server
+        AuthType::Session | AuthType::ApiKey | AuthType::Basic | AuthType::None => Value::Null,
+    }
+}
+
+/// Page of the admin panel, with the description of the entities embedded for its script
+pub fn admin_page(config: &Config) -> String {
+    let format = match config.server.response_format {
+        ResponseFormat::Bare => "bare",
+        ResponseFormat::Envelope => "envelope",
+        ResponseFormat::Tagged => "tagged",
+    };
+    let metadata = json!({
+        "entities": config.all_entities().iter().map(entity_metadata).collect::<Vec<_>>(),
+        "auth": auth_metadata(config),
+        "format": format,
+    });
+    // Entity descriptions cannot close the script element holding them
+    let metadata = metadata.to_string().replace("</", "<\\/");
+    format!("{}{}{}", PAGE_HEAD, metadata, PAGE_TAIL)
+}
+
+const PAGE_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Rawst admin</title>
+<style>
+body { font-family: system-ui, sans-serif; margin: 0; display: flex; min-height: 100vh; color: #222; }
+nav { width: 14rem; background: #f4f4f6; padding: 1rem; }
+nav a { display: block; padding: .3rem .5rem; color: inherit; text-decoration: none; border-radius: 4px; }
+nav a.active { background: #dde3f0; }
+main { flex: 1; padding: 1rem 2rem; overflow-x: auto; }
+table { border-collapse: collapse; margin: 1rem 0; }
+th, td { border: 1px solid #ddd; padding: .3rem .6rem; text-align: left; vertical-align: top; }
+form label { display: block; margin: .6rem 0 .2rem; font-weight: 600; }
+form input, form textarea { width: 24rem; max-width: 100%; }
+form input[type=checkbox] { width: auto; }
+button { margin-right: .4rem; }
+#error { color: #b00020; white-space: pre-wrap; }
+</style>
+</head>
+<body>
+<nav>
+<h3>Entities</h3>
+<div id="entities"></div>
+<div id="auth"></div>
+</nav>
+<main>
+<p id="error"></p>
+<div id="content"><p>Pick an entity.</p></div>
+</main>
+<script id="metadata" type="application/json">"#;
+
+const PAGE_TAIL: &str = r##"</script>
+<script>
+const META = JSON.parse(document.getElementById("metadata").textContent);
+const CREDENTIALS = "rawst-admin-credentials";
+const $ = (tag, attributes = {}, ...children) => {
+  const element = document.createElement(tag);
+  Object.entries(attributes).forEach(([name, value]) => name.startsWith("on") ? element.addEventListener(name.slice(2), value) : element.setAttribute(name, value));
+  children.forEach(child => element.append(child));
+  return element;
+};
+const content = document.getElementById("content");
+const showError = message => document.getElementById("error").textContent = message || "";
+
+function headers() {
+  const result = { "Content-Type": "application/json" };
+  const credentials = sessionStorage.getItem(CREDENTIALS);
+  if (META.auth && credentials) {
+    result[META.auth.header] = META.auth.prefix + credentials;
+  }
+  return result;
+}
+
+async function call(method, path, body) {
+  const response = await fetch("/api/" + path, { method, headers: headers(), body: body === undefined ? undefined : JSON.stringify(body) });
+  const text = await response.text();
+  if (!response.ok) throw new Error(response.status + " " + response.statusText + (text ? "\n" + text : ""));
+  return text ? JSON.parse(text) : null;
+}
+
+// Items and pagination fields of a response in the configured format
+function unwrap(body) {
+  if (META.format === "tagged" && body && typeof body === "object") {
+    const tag = Object.keys(body)[0];
+    if (["Single", "List", "Page", "Cursor"].includes(tag)) body = body[tag];
+  }
+  if (META.format === "envelope" && body && "data" in body) return { data: body.data, meta: body.meta || {} };
+  if (body && Array.isArray(body.items)) { const { items, ...meta } = body; return { data: items, meta }; }
+  return { data: body, meta: {} };
+}
+
+const itemPath = (entity, item) => entity.name + "/" + encodeURIComponent(item[entity.key]);
+const display = value => value === null || value === undefined ? "" : typeof value === "object" ? JSON.stringify(value) : String(value);
+
+async function list(entity, page = 1) {
+  showError();
+  document.querySelectorAll("nav a").forEach(link => link.classList.toggle("active", link.textContent === entity.name));
+  content.replaceChildren($("h2", {}, entity.name));
+  if (entity.create) content.append($("button", { onclick: () => edit(entity) }, "New " + entity.name));
+  if (!entity.list) return content.append($("p", {}, "The list endpoint of this entity is not generated."));
+  try {
+    const query = entity.page_param ? "?" + entity.page_param + "=" + page : "";
+    const { data, meta } = unwrap(await call("GET", entity.name + query));
+    const rows = (data || []).map(item => $("tr", {},
+      ...entity.fields.map(field => $("td", {}, display(item[field.name]))),
+      $("td", {},
+        ...(entity.update && entity.key ? [$("button", { onclick: () => open(entity, item) }, "Edit")] : []),
+        ...(entity.delete && entity.key ? [$("button", { onclick: () => remove(entity, item, page) }, "Delete")] : []))));
+    content.append($("table", {}, $("tr", {}, ...entity.fields.map(field => $("th", {}, field.name)), $("th")), ...rows));
+    if (meta.total_pages > 1) {
+      if (page > 1) content.append($("button", { onclick: () => list(entity, page - 1) }, "Previous"));
+      content.append("Page " + page + " of " + meta.total_pages + " ");
+      if (page < meta.total_pages) content.append($("button", { onclick: () => list(entity, page + 1) }, "Next"));
+    }
+  } catch (error) {
+    showError(error.message);
+  }
+}
+
+// Fresh copy of an entity to edit, when the read endpoint is generated
+async function open(entity, item) {
+  try {
+    edit(entity, entity.read ? unwrap(await call("GET", itemPath(entity, item))).data : item);
+  } catch (error) {
+    showError(error.message);
+  }
+}
+
+function edit(entity, item) {
+  showError();
+  const creating = item === undefined;
+  const fields = entity.fields.filter(field => creating ? field.create : field.update);
+  const form = $("form", { onsubmit: event => { event.preventDefault(); save(entity, item, fields, form); } });
+  fields.forEach(field => {
+    const value = item ? item[field.name] : undefined;
+    const attributes = { name: field.name };
+    if (field.required && creating && field.input !== "checkbox") attributes.required = "";
+    if (field.description) attributes.title = field.description;
+    let input;
+    if (field.input === "json") {
+      input = $("textarea", attributes, value === undefined ? "" : JSON.stringify(value, null, 2));
+    } else if (field.input === "checkbox") {
+      input = $("input", { ...attributes, type: "checkbox" });
+      input.checked = Boolean(value);
+    } else {
+      const type = { integer: "number", number: "number", date: "date", datetime: "datetime-local" }[field.input] || "text";
+      input = $("input", { ...attributes, type, step: field.input === "number" ? "any" : "1" });
+      if (value !== undefined && value !== null) input.value = field.input === "datetime" ? String(value).slice(0, 19) : value;
+    }
+    form.append($("label", {}, field.name + (field.required ? " *" : "")), input);
+  });
+  form.append($("p", {}, $("button", { type: "submit" }, "Save"), $("button", { type: "button", onclick: () => list(entity) }, "Cancel")));
+  content.replaceChildren($("h2", {}, (creating ? "New " : "Edit ") + entity.name), form);
+}
+
+// Updates send the whole entity, with the fields of the form replaced
+async function save(entity, item, fields, form) {
+  const body = item === undefined ? {} : { ...item };
+  try {
+    fields.forEach(field => {
+      const input = form.elements[field.name];
+      if (field.input === "checkbox") return body[field.name] = input.checked;
+      if (input.value === "") return item === undefined || (body[field.name] = null);
+      if (field.input === "integer") body[field.name] = parseInt(input.value, 10);
+      else if (field.input === "number") body[field.name] = parseFloat(input.value);
+      else if (field.input === "json") body[field.name] = JSON.parse(input.value);
+      else body[field.name] = input.value;
+    });
+    if (item === undefined) await call("POST", entity.name, body);
+    else await call("PUT", itemPath(entity, item), body);
+    list(entity);
+  } catch (error) {
+    showError(error.message);
+  }
+}
+
+async function remove(entity, item, page) {
+  if (!confirm("Delete " + entity.name + " " + item[entity.key] + "?")) return;
+  try {
+    await call("DELETE", itemPath(entity, item));
+    list(entity, page);
+  } catch (error) {
+    showError(error.message);
+  }
+}
+
+META.entities.forEach(entity => document.getElementById("entities").append($("a", { href: "#" + entity.name, onclick: () => list(entity) }, entity.name)));
+if (META.auth) {
+  const input = $("input", { type: "password", placeholder: META.auth.hint });
+  input.value = sessionStorage.getItem(CREDENTIALS) || "";
+  input.addEventListener("change", () => sessionStorage.setItem(CREDENTIALS, input.value));
+  document.getElementById("auth").append($("h3", {}, "Credentials"), input);
+}
+const initial = META.entities.find(entity => "#" + entity.name === location.hash);
+if (initial) list(initial);
+</script>
+</body>
+</html>
+"##;
@@ -0,0 +1,369 @@
+use crate::api::handlers::common::fieldset::FIELDS_PARAM;
+use crate::api::handlers::common::filter_expression::FILTER_PARAM;
+use crate::config::configuration::Config;
+use crate::config::specific::auth_config::AuthType;
+use crate::config::specific::documentation_config::{DocsUi, DocumentationConfig};
+use crate::config::specific::entity_config::{DataType, Entity, HttpMethod};
+use crate::error::{Result, RusterApiError};
+use serde_json::{json, Map, Value};
+
+/// Path the documentation is served at when `documentation.path` is not set
+pub const DEFAULT_DOCS_PATH: &str = "/docs";
+/// Path of the OpenAPI document, under the documentation path
+pub const OPENAPI_FILE: &str = "openapi.json";
+/// Security scheme of the entities requiring authentication
+const SECURITY_SCHEME: &str = "auth";
+
+/// Documentation of a served API: its OpenAPI document and the page browsing it
+#[derive(Clone)]
+pub struct ApiDocs {
+    /// Path the page is served at, like `/docs`, with the document at `/docs/openapi.json`
+    pub path: String,
+    pub spec: Value,
+    pub page: String,
+    /// JSON Schema of the configuration files, at `/docs/config.schema.json`
+    pub config_schema: Value,
+}
+
+impl ApiDocs {
+    /// Documentation of the API of a configuration, when `documentation.generate_openapi` is set
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let documentation = &config.documentation;
+        if !documentation.generate_openapi {
+            return Ok(None);
+        }
+
+        let path = docs_path(documentation)?;
+        let spec_url = format!("{}/{}", path.trim_end_matches('/'), OPENAPI_FILE);
+        Ok(Some(Self {
+            page: docs_page(documentation, &spec_url),
+            spec: openapi_spec(config),
+            path,
+            config_schema: Config::json_schema(),
+        }))
+    }
+
+    /// OpenAPI document naming the public origin the client reached the API at, like
+    /// `https://api.example.com`, as its server, so the viewers send their requests there
+    pub fn spec_at(&self, origin: Option<&str>) -> Value {
+        let mut spec = self.spec.clone();
+        if let Some(origin) = origin {
+            spec["servers"] = json!([{ "url": origin }]);
+        }
+        spec
+    }
+}
+
+/// Configured documentation path, without trailing slash. Paths under `/api` are rejected, as they
+/// would be taken for entity routes.
+fn docs_path(documentation: &DocumentationConfig) -> Result<String> {
+    let path = documentation.path.as_deref().unwrap_or(DEFAULT_DOCS_PATH).trim();
+    let valid = path.starts_with('/')
+        && !path.contains("//")
+        && path.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.'));
+    let under_api = path == "/api" || path.starts_with("/api/");
+    if !valid || under_api {
+        return Err(RusterApiError::ConfigError(format!(
+            "Invalid documentation.path {}: expected an absolute path like /docs outside of /api", path
+        )));
+    }
+    Ok(match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        path => path.to_string(),
+    })
+}
+
+/// Schema of a field of an entity
+fn field_schema(data_type: &DataType) -> Value {
+    match data_type {
+        DataType::Integer => json!({ "type": "integer", "format": "int64" }),
+        DataType::Float => json!({ "type": "number", "format": "double" }),
+        DataType::Boolean => json!({ "type": "boolean" }),
+        DataType::String => json!({ "type": "string" }),
+        DataType::Date => json!({ "type": "string", "format": "date" }),
+        DataType::DateTime => json!({ "type": "string", "format": "date-time" }),
+        DataType::Binary => json!({ "type": "string", "format": "byte" }),
+        DataType::JSON => json!({}),
+    }
+}
+
+/// Schema of an entity. Fields clients cannot write are read-only, and password fields write-only.
+fn entity_schema(entity: &Entity) -> Value {
+    let mut properties = Map::new();
+    for field in &entity.fields {
+        let mut schema = field_schema(&field.data_type);
+        if let Some(description) = &field.description {
+            schema["description"] = json!(description);
+        }
+        if field.generated || field.read_only || !field.is_stored() {
+            schema["readOnly"] = json!(true);
+        }
+        if field.password {
+            schema["writeOnly"] = json!(true);
+            schema["format"] = json!("password");
+        }
+        properties.insert(field.name.clone(), schema);
+    }
+    let required: Vec<&str> = entity.fields.iter()
+        .filter(|field| field.required && !field.generated && field.is_stored())
+        .map(|field| field.name.as_str())
+        .collect();
+
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+fn schema_ref(schema: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", schema) })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// Content of the bodies creating or updating an entity. Entities with Binary fields also accept
+/// forms uploading their files.
+fn request_content(entity: &Entity, schema: &str) -> Value {
+    let mut content = json_content(schema_ref(schema));
+    if entity.fields.iter().any(|field| matches!(field.data_type, DataType::Binary)) {
+        let mut form = entity_schema(entity);
+        for field in entity.fields.iter().filter(|field| matches!(field.data_type, DataType::Binary)) {
+            form["properties"][&field.name]["format"] = json!("binary");
+        }
+        content["multipart/form-data"] = json!({ "schema": form });
+    }
+    content
+}
+
+fn error_response(description: &str) -> Value {
+    json!({ "description": description, "content": json_content(json!({ "$ref": "#/components/schemas/Error" })) })
+}
+
+fn query_parameter(name: &str, description: &str, schema: Value) -> Value {
+    json!({ "name": name, "in": "query", "required": false, "description": description, "schema": schema })
+}
+
+/// Operation of an endpoint of an entity, requiring the configured authentication when the entity
+/// does, and the roles of its authorization when active
+fn operation(entity: &Entity, summary: String, secured: bool, mut details: Value) -> Value {
+    details["tags"] = json!([entity.name]);
+    details["summary"] = json!(summary);
+    if secured && entity.authentication {
+        details["security"] = json!([{ SECURITY_SCHEME: [] }]);
+        details["responses"]["401"] = error_response("Authentication required");
+    }
+    let field_roles = entity.fields.iter().any(|field| !field.read_roles.is_empty() || !field.write_roles.is_empty());
+    if entity.authorization.active || field_roles {
+        details["responses"]["403"] = error_response("Not allowed for the roles of the caller");
+    }
+    details
+}
+
+/// OpenAPI path of a route, with `{param}` for its `:param` segments
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(param) => format!("{{{}}}", param),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn method_name(method: &HttpMethod) -> &'static str {
+    match method {
+        HttpMethod::GET => "get",
+        HttpMethod::POST => "post",
+        HttpMethod::PUT => "put",
+        HttpMethod::DELETE => "delete",
+        HttpMethod::PATCH => "patch",
+    }
+}
+
+/// Security scheme of the configured authentication, if any
+fn security_scheme(config: &Config) -> Option<Value> {
+    let auth = config.auth.as_ref()?;
+    match auth.auth_type {
+        AuthType::JWT | AuthType::OAuth => Some(json!({ "type": "http", "scheme": "bearer" })),
+        AuthType::Session => {
+            let cookie = auth.session_config.as_ref().map(|session| session.cookie_name()).unwrap_or_default();
+            Some(json!({ "type": "apiKey", "in": "cookie", "name": cookie }))
+        }
+        // Not implemented by the server, so not advertised
+        AuthType::ApiKey | AuthType::Basic | AuthType::None => None,
+    }
+}
+
+/// Documents the endpoints of an entity, served under the given version of the API if any
+fn document_entity(entity: &Entity, version: Option<&str>, secured: bool, paths: &mut Map<String, Value>, schemas: &mut Map<String, Value>) {
+    let endpoints = &entity.endpoints;
+    let name = &entity.name;
+    // Versions are served under their name, with their own schemas and operations
+    let (base, schema, operation_prefix) = match version {
+        Some(version) => (format!("/api/{}", version), format!("{}_{}", version, name), format!("{}_", version)),
+        None => ("/api".to_string(), name.clone(), String::new()),
+    };
+    schemas.insert(schema.clone(), entity_schema(entity));
+
+    let mut collection = Map::new();
+    if endpoints.generate_list {
+        let mut parameters = vec![
+            query_parameter(FILTER_PARAM, "Filter expression, like `age > 30 and status == 'active'`", json!({ "type": "string" })),
+            query_parameter("sort", "Fields to sort by, prefixed with `-` for descending order", json!({ "type": "string" })),
+            query_parameter(FIELDS_PARAM, "Fields to return, separated by commas", json!({ "type": "string" })),
+        ];
+        if let Some(pagination) = &entity.pagination {
+            parameters.push(query_parameter(&pagination.page_param_name, "Page number", json!({ "type": "integer", "minimum": 1 })));
+            parameters.push(query_parameter(&pagination.size_param_name, "Page size", json!({
+                "type": "integer", "minimum": 1, "maximum": pagination.max_page_size, "default": pagination.default_page_size,
+            })));
+        }
+        // Pages hold their items along with the pagination fields
+        let items = json!({ "type": "array", "items": schema_ref(&schema) });
+        let list = match entity.pagination {
+            Some(_) => json!({ "type": "object", "properties": { "items": items } }),
+            None => items,
+        };
+        collection.insert("get".to_string(), operation(entity, format!("List {}", name), secured, json!({
+            "operationId": format!("{}list_{}", operation_prefix, name),
+            "parameters": parameters,
+            "responses": { "200": { "description": "Entities", "content": json_content(list) } },
+        })));
+    }
+    if endpoints.generate_create {
+        collection.insert("post".to_string(), operation(entity, format!("Create a {}", name), secured, json!({
+            "operationId": format!("{}create_{}", operation_prefix, name),
+            "requestBody": { "required": true, "content": request_content(entity, &schema) },
+            "responses": {
+                "201": { "description": "Created entity", "content": json_content(schema_ref(&schema)) },
+                "400": error_response("Invalid entity"),
+            },
+        })));
+    }
+    if !collection.is_empty() {
+        paths.insert(format!("{}/{}", base, name), Value::Object(collection));
+    }
+
+    let id_parameter = json!([{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }]);
+    let mut item = Map::new();
+    if endpoints.generate_read {
+        item.insert("get".to_string(), operation(entity, format!("Read a {}", name), secured, json!({
+            "operationId": format!("{}read_{}", operation_prefix, name),
+            "parameters": id_parameter,
+            "responses": {
+                "200": { "description": "Entity", "content": json_content(schema_ref(&schema)) },
+                "404": error_response("Entity not found"),
+            },
+        })));
+    }
+    if endpoints.generate_update {
+        item.insert("put".to_string(), operation(entity, format!("Update a {}", name), secured, json!({
+            "operationId": format!("{}update_{}", operation_prefix, name),
+            "parameters": id_parameter,
+            "requestBody": { "required": true, "content": request_content(entity, &schema) },
+            "responses": {
+                "200": { "description": "Updated entity", "content": json_content(schema_ref(&schema)) },
+                "400": error_response("Invalid entity"),
+                "404": error_response("Entity not found"),
+            },
+        })));
+    }
+    if endpoints.generate_delete {
+        item.insert("delete".to_string(), operation(entity, format!("Delete a {}", name), secured, json!({
+            "operationId": format!("{}delete_{}", operation_prefix, name),
+            "parameters": id_parameter,
+            "responses": {
+                "204": { "description": "Entity deleted" },
+                "404": error_response("Entity not found"),
+            },
+        })));
+    }
+    if !item.is_empty() {
+        paths.insert(format!("{}/{}/{{id}}", base, name), Value::Object(item));
+    }
+
+    for route in &endpoints.custom_routes {
+        let route_path = format!("{}/{}/{}", base, name, openapi_path(route.path.trim_matches('/')));
+        let parameters: Vec<Value> = route.path.split('/')
+            .filter_map(|segment| segment.strip_prefix(':'))
+            .map(|param| json!({ "name": param, "in": "path", "required": true, "schema": { "type": "string" } }))
+            .collect();
+        let custom = operation(entity, format!("{} {}", route.handler, route.path), secured, json!({
+            "parameters": parameters,
+            "responses": { "200": { "description": "Response of the route", "content": json_content(json!({})) } },
+        }));
+        if let Value::Object(operations) = paths.entry(route_path).or_insert_with(|| json!({})) {
+            operations.insert(method_name(&route.method).to_string(), custom);
+        }
+    }
+}
+
+/// OpenAPI 3 document of the endpoints generated for the entities of a configuration
+pub fn openapi_spec(config: &Config) -> Value {
+    let documentation = &config.documentation;
+    let scheme = security_scheme(config);
+    let secured = scheme.is_some();
+    let mut paths = Map::new();
+    let mut schemas = Map::new();
+    schemas.insert("Error".to_string(), json!({ "type": "object", "properties": { "error": { "type": "string" } } }));
+
+    for entity in config.all_entities() {
+        document_entity(&entity, None, secured, &mut paths, &mut schemas);
+    }
+    for version in &config.versions {
+        for entity in config.version_config(version).all_entities() {
+            document_entity(&entity, Some(&version.name), secured, &mut paths, &mut schemas);
+        }
+    }
+
+    let mut info = json!({
+        "title": if documentation.title.is_empty() { "Rawst API" } else { documentation.title.as_str() },
+        "version": if documentation.version.is_empty() { config.api_version.as_str() } else { documentation.version.as_str() },
+    });
+    if let Some(description) = &documentation.description {
+        info["description"] = json!(description);
+    }
+    if let Some(email) = &documentation.contact_email {
+        info["contact"] = json!({ "email": email });
+    }
+    if let Some(license) = &documentation.license {
+        info["license"] = json!({ "name": license });
+    }
+
+    let mut components = json!({ "schemas": schemas });
+    if let Some(scheme) = scheme {
+        components["securitySchemes"] = json!({ SECURITY_SCHEME: scheme });
+    }
+    json!({ "openapi": "3.0.3", "info": info, "paths": paths, "components": components })
+}
+
+/// HTML page browsing the OpenAPI document with the configured viewer, loaded from a CDN
+pub fn docs_page(documentation: &DocumentationConfig, spec_url: &str) -> String {
+    let title = if documentation.title.is_empty() { "Rawst API" } else { documentation.title.as_str() };
+    let title = title.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let body = match documentation.ui {
+        DocsUi::SwaggerUi => format!(
+            r##"<link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+<div id="swagger-ui"></div>
+<script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+<script>window.ui = SwaggerUIBundle({{ url: "{}", dom_id: "#swagger-ui" }});</script>"##,
+            spec_url
+        ),
+        DocsUi::Redoc => format!(
+            r#"</head>
+<body>
+<redoc spec-url="{}"></redoc>
+<script src="https://cdn.redoc.ly/redoc/latest/bundles/redoc.standalone.js"></script>"#,
+            spec_url
+        ),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n{}\n</body>\n</html>\n",
+        title, body
+    )
+}
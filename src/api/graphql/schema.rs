@@ -0,0 +1,435 @@
+use crate::api::adapters::api_adapter::{ApiAdapterTrait, ApiRequest, ApiResponse, ApiResponseBody};
+use crate::api::adapters::serving::{call_endpoint, response_items};
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::handlers::common::filter_expression::FILTER_PARAM;
+use crate::api::handlers::common::include::{INCLUDE_PARAM, MAX_INCLUDE_DEPTH};
+use crate::api::handlers::common::utils::default_headers;
+use crate::config::configuration::Config;
+use crate::config::specific::entity_config::{DataType, Entity, HttpMethod, Relationship, RelationshipType};
+use crate::error::{Result, RusterApiError};
+use async_graphql::dynamic::{
+    Field, FieldFuture, FieldValue, InputObject, InputValue, Object, ResolverContext, Scalar, Schema, TypeRef,
+};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{ErrorExtensions, Lookahead};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Path the GraphQL endpoint is served at
+pub const GRAPHQL_PATH: &str = "/graphql";
+/// Scalar of the JSON fields
+const JSON_SCALAR: &str = "JSON";
+/// Maximum nesting of the queries when `max_depth` is not set
+const DEFAULT_MAX_DEPTH: usize = 10;
+/// Headers of the GraphQL request not passed on to the endpoints, as they describe the GraphQL
+/// request itself or would make every operation of a document answer the same
+const NOT_FORWARDED_HEADERS: [&str; 8] = [
+    "accept",
+    "content-type",
+    "content-length",
+    "if-match",
+    "if-none-match",
+    "if-modified-since",
+    "if-unmodified-since",
+    "idempotency-key",
+];
+
+type Adapter<T> = Arc<dyn ApiAdapterTrait<T> + Send + Sync>;
+
+/// GraphQL endpoint of a served API
+#[derive(Clone)]
+pub struct GraphqlEndpoint {
+    pub schema: Schema,
+    /// Whether `GET /graphql` serves the GraphiQL IDE
+    pub graphiql: bool,
+}
+
+/// HTTP request a GraphQL document was sent with. The requests the resolvers send to the
+/// endpoints carry its headers and identifier.
+#[derive(Clone, Default)]
+pub struct GraphqlRequest {
+    pub headers: HashMap<String, String>,
+    pub request_id: String,
+}
+
+/// What the resolvers of an entity need: the adapter whose endpoints they call and the
+/// entities its relationships lead to
+struct Resolvers<T> {
+    api_adapter: Adapter<T>,
+    entity: Arc<Entity>,
+    entities: Arc<Vec<Entity>>,
+}
+
+// Derived Clone would require T: Clone
+impl<T> Clone for Resolvers<T> {
+    fn clone(&self) -> Self {
+        Self { api_adapter: self.api_adapter.clone(), entity: self.entity.clone(), entities: self.entities.clone() }
+    }
+}
+
+/// Name usable in a GraphQL schema: letters, digits and underscores, not starting with a digit
+fn graphql_name(name: &str) -> String {
+    let mut name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Type of an entity, like `OrderItems` for `order_items`
+fn type_name(entity: &str) -> String {
+    let pascal: String = graphql_name(entity)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| part[..1].to_ascii_uppercase() + &part[1..])
+        .collect();
+    graphql_name(&pascal)
+}
+
+/// Query listing an entity, like `orderItems` for `order_items`
+fn list_name(entity: &str) -> String {
+    let type_name = type_name(entity);
+    type_name[..1].to_ascii_lowercase() + &type_name[1..]
+}
+
+fn scalar_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Integer => TypeRef::INT,
+        DataType::Float => TypeRef::FLOAT,
+        DataType::Boolean => TypeRef::BOOLEAN,
+        DataType::JSON => JSON_SCALAR,
+        DataType::String | DataType::Date | DataType::DateTime | DataType::Binary => TypeRef::STRING,
+    }
+}
+
+/// Relationships of an entity exposed as fields, with their related entity. Many-to-many
+/// relationships are left out, as the endpoints cannot include them.
+fn relationships<'a>(entity: &'a Entity, entities: &'a [Entity]) -> impl Iterator<Item = (&'a Relationship, &'a Entity)> {
+    entity.relationships.iter()
+        .filter(|relationship| !matches!(relationship.type_, RelationshipType::ManyToMany))
+        .filter_map(|relationship| {
+            let related = entities.iter().find(|related| related.name.eq_ignore_ascii_case(&relationship.related_entity))?;
+            Some((relationship, related))
+        })
+}
+
+/// Relationships selected under a field, as paths of the `include` parameter like `author.country`
+fn selected_includes(lookahead: &Lookahead, entity: &Entity, entities: &[Entity], prefix: &str, paths: &mut Vec<String>) {
+    if prefix.split('.').count() >= MAX_INCLUDE_DEPTH {
+        return;
+    }
+    for (relationship, related) in relationships(entity, entities) {
+        let selected = lookahead.field(&graphql_name(&relationship.name));
+        if !selected.exists() {
+            continue;
+        }
+        let path = if prefix.is_empty() { relationship.name.clone() } else { format!("{}.{}", prefix, relationship.name) };
+        selected_includes(&selected, related, entities, &path, paths);
+        paths.push(path);
+    }
+}
+
+/// The JSON object a field belongs to
+fn parent<'a>(ctx: &ResolverContext<'a>) -> Option<&'a Value> {
+    ctx.parent_value.downcast_ref::<Value>()
+}
+
+/// Builds the request an operation sends to an endpoint
+fn api_request(ctx: &ResolverContext, method: HttpMethod, path: String, query: HashMap<String, String>, body: Option<Value>) -> ApiRequest {
+    let http = ctx.data_opt::<GraphqlRequest>().cloned().unwrap_or_default();
+    let headers = http.headers.into_iter()
+        .filter(|(name, _)| !NOT_FORWARDED_HEADERS.iter().any(|header| name.eq_ignore_ascii_case(header)))
+        .collect();
+    ApiRequest {
+        method,
+        path,
+        params: HashMap::new(),
+        query,
+        headers,
+        body: body.map(|body| body.to_string()),
+        request_id: http.request_id,
+        auth: None,
+        tenant: None,
+    }
+}
+
+/// GraphQL error of a failed request, with its status in the `status` extension
+fn graphql_error((status, message): (u16, String)) -> async_graphql::Error {
+    async_graphql::Error::new(message).extend_with(|_, extensions| extensions.set("status", status))
+}
+
+fn object_value(value: Option<Value>) -> Option<FieldValue<'static>> {
+    value.filter(|value| !value.is_null()).map(FieldValue::owned_any)
+}
+
+/// Object type of an entity, with its fields and the relationships it can embed
+fn object_type<T>(resolvers: &Resolvers<T>) -> Object {
+    let entity = &resolvers.entity;
+    let mut object = Object::new(type_name(&entity.name));
+    for field in &entity.fields {
+        let name = field.name.clone();
+        let mut graphql_field = Field::new(graphql_name(&field.name), TypeRef::named(scalar_type(&field.data_type)), move |ctx| {
+            let value = parent(&ctx).and_then(|parent| parent.get(&name)).filter(|value| !value.is_null());
+            FieldFuture::from_value(value.and_then(|value| async_graphql::Value::from_json(value.clone()).ok()))
+        });
+        if let Some(description) = &field.description {
+            graphql_field = graphql_field.description(description);
+        }
+        object = object.field(graphql_field);
+    }
+
+    for (relationship, related) in relationships(entity, &resolvers.entities) {
+        let name = relationship.name.clone();
+        let to_many = matches!(relationship.type_, RelationshipType::OneToMany);
+        let field_type = if to_many { TypeRef::named_nn_list(type_name(&related.name)) } else { TypeRef::named(type_name(&related.name)) };
+        object = object.field(Field::new(graphql_name(&relationship.name), field_type, move |ctx| {
+            let value = parent(&ctx).and_then(|parent| parent.get(&name)).cloned();
+            FieldFuture::Value(match value {
+                Some(Value::Array(items)) if to_many => Some(FieldValue::list(items.into_iter().map(FieldValue::owned_any))),
+                value => object_value(value),
+            })
+        }));
+    }
+    object
+}
+
+/// Fields of an entity clients can write, by GraphQL name
+fn writable_fields(entity: &Entity) -> Vec<(String, &crate::config::specific::entity_config::Field)> {
+    entity.fields.iter()
+        .filter(|field| field.is_stored() && !field.generated && !field.read_only)
+        .map(|field| (graphql_name(&field.name), field))
+        .collect()
+}
+
+/// Input type of the create and update mutations of an entity
+fn input_type(entity: &Entity) -> InputObject {
+    writable_fields(entity).into_iter().fold(
+        InputObject::new(format!("{}Input", type_name(&entity.name))),
+        |input, (name, field)| input.field(InputValue::new(name, TypeRef::named(scalar_type(&field.data_type)))),
+    )
+}
+
+/// Body of a create or update request from the `input` argument
+fn input_body(ctx: &ResolverContext, entity: &Entity) -> async_graphql::Result<Value> {
+    let fields = writable_fields(entity);
+    let mut body = Map::new();
+    for (name, value) in ctx.args.try_get("input")?.object()?.iter() {
+        if let Some((_, field)) = fields.iter().find(|(graphql_name, _)| graphql_name == name.as_str()) {
+            body.insert(field.name.clone(), value.as_value().clone().into_json()?);
+        }
+    }
+    Ok(Value::Object(body))
+}
+
+/// The `id` argument, given as a string or a number
+fn id_arg(ctx: &ResolverContext) -> async_graphql::Result<String> {
+    match ctx.args.try_get("id")?.as_value() {
+        async_graphql::Value::String(id) => Ok(id.clone()),
+        async_graphql::Value::Number(id) => Ok(id.to_string()),
+        _ => Err(async_graphql::Error::new("Invalid id")),
+    }
+}
+
+/// Queries listing an entity and reading one by key
+fn query_fields<T: ApiEntity>(resolvers: &Resolvers<T>) -> Vec<Field> {
+    let entity = &resolvers.entity;
+    let type_name = type_name(&entity.name);
+    let mut fields = Vec::new();
+
+    if entity.endpoints.generate_list {
+        let list = resolvers.clone();
+        let mut field = Field::new(list_name(&entity.name), TypeRef::named_nn_list_nn(&type_name), move |ctx| {
+            let mut query = HashMap::new();
+            let mut includes = Vec::new();
+            selected_includes(&ctx.look_ahead(), &list.entity, &list.entities, "", &mut includes);
+            if !includes.is_empty() {
+                query.insert(INCLUDE_PARAM.to_string(), includes.join(","));
+            }
+            let mut params = vec![("filter", FILTER_PARAM.to_string()), ("sort", "sort".to_string())];
+            if let Some(pagination) = &list.entity.pagination {
+                params.push(("page", pagination.page_param_name.clone()));
+                params.push(("size", pagination.size_param_name.clone()));
+            }
+            for (arg, param) in params {
+                match ctx.args.get(arg).map(|value| value.as_value().clone()) {
+                    Some(async_graphql::Value::String(value)) => query.insert(param, value),
+                    Some(async_graphql::Value::Number(value)) => query.insert(param, value.to_string()),
+                    _ => None,
+                };
+            }
+            let request = api_request(&ctx, HttpMethod::GET, list.entity.name.clone(), query, None);
+            let api_adapter = list.api_adapter.clone();
+            FieldFuture::new(async move {
+                let response = call_endpoint(api_adapter, request).await.map_err(graphql_error)?;
+                Ok(Some(FieldValue::list(response_items(response.body).into_iter().map(FieldValue::owned_any))))
+            })
+        })
+        .argument(InputValue::new("filter", TypeRef::named(TypeRef::STRING)))
+        .argument(InputValue::new("sort", TypeRef::named(TypeRef::STRING)));
+        if entity.pagination.is_some() {
+            field = field
+                .argument(InputValue::new("page", TypeRef::named(TypeRef::INT)))
+                .argument(InputValue::new("size", TypeRef::named(TypeRef::INT)));
+        }
+        fields.push(field);
+    }
+
+    if entity.endpoints.generate_read {
+        let read = resolvers.clone();
+        fields.push(Field::new(format!("{}ById", list_name(&entity.name)), TypeRef::named(&type_name), move |ctx| {
+            let id = match id_arg(&ctx) {
+                Ok(id) => id,
+                Err(err) => return FieldFuture::new(async move { Err::<Option<FieldValue>, _>(err) }),
+            };
+            let mut query = HashMap::new();
+            let mut includes = Vec::new();
+            selected_includes(&ctx.look_ahead(), &read.entity, &read.entities, "", &mut includes);
+            if !includes.is_empty() {
+                query.insert(INCLUDE_PARAM.to_string(), includes.join(","));
+            }
+            let request = api_request(&ctx, HttpMethod::GET, format!("{}/{}", read.entity.name, id), query, None);
+            let api_adapter = read.api_adapter.clone();
+            FieldFuture::new(async move {
+                match call_endpoint(api_adapter, request).await {
+                    Ok(response) => Ok(object_value(response_items(response.body).into_iter().next())),
+                    Err((404, _)) => Ok(None),
+                    Err(failure) => Err(graphql_error(failure)),
+                }
+            })
+        })
+        .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::ID))));
+    }
+    fields
+}
+
+/// Mutations creating, updating and deleting an entity
+fn mutation_fields<T: ApiEntity>(resolvers: &Resolvers<T>) -> Vec<Field> {
+    let entity = &resolvers.entity;
+    let type_name = type_name(&entity.name);
+    let input_name = format!("{}Input", type_name);
+    let mut fields = Vec::new();
+
+    if entity.endpoints.generate_create {
+        let create = resolvers.clone();
+        fields.push(Field::new(format!("create{}", type_name), TypeRef::named(&type_name), move |ctx| {
+            let request = input_body(&ctx, &create.entity)
+                .map(|body| api_request(&ctx, HttpMethod::POST, create.entity.name.clone(), HashMap::new(), Some(body)));
+            let api_adapter = create.api_adapter.clone();
+            FieldFuture::new(async move {
+                let response = call_endpoint(api_adapter, request?).await.map_err(graphql_error)?;
+                Ok(object_value(response_items(response.body).into_iter().next()))
+            })
+        })
+        .argument(InputValue::new("input", TypeRef::named_nn(&input_name))));
+    }
+
+    if entity.endpoints.generate_update {
+        let update = resolvers.clone();
+        fields.push(Field::new(format!("update{}", type_name), TypeRef::named(&type_name), move |ctx| {
+            let request = id_arg(&ctx).and_then(|id| {
+                let body = input_body(&ctx, &update.entity)?;
+                Ok(api_request(&ctx, HttpMethod::PUT, format!("{}/{}", update.entity.name, id), HashMap::new(), Some(body)))
+            });
+            let api_adapter = update.api_adapter.clone();
+            FieldFuture::new(async move {
+                let response = call_endpoint(api_adapter, request?).await.map_err(graphql_error)?;
+                Ok(object_value(response_items(response.body).into_iter().next()))
+            })
+        })
+        .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::ID)))
+        .argument(InputValue::new("input", TypeRef::named_nn(&input_name))));
+    }
+
+    if entity.endpoints.generate_delete {
+        let delete = resolvers.clone();
+        fields.push(Field::new(format!("delete{}", type_name), TypeRef::named_nn(TypeRef::BOOLEAN), move |ctx| {
+            let request = id_arg(&ctx)
+                .map(|id| api_request(&ctx, HttpMethod::DELETE, format!("{}/{}", delete.entity.name, id), HashMap::new(), None));
+            let api_adapter = delete.api_adapter.clone();
+            FieldFuture::new(async move {
+                match call_endpoint(api_adapter, request?).await {
+                    Ok(_) => Ok(Some(async_graphql::Value::Boolean(true))),
+                    Err((404, _)) => Ok(Some(async_graphql::Value::Boolean(false))),
+                    Err(failure) => Err(graphql_error(failure)),
+                }
+            })
+        })
+        .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::ID))));
+    }
+    fields
+}
+
+/// Generates the GraphQL schema of the entities an adapter serves: a type per entity with its
+/// to-one and one-to-many relationships as nested fields, queries listing and reading them and
+/// mutations creating, updating and deleting them. Resolvers call the endpoints of the adapter,
+/// so operations are validated, run the hooks and publish changes like REST requests do.
+pub fn build_schema<T: ApiEntity>(config: &Config, api_adapter: Adapter<T>) -> Result<Schema> {
+    let max_depth = config.server.graphql.as_ref().and_then(|graphql| graphql.max_depth).unwrap_or(DEFAULT_MAX_DEPTH);
+    let entities: Vec<Entity> = config.all_entities().into_iter()
+        .filter(|entity| api_adapter.route_for(&HttpMethod::GET, &entity.name).is_some()
+            || api_adapter.route_for(&HttpMethod::POST, &entity.name).is_some())
+        .collect();
+    let entities = Arc::new(entities);
+
+    let api_version = config.api_version.clone();
+    let mut query = Object::new("Query").field(Field::new("apiVersion", TypeRef::named_nn(TypeRef::STRING), move |_| {
+        FieldFuture::from_value(Some(async_graphql::Value::String(api_version.clone())))
+    }));
+    let mut mutation = Object::new("Mutation");
+    let mut types = Vec::new();
+    let mut inputs = Vec::new();
+    for entity in entities.iter() {
+        let resolvers = Resolvers {
+            api_adapter: api_adapter.clone(),
+            entity: Arc::new(entity.clone()),
+            entities: entities.clone(),
+        };
+        types.push(object_type(&resolvers));
+        query = query_fields(&resolvers).into_iter().fold(query, Object::field);
+        let mutations = mutation_fields(&resolvers);
+        if entity.endpoints.generate_create || entity.endpoints.generate_update {
+            inputs.push(input_type(entity));
+        }
+        mutation = mutations.into_iter().fold(mutation, Object::field);
+    }
+
+    let has_mutations = !inputs.is_empty() || entities.iter().any(|entity| entity.endpoints.generate_delete);
+    let mut builder = Schema::build("Query", has_mutations.then_some("Mutation"), None)
+        .register(Scalar::new(JSON_SCALAR))
+        .register(query)
+        .limit_depth(max_depth);
+    if has_mutations {
+        builder = builder.register(mutation);
+    }
+    builder = types.into_iter().fold(builder, |builder, object| builder.register(object));
+    builder = inputs.into_iter().fold(builder, |builder, input| builder.register(input));
+    builder.finish().map_err(|e| RusterApiError::ConfigError(format!("Failed to generate the GraphQL schema: {}", e)))
+}
+
+/// Runs a GraphQL request sent as JSON, with its `query`, `variables` and `operationName`.
+/// Failed operations are reported in the `errors` of the response, which is answered with 200.
+pub async fn execute(schema: &Schema, body: Option<&str>, http: GraphqlRequest) -> ApiResponse<Value> {
+    let request = match serde_json::from_str::<async_graphql::Request>(body.unwrap_or_default()) {
+        Ok(request) => request,
+        Err(e) => {
+            return ApiResponse {
+                status: 400,
+                headers: default_headers(),
+                body: Some(ApiResponseBody::Json(json!({ "errors": [{ "message": format!("Invalid GraphQL request: {}", e) }] }))),
+            };
+        }
+    };
+
+    let response = schema.execute(request.data(http)).await;
+    ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(serde_json::to_value(response).unwrap_or_default())),
+    }
+}
+
+/// GraphiQL page sending its queries to the GraphQL endpoint
+pub fn graphiql() -> String {
+    GraphiQLSource::build().endpoint(GRAPHQL_PATH).title("Rawst GraphQL").finish()
+}
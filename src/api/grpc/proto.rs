@@ -0,0 +1,234 @@
+use crate::config::configuration::Config;
+use crate::config::specific::entity_config::{DataType, Entity};
+
+/// Package of the generated messages and service
+pub const PACKAGE: &str = "rawst";
+/// Service holding the methods of every entity
+pub const SERVICE: &str = "Rawst";
+
+/// Scalar types of the message fields
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProtoType {
+    Int64,
+    Double,
+    Bool,
+    String,
+}
+
+impl ProtoType {
+    /// Type a field of an entity is sent as. JSON fields hold the JSON text.
+    pub fn of(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Integer => ProtoType::Int64,
+            DataType::Float => ProtoType::Double,
+            DataType::Boolean => ProtoType::Bool,
+            DataType::String | DataType::Date | DataType::DateTime | DataType::Binary | DataType::JSON => ProtoType::String,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProtoType::Int64 => "int64",
+            ProtoType::Double => "double",
+            ProtoType::Bool => "bool",
+            ProtoType::String => "string",
+        }
+    }
+}
+
+/// Type of a message field: a scalar or another message
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    Scalar(ProtoType),
+    Message(String),
+}
+
+/// Field of a message
+#[derive(Debug, Clone)]
+pub struct ProtoField {
+    pub name: String,
+    pub number: i32,
+    pub field_type: FieldType,
+    /// Whether the field tracks its presence, so unset fields are told apart from default values
+    pub optional: bool,
+    pub repeated: bool,
+    pub description: Option<String>,
+}
+
+/// Message of the generated file
+#[derive(Debug, Clone)]
+pub struct ProtoMessage {
+    pub name: String,
+    pub fields: Vec<ProtoField>,
+}
+
+/// Operations the methods of an entity run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RpcKind {
+    Get,
+    List,
+    Create,
+    Update,
+    Delete,
+}
+
+impl RpcKind {
+    /// Verb the methods running the operation start with
+    pub fn verb(&self) -> &'static str {
+        match self {
+            RpcKind::Get => "Get",
+            RpcKind::List => "List",
+            RpcKind::Create => "Create",
+            RpcKind::Update => "Update",
+            RpcKind::Delete => "Delete",
+        }
+    }
+}
+
+/// Method of the service, running an operation on an entity
+#[derive(Debug, Clone)]
+pub struct ProtoRpc {
+    pub name: String,
+    pub kind: RpcKind,
+    pub entity: Entity,
+    pub input: String,
+    pub output: String,
+}
+
+/// Protobuf definitions of the entities of a configuration: a message per entity, whose field
+/// numbers follow the order of the fields in the configuration, and a method per operation the
+/// endpoints of the entity allow
+#[derive(Debug, Clone)]
+pub struct ProtoFile {
+    pub messages: Vec<ProtoMessage>,
+    pub rpcs: Vec<ProtoRpc>,
+}
+
+/// Identifier usable in a protobuf file: letters, digits and underscores, not starting with a digit
+pub fn identifier(name: &str) -> String {
+    let mut name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Message of an entity, like `OrderItems` for `order_items`
+pub fn message_name(entity: &str) -> String {
+    let pascal: String = identifier(entity)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| part[..1].to_ascii_uppercase() + &part[1..])
+        .collect();
+    identifier(&pascal)
+}
+
+fn field(name: &str, number: i32, field_type: FieldType) -> ProtoField {
+    ProtoField { name: name.to_string(), number, field_type, optional: false, repeated: false, description: None }
+}
+
+fn scalar(name: &str, number: i32, proto_type: ProtoType) -> ProtoField {
+    field(name, number, FieldType::Scalar(proto_type))
+}
+
+fn message(name: String, fields: Vec<ProtoField>) -> ProtoMessage {
+    ProtoMessage { name, fields }
+}
+
+impl ProtoFile {
+    /// Generates the definitions of the entities of a configuration
+    pub fn from_config(config: &Config) -> Self {
+        let mut messages = Vec::new();
+        let mut rpcs = Vec::new();
+
+        for entity in config.all_entities() {
+            let endpoints = &entity.endpoints;
+            let operations = [
+                (RpcKind::Get, endpoints.generate_read),
+                (RpcKind::List, endpoints.generate_list),
+                (RpcKind::Create, endpoints.generate_create),
+                (RpcKind::Update, endpoints.generate_update),
+                (RpcKind::Delete, endpoints.generate_delete),
+            ];
+            if !operations.iter().any(|(_, generated)| *generated) {
+                continue;
+            }
+
+            let name = message_name(&entity.name);
+            let fields = entity.fields.iter().zip(1..).map(|(entity_field, number)| ProtoField {
+                optional: true,
+                description: entity_field.description.clone(),
+                ..scalar(&identifier(&entity_field.name), number, ProtoType::of(&entity_field.data_type))
+            });
+            messages.push(message(name.clone(), fields.collect()));
+
+            for (kind, _) in operations.into_iter().filter(|(_, generated)| *generated) {
+                let (input, output) = match kind {
+                    RpcKind::Get => {
+                        messages.push(message(format!("Get{}Request", name), vec![scalar("id", 1, ProtoType::String)]));
+                        (format!("Get{}Request", name), name.clone())
+                    }
+                    RpcKind::List => {
+                        let mut fields = vec![scalar("filter", 1, ProtoType::String), scalar("sort", 2, ProtoType::String)];
+                        if entity.pagination.is_some() {
+                            fields.push(scalar("page", 3, ProtoType::Int64));
+                            fields.push(scalar("size", 4, ProtoType::Int64));
+                        }
+                        messages.push(message(format!("List{}Request", name), fields));
+                        let items = ProtoField { repeated: true, ..field("items", 1, FieldType::Message(name.clone())) };
+                        messages.push(message(format!("List{}Response", name), vec![items]));
+                        (format!("List{}Request", name), format!("List{}Response", name))
+                    }
+                    RpcKind::Create => (name.clone(), name.clone()),
+                    RpcKind::Update => {
+                        let fields = vec![scalar("id", 1, ProtoType::String), field("item", 2, FieldType::Message(name.clone()))];
+                        messages.push(message(format!("Update{}Request", name), fields));
+                        (format!("Update{}Request", name), name.clone())
+                    }
+                    RpcKind::Delete => {
+                        messages.push(message(format!("Delete{}Request", name), vec![scalar("id", 1, ProtoType::String)]));
+                        messages.push(message(format!("Delete{}Response", name), vec![scalar("deleted", 1, ProtoType::Bool)]));
+                        (format!("Delete{}Request", name), format!("Delete{}Response", name))
+                    }
+                };
+                rpcs.push(ProtoRpc { name: format!("{}{}", kind.verb(), name), kind, entity: entity.clone(), input, output });
+            }
+        }
+        Self { messages, rpcs }
+    }
+
+    /// Text of the `.proto` file, for clients to generate their stubs from
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            "// Generated by Rawst from the entities of the configuration".to_string(),
+            "syntax = \"proto3\";".to_string(),
+            String::new(),
+            format!("package {};", PACKAGE),
+            String::new(),
+            format!("service {} {{", SERVICE),
+        ];
+        for rpc in &self.rpcs {
+            lines.push(format!("  rpc {}({}) returns ({});", rpc.name, rpc.input, rpc.output));
+        }
+        lines.push("}".to_string());
+
+        for message in &self.messages {
+            lines.push(String::new());
+            lines.push(format!("message {} {{", message.name));
+            for field in &message.fields {
+                if let Some(description) = &field.description {
+                    lines.extend(description.lines().map(|line| format!("  // {}", line)));
+                }
+                let label = if field.repeated { "repeated " } else if field.optional { "optional " } else { "" };
+                let field_type = match &field.field_type {
+                    FieldType::Scalar(proto_type) => proto_type.name(),
+                    FieldType::Message(name) => name,
+                };
+                lines.push(format!("  {}{} {} = {};", label, field_type, field.name, field.number));
+            }
+            lines.push("}".to_string());
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
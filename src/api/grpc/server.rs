@@ -0,0 +1,415 @@
+use crate::api::adapters::api_adapter::{ApiAdapterTrait, ApiRequest};
+use crate::api::adapters::serving::{call_endpoint, response_items};
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::grpc::proto::{FieldType, ProtoFile, ProtoRpc, ProtoType, RpcKind, PACKAGE, SERVICE};
+use crate::api::handlers::common::filter_expression::FILTER_PARAM;
+use crate::api::handlers::common::request_id::{request_id, REQUEST_ID_HEADER};
+use crate::config::configuration::Config;
+use crate::config::specific::entity_config::{DataType, Entity, Field, HttpMethod};
+use crate::error::{Result, RusterApiError};
+use crate::logging::logger::log_request;
+use prost_reflect::prost::Message;
+use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+use prost_reflect::prost_types::{
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto, OneofDescriptorProto,
+    ServiceDescriptorProto,
+};
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, ReflectMessage, Value as ProtoValue};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Instant;
+use tonic::body::Body;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::tokio_stream::wrappers::TcpListenerStream;
+use tonic::codegen::{http, BoxFuture, Context, Poll, Service};
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::server::{Grpc, UnaryService};
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status};
+use tracing::{error, info, info_span, Instrument};
+
+/// Address the gRPC server binds to when `server.host` is empty
+const DEFAULT_HOST: &str = "127.0.0.1";
+/// Metadata of the gRPC calls not passed on to the endpoints, as they describe the call itself
+const NOT_FORWARDED_HEADERS: [&str; 3] = ["content-type", "te", "content-length"];
+
+type Adapter<T> = Arc<dyn ApiAdapterTrait<T> + Send + Sync>;
+
+/// Method of the service with the descriptors of its messages
+struct Method {
+    rpc: ProtoRpc,
+    input: MessageDescriptor,
+    output: MessageDescriptor,
+}
+
+/// Descriptors of the messages and service of the generated definitions
+fn file_descriptor(proto: &ProtoFile) -> FileDescriptorProto {
+    let message_type = proto.messages.iter().map(|message| {
+        let mut descriptor = DescriptorProto { name: Some(message.name.clone()), ..Default::default() };
+        for field in &message.fields {
+            let (field_type, type_name) = match &field.field_type {
+                FieldType::Scalar(ProtoType::Int64) => (Type::Int64, None),
+                FieldType::Scalar(ProtoType::Double) => (Type::Double, None),
+                FieldType::Scalar(ProtoType::Bool) => (Type::Bool, None),
+                FieldType::Scalar(ProtoType::String) => (Type::String, None),
+                FieldType::Message(name) => (Type::Message, Some(format!(".{}.{}", PACKAGE, name))),
+            };
+            let mut field_descriptor = FieldDescriptorProto {
+                name: Some(field.name.clone()),
+                number: Some(field.number),
+                label: Some(if field.repeated { Label::Repeated } else { Label::Optional } as i32),
+                r#type: Some(field_type as i32),
+                type_name,
+                ..Default::default()
+            };
+            // Optional proto3 fields belong to a oneof of their own
+            if field.optional {
+                field_descriptor.proto3_optional = Some(true);
+                field_descriptor.oneof_index = Some(descriptor.oneof_decl.len() as i32);
+                descriptor.oneof_decl.push(OneofDescriptorProto { name: Some(format!("_{}", field.name)), ..Default::default() });
+            }
+            descriptor.field.push(field_descriptor);
+        }
+        descriptor
+    });
+    let method = proto.rpcs.iter().map(|rpc| MethodDescriptorProto {
+        name: Some(rpc.name.clone()),
+        input_type: Some(format!(".{}.{}", PACKAGE, rpc.input)),
+        output_type: Some(format!(".{}.{}", PACKAGE, rpc.output)),
+        ..Default::default()
+    });
+
+    FileDescriptorProto {
+        name: Some(format!("{}.proto", PACKAGE)),
+        package: Some(PACKAGE.to_string()),
+        message_type: message_type.collect(),
+        service: vec![ServiceDescriptorProto { name: Some(SERVICE.to_string()), method: method.collect(), ..Default::default() }],
+        syntax: Some("proto3".to_string()),
+        ..Default::default()
+    }
+}
+
+/// Codec of the messages of a method, decoding requests with the descriptor of its input
+#[derive(Clone)]
+struct DynamicCodec {
+    input: MessageDescriptor,
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicCodec;
+    type Decoder = DynamicCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.clone()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.clone()
+    }
+}
+
+impl Encoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: DynamicMessage, dst: &mut EncodeBuf<'_>) -> std::result::Result<(), Status> {
+        item.encode(dst).map_err(|e| Status::internal(format!("Failed to encode the response: {}", e)))
+    }
+}
+
+impl Decoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> std::result::Result<Option<DynamicMessage>, Status> {
+        DynamicMessage::decode(self.input.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::invalid_argument(format!("Invalid request message: {}", e)))
+    }
+}
+
+/// Value of an entity field in a message. Values the field cannot hold are left unset.
+fn proto_value(field: &Field, value: &Value) -> Option<ProtoValue> {
+    match (ProtoType::of(&field.data_type), value) {
+        (_, Value::Null) => None,
+        (ProtoType::Int64, Value::Number(number)) => number.as_i64().map(ProtoValue::I64),
+        (ProtoType::Int64, Value::String(text)) => text.parse().ok().map(ProtoValue::I64),
+        (ProtoType::Double, Value::Number(number)) => number.as_f64().map(ProtoValue::F64),
+        (ProtoType::Double, Value::String(text)) => text.parse().ok().map(ProtoValue::F64),
+        (ProtoType::Bool, Value::Bool(flag)) => Some(ProtoValue::Bool(*flag)),
+        (ProtoType::Bool, Value::String(text)) => text.parse().ok().map(ProtoValue::Bool),
+        (ProtoType::String, Value::String(text)) if !matches!(field.data_type, DataType::JSON) => {
+            Some(ProtoValue::String(text.clone()))
+        }
+        (ProtoType::String, value) => Some(ProtoValue::String(value.to_string())),
+        _ => None,
+    }
+}
+
+/// Message of an entity from its JSON
+fn entity_message(descriptor: &MessageDescriptor, entity: &Entity, item: &Value) -> DynamicMessage {
+    let mut message = DynamicMessage::new(descriptor.clone());
+    for (field, number) in entity.fields.iter().zip(1..) {
+        let value = item.get(&field.name).and_then(|value| proto_value(field, value));
+        if let (Some(field_descriptor), Some(value)) = (descriptor.get_field(number), value) {
+            message.set_field(&field_descriptor, value);
+        }
+    }
+    message
+}
+
+/// JSON of an entity from its message, with the fields the client set
+fn entity_json(entity: &Entity, message: &DynamicMessage) -> Value {
+    let mut body = Map::new();
+    for (field, number) in entity.fields.iter().zip(1..) {
+        let Some(field_descriptor) = message.descriptor().get_field(number) else {
+            continue;
+        };
+        if !message.has_field(&field_descriptor) {
+            continue;
+        }
+        let value = match (message.get_field(&field_descriptor).as_ref(), &field.data_type) {
+            (ProtoValue::String(text), DataType::JSON) => {
+                serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.clone()))
+            }
+            (ProtoValue::String(text), _) => Value::String(text.clone()),
+            (ProtoValue::I64(number), _) => json!(number),
+            (ProtoValue::F64(number), _) => json!(number),
+            (ProtoValue::Bool(flag), _) => Value::Bool(*flag),
+            _ => continue,
+        };
+        body.insert(field.name.clone(), value);
+    }
+    Value::Object(body)
+}
+
+fn string_field(message: &DynamicMessage, name: &str) -> String {
+    message.get_field_by_name(name).and_then(|value| value.as_str().map(str::to_string)).unwrap_or_default()
+}
+
+/// Status of a failed call, from the status the endpoint answered
+fn status((status, message): (u16, String), request_id: &str) -> Status {
+    let code = match status {
+        400 | 413 | 422 => Code::InvalidArgument,
+        401 => Code::Unauthenticated,
+        403 => Code::PermissionDenied,
+        404 => Code::NotFound,
+        409 => Code::AlreadyExists,
+        412 => Code::FailedPrecondition,
+        429 => Code::ResourceExhausted,
+        501 => Code::Unimplemented,
+        503 => Code::Unavailable,
+        504 => Code::DeadlineExceeded,
+        _ => Code::Internal,
+    };
+    Status::with_metadata(code, message, request_metadata(request_id))
+}
+
+/// Metadata echoing the identifier of the call
+fn request_metadata(request_id: &str) -> MetadataMap {
+    let mut metadata = MetadataMap::new();
+    if let Ok(value) = MetadataValue::try_from(request_id) {
+        metadata.insert(REQUEST_ID_HEADER, value);
+    }
+    metadata
+}
+
+/// Runs a call through the endpoint of its operation, so it is validated, runs the hooks and
+/// publishes changes like REST requests do
+async fn run<T: ApiEntity>(api_adapter: Adapter<T>, method: &Method, request: Request<DynamicMessage>) -> std::result::Result<Response<DynamicMessage>, Status> {
+    let started = Instant::now();
+    let headers: HashMap<String, String> = request.metadata().clone().into_headers().iter()
+        .filter(|(name, _)| !NOT_FORWARDED_HEADERS.contains(&name.as_str()) && !name.as_str().starts_with("grpc-"))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let request_id = request_id(&headers);
+    let message = request.into_inner();
+    let entity = &method.rpc.entity;
+
+    let mut query = HashMap::new();
+    let (http_method, path, body) = match method.rpc.kind {
+        RpcKind::Get => (HttpMethod::GET, format!("{}/{}", entity.name, string_field(&message, "id")), None),
+        RpcKind::List => {
+            let mut params = vec![("filter", FILTER_PARAM.to_string()), ("sort", "sort".to_string())];
+            if let Some(pagination) = &entity.pagination {
+                params.push(("page", pagination.page_param_name.clone()));
+                params.push(("size", pagination.size_param_name.clone()));
+            }
+            for (field, param) in params {
+                match message.get_field_by_name(field).as_deref() {
+                    Some(ProtoValue::String(value)) if !value.is_empty() => query.insert(param, value.clone()),
+                    Some(ProtoValue::I64(value)) if *value > 0 => query.insert(param, value.to_string()),
+                    _ => None,
+                };
+            }
+            (HttpMethod::GET, entity.name.clone(), None)
+        }
+        RpcKind::Create => (HttpMethod::POST, entity.name.clone(), Some(entity_json(entity, &message))),
+        RpcKind::Update => {
+            let item = message.get_field_by_name("item")
+                .and_then(|item| item.as_message().map(|item| entity_json(entity, item)))
+                .unwrap_or_else(|| json!({}));
+            (HttpMethod::PUT, format!("{}/{}", entity.name, string_field(&message, "id")), Some(item))
+        }
+        RpcKind::Delete => (HttpMethod::DELETE, format!("{}/{}", entity.name, string_field(&message, "id")), None),
+    };
+
+    let api_request = ApiRequest {
+        method: http_method,
+        path,
+        params: HashMap::new(),
+        query,
+        headers,
+        body: body.map(|body| body.to_string()),
+        request_id: request_id.clone(),
+        auth: None,
+        tenant: None,
+    };
+    let grpc_path = format!("{}.{}/{}", PACKAGE, SERVICE, method.rpc.name);
+    let span = info_span!("grpc", otel.name = grpc_path.as_str(), otel.kind = "server", request_id = request_id.as_str());
+    let result = call_endpoint(api_adapter, api_request).instrument(span).await;
+    let answered = match &result {
+        Ok(response) => response.status,
+        Err((status, _)) => *status,
+    };
+    log_request("POST", &grpc_path, None, &request_id, None, answered, started.elapsed());
+
+    let output = match (method.rpc.kind, result) {
+        (RpcKind::Delete, Ok(_) | Err((404, _))) => {
+            let mut output = DynamicMessage::new(method.output.clone());
+            if let Some(deleted) = method.output.get_field_by_name("deleted") {
+                output.set_field(&deleted, ProtoValue::Bool(answered < 400));
+            }
+            output
+        }
+        (RpcKind::List, Ok(response)) => {
+            let mut output = DynamicMessage::new(method.output.clone());
+            if let Some(items) = method.output.get_field_by_name("items") {
+                if let Some(item_descriptor) = items.kind().as_message() {
+                    let values = response_items(response.body).iter()
+                        .map(|item| ProtoValue::Message(entity_message(item_descriptor, entity, item)))
+                        .collect();
+                    output.set_field(&items, ProtoValue::List(values));
+                }
+            }
+            output
+        }
+        (_, Ok(response)) => match response_items(response.body).first() {
+            Some(item) => entity_message(&method.output, entity, item),
+            None => DynamicMessage::new(method.output.clone()),
+        },
+        (_, Err(failure)) => return Err(status(failure, &request_id)),
+    };
+
+    let mut response = Response::new(output);
+    *response.metadata_mut() = request_metadata(&request_id);
+    Ok(response)
+}
+
+/// Unary call of a method
+struct Call<T> {
+    api_adapter: Adapter<T>,
+    method: Arc<Method>,
+}
+
+impl<T: ApiEntity> UnaryService<DynamicMessage> for Call<T> {
+    type Response = DynamicMessage;
+    type Future = BoxFuture<Response<DynamicMessage>, Status>;
+
+    fn call(&mut self, request: Request<DynamicMessage>) -> Self::Future {
+        let api_adapter = self.api_adapter.clone();
+        let method = self.method.clone();
+        Box::pin(async move { run(api_adapter, &method, request).await })
+    }
+}
+
+/// gRPC service of the entities an adapter serves, with the methods of the generated definitions
+pub struct GrpcService<T> {
+    api_adapter: Adapter<T>,
+    /// Methods by path, like `/rawst.Rawst/GetItems`
+    methods: Arc<HashMap<String, Arc<Method>>>,
+}
+
+// Derived Clone would require T: Clone
+impl<T> Clone for GrpcService<T> {
+    fn clone(&self) -> Self {
+        Self { api_adapter: self.api_adapter.clone(), methods: self.methods.clone() }
+    }
+}
+
+impl<T: ApiEntity> GrpcService<T> {
+    /// Generates the service of the entities of a configuration
+    pub fn new(config: &Config, api_adapter: Adapter<T>) -> Result<Self> {
+        let proto = ProtoFile::from_config(config);
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_proto(file_descriptor(&proto))
+            .map_err(|e| RusterApiError::ConfigError(format!("Failed to generate the gRPC service: {}", e)))?;
+
+        let mut methods = HashMap::new();
+        for rpc in proto.rpcs {
+            let descriptor = |name: &str| {
+                pool.get_message_by_name(&format!("{}.{}", PACKAGE, name)).ok_or_else(|| {
+                    RusterApiError::ConfigError(format!("Failed to generate the gRPC service: missing message {}", name))
+                })
+            };
+            let (input, output) = (descriptor(&rpc.input)?, descriptor(&rpc.output)?);
+            let path = format!("/{}.{}/{}", PACKAGE, SERVICE, rpc.name);
+            methods.insert(path, Arc::new(Method { rpc, input, output }));
+        }
+        Ok(Self { api_adapter, methods: Arc::new(methods) })
+    }
+}
+
+impl<T: ApiEntity> Service<http::Request<Body>> for GrpcService<T> {
+    type Response = http::Response<Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let Some(method) = self.methods.get(request.uri().path()).cloned() else {
+            let status = Status::unimplemented(format!("Unknown method {}", request.uri().path()));
+            return Box::pin(async move { Ok(status.into_http()) });
+        };
+        let call = Call { api_adapter: self.api_adapter.clone(), method: method.clone() };
+        Box::pin(async move {
+            let mut grpc = Grpc::new(DynamicCodec { input: method.input.clone() });
+            Ok(grpc.unary(call, request).await)
+        })
+    }
+}
+
+/// Starts the gRPC server of an adapter in the background when `server.grpc` is configured,
+/// listening on its port on the host of the API
+pub fn spawn_server<T: ApiEntity>(config: &Config, api_adapter: Adapter<T>) -> Result<()> {
+    let Some(grpc) = &config.server.grpc else {
+        return Ok(());
+    };
+    let service = GrpcService::new(config, api_adapter)?;
+    let host = match config.server.host.trim() {
+        "" => DEFAULT_HOST,
+        host => host,
+    };
+
+    let bind_error = |e: std::io::Error| {
+        RusterApiError::ServerError(format!("Failed to bind the gRPC server to {}:{}: {}", host, grpc.port, e))
+    };
+    let listener = std::net::TcpListener::bind((host, grpc.port)).map_err(bind_error)?;
+    listener.set_nonblocking(true).map_err(bind_error)?;
+    let listener = tokio::net::TcpListener::from_std(listener).map_err(bind_error)?;
+
+    info!("Starting gRPC server on {}:{}", host, grpc.port);
+    tokio::spawn(async move {
+        if let Err(e) = Server::builder().serve_with_incoming(service, TcpListenerStream::new(listener)).await {
+            error!("gRPC server failed: {}", e);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,470 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::adapters::authentication::bearer_token;
+use crate::api::adapters::jwt::JwtTokens;
+use crate::api::adapters::ldap::LdapDirectory;
+use crate::api::adapters::session::{request_cookie, Sessions};
+use crate::api::handlers::common::filter::filter_value;
+use crate::api::handlers::common::password::verify_password;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::config::specific::auth_config::UsersConfig;
+use crate::config::specific::tenancy_config::TenancyConfig;
+use crate::config::specific::entity_config::{Entity, Field};
+use crate::data::datasource::base::{DataSource, Filter, FilterOperator, ListQuery};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Path the sign in endpoints are served under, like an entity of its own
+pub const AUTH_PATH: &str = "auth";
+/// Users entity when `users_entity` is not set
+pub const DEFAULT_USERS_ENTITY: &str = "users";
+const DEFAULT_USERNAME_FIELD: &str = "username";
+const DEFAULT_PASSWORD_FIELD: &str = "password";
+
+#[derive(Deserialize)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+struct RevokeRequest {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct LogoutRequest {
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+/// How signed in users are given their credentials
+#[derive(Clone)]
+pub enum SignIn {
+    /// Bearer tokens of the JWT authentication
+    Jwt(JwtTokens),
+    /// Session cookie of the session authentication
+    Session(Sessions),
+}
+
+impl SignIn {
+    /// Users signing in, with their fields
+    pub fn users(&self) -> &UsersConfig {
+        match self {
+            SignIn::Jwt(tokens) => &tokens.config().users,
+            SignIn::Session(sessions) => &sessions.config().users,
+        }
+    }
+}
+
+/// A user signing in: the subject of its credentials, its roles and its other claims
+struct Account {
+    subject: String,
+    roles: Vec<String>,
+    claims: Map<String, Value>,
+}
+
+/// Users entity of the authentication, signing its users in with the credentials it stores
+pub struct Users<T> {
+    datasource: Box<dyn DataSource<T>>,
+    entity: String,
+    key_field: Option<String>,
+    username_field: Field,
+    password_field: String,
+    roles_field: Option<String>,
+    soft_delete: SoftDelete,
+    /// Tenancy of the API, whose tenant the users carry from their tenant field
+    tenancy: Option<TenancyConfig>,
+}
+
+impl<T: ApiEntity> Users<T> {
+    /// User with the given user name, if any
+    fn find(&self, username: &str) -> Result<Option<Value>> {
+        let mut filters = vec![Filter {
+            field: self.username_field.name.clone(),
+            operator: FilterOperator::Eq,
+            value: filter_value(&self.username_field, FilterOperator::Eq, username)?,
+        }];
+        self.soft_delete.exclude_deleted(&mut filters);
+        let query = ListQuery { limit: Some(1), filters, ..ListQuery::default() };
+        let page = self.datasource.get_page(&query, Some(&self.entity)).map_err(handle_datasource_error)?;
+        page.items.into_iter().next().map(serde_json::to_value).transpose().map_err(Into::into)
+    }
+
+    /// User a token was issued to, if it still exists
+    fn get(&self, subject: &str) -> Result<Option<Value>> {
+        let user = self.datasource.get_by_id(subject, Some(&self.entity)).map_err(handle_datasource_error)?;
+        self.soft_delete.visible(user)?.map(serde_json::to_value).transpose().map_err(Into::into)
+    }
+
+    /// Whether a password matches the hash stored for a user
+    fn password_matches(&self, user: &Value, password: &str) -> bool {
+        user.get(&self.password_field).and_then(Value::as_str).is_some_and(|hash| verify_password(password, hash))
+    }
+
+    /// Subject of the tokens of a user: its key, so owner fields can reference it
+    fn subject(&self, user: &Value) -> String {
+        match self.key_field.as_ref().and_then(|field| user.get(field)) {
+            Some(Value::String(text)) => text.clone(),
+            Some(other) => other.to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// Roles of a user, from a list or a comma separated string
+    fn roles(&self, user: &Value) -> Vec<String> {
+        match self.roles_field.as_ref().and_then(|field| user.get(field)) {
+            Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            Some(Value::String(text)) => text.split(',').map(str::trim).filter(|role| !role.is_empty()).map(str::to_string).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Other claims of a user: its tenant under the claim of the tenancy, nested for dotted
+    /// claims like `org.id`, so the tenancy can tell which tenant the user belongs to
+    fn claims(&self, user: &Value) -> Map<String, Value> {
+        let mut claims = Map::new();
+        let Some(tenancy) = &self.tenancy else {
+            return claims;
+        };
+        let Some(tenant) = user.get(tenancy.field()).filter(|tenant| tenant.is_string() || tenant.is_number()) else {
+            return claims;
+        };
+        let (claim, nested) = tenancy.claim().split_once('.').map_or((tenancy.claim(), None), |(claim, nested)| (claim, Some(nested)));
+        let value = nested.map_or(tenant.clone(), |nested| nested.rsplit('.').fold(tenant.clone(), |value, key| json!({ key: value })));
+        claims.insert(claim.to_string(), value);
+        claims
+    }
+
+    /// Account of a user of the entity
+    fn account(&self, user: &Value) -> Account {
+        Account { subject: self.subject(user), roles: self.roles(user), claims: self.claims(user) }
+    }
+}
+
+/// Where the users signing in are verified
+pub enum Accounts<T> {
+    /// The users entity, holding their credentials
+    Entity(Users<T>),
+    /// An LDAP / Active Directory, which users bind to
+    Directory(LdapDirectory),
+}
+
+impl<T: ApiEntity> Accounts<T> {
+    /// Users of the users entity of the authentication, None when it lacks their fields. With a
+    /// tenancy, they carry the tenant of its tenant field.
+    pub fn users_entity(datasource: Box<dyn DataSource<T>>, users: &Entity, config: &UsersConfig, tenancy: Option<&TenancyConfig>) -> Option<Self> {
+        let username_field = config.username_field.as_deref().unwrap_or(DEFAULT_USERNAME_FIELD);
+        let password_field = config.password_field.as_deref().unwrap_or(DEFAULT_PASSWORD_FIELD);
+        let Some(username_field) = users.fields.iter().find(|field| field.name == username_field) else {
+            warn!("Skipping the sign in endpoints: {} has no field {}", users.name, username_field);
+            return None;
+        };
+        match users.fields.iter().find(|field| field.name == password_field) {
+            None => {
+                warn!("Skipping the sign in endpoints: {} has no field {}", users.name, password_field);
+                return None;
+            }
+            // Stored passwords are verified against their hash, so plain ones never match
+            Some(field) if !field.password => {
+                warn!("Field {} of {} is not a password field: its values are not hashed and sign ins will fail", password_field, users.name);
+            }
+            Some(_) => {}
+        }
+        Some(Accounts::Entity(Users {
+            datasource,
+            entity: users.name.clone(),
+            key_field: users.key_field().map(|field| field.name.clone()),
+            username_field: username_field.clone(),
+            password_field: password_field.to_string(),
+            roles_field: config.roles_field.clone(),
+            soft_delete: SoftDelete::new(users),
+            tenancy: tenancy.cloned(),
+        }))
+    }
+
+    /// Account of the user with the given credentials, None when they do not match
+    fn sign_in(&self, username: &str, password: &str) -> Result<Option<Account>> {
+        match self {
+            Accounts::Entity(users) => Ok(users.find(username)?
+                .filter(|user| users.password_matches(user, password))
+                .map(|user| users.account(&user))),
+            // Users of the directory are identified by their user name
+            Accounts::Directory(directory) => Ok(directory.authenticate(username, password)?
+                .map(|roles| Account { subject: username.to_string(), roles, claims: Map::new() })),
+        }
+    }
+
+    /// Account of the user a token was issued to, None when the user no longer exists
+    fn account_of(&self, subject: &str) -> Result<Option<Account>> {
+        match self {
+            Accounts::Entity(users) => Ok(users.get(subject)?.map(|user| users.account(&user))),
+            Accounts::Directory(directory) => Ok(directory.roles_of(subject)?
+                .map(|roles| Account { subject: subject.to_string(), roles, claims: Map::new() })),
+        }
+    }
+
+    /// Whether the roles of the users can be read again when they refresh their tokens
+    fn can_refresh(&self) -> bool {
+        match self {
+            Accounts::Entity(_) => true,
+            Accounts::Directory(directory) => directory.can_look_up(),
+        }
+    }
+}
+
+/// Body of a request to the sign in endpoints
+fn json_body<B: for<'de> Deserialize<'de>>(request: &ApiRequest) -> Result<B> {
+    let body = request.body.as_deref().filter(|body| !body.is_empty())
+        .ok_or_else(|| RusterApiError::BadRequest("Request body is required".to_string()))?;
+    serde_json::from_str(body).map_err(|e| RusterApiError::BadRequest(format!("Invalid request format: {}", e)))
+}
+
+/// Response carrying the tokens of a user
+fn tokens_response<T: ApiEntity>(tokens: &JwtTokens, account: &Account) -> Result<ApiResponse<T>> {
+    Ok(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(serde_json::from_value(tokens.issue(&account.subject, &account.roles, &account.claims)?)?)),
+    })
+}
+
+/// Response opening the session of a user, setting its cookie
+fn session_response<T: ApiEntity>(sessions: &Sessions, account: &Account) -> Result<ApiResponse<T>> {
+    let (cookie, body) = sessions.open(&account.subject, &account.roles, &account.claims)?;
+    let mut headers = default_headers();
+    headers.insert("Set-Cookie".to_string(), cookie);
+    Ok(ApiResponse {
+        status: 200,
+        headers,
+        body: Some(ApiResponseBody::Json(serde_json::from_value(body)?)),
+    })
+}
+
+/// Registers the sign in endpoints of the authentication over its accounts: `POST auth/login`
+/// exchanges a user name and password for tokens, or for a session cookie, and `POST auth/logout`
+/// revokes the tokens of the caller or closes the session. With JWT, `POST auth/revoke` revokes
+/// any token, like a compromised one, and `POST auth/refresh` exchanges a refresh token for new
+/// tokens when `refresh_token_enabled` is set.
+/// Users of the users entity are identified by their key and carry the roles of their roles field
+/// and the tenant of their tenant field; users of a directory are identified by their user name
+/// and carry the roles of their groups.
+pub fn register_auth_endpoints<T>(
+    accounts: Accounts<T>,
+    sign_in: SignIn,
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+)
+where
+    T: ApiEntity,
+{
+    let users = Arc::new(accounts);
+
+    let (login_users, login_sign_in) = (users.clone(), sign_in.clone());
+    let login_handler: EndpointHandler<T> = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let credentials: Credentials = json_body(&request)?;
+        let account = login_users.sign_in(&credentials.username, &credentials.password)?
+            .ok_or_else(|| RusterApiError::AuthError("Invalid user name or password".to_string()))?;
+        match &login_sign_in {
+            SignIn::Jwt(tokens) => tokens_response(tokens, &account),
+            SignIn::Session(sessions) => session_response(sessions, &account),
+        }
+    });
+
+    let logout_sign_in = sign_in.clone();
+    let logout_handler: EndpointHandler<T> = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let mut headers = default_headers();
+        match &logout_sign_in {
+            SignIn::Jwt(tokens) => {
+                let logout: LogoutRequest = match request.body.as_deref() {
+                    Some(body) if !body.trim().is_empty() => json_body(&request)?,
+                    _ => LogoutRequest { refresh_token: None },
+                };
+                if let Some(refresh_token) = &logout.refresh_token {
+                    tokens.revoke(refresh_token)?;
+                }
+                // The access token the request authenticated with is revoked as well
+                if let Some(access_token) = bearer_token(&request).filter(|_| request.auth.is_some()) {
+                    tokens.revoke(access_token)?;
+                }
+            }
+            SignIn::Session(sessions) => {
+                let cookie = sessions.close(request_cookie(&request, sessions.cookie_name()));
+                headers.insert("Set-Cookie".to_string(), cookie);
+            }
+        }
+        Ok(ApiResponse {
+            status: 204,
+            headers,
+            body: None,
+        })
+    });
+
+    let mut handlers = vec![("login", login_handler), ("logout", logout_handler)];
+    if let SignIn::Jwt(tokens) = &sign_in {
+        let revoke_tokens = tokens.clone();
+        let revoke_handler: EndpointHandler<T> = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+            let revoke: RevokeRequest = json_body(&request)?;
+            revoke_tokens.revoke(&revoke.token)?;
+            Ok(ApiResponse {
+                status: 204,
+                headers: default_headers(),
+                body: None,
+            })
+        });
+        handlers.push(("revoke", revoke_handler));
+    }
+    match sign_in {
+        SignIn::Jwt(tokens) if tokens.config().refresh_token_enabled && !users.can_refresh() => {
+            warn!("Skipping the refresh endpoint: the directory has no service account to read the users with");
+        }
+        SignIn::Jwt(tokens) if tokens.config().refresh_token_enabled => {
+            let refresh_handler: EndpointHandler<T> = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+                let refresh: RefreshRequest = json_body(&request)?;
+                let subject = tokens.redeem(&refresh.refresh_token)?;
+                // The roles and claims are read again, so refreshed tokens follow the changes of the user
+                let account = users.account_of(&subject)?
+                    .ok_or_else(|| RusterApiError::AuthError("The user of the refresh token no longer exists".to_string()))?;
+                tokens_response(&tokens, &account)
+            });
+            handlers.push(("refresh", refresh_handler));
+        }
+        _ => {}
+    }
+    for (name, handler) in handlers {
+        let base_path = format!("{}/{}", AUTH_PATH, name);
+        // Also register with a full API path to handle both cases
+        for endpoint_key in [format!("POST:{}", base_path), format!("POST:api/{}", base_path)] {
+            if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+                warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::adapters::api_adapter::{ApiAdapter, ApiRequest, ApiResponseBody};
+    use crate::api::adapters::serving::{response_items, ServedApi};
+    use crate::api::handlers::common::password::hash_password;
+    use crate::config::configuration::Config;
+    use crate::config::shared::{EntityBasic, FieldBasic};
+    use crate::config::specific::auth_config::{AuthConfig, AuthType, JWTConfig, UsersConfig};
+    use crate::config::specific::entity_config::{DataType, Entity, HttpMethod};
+    use crate::config::specific::tenancy_config::{TenancyConfig, TenantSource};
+    use crate::data::datasource::base::DataSource;
+    use crate::data::datasource::memory::in_memory::InMemoryDatasource;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    fn entity(name: &str, fields: &[&str]) -> Entity {
+        let mut entity = EntityBasic {
+            name: name.to_string(),
+            table_name: None,
+            fields: fields.iter()
+                .map(|name| FieldBasic { name: name.to_string(), data_type: DataType::String, required: false })
+                .collect(),
+            authentication: true,
+        }.to_entity();
+        for field in entity.fields.iter_mut().filter(|field| field.name == "password") {
+            field.password = true;
+        }
+        entity
+    }
+
+    fn config(source: TenantSource) -> Config {
+        let mut config = Config::new();
+        config.entities_advanced = vec![
+            entity("users", &["id", "username", "password", "tenant_id"]),
+            entity("invoices", &["id", "tenant_id", "amount"]),
+        ];
+        config.auth = Some(AuthConfig {
+            auth_type: AuthType::JWT,
+            jwt_config: Some(JWTConfig {
+                secret: "a secret long enough to sign the tokens".to_string(),
+                expiration_hours: 1,
+                issuer: None,
+                refresh_token_enabled: false,
+                refresh_expiration_hours: None,
+                revocation_entity: None,
+                users: UsersConfig::default(),
+            }),
+            oauth_config: None,
+            api_key_config: None,
+            session_config: None,
+            ldap_config: None,
+        });
+        config.tenancy = Some(TenancyConfig { source, header_name: None, domain: None, claim: None, field: None });
+        config
+    }
+
+    fn served(config: Config) -> ServedApi<Value> {
+        let datasource = InMemoryDatasource::new();
+        datasource.configure_entities(&config.all_entities());
+        datasource.seed("users", vec![
+            json!({ "id": "1", "username": "alice", "password": hash_password("secret").unwrap(), "tenant_id": "acme" }),
+        ]).unwrap();
+        datasource.seed("invoices", vec![
+            json!({ "id": "1", "tenant_id": "acme", "amount": "10" }),
+            json!({ "id": "2", "tenant_id": "globex", "amount": "20" }),
+        ]).unwrap();
+        let datasources: HashMap<String, Box<dyn DataSource<Value>>> = ["users", "invoices"].into_iter()
+            .map(|name| (name.to_string(), Box::new(datasource.clone()) as Box<dyn DataSource<Value>>))
+            .collect();
+        ServedApi::new(ApiAdapter::new(config, datasources)).unwrap()
+    }
+
+    fn request(method: HttpMethod, path: &str, headers: &[(&str, &str)], body: Option<Value>) -> ApiRequest {
+        ApiRequest {
+            method,
+            path: path.to_string(),
+            params: HashMap::new(),
+            query: HashMap::new(),
+            headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            body: body.map(|body| body.to_string()),
+            request_id: "test".to_string(),
+            auth: None,
+            tenant: None,
+        }
+    }
+
+    fn sign_in(served: &ServedApi<Value>) -> String {
+        let credentials = json!({ "username": "alice", "password": "secret" });
+        let response = served.api_adapter.handle_request(request(HttpMethod::POST, "auth/login", &[], Some(credentials))).unwrap();
+        match response.body {
+            Some(ApiResponseBody::Json(body)) => body["access_token"].as_str().unwrap().to_string(),
+            _ => panic!("the sign in answered no tokens"),
+        }
+    }
+
+    fn invoices(served: &ServedApi<Value>, headers: &[(&str, &str)]) -> crate::error::Result<Vec<Value>> {
+        let response = served.api_adapter.handle_request(request(HttpMethod::GET, "invoices", headers, None))?;
+        Ok(response_items(response.body))
+    }
+
+    #[test]
+    fn signed_in_users_read_the_rows_of_their_tenant_claim() {
+        let served = served(config(TenantSource::Claim));
+        let authorization = format!("Bearer {}", sign_in(&served));
+        let items = invoices(&served, &[("Authorization", &authorization)]).unwrap();
+        assert_eq!(items, vec![json!({ "id": "1", "tenant_id": "acme", "amount": "10" })]);
+    }
+
+    #[test]
+    fn signed_in_users_read_the_tenant_they_belong_to_only() {
+        let served = served(config(TenantSource::Header));
+        let authorization = format!("Bearer {}", sign_in(&served));
+        let own = invoices(&served, &[("Authorization", &authorization), ("X-Tenant-Id", "acme")]).unwrap();
+        assert_eq!(own.len(), 1);
+        assert!(invoices(&served, &[("Authorization", &authorization), ("X-Tenant-Id", "globex")]).is_err());
+    }
+}
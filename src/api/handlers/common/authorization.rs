@@ -0,0 +1,102 @@
+use crate::api::adapters::api_adapter::{ApiRequest, AuthContext, EndpointHandler};
+use crate::api::handlers::common::policy::Policy;
+use crate::config::specific::entity_config::Entity;
+use crate::error::{Result, RusterApiError};
+use std::sync::Arc;
+
+/// Actions the permissions of an entity grant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrudAction {
+    Create,
+    Read,
+    Update,
+    Delete,
+}
+
+impl CrudAction {
+    /// Every action, as granted by the `*` action
+    pub const ALL: [CrudAction; 4] = [CrudAction::Create, CrudAction::Read, CrudAction::Update, CrudAction::Delete];
+
+    /// Name of the action in the `action` of the permissions, like `read`
+    pub fn name(self) -> &'static str {
+        match self {
+            CrudAction::Create => "create",
+            CrudAction::Read => "read",
+            CrudAction::Update => "update",
+            CrudAction::Delete => "delete",
+        }
+    }
+
+    /// Action named in a permission, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Action of the endpoint registered with a key, like `GET:users/:id`. Reads are GET requests,
+    /// and PUT and PATCH update. POST and DELETE below an entity, like `users/:id/restore` or the
+    /// links of its relationships, change that entity, so they update it as well.
+    pub fn of_endpoint(key: &str) -> Self {
+        let (method, pattern) = key.split_once(':').unwrap_or((key, ""));
+        let depth = pattern.strip_prefix("api/").unwrap_or(pattern).split('/').filter(|s| !s.is_empty()).count();
+        match method {
+            "GET" | "HEAD" => CrudAction::Read,
+            "PUT" | "PATCH" => CrudAction::Update,
+            "POST" if depth <= 1 => CrudAction::Create,
+            "DELETE" if depth <= 2 => CrudAction::Delete,
+            _ => CrudAction::Update,
+        }
+    }
+}
+
+/// Role-based authorization of an entity, from its `authorization`. When active, the callers need
+/// one of its roles, and the policy of its permissions, if any, must permit the action to their roles.
+#[derive(Clone, Default)]
+pub struct EntityAuthorization {
+    entity: String,
+    active: bool,
+    roles: Vec<String>,
+    policy: Policy,
+}
+
+impl EntityAuthorization {
+    pub fn new(entity: &Entity) -> Self {
+        Self {
+            entity: entity.name.clone(),
+            active: entity.authorization.active,
+            roles: entity.authorization.roles.iter().map(|role| role.name.clone()).collect(),
+            policy: Policy::new(&entity.name, &entity.authorization.permissions),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Checks that a caller may run an action on the entity: anonymous callers are answered with
+    /// 401, and callers lacking the roles or permissions with 403
+    pub fn check(&self, auth: Option<&AuthContext>, action: CrudAction) -> Result<()> {
+        if !self.active {
+            return Ok(());
+        }
+        let Some(auth) = auth else {
+            return Err(RusterApiError::AuthError(format!("Authentication required to {} {}", action.name(), self.entity)));
+        };
+        if !self.roles.is_empty() && !auth.roles.iter().any(|role| self.roles.contains(role)) {
+            return Err(RusterApiError::Forbidden(format!("No role of the caller may access {}", self.entity)));
+        }
+        if !self.policy.permits(&auth.roles, action) {
+            return Err(RusterApiError::Forbidden(format!("The caller may not {} {}", action.name(), self.entity)));
+        }
+        Ok(())
+    }
+
+    /// Handler of an endpoint checking the action of its key before running it
+    pub fn guard<T: 'static>(&self, key: &str, handler: EndpointHandler<T>) -> EndpointHandler<T> {
+        let authorization = self.clone();
+        let action = CrudAction::of_endpoint(key);
+        Arc::new(move |request: ApiRequest| {
+            authorization.check(request.auth.as_ref(), action)?;
+            handler(request)
+        })
+    }
+}
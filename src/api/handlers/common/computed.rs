@@ -0,0 +1,322 @@
+use crate::config::specific::entity_config::Entity;
+use crate::error::Result;
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+/// Arithmetic operators of computed field expressions
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+/// A computed field expression: field names, number and string literals, `+ - * / %` and parentheses.
+/// `+` joins the values when either one is a string; other operations on a null or non-numeric value give null.
+#[derive(Debug, Clone)]
+enum Expression {
+    Literal(Value),
+    Field(String),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Name(String),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+fn tokenize(source: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '+' | '-' | '*' | '/' | '%' => {
+                chars.next();
+                tokens.push(Token::Operator(match c {
+                    '+' => Operator::Add,
+                    '-' => Operator::Subtract,
+                    '*' => Operator::Multiply,
+                    '/' => Operator::Divide,
+                    _ => Operator::Remainder,
+                }));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(other) => text.push(other),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(&digit) = chars.peek().filter(|d| d.is_ascii_digit() || **d == '.') {
+                    number.push(digit);
+                    chars.next();
+                }
+                let value = number.parse().map_err(|_| format!("invalid number '{}'", number))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut name = String::new();
+                while let Some(&letter) = chars.peek().filter(|l| l.is_alphanumeric() || **l == '_') {
+                    name.push(letter);
+                    chars.next();
+                }
+                tokens.push(Token::Name(name));
+            }
+            other => return Err(format!("unexpected character '{}'", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the tokens of an expression
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_operator(&self, operators: &[Operator]) -> Option<Operator> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) if operators.contains(operator) => Some(*operator),
+            _ => None,
+        }
+    }
+
+    fn binary(&mut self, operators: &[Operator], operand: fn(&mut Self) -> std::result::Result<Expression, String>) -> std::result::Result<Expression, String> {
+        let mut left = operand(self)?;
+        while let Some(operator) = self.peek_operator(operators) {
+            self.position += 1;
+            left = Expression::Binary(operator, Box::new(left), Box::new(operand(self)?));
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> std::result::Result<Expression, String> {
+        self.binary(&[Operator::Add, Operator::Subtract], Self::product)
+    }
+
+    fn product(&mut self) -> std::result::Result<Expression, String> {
+        self.binary(&[Operator::Multiply, Operator::Divide, Operator::Remainder], Self::operand)
+    }
+
+    fn operand(&mut self) -> std::result::Result<Expression, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expression::Literal(number_value(number))),
+            Some(Token::Text(text)) => Ok(Expression::Literal(Value::String(text))),
+            Some(Token::Name(name)) if name == "null" => Ok(Expression::Literal(Value::Null)),
+            Some(Token::Name(name)) => Ok(Expression::Field(name)),
+            Some(Token::Operator(Operator::Subtract)) => Ok(Expression::Negate(Box::new(self.operand()?))),
+            Some(Token::Open) => {
+                let inner = self.sum()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err("missing closing parenthesis".to_string()),
+                }
+            }
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+impl Expression {
+    fn parse(source: &str) -> std::result::Result<Self, String> {
+        let mut parser = Parser { tokens: tokenize(source)?, position: 0 };
+        let expression = parser.sum()?;
+        match parser.next() {
+            None => Ok(expression),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    /// Names of the fields the expression reads
+    fn fields<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expression::Literal(_) => {}
+            Expression::Field(name) => names.push(name),
+            Expression::Negate(inner) => inner.fields(names),
+            Expression::Binary(_, left, right) => {
+                left.fields(names);
+                right.fields(names);
+            }
+        }
+    }
+
+    fn evaluate(&self, object: &Map<String, Value>) -> Value {
+        match self {
+            Expression::Literal(value) => value.clone(),
+            Expression::Field(name) => object.get(name).cloned().unwrap_or(Value::Null),
+            Expression::Negate(inner) => match inner.evaluate(object).as_f64() {
+                Some(number) => number_value(-number),
+                None => Value::Null,
+            },
+            Expression::Binary(operator, left, right) => {
+                let (left, right) = (left.evaluate(object), right.evaluate(object));
+                if *operator == Operator::Add && (left.is_string() || right.is_string()) {
+                    return Value::String(format!("{}{}", text_of(&left), text_of(&right)));
+                }
+                let (Some(left), Some(right)) = (left.as_f64(), right.as_f64()) else {
+                    return Value::Null;
+                };
+                let result = match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide if right == 0.0 => return Value::Null,
+                    Operator::Divide => left / right,
+                    Operator::Remainder if right == 0.0 => return Value::Null,
+                    Operator::Remainder => left % right,
+                };
+                number_value(result)
+            }
+        }
+    }
+}
+
+/// JSON number of a result, written as an integer when it has no fraction
+fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::from(number as i64)
+    } else {
+        Value::from(number)
+    }
+}
+
+/// Text joined by `+`, where null is empty
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Fields the server controls: read-only fields clients cannot write and computed fields,
+/// derived from the other fields of every response
+#[derive(Default)]
+pub struct ServerFields {
+    read_only: Vec<String>,
+    computed: Vec<(String, Expression)>,
+}
+
+impl ServerFields {
+    /// Parses the computed fields of an entity. Invalid expressions are reported and their fields skipped.
+    pub fn new(entity: &Entity) -> Self {
+        let read_only = entity.fields.iter()
+            .filter(|field| field.read_only)
+            .map(|field| field.name.clone())
+            .collect();
+        let computed = entity.fields.iter()
+            .filter_map(|field| {
+                let source = field.computed.as_deref()?;
+                let parsed = Expression::parse(source).and_then(|expression| {
+                    let mut names = Vec::new();
+                    expression.fields(&mut names);
+                    match names.into_iter().find(|name| !entity.fields.iter().any(|field| field.name == *name)) {
+                        Some(unknown) => Err(format!("unknown field '{}'", unknown)),
+                        None => Ok(expression),
+                    }
+                });
+                match parsed {
+                    Ok(expression) => Some((field.name.clone(), expression)),
+                    Err(e) => {
+                        warn!("Skipping computed field {}.{}: {}", entity.name, field.name, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { read_only, computed }
+    }
+
+    /// Whether clients cannot write a field
+    pub fn is_protected(&self, field: &str) -> bool {
+        self.read_only.iter().any(|name| name == field) || self.computed.iter().any(|(name, _)| name == field)
+    }
+
+    /// Whether any of the selected fields is computed, so every field must be loaded to compute it
+    pub fn computes_any(&self, selected: &[String]) -> bool {
+        self.computed.iter().any(|(name, _)| selected.contains(name))
+    }
+
+    /// Removes the read-only and computed fields a client sent in a write request
+    pub fn strip(&self, object: &mut Map<String, Value>) {
+        object.retain(|name, _| !self.is_protected(name));
+    }
+
+    /// Copies the read-only values of the stored entity into the entity replacing it
+    pub fn with_stored<T: ApiEntity>(&self, item: T, stored: &T) -> Result<T> {
+        if self.read_only.is_empty() {
+            return Ok(item);
+        }
+        let (Value::Object(mut object), Value::Object(stored)) = (serde_json::to_value(&item)?, serde_json::to_value(stored)?) else {
+            return Ok(item);
+        };
+        for name in &self.read_only {
+            if let Some(value) = stored.get(name) {
+                object.insert(name.clone(), value.clone());
+            }
+        }
+        Ok(serde_json::from_value(Value::Object(object))?)
+    }
+
+    /// Adds the computed fields to an entity, and null for the read-only fields it lacks
+    pub fn complete<T: ApiEntity>(&self, item: T) -> Result<T> {
+        if self.read_only.is_empty() && self.computed.is_empty() {
+            return Ok(item);
+        }
+        match serde_json::to_value(&item)? {
+            Value::Object(mut object) => {
+                for name in &self.read_only {
+                    object.entry(name.clone()).or_insert(Value::Null);
+                }
+                // Computed fields may read the fields computed before them
+                for (name, expression) in &self.computed {
+                    let value = expression.evaluate(&object);
+                    object.insert(name.clone(), value);
+                }
+                Ok(serde_json::from_value(Value::Object(object))?)
+            }
+            _ => Ok(item),
+        }
+    }
+
+    /// Adds the computed fields to every entity of a list
+    pub fn complete_all<T: ApiEntity>(&self, items: Vec<T>) -> Result<Vec<T>> {
+        items.into_iter().map(|item| self.complete(item)).collect()
+    }
+}
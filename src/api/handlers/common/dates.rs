@@ -0,0 +1,108 @@
+/// Names of the days of the week, starting on Thursday like the Unix epoch
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Civil date of a number of days since the Unix epoch, following Howard Hinnant's civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Number of days since the Unix epoch of a civil date, following Howard Hinnant's days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Formats seconds since the Unix epoch as an ISO 8601 UTC date and time, like `2024-05-01T12:30:00Z`
+pub fn utc_timestamp(seconds: u64) -> String {
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3_600, time % 3_600 / 60, time % 60)
+}
+
+/// Formats seconds since the Unix epoch as an HTTP date, like `Wed, 01 May 2024 12:30:00 GMT`
+pub fn http_date(seconds: u64) -> String {
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(days % 7) as usize], day, MONTHS[(month - 1) as usize], year, time / 3_600, time % 3_600 / 60, time % 60
+    )
+}
+
+/// Seconds since the Unix epoch of a time of day, like `12:30:00`
+fn parse_time(time: &str) -> Option<u64> {
+    let mut parts = time.splitn(3, ':').map(|part| part.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next().unwrap_or(Some(0))?);
+    (hours < 24 && minutes < 60 && seconds < 61).then_some(hours * 3_600 + minutes * 60 + seconds)
+}
+
+/// Seconds since the Unix epoch of a civil date and time of day
+fn epoch_seconds(year: i64, month: i64, day: i64, time: u64) -> Option<u64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    Some(days * 86_400 + time)
+}
+
+/// Parses an HTTP date in the IMF-fixdate format, like `Wed, 01 May 2024 12:30:00 GMT`
+pub fn parse_http_date(date: &str) -> Option<u64> {
+    let (_, date) = date.trim().split_once(", ")?;
+    let mut parts = date.split_whitespace();
+    let day = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|name| *name == month)? as i64 + 1;
+    let year = parts.next()?.parse().ok()?;
+    let time = parse_time(parts.next()?)?;
+    (parts.next() == Some("GMT")).then_some(())?;
+    epoch_seconds(year, month, day, time)
+}
+
+/// Parses an ISO 8601 date, or date and time with an optional fraction and offset, like
+/// `2024-05-01`, `2024-05-01T12:30:00Z` or `2024-05-01 14:30:00.5+02:00`
+pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let timestamp = timestamp.trim();
+    let (date, time) = match timestamp.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (timestamp, None),
+    };
+    let mut date_parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+
+    let (seconds, offset) = match time {
+        None => (0, 0),
+        Some(time) => {
+            let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+                Some(index) => time.split_at(index),
+                None => (time, ""),
+            };
+            let offset = match offset.split_at_checked(1) {
+                None | Some(("Z" | "z", "")) => 0,
+                Some((sign, offset)) => {
+                    // Offsets are written `+02:00`, `+0200` or `+02`
+                    let (hours, minutes) = offset.split_once(':').unwrap_or_else(|| offset.split_at(offset.len().min(2)));
+                    let minutes = if minutes.is_empty() { 0 } else { minutes.parse::<i64>().ok()? };
+                    let offset = hours.parse::<i64>().ok()? * 3_600 + minutes * 60;
+                    if sign == "-" { -offset } else { offset }
+                }
+            };
+            let whole = time.split_once('.').map_or(time, |(whole, _)| whole);
+            (parse_time(whole)?, offset)
+        }
+    };
+    let seconds = epoch_seconds(year, month, day, seconds)? as i64 - offset;
+    u64::try_from(seconds).ok()
+}
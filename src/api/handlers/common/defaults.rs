@@ -0,0 +1,51 @@
+use crate::config::specific::entity_config::{DataType, Entity, Field};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+/// Converts the default value of a field to its data type
+fn typed_default(field: &Field, raw: &str) -> Result<Value, String> {
+    let invalid = |kind: &str| format!("default value '{}' of field '{}' is not {}", raw, field.name, kind);
+    match field.data_type {
+        DataType::Integer => raw.trim().parse::<i64>().map(Value::from).map_err(|_| invalid("an integer")),
+        DataType::Float => raw.trim().parse::<f64>().map(Value::from).map_err(|_| invalid("a number")),
+        DataType::Boolean => raw.trim().parse::<bool>().map(Value::from).map_err(|_| invalid("true or false")),
+        DataType::JSON => serde_json::from_str(raw).map_err(|_| invalid("valid JSON")),
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
+
+/// Fills in the `default_value` of the fields a create request omits
+#[derive(Default)]
+pub struct EntityDefaults {
+    defaults: Vec<(String, Value)>,
+}
+
+impl EntityDefaults {
+    /// Converts the default values of an entity to the types of their fields. Invalid defaults
+    /// are reported and skipped.
+    pub fn new(entity: &Entity) -> Self {
+        let defaults = entity.fields.iter()
+            .filter(|field| !field.generated && field.is_stored())
+            .filter_map(|field| {
+                let raw = field.default_value.as_deref()?;
+                match typed_default(field, raw) {
+                    Ok(value) => Some((field.name.clone(), value)),
+                    Err(e) => {
+                        warn!("Skipping a default value of {}: {}", entity.name, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { defaults }
+    }
+
+    /// Adds the default of every field missing from an object. Fields sent as null keep their null.
+    pub fn apply(&self, object: &mut Map<String, Value>) {
+        for (name, value) in &self.defaults {
+            if !object.contains_key(name) {
+                object.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
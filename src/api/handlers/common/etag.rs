@@ -0,0 +1,61 @@
+use crate::api::adapters::api_adapter::ApiRequest;
+use crate::data::datasource::relational::base::value_key;
+use crate::error::{Result, RusterApiError};
+use serde::Serialize;
+
+/// Response header holding the entity tag
+pub const ETAG_HEADER: &str = "ETag";
+/// Request header holding the tags the client already has, lowercase as captured
+pub const IF_NONE_MATCH_HEADER: &str = "if-none-match";
+/// Request header holding the tags the client expects the entity to have, lowercase as captured
+pub const IF_MATCH_HEADER: &str = "if-match";
+
+/// Hashes bytes with 64-bit FNV-1a, which is stable across builds unlike the std hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Computes the entity tag of an entity: the value of its version field when it has one,
+/// otherwise a hash of its JSON form. Weak tags describe partial representations, like sparse
+/// fieldsets, and never satisfy `If-Match`.
+pub fn entity_tag<T: Serialize>(item: &T, version_field: Option<&str>, weak: bool) -> Result<String> {
+    let value = serde_json::to_value(item)?;
+    let version = version_field
+        .and_then(|field| value.get(field))
+        .filter(|version| !version.is_null());
+    let tag = match version {
+        Some(version) => format!("\"{}\"", value_key(version).replace('"', "")),
+        None => format!("\"{:016x}\"", fnv1a(value.to_string().as_bytes())),
+    };
+    Ok(if weak { format!("W/{}", tag) } else { tag })
+}
+
+/// Splits a list of entity tags, like `"a", W/"b"`
+fn listed_tags(header: &str) -> impl Iterator<Item = &str> {
+    header.split(',').map(str::trim).filter(|tag| !tag.is_empty())
+}
+
+/// Whether the `If-None-Match` header lists the tag, comparing weakly as RFC 9110 requires
+pub fn none_match_hit(request: &ApiRequest, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim_start_matches("W/").to_string();
+    request.header(IF_NONE_MATCH_HEADER).is_some_and(|header| {
+        listed_tags(header).any(|tag| tag == "*" || opaque(tag) == opaque(etag))
+    })
+}
+
+/// Checks the `If-Match` header against the tag of the current entity, or None if it does not
+/// exist. Tags are compared strongly, so weak tags never match.
+pub fn check_if_match(request: &ApiRequest, current: Option<&str>) -> Result<()> {
+    let Some(header) = request.header(IF_MATCH_HEADER) else {
+        return Ok(());
+    };
+
+    let matched = current.is_some_and(|current| {
+        !current.starts_with("W/") && listed_tags(header).any(|tag| tag == "*" || tag == current)
+    });
+    if matched {
+        Ok(())
+    } else {
+        Err(RusterApiError::PreconditionFailed("The entity does not match the If-Match header".to_string()))
+    }
+}
@@ -0,0 +1,210 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, AuthContext, EndpointHandler};
+use crate::api::handlers::common::fieldset::FIELDS_PARAM;
+use crate::api::handlers::common::filter_expression::filter_expression_param;
+use crate::api::handlers::crud::list::SORT_PARAM;
+use crate::config::specific::entity_config::{Entity, Field, HttpMethod};
+use crate::data::datasource::base::{FilterExpression, FilterOperator};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// Whether a caller has one of the roles a field requires, if it requires any
+fn allowed(roles: &[String], auth: Option<&AuthContext>) -> bool {
+    roles.is_empty() || auth.is_some_and(|auth| auth.roles.iter().any(|role| roles.contains(role)))
+}
+
+/// Names of the fields an expression compares
+fn expression_fields<'a>(expression: &'a FilterExpression, names: &mut Vec<&'a str>) {
+    match expression {
+        FilterExpression::Filter(filter) => names.push(&filter.field),
+        FilterExpression::And(expressions) | FilterExpression::Or(expressions) => {
+            expressions.iter().for_each(|expression| expression_fields(expression, names));
+        }
+        FilterExpression::Not(expression) => expression_fields(expression, names),
+    }
+}
+
+/// Field-level access of an entity, from the `read_roles` and `write_roles` of its fields. Fields a
+/// caller cannot read are left out of the entities returned, and cannot filter, sort or be selected;
+/// writes of the fields a caller cannot write are answered with 403. Password fields are not read
+/// by any caller.
+#[derive(Clone, Default)]
+pub struct FieldAccess {
+    entity: String,
+    /// Stored fields, reading the filter expressions
+    fields: Vec<Field>,
+    passwords: Vec<String>,
+    read_roles: Vec<(String, Vec<String>)>,
+    write_roles: Vec<(String, Vec<String>)>,
+}
+
+impl FieldAccess {
+    pub fn new(entity: &Entity) -> Self {
+        let restricted = |roles: fn(&Field) -> &Vec<String>| entity.fields.iter()
+            .filter(|field| !roles(field).is_empty())
+            .map(|field| (field.name.clone(), roles(field).clone()))
+            .collect();
+        Self {
+            entity: entity.name.clone(),
+            fields: entity.fields.iter().filter(|field| field.is_stored()).cloned().collect(),
+            passwords: entity.fields.iter().filter(|field| field.password).map(|field| field.name.clone()).collect(),
+            read_roles: restricted(|field| &field.read_roles),
+            write_roles: restricted(|field| &field.write_roles),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.read_roles.is_empty() || !self.write_roles.is_empty() || !self.passwords.is_empty()
+    }
+
+    /// Fields a caller cannot read
+    pub fn hidden(&self, auth: Option<&AuthContext>) -> Vec<String> {
+        let restricted = self.read_roles.iter()
+            .filter(|(name, roles)| !allowed(roles, auth) && !self.passwords.contains(name))
+            .map(|(name, _)| name.clone());
+        self.passwords.iter().cloned().chain(restricted).collect()
+    }
+
+    /// Whether a caller may read a field
+    pub fn readable(&self, auth: Option<&AuthContext>, name: &str) -> bool {
+        !self.passwords.iter().any(|field| field == name)
+            && self.read_roles.iter().all(|(field, roles)| field != name || allowed(roles, auth))
+    }
+
+    /// Whether a caller may write a field
+    pub fn writable(&self, auth: Option<&AuthContext>, name: &str) -> bool {
+        self.write_roles.iter().all(|(field, roles)| field != name || allowed(roles, auth))
+    }
+
+    /// Rejects a written document holding fields the caller cannot write
+    pub fn check_write(&self, auth: Option<&AuthContext>, object: &Map<String, Value>) -> Result<()> {
+        match object.keys().find(|name| !self.writable(auth, name)) {
+            Some(name) => Err(RusterApiError::Forbidden(format!("The caller may not write field '{}' of {}", name, self.entity))),
+            None => Ok(()),
+        }
+    }
+
+    /// Rejects the requests filtering, sorting or selecting by fields the caller cannot read,
+    /// which would disclose their values
+    fn check_query(&self, request: &ApiRequest, hidden: &[String]) -> Result<()> {
+        let forbidden = |name: &str| RusterApiError::Forbidden(format!("The caller may not read field '{}' of {}", name, self.entity));
+        let is_hidden = |name: &str| hidden.iter().any(|field| field == name);
+
+        if let Some(field) = request.params.get("field").filter(|field| is_hidden(field)) {
+            return Err(forbidden(field));
+        }
+        for param in [SORT_PARAM, FIELDS_PARAM] {
+            let names = request.query.get(param).into_iter().flat_map(|value| value.split(','));
+            if let Some(name) = names.map(|name| name.trim().trim_start_matches(['-', '+'])).find(|name| is_hidden(name)) {
+                return Err(forbidden(name));
+            }
+        }
+        for name in request.query.keys() {
+            let filtered = is_hidden(name) || FilterOperator::SUFFIXED.iter()
+                .any(|operator| name.strip_suffix(operator.suffix()).is_some_and(is_hidden));
+            if filtered {
+                return Err(forbidden(name));
+            }
+        }
+        // Invalid expressions are left to the handler to report
+        if let Ok(Some(expression)) = filter_expression_param(request, &self.fields) {
+            let mut names = Vec::new();
+            expression_fields(&expression, &mut names);
+            if let Some(name) = names.into_iter().find(|name| is_hidden(name)) {
+                return Err(forbidden(name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects a request body writing fields the caller cannot write, for a single entity or a list of them
+    fn check_body(&self, request: &ApiRequest) -> Result<()> {
+        if !matches!(request.method, HttpMethod::POST | HttpMethod::PUT | HttpMethod::PATCH) {
+            return Ok(());
+        }
+        // Bodies that are not JSON are left to the handler to report
+        let Some(body) = request.body.as_deref().and_then(|body| serde_json::from_str::<Value>(body).ok()) else {
+            return Ok(());
+        };
+        let objects: Vec<&Map<String, Value>> = match &body {
+            Value::Object(object) => vec![object],
+            Value::Array(items) => items.iter().filter_map(Value::as_object).collect(),
+            _ => Vec::new(),
+        };
+        objects.into_iter().try_for_each(|object| self.check_write(request.auth.as_ref(), object))
+    }
+
+    /// Removes the fields a caller cannot read from an entity
+    pub fn strip<T: ApiEntity>(&self, hidden: &[String], item: T) -> Result<T> {
+        if hidden.is_empty() {
+            return Ok(item);
+        }
+        match serde_json::to_value(&item)? {
+            Value::Object(mut object) => {
+                object.retain(|name, _| !hidden.contains(name));
+                Ok(serde_json::from_value(Value::Object(object))?)
+            }
+            _ => Ok(item),
+        }
+    }
+
+    /// Removes the fields a caller cannot read from an entity, or from a list of them, as JSON
+    pub fn hide(&self, hidden: &[String], value: &mut Value) {
+        match value {
+            Value::Object(object) => object.retain(|name, _| !hidden.contains(name)),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.hide(hidden, item)),
+            _ => {}
+        }
+    }
+
+    /// Copies the stored values of the fields a caller cannot write into the entity replacing
+    /// it, so replacing an entity keeps them
+    pub fn with_stored<T: ApiEntity>(&self, auth: Option<&AuthContext>, item: T, stored: &T) -> Result<T> {
+        let kept: Vec<&String> = self.write_roles.iter()
+            .filter(|(_, roles)| !allowed(roles, auth))
+            .map(|(name, _)| name)
+            .collect();
+        if kept.is_empty() {
+            return Ok(item);
+        }
+        let (Value::Object(mut object), Value::Object(stored)) = (serde_json::to_value(&item)?, serde_json::to_value(stored)?) else {
+            return Ok(item);
+        };
+        for name in kept {
+            match stored.get(name) {
+                Some(value) => object.insert(name.clone(), value.clone()),
+                None => object.remove(name),
+            };
+        }
+        Ok(serde_json::from_value(Value::Object(object))?)
+    }
+
+    /// Handler of an endpoint checking the fields the request reads and writes before running it,
+    /// and removing the fields the caller cannot read from the entities it returns
+    pub fn guard<T: ApiEntity>(&self, handler: EndpointHandler<T>) -> EndpointHandler<T> {
+        let access = self.clone();
+        Arc::new(move |request: ApiRequest| {
+            let hidden = access.hidden(request.auth.as_ref());
+            access.check_query(&request, &hidden)?;
+            access.check_body(&request)?;
+            let response = handler(request)?;
+            if hidden.is_empty() {
+                return Ok(response);
+            }
+            let strip_all = |items: Vec<T>| items.into_iter().map(|item| access.strip(&hidden, item)).collect::<Result<Vec<T>>>();
+            let body = match response.body {
+                Some(ApiResponseBody::Single(item)) => Some(ApiResponseBody::Single(access.strip(&hidden, item)?)),
+                Some(ApiResponseBody::List(items)) => Some(ApiResponseBody::List(strip_all(items)?)),
+                Some(ApiResponseBody::Page { items, page, size, total, total_pages }) => {
+                    Some(ApiResponseBody::Page { items: strip_all(items)?, page, size, total, total_pages })
+                }
+                Some(ApiResponseBody::Cursor { items, limit, next_cursor }) => {
+                    Some(ApiResponseBody::Cursor { items: strip_all(items)?, limit, next_cursor })
+                }
+                body => body,
+            };
+            Ok(ApiResponse { body, ..response })
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use crate::api::adapters::api_adapter::ApiRequest;
+use crate::config::specific::entity_config::Field;
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::Value;
+
+/// Query parameter holding the comma separated fields to return
+pub const FIELDS_PARAM: &str = "fields";
+
+/// Parses the `fields` parameter, rejecting fields the entity does not have.
+/// Returns an empty list, meaning every field, when the parameter is missing.
+pub fn fields_param(request: &ApiRequest, fields: &[Field]) -> Result<Vec<String>> {
+    let Some(requested) = request.query.get(FIELDS_PARAM) else {
+        return Ok(Vec::new());
+    };
+
+    let mut names: Vec<String> = Vec::new();
+    for name in requested.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        if !fields.iter().any(|field| field.name == name) {
+            return Err(RusterApiError::BadRequest(format!("Cannot select unknown field '{}'", name)));
+        }
+        if !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Keeps only the selected fields of an entity, or every field if none is selected.
+/// Datasources may load more fields than selected, like the primary key, so they are removed here.
+pub fn project<T: ApiEntity>(item: T, fields: &[String]) -> Result<T> {
+    if fields.is_empty() {
+        return Ok(item);
+    }
+
+    match serde_json::to_value(&item)? {
+        Value::Object(mut object) => {
+            object.retain(|key, _| fields.iter().any(|field| field == key));
+            serde_json::from_value(Value::Object(object)).map_err(|e| RusterApiError::BadRequest(format!(
+                "The selected fields do not form a complete {}: {}", T::entity_name(), e
+            )))
+        }
+        _ => Ok(item),
+    }
+}
+
+/// Keeps only the selected fields of every entity of a list
+pub fn project_all<T: ApiEntity>(items: Vec<T>, fields: &[String]) -> Result<Vec<T>> {
+    items.into_iter().map(|item| project(item, fields)).collect()
+}
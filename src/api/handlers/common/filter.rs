@@ -0,0 +1,54 @@
+use crate::api::adapters::api_adapter::ApiRequest;
+use crate::config::specific::entity_config::{DataType, Field};
+use crate::data::datasource::base::{Filter, FilterOperator};
+use crate::error::{Result, RusterApiError};
+use serde_json::Value;
+
+/// Reads the filters of a request: a parameter named after a field compares it for
+/// equality, and the suffixes of `FilterOperator` select the other comparisons, like `age_gte`.
+/// Only searchable fields can be filtered; parameters naming no field are left to other features.
+pub fn filter_params(request: &ApiRequest, fields: &[Field], reserved: &[String]) -> Result<Vec<Filter>> {
+    let mut filters = Vec::new();
+    for (name, raw) in &request.query {
+        if reserved.contains(name) {
+            continue;
+        }
+
+        let field = |field_name: &str| fields.iter().find(|field| field.name == field_name);
+        let matched = field(name).map(|field| (field, FilterOperator::Eq)).or_else(|| {
+            FilterOperator::SUFFIXED.iter().find_map(|operator| {
+                name.strip_suffix(operator.suffix()).and_then(field).map(|field| (field, *operator))
+            })
+        });
+        let Some((field, operator)) = matched else {
+            continue;
+        };
+        if !field.searchable {
+            return Err(RusterApiError::BadRequest(format!("Field '{}' is not searchable", field.name)));
+        }
+
+        filters.push(Filter {
+            field: field.name.clone(),
+            operator,
+            value: filter_value(field, operator, raw)?,
+        });
+    }
+    // Query parameters are unordered, so the filters are sorted to keep the generated SQL stable
+    filters.sort_by(|a, b| a.field.cmp(&b.field).then_with(|| a.operator.suffix().cmp(b.operator.suffix())));
+    Ok(filters)
+}
+
+/// Converts a filter parameter to the type of its field. LIKE patterns are always text.
+pub fn filter_value(field: &Field, operator: FilterOperator, raw: &str) -> Result<Value> {
+    let invalid = |kind: &str| RusterApiError::BadRequest(format!("Filter on field '{}' must be {}", field.name, kind));
+    if operator == FilterOperator::Like {
+        return Ok(Value::String(raw.to_string()));
+    }
+
+    match field.data_type {
+        DataType::Integer => raw.parse::<i64>().map(Value::from).map_err(|_| invalid("an integer")),
+        DataType::Float => raw.parse::<f64>().map(Value::from).map_err(|_| invalid("a number")),
+        DataType::Boolean => raw.parse::<bool>().map(Value::from).map_err(|_| invalid("true or false")),
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
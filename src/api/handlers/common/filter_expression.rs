@@ -0,0 +1,362 @@
+use crate::api::adapters::api_adapter::ApiRequest;
+use crate::api::handlers::common::filter::filter_value;
+use crate::config::specific::entity_config::Field;
+use crate::data::datasource::base::{Filter, FilterExpression, FilterOperator};
+use crate::error::{Result, RusterApiError};
+
+/// Query parameter holding a filter expression
+pub const FILTER_PARAM: &str = "filter";
+/// Maximum length of a filter expression
+const MAX_EXPRESSION_LENGTH: usize = 2_000;
+/// Maximum number of comparisons of a filter expression
+const MAX_COMPARISONS: usize = 50;
+/// Maximum nesting of parentheses and `not` in a filter expression
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Text(String),
+    /// Field name, keyword or unquoted operand such as a number
+    Word(String),
+    Comparison(FilterOperator),
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+/// Splits an expression into tokens. Besides the word operators, comparisons are written
+/// `==`, `=`, `!=`, `<>`, `<`, `<=`, `>`, `>=` or RSQL style like `=ge=`, and `;` and `,` stand for
+/// `and` and `or`.
+fn tokenize(source: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            ';' => {
+                chars.next();
+                tokens.push(Token::And);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Or);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(other) => text.push(other),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let mut symbol = c.to_string();
+                if c == '=' && chars.peek().is_some_and(|next| next.is_ascii_alphabetic()) {
+                    // RSQL comparison, like `=ge=`
+                    while let Some(letter) = chars.next_if(|letter| letter.is_ascii_alphabetic()) {
+                        symbol.push(letter);
+                    }
+                    if chars.next() != Some('=') {
+                        return Err(format!("unterminated comparison '{}'", symbol));
+                    }
+                    symbol.push('=');
+                } else if let Some(next) = chars.next_if(|next| *next == '=' || (c == '<' && *next == '>')) {
+                    symbol.push(next);
+                }
+                let operator = match symbol.as_str() {
+                    "=" | "==" => FilterOperator::Eq,
+                    "!=" | "<>" | "=ne=" => FilterOperator::Ne,
+                    ">" | "=gt=" => FilterOperator::Gt,
+                    ">=" | "=ge=" => FilterOperator::Gte,
+                    "<" | "=lt=" => FilterOperator::Lt,
+                    "<=" | "=le=" => FilterOperator::Lte,
+                    "=like=" => FilterOperator::Like,
+                    other => return Err(format!("unknown comparison '{}'", other)),
+                };
+                tokens.push(Token::Comparison(operator));
+            }
+            c if c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '%') => {
+                let mut word = String::new();
+                while let Some(letter) = chars.next_if(|l| l.is_alphanumeric() || matches!(l, '_' | '-' | '.' | '%' | ':')) {
+                    word.push(letter);
+                }
+                tokens.push(Token::Word(word));
+            }
+            other => return Err(format!("unexpected character '{}'", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Comparison named by a word, OData style, like `ge`
+fn word_comparison(word: &str) -> Option<FilterOperator> {
+    match word.to_lowercase().as_str() {
+        "eq" => Some(FilterOperator::Eq),
+        "ne" => Some(FilterOperator::Ne),
+        "gt" => Some(FilterOperator::Gt),
+        "ge" => Some(FilterOperator::Gte),
+        "lt" => Some(FilterOperator::Lt),
+        "le" => Some(FilterOperator::Lte),
+        "like" => Some(FilterOperator::Like),
+        _ => None,
+    }
+}
+
+/// Recursive descent parser over the tokens of a filter expression, checking every
+/// comparison against the fields it may filter
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    fields: &'a [Field],
+    comparisons: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Consumes the next token if it is the given connective, written as a symbol or a word
+    fn accept(&mut self, connective: Token, word: &str) -> bool {
+        let found = match self.tokens.get(self.position) {
+            Some(Token::Word(text)) => text.eq_ignore_ascii_case(word),
+            Some(token) => *token == connective,
+            None => false,
+        };
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn disjunction(&mut self) -> std::result::Result<FilterExpression, String> {
+        let mut terms = vec![self.conjunction()?];
+        while self.accept(Token::Or, "or") {
+            terms.push(self.conjunction()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { FilterExpression::Or(terms) })
+    }
+
+    fn conjunction(&mut self) -> std::result::Result<FilterExpression, String> {
+        let mut terms = vec![self.negation()?];
+        while self.accept(Token::And, "and") {
+            terms.push(self.negation()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { FilterExpression::And(terms) })
+    }
+
+    fn negation(&mut self) -> std::result::Result<FilterExpression, String> {
+        let negated = self.accept(Token::Word("not".to_string()), "not");
+        let grouped = !negated && self.tokens.get(self.position) == Some(&Token::Open);
+        if !negated && !grouped {
+            return self.comparison();
+        }
+
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("at most {} levels of nesting are allowed", MAX_DEPTH));
+        }
+        let expression = if negated {
+            FilterExpression::Not(Box::new(self.negation()?))
+        } else {
+            self.position += 1;
+            let inner = self.disjunction()?;
+            match self.next() {
+                Some(Token::Close) => inner,
+                _ => return Err("missing closing parenthesis".to_string()),
+            }
+        };
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    /// A comparison of a field with a value: `age >= 18`, `name like "A%"` or `deleted == null`
+    fn comparison(&mut self) -> std::result::Result<FilterExpression, String> {
+        let name = match self.next() {
+            Some(Token::Word(name)) => name,
+            Some(token) => return Err(format!("expected a field name, found {:?}", token)),
+            None => return Err("unexpected end of expression".to_string()),
+        };
+        let field = self.fields.iter()
+            .find(|field| field.name == name)
+            .ok_or_else(|| format!("unknown field '{}'", name))?;
+        if !field.searchable {
+            return Err(format!("field '{}' is not searchable", name));
+        }
+        let operator = match self.next() {
+            Some(Token::Comparison(operator)) => operator,
+            Some(Token::Word(word)) => word_comparison(&word).ok_or_else(|| format!("unknown comparison '{}'", word))?,
+            Some(token) => return Err(format!("expected a comparison after '{}', found {:?}", name, token)),
+            None => return Err("unexpected end of expression".to_string()),
+        };
+        let (raw, quoted) = match self.next() {
+            Some(Token::Text(text)) => (text, true),
+            Some(Token::Word(word)) => (word, false),
+            Some(token) => return Err(format!("expected a value for '{}', found {:?}", name, token)),
+            None => return Err("unexpected end of expression".to_string()),
+        };
+
+        self.comparisons += 1;
+        if self.comparisons > MAX_COMPARISONS {
+            return Err(format!("at most {} comparisons are allowed", MAX_COMPARISONS));
+        }
+        // An unquoted null compares with the absence of a value
+        let operator = match (quoted || !raw.eq_ignore_ascii_case("null"), operator) {
+            (true, operator) => operator,
+            (false, FilterOperator::Eq) => FilterOperator::IsNull,
+            (false, FilterOperator::Ne) => FilterOperator::NotNull,
+            (false, _) => return Err(format!("'{}' can only be compared with null using == or !=", name)),
+        };
+        let value = if operator.is_null_check() {
+            serde_json::Value::Null
+        } else {
+            filter_value(field, operator, &raw).map_err(|err| match err {
+                RusterApiError::BadRequest(message) => message,
+                other => other.to_string(),
+            })?
+        };
+        Ok(FilterExpression::Filter(Filter { field: field.name.clone(), operator, value }))
+    }
+}
+
+/// Parses a filter expression such as `age>=18 and (status=="active" or role=="admin")`.
+/// Comparisons are joined with `and`, `or` and `not` and grouped with parentheses; only
+/// searchable fields can be compared, and values are typed like their field.
+pub fn parse_filter_expression(source: &str, fields: &[Field]) -> Result<FilterExpression> {
+    let invalid = |message: String| RusterApiError::BadRequest(format!("Invalid filter expression: {}", message));
+    if source.len() > MAX_EXPRESSION_LENGTH {
+        return Err(invalid(format!("it exceeds {} characters", MAX_EXPRESSION_LENGTH)));
+    }
+    let mut parser = Parser { tokens: tokenize(source).map_err(invalid)?, position: 0, fields, comparisons: 0, depth: 0 };
+    let expression = parser.disjunction().map_err(invalid)?;
+    match parser.next() {
+        None => Ok(expression),
+        Some(token) => Err(invalid(format!("unexpected {:?}", token))),
+    }
+}
+
+/// Reads the `filter` parameter of a request, if any
+pub fn filter_expression_param(request: &ApiRequest, fields: &[Field]) -> Result<Option<FilterExpression>> {
+    request.query.get(FILTER_PARAM)
+        .map(|source| parse_filter_expression(source, fields))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::shared::{EntityBasic, FieldBasic};
+    use crate::config::specific::entity_config::DataType;
+    use serde_json::{json, Value};
+
+    fn fields() -> Vec<Field> {
+        let basic = |name: &str, data_type| FieldBasic { name: name.to_string(), data_type, required: false };
+        let mut entity = EntityBasic {
+            name: "people".to_string(),
+            table_name: None,
+            fields: vec![basic("name", DataType::String), basic("age", DataType::Integer), basic("secret", DataType::String)],
+            authentication: false,
+        }.to_entity();
+        for field in entity.fields.iter_mut().filter(|field| field.name == "secret") {
+            field.searchable = false;
+        }
+        entity.fields
+    }
+
+    fn filter(field: &str, operator: FilterOperator, value: Value) -> FilterExpression {
+        FilterExpression::Filter(Filter { field: field.to_string(), operator, value })
+    }
+
+    fn parse(source: &str) -> Result<FilterExpression> {
+        parse_filter_expression(source, &fields())
+    }
+
+    fn message(source: &str) -> String {
+        match parse(source) {
+            Err(RusterApiError::BadRequest(message)) => message,
+            other => panic!("expected a bad request for {}, got {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(parse("age == 1 or age == 2 and name == 'A'").unwrap(), FilterExpression::Or(vec![
+            filter("age", FilterOperator::Eq, json!(1)),
+            FilterExpression::And(vec![
+                filter("age", FilterOperator::Eq, json!(2)),
+                filter("name", FilterOperator::Eq, json!("A")),
+            ]),
+        ]));
+        assert_eq!(parse("(age == 1 or age == 2) and name == 'A'").unwrap(), FilterExpression::And(vec![
+            FilterExpression::Or(vec![
+                filter("age", FilterOperator::Eq, json!(1)),
+                filter("age", FilterOperator::Eq, json!(2)),
+            ]),
+            filter("name", FilterOperator::Eq, json!("A")),
+        ]));
+    }
+
+    #[test]
+    fn not_negates_the_next_term() {
+        assert_eq!(parse("not age > 3 and name == 'A'").unwrap(), FilterExpression::And(vec![
+            FilterExpression::Not(Box::new(filter("age", FilterOperator::Gt, json!(3)))),
+            filter("name", FilterOperator::Eq, json!("A")),
+        ]));
+    }
+
+    #[test]
+    fn unquoted_null_checks_for_the_absence_of_a_value() {
+        assert_eq!(parse("name==null").unwrap(), filter("name", FilterOperator::IsNull, Value::Null));
+        assert_eq!(parse("name!=NULL").unwrap(), filter("name", FilterOperator::NotNull, Value::Null));
+        assert_eq!(parse("name=='null'").unwrap(), filter("name", FilterOperator::Eq, json!("null")));
+        assert!(message("age > null").contains("only be compared with null"));
+    }
+
+    #[test]
+    fn quoted_values_unescape_their_quotes() {
+        assert_eq!(parse(r#"name == "say \"hi\"""#).unwrap(), filter("name", FilterOperator::Eq, json!("say \"hi\"")));
+        assert_eq!(parse(r"name == 'it\'s'").unwrap(), filter("name", FilterOperator::Eq, json!("it's")));
+        assert!(message("name == 'open").contains("unterminated string"));
+    }
+
+    #[test]
+    fn rejects_unknown_and_unsearchable_fields() {
+        assert!(message("missing == 1").contains("unknown field 'missing'"));
+        assert!(message("secret == 'x'").contains("field 'secret' is not searchable"));
+    }
+
+    #[test]
+    fn rejects_expressions_past_the_limits() {
+        let deep = format!("{}age == 1{}", "(".repeat(MAX_DEPTH + 1), ")".repeat(MAX_DEPTH + 1));
+        assert!(message(&deep).contains("levels of nesting"));
+        let negated = format!("{}age == 1", "not ".repeat(MAX_DEPTH + 1));
+        assert!(message(&negated).contains("levels of nesting"));
+
+        let long = format!("name == '{}'", "a".repeat(MAX_EXPRESSION_LENGTH));
+        assert!(message(&long).contains("exceeds"));
+
+        let comparisons = vec!["age == 1"; MAX_COMPARISONS + 1].join(" or ");
+        assert!(message(&comparisons).contains("comparisons are allowed"));
+        assert!(parse(&vec!["age == 1"; MAX_COMPARISONS].join(" or ")).is_ok());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody};
+use crate::api::handlers::common::dates::{http_date, parse_http_date, parse_timestamp};
+use crate::api::handlers::common::etag::{entity_tag, none_match_hit, ETAG_HEADER, IF_NONE_MATCH_HEADER};
+use crate::config::specific::entity_config::{DataType, Entity, HttpCachePolicy};
+use crate::error::Result;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+/// Response header telling how long a response may be reused
+pub const CACHE_CONTROL_HEADER: &str = "Cache-Control";
+/// Response header holding the last modification of the returned entities
+pub const LAST_MODIFIED_HEADER: &str = "Last-Modified";
+/// Request header holding the date of the response the client already has, lowercase as captured
+pub const IF_MODIFIED_SINCE_HEADER: &str = "if-modified-since";
+
+/// Caching headers of the read endpoints of an entity, from its `http_cache` policy
+#[derive(Default)]
+pub struct HttpCache {
+    policy: Option<HttpCachePolicy>,
+    /// Field holding the last modification of an entity, when the entity has it
+    last_modified_field: Option<String>,
+}
+
+/// Seconds since the Unix epoch of a DateTime value, written as an ISO 8601 string or a number of seconds
+fn timestamp(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(text) => parse_timestamp(text),
+        Value::Number(number) => number.as_u64(),
+        _ => None,
+    }
+}
+
+impl HttpCache {
+    /// Reads the caching policy of an entity. A last modified field the entity lacks, or that
+    /// cannot hold a date and time, is reported and left out.
+    pub fn new(entity: &Entity) -> Self {
+        let Some(policy) = &entity.http_cache else {
+            return Self::default();
+        };
+        let last_modified_field = policy.last_modified_field.as_ref().filter(|name| {
+            match entity.fields.iter().find(|field| &field.name == *name) {
+                Some(field) if matches!(field.data_type, DataType::DateTime | DataType::String | DataType::Integer) => true,
+                Some(_) => {
+                    warn!("Last-Modified of {} is disabled: field {} must be a DateTime, String or Integer field", entity.name, name);
+                    false
+                }
+                None => {
+                    warn!("Last-Modified of {} is disabled: unknown field {}", entity.name, name);
+                    false
+                }
+            }
+        });
+        Self { policy: Some(policy.clone()), last_modified_field: last_modified_field.cloned() }
+    }
+
+    /// Value of the `Cache-Control` header, like `public, max-age=60`
+    fn cache_control(policy: &HttpCachePolicy) -> String {
+        let mut directives = vec![
+            if policy.public { "public" } else { "private" }.to_string(),
+            format!("max-age={}", policy.max_age_seconds),
+        ];
+        if policy.must_revalidate {
+            directives.push("must-revalidate".to_string());
+        }
+        directives.join(", ")
+    }
+
+    /// Latest modification of the entities of a body, from their last modified field
+    fn last_modified<T: Serialize>(&self, body: Option<&ApiResponseBody<T>>) -> Result<Option<u64>> {
+        let Some(field) = &self.last_modified_field else {
+            return Ok(None);
+        };
+        let items: Vec<&T> = match body {
+            Some(ApiResponseBody::Single(item)) => vec![item],
+            Some(ApiResponseBody::List(items) | ApiResponseBody::Page { items, .. } | ApiResponseBody::Cursor { items, .. }) => {
+                items.iter().collect()
+            }
+            _ => return Ok(None),
+        };
+        let mut latest = None;
+        for item in items {
+            let modified = serde_json::to_value(item)?.get(field).and_then(timestamp);
+            latest = latest.max(modified);
+        }
+        Ok(latest)
+    }
+
+    /// Adds the caching headers of the policy to a successful response, with an ETag of the body
+    /// when the endpoint did not set one, and answers it with 304 when the client already has it:
+    /// when `If-None-Match` lists its ETag, or without that header, when it was not modified after
+    /// `If-Modified-Since`
+    pub fn respond<T: Serialize>(&self, request: &ApiRequest, mut response: ApiResponse<T>) -> Result<ApiResponse<T>> {
+        if response.status != 200 {
+            return Ok(response);
+        }
+        let mut last_modified = None;
+        if let Some(policy) = &self.policy {
+            if let (false, Some(body)) = (response.headers.contains_key(ETAG_HEADER), &response.body) {
+                response.headers.insert(ETAG_HEADER.to_string(), entity_tag(body, None, false)?);
+            }
+            last_modified = self.last_modified(response.body.as_ref())?;
+            response.headers.insert(CACHE_CONTROL_HEADER.to_string(), Self::cache_control(policy));
+            if let Some(seconds) = last_modified {
+                response.headers.insert(LAST_MODIFIED_HEADER.to_string(), http_date(seconds));
+            }
+        }
+
+        let not_modified = match request.header(IF_NONE_MATCH_HEADER) {
+            Some(_) => response.headers.get(ETAG_HEADER).is_some_and(|etag| none_match_hit(request, etag)),
+            None => last_modified
+                .zip(request.header(IF_MODIFIED_SINCE_HEADER).and_then(parse_http_date))
+                .is_some_and(|(modified, since)| modified <= since),
+        };
+        if not_modified {
+            response.status = 304;
+            response.body = None;
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Request header holding the key of a create request, lowercase as captured
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Response header marking a response replayed for a retried request
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+/// Seconds responses are kept when `idempotency_ttl_seconds` is not set
+pub const DEFAULT_IDEMPOTENCY_TTL_SECONDS: u64 = 86_400;
+/// Maximum number of keys kept per entity, so clients cannot grow the cache without bounds
+const MAX_KEYS: usize = 10_000;
+
+/// Response of a completed request, kept to answer its retries
+struct StoredResponse<T> {
+    status: u16,
+    headers: HashMap<String, String>,
+    item: T,
+}
+
+struct Entry<T> {
+    /// Hash of the request body, so a key reused for another request is detected
+    fingerprint: u64,
+    created_at: Instant,
+    /// Response of the request, or `None` while it runs
+    response: Option<StoredResponse<T>>,
+}
+
+/// Responses of the create requests sent with an `Idempotency-Key` header, so retries return
+/// the original result instead of inserting again. Clones share the same responses.
+pub struct IdempotencyCache<T> {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, Entry<T>>>>,
+}
+
+impl<T> Clone for IdempotencyCache<T> {
+    fn clone(&self) -> Self {
+        Self { ttl: self.ttl, entries: self.entries.clone() }
+    }
+}
+
+impl<T: ApiEntity> IdempotencyCache<T> {
+    pub fn new(ttl_seconds: u64) -> Self {
+        Self {
+            ttl: Duration::from_secs(ttl_seconds),
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Runs a create request once per key. Retries get the stored response, marked with the
+    /// `Idempotent-Replayed` header. Failed requests are not stored, so they can be retried.
+    /// Reusing a key for another body is answered with 422, and retrying a running request with 409.
+    pub fn run(&self, request: &ApiRequest, create: impl FnOnce() -> Result<ApiResponse<T>>) -> Result<ApiResponse<T>> {
+        let Some(key) = request.header(IDEMPOTENCY_KEY_HEADER).map(|key| key.trim().to_string()) else {
+            return create();
+        };
+        let mut hasher = DefaultHasher::new();
+        request.body.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+
+        {
+            let mut entries = self.entries.lock().unwrap();
+            entries.retain(|_, entry| entry.created_at.elapsed() < self.ttl);
+            match entries.get(&key) {
+                Some(entry) if entry.fingerprint != fingerprint => {
+                    return Err(RusterApiError::UnprocessableEntity(
+                        "The Idempotency-Key was already used for another request".to_string(),
+                    ));
+                }
+                Some(Entry { response: Some(stored), .. }) => {
+                    let mut headers = stored.headers.clone();
+                    headers.insert(IDEMPOTENT_REPLAYED_HEADER.to_string(), "true".to_string());
+                    return Ok(ApiResponse {
+                        status: stored.status,
+                        headers,
+                        body: Some(ApiResponseBody::Single(stored.item.clone())),
+                    });
+                }
+                Some(_) => {
+                    return Err(RusterApiError::Conflict(
+                        "A request with this Idempotency-Key is still running".to_string(),
+                    ));
+                }
+                None => {}
+            }
+            if entries.len() >= MAX_KEYS {
+                let oldest = entries.iter().min_by_key(|(_, entry)| entry.created_at).map(|(key, _)| key.clone());
+                entries.remove(&oldest.unwrap_or_default());
+            }
+            entries.insert(key.clone(), Entry { fingerprint, created_at: Instant::now(), response: None });
+        }
+
+        // The lock is released while creating, so other keys are not held up
+        let result = create();
+        let mut entries = self.entries.lock().unwrap();
+        match &result {
+            Ok(ApiResponse { status, headers, body: Some(ApiResponseBody::Single(item)) }) if (200..300).contains(status) => {
+                if let Some(entry) = entries.get_mut(&key) {
+                    entry.response = Some(StoredResponse { status: *status, headers: headers.clone(), item: item.clone() });
+                }
+            }
+            _ => {
+                entries.remove(&key);
+            }
+        }
+        result
+    }
+}
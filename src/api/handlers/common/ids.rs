@@ -0,0 +1,39 @@
+use crate::config::specific::entity_config::{Entity, IdStrategy};
+use serde_json::{Map, Value};
+
+/// Generates the key of entities created without one, following the `id_strategy` of the entity
+pub struct IdGenerator {
+    field: String,
+    strategy: Option<IdStrategy>,
+}
+
+impl IdGenerator {
+    pub fn new(entity: &Entity) -> Self {
+        Self {
+            field: entity.key_field().map(|field| field.name.clone()).unwrap_or_else(|| "id".to_string()),
+            strategy: entity.id_strategy,
+        }
+    }
+
+    /// Generates a new key, or `None` when the datasource generates it
+    fn generate(&self) -> Option<Value> {
+        let id = match self.strategy? {
+            IdStrategy::UuidV4 => uuid::Uuid::new_v4().to_string(),
+            IdStrategy::UuidV7 => uuid::Uuid::now_v7().to_string(),
+            IdStrategy::Ulid => ulid::Ulid::new().to_string(),
+            IdStrategy::Nanoid => nanoid::nanoid!(),
+            IdStrategy::DbAuto => return None,
+        };
+        Some(Value::String(id))
+    }
+
+    /// Sets a generated key on an object whose key is missing or null. Keys sent by the client are kept.
+    pub fn apply(&self, object: &mut Map<String, Value>) {
+        if object.get(&self.field).is_some_and(|id| !id.is_null()) {
+            return;
+        }
+        if let Some(id) = self.generate() {
+            object.insert(self.field.clone(), id);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::config::specific::entity_config::{Entity, ImmutableChanges};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Guards the immutable fields of an entity against update requests
+#[derive(Default)]
+pub struct ImmutableFields {
+    fields: Vec<String>,
+    changes: ImmutableChanges,
+}
+
+impl ImmutableFields {
+    pub fn new(entity: &Entity) -> Self {
+        Self {
+            fields: entity.fields.iter().filter(|field| field.immutable).map(|field| field.name.clone()).collect(),
+            changes: entity.endpoints.immutable_changes,
+        }
+    }
+
+    fn rejected<'a>(&self, names: impl Iterator<Item = &'a String>) -> Result<()> {
+        let errors: BTreeMap<String, Vec<String>> = names
+            .map(|name| (name.clone(), vec!["cannot be changed".to_string()]))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(RusterApiError::InvalidFields(errors))
+        }
+    }
+
+    /// Checks an entity replacing the stored one. Changed immutable fields are answered with 400,
+    /// or reset to their stored value when changes are ignored. Omitted ones keep their stored value.
+    pub fn check_replacement<T: ApiEntity>(&self, item: T, stored: &T) -> Result<T> {
+        if self.fields.is_empty() {
+            return Ok(item);
+        }
+        let (Value::Object(mut object), Value::Object(stored)) = (serde_json::to_value(&item)?, serde_json::to_value(stored)?) else {
+            return Ok(item);
+        };
+
+        let changed: Vec<&String> = self.fields.iter()
+            .filter(|name| match object.get(*name) {
+                None | Some(Value::Null) => false,
+                Some(value) => stored.get(*name).is_some_and(|current| current != value),
+            })
+            .collect();
+        if self.changes == ImmutableChanges::Reject {
+            self.rejected(changed.into_iter())?;
+        }
+
+        for name in &self.fields {
+            if let Some(current) = stored.get(name) {
+                object.insert(name.clone(), current.clone());
+            }
+        }
+        Ok(serde_json::from_value(Value::Object(object))?)
+    }
+
+    /// Checks the changes of a bulk update, which cannot be compared with every stored entity,
+    /// so any immutable field is answered with 400, or dropped when changes are ignored
+    pub fn check_changes(&self, changes: &mut Map<String, Value>) -> Result<()> {
+        match self.changes {
+            ImmutableChanges::Reject => self.rejected(self.fields.iter().filter(|name| changes.contains_key(*name))),
+            ImmutableChanges::Ignore => {
+                changes.retain(|name, _| !self.fields.contains(name));
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use crate::api::adapters::api_adapter::{ApiRequest, AuthContext};
+use crate::config::specific::entity_config::{Entity, Field, Relationship, RelationshipType};
+use crate::data::datasource::base::{DataSource, Filter, FilterOperator, ListQuery};
+use crate::data::datasource::relational::base::value_key;
+use crate::api::handlers::common::authorization::{CrudAction, EntityAuthorization};
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::utils::handle_datasource_error;
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Query parameter holding the comma separated relationships to embed, like `author,comments.author`
+pub const INCLUDE_PARAM: &str = "include";
+/// Maximum number of relationships in an include path, so cyclic relationships cannot expand forever
+pub const MAX_INCLUDE_DEPTH: usize = 3;
+
+/// A relationship to embed in the responses, with the relationships to embed in turn
+#[derive(Debug, Clone)]
+pub struct Include {
+    relationship: Relationship,
+    related: Entity,
+    nested: Vec<Include>,
+}
+
+impl Include {
+    /// Name of the embedded relationship, used as the key of the related entities
+    pub fn name(&self) -> &str {
+        &self.relationship.name
+    }
+}
+
+/// Fields to return when relationships are included: the selected fields and the included
+/// relationships, or every field if none is selected
+pub fn with_included(fields: &[String], includes: &[Include]) -> Vec<String> {
+    if fields.is_empty() {
+        return Vec::new();
+    }
+    fields.iter().cloned().chain(includes.iter().map(|include| include.name().to_string())).collect()
+}
+
+/// Finds the field named by a foreign key, which may name either the field or its column
+fn key_field<'a>(entity: &'a Entity, foreign_key: &str) -> Option<&'a Field> {
+    entity.fields.iter()
+        .find(|field| field.name == foreign_key || field.column_name.as_deref() == Some(foreign_key))
+}
+
+/// Parses the `include` parameter into the relationships to embed, rejecting unknown
+/// relationships, paths deeper than `MAX_INCLUDE_DEPTH` and related entities the caller
+/// may not read. Relationships marked with `include_in_responses` are embedded as well, when
+/// the caller may read their related entity.
+pub fn include_param(request: &ApiRequest, entity: &Entity, all_entities: &[Entity]) -> Result<Vec<Include>> {
+    let mut includes = Vec::new();
+    for relationship in entity.relationships.iter().filter(|relationship| relationship.include_in_responses) {
+        // Callers did not ask for these, so the ones they may not read are left out rather than refused
+        let _ = add_path(&mut includes, entity, &[relationship.name.as_str()], all_entities, request.auth.as_ref());
+    }
+    let Some(requested) = request.query.get(INCLUDE_PARAM) else {
+        return Ok(includes);
+    };
+
+    for path in requested.split(',').map(str::trim).filter(|path| !path.is_empty()) {
+        let segments: Vec<&str> = path.split('.').collect();
+        if segments.len() > MAX_INCLUDE_DEPTH {
+            return Err(RusterApiError::BadRequest(format!(
+                "Include path '{}' is deeper than {} relationships", path, MAX_INCLUDE_DEPTH
+            )));
+        }
+        add_path(&mut includes, entity, &segments, all_entities, request.auth.as_ref())?;
+    }
+    Ok(includes)
+}
+
+/// Adds an include path to a set of includes, merging the paths sharing a prefix
+fn add_path(includes: &mut Vec<Include>, entity: &Entity, segments: &[&str], all_entities: &[Entity], auth: Option<&AuthContext>) -> Result<()> {
+    let Some((name, rest)) = segments.split_first() else {
+        return Ok(());
+    };
+
+    let position = match includes.iter().position(|include| include.relationship.name.eq_ignore_ascii_case(name)) {
+        Some(position) => position,
+        None => {
+            let relationship = entity.relationships.iter()
+                .find(|relationship| relationship.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| RusterApiError::BadRequest(format!("Unknown relationship '{}' of {}", name, entity.name)))?;
+            if matches!(relationship.type_, RelationshipType::ManyToMany) {
+                return Err(RusterApiError::BadRequest(format!("Many-to-many relationship '{}' cannot be included", name)));
+            }
+            let related = all_entities.iter()
+                .find(|candidate| candidate.name.eq_ignore_ascii_case(&relationship.related_entity))
+                .ok_or_else(|| RusterApiError::BadRequest(format!(
+                    "Related entity '{}' of relationship '{}' is not configured", relationship.related_entity, name
+                )))?;
+            // Related entities are read like on their own endpoints
+            if related.authentication && auth.is_none() {
+                return Err(RusterApiError::AuthError(format!("Authentication required to include '{}'", name)));
+            }
+            EntityAuthorization::new(related).check(auth, CrudAction::Read)?;
+            includes.push(Include {
+                relationship: relationship.clone(),
+                related: related.clone(),
+                nested: Vec::new(),
+            });
+            includes.len() - 1
+        }
+    };
+
+    let include = &mut includes[position];
+    let related = include.related.clone();
+    add_path(&mut include.nested, &related, rest, all_entities, auth)
+}
+
+/// Embeds the included relationships into entities, under the relationship names.
+/// To-one relationships embed an object or null, to-many relationships a list.
+/// Only the related entities of the tenant and of the owner of the request that are not deleted
+/// are embedded, without the fields the caller may not read.
+pub fn embed_all<T: ApiEntity>(
+    datasource: &dyn DataSource<T>,
+    request: &ApiRequest,
+    entity: &Entity,
+    items: Vec<T>,
+    includes: &[Include],
+) -> Result<Vec<T>> {
+    if includes.is_empty() {
+        return Ok(items);
+    }
+
+    let mut objects = items.iter().map(serde_json::to_value).collect::<std::result::Result<Vec<Value>, _>>()?;
+    embed_objects(datasource, request, entity, &mut objects, includes)?;
+    objects.into_iter().map(|object| Ok(serde_json::from_value(object)?)).collect()
+}
+
+fn embed_objects<T: ApiEntity>(
+    datasource: &dyn DataSource<T>,
+    request: &ApiRequest,
+    entity: &Entity,
+    objects: &mut [Value],
+    includes: &[Include],
+) -> Result<()> {
+    for include in includes {
+        let relationship = &include.relationship;
+        let tenancy = Tenancy::new(&include.related);
+        let ownership = Ownership::new(&include.related);
+        let soft_delete = SoftDelete::new(&include.related);
+        let local_key = key_field(entity, &relationship.foreign_key);
+        // The foreign key lives on this entity for ManyToOne and owning OneToOne sides,
+        // otherwise it lives on the related entity and references this primary key
+        let owning_side = match relationship.type_ {
+            RelationshipType::ManyToOne => true,
+            RelationshipType::OneToOne => local_key.is_some(),
+            _ => false,
+        };
+
+        let mut related_values = Vec::with_capacity(objects.len());
+        if owning_side {
+            let Some(local_key) = local_key else {
+                return Err(RusterApiError::BadRequest(format!(
+                    "Relationship '{}' has no foreign key field '{}'", relationship.name, relationship.foreign_key
+                )));
+            };
+            // Entities sharing a parent load it once
+            let mut loaded: HashMap<String, Value> = HashMap::new();
+            for object in objects.iter() {
+                let related = match object.get(&local_key.name) {
+                    None | Some(Value::Null) => Value::Null,
+                    Some(key) => {
+                        let key = value_key(key);
+                        match loaded.get(&key) {
+                            Some(related) => related.clone(),
+                            None => {
+                                let related = datasource.get_by_id(&key, Some(&include.related.name))
+                                    .map_err(handle_datasource_error)?;
+                                let related = tenancy.visible(request, soft_delete.visible(related)?)?;
+                                let related = ownership.visible(request.auth.as_ref(), related)?
+                                    .map(|item| serde_json::to_value(&item))
+                                    .transpose()?
+                                    .unwrap_or(Value::Null);
+                                loaded.insert(key, related.clone());
+                                related
+                            }
+                        }
+                    }
+                };
+                related_values.push(related);
+            }
+        } else {
+            let remote_key = key_field(&include.related, &relationship.foreign_key).ok_or_else(|| RusterApiError::BadRequest(format!(
+                "Relationship '{}' has no foreign key field '{}' on {}", relationship.name, relationship.foreign_key, include.related.name
+            )))?;
+            let primary_key = entity.key_field().map(|field| field.name.clone()).unwrap_or_else(|| "id".to_string());
+            for object in objects.iter() {
+                let children = match object.get(&primary_key) {
+                    None | Some(Value::Null) => Vec::new(),
+                    Some(key) => {
+                        let mut filters = vec![Filter {
+                            field: remote_key.name.clone(),
+                            operator: FilterOperator::Eq,
+                            value: key.clone(),
+                        }];
+                        soft_delete.exclude_deleted(&mut filters);
+                        tenancy.restrict(request, &mut filters)?;
+                        ownership.restrict(request.auth.as_ref(), &mut filters)?;
+                        let query = ListQuery { filters, ..ListQuery::default() };
+                        datasource.get_page(&query, Some(&include.related.name))
+                            .map_err(handle_datasource_error)?
+                            .items.iter()
+                            .map(serde_json::to_value)
+                            .collect::<std::result::Result<Vec<Value>, _>>()?
+                    }
+                };
+                related_values.push(if matches!(relationship.type_, RelationshipType::OneToOne) {
+                    children.into_iter().next().unwrap_or(Value::Null)
+                } else {
+                    Value::Array(children)
+                });
+            }
+        }
+
+        if !include.nested.is_empty() {
+            let mut nested_objects: Vec<Value> = related_values.iter()
+                .flat_map(|related| match related {
+                    Value::Array(children) => children.clone(),
+                    Value::Null => Vec::new(),
+                    other => vec![other.clone()],
+                })
+                .collect();
+            embed_objects(datasource, request, &include.related, &mut nested_objects, &include.nested)?;
+
+            // Put the expanded entities back in place, in the order they were taken out
+            let mut expanded = nested_objects.into_iter();
+            for related in related_values.iter_mut() {
+                match related {
+                    Value::Array(children) => children.iter_mut().for_each(|child| *child = expanded.next().unwrap_or(Value::Null)),
+                    Value::Null => {}
+                    other => *other = expanded.next().unwrap_or(Value::Null),
+                }
+            }
+        }
+
+        // Embedded entities never carry their passwords nor the fields the caller may not read either
+        let field_access = FieldAccess::new(&include.related);
+        let hidden = field_access.hidden(request.auth.as_ref());
+        for (object, mut related) in objects.iter_mut().zip(related_values) {
+            field_access.hide(&hidden, &mut related);
+            if let Value::Object(map) = object {
+                map.insert(relationship.name.clone(), related);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::api::adapters::api_adapter::ApiRequest;
+use crate::error::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Request header listing the media types the client accepts, lowercase as captured
+pub const ACCEPT_HEADER: &str = "accept";
+/// Media type of comma separated values
+pub const CSV_MEDIA_TYPE: &str = "text/csv";
+/// Media type of newline-delimited JSON
+pub const NDJSON_MEDIA_TYPE: &str = "application/x-ndjson";
+
+/// Representations of a list of entities
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListFormat {
+    Json,
+    /// One row per entity under a header row naming the fields
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+impl ListFormat {
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            ListFormat::Json => "application/json",
+            ListFormat::Csv => CSV_MEDIA_TYPE,
+            ListFormat::Ndjson => NDJSON_MEDIA_TYPE,
+        }
+    }
+
+    /// Extension of the files holding the representation
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ListFormat::Json => "json",
+            ListFormat::Csv => "csv",
+            ListFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// Picks the representation of a list from the `Accept` header: the first listed media type
+/// that is supported, ignoring those with a zero quality. JSON is the default.
+pub fn list_format(request: &ApiRequest) -> ListFormat {
+    let Some(accept) = request.header(ACCEPT_HEADER) else {
+        return ListFormat::Json;
+    };
+
+    for range in accept.split(',') {
+        let mut parts = range.split(';').map(str::trim);
+        let media_type = parts.next().unwrap_or_default().to_lowercase();
+        let refused = parts.any(|param| {
+            param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()).is_some_and(|q| q <= 0.0)
+        });
+        if refused {
+            continue;
+        }
+        match media_type.as_str() {
+            CSV_MEDIA_TYPE => return ListFormat::Csv,
+            NDJSON_MEDIA_TYPE => return ListFormat::Ndjson,
+            "application/json" | "application/*" | "*/*" => return ListFormat::Json,
+            _ => {}
+        }
+    }
+    ListFormat::Json
+}
+
+/// Writes a value as a CSV cell: null is empty, text is written as is and other values as JSON,
+/// quoted when they hold a separator, a quote or a line break
+fn csv_cell(value: Option<&Value>) -> String {
+    let text = match value {
+        None | Some(Value::Null) => return String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Writes entities as CSV or NDJSON. CSV columns follow the given field order.
+pub fn render_rows<T: Serialize>(items: &[T], columns: &[String], format: ListFormat) -> Result<String> {
+    let mut output = String::new();
+    if format == ListFormat::Csv {
+        let header: Vec<String> = columns.iter().map(|column| csv_cell(Some(&Value::from(column.as_str())))).collect();
+        output.push_str(&header.join(","));
+        output.push_str("\r\n");
+    }
+
+    for item in items {
+        let value = serde_json::to_value(item)?;
+        match format {
+            ListFormat::Csv => {
+                let row: Vec<String> = columns.iter().map(|column| csv_cell(value.get(column))).collect();
+                output.push_str(&row.join(","));
+                output.push_str("\r\n");
+            }
+            _ => {
+                output.push_str(&value.to_string());
+                output.push('\n');
+            }
+        }
+    }
+    Ok(output)
+}
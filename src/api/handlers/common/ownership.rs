@@ -0,0 +1,172 @@
+use crate::api::adapters::api_adapter::AuthContext;
+use crate::api::handlers::common::filter::filter_value;
+use crate::config::specific::entity_config::{Entity, Field};
+use crate::data::datasource::base::{Filter, FilterOperator};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+/// Ownership of the rows of an entity: its `owner_field` holds the subject of the caller that
+/// created a row, and the other callers can neither see nor change it, unless they have one of
+/// the `owner_bypass_roles`
+#[derive(Clone, Default)]
+pub struct Ownership {
+    entity: String,
+    field: Option<Field>,
+    bypass_roles: Vec<String>,
+}
+
+/// Text of a value, so owners stored as numbers match the subject of the caller
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl Ownership {
+    /// Reads the owner field of an entity. Fields the entity lacks, or that are not stored, are
+    /// reported and ownership left disabled.
+    pub fn new(entity: &Entity) -> Self {
+        let Some(name) = &entity.owner_field else {
+            return Self::default();
+        };
+        match entity.fields.iter().find(|field| &field.name == name) {
+            Some(field) if field.is_stored() => Self {
+                entity: entity.name.clone(),
+                field: Some(field.clone()),
+                bypass_roles: entity.owner_bypass_roles.clone(),
+            },
+            Some(_) => {
+                warn!("Ownership of {} is disabled: field {} must be stored", entity.name, name);
+                Self::default()
+            }
+            None => {
+                warn!("Ownership of {} is disabled: unknown field {}", entity.name, name);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.field.is_some()
+    }
+
+    /// Owner value of the rows of a caller, typed like the owner field, or none when the caller
+    /// may access every row. Anonymous callers are answered with 401.
+    pub fn owner(&self, auth: Option<&AuthContext>) -> Result<Option<Value>> {
+        let Some(field) = &self.field else {
+            return Ok(None);
+        };
+        let Some(auth) = auth else {
+            return Err(RusterApiError::AuthError(format!("Authentication required to access {}", self.entity)));
+        };
+        if auth.roles.iter().any(|role| self.bypass_roles.contains(role)) {
+            return Ok(None);
+        }
+        filter_value(field, FilterOperator::Eq, &auth.subject)
+            .map(Some)
+            .map_err(|_| RusterApiError::Forbidden(format!("The caller cannot own {}", self.entity)))
+    }
+
+    /// Adds the filter selecting the rows of the caller to the filters of a query
+    pub fn restrict(&self, auth: Option<&AuthContext>, filters: &mut Vec<Filter>) -> Result<()> {
+        if let (Some(field), Some(owner)) = (&self.field, self.owner(auth)?) {
+            filters.push(Filter { field: field.name.clone(), operator: FilterOperator::Eq, value: owner });
+        }
+        Ok(())
+    }
+
+    /// Adds the owner field to a selection of fields to load, so the rows of other owners can be told apart
+    pub fn also_load(&self, fields: &mut Vec<String>) {
+        if let Some(field) = &self.field {
+            if !fields.is_empty() && !fields.contains(&field.name) {
+                fields.push(field.name.clone());
+            }
+        }
+    }
+
+    /// Hides a row of another owner, as if it did not exist
+    pub fn visible<T: ApiEntity>(&self, auth: Option<&AuthContext>, item: Option<T>) -> Result<Option<T>> {
+        let (Some(field), Some(owner)) = (&self.field, self.owner(auth)?) else {
+            return Ok(item);
+        };
+        match item {
+            Some(item) => {
+                let stored = serde_json::to_value(&item)?.get(&field.name).map(value_text);
+                Ok((stored == Some(value_text(&owner))).then_some(item))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Sets the caller as the owner of a written document, so rows cannot be created for or
+    /// handed over to other owners
+    pub fn assign(&self, auth: Option<&AuthContext>, object: &mut Map<String, Value>) -> Result<()> {
+        if let (Some(field), Some(owner)) = (&self.field, self.owner(auth)?) {
+            object.insert(field.name.clone(), owner);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::shared::{EntityBasic, FieldBasic};
+    use crate::config::specific::entity_config::DataType;
+    use serde_json::json;
+
+    /// Ownership of notes owned by the numeric id of their author
+    fn ownership() -> Ownership {
+        let field = |name: &str, data_type| FieldBasic { name: name.to_string(), data_type, required: false };
+        let mut entity = EntityBasic {
+            name: "notes".to_string(),
+            table_name: None,
+            fields: vec![field("id", DataType::Integer), field("text", DataType::String), field("author", DataType::Integer)],
+            authentication: true,
+        }.to_entity();
+        entity.owner_field = Some("author".to_string());
+        entity.owner_bypass_roles = vec!["admin".to_string()];
+        Ownership::new(&entity)
+    }
+
+    fn caller(subject: &str, roles: &[&str]) -> AuthContext {
+        AuthContext { subject: subject.to_string(), roles: roles.iter().map(|role| role.to_string()).collect(), ..AuthContext::default() }
+    }
+
+    #[test]
+    fn shows_the_rows_of_their_owner_only() {
+        let ownership = ownership();
+        let note = json!({ "id": 1, "text": "mine", "author": 7 });
+        assert_eq!(ownership.visible(Some(&caller("7", &[])), Some(note.clone())).unwrap(), Some(note.clone()));
+        assert_eq!(ownership.visible(Some(&caller("8", &[])), Some(note.clone())).unwrap(), None);
+        assert!(ownership.visible(None, Some(note)).is_err());
+    }
+
+    #[test]
+    fn shows_every_row_to_the_bypass_roles() {
+        let note = json!({ "id": 1, "text": "theirs", "author": 7 });
+        assert_eq!(ownership().visible(Some(&caller("8", &["admin"])), Some(note.clone())).unwrap(), Some(note));
+    }
+
+    #[test]
+    fn assigns_written_rows_to_their_caller() {
+        let ownership = ownership();
+        let mut object = json!({ "text": "handed over", "author": 8 }).as_object().cloned().unwrap();
+        ownership.assign(Some(&caller("7", &[])), &mut object).unwrap();
+        assert_eq!(object["author"], json!(7));
+
+        // Bypass roles write the owner they choose
+        let mut object = json!({ "text": "on behalf", "author": 8 }).as_object().cloned().unwrap();
+        ownership.assign(Some(&caller("1", &["admin"])), &mut object).unwrap();
+        assert_eq!(object["author"], json!(8));
+    }
+
+    #[test]
+    fn refuses_owners_not_typed_like_the_field() {
+        let mut object = Map::new();
+        assert!(ownership().assign(Some(&caller("ana", &[])), &mut object).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::config::specific::entity_config::Entity;
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde_json::{Map, Value};
+
+/// Hashes a password with Argon2id, in the PHC string format storing its salt and parameters
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| RusterApiError::ServerError(format!("Failed to hash the password: {}", e)))
+}
+
+/// Whether a password matches a hash made by `hash_password`. Values that are not hashes never match.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// Password fields of an entity: their values are hashed before they are stored and they are never
+/// returned. Every value clients write is taken for a password and hashed, hashes included, so
+/// they cannot set a hash of their own; stored hashes are only kept through `with_stored`.
+#[derive(Clone, Default)]
+pub struct Passwords {
+    fields: Vec<String>,
+}
+
+impl Passwords {
+    pub fn new(entity: &Entity) -> Self {
+        Self { fields: entity.fields.iter().filter(|field| field.password).map(|field| field.name.clone()).collect() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.fields.is_empty()
+    }
+
+    /// Names of the password fields
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Hashes the passwords of a written document
+    pub fn hash_object(&self, object: &mut Map<String, Value>) -> Result<()> {
+        self.hash_fields(object, None)
+    }
+
+    /// Hashes the passwords of a document, but for the ones equal to the stored hashes
+    fn hash_fields(&self, object: &mut Map<String, Value>, stored: Option<&Map<String, Value>>) -> Result<()> {
+        for name in &self.fields {
+            if let Some(Value::String(password)) = object.get(name) {
+                if stored.and_then(|stored| stored.get(name)).and_then(Value::as_str) == Some(password.as_str()) {
+                    continue;
+                }
+                let hash = hash_password(password)?;
+                object.insert(name.clone(), Value::String(hash));
+            }
+        }
+        Ok(())
+    }
+
+    /// Hashes the passwords of an entity about to be stored
+    pub fn hash<T: ApiEntity>(&self, item: T) -> Result<T> {
+        self.hash_over(item, None)
+    }
+
+    /// Hashes the passwords of an entity replacing a stored one, keeping the stored hashes that
+    /// `with_stored` copied into it
+    pub fn hash_replacement<T: ApiEntity>(&self, item: T, stored: &T) -> Result<T> {
+        if self.fields.is_empty() {
+            return Ok(item);
+        }
+        match serde_json::to_value(stored)? {
+            Value::Object(stored) => self.hash_over(item, Some(&stored)),
+            _ => self.hash_over(item, None),
+        }
+    }
+
+    fn hash_over<T: ApiEntity>(&self, item: T, stored: Option<&Map<String, Value>>) -> Result<T> {
+        if self.fields.is_empty() {
+            return Ok(item);
+        }
+        match serde_json::to_value(&item)? {
+            Value::Object(mut object) => {
+                self.hash_fields(&mut object, stored)?;
+                Ok(serde_json::from_value(Value::Object(object))?)
+            }
+            _ => Ok(item),
+        }
+    }
+
+    /// Copies the stored passwords into the entity replacing it when it has none, as clients
+    /// never read them back
+    pub fn with_stored<T: ApiEntity>(&self, item: T, stored: &T) -> Result<T> {
+        if self.fields.is_empty() {
+            return Ok(item);
+        }
+        let (Value::Object(mut object), Value::Object(stored)) = (serde_json::to_value(&item)?, serde_json::to_value(stored)?) else {
+            return Ok(item);
+        };
+        for name in &self.fields {
+            if matches!(object.get(name), None | Some(Value::Null)) {
+                if let Some(value) = stored.get(name) {
+                    object.insert(name.clone(), value.clone());
+                }
+            }
+        }
+        Ok(serde_json::from_value(Value::Object(object))?)
+    }
+
+    /// Removes the passwords from an entity, or from a list of them, as JSON
+    pub fn hide(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => object.retain(|name, _| !self.fields.contains(name)),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.hide(item)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn passwords() -> Passwords {
+        Passwords { fields: vec!["password".to_string()] }
+    }
+
+    #[test]
+    fn hashes_client_values_even_when_they_look_like_hashes() {
+        let weak = "$argon2id$v=19$m=8,t=1,p=1$c2FsdHNhbHQ$aGFzaGhhc2g";
+        let item = passwords().hash(json!({ "name": "ana", "password": weak })).unwrap();
+        let stored = item["password"].as_str().unwrap();
+        assert_ne!(stored, weak);
+        assert!(verify_password(weak, stored));
+    }
+
+    #[test]
+    fn keeps_the_stored_hash_of_a_replacement() {
+        let stored = passwords().hash(json!({ "password": "secret" })).unwrap();
+        let replacement = passwords().with_stored(json!({ "name": "ana" }), &stored).unwrap();
+        let replacement = passwords().hash_replacement(replacement, &stored).unwrap();
+        assert_eq!(replacement["password"], stored["password"]);
+        assert!(verify_password("secret", replacement["password"].as_str().unwrap()));
+
+        let changed = passwords().hash_replacement(json!({ "password": "other" }), &stored).unwrap();
+        assert!(verify_password("other", changed["password"].as_str().unwrap()));
+    }
+
+    #[test]
+    fn hides_the_passwords_of_lists() {
+        let mut items = json!([{ "name": "ana", "password": "hash" }, { "name": "luis" }]);
+        passwords().hide(&mut items);
+        assert_eq!(items, json!([{ "name": "ana" }, { "name": "luis" }]));
+    }
+}
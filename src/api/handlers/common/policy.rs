@@ -0,0 +1,178 @@
+use crate::api::handlers::common::authorization::CrudAction;
+use crate::config::specific::entity_config::{Permission, PermissionEffect};
+use tracing::warn;
+
+/// Outcome of evaluating a policy for an action
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    /// A permission allows the action and none denies it
+    Allow,
+    /// A permission denies the action
+    Deny,
+    /// No permission applies to the action
+    NotApplicable,
+}
+
+/// A permission of an entity, resolved to the actions and roles it applies to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    effect: PermissionEffect,
+    /// Actions the rule applies to: all of them for `*`, none for an unknown action
+    actions: Vec<CrudAction>,
+    /// Roles the rule applies to, all callers when empty
+    roles: Vec<String>,
+}
+
+impl Rule {
+    /// Rule of a permission of an entity. The `action` may carry the subject, like `create:users`,
+    /// and a subject that is neither the entity nor `*` names a role, as it did before subjects
+    /// named entities.
+    pub fn new(entity: &str, permission: &Permission) -> Self {
+        let (action, subject) = match permission.action.split_once(':') {
+            Some((action, subject)) if permission.subject.trim().is_empty() => (action.trim(), subject.trim()),
+            _ => (permission.action.trim(), permission.subject.trim()),
+        };
+        let actions = match action {
+            "*" => CrudAction::ALL.to_vec(),
+            name => match CrudAction::from_name(name) {
+                Some(action) => vec![action],
+                None => {
+                    warn!("Permission {}:{} of {} names an unknown action and applies to none", action, subject, entity);
+                    Vec::new()
+                }
+            },
+        };
+        let mut roles = permission.roles.clone();
+        if !subject.is_empty() && subject != "*" && !subject.eq_ignore_ascii_case(entity) {
+            roles.push(subject.to_string());
+        }
+        Self { effect: permission.effect, actions, roles }
+    }
+
+    /// Whether the rule applies to callers with some roles running an action
+    pub fn applies(&self, roles: &[String], action: CrudAction) -> bool {
+        self.actions.contains(&action) && (self.roles.is_empty() || self.roles.iter().any(|role| roles.contains(role)))
+    }
+}
+
+/// Permissions of an entity, evaluated with denials taking precedence over allowances. Once a
+/// permission allows some action, the actions no permission allows are denied; a policy of
+/// denials only denies just the actions they name.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    rules: Vec<Rule>,
+}
+
+impl Policy {
+    pub fn new(entity: &str, permissions: &[Permission]) -> Self {
+        Self { rules: permissions.iter().map(|permission| Rule::new(entity, permission)).collect() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Decision of the permissions for callers with some roles running an action
+    pub fn evaluate(&self, roles: &[String], action: CrudAction) -> Decision {
+        let mut decision = Decision::NotApplicable;
+        for rule in self.rules.iter().filter(|rule| rule.applies(roles, action)) {
+            match rule.effect {
+                PermissionEffect::Deny => return Decision::Deny,
+                PermissionEffect::Allow => decision = Decision::Allow,
+            }
+        }
+        decision
+    }
+
+    /// Whether callers with some roles may run an action
+    pub fn permits(&self, roles: &[String], action: CrudAction) -> bool {
+        match self.evaluate(roles, action) {
+            Decision::Allow => true,
+            Decision::Deny => false,
+            Decision::NotApplicable => !self.rules.iter().any(|rule| rule.effect == PermissionEffect::Allow),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permission(action: &str, subject: &str, roles: &[&str], effect: PermissionEffect) -> Permission {
+        Permission {
+            action: action.to_string(),
+            subject: subject.to_string(),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            effect,
+        }
+    }
+
+    fn roles(roles: &[&str]) -> Vec<String> {
+        roles.iter().map(|role| role.to_string()).collect()
+    }
+
+    #[test]
+    fn wildcard_actions_apply_to_every_action() {
+        let policy = Policy::new("users", &[permission("*", "users", &["admin"], PermissionEffect::Allow)]);
+        for action in CrudAction::ALL {
+            assert!(policy.permits(&roles(&["admin"]), action));
+            assert!(!policy.permits(&roles(&["guest"]), action));
+        }
+    }
+
+    #[test]
+    fn actions_may_carry_their_subject() {
+        let policy = Policy::new("users", &[permission("create:users", "", &[], PermissionEffect::Allow)]);
+        assert!(policy.permits(&[], CrudAction::Create));
+        assert!(!policy.permits(&[], CrudAction::Delete));
+        // A subject carried by the action names a role, like the subject field does
+        let policy = Policy::new("users", &[permission("read:auditor", "", &[], PermissionEffect::Allow)]);
+        assert!(policy.permits(&roles(&["auditor"]), CrudAction::Read));
+        assert!(!policy.permits(&roles(&["guest"]), CrudAction::Read));
+    }
+
+    #[test]
+    fn denials_take_precedence_over_allowances() {
+        let policy = Policy::new("users", &[
+            permission("*", "*", &[], PermissionEffect::Allow),
+            permission("delete", "users", &["intern"], PermissionEffect::Deny),
+        ]);
+        assert_eq!(policy.evaluate(&roles(&["intern", "admin"]), CrudAction::Delete), Decision::Deny);
+        assert!(!policy.permits(&roles(&["intern", "admin"]), CrudAction::Delete));
+        assert!(policy.permits(&roles(&["intern"]), CrudAction::Update));
+        assert!(policy.permits(&roles(&["admin"]), CrudAction::Delete));
+    }
+
+    #[test]
+    fn denials_only_deny_just_their_actions() {
+        let policy = Policy::new("users", &[permission("delete", "users", &[], PermissionEffect::Deny)]);
+        assert_eq!(policy.evaluate(&[], CrudAction::Read), Decision::NotApplicable);
+        assert!(policy.permits(&[], CrudAction::Read));
+        assert!(policy.permits(&[], CrudAction::Create));
+        assert!(!policy.permits(&[], CrudAction::Delete));
+    }
+
+    #[test]
+    fn allowances_deny_the_actions_they_leave_out() {
+        let policy = Policy::new("users", &[permission("read", "users", &[], PermissionEffect::Allow)]);
+        assert!(policy.permits(&[], CrudAction::Read));
+        assert!(!policy.permits(&[], CrudAction::Update));
+    }
+
+    #[test]
+    fn subjects_naming_a_role_apply_to_that_role() {
+        let policy = Policy::new("users", &[permission("update", "editor", &[], PermissionEffect::Allow)]);
+        assert_eq!(policy.rules[0].roles, roles(&["editor"]));
+        assert!(policy.permits(&roles(&["editor"]), CrudAction::Update));
+        assert!(!policy.permits(&roles(&["viewer"]), CrudAction::Update));
+        // The entity and `*` are subjects, whatever their case
+        let policy = Policy::new("users", &[permission("update", "Users", &[], PermissionEffect::Allow)]);
+        assert!(policy.permits(&roles(&["viewer"]), CrudAction::Update));
+    }
+
+    #[test]
+    fn unknown_actions_apply_to_none() {
+        let policy = Policy::new("users", &[permission("publish", "users", &[], PermissionEffect::Deny)]);
+        assert!(CrudAction::ALL.into_iter().all(|action| policy.permits(&[], action)));
+    }
+}
@@ -0,0 +1,36 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Request header holding the identifier of a request, lowercase as captured
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Response header echoing the identifier of the request
+pub const REQUEST_ID_RESPONSE_HEADER: &str = "X-Request-Id";
+/// Member of the error bodies holding the identifier of the request
+pub const REQUEST_ID_FIELD: &str = "request_id";
+/// Maximum length of an identifier sent by a client
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Identifier of a request: the one of its `X-Request-Id` header, so the caller's logs can be
+/// correlated with ours, or a new UUID when it is missing or is not a short printable token
+pub fn request_id(headers: &HashMap<String, String>) -> String {
+    headers.iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(REQUEST_ID_HEADER))
+        .map(|(_, value)| value.trim())
+        .filter(|value| {
+            !value.is_empty() && value.len() <= MAX_REQUEST_ID_LENGTH && value.chars().all(|c| c.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Echoes the identifier of the request in a response, and in the body of error responses
+pub fn tag_response(response: &mut ApiResponse<Value>, request_id: &str) {
+    response.headers.insert(REQUEST_ID_RESPONSE_HEADER.to_string(), request_id.to_string());
+    if response.status < 400 {
+        return;
+    }
+    if let Some(ApiResponseBody::Json(Value::Object(body))) = &mut response.body {
+        body.insert(REQUEST_ID_FIELD.to_string(), Value::String(request_id.to_string()));
+    }
+}
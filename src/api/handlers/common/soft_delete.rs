@@ -0,0 +1,106 @@
+use crate::api::handlers::common::dates::utc_timestamp;
+use crate::config::specific::entity_config::{DataType, Entity};
+use crate::data::datasource::base::{Filter, FilterOperator};
+use crate::error::Result;
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Soft deletion of an entity: deleting it sets its `soft_delete_field` to the time of the deletion
+/// instead of removing it, and the other endpoints skip the entities where the field is set
+#[derive(Default)]
+pub struct SoftDelete {
+    field: Option<String>,
+}
+
+impl SoftDelete {
+    /// Reads the soft delete field of an entity. Fields the entity lacks, or that cannot hold a date
+    /// and time, are reported and soft deletion left disabled.
+    pub fn new(entity: &Entity) -> Self {
+        let Some(name) = &entity.soft_delete_field else {
+            return Self::default();
+        };
+        match entity.fields.iter().find(|field| &field.name == name) {
+            Some(field) if matches!(field.data_type, DataType::DateTime | DataType::String) && field.is_stored() => {
+                Self { field: Some(name.clone()) }
+            }
+            Some(_) => {
+                warn!("Soft deletion of {} is disabled: field {} must be a stored DateTime or String field", entity.name, name);
+                Self::default()
+            }
+            None => {
+                warn!("Soft deletion of {} is disabled: unknown field {}", entity.name, name);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.field.is_some()
+    }
+
+    /// Filter selecting the entities that were not deleted, if soft deletion is enabled
+    pub fn active_filter(&self) -> Option<Filter> {
+        self.field.as_ref().map(|field| Filter { field: field.clone(), operator: FilterOperator::IsNull, value: Value::Null })
+    }
+
+    /// Filter selecting the deleted entities, if soft deletion is enabled
+    pub fn deleted_filter(&self) -> Option<Filter> {
+        self.field.as_ref().map(|field| Filter { field: field.clone(), operator: FilterOperator::NotNull, value: Value::Null })
+    }
+
+    /// Adds the soft delete field to a selection of fields to load, so deleted entities can be told apart
+    pub fn also_load(&self, fields: &mut Vec<String>) {
+        if let Some(field) = &self.field {
+            if !fields.is_empty() && !fields.contains(field) {
+                fields.push(field.clone());
+            }
+        }
+    }
+
+    /// Adds the filter skipping deleted entities to the filters of a query
+    pub fn exclude_deleted(&self, filters: &mut Vec<Filter>) {
+        filters.extend(self.active_filter());
+    }
+
+    /// Whether an entity was deleted
+    pub fn is_deleted<T: ApiEntity>(&self, item: &T) -> Result<bool> {
+        let Some(field) = &self.field else {
+            return Ok(false);
+        };
+        Ok(!matches!(serde_json::to_value(item)?.get(field), None | Some(Value::Null)))
+    }
+
+    /// Hides a deleted entity, as if it did not exist
+    pub fn visible<T: ApiEntity>(&self, item: Option<T>) -> Result<Option<T>> {
+        match item {
+            Some(item) if self.is_deleted(&item)? => Ok(None),
+            item => Ok(item),
+        }
+    }
+
+    /// Marks an entity as deleted now
+    pub fn mark<T: ApiEntity>(&self, item: T) -> Result<T> {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.set(item, Value::String(utc_timestamp(seconds)))
+    }
+
+    /// Clears the deletion mark of an entity
+    pub fn restore<T: ApiEntity>(&self, item: T) -> Result<T> {
+        self.set(item, Value::Null)
+    }
+
+    fn set<T: ApiEntity>(&self, item: T, value: Value) -> Result<T> {
+        let Some(field) = &self.field else {
+            return Ok(item);
+        };
+        match serde_json::to_value(&item)? {
+            Value::Object(mut object) => {
+                object.insert(field.clone(), value);
+                Ok(serde_json::from_value(Value::Object(object))?)
+            }
+            _ => Ok(item),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::api::adapters::api_adapter::ApiRequest;
+use crate::api::handlers::common::filter::filter_value;
+use crate::api::handlers::common::ownership::value_text;
+use crate::config::specific::entity_config::{Entity, Field};
+use crate::data::datasource::base::{Filter, FilterOperator};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::{Map, Value};
+
+/// Tenancy of the rows of an entity: when it has the tenant field of the tenant of a request, the
+/// request only reads and writes the rows of that tenant, and the rows it creates belong to it
+#[derive(Clone, Default)]
+pub struct Tenancy {
+    entity: String,
+    /// Stored fields, one of which may hold the tenant
+    fields: Vec<Field>,
+}
+
+impl Tenancy {
+    pub fn new(entity: &Entity) -> Self {
+        Self {
+            entity: entity.name.clone(),
+            fields: entity.fields.iter().filter(|field| field.is_stored()).cloned().collect(),
+        }
+    }
+
+    /// Tenant field of the entity and the tenant of a request, typed like the field, when the
+    /// request is scoped to a tenant and the entity has the field
+    fn scope(&self, request: &ApiRequest) -> Result<Option<(&Field, Value)>> {
+        let Some(tenant) = &request.tenant else {
+            return Ok(None);
+        };
+        let Some(field) = self.fields.iter().find(|field| field.name == tenant.field) else {
+            return Ok(None);
+        };
+        filter_value(field, FilterOperator::Eq, &tenant.id)
+            .map(|value| Some((field, value)))
+            .map_err(|_| RusterApiError::BadRequest(format!("Tenant '{}' cannot own {}", tenant.id, self.entity)))
+    }
+
+    /// Whether a request only accesses the rows of its tenant
+    pub fn is_scoped(&self, request: &ApiRequest) -> bool {
+        request.tenant.as_ref().is_some_and(|tenant| self.fields.iter().any(|field| field.name == tenant.field))
+    }
+
+    /// Adds the filter selecting the rows of the tenant of a request to the filters of a query
+    pub fn restrict(&self, request: &ApiRequest, filters: &mut Vec<Filter>) -> Result<()> {
+        if let Some((field, tenant)) = self.scope(request)? {
+            filters.push(Filter { field: field.name.clone(), operator: FilterOperator::Eq, value: tenant });
+        }
+        Ok(())
+    }
+
+    /// Adds the tenant field to a selection of fields to load, so the rows of other tenants can be told apart
+    pub fn also_load(&self, request: &ApiRequest, fields: &mut Vec<String>) {
+        if let Some(tenant) = &request.tenant {
+            if !fields.is_empty() && !fields.contains(&tenant.field) && self.is_scoped(request) {
+                fields.push(tenant.field.clone());
+            }
+        }
+    }
+
+    /// Hides a row of another tenant, as if it did not exist
+    pub fn visible<T: ApiEntity>(&self, request: &ApiRequest, item: Option<T>) -> Result<Option<T>> {
+        let Some((field, tenant)) = self.scope(request)? else {
+            return Ok(item);
+        };
+        match item {
+            Some(item) => {
+                let stored = serde_json::to_value(&item)?.get(&field.name).map(value_text);
+                Ok((stored == Some(value_text(&tenant))).then_some(item))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Sets the tenant of a request on a written document, so rows cannot be created for or moved
+    /// to other tenants
+    pub fn assign(&self, request: &ApiRequest, object: &mut Map<String, Value>) -> Result<()> {
+        if let Some((field, tenant)) = self.scope(request)? {
+            object.insert(field.name.clone(), tenant);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::adapters::api_adapter::TenantContext;
+    use crate::config::shared::{EntityBasic, FieldBasic};
+    use crate::config::specific::entity_config::{DataType, HttpMethod};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn tenancy(fields: &[&str]) -> Tenancy {
+        Tenancy::new(&EntityBasic {
+            name: "invoices".to_string(),
+            table_name: None,
+            fields: fields.iter()
+                .map(|name| FieldBasic { name: name.to_string(), data_type: DataType::String, required: false })
+                .collect(),
+            authentication: false,
+        }.to_entity())
+    }
+
+    fn request(tenant: Option<&str>) -> ApiRequest {
+        ApiRequest {
+            method: HttpMethod::GET,
+            path: "invoices".to_string(),
+            params: HashMap::new(),
+            query: HashMap::new(),
+            headers: HashMap::new(),
+            body: None,
+            request_id: "test".to_string(),
+            auth: None,
+            tenant: tenant.map(|id| TenantContext { id: id.to_string(), field: "tenant_id".to_string() }),
+        }
+    }
+
+    #[test]
+    fn shows_the_rows_of_the_tenant_only() {
+        let tenancy = tenancy(&["id", "tenant_id"]);
+        let invoice = json!({ "id": "1", "tenant_id": "acme" });
+        assert_eq!(tenancy.visible(&request(Some("acme")), Some(invoice.clone())).unwrap(), Some(invoice.clone()));
+        assert_eq!(tenancy.visible(&request(Some("globex")), Some(invoice.clone())).unwrap(), None);
+        assert_eq!(tenancy.visible(&request(None), Some(invoice.clone())).unwrap(), Some(invoice));
+    }
+
+    #[test]
+    fn leaves_entities_without_the_tenant_field_unscoped() {
+        let tenancy = tenancy(&["id", "name"]);
+        let currency = json!({ "id": "eur", "name": "Euro" });
+        assert!(!tenancy.is_scoped(&request(Some("acme"))));
+        assert_eq!(tenancy.visible(&request(Some("acme")), Some(currency.clone())).unwrap(), Some(currency));
+    }
+
+    #[test]
+    fn assigns_written_rows_to_the_tenant() {
+        let mut object = json!({ "id": "2", "tenant_id": "globex" }).as_object().cloned().unwrap();
+        tenancy(&["id", "tenant_id"]).assign(&request(Some("acme")), &mut object).unwrap();
+        assert_eq!(object["tenant_id"], json!("acme"));
+
+        let mut object = json!({ "id": "2", "tenant_id": "globex" }).as_object().cloned().unwrap();
+        tenancy(&["id", "tenant_id"]).assign(&request(None), &mut object).unwrap();
+        assert_eq!(object["tenant_id"], json!("globex"));
+    }
+
+    #[test]
+    fn restricts_queries_to_the_tenant() {
+        let mut filters = Vec::new();
+        tenancy(&["id", "tenant_id"]).restrict(&request(Some("acme")), &mut filters).unwrap();
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].field, "tenant_id");
+        assert_eq!(filters[0].value, json!("acme"));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::handlers::common::filter::filter_value;
+use crate::api::handlers::common::utils::handle_datasource_error;
+use crate::config::specific::entity_config::{Entity, Field};
+use crate::data::datasource::base::{DataSource, Filter, FilterOperator};
+use crate::error::{Result, RusterApiError};
+use serde_json::Value;
+
+/// Checks that writes keep the values of the `unique` fields of an entity unique,
+/// so duplicates are reported as conflicts naming the field
+pub struct UniqueFields {
+    entity_name: String,
+    fields: Vec<Field>,
+    key_field: Option<Field>,
+}
+
+impl UniqueFields {
+    pub fn new(entity: &Entity) -> Self {
+        Self {
+            entity_name: entity.name.clone(),
+            fields: entity.fields.iter().filter(|field| field.unique && !field.generated).cloned().collect(),
+            key_field: entity.key_field().cloned(),
+        }
+    }
+
+    /// Fails with a conflict when another entity already holds the value of a unique field.
+    /// `current_id` identifies the entity being updated, which may keep its own values.
+    pub fn check<T: ApiEntity>(&self, datasource: &dyn DataSource<T>, item: &T, current_id: Option<&str>) -> Result<()> {
+        if self.fields.is_empty() {
+            return Ok(());
+        }
+
+        let object = serde_json::to_value(item)?;
+        for field in &self.fields {
+            let value = match object.get(&field.name) {
+                None | Some(Value::Null) => continue,
+                Some(value) => value,
+            };
+            let mut filters = vec![Filter {
+                field: field.name.clone(),
+                operator: FilterOperator::Eq,
+                value: value.clone(),
+            }];
+            if let (Some(id), Some(key_field)) = (current_id, &self.key_field) {
+                filters.push(Filter {
+                    field: key_field.name.clone(),
+                    operator: FilterOperator::Ne,
+                    value: filter_value(key_field, FilterOperator::Ne, id)?,
+                });
+            }
+
+            let duplicates = datasource.count(&filters, Some(&self.entity_name)).map_err(handle_datasource_error)?;
+            if duplicates > 0 {
+                let shown = match value {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                return Err(RusterApiError::Conflict(format!(
+                    "Field '{}' of {} must be unique, '{}' is already used", field.name, self.entity_name, shown
+                )));
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,8 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use crate::config::specific::server_config::ErrorFormat;
+use crate::data::datasource::base::DataSourceError;
 use crate::error::RusterApiError;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 
 /// Returns default headers for API responses
@@ -8,8 +12,107 @@ pub fn default_headers() -> HashMap<String, String> {
     headers
 }
 
-/// Handles errors from the datasource and formats them into an API error
-pub fn handle_datasource_error(err: impl std::fmt::Display) -> RusterApiError {
+/// Maps an API error to the HTTP status code returned to the client
+pub fn status_for_error(err: &RusterApiError) -> u16 {
+    match err {
+        RusterApiError::EntityNotFound(_) => 404,
+        RusterApiError::ValidationError(_) => 400,
+        RusterApiError::BadRequest(_) => 400,
+        RusterApiError::PreconditionFailed(_) => 412,
+        RusterApiError::Conflict(_) => 409,
+        RusterApiError::MethodNotAllowed(_) => 405,
+        RusterApiError::PayloadTooLarge(_) => 413,
+        RusterApiError::UnprocessableEntity(_) => 422,
+        RusterApiError::TooManyRequests(_) => 429,
+        RusterApiError::AuthError(_) => 401,
+        RusterApiError::Forbidden(_) => 403,
+        RusterApiError::InvalidFields(_) => 400,
+        RusterApiError::NotFound(_) => 404,
+        RusterApiError::Timeout(_) => 504,
+        RusterApiError::Maintenance(_) => 503,
+        _ => 500,
+    }
+}
+
+/// Builds the JSON body describing an API error. Validation failures also list the messages of every field.
+pub fn error_body(err: &RusterApiError) -> Value {
+    match err {
+        RusterApiError::InvalidFields(fields) => json!({ "error": err.to_string(), "fields": fields }),
+        _ => json!({ "error": err.to_string() }),
+    }
+}
+
+/// Prefix of the `type` of problem details, followed by the kind of error
+pub const PROBLEM_TYPE_PREFIX: &str = "urn:rawst:problem:";
+
+/// Builds the RFC 7807 problem details of an API error for the request to `instance`.
+/// Validation failures also list the messages of every field in `errors`.
+pub fn problem_details(err: &RusterApiError, instance: &str) -> Value {
+    let (kind, title, detail) = match err {
+        RusterApiError::EndpointGenerationError(detail) => ("endpoint-error", "Endpoint error", detail.clone()),
+        RusterApiError::ConfigError(detail) => ("configuration-error", "Configuration error", detail.clone()),
+        #[cfg(feature = "native")]
+        RusterApiError::DatabaseError(e) => ("database-error", "Database error", e.to_string()),
+        RusterApiError::EntityNotFound(detail) => ("entity-not-found", "Entity not found", detail.clone()),
+        RusterApiError::SerializationError(e) => ("serialization-error", "Serialization error", e.to_string()),
+        RusterApiError::ValidationError(detail) => ("validation-error", "Validation error", detail.clone()),
+        RusterApiError::BadRequest(detail) => ("bad-request", "Bad request", detail.clone()),
+        RusterApiError::PreconditionFailed(detail) => ("precondition-failed", "Precondition failed", detail.clone()),
+        RusterApiError::Conflict(detail) => ("conflict", "Conflict", detail.clone()),
+        RusterApiError::MethodNotAllowed(detail) => ("method-not-allowed", "Method not allowed", detail.clone()),
+        RusterApiError::PayloadTooLarge(detail) => ("payload-too-large", "Payload too large", detail.clone()),
+        RusterApiError::UnprocessableEntity(detail) => ("unprocessable-entity", "Unprocessable entity", detail.clone()),
+        RusterApiError::TooManyRequests(_) => ("too-many-requests", "Too many requests", err.to_string()),
+        RusterApiError::InvalidFields(_) => ("invalid-fields", "Validation failed", err.to_string()),
+        RusterApiError::AuthError(detail) => ("authentication-error", "Authentication error", detail.clone()),
+        RusterApiError::Forbidden(detail) => ("forbidden", "Forbidden", detail.clone()),
+        RusterApiError::IoError(e) => ("io-error", "I/O error", e.to_string()),
+        RusterApiError::NotFound(detail) => ("not-found", "Not found", detail.clone()),
+        RusterApiError::ServerError(detail) => ("server-error", "Server error", detail.clone()),
+        RusterApiError::Timeout(detail) => ("timeout", "Timeout", detail.clone()),
+        RusterApiError::Maintenance(_) => ("maintenance", "Under maintenance", err.to_string()),
+    };
+
+    let mut problem = json!({
+        "type": format!("{}{}", PROBLEM_TYPE_PREFIX, kind),
+        "title": title,
+        "status": status_for_error(err),
+        "detail": detail,
+        "instance": instance,
+    });
+    if let RusterApiError::InvalidFields(fields) = err {
+        problem["errors"] = json!(fields);
+    }
+    problem
+}
+
+/// Builds the response of an API error in an error format, for the request to `instance`.
+/// Exceeded rate limits and maintenance tell the client when to retry in the `Retry-After` header.
+pub fn error_response(err: &RusterApiError, format: ErrorFormat, instance: &str) -> ApiResponse<Value> {
+    let mut headers = default_headers();
+    if let RusterApiError::TooManyRequests(seconds) | RusterApiError::Maintenance(seconds) = err {
+        headers.insert("Retry-After".to_string(), seconds.to_string());
+    }
+    let body = match format {
+        ErrorFormat::Simple => error_body(err),
+        ErrorFormat::Problem => {
+            headers.insert("Content-Type".to_string(), "application/problem+json".to_string());
+            problem_details(err, instance)
+        }
+    };
+    ApiResponse {
+        status: status_for_error(err),
+        headers,
+        body: Some(ApiResponseBody::Json(body)),
+    }
+}
+
+/// Handles errors from the datasource and formats them into an API error.
+/// Duplicate values of unique fields become conflicts.
+pub fn handle_datasource_error(err: Box<dyn std::error::Error>) -> RusterApiError {
+    if let Some(DataSourceError::Conflict(message)) = err.downcast_ref::<DataSourceError>() {
+        return RusterApiError::Conflict(message.clone());
+    }
     let error_message = format!("Error retrieving items: {}", err);
     RusterApiError::EndpointGenerationError(error_message)
 }
\ No newline at end of file
@@ -0,0 +1,146 @@
+use crate::config::specific::entity_config::{Entity, ValidationType};
+use crate::error::{Result, RusterApiError};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// A validation rule of the configuration, with its regular expression compiled
+enum Rule {
+    Length(u32, Option<u32>),
+    Regex(Regex),
+    Email,
+    Numeric,
+    Range(f64, f64),
+}
+
+struct FieldRule {
+    field: String,
+    rule: Rule,
+    error_message: Option<String>,
+}
+
+/// Runs the `validations` of an entity against request bodies.
+/// Missing and null fields are skipped, so partial documents only check the fields they hold.
+#[derive(Default)]
+pub struct EntityValidator {
+    rules: Vec<FieldRule>,
+}
+
+impl EntityValidator {
+    /// Compiles the validation rules of an entity. Rules with an invalid regular expression
+    /// are reported and skipped.
+    pub fn new(entity: &Entity) -> Self {
+        let rules = entity.validations.iter()
+            .filter_map(|validation| {
+                let rule = match &validation.validation_type {
+                    ValidationType::Length(min, max) => Rule::Length(*min, *max),
+                    ValidationType::Regex(pattern) => match Regex::new(pattern) {
+                        Ok(regex) => Rule::Regex(regex),
+                        Err(e) => {
+                            warn!("Skipping regex validation of {}.{}: {}", entity.name, validation.field, e);
+                            return None;
+                        }
+                    },
+                    ValidationType::Email => Rule::Email,
+                    ValidationType::Numeric => Rule::Numeric,
+                    ValidationType::Range(min, max) => Rule::Range(*min, *max),
+                };
+                Some(FieldRule {
+                    field: validation.field.clone(),
+                    rule,
+                    error_message: validation.error_message.clone(),
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Checks a JSON document, failing with the messages of every invalid field
+    pub fn validate(&self, document: &Value) -> Result<()> {
+        let mut errors: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for field_rule in &self.rules {
+            let value = match document.get(&field_rule.field) {
+                None | Some(Value::Null) => continue,
+                Some(value) => value,
+            };
+            if let Some(message) = check(&field_rule.rule, value) {
+                errors.entry(field_rule.field.clone())
+                    .or_default()
+                    .push(field_rule.error_message.clone().unwrap_or(message));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(RusterApiError::InvalidFields(errors))
+        }
+    }
+
+    /// Checks a request body deserialized as an entity
+    pub fn validate_item<T: serde::Serialize>(&self, item: &T) -> Result<()> {
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+        self.validate(&serde_json::to_value(item)?)
+    }
+}
+
+/// Numeric value of a number or of a string holding one
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whether a text looks like an email address: one `@` between a local part and a dotted domain
+fn is_email(text: &str) -> bool {
+    let Some((local, domain)) = text.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && !text.chars().any(char::is_whitespace)
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|label| !label.is_empty())
+}
+
+/// Checks a value against a rule, returning the default message when it fails
+fn check(rule: &Rule, value: &Value) -> Option<String> {
+    match rule {
+        Rule::Length(min, max) => {
+            let length = match value {
+                Value::String(text) => text.chars().count(),
+                Value::Array(items) => items.len(),
+                _ => return Some("must be text".to_string()),
+            } as u64;
+            let too_long = max.is_some_and(|max| length > u64::from(max));
+            if length < u64::from(*min) || too_long {
+                return Some(match max {
+                    Some(max) => format!("must be between {} and {} characters long", min, max),
+                    None => format!("must be at least {} characters long", min),
+                });
+            }
+            None
+        }
+        Rule::Regex(regex) => {
+            let text = match value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            (!regex.is_match(&text)).then(|| format!("must match {}", regex.as_str()))
+        }
+        Rule::Email => {
+            let valid = value.as_str().is_some_and(is_email);
+            (!valid).then(|| "must be a valid email address".to_string())
+        }
+        Rule::Numeric => as_number(value).is_none().then(|| "must be numeric".to_string()),
+        Rule::Range(min, max) => match as_number(value) {
+            Some(number) if number >= *min && number <= *max => None,
+            _ => Some(format!("must be between {} and {}", min, max)),
+        },
+    }
+}
@@ -0,0 +1,320 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::authorization::{CrudAction, EntityAuthorization};
+use crate::api::handlers::common::computed::ServerFields;
+use crate::api::handlers::common::defaults::EntityDefaults;
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::common::ids::IdGenerator;
+use crate::api::handlers::common::immutable::ImmutableFields;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::password::Passwords;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::unique::UniqueFields;
+use crate::api::handlers::common::utils::{default_headers, error_body, handle_datasource_error, status_for_error};
+use crate::api::handlers::common::validation::EntityValidator;
+use crate::api::handlers::hooks::{EntityHooks, HookContext};
+use crate::config::specific::entity_config::Entity;
+use crate::data::datasource::base::{BatchWrite, DataSource, DataSourceError};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Path of the batch endpoint, served next to the entities
+pub const BATCH_PATH: &str = "_batch";
+/// Maximum number of operations of a batch
+const MAX_OPERATIONS: usize = 100;
+/// Status of the operations of a failed batch other than the failing one
+const NOT_APPLIED_STATUS: u16 = 424;
+
+/// An operation of a batch request, tagged by `op`
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Operation {
+    Create { entity: String, body: Value },
+    Update { entity: String, id: Value, body: Value },
+    Delete { entity: String, id: Value },
+}
+
+#[derive(Deserialize)]
+struct BatchRequest {
+    operations: Vec<Operation>,
+}
+
+/// What a batch needs to write an entity the way its create, update and delete endpoints do
+pub struct BatchEntity<T> {
+    name: String,
+    allows_create: bool,
+    allows_update: bool,
+    allows_delete: bool,
+    hooks: EntityHooks<T>,
+    authorization: EntityAuthorization,
+    server_fields: ServerFields,
+    defaults: EntityDefaults,
+    ids: IdGenerator,
+    validator: EntityValidator,
+    unique: UniqueFields,
+    immutable: ImmutableFields,
+    soft_delete: SoftDelete,
+    ownership: Ownership,
+    tenancy: Tenancy,
+    field_access: FieldAccess,
+    passwords: Passwords,
+}
+
+impl<T: ApiEntity> BatchEntity<T> {
+    pub fn new(entity: &Entity, hooks: EntityHooks<T>) -> Self {
+        Self {
+            name: entity.name.clone(),
+            allows_create: entity.endpoints.generate_create,
+            allows_update: entity.endpoints.generate_update,
+            allows_delete: entity.endpoints.generate_delete,
+            hooks,
+            authorization: EntityAuthorization::new(entity),
+            server_fields: ServerFields::new(entity),
+            defaults: EntityDefaults::new(entity),
+            ids: IdGenerator::new(entity),
+            validator: EntityValidator::new(entity),
+            unique: UniqueFields::new(entity),
+            immutable: ImmutableFields::new(entity),
+            soft_delete: SoftDelete::new(entity),
+            ownership: Ownership::new(entity),
+            tenancy: Tenancy::new(entity),
+            field_access: FieldAccess::new(entity),
+            passwords: Passwords::new(entity),
+        }
+    }
+
+    /// Entity written by an operation, as returned to a caller: without the fields they cannot read
+    fn readable_body(&self, request: &ApiRequest, item: T) -> Result<T> {
+        let hidden = self.field_access.hidden(request.auth.as_ref());
+        self.field_access.strip(&hidden, self.server_fields.complete(item)?)
+    }
+}
+
+/// Identifier of an operation, sent as a string or a number
+fn key_text(id: &Value) -> String {
+    match id {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Body of a batch response: whether its writes were applied and the result of each operation
+fn batch_body<T: ApiEntity>(committed: bool, results: Vec<Value>) -> Result<ApiResponseBody<T>> {
+    Ok(ApiResponseBody::Json(serde_json::from_value(json!({ "committed": committed, "results": results }))?))
+}
+
+/// Response of a batch rolled back because the operation at `index` failed. The other operations
+/// are answered with 424, as none of them was applied.
+fn failed_batch<T: ApiEntity>(index: usize, count: usize, err: &RusterApiError) -> Result<ApiResponse<T>> {
+    let results = (0..count)
+        .map(|position| if position == index {
+            json!({ "status": status_for_error(err), "body": error_body(err) })
+        } else {
+            json!({
+                "status": NOT_APPLIED_STATUS,
+                "body": { "error": format!("Not applied: operation {} failed", index) },
+            })
+        })
+        .collect();
+    Ok(ApiResponse {
+        status: status_for_error(err),
+        headers: default_headers(),
+        body: Some(batch_body(false, results)?),
+    })
+}
+
+/// Registers the batch endpoint, applying the creates, updates and deletes of several entities in a
+/// single transaction of the datasource. Each operation is checked like the endpoint it stands for, with
+/// its hooks; updates replace the entity like PUT. When any operation fails none is applied, and the
+/// response carries the status of the failed one. Datasources without transactions answer with 500.
+pub fn register_batch_endpoint<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entities: Vec<BatchEntity<T>>,
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+) where
+    T: ApiEntity,
+{
+    let endpoint_key = format!("POST:{}", BATCH_PATH);
+    let entities: HashMap<String, BatchEntity<T>> = entities.into_iter()
+        .map(|entity| (entity.name.to_lowercase(), entity))
+        .collect();
+
+    let handler = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let body = match &request.body {
+            Some(b) if !b.is_empty() => b,
+            _ => return Err(RusterApiError::BadRequest("Request body is required".to_string())),
+        };
+        let invalid = |e: serde_json::Error| RusterApiError::BadRequest(format!("Invalid request format: {}", e));
+        let batch: BatchRequest = serde_json::from_str(body).map_err(invalid)?;
+        if batch.operations.is_empty() || batch.operations.len() > MAX_OPERATIONS {
+            return Err(RusterApiError::BadRequest(format!(
+                "A batch holds between 1 and {} operations", MAX_OPERATIONS
+            )));
+        }
+        let count = batch.operations.len();
+
+        // Every operation is checked before the transaction starts
+        let mut writes = Vec::with_capacity(count);
+        let mut soft_deletions = Vec::with_capacity(count);
+        for (index, operation) in batch.operations.into_iter().enumerate() {
+            match prepare(&entities, datasource.as_ref(), &request, operation) {
+                Ok((write, soft_deletion)) => {
+                    writes.push(write);
+                    soft_deletions.push(soft_deletion);
+                }
+                Err(err) => return failed_batch(index, count, &err),
+            }
+        }
+
+        let written = match datasource.write_batch(writes.clone()) {
+            Ok(written) => written,
+            Err(failure) => {
+                let err = match failure.error.downcast_ref::<DataSourceError>() {
+                    Some(DataSourceError::NotFound(message)) => RusterApiError::EntityNotFound(message.clone()),
+                    _ => handle_datasource_error(failure.error),
+                };
+                return match failure.index {
+                    Some(index) => failed_batch(index, count, &err),
+                    None => Err(RusterApiError::ServerError(format!("The batch could not run: {}", err))),
+                };
+            }
+        };
+
+        let mut results = Vec::with_capacity(count);
+        for ((write, item), soft_deletion) in writes.iter().zip(written).zip(soft_deletions) {
+            let entity = &entities[&write.entity().to_lowercase()];
+            let result = match (write, item) {
+                (BatchWrite::Update { id, .. }, _) if soft_deletion => {
+                    let context = HookContext { entity: &entity.name, id: Some(id), request: &request };
+                    entity.hooks.after_delete(&context)?;
+                    json!({ "status": 204 })
+                }
+                (BatchWrite::Create { .. }, Some(item)) => {
+                    let context = HookContext { entity: &entity.name, id: None, request: &request };
+                    entity.hooks.after_create(&context, &item)?;
+                    json!({ "status": 201, "body": entity.readable_body(&request, item)? })
+                }
+                (BatchWrite::Update { id, .. }, Some(item)) => {
+                    let context = HookContext { entity: &entity.name, id: Some(id), request: &request };
+                    entity.hooks.after_update(&context, &item)?;
+                    json!({ "status": 200, "body": entity.readable_body(&request, item)? })
+                }
+                (BatchWrite::Delete { id, .. }, _) => {
+                    let context = HookContext { entity: &entity.name, id: Some(id), request: &request };
+                    entity.hooks.after_delete(&context)?;
+                    json!({ "status": 204 })
+                }
+                (_, None) => json!({ "status": 200 }),
+            };
+            results.push(result);
+        }
+
+        Ok(ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(batch_body(true, results)?),
+        })
+    });
+
+    if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+    }
+
+    // Also register with a full API path to handle both cases
+    let api_endpoint_key = format!("POST:api/{}", BATCH_PATH);
+    if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+    }
+}
+
+/// Checks an operation like its endpoint does and runs its `before_*` hooks, returning the write to apply
+/// and whether it soft deletes the entity
+fn prepare<T: ApiEntity>(
+    entities: &HashMap<String, BatchEntity<T>>,
+    datasource: &dyn DataSource<T>,
+    request: &ApiRequest,
+    operation: Operation,
+) -> Result<(BatchWrite<T>, bool)> {
+    let name = match &operation {
+        Operation::Create { entity, .. } | Operation::Update { entity, .. } | Operation::Delete { entity, .. } => entity,
+    };
+    let entity = entities.get(&name.to_lowercase())
+        .ok_or_else(|| RusterApiError::EntityNotFound(format!("Entity not found: {}", name)))?;
+    let invalid = |e: serde_json::Error| RusterApiError::BadRequest(format!("Invalid request format: {}", e));
+
+    match operation {
+        Operation::Create { mut body, .. } => {
+            if !entity.allows_create {
+                return Err(RusterApiError::BadRequest(format!("Entity {} does not allow creating", entity.name)));
+            }
+            entity.authorization.check(request.auth.as_ref(), CrudAction::Create)?;
+            if let Value::Object(object) = &mut body {
+                entity.field_access.check_write(request.auth.as_ref(), object)?;
+                entity.server_fields.strip(object);
+                entity.defaults.apply(object);
+                entity.ids.apply(object);
+                entity.ownership.assign(request.auth.as_ref(), object)?;
+                entity.tenancy.assign(request, object)?;
+            }
+            let item: T = serde_json::from_value(body).map_err(invalid)?;
+            entity.validator.validate_item(&item)?;
+            let context = HookContext { entity: &entity.name, id: None, request };
+            let item = entity.passwords.hash(entity.hooks.before_create(&context, item)?)?;
+            entity.unique.check(datasource, &item, None)?;
+            Ok((BatchWrite::Create { entity: entity.name.clone(), item }, false))
+        }
+        Operation::Update { id, mut body, .. } => {
+            if !entity.allows_update {
+                return Err(RusterApiError::BadRequest(format!("Entity {} does not allow updating", entity.name)));
+            }
+            entity.authorization.check(request.auth.as_ref(), CrudAction::Update)?;
+            let id = key_text(&id);
+            if let Value::Object(object) = &mut body {
+                entity.field_access.check_write(request.auth.as_ref(), object)?;
+                entity.server_fields.strip(object);
+                entity.ownership.assign(request.auth.as_ref(), object)?;
+                entity.tenancy.assign(request, object)?;
+            }
+            let item: T = serde_json::from_value(body).map_err(invalid)?;
+            entity.validator.validate_item(&item)?;
+            let current = entity.soft_delete.visible(datasource.get_by_id(&id, Some(&entity.name)).map_err(handle_datasource_error)?)?;
+            let current = entity.ownership.visible(request.auth.as_ref(), entity.tenancy.visible(request, current)?)?
+                .ok_or_else(|| RusterApiError::EntityNotFound(format!("Item with ID {} not found", id)))?;
+            let current = entity.server_fields.complete(current)?;
+            let item = entity.field_access.with_stored(request.auth.as_ref(), entity.server_fields.with_stored(item, &current)?, &current)?;
+            let item = entity.passwords.with_stored(entity.immutable.check_replacement(item, &current)?, &current)?;
+            let context = HookContext { entity: &entity.name, id: Some(&id), request };
+            let item = entity.passwords.hash_replacement(entity.hooks.before_update(&context, item)?, &current)?;
+            entity.unique.check(datasource, &item, Some(&id))?;
+            Ok((BatchWrite::Update { entity: entity.name.clone(), id, item }, false))
+        }
+        Operation::Delete { id, .. } => {
+            if !entity.allows_delete {
+                return Err(RusterApiError::BadRequest(format!("Entity {} does not allow deleting", entity.name)));
+            }
+            entity.authorization.check(request.auth.as_ref(), CrudAction::Delete)?;
+            let id = key_text(&id);
+            if entity.ownership.is_enabled() || entity.tenancy.is_scoped(request) {
+                let current = entity.soft_delete.visible(datasource.get_by_id(&id, Some(&entity.name)).map_err(handle_datasource_error)?)?;
+                if entity.ownership.visible(request.auth.as_ref(), entity.tenancy.visible(request, current)?)?.is_none() {
+                    return Err(RusterApiError::EntityNotFound(format!("Item with ID {} not found", id)));
+                }
+            }
+            let context = HookContext { entity: &entity.name, id: Some(&id), request };
+            entity.hooks.before_delete(&context)?;
+            if !entity.soft_delete.is_enabled() {
+                return Ok((BatchWrite::Delete { entity: entity.name.clone(), id }, false));
+            }
+            // Soft deletion marks the entity, so it is written like an update
+            let current = entity.soft_delete.visible(datasource.get_by_id(&id, Some(&entity.name)).map_err(handle_datasource_error)?)?
+                .ok_or_else(|| RusterApiError::EntityNotFound(format!("Item with ID {} not found", id)))?;
+            let item = entity.soft_delete.mark(current)?;
+            Ok((BatchWrite::Update { entity: entity.name.clone(), id, item }, true))
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::filter::filter_params;
+use crate::api::handlers::common::immutable::ImmutableFields;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::password::Passwords;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::api::handlers::common::validation::EntityValidator;
+use crate::config::specific::entity_config::Entity;
+use crate::data::datasource::base::DataSource;
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Registers a bulk update endpoint for an entity, applying the partial document in the body
+/// to every entity meeting the filter parameters, as accepted by the list endpoint.
+/// At least one filter is required so a missing parameter cannot update the whole table.
+/// Immutable fields are answered with 400 or dropped, as configured by `immutable_changes`.
+/// Soft deleted entities, and those of other owners, are left unchanged. Passwords are stored hashed.
+pub fn register_bulk_update_endpoint<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+)
+where
+    T: ApiEntity,
+{
+    let base_path = entity.name.clone();
+    let endpoint_key = format!("PATCH:{}", base_path);
+    let entity_name = entity.name.clone();
+    let fields = entity.fields.clone();
+    let immutable = ImmutableFields::new(entity);
+    let validator = EntityValidator::new(entity);
+    let soft_delete = SoftDelete::new(entity);
+    let ownership = Ownership::new(entity);
+    let tenancy = Tenancy::new(entity);
+    let passwords = Passwords::new(entity);
+
+    // Handler for the bulk update endpoint
+    let handler = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let mut filters = filter_params(&request, &fields, &[])?;
+        if filters.is_empty() {
+            return Err(RusterApiError::BadRequest("Bulk updates require at least one filter".to_string()));
+        }
+        soft_delete.exclude_deleted(&mut filters);
+        ownership.restrict(request.auth.as_ref(), &mut filters)?;
+        tenancy.restrict(&request, &mut filters)?;
+
+        let body = match &request.body {
+            Some(b) if !b.is_empty() => b,
+            _ => return Err(RusterApiError::BadRequest("Request body is required".to_string())),
+        };
+        let mut changes: Map<String, Value> = serde_json::from_str(body).map_err(|e| {
+            RusterApiError::BadRequest(format!("Invalid request format: {}", e))
+        })?;
+
+        for name in changes.keys() {
+            match fields.iter().find(|field| field.name == *name) {
+                None => return Err(RusterApiError::BadRequest(format!("Unknown field '{}'", name))),
+                Some(field) if field.generated || !field.updatable || field.read_only || !field.is_stored() => {
+                    return Err(RusterApiError::BadRequest(format!("Field '{}' is not updatable", name)));
+                }
+                Some(_) => {}
+            }
+        }
+        immutable.check_changes(&mut changes)?;
+        ownership.assign(request.auth.as_ref(), &mut changes)?;
+        tenancy.assign(&request, &mut changes)?;
+        validator.validate(&Value::Object(changes.clone()))?;
+        passwords.hash_object(&mut changes)?;
+
+        let updated = datasource.update_matching(&filters, &changes, Some(&entity_name))
+            .map_err(handle_datasource_error)?;
+        Ok(ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Json(serde_json::from_value(json!({ "updated": updated }))?)),
+        })
+    });
+
+    // Handler and endpoint key registration
+    if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+    }
+    
+    // Also register with a full API path to handle both cases
+    let api_endpoint_key = format!("PATCH:api/{}", base_path);
+    if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+    }
+}
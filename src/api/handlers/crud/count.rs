@@ -0,0 +1,78 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::filter::filter_params;
+use crate::api::handlers::common::filter_expression::{filter_expression_param, FILTER_PARAM};
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::config::specific::entity_config::{Entity, Field};
+use crate::data::datasource::base::{DataSource, ListQuery};
+use crate::error::Result;
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Registers a count endpoint for an entity, counting the entities meeting the
+/// filter parameters and filter expression accepted by the list endpoint
+pub fn register_count_endpoint<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+)
+where
+    T: ApiEntity,
+{
+    let base_path = format!("{}/count", entity.name);
+    let endpoint_key = format!("GET:{}", base_path);
+    let handler = count_handler(datasource, entity);
+
+    // Handler and endpoint key registration
+    if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+    }
+    
+    // Also register with a full API path to handle both cases
+    let api_endpoint_key = format!("GET:api/{}", base_path);
+    if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+    }
+}
+
+/// Builds the handler counting the entities meeting the filter parameters and filter expression
+pub fn count_handler<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+) -> EndpointHandler<T>
+where
+    T: ApiEntity,
+{
+    let entity_name = entity.name.clone();
+    // Computed fields are not stored, so they cannot filter
+    let stored: Vec<Field> = entity.fields.iter().filter(|field| field.is_stored()).cloned().collect();
+    let soft_delete = SoftDelete::new(entity);
+    let ownership = Ownership::new(entity);
+    let tenancy = Tenancy::new(entity);
+    let reserved = vec![FILTER_PARAM.to_string()];
+
+    Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let mut filters = filter_params(&request, &stored, &reserved)?;
+        soft_delete.exclude_deleted(&mut filters);
+        ownership.restrict(request.auth.as_ref(), &mut filters)?;
+        tenancy.restrict(&request, &mut filters)?;
+        let count = match filter_expression_param(&request, &stored)? {
+            None => datasource.count(&filters, Some(&entity_name)).map_err(handle_datasource_error)?,
+            // Expressions are part of list queries, so they are counted through the total of an empty page
+            Some(expression) => {
+                let query = ListQuery { limit: Some(0), with_total: true, filters, expression: Some(expression), ..ListQuery::default() };
+                datasource.get_page(&query, Some(&entity_name)).map_err(handle_datasource_error)?.total.unwrap_or(0)
+            }
+        };
+        Ok(ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Json(serde_json::from_value(json!({ "count": count }))?)),
+        })
+    })
+}
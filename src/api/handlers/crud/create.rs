@@ -1,16 +1,113 @@
 use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
-use crate::api::handlers::common::utils::default_headers;
-use crate::config::specific::entity_config::Entity;
+use crate::api::handlers::common::computed::ServerFields;
+use crate::api::handlers::common::defaults::EntityDefaults;
+use crate::api::handlers::common::idempotency::IdempotencyCache;
+use crate::api::handlers::common::ids::IdGenerator;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::password::Passwords;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::unique::UniqueFields;
+use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::api::handlers::common::validation::EntityValidator;
+use crate::api::handlers::hooks::{EntityHooks, HookContext};
+use crate::config::specific::entity_config::{DataType, Entity, Field};
 use crate::data::datasource::base::DataSource;
 use crate::error::{Result, RusterApiError};
 use crate::api::common::api_entity::ApiEntity;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::warn;
 
-/// Registers a create endpoint for an entity
+/// Creates the entities of the request bodies. Read-only and computed fields of the body are
+/// ignored, omitted fields take their default value and a missing key is generated following the
+/// `id_strategy` of the entity, then the body must pass the validations of the entity and goes
+/// through the `before_create` hooks. Entities with an `owner_field` are owned by the caller
+/// creating them, and password fields are stored hashed.
+/// Values already held by another entity in a unique field are answered with 409.
+pub struct EntityCreator<T> {
+    datasource: Box<dyn DataSource<T>>,
+    entity_name: String,
+    key_field: Option<Field>,
+    hooks: EntityHooks<T>,
+    server_fields: ServerFields,
+    defaults: EntityDefaults,
+    ids: IdGenerator,
+    validator: EntityValidator,
+    unique: UniqueFields,
+    ownership: Ownership,
+    tenancy: Tenancy,
+    passwords: Passwords,
+}
+
+impl<T: ApiEntity> EntityCreator<T> {
+    pub fn new(datasource: Box<dyn DataSource<T>>, entity: &Entity, hooks: EntityHooks<T>) -> Self {
+        Self {
+            datasource,
+            entity_name: entity.name.clone(),
+            key_field: entity.key_field().cloned(),
+            hooks,
+            server_fields: ServerFields::new(entity),
+            defaults: EntityDefaults::new(entity),
+            ids: IdGenerator::new(entity),
+            validator: EntityValidator::new(entity),
+            unique: UniqueFields::new(entity),
+            ownership: Ownership::new(entity),
+            tenancy: Tenancy::new(entity),
+            passwords: Passwords::new(entity),
+        }
+    }
+
+    /// Creates the entity of the body of a request, under the given key rather than the one of
+    /// the body, if any, returning it with its computed fields
+    pub fn create(&self, request: &ApiRequest, id: Option<&str>) -> Result<T> {
+        // Validate that we have a request body
+        let body = match &request.body {
+            Some(b) if !b.is_empty() => b,
+            _ => return Err(RusterApiError::BadRequest("Request body is required".to_string())),
+        };
+
+        // Deserialize the request body into the entity type without the fields clients cannot write,
+        // with the defaults of the omitted fields and a generated key when the client sent none
+        let invalid = |e: serde_json::Error| RusterApiError::BadRequest(format!("Invalid request format: {}", e));
+        let mut document: Value = serde_json::from_str(body).map_err(invalid)?;
+        if let Value::Object(object) = &mut document {
+            self.server_fields.strip(object);
+            self.defaults.apply(object);
+            match (id, &self.key_field) {
+                (Some(id), Some(key_field)) => {
+                    let key = match (&key_field.data_type, id.parse::<i64>()) {
+                        (DataType::Integer, Ok(number)) => Value::from(number),
+                        _ => Value::String(id.to_string()),
+                    };
+                    object.insert(key_field.name.clone(), key);
+                }
+                _ => self.ids.apply(object),
+            }
+            self.ownership.assign(request.auth.as_ref(), object)?;
+            self.tenancy.assign(request, object)?;
+        }
+        let new_item: T = serde_json::from_value(document).map_err(invalid)?;
+        self.validator.validate_item(&new_item)?;
+        let context = HookContext { entity: &self.entity_name, id, request };
+        let new_item = self.passwords.hash(self.hooks.before_create(&context, new_item)?)?;
+        self.unique.check(self.datasource.as_ref(), &new_item, None)?;
+
+        // Attempt to create the item in the datasource
+        let created_item = self.datasource.create(new_item, Some(&self.entity_name)).map_err(handle_datasource_error)?;
+        self.hooks.after_create(&context, &created_item)?;
+        self.server_fields.complete(created_item)
+    }
+}
+
+/// Registers a create endpoint for an entity, creating the entities of the request bodies like
+/// `EntityCreator` does.
+/// Requests with an `Idempotency-Key` header are run once, and their retries get the original response.
 pub fn register_create_endpoint<T>(
     datasource: Box<dyn DataSource<T>>,
     entity: &Entity,
+    hooks: EntityHooks<T>,
+    idempotency: IdempotencyCache<T>,
     endpoints: &mut HashMap<String, EndpointHandler<T>>,
 )
 where
@@ -18,47 +115,29 @@ where
 {
     let base_path = entity.name.clone();
     let endpoint_key = format!("POST:{}", base_path);
+    let creator = EntityCreator::new(datasource, entity, hooks);
 
-    // Create a thread-safe clone of the datasource for the handler
-    let ds = datasource.box_clone();
-    let entity_name = entity.name.clone();
+    let create = move |request: &ApiRequest| -> Result<ApiResponse<T>> {
+        Ok(ApiResponse {
+            status: 201,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Single(creator.create(request, None)?)),
+        })
+    };
 
     // Handler for the create endpoint
     let handler = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
-        // Validate that we have a request body
-        let body = match &request.body {
-            Some(b) if !b.is_empty() => b,
-            _ => return Err(RusterApiError::BadRequest("Request body is required".to_string())),
-        };
-
-        // Deserialize the request body into the entity type
-        let new_item: T = serde_json::from_str(body).map_err(|e| {
-            RusterApiError::BadRequest(format!("Invalid request format: {}", e))
-        })?;
-
-        // Attempt to create the item in the datasource
-        match ds.create(new_item, Some(&entity_name)) {
-            Ok(created_item) => {
-                Ok(ApiResponse {
-                    status: 201,
-                    headers: default_headers(),
-                    body: Some(ApiResponseBody::Single(created_item)),
-                })
-            },
-            Err(e) => {
-                Err(RusterApiError::ServerError(format!("Failed to create item: {}", e)))
-            }
-        }
+        idempotency.run(&request, || create(&request))
     });
 
     // Register the handler for this endpoint
     if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", endpoint_key);
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
     }
     
     // Also register with a full API path to handle both cases
     let api_endpoint_key = format!("POST:api/{}", base_path);
     if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
     }
 }
\ No newline at end of file
@@ -1,16 +1,26 @@
 use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, EndpointHandler};
+use crate::api::handlers::common::etag::{check_if_match, entity_tag, IF_MATCH_HEADER};
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
 use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::api::handlers::hooks::{EntityHooks, HookContext};
 use crate::config::specific::entity_config::Entity;
 use crate::data::datasource::base::DataSource;
 use crate::error::{Result, RusterApiError};
 use crate::api::common::api_entity::ApiEntity;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::warn;
 
-/// Registers a delete endpoint for an entity
+/// Registers a delete endpoint for an entity.
+/// An `If-Match` header must match the ETag of the current entity. Deletions run the
+/// `before_delete` and `after_delete` hooks. Entities with a `soft_delete_field` are only marked as deleted,
+/// and entities with an `owner_field` only deleted by their owner.
 pub fn register_delete_endpoint<T>(
     datasource: Box<dyn DataSource<T>>,
     entity: &Entity,
+    hooks: EntityHooks<T>,
     endpoints: &mut HashMap<String, EndpointHandler<T>>,
 ) where
     T: ApiEntity,
@@ -19,6 +29,10 @@ pub fn register_delete_endpoint<T>(
     let endpoint_key = format!("DELETE:{}", base_path);
 
     let entity_name = entity.name.clone();
+    let version_field = entity.version_field.clone();
+    let soft_delete = SoftDelete::new(entity);
+    let ownership = Ownership::new(entity);
+    let tenancy = Tenancy::new(entity);
 
     // Handler for the delete endpoint
     let handler = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
@@ -27,8 +41,32 @@ pub fn register_delete_endpoint<T>(
             .get("id")
             .ok_or_else(|| RusterApiError::ValidationError("ID parameter missing".to_string()))?;
 
-        match datasource.delete(id, Some(&entity_name)) {
+        // The rows of other owners and other tenants are as good as missing
+        let scoped = ownership.is_enabled() || tenancy.is_scoped(&request);
+        if request.headers.contains_key(IF_MATCH_HEADER) || scoped {
+            let current = soft_delete.visible(datasource.get_by_id(id, Some(&entity_name)).map_err(handle_datasource_error)?)?;
+            let current = ownership.visible(request.auth.as_ref(), tenancy.visible(&request, current)?)?;
+            if current.is_none() && scoped {
+                return Err(RusterApiError::EntityNotFound(format!("Item with ID {} not found", id)));
+            }
+            let current_tag = current.map(|item| entity_tag(&item, version_field.as_deref(), false)).transpose()?;
+            check_if_match(&request, current_tag.as_deref())?;
+        }
+
+        let context = HookContext { entity: &entity_name, id: Some(id), request: &request };
+        hooks.before_delete(&context)?;
+        let deleted = if soft_delete.is_enabled() {
+            let current = datasource.get_by_id(id, Some(&entity_name)).map_err(handle_datasource_error)?;
+            match soft_delete.visible(current)? {
+                Some(current) => datasource.update(id, soft_delete.mark(current)?, Some(&entity_name)).map(|_| true),
+                None => Ok(false),
+            }
+        } else {
+            datasource.delete(id, Some(&entity_name))
+        };
+        match deleted {
             Ok(true) => {
+                hooks.after_delete(&context)?;
                 let headers = default_headers();
                 Ok(ApiResponse {
                     status: 204, // No Content
@@ -46,8 +84,8 @@ pub fn register_delete_endpoint<T>(
 
     // Handler and endpoint key registration
     if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!(
-            "Warning: Overwriting existing handler for endpoint key: {}",
+        warn!(
+            "Overwriting existing handler for endpoint key: {}",
             endpoint_key
         );
     }
@@ -55,8 +93,8 @@ pub fn register_delete_endpoint<T>(
     // Also register with a full API path to handle both cases
     let api_endpoint_key = format!("DELETE:api/{}", base_path);
     if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!(
-            "Warning: Overwriting existing handler for endpoint key: {}",
+        warn!(
+            "Overwriting existing handler for endpoint key: {}",
             api_endpoint_key
         );
     }
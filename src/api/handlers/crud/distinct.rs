@@ -0,0 +1,90 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::filter::filter_params;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::config::specific::entity_config::{Entity, Field};
+use crate::data::datasource::base::DataSource;
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Query parameter asking for the number of entities holding each value
+const COUNTS_PARAM: &str = "counts";
+
+/// Registers a distinct values endpoint for an entity. `GET <entity>/distinct/:field` returns the
+/// unique values of a searchable field in ascending order, among the entities meeting the filter
+/// parameters of the list endpoint. With `counts=true` each value comes with its number of entities.
+/// At most `max_results` values are returned.
+pub fn register_distinct_endpoint<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    max_results: u64,
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+)
+where
+    T: ApiEntity,
+{
+    let base_path = format!("{}/distinct/:field", entity.name);
+    let endpoint_key = format!("GET:{}", base_path);
+
+    let entity_name = entity.name.clone();
+    // Computed fields are not stored, so they have no values to select
+    let stored: Vec<Field> = entity.fields.iter().filter(|field| field.is_stored()).cloned().collect();
+    let soft_delete = SoftDelete::new(entity);
+    let ownership = Ownership::new(entity);
+    let tenancy = Tenancy::new(entity);
+    let reserved = vec![COUNTS_PARAM.to_string()];
+
+    let handler = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let name = request
+            .params
+            .get("field")
+            .ok_or_else(|| RusterApiError::ValidationError("Field parameter missing".to_string()))?;
+        let field = stored.iter()
+            .find(|field| &field.name == name)
+            .ok_or_else(|| RusterApiError::BadRequest(format!("Unknown field '{}'", name)))?;
+        if !field.searchable {
+            return Err(RusterApiError::BadRequest(format!("Field '{}' is not searchable", field.name)));
+        }
+        let with_counts = match request.query.get(COUNTS_PARAM) {
+            Some(raw) => raw.parse::<bool>().map_err(|_| {
+                RusterApiError::BadRequest(format!("Query parameter '{}' must be true or false", COUNTS_PARAM))
+            })?,
+            None => false,
+        };
+
+        let mut filters = filter_params(&request, &stored, &reserved)?;
+        soft_delete.exclude_deleted(&mut filters);
+        ownership.restrict(request.auth.as_ref(), &mut filters)?;
+        tenancy.restrict(&request, &mut filters)?;
+        let values = datasource
+            .distinct_values(&field.name, &filters, with_counts, max_results.max(1), Some(&entity_name))
+            .map_err(handle_datasource_error)?;
+        let values: Vec<Value> = if with_counts {
+            values.into_iter().map(|distinct| json!(distinct)).collect()
+        } else {
+            values.into_iter().map(|distinct| distinct.value).collect()
+        };
+        Ok(ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Json(serde_json::from_value(json!({ "field": field.name, "values": values }))?)),
+        })
+    });
+
+    // Handler and endpoint key registration
+    if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+    }
+
+    // Also register with a full API path to handle both cases
+    let api_endpoint_key = format!("GET:api/{}", base_path);
+    if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::computed::ServerFields;
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::common::filter::filter_params;
+use crate::api::handlers::common::filter_expression::{filter_expression_param, FILTER_PARAM};
+use crate::api::handlers::common::negotiation::{render_rows, ListFormat};
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::utils::handle_datasource_error;
+use crate::config::specific::entity_config::{Entity, Field};
+use crate::data::datasource::base::{DataSource, ListQuery};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Query parameter selecting the format of the exported file
+const FORMAT_PARAM: &str = "format";
+/// Number of entities read from the datasource at a time
+const EXPORT_CHUNK_SIZE: u64 = 500;
+
+/// Reads the `format` parameter: `json` (the default), `csv` or `ndjson`
+fn format_param(request: &ApiRequest) -> Result<ListFormat> {
+    match request.query.get(FORMAT_PARAM).map(|format| format.to_lowercase()).as_deref() {
+        None | Some("json") => Ok(ListFormat::Json),
+        Some("csv") => Ok(ListFormat::Csv),
+        Some("ndjson") => Ok(ListFormat::Ndjson),
+        Some(other) => Err(RusterApiError::BadRequest(format!("Unsupported export format '{}'", other))),
+    }
+}
+
+/// Registers an export endpoint for an entity, returning every entity meeting the filter parameters
+/// and filter expression of the list endpoint as a file to download. The `format` parameter picks JSON, CSV or NDJSON.
+/// Entities are read in primary key order, a chunk at a time. The file is built in memory, so exports
+/// of more than `max_results` entities are answered with 413 and have to be narrowed with filters.
+pub fn register_export_endpoint<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    max_results: u64,
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+)
+where
+    T: ApiEntity,
+{
+    let base_path = format!("{}/export", entity.name);
+    let endpoint_key = format!("GET:{}", base_path);
+
+    let entity_name = entity.name.clone();
+    let columns: Vec<String> = entity.fields.iter().map(|field| field.name.clone()).collect();
+    // Computed fields are not stored, so they cannot filter
+    let stored: Vec<Field> = entity.fields.iter().filter(|field| field.is_stored()).cloned().collect();
+    let server_fields = ServerFields::new(entity);
+    let soft_delete = SoftDelete::new(entity);
+    let ownership = Ownership::new(entity);
+    let tenancy = Tenancy::new(entity);
+    let field_access = FieldAccess::new(entity);
+    let reserved = vec![FORMAT_PARAM.to_string(), FILTER_PARAM.to_string()];
+    let max_results = max_results.max(1);
+
+    let handler = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let format = format_param(&request)?;
+        let mut filters = filter_params(&request, &stored, &reserved)?;
+        soft_delete.exclude_deleted(&mut filters);
+        ownership.restrict(request.auth.as_ref(), &mut filters)?;
+        tenancy.restrict(&request, &mut filters)?;
+        let expression = filter_expression_param(&request, &stored)?;
+        // Fields the caller cannot read are left out of the file
+        let hidden = field_access.hidden(request.auth.as_ref());
+
+        let mut items = Vec::new();
+        let mut after = None;
+        loop {
+            let query = ListQuery {
+                limit: Some(EXPORT_CHUNK_SIZE),
+                order_by_key: true,
+                after: after.take(),
+                filters: filters.clone(),
+                expression: expression.clone(),
+                ..ListQuery::default()
+            };
+            let page = datasource.get_page(&query, Some(&entity_name)).map_err(handle_datasource_error)?;
+            // A short chunk is the last one
+            let last = (page.items.len() as u64) < EXPORT_CHUNK_SIZE || page.last_key.is_none();
+            if (items.len() + page.items.len()) as u64 > max_results {
+                return Err(RusterApiError::PayloadTooLarge(format!(
+                    "The export of {} exceeds the limit of {} entities, narrow it with filters",
+                    entity_name, max_results
+                )));
+            }
+            for item in server_fields.complete_all(page.items)? {
+                items.push(field_access.strip(&hidden, item)?);
+            }
+            if last {
+                break;
+            }
+            after = page.last_key;
+        }
+
+        let content = match format {
+            ListFormat::Json => serde_json::to_string(&items)?,
+            _ => {
+                let columns: Vec<String> = columns.iter().filter(|column| !hidden.contains(column)).cloned().collect();
+                render_rows(&items, &columns, format)?
+            }
+        };
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), format!("{}; charset=utf-8", format.media_type()));
+        headers.insert(
+            "Content-Disposition".to_string(),
+            format!("attachment; filename=\"{}.{}\"", entity_name, format.extension()),
+        );
+        Ok(ApiResponse {
+            status: 200,
+            headers,
+            body: Some(ApiResponseBody::Text(content)),
+        })
+    });
+
+    // Handler and endpoint key registration
+    if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+    }
+
+    // Also register with a full API path to handle both cases
+    let api_endpoint_key = format!("GET:api/{}", base_path);
+    if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+    }
+}
@@ -1,52 +1,348 @@
 use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::computed::ServerFields;
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::common::fieldset::{fields_param, project_all, FIELDS_PARAM};
+use crate::api::handlers::common::include::{embed_all, include_param, with_included, INCLUDE_PARAM};
+use crate::api::handlers::common::filter::{filter_params, filter_value};
+use crate::api::handlers::common::filter_expression::{filter_expression_param, FILTER_PARAM};
+use crate::api::handlers::common::http_cache::HttpCache;
+use crate::api::handlers::common::negotiation::{list_format, render_rows, ListFormat};
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
 use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
-use crate::data::datasource::base::DataSource;
-use crate::error::Result;
+use crate::config::specific::entity_config::{Entity, Field, PaginationConfig};
+use crate::data::datasource::base::{DataSource, Filter, FilterOperator, ListQuery, SortField};
+use crate::error::{Result, RusterApiError};
 use crate::api::common::api_entity::ApiEntity;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::{error, warn};
 
-/// Registers a list endpoint for an entity
+/// Query parameter holding the cursor of the page to list
+const CURSOR_PARAM: &str = "after";
+/// Query parameter holding the maximum number of entities of a cursor page
+const CURSOR_LIMIT_PARAM: &str = "limit";
+/// Cursor page size of entities without a pagination configuration
+const DEFAULT_CURSOR_LIMIT: u64 = 20;
+/// Query parameter holding the comma separated fields to sort by, descending when prefixed with `-`
+pub const SORT_PARAM: &str = "sort";
+
+/// Reads a positive integer query parameter, falling back to a default when it is missing
+fn positive_param(request: &ApiRequest, name: &str, default: u64) -> Result<u64> {
+    match request.query.get(name) {
+        Some(value) => match value.parse::<u64>() {
+            Ok(number) if number > 0 => Ok(number),
+            _ => Err(RusterApiError::BadRequest(format!("Query parameter '{}' must be a positive integer", name))),
+        },
+        None => Ok(default),
+    }
+}
+
+/// Parses the `sort` parameter, rejecting fields the entity does not have
+fn sort_param(request: &ApiRequest, fields: &[Field]) -> Result<Vec<SortField>> {
+    let Some(sort) = request.query.get(SORT_PARAM) else {
+        return Ok(Vec::new());
+    };
+
+    sort.split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            let (name, descending) = match field.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (field.strip_prefix('+').unwrap_or(field), false),
+            };
+            if !fields.iter().any(|entity_field| entity_field.name == name) {
+                return Err(RusterApiError::BadRequest(format!("Cannot sort by unknown field '{}'", name)));
+            }
+            Ok(SortField { field: name.to_string(), descending })
+        })
+        .collect()
+}
+
+/// Largest page an entity may return: its `max_page_size`, within the result cap of the server
+fn page_cap(pagination: Option<&PaginationConfig>, max_results: u64) -> u64 {
+    let max_results = max_results.max(1);
+    pagination.map_or(max_results, |p| (p.max_page_size.max(1) as u64).min(max_results))
+}
+
+/// Lists a page of the entity, as selected by the page and size parameters of the configuration.
+/// Sizes above the page cap are reduced to it.
+fn list_page<T: ApiEntity>(
+    datasource: &dyn DataSource<T>,
+    entity_name: &str,
+    pagination: &PaginationConfig,
+    max_results: u64,
+    request: &ApiRequest,
+    query: ListQuery,
+    shape: impl Fn(Vec<T>) -> Result<Vec<T>>,
+) -> Result<ApiResponse<T>> {
+    let cap = page_cap(Some(pagination), max_results);
+    let page = positive_param(request, &pagination.page_param_name, 1)?;
+    let size = positive_param(request, &pagination.size_param_name, pagination.default_page_size.max(1) as u64)?.min(cap);
+    let query = ListQuery {
+        offset: (page - 1).saturating_mul(size),
+        limit: Some(size),
+        with_total: true,
+        ..query
+    };
+
+    let result = datasource.get_page(&query, Some(entity_name)).map_err(handle_datasource_error)?;
+    let total = result.total.unwrap_or(0);
+    Ok(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Page {
+            items: shape(result.items)?,
+            page,
+            size,
+            total,
+            total_pages: total.div_ceil(size),
+        }),
+    })
+}
+
+/// Lists the entities after the primary key encoded in the `after` cursor, in primary key order.
+/// Limits above the page cap are reduced to it.
+fn list_after_cursor<T: ApiEntity>(
+    datasource: &dyn DataSource<T>,
+    entity_name: &str,
+    pagination: Option<&PaginationConfig>,
+    max_results: u64,
+    request: &ApiRequest,
+    query: ListQuery,
+    shape: impl Fn(Vec<T>) -> Result<Vec<T>>,
+) -> Result<ApiResponse<T>> {
+    if !query.sort.is_empty() {
+        return Err(RusterApiError::BadRequest("Sorting cannot be combined with cursor pagination".to_string()));
+    }
+    let default_limit = pagination.map_or(DEFAULT_CURSOR_LIMIT, |p| p.default_page_size.max(1) as u64);
+    let cap = page_cap(pagination, max_results);
+    let limit = positive_param(request, CURSOR_LIMIT_PARAM, default_limit)?.min(cap);
+    let after = request.query.get(CURSOR_PARAM)
+        .map(|cursor| decode_cursor(cursor)
+            .ok_or_else(|| RusterApiError::BadRequest(format!("Invalid cursor: {}", cursor))))
+        .transpose()?;
+    let query = ListQuery {
+        limit: Some(limit),
+        order_by_key: true,
+        after,
+        ..query
+    };
+
+    let result = datasource.get_page(&query, Some(entity_name)).map_err(handle_datasource_error)?;
+    // A short page is the last one
+    let next_cursor = if (result.items.len() as u64) < limit {
+        None
+    } else {
+        result.last_key.as_deref().map(encode_cursor)
+    };
+    Ok(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Cursor {
+            items: shape(result.items)?,
+            limit,
+            next_cursor,
+        }),
+    })
+}
+
+/// Encodes a primary key into an opaque cursor
+fn encode_cursor(key: &str) -> String {
+    URL_SAFE_NO_PAD.encode(key)
+}
+
+/// Decodes the primary key of a cursor
+fn decode_cursor(cursor: &str) -> Option<String> {
+    URL_SAFE_NO_PAD.decode(cursor).ok().and_then(|key| String::from_utf8(key).ok())
+}
+
+/// Writes a list response as CSV or NDJSON, with the columns in the given order.
+/// The pagination details move to `X-` headers, like `X-Total-Count` and `X-Next-Cursor`.
+fn as_rows<T: ApiEntity>(response: ApiResponse<T>, format: ListFormat, columns: &[String]) -> Result<ApiResponse<T>> {
+    let mut headers = response.headers;
+    let items = match response.body {
+        Some(ApiResponseBody::List(items)) => items,
+        Some(ApiResponseBody::Page { items, page, size, total, total_pages }) => {
+            headers.insert("X-Page".to_string(), page.to_string());
+            headers.insert("X-Page-Size".to_string(), size.to_string());
+            headers.insert("X-Total-Count".to_string(), total.to_string());
+            headers.insert("X-Total-Pages".to_string(), total_pages.to_string());
+            items
+        }
+        Some(ApiResponseBody::Cursor { items, limit, next_cursor }) => {
+            headers.insert("X-Limit".to_string(), limit.to_string());
+            if let Some(next_cursor) = next_cursor {
+                headers.insert("X-Next-Cursor".to_string(), next_cursor);
+            }
+            items
+        }
+        body => return Ok(ApiResponse { status: response.status, headers, body }),
+    };
+
+    headers.insert("Content-Type".to_string(), format!("{}; charset=utf-8", format.media_type()));
+    Ok(ApiResponse {
+        status: response.status,
+        headers,
+        body: Some(ApiResponseBody::Text(render_rows(&items, columns, format)?)),
+    })
+}
+
+/// Restricts a list to the children of the entity identified by the `id` path parameter
+#[derive(Clone)]
+pub struct ParentScope {
+    /// Name of the parent entity
+    pub parent_entity: String,
+    /// Field of the listed entity holding the key of the parent
+    pub foreign_key: Field,
+    /// Owners of the parents, whose children are only listed to the owner of the parent
+    pub parent_ownership: Ownership,
+    /// Tenants of the parents, whose children are only listed within the tenant of the parent
+    pub parent_tenancy: Tenancy,
+}
+
+/// Registers a list endpoint for an entity.
+/// Entities with a pagination configuration are listed one page at a time, and any entity
+/// can be listed with a cursor through the `after` and `limit` parameters.
+/// The `sort` parameter orders the entities, except when listing with a cursor, and parameters
+/// named after searchable fields filter them, along with the expression of the `filter` parameter. The `fields` parameter selects the fields returned
+/// and the `include` parameter embeds related entities.
+/// Clients accepting `text/csv` or `application/x-ndjson` get the entities in that format,
+/// with CSV columns in the order of the fields.
+/// Entities with an `http_cache` policy get its caching headers and an ETag of the list.
+/// No request returns more than `max_results` entities, nor pages larger than the `max_page_size` of the entity.
 pub fn register_list_endpoint<T>(
     datasource: Box<dyn DataSource<T>>,
-    base_path: &str,
+    entity: &Entity,
+    all_entities: &[Entity],
+    max_results: u64,
     endpoints: &mut HashMap<String, EndpointHandler<T>>,
 )
 where
     T: ApiEntity,
 {
+    let base_path = entity.name.as_str();
     if base_path.is_empty() || base_path.contains(' ') {
-        eprintln!("Invalid base_path: {}", base_path);
+        error!("Invalid base_path: {}", base_path);
         return;
     }
 
     // Define paths for different scenarios (with and without API prefix)
     // Full path
     let endpoint_key = format!("GET:{}", base_path);
-    let entity_name = base_path.to_string();
-    // Handler for the list endpoint
-    let handler = Arc::new(move |_request: ApiRequest| -> Result<ApiResponse<T>> {
-        match datasource.get_all( Some(&entity_name)) {
-            Ok(items) => {
-                let headers = default_headers();
-                Ok(ApiResponse {
-                    status: 200,
-                    headers,
-                    body: Some(ApiResponseBody::List(items)),
-                })
-            }
-            Err(err) => Err(handle_datasource_error(err)),
-        }
-    });
+    let handler = list_handler(datasource, entity, all_entities, None, max_results);
 
     // Handler and endpoint key registration for the base path
     if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", endpoint_key);
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
     }
     
     // Also register with a full API path to handle both cases
     let api_endpoint_key = format!("GET:api/{}", base_path);
     if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+    }
+}
+
+/// Builds the handler listing an entity, optionally restricted to the children of a parent,
+/// returning at most `max_results` entities
+pub fn list_handler<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    all_entities: &[Entity],
+    scope: Option<ParentScope>,
+    max_results: u64,
+) -> EndpointHandler<T>
+where
+    T: ApiEntity,
+{
+    let entity = entity.clone();
+    let all_entities = all_entities.to_vec();
+    let entity_name = entity.name.clone();
+    let pagination = entity.pagination.clone();
+    let fields = entity.fields.clone();
+    // Computed fields are not stored, so they cannot sort or filter
+    let stored: Vec<Field> = fields.iter().filter(|field| field.is_stored()).cloned().collect();
+    let server_fields = ServerFields::new(&entity);
+    let soft_delete = SoftDelete::new(&entity);
+    let ownership = Ownership::new(&entity);
+    let tenancy = Tenancy::new(&entity);
+    let field_access = FieldAccess::new(&entity);
+    let http_cache = HttpCache::new(&entity);
+    let mut reserved: Vec<String> = [SORT_PARAM, FIELDS_PARAM, INCLUDE_PARAM, CURSOR_PARAM, CURSOR_LIMIT_PARAM, FILTER_PARAM].map(String::from).to_vec();
+    if let Some(pagination) = &pagination {
+        reserved.extend([pagination.page_param_name.clone(), pagination.size_param_name.clone()]);
     }
-}
\ No newline at end of file
+
+    Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let selected = fields_param(&request, &fields)?;
+        let includes = include_param(&request, &entity, &all_entities)?;
+        let mut query = ListQuery {
+            sort: sort_param(&request, &stored)?,
+            filters: filter_params(&request, &stored, &reserved)?,
+            expression: filter_expression_param(&request, &stored)?,
+            // Included relationships and computed fields may need fields left out of the selection, so every field is loaded
+            fields: if includes.is_empty() && !server_fields.computes_any(&selected) { selected.clone() } else { Vec::new() },
+            ..ListQuery::default()
+        };
+        soft_delete.exclude_deleted(&mut query.filters);
+        ownership.restrict(request.auth.as_ref(), &mut query.filters)?;
+        tenancy.restrict(&request, &mut query.filters)?;
+        let returned = with_included(&selected, &includes);
+        let format = list_format(&request);
+        let shape = |items: Vec<T>| {
+            let items = server_fields.complete_all(items)?;
+            project_all(embed_all(datasource.as_ref(), &request, &entity, items, &includes)?, &returned)
+        };
+
+        if let Some(scope) = &scope {
+            let id = request
+                .params
+                .get("id")
+                .ok_or_else(|| RusterApiError::ValidationError("ID parameter missing".to_string()))?;
+            let parent = datasource.get_by_id(id, Some(&scope.parent_entity)).map_err(handle_datasource_error)?;
+            let parent = scope.parent_tenancy.visible(&request, parent)?;
+            if scope.parent_ownership.visible(request.auth.as_ref(), parent)?.is_none() {
+                return Err(RusterApiError::EntityNotFound(format!("Item with ID {} not found", id)));
+            }
+            query.filters.push(Filter {
+                field: scope.foreign_key.name.clone(),
+                operator: FilterOperator::Eq,
+                value: filter_value(&scope.foreign_key, FilterOperator::Eq, id)?,
+            });
+        }
+
+        let response = if request.query.contains_key(CURSOR_PARAM) || request.query.contains_key(CURSOR_LIMIT_PARAM) {
+            list_after_cursor(datasource.as_ref(), &entity_name, pagination.as_ref(), max_results, &request, query, shape)?
+        } else if let Some(pagination) = &pagination {
+            list_page(datasource.as_ref(), &entity_name, pagination, max_results, &request, query, shape)?
+        } else {
+            query.limit = Some(max_results.max(1));
+            let items = datasource.get_page(&query, Some(&entity_name)).map_err(handle_datasource_error)?.items;
+            ApiResponse {
+                status: 200,
+                headers: default_headers(),
+                body: Some(ApiResponseBody::List(shape(items)?)),
+            }
+        };
+
+        if format == ListFormat::Json {
+            return http_cache.respond(&request, response);
+        }
+        // Rows are rendered before the field access of the endpoint runs, so they skip the hidden fields here
+        let columns = if returned.is_empty() {
+            let names: Vec<String> = fields.iter()
+                .filter(|field| field_access.readable(request.auth.as_ref(), &field.name))
+                .map(|field| field.name.clone())
+                .collect();
+            names.into_iter().chain(includes.iter().map(|include| include.name().to_string())).collect()
+        } else {
+            returned.clone()
+        };
+        http_cache.respond(&request, as_rows(response, format, &columns)?)
+    })
+}
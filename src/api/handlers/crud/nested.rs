@@ -0,0 +1,65 @@
+use crate::api::adapters::api_adapter::EndpointHandler;
+use crate::api::handlers::common::authorization::EntityAuthorization;
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::crud::list::{list_handler, ParentScope};
+use crate::config::specific::entity_config::{Entity, RelationshipType};
+use crate::data::datasource::base::DataSource;
+use crate::api::common::api_entity::ApiEntity;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Registers the nested list endpoints of an entity, like `authors/:id/books`, for every
+/// entity with a many-to-one relationship to it. The children are filtered by the
+/// foreign key of the relationship and accept the parameters of their list endpoint. They are
+/// guarded by the authorization of the child, and only list the children of the parents the
+/// caller may see.
+pub fn register_nested_endpoints<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    all_entities: &[Entity],
+    max_results: u64,
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+)
+where
+    T: ApiEntity,
+{
+    for child in all_entities.iter().filter(|child| child.endpoints.generate_list) {
+        let relationships = child.relationships.iter().filter(|relationship| {
+            matches!(relationship.type_, RelationshipType::ManyToOne)
+                && relationship.related_entity.eq_ignore_ascii_case(&entity.name)
+        });
+
+        for relationship in relationships {
+            // The foreign key may name either the field or its column
+            let foreign_key = child.fields.iter().find(|field| {
+                field.name == relationship.foreign_key
+                    || field.column_name.as_deref() == Some(relationship.foreign_key.as_str())
+            });
+            let Some(foreign_key) = foreign_key else {
+                warn!("Skipping nested route for relationship '{}' of {}, no field matches foreign key '{}'",
+                          relationship.name, child.name, relationship.foreign_key);
+                continue;
+            };
+
+            let scope = ParentScope {
+                parent_entity: entity.name.clone(),
+                foreign_key: foreign_key.clone(),
+                parent_ownership: Ownership::new(entity),
+                parent_tenancy: Tenancy::new(entity),
+            };
+            let handler = FieldAccess::new(child).guard(list_handler(datasource.clone(), child, all_entities, Some(scope), max_results));
+            // The children are read, so the authorization of the child applies along with the one of the parent
+            let authorization = EntityAuthorization::new(child);
+
+            let base_path = format!("{}/:id/{}", entity.name, child.name);
+            for endpoint_key in [format!("GET:{}", base_path), format!("GET:api/{}", base_path)] {
+                let handler = if authorization.is_active() { authorization.guard(&endpoint_key, handler.clone()) } else { handler.clone() };
+                if endpoints.insert(endpoint_key.clone(), handler).is_some() {
+                    warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,12 @@
 use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::computed::ServerFields;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::fieldset::{fields_param, project};
+use crate::api::handlers::common::include::{embed_all, include_param, with_included};
+use crate::api::handlers::common::etag::{entity_tag, ETAG_HEADER};
+use crate::api::handlers::common::http_cache::HttpCache;
+use crate::api::handlers::common::tenancy::Tenancy;
 use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
 use crate::config::specific::entity_config::Entity;
 use crate::data::datasource::base::DataSource;
@@ -6,11 +14,17 @@ use crate::error::{Result, RusterApiError};
 use crate::api::common::api_entity::ApiEntity;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::warn;
 
-/// Registers a read endpoint for an entity
+/// Registers a read endpoint for an entity. The `fields` parameter selects the fields returned
+/// and the `include` parameter embeds related entities.
+/// Entities with an `owner_field` are only read by their owner.
+/// Responses carry an ETag and the caching headers of the entity, and a matching `If-None-Match`
+/// or `If-Modified-Since` header is answered with 304.
 pub fn register_read_endpoint<T>(
     datasource: Box<dyn DataSource<T>>,
     entity: &Entity,
+    all_entities: &[Entity],
     endpoints: &mut HashMap<String, EndpointHandler<T>>,
 )
 where
@@ -18,19 +32,66 @@ where
 {
     let base_path = format!("{}/:id", entity.name);
     let endpoint_key = format!("GET:{}", base_path);
+    let handler = read_handler(datasource, entity, all_entities);
+
+    // Handler and endpoint key registration
+    if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+    }
+    
+    // Also register with a full API path to handle both cases
+    let api_endpoint_key = format!("GET:api/{}", base_path);
+    if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+    }
+}
+
+/// Builds the handler reading the entity identified by the `id` path parameter
+pub fn read_handler<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    all_entities: &[Entity],
+) -> EndpointHandler<T>
+where
+    T: ApiEntity,
+{
     let entity_name = entity.name.clone();
+    let fields = entity.fields.clone();
+    let version_field = entity.version_field.clone();
+    let server_fields = ServerFields::new(entity);
+    let soft_delete = SoftDelete::new(entity);
+    let ownership = Ownership::new(entity);
+    let tenancy = Tenancy::new(entity);
+    let http_cache = HttpCache::new(entity);
+    let entity = entity.clone();
+    let all_entities = all_entities.to_vec();
 
-    // Handler for the read endpoint
-    let handler = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+    Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
         let id = request
             .params
             .get("id")
             .ok_or_else(|| RusterApiError::ValidationError("ID parameter missing".to_string()))?;
+        let selected = fields_param(&request, &fields)?;
+        let includes = include_param(&request, &entity, &all_entities)?;
+        // Included relationships and computed fields may need fields left out of the selection, so every field is loaded
+        let mut loaded = if includes.is_empty() && !server_fields.computes_any(&selected) { selected.clone() } else { Vec::new() };
+        soft_delete.also_load(&mut loaded);
+        ownership.also_load(&mut loaded);
+        tenancy.also_load(&request, &mut loaded);
 
-        match datasource.get_by_id(id, Some(&entity_name)) {
+        let found = datasource.get_fields_by_id(id, &loaded, Some(&entity_name)).map_err(handle_datasource_error);
+        let found = found.and_then(|item| tenancy.visible(&request, soft_delete.visible(item)?));
+        match found.and_then(|item| ownership.visible(request.auth.as_ref(), item)) {
             Ok(Some(item)) => {
-                let headers = default_headers();
-                Ok(ApiResponse {
+                let item = server_fields.complete(item)?;
+                let item = embed_all(datasource.as_ref(), &request, &entity, vec![item], &includes)?.remove(0);
+                let item = project(item, &with_included(&selected, &includes))?;
+                // Partial or expanded representations get weak tags, so they cannot be used with If-Match
+                let reshaped = !selected.is_empty() || !includes.is_empty();
+                let etag = entity_tag(&item, version_field.as_deref(), reshaped)?;
+                let mut headers = default_headers();
+                headers.insert(ETAG_HEADER.to_string(), etag);
+                http_cache.respond(&request, ApiResponse {
                     status: 200,
                     headers,
                     body: Some(ApiResponseBody::Single(item)),
@@ -40,18 +101,7 @@ where
                 "Item with ID {} not found",
                 id
             ))),
-            Err(err) => Err(handle_datasource_error(err)),
+            Err(err) => Err(err),
         }
-    });
-
-    // Handler and endpoint key registration
-    if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", endpoint_key);
-    }
-    
-    // Also register with a full API path to handle both cases
-    let api_endpoint_key = format!("GET:api/{}", base_path);
-    if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", api_endpoint_key);
-    }
-}
\ No newline at end of file
+    })
+}
@@ -0,0 +1,114 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, EndpointHandler};
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::config::specific::entity_config::{Entity, Relationship, RelationshipType};
+use crate::data::datasource::base::DataSource;
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Registers the link and unlink endpoints of the many-to-many relationships of an entity:
+/// `POST {entity}/:id/{relationship}/:related_id` and `DELETE {entity}/:id/{relationship}/:related_id`.
+/// Entities with an `owner_field` are only linked by their owner, and entities with the tenant
+/// field only to the entities of their tenant.
+pub fn register_relationship_endpoints<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    all_entities: &[Entity],
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+)
+where
+    T: ApiEntity,
+{
+    let many_to_many = entity.relationships.iter()
+        .filter(|r| matches!(r.type_, RelationshipType::ManyToMany) && r.join_table.is_some());
+
+    for relationship in many_to_many {
+        let base_path = format!("{}/:id/{}/:related_id", entity.name, relationship.name);
+
+        let related = all_entities.iter().find(|related| related.name.eq_ignore_ascii_case(&relationship.related_entity));
+        let related_tenancy = related.map(Tenancy::new).unwrap_or_default();
+        let link_handler = relationship_handler(datasource.clone(), entity, relationship, related_tenancy.clone(), true);
+        let unlink_handler = relationship_handler(datasource.clone(), entity, relationship, related_tenancy, false);
+
+        for (method, handler) in [("POST", link_handler), ("DELETE", unlink_handler)] {
+            // Also register with a full API path to handle both cases
+            for endpoint_key in [format!("{}:{}", method, base_path), format!("{}:api/{}", method, base_path)] {
+                if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+                    warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+                }
+            }
+        }
+    }
+}
+
+/// Builds the handler linking (or unlinking) both entities of a relationship
+fn relationship_handler<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    relationship: &Relationship,
+    related_tenancy: Tenancy,
+    link: bool,
+) -> EndpointHandler<T>
+where
+    T: ApiEntity,
+{
+    let entity_name = entity.name.clone();
+    let relationship_name = relationship.name.clone();
+    let related_entity = relationship.related_entity.clone();
+    let ownership = Ownership::new(entity);
+    let tenancy = Tenancy::new(entity);
+
+    Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let id = request
+            .params
+            .get("id")
+            .ok_or_else(|| RusterApiError::ValidationError("ID parameter missing".to_string()))?;
+        let related_id = request
+            .params
+            .get("related_id")
+            .ok_or_else(|| RusterApiError::ValidationError("Related ID parameter missing".to_string()))?;
+
+        // Both sides must exist in the tenant of the request before touching the join table, and
+        // the entity must be owned by the caller
+        let sides = [(&entity_name, id, &ownership, &tenancy), (&related_entity, related_id, &Ownership::default(), &related_tenancy)];
+        for (name, key, ownership, tenancy) in sides {
+            let found = datasource.get_by_id(key, Some(name)).map_err(handle_datasource_error);
+            match found.and_then(|item| ownership.visible(request.auth.as_ref(), tenancy.visible(&request, item)?)) {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return Err(RusterApiError::EntityNotFound(format!("{} with ID {} not found", name, key)));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        let status = if link {
+            match datasource.link_related(id, &relationship_name, related_id, Some(&entity_name)) {
+                Ok(true) => 201,  // Created
+                Ok(false) => 200, // Already linked
+                Err(err) => return Err(handle_datasource_error(err)),
+            }
+        } else {
+            match datasource.unlink_related(id, &relationship_name, related_id, Some(&entity_name)) {
+                Ok(true) => 204, // No Content
+                Ok(false) => {
+                    return Err(RusterApiError::EntityNotFound(format!(
+                        "{} with ID {} is not linked to {} with ID {}",
+                        entity_name, id, related_entity, related_id
+                    )));
+                }
+                Err(err) => return Err(handle_datasource_error(err)),
+            }
+        };
+
+        Ok(ApiResponse {
+            status,
+            headers: default_headers(),
+            body: None,
+        })
+    })
+}
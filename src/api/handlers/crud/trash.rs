@@ -0,0 +1,96 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::computed::ServerFields;
+use crate::api::handlers::common::filter::filter_params;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::tenancy::Tenancy;
+use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::config::specific::entity_config::{Entity, Field};
+use crate::data::datasource::base::{DataSource, ListQuery, SortField};
+use crate::error::{Result, RusterApiError};
+use crate::api::common::api_entity::ApiEntity;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Registers the trash endpoints of an entity with a `soft_delete_field`.
+/// `GET <entity>/trash` lists the deleted entities meeting the filter parameters, last deleted first,
+/// returning at most `max_results` of them. `POST <entity>/:id/restore` clears the deletion mark of
+/// an entity; restoring an entity that is not deleted leaves it unchanged. Entities with an
+/// `owner_field` are only listed and restored by their owner, and those with the tenant field by their tenant.
+pub fn register_trash_endpoints<T>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    max_results: u64,
+    endpoints: &mut HashMap<String, EndpointHandler<T>>,
+)
+where
+    T: ApiEntity,
+{
+    let soft_delete = Arc::new(SoftDelete::new(entity));
+    let Some(deleted_filter) = soft_delete.deleted_filter() else {
+        return;
+    };
+    let entity_name = entity.name.clone();
+    let server_fields = Arc::new(ServerFields::new(entity));
+    let ownership = Arc::new(Ownership::new(entity));
+    let tenancy = Arc::new(Tenancy::new(entity));
+    // Computed fields are not stored, so they cannot filter
+    let stored: Vec<Field> = entity.fields.iter().filter(|field| field.is_stored()).cloned().collect();
+
+    let ds = datasource.clone();
+    let name = entity_name.clone();
+    let fields = server_fields.clone();
+    let owners = ownership.clone();
+    let tenants = tenancy.clone();
+    let trash_handler: EndpointHandler<T> = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let mut filters = filter_params(&request, &stored, &[])?;
+        filters.push(deleted_filter.clone());
+        owners.restrict(request.auth.as_ref(), &mut filters)?;
+        tenants.restrict(&request, &mut filters)?;
+        let query = ListQuery {
+            limit: Some(max_results.max(1)),
+            sort: vec![SortField { field: deleted_filter.field.clone(), descending: true }],
+            filters,
+            ..ListQuery::default()
+        };
+        let items = ds.get_page(&query, Some(&name)).map_err(handle_datasource_error)?.items;
+        Ok(ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::List(fields.complete_all(items)?)),
+        })
+    });
+
+    let restore_handler: EndpointHandler<T> = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let id = request
+            .params
+            .get("id")
+            .ok_or_else(|| RusterApiError::ValidationError("ID parameter missing".to_string()))?;
+
+        let current = tenancy.visible(&request, datasource.get_by_id(id, Some(&entity_name)).map_err(handle_datasource_error)?)?;
+        let current = ownership.visible(request.auth.as_ref(), current)?
+            .ok_or_else(|| RusterApiError::EntityNotFound(format!("Item with ID {} not found", id)))?;
+        let item = if soft_delete.is_deleted(&current)? {
+            datasource.update(id, soft_delete.restore(current)?, Some(&entity_name)).map_err(handle_datasource_error)?
+        } else {
+            current
+        };
+        Ok(ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Single(server_fields.complete(item)?)),
+        })
+    });
+
+    for (method, base_path, handler) in [
+        ("GET", format!("{}/trash", entity.name), trash_handler),
+        ("POST", format!("{}/:id/restore", entity.name), restore_handler),
+    ] {
+        for endpoint_key in [format!("{}:{}", method, base_path), format!("{}:api/{}", method, base_path)] {
+            if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+                warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+            }
+        }
+    }
+}
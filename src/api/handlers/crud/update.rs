@@ -1,16 +1,43 @@
 use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::handlers::common::authorization::{CrudAction, EntityAuthorization};
+use crate::api::handlers::common::computed::ServerFields;
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::common::immutable::ImmutableFields;
+use crate::api::handlers::common::ownership::Ownership;
+use crate::api::handlers::common::password::Passwords;
+use crate::api::handlers::common::soft_delete::SoftDelete;
+use crate::api::handlers::common::etag::{check_if_match, entity_tag, ETAG_HEADER};
+use crate::api::handlers::common::tenancy::Tenancy;
 use crate::api::handlers::common::utils::{default_headers, handle_datasource_error};
+use crate::api::handlers::common::unique::UniqueFields;
+use crate::api::handlers::common::validation::EntityValidator;
+use crate::api::handlers::crud::create::EntityCreator;
+use crate::api::handlers::hooks::{EntityHooks, HookContext};
 use crate::config::specific::entity_config::Entity;
 use crate::data::datasource::base::DataSource;
 use crate::error::{Result, RusterApiError};
 use crate::api::common::api_entity::ApiEntity;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::warn;
 
-/// Registers an update endpoint for an entity
+/// Registers an update endpoint for an entity.
+/// With `upsert_on_put`, updating a missing ID creates the entity like the create endpoint instead
+/// of answering 404, when the caller may create entities. IDs of entities the caller cannot see,
+/// like deleted ones or those of other owners or tenants, are answered with 404 still.
+/// An `If-Match` header must match the ETag of the current entity, and the body must pass the validations of the entity.
+/// Read-only and computed fields of the body are ignored, and updates keep the stored read-only values
+/// and those of the fields the caller cannot write. Password fields are stored hashed, keeping the
+/// stored password when the body has none.
+/// Changes to immutable fields are answered with 400 or dropped, as configured by `immutable_changes`.
+/// Updates run the `before_update` and `after_update` hooks.
+/// Entities with an `owner_field` are only updated by their owner, who stays their owner.
+/// Values already held by another entity in a unique field are answered with 409.
 pub fn register_update_endpoint<T>(
     datasource: Box<dyn DataSource<T>>,
     entity: &Entity,
+    hooks: EntityHooks<T>,
     endpoints: &mut HashMap<String, EndpointHandler<T>>,
 )
 where
@@ -19,6 +46,17 @@ where
     let base_path = format!("{}/:id", entity.name);
     let endpoint_key = format!("PUT:{}", base_path);
     let entity_name = entity.name.clone();
+    let upsert = entity.endpoints.upsert_on_put.then(|| (EntityCreator::new(datasource.clone(), entity, hooks.clone()), EntityAuthorization::new(entity)));
+    let version_field = entity.version_field.clone();
+    let server_fields = ServerFields::new(entity);
+    let immutable = ImmutableFields::new(entity);
+    let validator = EntityValidator::new(entity);
+    let unique = UniqueFields::new(entity);
+    let soft_delete = SoftDelete::new(entity);
+    let ownership = Ownership::new(entity);
+    let tenancy = Tenancy::new(entity);
+    let field_access = FieldAccess::new(entity);
+    let passwords = Passwords::new(entity);
 
     // Handler for the update endpoint
     let handler = Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
@@ -32,17 +70,36 @@ where
             _ => return Err(RusterApiError::BadRequest("Request body is required".to_string())),
         };
 
-        let updated_item: T = serde_json::from_str(body).map_err(|e| {
-            RusterApiError::BadRequest(format!("Invalid request format: {}", e))
-        })?;
+        // First check if the item exists, soft deleted items and those of other owners or tenants being as good as missing
+        let stored = datasource.get_by_id(id, Some(&entity_name)).map_err(handle_datasource_error)?;
+        let exists = stored.is_some();
+        let found = tenancy.visible(&request, soft_delete.visible(stored)?)?;
+        match ownership.visible(request.auth.as_ref(), found)? {
+            Some(current) => {
+                let invalid = |e: serde_json::Error| RusterApiError::BadRequest(format!("Invalid request format: {}", e));
+                let mut document: Value = serde_json::from_str(body).map_err(invalid)?;
+                if let Value::Object(object) = &mut document {
+                    server_fields.strip(object);
+                    ownership.assign(request.auth.as_ref(), object)?;
+                    tenancy.assign(&request, object)?;
+                }
+                let updated_item: T = serde_json::from_value(document).map_err(invalid)?;
+                validator.validate_item(&updated_item)?;
 
-        // First check if the item exists
-        match datasource.get_by_id(id, Some(&entity_name)) {
-            Ok(Some(_)) => {
-                // Item exists, proceed with update
+                // Item exists, proceed with update unless the client edited another version
+                let current = server_fields.complete(current)?;
+                check_if_match(&request, Some(&entity_tag(&current, version_field.as_deref(), false)?))?;
+                let context = HookContext { entity: &entity_name, id: Some(id), request: &request };
+                let updated_item = field_access.with_stored(request.auth.as_ref(), server_fields.with_stored(updated_item, &current)?, &current)?;
+                let updated_item = passwords.with_stored(immutable.check_replacement(updated_item, &current)?, &current)?;
+                let updated_item = passwords.hash_replacement(hooks.before_update(&context, updated_item)?, &current)?;
+                unique.check(datasource.as_ref(), &updated_item, Some(id))?;
                 match datasource.update(id, updated_item , Some(&entity_name)) {
                     Ok(item) => {
-                        let headers = default_headers();
+                        hooks.after_update(&context, &item)?;
+                        let item = server_fields.complete(item)?;
+                        let mut headers = default_headers();
+                        headers.insert(ETAG_HEADER.to_string(), entity_tag(&item, version_field.as_deref(), false)?);
                         Ok(ApiResponse {
                             status: 200,
                             headers,
@@ -52,25 +109,34 @@ where
                     Err(err) => Err(handle_datasource_error(err)),
                 }
             }
-            Ok(None) => {
-                // Item doesn't exist
-                Err(RusterApiError::EntityNotFound(format!(
-                    "Item with ID {} not found",
-                    id
-                )))
-            }
-            Err(err) => Err(handle_datasource_error(err)),
+            // Items the caller cannot see are not replaced, nor told apart from missing ones
+            None => match upsert.as_ref().filter(|_| !exists) {
+                Some((creator, authorization)) => {
+                    // Item doesn't exist, create it under the ID of the path
+                    check_if_match(&request, None)?;
+                    authorization.check(request.auth.as_ref(), CrudAction::Create)?;
+                    let item = creator.create(&request, Some(id))?;
+                    let mut headers = default_headers();
+                    headers.insert(ETAG_HEADER.to_string(), entity_tag(&item, version_field.as_deref(), false)?);
+                    Ok(ApiResponse {
+                        status: 201,
+                        headers,
+                        body: Some(ApiResponseBody::Single(item)),
+                    })
+                }
+                None => Err(RusterApiError::EntityNotFound(format!("Item with ID {} not found", id))),
+            },
         }
     });
 
     // Handler and endpoint key registration
     if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", endpoint_key);
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
     }
     
     // Also register with a full API path to handle both cases
     let api_endpoint_key = format!("PUT:api/{}", base_path);
     if endpoints.insert(api_endpoint_key.clone(), handler.clone()).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", api_endpoint_key);
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
     }
 }
\ No newline at end of file
@@ -1,50 +1,153 @@
 use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, EndpointHandler};
-use crate::api::handlers::common::utils::default_headers;
-use crate::config::specific::entity_config::{CustomRoute, Entity, HttpMethod};
+use crate::api::handlers::crud::count::count_handler;
+use crate::api::handlers::crud::list::list_handler;
+use crate::api::handlers::crud::read::read_handler;
+#[cfg(feature = "scripting")]
+use crate::api::handlers::custom::script::{script_handler, Script};
+use crate::config::specific::entity_config::{CustomRoute, Entity};
 use crate::data::datasource::base::DataSource;
-use crate::error::Result;
+use crate::error::{Result, RusterApiError};
 use crate::api::common::api_entity::ApiEntity;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::warn;
 
-/// Registers a custom endpoint for an entity
-pub fn register_custom_endpoint<T>(
+/// Prefix of the handlers running a script of the scripts directory, like `script:reports/summary.rhai`
+pub const SCRIPT_HANDLER_PREFIX: &str = "script:";
+
+/// Built-in operation run by a custom route template
+#[derive(Debug, Clone, Copy)]
+enum TemplateAction {
+    /// Lists the entities, like the list endpoint
+    List,
+    /// Counts the entities, like the count endpoint
+    Count,
+    /// Reads the entity identified by the `id` path parameter, like the read endpoint
+    Get,
+}
+
+/// A datasource-backed handler parsed from the `handler` string of a custom route,
+/// like `list?status=active&fields=id,name&sort=-created_at`.
+///
+/// The query parameters of the template are fixed: they replace the parameters of the request
+/// with the same name. Values starting with `:` are taken from the path parameters of the route.
+#[derive(Debug, Clone)]
+struct RouteTemplate {
+    action: TemplateAction,
+    params: Vec<(String, String)>,
+}
+
+impl RouteTemplate {
+    fn parse(handler: &str) -> std::result::Result<Self, String> {
+        let (action, query) = handler.trim().split_once('?').unwrap_or((handler.trim(), ""));
+        let action = match action.to_lowercase().as_str() {
+            "list" => TemplateAction::List,
+            "count" => TemplateAction::Count,
+            "get" => TemplateAction::Get,
+            other => return Err(format!("unknown handler '{}', expected list, count or get", other)),
+        };
+
+        let mut params = Vec::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=')
+                .ok_or_else(|| format!("parameter '{}' has no value", pair))?;
+            params.push((name.to_string(), value.to_string()));
+        }
+        Ok(Self { action, params })
+    }
+
+    /// Applies the fixed parameters of the template to a request
+    fn apply(&self, mut request: ApiRequest) -> Result<ApiRequest> {
+        for (name, value) in &self.params {
+            let value = match value.strip_prefix(':') {
+                Some(param) => request.params.get(param).cloned().ok_or_else(|| {
+                    RusterApiError::ValidationError(format!("{} parameter missing", param))
+                })?,
+                None => value.clone(),
+            };
+            request.query.insert(name.clone(), value);
+        }
+        Ok(request)
+    }
+}
+
+/// Builds the handler running a template over the datasource
+fn template_handler<T: ApiEntity>(
+    datasource: Box<dyn DataSource<T>>,
+    entity: &Entity,
+    all_entities: &[Entity],
+    max_results: u64,
+    handler: &str,
+) -> std::result::Result<EndpointHandler<T>, String> {
+    let template = RouteTemplate::parse(handler)?;
+    let inner = match template.action {
+        TemplateAction::List => list_handler(datasource, entity, all_entities, None, max_results),
+        TemplateAction::Count => count_handler(datasource, entity),
+        TemplateAction::Get => read_handler(datasource, entity, all_entities),
+    };
+    Ok(Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        inner(template.apply(request)?)
+    }))
+}
+
+/// Builds the handler running a script of the scripts directory
+#[cfg(feature = "scripting")]
+fn script_route_handler<T: ApiEntity>(
+    datasource: Box<dyn DataSource<T>>,
+    scripts_dir: &str,
+    script: &str,
+) -> std::result::Result<EndpointHandler<T>, String> {
+    let script = Script::load(&std::path::Path::new(scripts_dir).join(script.trim()), datasource)
+        .map_err(|e| e.to_string())?;
+    Ok(script_handler(script))
+}
+
+#[cfg(not(feature = "scripting"))]
+fn script_route_handler<T: ApiEntity>(
     _datasource: Box<dyn DataSource<T>>,
+    _scripts_dir: &str,
+    _script: &str,
+) -> std::result::Result<EndpointHandler<T>, String> {
+    Err("scripts require the scripting feature".to_string())
+}
+
+/// Registers a custom endpoint for an entity, running the template of its `handler` string,
+/// or the script it names after `script:`. Routes with an invalid handler are reported and left unregistered.
+pub fn register_custom_endpoint<T>(
+    datasource: Box<dyn DataSource<T>>,
     entity: &Entity,
+    all_entities: &[Entity],
+    max_results: u64,
+    scripts_dir: &str,
     custom_route: &CustomRoute,
     endpoints: &mut HashMap<String, EndpointHandler<T>>,
 )
 where
     T: ApiEntity,
 {
-    let path = format!("{}{}", entity.name, custom_route.path);
+    let path = format!("{}/{}", entity.name, custom_route.path.trim_matches('/'));
     let endpoint_key = format!("{:?}:{}", custom_route.method, path);
 
-    let method = custom_route.method.clone();
-
-    let handler = Arc::new(move |_request: ApiRequest| -> Result<ApiResponse<T>> {
-        match method {
-            HttpMethod::GET => {
-            }
-            HttpMethod::POST => {
-            }
-            HttpMethod::PUT => {
-            }
-            HttpMethod::DELETE => {
-            }
-            HttpMethod::PATCH => {
-            }
+    let handler = match custom_route.handler.trim().strip_prefix(SCRIPT_HANDLER_PREFIX) {
+        Some(script) => script_route_handler(datasource, scripts_dir, script),
+        None => template_handler(datasource, entity, all_entities, max_results, &custom_route.handler),
+    };
+    let handler = match handler {
+        Ok(handler) => handler,
+        Err(e) => {
+            warn!("Skipping custom route {}: {}", endpoint_key, e);
+            return;
         }
-
-        Ok(ApiResponse {
-            status: 200,
-            headers: default_headers(),
-            body: None,
-        })
-    });
+    };
 
     // Handler and endpoint key registration
-    if endpoints.insert(endpoint_key.clone(), handler).is_some() {
-        eprintln!("Warning: Overwriting existing handler for endpoint key: {}", endpoint_key);
+    if endpoints.insert(endpoint_key.clone(), handler.clone()).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", endpoint_key);
+    }
+
+    // Also register with a full API path to handle both cases
+    let api_endpoint_key = format!("{:?}:api/{}", custom_route.method, path);
+    if endpoints.insert(api_endpoint_key.clone(), handler).is_some() {
+        warn!("Overwriting existing handler for endpoint key: {}", api_endpoint_key);
     }
 }
@@ -0,0 +1,221 @@
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody, EndpointHandler};
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::handlers::common::utils::default_headers;
+use crate::data::datasource::base::{DataSource, Filter, FilterOperator, ListQuery};
+use crate::error::{Result, RusterApiError};
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Maximum number of operations a script may run per call, so a runaway loop cannot hold a worker
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+/// Response built by a script with `response(status)`, `.header(name, value)` and `.body(value)`
+#[derive(Debug, Clone)]
+pub struct ScriptResponse {
+    status: i64,
+    headers: Map,
+    body: Dynamic,
+}
+
+impl ScriptResponse {
+    fn new(status: i64) -> Self {
+        Self { status, headers: Map::new(), body: Dynamic::UNIT }
+    }
+}
+
+/// A compiled Rhai script with access to the datasource.
+///
+/// Scripts call `db_list(entity)`, `db_list(entity, filters)`, `db_count(entity, filters)`,
+/// `db_get(entity, id)`, `db_create(entity, item)`, `db_update(entity, id, item)` and
+/// `db_delete(entity, id)`, where filters map field names to the values they must equal.
+/// Clones share the same engine and compiled script.
+#[derive(Clone)]
+pub struct Script {
+    name: String,
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+}
+
+impl Script {
+    /// Compiles a script file, registering the datasource functions on its engine
+    pub fn load<T: ApiEntity>(path: &Path, datasource: Box<dyn DataSource<T>>) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        register_response_builder(&mut engine);
+        register_datasource(&mut engine, Arc::from(datasource));
+
+        let ast = engine.compile_file(path.to_path_buf()).map_err(|e| {
+            RusterApiError::ConfigError(format!("Failed to compile script {}: {}", path.display(), e))
+        })?;
+        Ok(Self {
+            name: path.display().to_string(),
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+        })
+    }
+
+    /// Runs the script with the given variables in scope, returning its last value
+    pub fn run(&self, variables: Vec<(&str, Dynamic)>) -> Result<Dynamic> {
+        let mut scope = Scope::new();
+        for (name, value) in variables {
+            scope.push_dynamic(name, value);
+        }
+        self.engine.eval_ast_with_scope(&mut scope, &self.ast).map_err(|e| self.error(e))
+    }
+
+    /// Calls a function defined by the script, or returns `None` if the script does not define it
+    pub fn call(&self, function: &str, args: Vec<Dynamic>) -> Result<Option<Dynamic>> {
+        if !self.ast.iter_functions().any(|f| f.name == function && f.params.len() == args.len()) {
+            return Ok(None);
+        }
+        self.engine.call_fn::<Dynamic>(&mut Scope::new(), &self.ast, function, args)
+            .map(Some)
+            .map_err(|e| self.error(*e))
+    }
+
+    fn error(&self, err: impl std::fmt::Display) -> RusterApiError {
+        RusterApiError::ServerError(format!("Script {} failed: {}", self.name, err))
+    }
+}
+
+/// Converts a JSON value into a script value
+pub fn to_script(value: &Value) -> Result<Dynamic> {
+    to_dynamic(value).map_err(|e| RusterApiError::ServerError(format!("Invalid script value: {}", e)))
+}
+
+/// Converts a script value into a JSON value
+pub fn from_script(value: &Dynamic) -> Result<Value> {
+    from_dynamic(value).map_err(|e| RusterApiError::ServerError(format!("Invalid script value: {}", e)))
+}
+
+/// Describes a request to a script as an object map. The body is parsed as JSON when possible.
+pub fn request_map(request: &ApiRequest) -> Result<Dynamic> {
+    let body = match &request.body {
+        Some(body) if !body.is_empty() => serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.clone())),
+        _ => Value::Null,
+    };
+    to_script(&serde_json::json!({
+        "method": format!("{:?}", request.method),
+        "path": request.path,
+        "params": request.params,
+        "query": request.query,
+        "headers": request.headers,
+        "body": body,
+    }))
+}
+
+/// Builds the handler running a script for every request, with the request in the `request` variable.
+/// The script returns a `response(...)`, a value answered with 200, or nothing for 204.
+pub fn script_handler<T: ApiEntity>(script: Script) -> EndpointHandler<T> {
+    Arc::new(move |request: ApiRequest| -> Result<ApiResponse<T>> {
+        let result = script.run(vec![("request", request_map(&request)?)])?;
+
+        let response = match result.clone().try_cast::<ScriptResponse>() {
+            Some(response) => response,
+            None if result.is_unit() => ScriptResponse::new(204),
+            None => ScriptResponse { body: result, ..ScriptResponse::new(200) },
+        };
+
+        let mut headers = default_headers();
+        for (name, value) in response.headers {
+            headers.insert(name.to_string(), value.to_string());
+        }
+        let body = if response.body.is_unit() {
+            None
+        } else {
+            Some(ApiResponseBody::Json(serde_json::from_value(from_script(&response.body)?)?))
+        };
+        Ok(ApiResponse {
+            status: u16::try_from(response.status)
+                .map_err(|_| RusterApiError::ServerError(format!("Invalid script response status {}", response.status)))?,
+            headers,
+            body,
+        })
+    })
+}
+
+fn register_response_builder(engine: &mut Engine) {
+    engine.register_type_with_name::<ScriptResponse>("Response")
+        .register_fn("response", || ScriptResponse::new(200))
+        .register_fn("response", ScriptResponse::new)
+        .register_fn("status", |response: &mut ScriptResponse, status: i64| {
+            ScriptResponse { status, ..response.clone() }
+        })
+        .register_fn("header", |response: &mut ScriptResponse, name: &str, value: Dynamic| {
+            let mut response = response.clone();
+            response.headers.insert(name.into(), value);
+            response
+        })
+        .register_fn("body", |response: &mut ScriptResponse, body: Dynamic| {
+            ScriptResponse { body, ..response.clone() }
+        });
+}
+
+/// Converts a script value into an entity
+fn to_entity<T: ApiEntity>(value: &Dynamic) -> ScriptResult<T> {
+    from_dynamic(value)
+}
+
+/// Builds equality filters from a map of field names to values
+fn filters_of(filters: &Map) -> ScriptResult<Vec<Filter>> {
+    filters.iter()
+        .map(|(field, value)| Ok(Filter {
+            field: field.to_string(),
+            operator: FilterOperator::Eq,
+            value: from_dynamic(value)?,
+        }))
+        .collect()
+}
+
+fn register_datasource<T: ApiEntity>(engine: &mut Engine, datasource: Arc<dyn DataSource<T>>) {
+    let fail = |action: &str, entity: &str, err: Box<dyn std::error::Error>| -> Box<EvalAltResult> {
+        format!("Failed to {} {}: {}", action, entity, err).into()
+    };
+
+    let ds = datasource.clone();
+    let list = move |entity: &str, filters: Map| -> ScriptResult<Array> {
+        let query = ListQuery { filters: filters_of(&filters)?, ..ListQuery::default() };
+        let page = ds.get_page(&query, Some(entity)).map_err(|e| fail("list", entity, e))?;
+        page.items.iter().map(to_dynamic).collect()
+    };
+    let list = Arc::new(list);
+    let list_all = list.clone();
+    engine.register_fn("db_list", move |entity: &str, filters: Map| list(entity, filters));
+    engine.register_fn("db_list", move |entity: &str| list_all(entity, Map::new()));
+
+    let ds = datasource.clone();
+    engine.register_fn("db_count", move |entity: &str, filters: Map| -> ScriptResult<i64> {
+        let count = ds.count(&filters_of(&filters)?, Some(entity)).map_err(|e| fail("count", entity, e))?;
+        Ok(count as i64)
+    });
+
+    let ds = datasource.clone();
+    engine.register_fn("db_get", move |entity: &str, id: Dynamic| -> ScriptResult<Dynamic> {
+        match ds.get_by_id(&id.to_string(), Some(entity)).map_err(|e| fail("read", entity, e))? {
+            Some(item) => to_dynamic(&item),
+            None => Ok(Dynamic::UNIT),
+        }
+    });
+
+    let ds = datasource.clone();
+    engine.register_fn("db_create", move |entity: &str, item: Dynamic| -> ScriptResult<Dynamic> {
+        let created = ds.create(to_entity(&item)?, Some(entity)).map_err(|e| fail("create", entity, e))?;
+        to_dynamic(&created)
+    });
+
+    let ds = datasource.clone();
+    engine.register_fn("db_update", move |entity: &str, id: Dynamic, item: Dynamic| -> ScriptResult<Dynamic> {
+        let updated = ds.update(&id.to_string(), to_entity(&item)?, Some(entity)).map_err(|e| fail("update", entity, e))?;
+        to_dynamic(&updated)
+    });
+
+    let ds = datasource;
+    engine.register_fn("db_delete", move |entity: &str, id: Dynamic| -> ScriptResult<bool> {
+        ds.delete(&id.to_string(), Some(entity)).map_err(|e| fail("delete", entity, e))
+    });
+}
@@ -0,0 +1,267 @@
+use crate::api::adapters::api_adapter::ApiRequest;
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::handlers::common::password::Passwords;
+#[cfg(feature = "scripting")]
+use crate::api::handlers::custom::script::{from_script, request_map, to_script, Script};
+use crate::config::specific::entity_config::Entity;
+use crate::error::Result;
+use crate::events::event_bus::{ChangeAction, EntityChange, EventBus, RawstEvent};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What a lifecycle hook knows about the request that triggered it
+pub struct HookContext<'a> {
+    /// Name of the entity, as configured
+    pub entity: &'a str,
+    /// ID of the entity, except when creating it
+    pub id: Option<&'a str>,
+    pub request: &'a ApiRequest,
+}
+
+/// Hook run before an entity is stored, returning the entity to store or an error rejecting the request
+pub type BeforeWriteHook<T> = Arc<dyn Fn(&HookContext, T) -> Result<T> + Send + Sync>;
+/// Hook run after an entity is stored, with the stored entity
+pub type AfterWriteHook<T> = Arc<dyn Fn(&HookContext, &T) -> Result<()> + Send + Sync>;
+/// Hook run around the deletion of an entity
+pub type DeleteHook = Arc<dyn Fn(&HookContext) -> Result<()> + Send + Sync>;
+
+/// Change of an entity stored by an endpoint, without the `hidden` fields. Created entities get
+/// their key from the stored entity, read from its key field.
+pub(crate) fn entity_change<T: Serialize>(
+    entity: &str,
+    key: Option<&str>,
+    hidden: &[String],
+    action: ChangeAction,
+    id: Option<&str>,
+    item: Option<&T>,
+) -> Result<EntityChange> {
+    let data = item.map(serde_json::to_value).transpose()?;
+    let id = id.map(str::to_string).or_else(|| {
+        match data.as_ref().zip(key).and_then(|(data, key)| data.get(key))? {
+            Value::Null => None,
+            Value::String(id) => Some(id.clone()),
+            id => Some(id.to_string()),
+        }
+    });
+    let mut change = EntityChange { entity: entity.to_string(), action, id, data };
+    change.hide(hidden);
+    Ok(change)
+}
+
+/// Lifecycle hooks of an entity, run by the create, update and delete endpoints in registration order.
+/// Errors of `after_*` hooks fail the request although the change is already stored.
+pub struct EntityHooks<T> {
+    before_create: Vec<BeforeWriteHook<T>>,
+    after_create: Vec<AfterWriteHook<T>>,
+    before_update: Vec<BeforeWriteHook<T>>,
+    after_update: Vec<AfterWriteHook<T>>,
+    before_delete: Vec<DeleteHook>,
+    after_delete: Vec<DeleteHook>,
+}
+
+impl<T> Default for EntityHooks<T> {
+    fn default() -> Self {
+        Self {
+            before_create: Vec::new(),
+            after_create: Vec::new(),
+            before_update: Vec::new(),
+            after_update: Vec::new(),
+            before_delete: Vec::new(),
+            after_delete: Vec::new(),
+        }
+    }
+}
+
+impl<T> Clone for EntityHooks<T> {
+    fn clone(&self) -> Self {
+        Self {
+            before_create: self.before_create.clone(),
+            after_create: self.after_create.clone(),
+            before_update: self.before_update.clone(),
+            after_update: self.after_update.clone(),
+            before_delete: self.before_delete.clone(),
+            after_delete: self.after_delete.clone(),
+        }
+    }
+}
+
+impl<T: ApiEntity> EntityHooks<T> {
+    /// Runs the `before_create` hooks, each receiving the entity returned by the previous one
+    pub fn before_create(&self, context: &HookContext, item: T) -> Result<T> {
+        self.before_create.iter().try_fold(item, |item, hook| hook(context, item))
+    }
+
+    pub fn after_create(&self, context: &HookContext, item: &T) -> Result<()> {
+        self.after_create.iter().try_for_each(|hook| hook(context, item))
+    }
+
+    /// Runs the `before_update` hooks, each receiving the entity returned by the previous one
+    pub fn before_update(&self, context: &HookContext, item: T) -> Result<T> {
+        self.before_update.iter().try_fold(item, |item, hook| hook(context, item))
+    }
+
+    pub fn after_update(&self, context: &HookContext, item: &T) -> Result<()> {
+        self.after_update.iter().try_for_each(|hook| hook(context, item))
+    }
+
+    pub fn before_delete(&self, context: &HookContext) -> Result<()> {
+        self.before_delete.iter().try_for_each(|hook| hook(context))
+    }
+
+    pub fn after_delete(&self, context: &HookContext) -> Result<()> {
+        self.after_delete.iter().try_for_each(|hook| hook(context))
+    }
+
+    /// Publishes the changes stored by the endpoints to an event bus, without their passwords. They
+    /// are published before the other `after_*` hooks run, as the changes are stored even if those
+    /// hooks fail. Subscribers leave out the fields their receivers may not read.
+    pub fn with_change_events(mut self, events: EventBus, entity: &Entity) -> Self {
+        let entity_name = entity.name.clone();
+        let key = entity.key_field().map(|field| field.name.clone());
+        let passwords = Passwords::new(entity).fields().to_vec();
+        let publisher = move |action: ChangeAction, id: Option<&str>, item: Option<&T>| -> Result<()> {
+            if !events.has_subscribers() {
+                return Ok(());
+            }
+            events.publish(RawstEvent::EntityChanged(entity_change(&entity_name, key.as_deref(), &passwords, action, id, item)?));
+            Ok(())
+        };
+
+        let publish = publisher.clone();
+        self.after_create.insert(0, Arc::new(move |context, item| publish(ChangeAction::Created, context.id, Some(item))));
+        let publish = publisher.clone();
+        self.after_update.insert(0, Arc::new(move |context, item| publish(ChangeAction::Updated, context.id, Some(item))));
+        self.after_delete.insert(0, Arc::new(move |context| publisher(ChangeAction::Deleted, context.id, None)));
+        self
+    }
+
+    /// Adds the hooks defined by a script as functions named after them. `before_create(item, request)`
+    /// and `before_update(id, item, request)` return the entity to store, or nothing to keep it;
+    /// `after_create(item, request)`, `after_update(id, item, request)`, `before_delete(id, request)`
+    /// and `after_delete(id, request)` return nothing. Hooks the script does not define are skipped.
+    #[cfg(feature = "scripting")]
+    pub fn with_script(mut self, script: Script) -> Self {
+        let item_of = |item: &T| to_script(&serde_json::to_value(item)?);
+        let id_of = |context: &HookContext| to_script(&serde_json::Value::from(context.id.unwrap_or_default()));
+
+        let s = script.clone();
+        self.before_create.push(Arc::new(move |context, item| {
+            match s.call("before_create", vec![item_of(&item)?, request_map(context.request)?])? {
+                Some(result) if !result.is_unit() => Ok(serde_json::from_value(from_script(&result)?)?),
+                _ => Ok(item),
+            }
+        }));
+        let s = script.clone();
+        self.after_create.push(Arc::new(move |context, item| {
+            s.call("after_create", vec![item_of(item)?, request_map(context.request)?]).map(|_| ())
+        }));
+        let s = script.clone();
+        self.before_update.push(Arc::new(move |context, item| {
+            match s.call("before_update", vec![id_of(context)?, item_of(&item)?, request_map(context.request)?])? {
+                Some(result) if !result.is_unit() => Ok(serde_json::from_value(from_script(&result)?)?),
+                _ => Ok(item),
+            }
+        }));
+        let s = script.clone();
+        self.after_update.push(Arc::new(move |context, item| {
+            s.call("after_update", vec![id_of(context)?, item_of(item)?, request_map(context.request)?]).map(|_| ())
+        }));
+        let s = script.clone();
+        self.before_delete.push(Arc::new(move |context| {
+            s.call("before_delete", vec![id_of(context)?, request_map(context.request)?]).map(|_| ())
+        }));
+        self.after_delete.push(Arc::new(move |context| {
+            script.call("after_delete", vec![id_of(context)?, request_map(context.request)?]).map(|_| ())
+        }));
+        self
+    }
+}
+
+/// Lifecycle hooks registered by library users, keyed by lowercase entity name.
+/// Pass them to `ApiAdapter::with_hooks` so the endpoints run them.
+pub struct HookRegistry<T> {
+    entities: HashMap<String, EntityHooks<T>>,
+}
+
+impl<T> Default for HookRegistry<T> {
+    fn default() -> Self {
+        Self { entities: HashMap::new() }
+    }
+}
+
+impl<T> Clone for HookRegistry<T> {
+    fn clone(&self) -> Self {
+        Self { entities: self.entities.clone() }
+    }
+}
+
+impl<T: ApiEntity> HookRegistry<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the hooks of an entity, which are empty if none was registered
+    pub fn for_entity(&self, entity: &str) -> EntityHooks<T> {
+        self.entities.get(&entity.to_lowercase()).cloned().unwrap_or_default()
+    }
+
+    fn entity_mut(&mut self, entity: &str) -> &mut EntityHooks<T> {
+        self.entities.entry(entity.to_lowercase()).or_default()
+    }
+
+    /// Registers a hook run before an entity is created, which may change the entity or reject it
+    pub fn before_create<F>(&mut self, entity: &str, hook: F) -> &mut Self
+    where
+        F: Fn(&HookContext, T) -> Result<T> + Send + Sync + 'static,
+    {
+        self.entity_mut(entity).before_create.push(Arc::new(hook));
+        self
+    }
+
+    /// Registers a hook run after an entity is created
+    pub fn after_create<F>(&mut self, entity: &str, hook: F) -> &mut Self
+    where
+        F: Fn(&HookContext, &T) -> Result<()> + Send + Sync + 'static,
+    {
+        self.entity_mut(entity).after_create.push(Arc::new(hook));
+        self
+    }
+
+    /// Registers a hook run before an entity is updated, which may change the entity or reject it
+    pub fn before_update<F>(&mut self, entity: &str, hook: F) -> &mut Self
+    where
+        F: Fn(&HookContext, T) -> Result<T> + Send + Sync + 'static,
+    {
+        self.entity_mut(entity).before_update.push(Arc::new(hook));
+        self
+    }
+
+    /// Registers a hook run after an entity is updated
+    pub fn after_update<F>(&mut self, entity: &str, hook: F) -> &mut Self
+    where
+        F: Fn(&HookContext, &T) -> Result<()> + Send + Sync + 'static,
+    {
+        self.entity_mut(entity).after_update.push(Arc::new(hook));
+        self
+    }
+
+    /// Registers a hook run before an entity is deleted, which may reject the deletion
+    pub fn before_delete<F>(&mut self, entity: &str, hook: F) -> &mut Self
+    where
+        F: Fn(&HookContext) -> Result<()> + Send + Sync + 'static,
+    {
+        self.entity_mut(entity).before_delete.push(Arc::new(hook));
+        self
+    }
+
+    /// Registers a hook run after an entity is deleted
+    pub fn after_delete<F>(&mut self, entity: &str, hook: F) -> &mut Self
+    where
+        F: Fn(&HookContext) -> Result<()> + Send + Sync + 'static,
+    {
+        self.entity_mut(entity).after_delete.push(Arc::new(hook));
+        self
+    }
+}
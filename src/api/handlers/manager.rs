@@ -1,15 +1,26 @@
 use crate::api::adapters::api_adapter::EndpointHandler;
-use crate::api::handlers::crud::{create, delete, list, read, update};
+use crate::api::handlers::common::authorization::EntityAuthorization;
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::crud::{bulk_update, count, create, delete, distinct, export, list, nested, read, relationships, trash, update};
 use crate::api::handlers::custom::routes;
+#[cfg(feature = "scripting")]
+use crate::api::handlers::custom::script::Script;
+use crate::api::handlers::common::idempotency::{IdempotencyCache, DEFAULT_IDEMPOTENCY_TTL_SECONDS};
+use crate::api::handlers::hooks::{EntityHooks, HookRegistry};
 use crate::config::configuration::Config;
+use crate::config::specific::server_config::{DEFAULT_MAX_RESULTS, DEFAULT_SCRIPTS_DIR};
 use crate::config::specific::entity_config::Entity;
 use crate::data::datasource::base::DataSource;
+use crate::events::event_bus::EventBus;
 use crate::api::common::api_entity::ApiEntity;
 use std::collections::HashMap;
+use tracing::warn;
 
 pub struct ApiHandlerManager<T> {
-    _config: Config,
+    config: Config,
     datasource: Box<dyn DataSource<T>>,
+    hooks: HookRegistry<T>,
+    events: Option<EventBus>,
 }
 
 impl<T> ApiHandlerManager<T>
@@ -18,44 +29,128 @@ where
 {
     /// Creates a new ApiHandlerManager for a specific entity
     pub fn new(config: Config, datasource: Box<dyn DataSource<T>>) -> Self {
-        Self { _config: config, datasource }
+        Self { config, datasource, hooks: HookRegistry::new(), events: None }
     }
 
-    /// Initializes all endpoints for a specific entity based on its configuration
+    /// Runs the given lifecycle hooks in the create, update and delete endpoints
+    pub fn with_hooks(mut self, hooks: HookRegistry<T>) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Publishes the changes stored by the create, update and delete endpoints to the given bus
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Returns the lifecycle hooks of an entity: the registered ones, then those of its hooks script.
+    /// Changes are published to the event bus, if any, before they run.
+    pub fn entity_hooks(&self, entity: &Entity) -> EntityHooks<T> {
+        let mut hooks = self.hooks.for_entity(&entity.name);
+        if let Some(events) = &self.events {
+            hooks = hooks.with_change_events(events.clone(), entity);
+        }
+        let Some(hooks_script) = &entity.hooks_script else {
+            return hooks;
+        };
+
+        #[cfg(feature = "scripting")]
+        {
+            let scripts_dir = self.config.server.scripts_dir.as_deref().unwrap_or(DEFAULT_SCRIPTS_DIR);
+            match Script::load(&std::path::Path::new(scripts_dir).join(hooks_script), self.datasource.clone()) {
+                Ok(script) => hooks.with_script(script),
+                Err(e) => {
+                    warn!("Skipping the hooks script of {}: {}", entity.name, e);
+                    hooks
+                }
+            }
+        }
+        #[cfg(not(feature = "scripting"))]
+        {
+            warn!("Skipping the hooks script {} of {}: scripts require the scripting feature", hooks_script, entity.name);
+            hooks
+        }
+    }
+
+    /// Initializes all endpoints for a specific entity based on its configuration. With an active
+    /// `authorization`, every endpoint checks the roles and permissions of the caller first, and
+    /// fields with `read_roles` or `write_roles` are only read and written by callers with those roles.
+    /// With `tenancy`, entities with its tenant field only read and write the rows of the tenant of each request.
     pub fn initialize_endpoints(&self, entity: &Entity) -> HashMap<String, EndpointHandler<T>> {
         let mut endpoints = HashMap::new();
+        let all_entities = self.config.all_entities();
+        let hooks = self.entity_hooks(entity);
+        let max_results = self.config.server.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
 
         // Register standard CRUD endpoints
         if entity.endpoints.generate_create {
-            create::register_create_endpoint(self.datasource.clone(), entity, &mut endpoints);
+            let ttl = self.config.server.idempotency_ttl_seconds.unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECONDS);
+            create::register_create_endpoint(self.datasource.clone(), entity, hooks.clone(), IdempotencyCache::new(ttl), &mut endpoints);
         }
 
         if entity.endpoints.generate_read {
-            read::register_read_endpoint(self.datasource.clone(), entity, &mut endpoints);
+            read::register_read_endpoint(self.datasource.clone(), entity, &all_entities, &mut endpoints);
         }
 
         if entity.endpoints.generate_update {
-            update::register_update_endpoint(self.datasource.clone(), entity, &mut endpoints);
+            update::register_update_endpoint(self.datasource.clone(), entity, hooks.clone(), &mut endpoints);
+            bulk_update::register_bulk_update_endpoint(self.datasource.clone(), entity, &mut endpoints);
         }
 
         if entity.endpoints.generate_delete {
-            delete::register_delete_endpoint(self.datasource.clone(), entity, &mut endpoints);
+            delete::register_delete_endpoint(self.datasource.clone(), entity, hooks, &mut endpoints);
+            trash::register_trash_endpoints(self.datasource.clone(), entity, max_results, &mut endpoints);
         }
 
         if entity.endpoints.generate_list {
-            list::register_list_endpoint(self.datasource.clone(), &entity.name, &mut endpoints);
+            list::register_list_endpoint(self.datasource.clone(), entity, &all_entities, max_results, &mut endpoints);
+            count::register_count_endpoint(self.datasource.clone(), entity, &mut endpoints);
+            export::register_export_endpoint(self.datasource.clone(), entity, max_results, &mut endpoints);
+            distinct::register_distinct_endpoint(self.datasource.clone(), entity, max_results, &mut endpoints);
         }
 
+        // Register the nested list endpoints of the entities referencing this one. They return those
+        // entities, so they are kept apart from the field access of this one and guarded by theirs.
+        let mut nested_endpoints = HashMap::new();
+        nested::register_nested_endpoints(self.datasource.clone(), entity, &all_entities, max_results, &mut nested_endpoints);
+
+        // Register the link endpoints of many-to-many relationships
+        relationships::register_relationship_endpoints(self.datasource.clone(), entity, &all_entities, &mut endpoints);
+
         // Register custom routes
+        let scripts_dir = self.config.server.scripts_dir.as_deref().unwrap_or(DEFAULT_SCRIPTS_DIR);
         for custom_route in &entity.endpoints.custom_routes {
             routes::register_custom_endpoint(
                 self.datasource.clone(),
                 entity,
+                &all_entities,
+                max_results,
+                scripts_dir,
                 custom_route,
                 &mut endpoints,
             );
         }
 
+        let field_access = FieldAccess::new(entity);
+        if field_access.is_active() {
+            endpoints = endpoints.into_iter()
+                .map(|(key, handler)| (key, field_access.guard(handler)))
+                .collect();
+        }
+        for (key, handler) in nested_endpoints {
+            endpoints.entry(key).or_insert(handler);
+        }
+
+        let authorization = EntityAuthorization::new(entity);
+        if authorization.is_active() {
+            endpoints = endpoints.into_iter()
+                .map(|(key, handler)| {
+                    let handler = authorization.guard(&key, handler);
+                    (key, handler)
+                })
+                .collect();
+        }
         endpoints
     }
 }
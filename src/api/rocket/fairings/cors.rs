@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Header, Method, Status};
+use rocket::{Request, Response};
+use crate::config::configuration::Config;
+use crate::config::specific::cors_config::CorsConfig;
+
+/// Methods advertised in preflight responses when `allowed_methods` is empty
+const DEFAULT_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, OPTIONS";
+
+/// Fairing applying the CORS configuration to every response.
+///
+/// The global `CorsConfig` applies to every route; entities can override it through
+/// `Entity.cors`, matched by the entity name in the request path.
+/// Preflight requests to paths without endpoints, answered 404 by the OPTIONS route,
+/// are answered with 204.
+pub struct CorsFairing {
+    global: CorsConfig,
+    entities: HashMap<String, CorsConfig>,
+}
+
+impl CorsFairing {
+    /// Builds the fairing from the global configuration and the entity overrides
+    pub fn new(config: &Config) -> Self {
+        let entities = config.all_entities().into_iter()
+            .filter_map(|entity| entity.cors.as_ref()
+                .map(|overrides| (entity.name.to_lowercase(), config.cors.merged_with(overrides))))
+            .collect();
+
+        CorsFairing {
+            global: config.cors.clone(),
+            entities,
+        }
+    }
+
+    /// Resolves the CORS configuration of a path: the first segment naming an entity
+    /// with overrides selects its configuration, otherwise the global one applies
+    pub fn policy_for_path(&self, path: &str) -> &CorsConfig {
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .find_map(|segment| self.entities.get(&segment.to_lowercase()))
+            .unwrap_or(&self.global)
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for CorsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "CORS",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let origin = match request.headers().get_one("Origin") {
+            Some(origin) => origin,
+            None => return,
+        };
+
+        let policy = self.policy_for_path(request.uri().path().as_str());
+        if !policy.allows_origin(origin) {
+            return;
+        }
+
+        let preflight_method = request.headers().get_one("Access-Control-Request-Method");
+        let requested_headers = request.headers().get_one("Access-Control-Request-Headers").unwrap_or_default();
+        let is_preflight = request.method() == Method::Options && preflight_method.is_some();
+        // Preflights asking for a method or header that is not allowed get no CORS headers,
+        // so the browser blocks the actual request
+        if is_preflight
+            && !(preflight_method.is_some_and(|method| policy.allows_method(method)) && policy.allows_headers(requested_headers))
+        {
+            return;
+        }
+
+        let wildcard = policy.allowed_origins.iter().any(|allowed| allowed == "*");
+        if wildcard && !policy.allow_credentials {
+            response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
+        } else {
+            // Credentials cannot be used with `*`, so the origin is echoed back
+            response.set_header(Header::new("Access-Control-Allow-Origin", origin.to_string()));
+            response.adjoin_header(Header::new("Vary", "Origin"));
+        }
+        if policy.allow_credentials {
+            response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
+        }
+
+        if !is_preflight {
+            if !policy.exposed_headers.is_empty() {
+                response.set_header(Header::new("Access-Control-Expose-Headers", policy.exposed_headers.join(", ")));
+            }
+            return;
+        }
+        response.adjoin_header(Header::new("Vary", "Access-Control-Request-Method, Access-Control-Request-Headers"));
+
+        let methods = if policy.allowed_methods.is_empty() || policy.allowed_methods.iter().any(|m| m == "*") {
+            DEFAULT_METHODS.to_string()
+        } else {
+            policy.allowed_methods.join(", ")
+        };
+        response.set_header(Header::new("Access-Control-Allow-Methods", methods));
+
+        let headers = if policy.allowed_headers.iter().any(|h| h == "*") {
+            requested_headers.to_string()
+        } else {
+            policy.allowed_headers.join(", ")
+        };
+        if !headers.is_empty() {
+            response.set_header(Header::new("Access-Control-Allow-Headers", headers));
+        }
+        if let Some(max_age) = policy.max_age_seconds {
+            response.set_header(Header::new("Access-Control-Max-Age", max_age.to_string()));
+        }
+
+        // The OPTIONS route only answers paths with endpoints, so answer the other preflights here
+        if response.status() == Status::NotFound {
+            response.set_status(Status::NoContent);
+            response.remove_header("Content-Type");
+            response.set_sized_body(0, Cursor::new(Vec::new()));
+        }
+    }
+}
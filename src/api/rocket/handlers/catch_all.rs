@@ -1,180 +1,313 @@
-use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse, ApiResponseBody};
-use crate::api::handlers::common::utils::default_headers;
-use crate::config::specific::entity_config::HttpMethod;
+use crate::api::adapters::api_adapter::{ApiRequest, ApiResponse};
+use crate::api::adapters::serving;
+use crate::api::adapters::uploads::UploadStore;
+use crate::api::handlers::common::request_id::{request_id, tag_response};
+use crate::api::handlers::common::utils::error_response;
 use crate::error::RusterApiError;
+use crate::config::specific::entity_config::HttpMethod;
 use rocket::data::ToByteUnit;
 use rocket::http::Status;
-use rocket::State;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Request, State};
 use serde_json;
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 // Import our RocketApiState wrapper
 use crate::api::rocket::rocket_adapter::RocketApiState;
 use crate::api::rocket::rocket_adapter::ApiResponseWrapper;
 
+/// Decoded query string parameters of a request
+pub struct QueryParams(pub HashMap<String, String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for QueryParams {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let params = request.uri().query()
+            .map(|query| query.segments().map(|(key, value)| (key.to_string(), value.to_string())).collect())
+            .unwrap_or_default();
+        Outcome::Success(QueryParams(params))
+    }
+}
+
+/// Headers of a request, keyed by lowercase name. Repeated headers are joined with commas.
+pub struct RequestHeaders(pub HashMap<String, String>);
+
+/// Headers of a Rocket request, keyed by lowercase name
+pub(crate) fn header_map(request: &Request<'_>) -> HashMap<String, String> {
+    let mut headers: HashMap<String, String> = HashMap::new();
+    for header in request.headers().iter() {
+        headers.entry(header.name().as_str().to_lowercase())
+            .and_modify(|value| {
+                value.push_str(", ");
+                value.push_str(header.value());
+            })
+            .or_insert_with(|| header.value().to_string());
+    }
+    headers
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestHeaders {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestHeaders(header_map(request)))
+    }
+}
+
+/// IP address of the client sending a request, if known. Behind the trusted proxies of
+/// `server.proxy`, it is read from `X-Forwarded-For`.
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientIp {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let peer = request.remote().map(|address| address.ip());
+        let client = match request.rocket().state::<RocketApiState<serde_json::Value>>() {
+            Some(state) => state.proxy.client(peer, &header_map(request)),
+            None => peer,
+        };
+        Outcome::Success(ClientIp(client))
+    }
+}
+
+/// Public origin of the API as the client of a request reached it, like `https://api.example.com`.
+/// Behind the trusted proxies of `server.proxy`, it is read from `X-Forwarded-Proto` and `X-Forwarded-Host`.
+pub struct PublicOrigin(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PublicOrigin {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let origin = request.rocket().state::<RocketApiState<serde_json::Value>>()
+            .and_then(|state| state.proxy.origin(request.remote().map(|address| address.ip()), &header_map(request)));
+        Outcome::Success(PublicOrigin(origin))
+    }
+}
+
 /// Catch-all handler for GET requests
 #[rocket::get("/<path..>")]
-pub async fn get_handler(path: PathBuf, state: &State<RocketApiState<serde_json::Value>>) 
+pub async fn get_handler(path: PathBuf, query: QueryParams, headers: RequestHeaders, client: ClientIp, state: &State<RocketApiState<serde_json::Value>>) 
 -> ApiResponseWrapper<serde_json::Value> {
+    let request_id = request_id(&headers.0);
     // Create API request with the path info but without request body
     let api_request = ApiRequest {
         method: HttpMethod::GET,
         path: path.to_string_lossy().to_string(),
         params: HashMap::new(),
-        headers: HashMap::new(),
+        query: query.0,
+        headers: headers.0,
         body: None,
+        request_id,
+        auth: None,
+        tenant: None,
     };
-    let api_response_wrapper = process_request(api_request, state).await;
+    let api_response_wrapper = process_request(api_request, client, state).await;
     ApiResponseWrapper(api_response_wrapper)
 }
 
+/// Catch-all handler for HEAD requests, processed as GET requests.
+/// Rocket strips the body of HEAD responses but keeps its length and headers.
+#[rocket::head("/<path..>")]
+pub async fn head_handler(path: PathBuf, query: QueryParams, headers: RequestHeaders, client: ClientIp, state: &State<RocketApiState<serde_json::Value>>) 
+-> ApiResponseWrapper<serde_json::Value> {
+    get_handler(path, query, headers, client, state).await
+}
+
+/// Catch-all handler for OPTIONS requests, listing the methods of the endpoints matching the path
+/// in the `Allow` header
+#[rocket::options("/<path..>")]
+pub async fn options_handler(path: PathBuf, state: &State<RocketApiState<serde_json::Value>>) 
+-> ApiResponseWrapper<serde_json::Value> {
+    let path = path.to_string_lossy().to_string();
+    let methods: Vec<HttpMethod> = [HttpMethod::GET, HttpMethod::POST, HttpMethod::PUT, HttpMethod::PATCH, HttpMethod::DELETE]
+        .into_iter()
+        .filter(|method| state.api_adapter.route_for(method, &path).is_some())
+        .collect();
+    if methods.is_empty() {
+        let err = RusterApiError::NotFound(format!("No endpoint matches {}", path));
+        return ApiResponseWrapper(error_response(&err, state.error_format, &format!("/api/{}", path)));
+    }
+
+    let mut allowed: Vec<String> = Vec::new();
+    for method in methods {
+        allowed.push(format!("{:?}", method));
+        if matches!(method, HttpMethod::GET) {
+            allowed.push("HEAD".to_string());
+        }
+    }
+    allowed.push("OPTIONS".to_string());
+
+    let mut headers = HashMap::new();
+    headers.insert("Allow".to_string(), allowed.join(", "));
+    ApiResponseWrapper(ApiResponse {
+        status: Status::NoContent.code,
+        headers,
+        body: None,
+    })
+}
+
 /// Catch-all handler for POST requests
 #[rocket::post("/<path..>", data = "<body>")]
-pub async fn post_handler(path: PathBuf, body: rocket::Data<'_>, state: &State<RocketApiState<serde_json::Value>>) 
+pub async fn post_handler(path: PathBuf, query: QueryParams, headers: RequestHeaders, client: ClientIp, body: rocket::Data<'_>, state: &State<RocketApiState<serde_json::Value>>) 
 -> ApiResponseWrapper<serde_json::Value> {
-    let body_string = body_to_string(body).await;
+    let request_id = request_id(&headers.0);
+    let path_string = path.to_string_lossy().to_string();
+    let (body_string, files) = match request_body(body, &path_string, &headers.0, state).await {
+        Ok(request_body) => request_body,
+        Err(err) => {
+            let mut api_response = error_response(&err, state.error_format, &format!("/api/{}", path.display()));
+            tag_response(&mut api_response, &request_id);
+            return ApiResponseWrapper(api_response);
+        }
+    };
     
     // Create API request with the path info and request body
     let api_request = ApiRequest {
         method: HttpMethod::POST,
-        path: path.to_string_lossy().to_string(),
+        path: path_string,
         params: HashMap::new(),
-        headers: HashMap::new(),
+        query: query.0,
+        headers: headers.0,
         body: body_string,
+        request_id,
+        auth: None,
+        tenant: None,
     };
-    let api_response = process_request(api_request, state).await;
+    let api_response = process_request(api_request, client, state).await;
+    if api_response.status >= 400 {
+        state.uploads.discard(&files).await;
+    }
     ApiResponseWrapper(api_response)
 }
 
 /// Catch-all handler for PUT requests
 #[rocket::put("/<path..>", data = "<body>")]
-pub async fn put_handler(path: PathBuf, body: rocket::Data<'_>, state: &State<RocketApiState<serde_json::Value>>) 
+pub async fn put_handler(path: PathBuf, query: QueryParams, headers: RequestHeaders, client: ClientIp, body: rocket::Data<'_>, state: &State<RocketApiState<serde_json::Value>>) 
 -> ApiResponseWrapper<serde_json::Value> {
-    let body_string = body_to_string(body).await;
+    let request_id = request_id(&headers.0);
+    let path_string = path.to_string_lossy().to_string();
+    let (body_string, files) = match request_body(body, &path_string, &headers.0, state).await {
+        Ok(request_body) => request_body,
+        Err(err) => {
+            let mut api_response = error_response(&err, state.error_format, &format!("/api/{}", path.display()));
+            tag_response(&mut api_response, &request_id);
+            return ApiResponseWrapper(api_response);
+        }
+    };
     
     // Create API request with the path info and request body
     let api_request = ApiRequest {
         method: HttpMethod::PUT,
-        path: path.to_string_lossy().to_string(),
+        path: path_string,
         params: HashMap::new(),
-        headers: HashMap::new(),
+        query: query.0,
+        headers: headers.0,
         body: body_string,
+        request_id,
+        auth: None,
+        tenant: None,
     };
-    let api_response = process_request(api_request, state).await;
+    let api_response = process_request(api_request, client, state).await;
+    if api_response.status >= 400 {
+        state.uploads.discard(&files).await;
+    }
     ApiResponseWrapper(api_response)
 }
 
 /// Catch-all handler for DELETE requests
 #[rocket::delete("/<path..>")]
-pub async fn delete_handler(path: PathBuf, state: &State<RocketApiState<serde_json::Value>>) 
+pub async fn delete_handler(path: PathBuf, query: QueryParams, headers: RequestHeaders, client: ClientIp, state: &State<RocketApiState<serde_json::Value>>) 
 -> ApiResponseWrapper<serde_json::Value> {
+    let request_id = request_id(&headers.0);
     // Create API request with the path info
     let api_request = ApiRequest {
         method: HttpMethod::DELETE,
         path: path.to_string_lossy().to_string(),
         params: HashMap::new(),
-        headers: HashMap::new(),
+        query: query.0,
+        headers: headers.0,
         body: None,
+        request_id,
+        auth: None,
+        tenant: None,
     };
-    let api_response = process_request(api_request, state).await;
+    let api_response = process_request(api_request, client, state).await;
     ApiResponseWrapper(api_response)
 }
 
 /// Catch-all handler for PATCH requests
 #[rocket::patch("/<path..>", data = "<body>")]
-pub async fn patch_handler(path: PathBuf, body: rocket::Data<'_>, state: &State<RocketApiState<serde_json::Value>>) 
+pub async fn patch_handler(path: PathBuf, query: QueryParams, headers: RequestHeaders, client: ClientIp, body: rocket::Data<'_>, state: &State<RocketApiState<serde_json::Value>>) 
 -> ApiResponseWrapper<serde_json::Value> {
-    let body_string = body_to_string(body).await;
+    let request_id = request_id(&headers.0);
+    let path_string = path.to_string_lossy().to_string();
+    let (body_string, files) = match request_body(body, &path_string, &headers.0, state).await {
+        Ok(request_body) => request_body,
+        Err(err) => {
+            let mut api_response = error_response(&err, state.error_format, &format!("/api/{}", path.display()));
+            tag_response(&mut api_response, &request_id);
+            return ApiResponseWrapper(api_response);
+        }
+    };
     
     // Create API request with the path info and request body
     let api_request = ApiRequest {
         method: HttpMethod::PATCH,
-        path: path.to_string_lossy().to_string(),
+        path: path_string,
         params: HashMap::new(),
-        headers: HashMap::new(),
+        query: query.0,
+        headers: headers.0,
         body: body_string,
+        request_id,
+        auth: None,
+        tenant: None,
     };
-    let api_response = process_request(api_request, state).await;
+    let api_response = process_request(api_request, client, state).await;
+    if api_response.status >= 400 {
+        state.uploads.discard(&files).await;
+    }
     ApiResponseWrapper(api_response)
 }
 
-/// Helper to convert Rocket's Data to String
-async fn body_to_string(body: rocket::Data<'_>) 
--> Option<String> {
-    use rocket::tokio::io::AsyncReadExt;
-    
-    let mut stream = body.open(2.mebibytes()); // TODO: Make this configurable
-    let mut body_bytes = Vec::new();
-    
-    if let Ok(_) = stream.read_to_end(&mut body_bytes).await {
-        String::from_utf8(body_bytes).ok()
-    } else {
-        None
+/// Helper to read Rocket's Data, failing when the body is larger than the limit in megabytes
+async fn body_to_bytes(body: rocket::Data<'_>, limit_mb: u32) -> Result<Vec<u8>, RusterApiError> {
+    let body_bytes = body.open(u64::from(limit_mb).mebibytes()).into_bytes().await
+        .map_err(|e| RusterApiError::BadRequest(format!("Failed to read the request body: {}", e)))?;
+    if !body_bytes.is_complete() {
+        return Err(RusterApiError::PayloadTooLarge(format!("The request body exceeds the limit of {} MB", limit_mb)));
     }
+    Ok(body_bytes.into_inner())
 }
 
-/// Common request processing logic
-pub async fn process_request(api_request: ApiRequest, state: &State<RocketApiState<serde_json::Value>>) 
--> ApiResponse<serde_json::Value> {
-    
-    let api_adapter_clone = state.api_adapter.clone();
-    let timeout_duration = std::time::Duration::from_secs(30);
-    match tokio::time::timeout(
-        timeout_duration,
-        tokio::task::spawn_blocking(move || {
-            // DEBUG
-            eprintln!("Processing request: {:?} {}", api_request.method, api_request.path);
-            let result = api_adapter_clone.handle_request(api_request);
-            eprintln!("Request processing completed with result: {:?}", result.is_ok());
-            result
-        })
-    ).await {
-        Ok(join_result) => match join_result {
-            Ok(result) => match result {
-                Ok(api_response) => api_response, 
-                Err(err) => {
-                    // Convert error to ApiResponse
-                    let status = match err {
-                        RusterApiError::EntityNotFound(_) => Status::NotFound,
-                        RusterApiError::ValidationError(_) => Status::BadRequest,
-                        RusterApiError::BadRequest(_) => Status::BadRequest,
-                        RusterApiError::DatabaseError(_) => Status::InternalServerError,
-                        _ => Status::InternalServerError,
-                    };
-                    
-                    // Log the error for debugging
-                    eprintln!("API Error: {:?}", err);
-                    
-                    // Create API error response
-                    ApiResponse {
-                        status: status.code,
-                        body: Some(ApiResponseBody::Json(serde_json::json!({ "error": err.to_string() }))),
-                        headers: default_headers(),
-                    }
-                }
-            },
-            Err(join_err) => {
-                eprintln!("Task join error: {:?}", join_err);
-                ApiResponse {
-                    status: Status::InternalServerError.code,
-                    body: Some(ApiResponseBody::Json(serde_json::json!({ 
-                        "error": "Internal server error: Request processing failed" 
-                    }))),
-                    headers: default_headers(),
-                }
-            }
-        },
-        Err(_) => {
-            // Timeout occurred
-            eprintln!("Request processing timed out after {} seconds", timeout_duration.as_secs());
-            ApiResponse {
-                status: Status::GatewayTimeout.code,
-                body: Some(ApiResponseBody::Json(serde_json::json!({ 
-                    "error": "Request timed out - database operation may be taking too long" 
-                }))),
-                headers: default_headers(),
-            }
-        }
+/// Helper to convert Rocket's Data to String, failing when the body is larger than the limit in megabytes.
+/// Bodies that are not UTF-8 are treated as missing.
+pub(crate) async fn body_to_string(body: rocket::Data<'_>, limit_mb: u32) 
+-> Result<Option<String>, RusterApiError> {
+    Ok(String::from_utf8(body_to_bytes(body, limit_mb).await?).ok())
+}
+
+/// Body of a request to the endpoints, with the files uploaded by `multipart/form-data` forms,
+/// which are converted to the JSON body of the entity
+async fn request_body(body: rocket::Data<'_>, path: &str, headers: &HashMap<String, String>, state: &State<RocketApiState<serde_json::Value>>)
+-> Result<(Option<String>, Vec<PathBuf>), RusterApiError> {
+    if !UploadStore::is_form(headers) {
+        return Ok((body_to_string(body, state.max_payload_size_mb).await?, Vec::new()));
     }
-}
\ No newline at end of file
+    let bytes = body_to_bytes(body, state.max_payload_size_mb).await?;
+    let form = state.uploads.form_body(path, headers, bytes).await?;
+    Ok((Some(form.body), form.files))
+}
+
+/// Common request processing logic. Requests over a rate limit are answered with 429.
+pub async fn process_request(api_request: ApiRequest, client: ClientIp, state: &State<RocketApiState<serde_json::Value>>) 
+-> ApiResponse<serde_json::Value> {
+    serving::process_request(state.api_adapter.clone(), &state.rate_limiter, &state.timeouts, state.error_format, api_request, client.0).await
+}
@@ -0,0 +1,102 @@
+use crate::api::adapters::api_adapter::ApiResponseBody;
+use crate::api::handlers::common::request_id::{request_id, tag_response};
+use crate::api::handlers::common::utils::error_response;
+use crate::config::configuration::Config;
+use crate::config::specific::server_config::ErrorFormat;
+use crate::error::RusterApiError;
+use rocket::{catchers, Catcher, Request};
+use serde_json::{json, Value};
+
+use crate::api::rocket::handlers::catch_all::header_map;
+use crate::api::rocket::rocket_adapter::ApiResponseWrapper;
+
+/// Number of entity paths suggested by the 404 responses of the debug profile
+const SUGGESTIONS: usize = 5;
+
+/// What the catchers answer with: the configured error format and the paths of the entities
+pub struct ErrorPages {
+    error_format: ErrorFormat,
+    /// Paths of the served entities, like `/api/users` or `/api/v2/users`
+    entity_paths: Vec<String>,
+}
+
+impl ErrorPages {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            error_format: config.server.error_format,
+            entity_paths: config.served_entities().into_iter().map(|(path, _)| format!("/api/{}", path)).collect(),
+        }
+    }
+
+    /// Entity paths closest to a path, most similar first
+    fn suggestions(&self, path: &str) -> Vec<&str> {
+        let path = path.trim_end_matches('/').to_lowercase();
+        let mut paths: Vec<(usize, &str)> = self.entity_paths.iter()
+            .map(|candidate| (edit_distance(&path, &candidate.to_lowercase()), candidate.as_str()))
+            .collect();
+        paths.sort();
+        paths.into_iter().take(SUGGESTIONS).map(|(_, candidate)| candidate).collect()
+    }
+}
+
+/// Number of single character insertions, deletions and substitutions turning a string into another
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Response of an error caught outside of the routes, in the configured error format
+fn caught(request: &Request<'_>, err: RusterApiError) -> ApiResponseWrapper<Value> {
+    let error_format = request.rocket().state::<ErrorPages>().map(|pages| pages.error_format).unwrap_or_default();
+    let mut api_response = error_response(&err, error_format, request.uri().path().as_str());
+    tag_response(&mut api_response, &request_id(&header_map(request)));
+    ApiResponseWrapper(api_response)
+}
+
+/// Answers the requests no route matched. With the debug profile of Rocket, the response suggests
+/// the entity paths closest to the requested one in `did_you_mean`.
+#[rocket::catch(404)]
+pub fn not_found(request: &Request<'_>) -> ApiResponseWrapper<Value> {
+    let path = request.uri().path().to_string();
+    let mut response = caught(request, RusterApiError::NotFound(format!("No route for {} {}", request.method(), path)));
+    let debug = request.rocket().figment().profile() == rocket::Config::DEBUG_PROFILE;
+    if let (true, Some(pages)) = (debug, request.rocket().state::<ErrorPages>()) {
+        if let Some(ApiResponseBody::Json(Value::Object(body))) = response.0.body.as_mut() {
+            body.insert("did_you_mean".to_string(), json!(pages.suggestions(&path)));
+        }
+    }
+    response
+}
+
+/// Answers the requests sent with a method their route does not accept
+#[rocket::catch(405)]
+pub fn method_not_allowed(request: &Request<'_>) -> ApiResponseWrapper<Value> {
+    let err = RusterApiError::MethodNotAllowed(format!("{} is not allowed on {}", request.method(), request.uri().path()));
+    caught(request, err)
+}
+
+/// Answers the requests whose body or parameters a route could not read
+#[rocket::catch(422)]
+pub fn unprocessable_entity(request: &Request<'_>) -> ApiResponseWrapper<Value> {
+    caught(request, RusterApiError::UnprocessableEntity("The request could not be processed".to_string()))
+}
+
+/// Answers the requests whose handler failed or panicked
+#[rocket::catch(500)]
+pub fn internal_error(request: &Request<'_>) -> ApiResponseWrapper<Value> {
+    caught(request, RusterApiError::ServerError("Internal server error".to_string()))
+}
+
+/// Catchers answering the errors raised outside of the routes of the API
+pub fn error_catchers() -> Vec<Catcher> {
+    catchers![not_found, method_not_allowed, unprocessable_entity, internal_error]
+}
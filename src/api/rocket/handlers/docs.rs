@@ -0,0 +1,32 @@
+use rocket::http::ContentType;
+use rocket::response::content::RawHtml;
+use rocket::State;
+use serde_json::Value;
+
+use crate::api::rocket::handlers::catch_all::PublicOrigin;
+use crate::api::rocket::rocket_adapter::RocketApiState;
+
+/// Serves the page browsing the OpenAPI document, mounted at the documentation path
+#[rocket::get("/")]
+pub fn docs_handler(state: &State<RocketApiState<Value>>) -> Option<RawHtml<String>> {
+    state.docs.as_ref().map(|docs| RawHtml(docs.page.clone()))
+}
+
+/// Serves the admin panel of the entities, when enabled
+#[rocket::get("/admin")]
+pub fn admin_panel_handler(state: &State<RocketApiState<Value>>) -> Option<RawHtml<String>> {
+    state.admin_panel.as_ref().map(|panel| RawHtml(panel.page.clone()))
+}
+
+/// Serves the OpenAPI document of the API, mounted under the documentation path, with the public
+/// origin of the request as its server
+#[rocket::get("/openapi.json")]
+pub fn openapi_handler(origin: PublicOrigin, state: &State<RocketApiState<Value>>) -> Option<(ContentType, String)> {
+    state.docs.as_ref().map(|docs| (ContentType::JSON, docs.spec_at(origin.0.as_deref()).to_string()))
+}
+
+/// Serves the JSON Schema of the configuration files, mounted under the documentation path
+#[rocket::get("/config.schema.json")]
+pub fn config_schema_handler(state: &State<RocketApiState<Value>>) -> Option<(ContentType, String)> {
+    state.docs.as_ref().map(|docs| (ContentType::JSON, docs.config_schema.to_string()))
+}
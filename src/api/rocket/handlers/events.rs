@@ -0,0 +1,61 @@
+use crate::api::adapters::api_adapter::ApiResponse;
+use crate::api::adapters::serving::change_stream;
+use crate::api::handlers::common::request_id::{request_id, tag_response};
+use crate::api::handlers::common::utils::error_response;
+use crate::config::specific::entity_config::HttpMethod;
+use crate::error::RusterApiError;
+use futures::{Stream, StreamExt};
+use rocket::http::ContentType;
+use rocket::response::stream::ReaderStream;
+use rocket::response::{self, Responder};
+use rocket::{Request, Response, Shutdown, State};
+use serde_json::Value;
+use std::io::Cursor;
+use tracing::debug;
+
+use crate::api::rocket::handlers::catch_all::RequestHeaders;
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Streamed `text/event-stream` response
+pub struct EventStreamResponse<S>(S);
+
+impl<'r, S: Stream<Item = String> + Send + 'static> Responder<'r, 'static> for EventStreamResponse<S> {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(ContentType::EventStream)
+            .raw_header("Cache-Control", "no-cache")
+            .streamed_body(ReaderStream::from(self.0.map(Cursor::new)))
+            .ok()
+    }
+}
+
+/// Streams the changes of an entity as Server-Sent Events, until the client disconnects
+/// or the server shuts down
+#[rocket::get("/<entity>/events")]
+pub async fn events_handler(
+    entity: &str,
+    headers: RequestHeaders,
+    shutdown: Shutdown,
+    state: &State<RocketApiState<Value>>,
+) -> Result<EventStreamResponse<impl Stream<Item = String>>, ApiResponseWrapper<Value>> {
+    if state.api_adapter.route_for(&HttpMethod::GET, entity).is_none() {
+        let err = RusterApiError::EntityNotFound(entity.to_string());
+        let mut response: ApiResponse<Value> = error_response(&err, state.error_format, &format!("/api/{}/events", entity));
+        tag_response(&mut response, &request_id(&headers.0));
+        return Err(ApiResponseWrapper(response));
+    }
+    let path = format!("{}/events", entity);
+    let reader = state.changes.admit(&path, headers.0.clone())
+        .and_then(|reader| reader.check(entity).map(|_| reader));
+    let reader = match reader {
+        Ok(reader) => reader,
+        Err(err) => {
+            let mut response: ApiResponse<Value> = error_response(&err, state.error_format, &format!("/api/{}", path));
+            tag_response(&mut response, &request_id(&headers.0));
+            return Err(ApiResponseWrapper(response));
+        }
+    };
+
+    debug!("Streaming the changes of {}", entity);
+    Ok(EventStreamResponse(change_stream(&state.events, entity, reader).take_until(shutdown)))
+}
@@ -0,0 +1,44 @@
+use crate::api::adapters::serving::process_graphql;
+use crate::api::graphql::schema::{graphiql, GRAPHQL_PATH};
+use crate::api::handlers::common::request_id::{request_id, tag_response};
+use crate::api::handlers::common::utils::error_response;
+use crate::error::RusterApiError;
+use rocket::response::content::RawHtml;
+use rocket::State;
+use serde_json::Value;
+
+use crate::api::rocket::handlers::catch_all::{body_to_string, ClientIp, RequestHeaders};
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Runs the GraphQL operations of a request
+#[rocket::post("/graphql", data = "<body>")]
+pub async fn graphql_handler(headers: RequestHeaders, client: ClientIp, body: rocket::Data<'_>, state: &State<RocketApiState<Value>>)
+-> ApiResponseWrapper<Value> {
+    let result = match &state.graphql {
+        Some(graphql) => body_to_string(body, state.max_payload_size_mb).await.map(|body| (graphql, body)),
+        None => Err(RusterApiError::NotFound("GraphQL is not enabled".to_string())),
+    };
+    match result {
+        Ok((graphql, body)) => ApiResponseWrapper(
+            process_graphql(&graphql.schema, &state.rate_limiter, state.error_format, body, headers.0, client.0).await
+        ),
+        Err(err) => {
+            let mut api_response = error_response(&err, state.error_format, GRAPHQL_PATH);
+            tag_response(&mut api_response, &request_id(&headers.0));
+            ApiResponseWrapper(api_response)
+        }
+    }
+}
+
+/// Serves the GraphiQL IDE, when enabled
+#[rocket::get("/graphql")]
+pub async fn graphiql_handler(headers: RequestHeaders, state: &State<RocketApiState<Value>>)
+-> Result<RawHtml<String>, ApiResponseWrapper<Value>> {
+    if state.graphql.as_ref().is_some_and(|graphql| graphql.graphiql) {
+        return Ok(RawHtml(graphiql()));
+    }
+    let err = RusterApiError::NotFound("GraphiQL is not enabled".to_string());
+    let mut api_response = error_response(&err, state.error_format, GRAPHQL_PATH);
+    tag_response(&mut api_response, &request_id(&headers.0));
+    Err(ApiResponseWrapper(api_response))
+}
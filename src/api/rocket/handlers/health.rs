@@ -0,0 +1,23 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use crate::api::handlers::common::utils::default_headers;
+use rocket::State;
+use serde_json;
+
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Reports the reachability of the configured webhooks and upstream proxies.
+/// Responds with 503 when any of them is down.
+#[rocket::get("/health")]
+pub async fn health_handler(state: &State<RocketApiState<serde_json::Value>>)
+-> ApiResponseWrapper<serde_json::Value> {
+    let healthy = state.health.is_healthy();
+
+    ApiResponseWrapper(ApiResponse {
+        status: if healthy { 200 } else { 503 },
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(serde_json::json!({
+            "status": if healthy { "up" } else { "degraded" },
+            "targets": state.health.snapshot(),
+        }))),
+    })
+}
@@ -0,0 +1,34 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use crate::api::adapters::maintenance::MAINTENANCE_PATH;
+use crate::api::handlers::common::utils::{default_headers, error_response};
+use rocket::State;
+use serde_json::Value;
+
+use crate::api::rocket::handlers::catch_all::body_to_string;
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Reports whether the maintenance mode is on
+#[rocket::get("/admin/maintenance")]
+pub async fn maintenance_handler(state: &State<RocketApiState<Value>>) -> ApiResponseWrapper<Value> {
+    ApiResponseWrapper(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(state.maintenance.status())),
+    })
+}
+
+/// Turns the maintenance mode on or off with a `{"enabled": true}` body
+#[rocket::put("/admin/maintenance", data = "<body>")]
+pub async fn toggle_maintenance_handler(body: rocket::Data<'_>, state: &State<RocketApiState<Value>>)
+-> ApiResponseWrapper<Value> {
+    let result = body_to_string(body, state.max_payload_size_mb).await
+        .and_then(|body| state.maintenance.toggle(&body.unwrap_or_default()));
+    ApiResponseWrapper(match result {
+        Ok(status) => ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Json(status)),
+        },
+        Err(err) => error_response(&err, state.error_format, MAINTENANCE_PATH),
+    })
+}
@@ -0,0 +1,19 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use crate::api::handlers::common::utils::default_headers;
+use rocket::State;
+use serde_json;
+
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Reports runtime metrics of the API, such as the datasource connection pool statistics
+#[rocket::get("/metrics")]
+pub async fn metrics_handler(state: &State<RocketApiState<serde_json::Value>>) 
+-> ApiResponseWrapper<serde_json::Value> {
+    let pool_stats = state.api_adapter.pool_stats();
+
+    ApiResponseWrapper(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(serde_json::json!({ "pool": pool_stats }))),
+    })
+}
@@ -0,0 +1,45 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use crate::api::adapters::oauth::OAUTH_LOGIN_PATH;
+use crate::api::handlers::common::utils::{default_headers, error_response};
+use crate::error::RusterApiError;
+use rocket::State;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::api::rocket::handlers::catch_all::QueryParams;
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Redirects to the authorization page of a provider to sign in with it, mounted at `/auth/oauth`
+#[rocket::get("/<provider>")]
+pub async fn login_handler(provider: &str, state: &State<RocketApiState<Value>>) -> ApiResponseWrapper<Value> {
+    let result = match &state.oauth {
+        Some(oauth) => oauth.authorize_url(provider),
+        None => Err(RusterApiError::NotFound("OAuth is not enabled".to_string())),
+    };
+    ApiResponseWrapper(match result {
+        Ok(url) => ApiResponse {
+            status: 302,
+            headers: HashMap::from([("Location".to_string(), url)]),
+            body: None,
+        },
+        Err(err) => error_response(&err, state.error_format, &format!("{}/{}", OAUTH_LOGIN_PATH, provider)),
+    })
+}
+
+/// Exchanges the authorization code sent back by a provider for its tokens, mounted at the path
+/// of the callback URL
+#[rocket::get("/")]
+pub async fn callback_handler(query: QueryParams, state: &State<RocketApiState<Value>>) -> ApiResponseWrapper<Value> {
+    let Some(oauth) = &state.oauth else {
+        let err = RusterApiError::NotFound("OAuth is not enabled".to_string());
+        return ApiResponseWrapper(error_response(&err, state.error_format, OAUTH_LOGIN_PATH));
+    };
+    ApiResponseWrapper(match oauth.exchange(&query.0).await {
+        Ok(tokens) => ApiResponse {
+            status: 200,
+            headers: default_headers(),
+            body: Some(ApiResponseBody::Json(tokens)),
+        },
+        Err(err) => error_response(&err, state.error_format, &oauth.callback_path()),
+    })
+}
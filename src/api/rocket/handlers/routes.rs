@@ -0,0 +1,17 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use crate::api::handlers::common::utils::default_headers;
+use rocket::State;
+use serde_json;
+
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Lists the endpoints of the API with their method, entity and whether they require authentication
+#[rocket::get("/_routes")]
+pub async fn routes_handler(state: &State<RocketApiState<serde_json::Value>>)
+-> ApiResponseWrapper<serde_json::Value> {
+    ApiResponseWrapper(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(serde_json::json!({ "routes": state.api_adapter.routes() }))),
+    })
+}
@@ -0,0 +1,26 @@
+use crate::api::adapters::api_adapter::{ApiResponse, ApiResponseBody};
+use crate::api::handlers::common::utils::default_headers;
+use rocket::State;
+use serde_json;
+
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Reports the endpoints whose response shape drifted from the stored snapshots
+#[rocket::get("/admin/schema-drift")]
+pub async fn schema_drift_handler(state: &State<RocketApiState<serde_json::Value>>)
+-> ApiResponseWrapper<serde_json::Value> {
+    let body = match &state.schema_drift {
+        Some(monitor) => serde_json::json!({
+            "enabled": true,
+            "snapshots": monitor.snapshot_count(),
+            "drifts": monitor.reports(),
+        }),
+        None => serde_json::json!({ "enabled": false, "snapshots": 0, "drifts": [] }),
+    };
+
+    ApiResponseWrapper(ApiResponse {
+        status: 200,
+        headers: default_headers(),
+        body: Some(ApiResponseBody::Json(body)),
+    })
+}
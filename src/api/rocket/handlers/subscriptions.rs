@@ -0,0 +1,90 @@
+use crate::api::adapters::api_adapter::{ApiAdapterTrait, ApiResponse};
+use crate::api::adapters::change_access::ChangeReader;
+use crate::api::adapters::subscriptions::subscription_stream;
+use crate::api::handlers::common::request_id::{request_id, tag_response};
+use crate::api::handlers::common::utils::error_response;
+use crate::config::specific::entity_config::HttpMethod;
+use crate::error::RusterApiError;
+use crate::events::event_bus::EventBus;
+use futures::future::ready;
+use futures::{SinkExt, StreamExt};
+use rocket::data::{IoHandler, IoStream};
+use rocket::response::{self, Responder};
+use rocket::{Request, Response, State};
+use serde_json::Value;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::api::rocket::handlers::catch_all::RequestHeaders;
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Header of the WebSocket handshake the accept key is derived from
+const WEBSOCKET_KEY_HEADER: &str = "sec-websocket-key";
+
+/// Accepts a WebSocket handshake, handing the upgraded connection to the subscriptions
+pub struct SubscriptionsSocket {
+    accept_key: String,
+    api_adapter: Arc<dyn ApiAdapterTrait<Value> + Send + Sync>,
+    events: EventBus,
+    reader: ChangeReader,
+}
+
+impl<'r> Responder<'r, 'static> for SubscriptionsSocket {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .raw_header("Sec-WebSocket-Accept", self.accept_key.clone())
+            .upgrade("websocket", self)
+            .ok()
+    }
+}
+
+#[rocket::async_trait]
+impl IoHandler for SubscriptionsSocket {
+    async fn io(self: Pin<Box<Self>>, io: IoStream) -> io::Result<()> {
+        let socket = *Pin::into_inner(self);
+        let (mut sink, stream) = WebSocketStream::from_raw_socket(io, Role::Server, None).await.split();
+        let incoming = stream
+            .take_while(|message| ready(message.as_ref().is_ok_and(|message| !message.is_close())))
+            .filter_map(|message| ready(match message {
+                Ok(Message::Text(text)) => Some(text),
+                _ => None,
+            }));
+        let api_adapter = socket.api_adapter;
+        let serves = move |entity: &str| api_adapter.route_for(&HttpMethod::GET, entity).is_some();
+
+        let mut outgoing = Box::pin(subscription_stream(&socket.events, Box::pin(incoming), serves, socket.reader));
+        while let Some(text) = outgoing.next().await {
+            sink.send(Message::Text(text)).await.map_err(io::Error::other)?;
+        }
+        sink.close().await.map_err(io::Error::other)
+    }
+}
+
+/// WebSocket where clients subscribe to the changes of entities
+#[rocket::get("/ws")]
+pub async fn subscriptions_handler(headers: RequestHeaders, state: &State<RocketApiState<Value>>)
+-> Result<SubscriptionsSocket, ApiResponseWrapper<Value>> {
+    let Some(key) = headers.0.get(WEBSOCKET_KEY_HEADER) else {
+        let err = RusterApiError::BadRequest("Expected a WebSocket handshake".to_string());
+        let mut response: ApiResponse<Value> = error_response(&err, state.error_format, "/ws");
+        tag_response(&mut response, &request_id(&headers.0));
+        return Err(ApiResponseWrapper(response));
+    };
+    let reader = state.changes.admit("ws", headers.0.clone()).map_err(|err| {
+        let mut response: ApiResponse<Value> = error_response(&err, state.error_format, "/ws");
+        tag_response(&mut response, &request_id(&headers.0));
+        ApiResponseWrapper(response)
+    })?;
+
+    Ok(SubscriptionsSocket {
+        accept_key: derive_accept_key(key.as_bytes()),
+        api_adapter: state.api_adapter.clone(),
+        events: state.events.clone(),
+        reader,
+    })
+}
@@ -0,0 +1,20 @@
+use crate::api::handlers::common::utils::error_response;
+use crate::api::adapters::uploads::UPLOADS_PATH;
+use rocket::http::ContentType;
+use rocket::State;
+use serde_json::Value;
+
+use crate::api::rocket::rocket_adapter::{ApiResponseWrapper, RocketApiState};
+
+/// Serves a file uploaded to a Binary field, mounted at the uploads path
+#[rocket::get("/<folder>/<file>")]
+pub async fn download_handler(folder: &str, file: &str, state: &State<RocketApiState<Value>>)
+-> Result<(ContentType, Vec<u8>), ApiResponseWrapper<Value>> {
+    match state.uploads.download(folder, file).await {
+        Ok((bytes, content_type)) => Ok((ContentType::parse_flexible(&content_type).unwrap_or(ContentType::Binary), bytes)),
+        Err(err) => {
+            let instance = format!("{}/{}/{}", UPLOADS_PATH, folder, file);
+            Err(ApiResponseWrapper(error_response(&err, state.error_format, &instance)))
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::config::configuration::Config;
+use crate::config::specific::server_config::RateLimitConfig;
+use crate::error::{Result, RusterApiError};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Number of buckets above which idle buckets are dropped, so per-IP limits cannot grow without bounds
+const MAX_BUCKETS: usize = 10_000;
+
+/// A token bucket holding up to `burst` requests, refilled at `requests_per_minute`
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    capacity: f64,
+    /// Tokens added per second
+    rate: f64,
+}
+
+impl Bucket {
+    /// Adds the tokens earned since the last refill
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled_at = now;
+    }
+}
+
+/// A configured limit
+#[derive(Clone)]
+struct Limit {
+    capacity: f64,
+    /// Tokens added per second
+    rate: f64,
+    per_ip: bool,
+}
+
+impl Limit {
+    fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            capacity: f64::from(config.burst.max(1)),
+            rate: f64::from(config.requests_per_minute) / 60.0,
+            per_ip: config.per_ip,
+        }
+    }
+
+    /// Key of the bucket of a client, shared by every client unless the limit is per IP
+    fn bucket_key(&self, scope: &str, client: Option<IpAddr>) -> String {
+        match client.filter(|_| self.per_ip) {
+            Some(ip) => format!("{}@{}", scope, ip),
+            None => scope.to_string(),
+        }
+    }
+}
+
+/// Enforces `server.rate_limiting` on every request and the `rate_limiting` of each entity on
+/// its endpoints, with token buckets. Clones share the same buckets.
+#[derive(Clone)]
+pub struct RateLimiter {
+    server: Option<Limit>,
+    /// Limits of the entities by the lowercase path they are served at, like `users` or `v2/users`
+    entities: HashMap<String, Limit>,
+    /// Lowercase names of the versions of the API
+    versions: Vec<String>,
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            server: config.server.rate_limiting.as_ref().map(Limit::new),
+            entities: config.served_entities().into_iter()
+                .filter_map(|(path, entity)| Some((path.to_lowercase(), Limit::new(entity.rate_limiting.as_ref()?))))
+                .collect(),
+            versions: config.versions.iter().map(|version| version.name.to_lowercase()).collect(),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Limit of the entity a request to `path` is for: the one served at its first segment after the
+    /// `api` prefix, or at its first two when the first one names a version
+    fn entity_limit(&self, path: &str) -> Option<(&String, &Limit)> {
+        let mut segments = path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_lowercase)
+            .peekable();
+        segments.next_if(|segment| segment == "api");
+        let first = segments.next()?;
+        let served = match segments.next() {
+            Some(second) if self.versions.contains(&first) => format!("{}/{}", first, second),
+            _ => first,
+        };
+        self.entities.get_key_value(&served)
+    }
+
+    /// Takes a token for a request to `path` from every bucket applying to it, or fails with the
+    /// seconds to wait when one of them is empty. No token is taken from a rejected request.
+    pub fn check(&self, path: &str, client: Option<IpAddr>) -> Result<()> {
+        let entity = self.entity_limit(path);
+        let mut limits: Vec<(String, &Limit)> = Vec::new();
+        if let Some(limit) = &self.server {
+            limits.push((limit.bucket_key("server", client), limit));
+        }
+        if let Some((name, limit)) = entity {
+            limits.push((limit.bucket_key(&format!("entity:{}", name), client), limit));
+        }
+        if limits.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let mut wait: f64 = 0.0;
+        for (key, limit) in &limits {
+            let bucket = buckets.entry(key.clone()).or_insert(Bucket {
+                tokens: limit.capacity,
+                refilled_at: now,
+                capacity: limit.capacity,
+                rate: limit.rate,
+            });
+            bucket.refill(now);
+            if bucket.tokens < 1.0 {
+                let seconds = if limit.rate > 0.0 { (1.0 - bucket.tokens) / limit.rate } else { 60.0 };
+                wait = wait.max(seconds);
+            }
+        }
+        if wait > 0.0 {
+            return Err(RusterApiError::TooManyRequests(wait.ceil() as u64));
+        }
+
+        for (key, _) in &limits {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        // Full buckets are the same as new ones, so they can be forgotten
+        if buckets.len() > MAX_BUCKETS {
+            buckets.retain(|_, bucket| {
+                bucket.refill(now);
+                bucket.tokens < bucket.capacity
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::shared::EntityBasic;
+
+    fn limiter() -> RateLimiter {
+        let mut config = Config::new();
+        let mut orders = EntityBasic { name: "Orders".to_string(), table_name: None, fields: Vec::new(), authentication: false }.to_entity();
+        orders.rate_limiting = Some(RateLimitConfig { requests_per_minute: 0, burst: 1, per_ip: false });
+        let users = EntityBasic { name: "users".to_string(), table_name: None, fields: Vec::new(), authentication: false }.to_entity();
+        config.entities_advanced = vec![orders, users];
+        RateLimiter::new(&config)
+    }
+
+    #[test]
+    fn limits_an_entity_whatever_the_case_of_its_path() {
+        let limiter = limiter();
+        assert!(limiter.check("api/orders", None).is_ok());
+        assert!(matches!(limiter.check("ORDERS/1", None), Err(RusterApiError::TooManyRequests(_))));
+    }
+
+    #[test]
+    fn only_the_entity_segment_picks_the_limit() {
+        let limiter = limiter();
+        for _ in 0..3 {
+            assert!(limiter.check("users/orders", None).is_ok());
+            assert!(limiter.check("api/users/1/orders", None).is_ok());
+        }
+    }
+}
@@ -1,45 +1,93 @@
-use crate::api::adapters::api_adapter::{ApiAdapter, ApiAdapterTrait, ApiResponse, ApiResponseBody};
+use crate::api::adapters::api_adapter::{ApiAdapter, ApiAdapterTrait, ApiResponse};
+use crate::api::adapters::change_access::ChangeAccess;
+use crate::api::adapters::maintenance::MaintenanceMode;
+use crate::api::adapters::oauth::{OAuthProviders, OAUTH_LOGIN_PATH};
+use crate::api::adapters::proxy::ProxyTrust;
+use crate::api::adapters::schema_drift::SchemaDriftMonitor;
+use crate::api::adapters::serving::{listeners, max_payload_size_mb, render_body, tls_certificate, ServedApi};
+use crate::api::adapters::uploads::{UploadStore, UPLOADS_PATH};
+use crate::api::docs::admin_panel::AdminPanel;
+use crate::api::docs::openapi::ApiDocs;
+use crate::events::event_bus::EventBus;
+#[cfg(feature = "graphql")]
+use crate::api::graphql::schema::GraphqlEndpoint;
+#[cfg(feature = "graphql")]
+use crate::api::rocket::handlers::graphql;
+use crate::health::health_monitor::HealthRegistry;
 use crate::error::{Result, RusterApiError};
 use crate::api::common::api_entity::ApiEntity;
-use rocket::{Request, Response};
+use crate::config::configuration::Config;
+use crate::config::specific::server_config::{ErrorFormat, ResponseFormat};
+use futures::future::try_join_all;
+use rocket::{Build, Request, Response, Rocket};
+use rocket::config::TlsConfig;
+use rocket::data::{Limits, ToByteUnit};
+use rocket::figment::Figment;
+use rocket::fs::FileServer;
 use rocket::http::{ContentType, Status as RocketStatus};
 use rocket::response::{self, Responder};
 use rocket::routes;
 use serde::Serialize;
+use serde_json::Value;
 use std::io::Cursor;
+use std::net::ToSocketAddrs;
+use std::path::Path;
 use std::sync::Arc;
 
 // Import handlers from our new module
-use crate::api::rocket::handlers::catch_all;
+use crate::api::rocket::handlers::catchers::{error_catchers, ErrorPages};
+use crate::api::rocket::handlers::{catch_all, docs, events, health, maintenance, metrics, oauth, routes, schema_drift, subscriptions, uploads};
+use crate::api::rocket::fairings::cors::CorsFairing;
+use crate::api::adapters::request_timeout::RequestTimeouts;
+use crate::api::rocket::rate_limit::RateLimiter;
+
+// Rank of the static files served from the directory of the longest `server.static_dirs` prefix
+const STATIC_FILES_RANK: isize = 10;
 
 // Structure to hold the API adapter for use in Rocket routes - now public
+#[derive(Clone)]
 pub struct RocketApiState<T: ApiEntity> {
     pub api_adapter: Arc<dyn ApiAdapterTrait<T> + Send + Sync>,
+    pub health: HealthRegistry,
+    pub schema_drift: Option<SchemaDriftMonitor>,
+    /// Bus the changes of the entities are streamed from
+    pub events: EventBus,
+    /// GraphQL endpoint, when configured
+    #[cfg(feature = "graphql")]
+    pub graphql: Option<GraphqlEndpoint>,
+    /// OpenAPI document and its viewer, when enabled
+    pub docs: Option<ApiDocs>,
+    /// Admin panel of the entities, when enabled
+    pub admin_panel: Option<AdminPanel>,
+    /// Files uploaded to the Binary fields of the entities
+    pub uploads: UploadStore,
+    /// Trust of the forwarding headers of the reverse proxies in front of the server
+    pub proxy: ProxyTrust,
+    /// Maintenance mode rejecting the changes of the entities
+    pub maintenance: MaintenanceMode,
+    /// OAuth providers signing users in, when enabled
+    pub oauth: Option<OAuthProviders>,
+    /// Access of the callers to the streamed changes of the entities
+    pub changes: ChangeAccess,
+    pub error_format: ErrorFormat,
+    pub rate_limiter: RateLimiter,
+    /// Timeouts of the requests to the endpoints
+    pub timeouts: RequestTimeouts,
+    /// Maximum size of request bodies in megabytes
+    pub max_payload_size_mb: u32,
 }
 
 // Custom responder to handle our API responses - now public
 pub struct ApiResponseWrapper<T: Serialize>(pub ApiResponse<T>);
 
 impl<'r, T: Serialize> Responder<'r, 'static> for ApiResponseWrapper<T> {
-    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
         let api_response = self.0;
         let status = RocketStatus::from_code(api_response.status).unwrap_or(RocketStatus::Ok);
-        
-        // Serialization of the body , there are 3 types of body
-        // 1. Json
-        // 2. Single
-        // 3. List
-        let body = match api_response.body {
-            Some(ApiResponseBody::Json(value)) => match serde_json::to_string(&value) { // Json
-                Ok(json) => json,
-                Err(_) => r#"{"error": "Failed to serialize response"}"#.to_string(),
-            },
-            Some(body) => match serde_json::to_string(&body) { // Single or List
-                Ok(json) => json,
-                Err(_) => r#"{"error": "Failed to serialize response"}"#.to_string(),
-            },
-            None => String::new(),
-        };
+        let format = request.rocket().state::<ResponseFormat>().copied().unwrap_or_default();
+
+        // Serialization of the body in the configured response format
+        let body = render_body(&api_response, format);
 
         let body_len = body.len();
         // Create a response with the body owned by the response
@@ -52,12 +100,13 @@ impl<'r, T: Serialize> Responder<'r, 'static> for ApiResponseWrapper<T> {
         let mut response_builder = Response::build_from(response);
         
         // Add headers
+        let has_content_type = api_response.headers.keys().any(|key| key.eq_ignore_ascii_case("Content-Type"));
         for (key, value) in api_response.headers {
             response_builder.raw_header(key, value);
         }
         
-        // If we have a body, ensure content type is set to JSON
-        if body_len > 0 {
+        // If we have a body without a content type, set it to JSON
+        if body_len > 0 && !has_content_type {
             response_builder.header(ContentType::JSON);
         }
         
@@ -67,23 +116,192 @@ impl<'r, T: Serialize> Responder<'r, 'static> for ApiResponseWrapper<T> {
 
 // Main function to start the Rocket server
 pub async fn start_server<T: ApiEntity>(api_adapter: ApiAdapter<T>) -> Result<()> {
+    let config = api_adapter.config.clone();
+    launch(rocket_state(ServedApi::new(api_adapter)?, &config), &config).await
+}
+
+// Starts a stub server replaying the recording configured in `ServerConfig.record_replay`,
+// without connecting to any datasource
+pub async fn start_replay_server(config: &Config) -> Result<()> {
+    launch(rocket_state(ServedApi::replay(config)?, config), config).await
+}
+
+// State of the Rocket routes serving an API
+fn rocket_state<T: ApiEntity>(served: ServedApi<T>, config: &Config) -> RocketApiState<T> {
+    RocketApiState {
+        api_adapter: served.api_adapter,
+        health: served.health,
+        schema_drift: served.schema_drift,
+        events: served.events,
+        #[cfg(feature = "graphql")]
+        graphql: served.graphql,
+        docs: served.docs,
+        admin_panel: served.admin_panel,
+        uploads: served.uploads,
+        proxy: served.proxy,
+        maintenance: served.maintenance,
+        oauth: served.oauth,
+        changes: served.changes,
+        error_format: config.server.error_format,
+        rate_limiter: RateLimiter::new(config),
+        timeouts: RequestTimeouts::new(config),
+        max_payload_size_mb: max_payload_size_mb(config),
+    }
+}
+
+// Directories of `server.static_dirs` by mount point, longest first, which must exist and lie
+// outside of `/api`
+fn static_dirs(config: &Config) -> Result<Vec<(String, String)>> {
+    let mut dirs = Vec::new();
+    for (prefix, directory) in &config.server.static_dirs {
+        let prefix = match prefix.trim().trim_end_matches('/') {
+            "" => "/",
+            prefix => prefix,
+        };
+        if !prefix.starts_with('/') || prefix == "/api" || prefix.starts_with("/api/") {
+            return Err(RusterApiError::ConfigError(format!(
+                "Invalid server.static_dirs prefix {}: expected an absolute path outside of /api", prefix
+            )));
+        }
+        if !Path::new(directory).is_dir() {
+            return Err(RusterApiError::ConfigError(format!(
+                "server.static_dirs directory {} of {} does not exist", directory, prefix
+            )));
+        }
+        dirs.push((prefix.to_string(), directory.clone()));
+    }
+    dirs.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    Ok(dirs)
+}
+
+// Builds and launches the Rocket instances serving the given state: one per listener of
+// `server.listeners`, or a single one at the address of the Rocket configuration
+async fn launch<T: ApiEntity>(rocket_api_state: RocketApiState<T>, config: &Config) -> Result<()> {
+    // Rocket applies the same payload limit to the bodies it reads itself
+    let payload_limit = u64::from(rocket_api_state.max_payload_size_mb).mebibytes();
+    let limits = Limits::default()
+        .limit("bytes", payload_limit)
+        .limit("string", payload_limit)
+        .limit("json", payload_limit);
+    if config.server.socket_path.is_some() {
+        return Err(RusterApiError::ConfigError("server.socket_path is only supported by the Actix framework".to_string()));
+    }
+    let mut figment = rocket::Config::figment().merge(("limits", limits));
+    if let Some(tuning) = &config.server.tuning {
+        if tuning.max_connections.is_some() {
+            return Err(RusterApiError::ConfigError(
+                "server.tuning.max_connections is only supported by the Actix framework".to_string()
+            ));
+        }
+        if let Some(seconds) = tuning.keep_alive_seconds {
+            figment = figment.merge(("keep_alive", seconds));
+        }
+        // Only used when Rocket starts the runtime; the runtime of `server_runtime` has them already
+        if let Some(workers) = tuning.workers {
+            figment = figment.merge(("workers", workers));
+        }
+        if let Some(threads) = tuning.max_blocking_threads {
+            figment = figment.merge(("max_blocking", threads));
+        }
+    }
+    if let Some(certificate) = tls_certificate(config)? {
+        figment = figment.merge(("tls", TlsConfig::from_bytes(&certificate.cert, &certificate.key)));
+    }
 
-    let rocket_api_state = RocketApiState {
-        api_adapter: Arc::new(api_adapter),
-    };
+    let listeners = listeners(config)?;
+    if listeners.is_empty() {
+        return launch_instance(build_instance(rocket_api_state, config, figment, true)?).await;
+    }
+    let mut instances = Vec::new();
+    for listener in listeners {
+        // Rocket binds to an IP address, so host names are resolved first
+        let address = (listener.host.as_str(), listener.port).to_socket_addrs().ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or_else(|| RusterApiError::ConfigError(format!("Cannot resolve listener {}:{}", listener.host, listener.port)))?;
+        let figment = figment.clone().merge(("address", address.ip())).merge(("port", address.port()));
+        instances.push(launch_instance(build_instance(rocket_api_state.clone(), config, figment, listener.admin)?));
+    }
+    try_join_all(instances).await.map(|_| ())
+}
+
+// Builds a Rocket instance with our routes and state. The admin routes, including the
+// documentation, are left out of the instances of listeners that do not serve them.
+fn build_instance<T: ApiEntity>(rocket_api_state: RocketApiState<T>, config: &Config, figment: Figment, admin: bool)
+-> Result<Rocket<Build>> {
+    Ok(mount(rocket::custom(figment), rocket_api_state, config, admin)?.register("/", error_catchers()))
+}
 
-    // Create a Rocket instance with our routes and state
-    let rocket_instance = rocket::build()
+/// Adds the routes, state and CORS fairing of an API to the Rocket instance of an embedding
+/// application, which keeps its own configuration, routes and launch. The API is served under
+/// `/api`, with the admin routes, the documentation and the static directories of the configuration,
+/// so the application must not mount routes of its own there. Errors under `/api` are caught in the
+/// configured error format, leaving the other paths to the catchers of the application. Starts the
+/// background tasks of the API like `start_server` does, so it is called on the Tokio runtime, as
+/// from a `#[launch]` function.
+pub fn attach(rocket: Rocket<Build>, api_adapter: ApiAdapter<Value>) -> Result<Rocket<Build>> {
+    let config = api_adapter.config.clone();
+    Ok(mount(rocket, rocket_state(ServedApi::new(api_adapter)?, &config), &config, true)?.register("/api", error_catchers()))
+}
+
+// Adds our routes and state to a Rocket instance, with the admin routes when asked to
+fn mount<T: ApiEntity>(rocket_instance: Rocket<Build>, rocket_api_state: RocketApiState<T>, config: &Config, admin: bool)
+-> Result<Rocket<Build>> {
+    #[cfg(feature = "graphql")]
+    let graphql_routes = routes![graphql::graphql_handler, graphql::graphiql_handler];
+    #[cfg(not(feature = "graphql"))]
+    let graphql_routes = routes![];
+
+    // The documentation and the OAuth callback are mounted at their configured paths
+    let docs_path = rocket_api_state.docs.as_ref().map(|docs| docs.path.clone());
+    let callback_path = rocket_api_state.oauth.as_ref().map(OAuthProviders::callback_path);
+
+    let mut rocket_instance = rocket_instance
         .manage(rocket_api_state)
+        .manage(config.server.response_format)
+        .manage(ErrorPages::new(config))
+        .attach(CorsFairing::new(config))
         .mount("/api", routes![
             catch_all::get_handler,
+            catch_all::head_handler,
+            catch_all::options_handler,
             catch_all::post_handler,
             catch_all::put_handler,
             catch_all::delete_handler,
-            catch_all::patch_handler
+            catch_all::patch_handler,
+            events::events_handler
+        ])
+        .mount("/", routes![subscriptions::subscriptions_handler])
+        .mount("/", graphql_routes)
+        .mount(UPLOADS_PATH, routes![uploads::download_handler]);
+    if let Some(callback_path) = callback_path {
+        rocket_instance = rocket_instance
+            .mount(OAUTH_LOGIN_PATH, routes![oauth::login_handler])
+            .mount(callback_path, routes![oauth::callback_handler]);
+    }
+    if admin {
+        rocket_instance = rocket_instance.mount("/", routes![
+            metrics::metrics_handler,
+            health::health_handler,
+            schema_drift::schema_drift_handler,
+            maintenance::maintenance_handler,
+            maintenance::toggle_maintenance_handler,
+            routes::routes_handler,
+            docs::admin_panel_handler
         ]);
+        if let Some(docs_path) = docs_path {
+            rocket_instance = rocket_instance.mount(docs_path, routes![docs::docs_handler, docs::openapi_handler, docs::config_schema_handler]);
+        }
+    }
+    // Static files rank below the routes of the API, so a directory mounted at `/` does not hide them,
+    // and longer prefixes are tried first
+    for ((prefix, directory), rank) in static_dirs(config)?.into_iter().zip(STATIC_FILES_RANK..) {
+        rocket_instance = rocket_instance.mount(prefix, FileServer::from(directory).rank(rank));
+    }
+    Ok(rocket_instance)
+}
 
-    // Launch Rocket and handle any errors
+// Launches a Rocket instance and handles any errors
+async fn launch_instance(rocket_instance: Rocket<Build>) -> Result<()> {
     rocket_instance.launch().await.map(|_| ()).map_err(|e| {
         RusterApiError::ServerError(format!("Failed to launch Rocket server: {:?}", e))
     })
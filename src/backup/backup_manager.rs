@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Serialize, Deserialize};
+use crate::config::configuration::{Config, ConfigFormat, Configuration};
+use crate::config::specific::backup_config::BackupConfig;
+use crate::data::datasource::base::DataSourceError;
+use crate::data::transfer::{read_file, write_file};
+use tracing::warn;
+#[cfg(feature = "native")]
+use tracing::{error, info};
+
+/// Name of the manifest file stored in every backup directory
+const MANIFEST_FILE: &str = "manifest.json";
+/// Name of the configuration snapshot stored in every backup directory
+const CONFIG_FILE: &str = "config.json";
+/// Prefix of the backup directory names
+const BACKUP_PREFIX: &str = "backup-";
+
+/// A data file stored in a backup
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupFile {
+    /// Path of the file when the backup was taken
+    pub original_path: String,
+    /// Name of the copy inside the backup directory
+    pub stored_name: String,
+}
+
+/// Description of a backup, stored as its manifest
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupManifest {
+    /// Identifier of the backup (name of its directory)
+    pub id: String,
+    /// Creation time in seconds since the Unix epoch
+    pub created_at: u64,
+    /// Whether the stored files are encrypted
+    pub encrypted: bool,
+    /// Data files included in the backup
+    pub files: Vec<BackupFile>,
+}
+
+/// Takes, lists, prunes and restores snapshots of the active configuration
+/// and the configured data files
+pub struct BackupManager {
+    backup_config: BackupConfig,
+    config: Config,
+}
+
+impl BackupManager {
+    /// Creates a backup manager for the given configuration.
+    /// Returns None when backups are not configured.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.backup.clone().map(|backup_config| Self {
+            backup_config,
+            config: config.clone(),
+        })
+    }
+
+    fn backup_dir(&self) -> &Path {
+        Path::new(&self.backup_config.directory)
+    }
+
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+    }
+
+    /// Takes a snapshot of the configuration and the data files, then applies the retention policy
+    pub fn create_backup(&self) -> Result<BackupManifest, Box<dyn Error>> {
+        fs::create_dir_all(self.backup_dir())?;
+
+        let created_at = Self::now();
+        let mut id = format!("{}{}", BACKUP_PREFIX, created_at);
+        let mut suffix = 1;
+        while self.backup_dir().join(&id).exists() {
+            id = format!("{}{}-{}", BACKUP_PREFIX, created_at, suffix);
+            suffix += 1;
+        }
+        let target = self.backup_dir().join(&id);
+        fs::create_dir_all(&target)?;
+
+        let encryption = self.backup_config.encryption.as_ref();
+        write_file(&target.join(CONFIG_FILE), self.config.get_config()?.as_bytes(), encryption)?;
+
+        let mut files = Vec::new();
+        for (index, original_path) in self.backup_config.files.iter().enumerate() {
+            let path = Path::new(original_path);
+            if !path.exists() {
+                warn!("Backup: skipping missing file {}", original_path);
+                continue;
+            }
+            let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let stored_name = format!("{}-{}", index, file_name);
+            write_file(&target.join(&stored_name), &fs::read(path)?, encryption)?;
+            files.push(BackupFile {
+                original_path: original_path.clone(),
+                stored_name,
+            });
+        }
+
+        let manifest = BackupManifest {
+            id,
+            created_at,
+            encrypted: encryption.is_some(),
+            files,
+        };
+        fs::write(target.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+
+        self.apply_retention()?;
+        Ok(manifest)
+    }
+
+    /// Lists the available backups, newest first
+    pub fn list_backups(&self) -> Result<Vec<BackupManifest>, Box<dyn Error>> {
+        if !self.backup_dir().exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(self.backup_dir())? {
+            let path = entry?.path().join(MANIFEST_FILE);
+            if let Ok(data) = fs::read(&path) {
+                match serde_json::from_slice::<BackupManifest>(&data) {
+                    Ok(manifest) => backups.push(manifest),
+                    Err(e) => warn!("Backup: ignoring invalid manifest {}: {}", path.display(), e),
+                }
+            }
+        }
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+        Ok(backups)
+    }
+
+    /// Deletes the backups exceeding the configured count or age.
+    /// Returns the identifiers of the deleted backups.
+    pub fn apply_retention(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let retention = &self.backup_config.retention;
+        let oldest_allowed = retention.max_age_days
+            .map(|days| Self::now().saturating_sub(days as u64 * 24 * 60 * 60));
+
+        let mut deleted = Vec::new();
+        for (position, backup) in self.list_backups()?.into_iter().enumerate() {
+            let over_count = retention.max_backups.is_some_and(|max| position >= max as usize);
+            let too_old = oldest_allowed.is_some_and(|oldest| backup.created_at < oldest);
+            // Never delete the newest backup, even if it is older than the max age
+            if position > 0 && (over_count || too_old) {
+                fs::remove_dir_all(self.backup_dir().join(&backup.id))?;
+                deleted.push(backup.id);
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Restores a backup: the data files are written back to their original paths
+    /// and the configuration snapshot is written to `config_path`, in the format of its extension
+    pub fn restore(&self, backup_id: &str, config_path: &str) -> Result<BackupManifest, Box<dyn Error>> {
+        let source = self.backup_dir().join(backup_id);
+        let manifest: BackupManifest = serde_json::from_slice(&fs::read(source.join(MANIFEST_FILE)).map_err(|_| {
+            DataSourceError::NotFound(format!("Backup {} not found", backup_id))
+        })?)?;
+        let encryption = self.backup_config.encryption.as_ref();
+
+        // Read everything first so a wrong key or a corrupted file leaves the current files untouched
+        let mut config_data = read_file(&source.join(CONFIG_FILE), encryption)?;
+        // The snapshot is JSON, and is written back in the format of the configuration file
+        let format = ConfigFormat::from_path(config_path);
+        if format != ConfigFormat::Json {
+            let config: Config = serde_json::from_slice(&config_data)?;
+            config_data = format.serialize(&config)?.into_bytes();
+        }
+        let mut restored_files = Vec::new();
+        for file in &manifest.files {
+            restored_files.push((PathBuf::from(&file.original_path), read_file(&source.join(&file.stored_name), encryption)?));
+        }
+
+        for (path, data) in restored_files {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, data)?;
+        }
+        fs::write(config_path, config_data)?;
+
+        Ok(manifest)
+    }
+
+    /// Spawns a background task taking a backup every `interval_minutes`
+    #[cfg(feature = "native")]
+    pub fn spawn_scheduler(self) -> tokio::task::JoinHandle<()> {
+        let interval = std::time::Duration::from_secs(self.backup_config.interval_minutes.max(1) as u64 * 60);
+        let manager = std::sync::Arc::new(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let manager = manager.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    manager.create_backup().map_err(|e| e.to_string())
+                }).await;
+
+                match result {
+                    Ok(Ok(manifest)) => info!("Backup {} created", manifest.id),
+                    Ok(Err(e)) => error!("Backup failed: {}", e),
+                    Err(e) => error!("Backup task failed: {}", e),
+                }
+            }
+        })
+    }
+}
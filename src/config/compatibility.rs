@@ -0,0 +1,262 @@
+use serde::Serialize;
+use crate::config::configuration::Config;
+use crate::config::shared::ConfigError;
+use crate::config::specific::database_config::DatabaseType;
+use crate::config::specific::documentation_config::DocsUi;
+use crate::config::specific::entity_config::{ImmutableChanges, PermissionEffect};
+use crate::config::specific::server_config::{ErrorFormat, LogFormat, ResponseFormat, ServerFramework};
+
+/// Version of this Rawst build
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A configuration feature and the first Rawst version supporting it
+struct Feature {
+    /// Path of the feature in the configuration
+    path: &'static str,
+    /// First version supporting the feature
+    since: &'static str,
+    /// Whether the configuration uses the feature
+    used_by: fn(&Config) -> bool,
+}
+
+/// Features introduced after the first release. Add an entry when adding a configuration feature.
+const FEATURES: &[Feature] = &[
+    Feature { path: "server.record_replay", since: "0.2.0", used_by: |c| c.server.record_replay.is_some() },
+    Feature { path: "server.schema_snapshots", since: "0.2.0", used_by: |c| c.server.schema_snapshots.is_some() },
+    Feature { path: "server.scripts_dir", since: "0.2.0", used_by: |c| c.server.scripts_dir.is_some() },
+    Feature { path: "server.uploads_dir", since: "0.2.0", used_by: |c| c.server.uploads_dir.is_some() },
+    Feature {
+        path: "server.response_format",
+        since: "0.2.0",
+        used_by: |c| c.server.response_format != ResponseFormat::Bare,
+    },
+    Feature {
+        path: "server.error_format",
+        since: "0.2.0",
+        used_by: |c| c.server.error_format != ErrorFormat::Simple,
+    },
+    Feature {
+        path: "server.idempotency_ttl_seconds",
+        since: "0.2.0",
+        used_by: |c| c.server.idempotency_ttl_seconds.is_some(),
+    },
+    Feature {
+        path: "server.rate_limiting.per_ip",
+        since: "0.2.0",
+        used_by: |c| c.server.rate_limiting.as_ref().is_some_and(|r| r.per_ip),
+    },
+    Feature { path: "server.max_results", since: "0.2.0", used_by: |c| c.server.max_results.is_some() },
+    Feature {
+        path: "server.framework",
+        since: "0.2.0",
+        used_by: |c| c.server.framework != ServerFramework::Rocket,
+    },
+    Feature {
+        path: "server.log_format",
+        since: "0.2.0",
+        used_by: |c| c.server.log_format != LogFormat::Text,
+    },
+    Feature { path: "server.telemetry", since: "0.2.0", used_by: |c| c.server.telemetry.is_some() },
+    Feature { path: "server.graphql", since: "0.2.0", used_by: |c| c.server.graphql.is_some() },
+    Feature { path: "server.grpc", since: "0.2.0", used_by: |c| c.server.grpc.is_some() },
+    Feature { path: "server.static_dirs", since: "0.2.0", used_by: |c| !c.server.static_dirs.is_empty() },
+    Feature { path: "server.tls", since: "0.2.0", used_by: |c| c.server.tls.is_some() },
+    Feature { path: "server.tuning", since: "0.2.0", used_by: |c| c.server.tuning.is_some() },
+    Feature { path: "server.listeners", since: "0.2.0", used_by: |c| !c.server.listeners.is_empty() },
+    Feature { path: "server.socket_path", since: "0.2.0", used_by: |c| c.server.socket_path.is_some() },
+    Feature { path: "server.proxy", since: "0.2.0", used_by: |c| c.server.proxy.is_some() },
+    Feature { path: "server.admin_panel", since: "0.2.0", used_by: |c| c.server.admin_panel },
+    Feature { path: "server.maintenance", since: "0.2.0", used_by: |c| c.server.maintenance.is_some() },
+    Feature {
+        path: "server.listeners[].admin",
+        since: "0.2.0",
+        used_by: |c| c.server.listeners.iter().any(|l| l.admin),
+    },
+    Feature { path: "cors.exposed_headers", since: "0.2.0", used_by: |c| !c.cors.exposed_headers.is_empty() },
+    Feature { path: "documentation.ui", since: "0.2.0", used_by: |c| c.documentation.ui != DocsUi::SwaggerUi },
+    Feature { path: "documentation.path", since: "0.2.0", used_by: |c| c.documentation.path.is_some() },
+    Feature { path: "backup", since: "0.2.0", used_by: |c| c.backup.is_some() },
+    Feature { path: "health", since: "0.2.0", used_by: |c| c.health.is_some() },
+    Feature { path: "events", since: "0.2.0", used_by: |c| c.events.is_some() },
+    Feature { path: "tenancy", since: "0.2.0", used_by: |c| c.tenancy.is_some() },
+    Feature { path: "versions", since: "0.2.0", used_by: |c| !c.versions.is_empty() },
+    Feature { path: "include", since: "0.2.0", used_by: |c| !c.include.is_empty() },
+    Feature { path: "auth.session_config", since: "0.2.0", used_by: |c| c.auth.as_ref().is_some_and(|a| a.session_config.is_some()) },
+    Feature { path: "auth.ldap_config", since: "0.2.0", used_by: |c| c.auth.as_ref().is_some_and(|a| a.ldap_config.is_some()) },
+    Feature {
+        path: "auth.jwt_config.revocation_entity",
+        since: "0.2.0",
+        used_by: |c| c.auth.as_ref().and_then(|a| a.jwt_config.as_ref()).is_some_and(|j| j.revocation_entity.is_some()),
+    },
+    Feature {
+        path: "database.db_type (SurrealDB)",
+        since: "0.2.0",
+        used_by: |c| matches!(c.database.db_type, DatabaseType::SurrealDB),
+    },
+    Feature {
+        path: "entities_advanced[].encryption",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.encryption.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].cors",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.cors.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].rate_limiting",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.rate_limiting.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].request_timeout_seconds",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.request_timeout_seconds.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].schema",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.schema.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].endpoints.upsert_on_put",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.endpoints.upsert_on_put),
+    },
+    Feature {
+        path: "entities_advanced[].endpoints.custom_routes[].handler (script:)",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().flat_map(|e| &e.endpoints.custom_routes).any(|r| r.handler.trim().starts_with("script:")),
+    },
+    Feature {
+        path: "entities_advanced[].endpoints.immutable_changes",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.endpoints.immutable_changes != ImmutableChanges::Reject),
+    },
+    Feature {
+        path: "entities_advanced[].authorization.permissions[].roles/effect",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().flat_map(|e| &e.authorization.permissions)
+            .any(|p| !p.roles.is_empty() || p.effect != PermissionEffect::Allow),
+    },
+    Feature {
+        path: "entities_advanced[].hooks_script",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.hooks_script.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].id_strategy",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.id_strategy.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].soft_delete_field",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.soft_delete_field.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].owner_field",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.owner_field.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].cache",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.cache.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].http_cache",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.http_cache.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].webhooks",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| !e.webhooks.is_empty()),
+    },
+    Feature {
+        path: "entities_advanced[].version_field",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().any(|e| e.version_field.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].relationships[].join_table",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().flat_map(|e| &e.relationships).any(|r| r.join_table.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].fields[].generated/insertable/updatable",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().flat_map(|e| &e.fields).any(|f| f.generated || !f.insertable || !f.updatable),
+    },
+    Feature {
+        path: "entities_advanced[].fields[].read_only/computed",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().flat_map(|e| &e.fields).any(|f| f.read_only || f.computed.is_some()),
+    },
+    Feature {
+        path: "entities_advanced[].fields[].immutable",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().flat_map(|e| &e.fields).any(|f| f.immutable),
+    },
+    Feature {
+        path: "entities_advanced[].fields[].read_roles/write_roles",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().flat_map(|e| &e.fields).any(|f| !f.read_roles.is_empty() || !f.write_roles.is_empty()),
+    },
+    Feature {
+        path: "entities_advanced[].fields[].password",
+        since: "0.2.0",
+        used_by: |c| c.entities_advanced.iter().flat_map(|e| &e.fields).any(|f| f.password),
+    },
+];
+
+/// A feature used by the configuration that the target version does not support
+#[derive(Serialize, Debug, Clone)]
+pub struct CompatibilityIssue {
+    /// Path of the feature in the configuration
+    pub path: String,
+    /// First version supporting the feature
+    pub since: String,
+}
+
+impl std::fmt::Display for CompatibilityIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} requires Rawst {} or later", self.path, self.since)
+    }
+}
+
+/// Parses a `major.minor.patch` version, accepting a leading `v` and missing components
+fn parse_version(version: &str) -> Result<(u64, u64, u64), ConfigError> {
+    let invalid = || ConfigError::ValidationError(format!("Invalid version: {}", version));
+    let mut parts = version.trim().trim_start_matches('v')
+        .split(['-', '+']).next().unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()));
+
+    let major = parts.next().ok_or_else(invalid)??;
+    let minor = parts.next().transpose()?.unwrap_or(0);
+    let patch = parts.next().transpose()?.unwrap_or(0);
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok((major, minor, patch))
+}
+
+impl Config {
+    /// Reports the features used by this configuration that the given Rawst version does not support
+    pub fn check_compatibility(&self, version: &str) -> Result<Vec<CompatibilityIssue>, ConfigError> {
+        let target = parse_version(version)?;
+
+        let mut issues = Vec::new();
+        for feature in FEATURES {
+            if parse_version(feature.since)? > target && (feature.used_by)(self) {
+                issues.push(CompatibilityIssue {
+                    path: feature.path.to_string(),
+                    since: feature.since.to_string(),
+                });
+            }
+        }
+        Ok(issues)
+    }
+}
@@ -1,22 +1,106 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
 use std::fs;
 use std::path::Path;
 use crate::config::specific::{
     database_config::DatabaseConfig, server_config::ServerConfig, entity_config::Entity,
     auth_config::AuthConfig, cors_config::CorsConfig, documentation_config::DocumentationConfig,
-    api_config::ApiConfig,
+    api_config::ApiConfig, backup_config::BackupConfig, health_config::HealthConfig,
+    version_config::ApiVersionConfig, events_config::EventsConfig, tenancy_config::TenancyConfig,
 };
+use crate::config::migration::{current_config_version, migrate_value, CURRENT_CONFIG_VERSION};
 use crate::config::shared::{ConfigError, EntityBasic};
 
 pub trait Configuration {
+    /// The configuration as JSON, with the entities of the included files inline
     fn get_config(&self) -> Result<String, Box<dyn std::error::Error>>;
     fn set_config(&mut self, config: String) -> Result<(), Box<dyn std::error::Error>>;
+    /// Loads a file in the format of its extension
     fn load_from_file(&mut self, path: &str) -> Result<(), ConfigError>;
+    /// Loads a file in the given format, whatever its extension
+    fn load_from_file_as(&mut self, path: &str, format: ConfigFormat) -> Result<(), ConfigError>;
+    /// Saves to a file in the format of its extension, leaving out the entities of the included files
     fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>>;
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Formats of the configuration files
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConfigFormat {
+    #[default]
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Format of a file from its extension: `.yaml` and `.yml` are YAML, `.toml` is TOML and
+    /// anything else is JSON
+    pub fn from_path(path: &str) -> Self {
+        let extension = Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        Self::from_name(extension).unwrap_or_default()
+    }
+
+    /// Format of a name like `yaml`, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(ConfigFormat::Json),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            "toml" => Some(ConfigFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Reads a configuration written in this format, naming its file in the errors. Configurations
+    /// of older versions are upgraded to the current structure.
+    pub fn parse(self, data: &str, path: &str) -> Result<Config, ConfigError> {
+        let mut value = self.parse_value(data, path)?;
+        if migrate_value(&mut value, path)? == CURRENT_CONFIG_VERSION {
+            return self.deserialize(data, path);
+        }
+        // The upgraded configuration only exists as a document, without lines to point at
+        serde_path_to_error::deserialize(value).map_err(|e| ConfigError::ParseError(path.to_string(), e.to_string()))
+    }
+
+    /// Reads a configuration written in this format, as it is
+    fn deserialize(self, data: &str, path: &str) -> Result<Config, ConfigError> {
+        // Errors of nested values, like an unknown data type of a field, name the path of the value
+        let invalid = |at: serde_path_to_error::Path, e: String| {
+            ConfigError::ParseError(path.to_string(), format!("{}: {}", at, e))
+        };
+        match self {
+            ConfigFormat::Json => {
+                serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(data)).map_err(|e| {
+                    match e.path().iter().next() {
+                        Some(_) => invalid(e.path().clone(), e.into_inner().to_string()),
+                        None => ConfigError::DeserializeError(path.to_string(), e.into_inner()),
+                    }
+                })
+            }
+            ConfigFormat::Yaml => serde_path_to_error::deserialize(serde_yaml::Deserializer::from_str(data))
+                .map_err(|e| invalid(e.path().clone(), e.into_inner().to_string())),
+            ConfigFormat::Toml => serde_path_to_error::deserialize(toml::Deserializer::new(data))
+                .map_err(|e| invalid(e.path().clone(), e.into_inner().to_string())),
+        }
+    }
+
+    /// Writes a configuration in this format
+    pub fn serialize(self, config: &Config) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(config)?,
+            ConfigFormat::Yaml => serde_yaml::to_string(config)?,
+            ConfigFormat::Toml => toml::to_string_pretty(config)?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration of a Rawst API: its server, database, entities and the features serving them
 pub struct Config {
+    /// Version of the structure of the configuration. Files of older versions are upgraded when
+    /// loaded, see `config::migration`.
+    #[serde(default = "current_config_version")]
+    pub config_version: u32,
+
     // Basic configuration to set in the GUI
     pub api_version: String,
     pub api_prefix: Option<String>,
@@ -27,23 +111,51 @@ pub struct Config {
     // Advanced configuration
     #[serde(default)]
     pub entities_advanced: Vec<Entity>,
+    /// Files of entities added to `entities_advanced` when the configuration is loaded, like
+    /// `entities/*.json`, relative to the configuration file
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Names of the entities read from the `include` files, with their files
+    #[serde(skip)]
+    pub included: Vec<(String, String)>,
     pub auth: Option<AuthConfig>,
     pub cors: CorsConfig,
     pub documentation: DocumentationConfig,
+    #[serde(default)]
+    pub backup: Option<BackupConfig>,
+    #[serde(default)]
+    pub health: Option<HealthConfig>,
+    /// Versions of the API served next to the entities above, each under `/api/<name>`
+    #[serde(default)]
+    pub versions: Vec<ApiVersionConfig>,
+    /// Publishing of the changes of the entities to a message broker
+    #[serde(default)]
+    pub events: Option<EventsConfig>,
+    /// Scoping of the entities to the tenant of each request
+    #[serde(default)]
+    pub tenancy: Option<TenancyConfig>,
 }
 
 impl Config {
     pub fn new() -> Self {
         Config {
+            config_version: CURRENT_CONFIG_VERSION,
             api_version: "1.0".to_string(),
             api_prefix: Some("/api".to_string()),
             server: ServerConfig::default(),
             database: DatabaseConfig::default(),
             entities_basic: Vec::new(),
             entities_advanced: Vec::new(),
+            include: Vec::new(),
+            included: Vec::new(),
             auth: None,
             cors: CorsConfig::default(),
             documentation: DocumentationConfig::default(),
+            backup: None,
+            health: None,
+            versions: Vec::new(),
+            events: None,
+            tenancy: None,
         }
     }
 
@@ -62,39 +174,106 @@ impl Config {
 
     pub fn from_api_config(api_config: &ApiConfig) -> Self {
         Config {
+            config_version: CURRENT_CONFIG_VERSION,
             api_version: api_config.api_version.clone(),
             api_prefix: api_config.api_prefix.clone(),
             server: api_config.server_config.clone(),
             database: api_config.database_config.clone(),
             entities_basic: Vec::new(),
             entities_advanced: Vec::new(),
+            include: Vec::new(),
+            included: Vec::new(),
             auth: api_config.global_auth.clone(),
             cors: api_config.cors_config.clone(),
             documentation: api_config.documentation.clone(),
+            backup: None,
+            health: None,
+            versions: Vec::new(),
+            events: None,
+            tenancy: None,
         }
     }
 
-    fn set_config_string(&mut self, config: String) -> Result<(), ConfigError> {
-        let new_config: Config = serde_json::from_str(&config)
-            .map_err(|e| ConfigError::DeserializeError("config.json".to_string(), e))?;
+    /// Returns every entity definition: the advanced entities followed by the basic
+    /// entities that are not already defined as advanced ones.
+    pub fn all_entities(&self) -> Vec<Entity> {
+        let mut entities = self.entities_advanced.clone();
+        for entity_basic in &self.entities_basic {
+            let already_defined = entities.iter()
+                .any(|e| e.name.to_lowercase() == entity_basic.name.to_lowercase());
+            if !already_defined {
+                entities.push(entity_basic.to_entity());
+            }
+        }
+        entities
+    }
+
+    /// Configuration of a version of the API: this configuration serving the entities of the
+    /// version instead of its own
+    pub fn version_config(&self, version: &ApiVersionConfig) -> Config {
+        Config {
+            entities_basic: version.entities_basic.clone(),
+            entities_advanced: version.entities_advanced.clone(),
+            versions: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns every entity the API serves with the path it is served at, like `users` for the
+    /// entities of the configuration and `v2/users` for the entities of its versions
+    pub fn served_entities(&self) -> Vec<(String, Entity)> {
+        let mut entities: Vec<(String, Entity)> = self.all_entities().into_iter()
+            .map(|entity| (entity.name.clone(), entity))
+            .collect();
+        for version in &self.versions {
+            entities.extend(self.version_config(version).all_entities().into_iter()
+                .map(|entity| (format!("{}/{}", version.name, entity.name), entity)));
+        }
+        entities
+    }
+
+    /// Returns every entity the datasources store: the entities of the configuration followed by
+    /// the entities of its versions that are not already defined. Entities of a version named
+    /// like an entity of the configuration or of an earlier version share its storage.
+    pub fn stored_entities(&self) -> Vec<Entity> {
+        let mut entities = self.all_entities();
+        for version in &self.versions {
+            for entity in self.version_config(version).all_entities() {
+                if !entities.iter().any(|e| e.name.to_lowercase() == entity.name.to_lowercase()) {
+                    entities.push(entity);
+                }
+            }
+        }
+        entities
+    }
+
+    fn set_config_string(&mut self, config: &str, format: ConfigFormat, path: &str) -> Result<(), ConfigError> {
+        let mut new_config = format.parse(config, path)?;
+        new_config.load_includes(path)?;
         self.validate(&new_config)?;
         *self = new_config;
         Ok(())
     }
 
     fn validate(&self, config: &Config) -> Result<(), ConfigError> {
-        if config.server.port < 1024 || config.server.port > 65535 {
-            return Err(ConfigError::ValidationError(
-                "Server port must be between 1024 and 65535".to_string(),
-            ));
+        let issues = config.validation_issues();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::InvalidConfig(issues))
         }
-        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Configuration for Config {
     fn get_config(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let config = serde_json::to_string_pretty(self)?;
+        let config = serde_json::to_string_pretty(&self.flattened())?;
         Ok(config)
     }
 
@@ -105,6 +284,10 @@ impl Configuration for Config {
     }
 
     fn load_from_file(&mut self, path: &str) -> Result<(), ConfigError> {
+        self.load_from_file_as(path, ConfigFormat::from_path(path))
+    }
+
+    fn load_from_file_as(&mut self, path: &str, format: ConfigFormat) -> Result<(), ConfigError> {
         if !Path::new(path).exists() {
             return Err(ConfigError::FileNotFound(path.to_string()));
         }
@@ -112,18 +295,18 @@ impl Configuration for Config {
         let config_data = fs::read_to_string(path)
             .map_err(|e| ConfigError::FileReadError(path.to_string(), e))?;
 
-        self.set_config_string(config_data)
+        self.set_config_string(&config_data, format, path)
     }
 
     fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let config_json = self.get_config()?;
-        fs::write(path, config_json)?;
+        fs::write(path, ConfigFormat::from_path(path).serialize(&self.without_included())?)?;
         Ok(())
     }
 }
 
 /// Load configuration from a file and return a Config object.
-/// This function will read the configuration file, parse it, and return a Config object.
+/// This function will read the configuration file, parse it in the JSON, YAML or TOML format of
+/// its extension, and return a Config object.
 /// If the file does not exist or cannot be read, it will return a ConfigError.
 ///
 /// # Arguments
@@ -134,3 +317,10 @@ pub fn load_configuration(config_path: &str) -> Result<Config, ConfigError> {
     config.load_from_file(config_path)?;
     Ok(config)
 }
+
+/// Load configuration from a file written in the given format, whatever its extension
+pub fn load_configuration_as(config_path: &str, format: ConfigFormat) -> Result<Config, ConfigError> {
+    let mut config = Config::new();
+    config.load_from_file_as(config_path, format)?;
+    Ok(config)
+}
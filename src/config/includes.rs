@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::config::configuration::{Config, ConfigFormat};
+use crate::config::shared::ConfigError;
+use crate::config::specific::entity_config::Entity;
+use crate::config::validation::ValidationIssue;
+use tracing::warn;
+
+impl Config {
+    /// Adds the entities of the files matching the `include` patterns, relative to the directory
+    /// of the configuration file, to `entities_advanced`. Each file holds an entity or a list of
+    /// them, in the format of its extension. Entities defined twice, in the configuration or in
+    /// the files, are reported together.
+    pub fn load_includes(&mut self, config_path: &str) -> Result<(), ConfigError> {
+        let directory = Path::new(config_path).parent().unwrap_or_else(|| Path::new(""));
+        let mut issues = Vec::new();
+        let mut files: Vec<(usize, PathBuf)> = Vec::new();
+        for (index, pattern) in self.include.iter().enumerate() {
+            let full_pattern = directory.join(pattern).to_string_lossy().to_string();
+            let paths = match glob::glob(&full_pattern) {
+                Ok(paths) => paths,
+                Err(e) => {
+                    issues.push(ValidationIssue { path: format!("include[{}]", index), message: format!("Invalid pattern: {}", e) });
+                    continue;
+                }
+            };
+            let mut matched = false;
+            for path in paths.filter_map(|path| path.ok()).filter(|path| path.is_file()) {
+                matched = true;
+                // Files matched by several patterns are included once
+                if !files.iter().any(|(_, file)| file == &path) {
+                    files.push((index, path));
+                }
+            }
+            if !matched {
+                warn!("include[{}] {} matches no file", index, pattern);
+            }
+        }
+
+        for (index, path) in files {
+            let file = path.to_string_lossy().to_string();
+            for entity in read_entities(&file)? {
+                let defined_in = self.entities_advanced.iter().position(|other| other.name.eq_ignore_ascii_case(&entity.name))
+                    .map(|position| match self.included.iter().find(|(name, _)| name.eq_ignore_ascii_case(&entity.name)) {
+                        Some((_, other_file)) => other_file.clone(),
+                        None => format!("entities_advanced[{}]", position),
+                    });
+                if let Some(defined_in) = defined_in {
+                    issues.push(ValidationIssue {
+                        path: format!("include[{}]", index),
+                        message: format!("Entity {} of {} is already defined in {}", entity.name, file, defined_in),
+                    });
+                    continue;
+                }
+                self.included.push((entity.name.clone(), file.clone()));
+                self.entities_advanced.push(entity);
+            }
+        }
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::InvalidConfig(issues))
+        }
+    }
+
+    /// This configuration with the included entities inline, without its `include` patterns
+    pub fn flattened(&self) -> Config {
+        Config { include: Vec::new(), included: Vec::new(), ..self.clone() }
+    }
+
+    /// This configuration without the entities of its included files, as its own file holds it
+    pub fn without_included(&self) -> Config {
+        let entities_advanced = self.entities_advanced.iter()
+            .filter(|entity| !self.included.iter().any(|(name, _)| name == &entity.name))
+            .cloned()
+            .collect();
+        Config { entities_advanced, included: Vec::new(), ..self.clone() }
+    }
+}
+
+/// Entities of an included file: a single entity, or a list of them
+fn read_entities(path: &str) -> Result<Vec<Entity>, ConfigError> {
+    let data = fs::read_to_string(path).map_err(|e| ConfigError::FileReadError(path.to_string(), e))?;
+    let entities = match ConfigFormat::from_path(path).parse_value(&data, path)? {
+        Value::Array(entities) => entities,
+        entity => vec![entity],
+    };
+    entities.into_iter()
+        .map(|entity| serde_path_to_error::deserialize(entity).map_err(|e| ConfigError::ParseError(path.to_string(), e.to_string())))
+        .collect()
+}
@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use crate::config::configuration::{load_configuration, ConfigFormat, Configuration};
+use crate::config::shared::ConfigError;
+use tracing::info;
+
+/// Key of the version of the structure of a configuration file
+pub const CONFIG_VERSION_KEY: &str = "config_version";
+
+/// Version of the configuration structure this build reads and writes
+pub const CURRENT_CONFIG_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+/// An upgrade of a configuration document from a version to the next one
+struct Migration {
+    /// Version upgraded, to `from + 1`
+    from: u32,
+    /// What changed in the structure
+    description: &'static str,
+    /// Rewrites a document of version `from` into the structure of the next version
+    apply: fn(&mut Value),
+}
+
+/// Upgrades of the configuration structure, in order. Add an entry when renaming or moving a
+/// configuration value, so files written for older versions keep loading.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "permission subjects naming a role moved to the roles of the permission",
+        apply: move_role_subjects,
+    },
+];
+
+/// Default version of configurations built without going through the migrations, like the
+/// ones of the GUI
+pub fn current_config_version() -> u32 {
+    CURRENT_CONFIG_VERSION
+}
+
+/// Version of a configuration document. Files without one predate versioning and are version 1.
+pub fn config_version(config: &Value, path: &str) -> Result<u32, ConfigError> {
+    match config.get(CONFIG_VERSION_KEY) {
+        None | Some(Value::Null) => Ok(1),
+        Some(version) => version.as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| ConfigError::ParseError(
+                path.to_string(),
+                format!("{}: expected a version number, found {}", CONFIG_VERSION_KEY, version),
+            )),
+    }
+}
+
+/// Upgrades a configuration document to the current structure, returning the version it had.
+/// Configurations written for a newer Rawst are rejected rather than misread.
+pub fn migrate_value(config: &mut Value, path: &str) -> Result<u32, ConfigError> {
+    let version = config_version(config, path)?;
+    if version > CURRENT_CONFIG_VERSION {
+        return Err(ConfigError::ValidationError(format!(
+            "{} has {} {}, newer than the version {} this Rawst reads",
+            path, CONFIG_VERSION_KEY, version, CURRENT_CONFIG_VERSION
+        )));
+    }
+    if version == CURRENT_CONFIG_VERSION {
+        return Ok(version);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|migration| migration.from >= version) {
+        (migration.apply)(config);
+        info!("Upgraded {} from {} {} to {}: {}", path, CONFIG_VERSION_KEY, migration.from, migration.from + 1, migration.description);
+    }
+    if let Value::Object(config) = config {
+        config.insert(CONFIG_VERSION_KEY.to_string(), Value::from(CURRENT_CONFIG_VERSION));
+    }
+    Ok(version)
+}
+
+/// Writes a configuration file back in the current structure, in its own format, returning the
+/// version it had, or None when it was already current. The original file is kept next to it as
+/// `<file>.v<version>.bak`.
+pub fn migrate_file(path: &str) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+    if !Path::new(path).exists() {
+        return Err(Box::new(ConfigError::FileNotFound(path.to_string())));
+    }
+    let data = fs::read_to_string(path).map_err(|e| ConfigError::FileReadError(path.to_string(), e))?;
+    let version = config_version(&ConfigFormat::from_path(path).parse_value(&data, path)?, path)?;
+    if version == CURRENT_CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    // Loading upgrades and validates the configuration before anything is written
+    let config = load_configuration(path)?;
+    fs::copy(path, format!("{}.v{}.bak", path, version))?;
+    config.save_to_file(path)?;
+    Ok(Some(version))
+}
+
+/// Version 1 → 2: a permission subject that is neither `*` nor its entity named the role the
+/// permission applied to. Subjects name entities now, and roles have their own list.
+fn move_role_subjects(config: &mut Value) {
+    for entity in entities_mut(config) {
+        let name = entity.get("name").and_then(Value::as_str).unwrap_or_default().to_string();
+        let Some(permissions) = entity.pointer_mut("/authorization/permissions").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for permission in permissions.iter_mut().filter_map(Value::as_object_mut) {
+            let subject = permission.get("subject").and_then(Value::as_str).unwrap_or_default().trim().to_string();
+            if subject.is_empty() || subject == "*" || subject.eq_ignore_ascii_case(&name) {
+                continue;
+            }
+            permission.insert("subject".to_string(), Value::String(name.clone()));
+            if let Some(roles) = permission.entry("roles").or_insert_with(|| Value::Array(Vec::new())).as_array_mut() {
+                roles.push(Value::String(subject));
+            }
+        }
+    }
+}
+
+/// Advanced entities of a configuration document, the ones of its API versions included
+fn entities_mut(config: &mut Value) -> Vec<&mut Value> {
+    let Some(config) = config.as_object_mut() else {
+        return Vec::new();
+    };
+    let mut entities = Vec::new();
+    for (key, value) in config.iter_mut() {
+        match (key.as_str(), value) {
+            ("entities_advanced", Value::Array(own)) => entities.extend(own.iter_mut()),
+            ("versions", Value::Array(versions)) => entities.extend(
+                versions.iter_mut()
+                    .filter_map(|version| version.get_mut("entities_advanced"))
+                    .filter_map(Value::as_array_mut)
+                    .flat_map(|version_entities| version_entities.iter_mut()),
+            ),
+            _ => {}
+        }
+    }
+    entities
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::config::configuration::{load_configuration, Config, ConfigFormat};
+use crate::config::migration::migrate_value;
+use crate::config::shared::ConfigError;
+
+/// Environment variable selecting the profile overlaid on the configuration, like `dev` or `prod`
+pub const PROFILE_ENV_VAR: &str = "RAWST_PROFILE";
+
+/// Extensions an overlay may have, whatever the format of the base configuration
+const OVERLAY_EXTENSIONS: [&str; 4] = ["json", "yaml", "yml", "toml"];
+
+impl ConfigFormat {
+    /// Reads a document written in this format without checking it is a configuration
+    pub fn parse_value(self, data: &str, path: &str) -> Result<Value, ConfigError> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(data).map_err(|e| ConfigError::DeserializeError(path.to_string(), e)),
+            ConfigFormat::Yaml => serde_yaml::from_str(data).map_err(|e| ConfigError::ParseError(path.to_string(), e.to_string())),
+            ConfigFormat::Toml => toml::from_str(data).map_err(|e| ConfigError::ParseError(path.to_string(), e.to_string())),
+        }
+    }
+}
+
+/// Merges an overlay into a base document: objects are merged key by key, recursively, and any
+/// other overlay value, arrays included, replaces the base one
+pub fn merge_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Overlay of a profile for a configuration file: `<name>.<profile>.<ext>` next to it, like
+/// `config.prod.yaml` for `config.json`, or else `<profile>.<ext>`, like `prod.json`. Overlays
+/// may be written in any format.
+pub fn profile_path(config_path: &str, profile: &str) -> Option<PathBuf> {
+    let path = Path::new(config_path);
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let stem = path.file_stem()?.to_str()?;
+    let own_extension = path.extension().and_then(|extension| extension.to_str());
+    let extensions: Vec<&str> = own_extension.into_iter()
+        .chain(OVERLAY_EXTENSIONS.into_iter().filter(|extension| Some(*extension) != own_extension))
+        .collect();
+
+    // The names starting with the one of the configuration come first, so that configurations
+    // sharing a directory keep their own overlays
+    let prefixed = extensions.iter().map(|extension| format!("{}.{}.{}", stem, profile, extension));
+    let plain = extensions.iter().map(|extension| format!("{}.{}", profile, extension));
+    prefixed.chain(plain)
+        .map(|name| directory.join(name))
+        .find(|candidate| candidate.is_file() && candidate.as_path() != path)
+}
+
+/// Loads a configuration with the overlay of a profile merged over it, so environments only
+/// differ in the values their overlays set, like hosts, credentials or logging. Without a
+/// `profile`, the one of the `RAWST_PROFILE` environment variable is used, if any. The merged
+/// configuration is validated as a whole.
+pub fn load_configuration_with_profile(config_path: &str, profile: Option<&str>) -> Result<Config, ConfigError> {
+    let profile = match profile {
+        Some(profile) => Some(profile.to_string()),
+        None => std::env::var(PROFILE_ENV_VAR).ok(),
+    };
+    let Some(profile) = profile.map(|profile| profile.trim().to_string()).filter(|profile| !profile.is_empty()) else {
+        return load_configuration(config_path);
+    };
+    if !profile.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')) {
+        return Err(ConfigError::ValidationError(format!("Invalid profile name: {:?}", profile)));
+    }
+
+    let mut config = read_value(config_path)?;
+    let overlay_path = profile_path(config_path, &profile)
+        .ok_or_else(|| ConfigError::FileNotFound(format!("overlay of the {} profile for {}", profile, config_path)))?;
+    let overlay_path = overlay_path.to_string_lossy().to_string();
+    merge_values(&mut config, read_value(&overlay_path)?);
+    migrate_value(&mut config, config_path)?;
+
+    // Errors name the path of the value, like `database.port: invalid type`
+    let mut config: Config = serde_path_to_error::deserialize(config)
+        .map_err(|e| ConfigError::ParseError(format!("{} + {}", config_path, overlay_path), e.to_string()))?;
+    config.load_includes(config_path)?;
+    let issues = config.validation_issues();
+    if !issues.is_empty() {
+        return Err(ConfigError::InvalidConfig(issues));
+    }
+    Ok(config)
+}
+
+/// Reads a configuration file, or an overlay, in the format of its extension
+fn read_value(path: &str) -> Result<Value, ConfigError> {
+    if !Path::new(path).exists() {
+        return Err(ConfigError::FileNotFound(path.to_string()));
+    }
+    let data = fs::read_to_string(path).map_err(|e| ConfigError::FileReadError(path.to_string(), e))?;
+    ConfigFormat::from_path(path).parse_value(&data, path)
+}
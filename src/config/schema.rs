@@ -0,0 +1,21 @@
+use serde_json::Value;
+use crate::config::configuration::Config;
+use crate::config::migration::CURRENT_CONFIG_VERSION;
+
+/// Name of the JSON Schema of the configuration, served under the documentation path
+pub const CONFIG_SCHEMA_FILE: &str = "config.schema.json";
+
+impl Config {
+    /// JSON Schema of the configuration files, with the documentation of each value, for editors
+    /// to complete and check them. JSON files reference it with a `$schema` key, YAML files with a
+    /// `# yaml-language-server: $schema=<url>` comment.
+    pub fn json_schema() -> Value {
+        let mut schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default();
+        schema["title"] = Value::from("Rawst configuration");
+        // Editors add the key to the files they check against the schema
+        schema["properties"]["$schema"] = serde_json::json!({ "type": "string" });
+        schema["properties"]["config_version"]["minimum"] = Value::from(1);
+        schema["properties"]["config_version"]["maximum"] = Value::from(CURRENT_CONFIG_VERSION);
+        schema
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, OnceLock, RwLock};
+use serde_json::Value;
+use crate::config::shared::ConfigError;
+use crate::config::specific::database_config::DatabaseConfig;
+use crate::data::encryption::{is_encrypted, resolve_key, FileEncryptor};
+
+/// Prefix of the values read from a secrets backend instead of the configuration
+pub const SECRET_SCHEME: &str = "secret://";
+
+/// Environment variable holding the base64 key of the encrypted secret files
+pub const SECRETS_KEY_ENV_VAR: &str = "RAWST_SECRETS_KEY";
+
+/// A reference to a secret, like `secret://vault/db/password` or `secret://aws/prod/db#password`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretReference {
+    /// Name of the backend holding the secret, like `vault`
+    pub backend: String,
+    /// Path of the secret in the backend
+    pub path: String,
+    /// Key of the value in a secret holding several, given after `#`
+    pub key: Option<String>,
+}
+
+impl SecretReference {
+    /// Reference of a configuration value, None for plain values
+    pub fn parse(value: &str) -> Option<Result<Self, String>> {
+        let reference = value.strip_prefix(SECRET_SCHEME)?;
+        let (location, key) = match reference.split_once('#') {
+            Some((location, key)) => (location, Some(key.to_string())),
+            None => (reference, None),
+        };
+        Some(match location.split_once('/') {
+            Some((backend, path)) if !backend.is_empty() && !path.is_empty() && key.as_deref() != Some("") => {
+                Ok(Self { backend: backend.to_string(), path: path.to_string(), key })
+            }
+            _ => Err(format!("Invalid secret reference {}: expected {}<backend>/<path>[#<key>]", value, SECRET_SCHEME)),
+        })
+    }
+}
+
+/// A store of secrets, like Vault or AWS Secrets Manager
+pub trait SecretBackend: Send + Sync {
+    /// Value of the secret at a path, or of one of its keys
+    fn fetch(&self, path: &str, key: Option<&str>) -> Result<String, String>;
+}
+
+static BACKENDS: OnceLock<RwLock<HashMap<String, Arc<dyn SecretBackend>>>> = OnceLock::new();
+
+fn backends() -> &'static RwLock<HashMap<String, Arc<dyn SecretBackend>>> {
+    BACKENDS.get_or_init(|| {
+        let mut backends: HashMap<String, Arc<dyn SecretBackend>> = HashMap::new();
+        backends.insert("env".to_string(), Arc::new(EnvironmentSecrets));
+        backends.insert("file".to_string(), Arc::new(FileSecrets));
+        #[cfg(feature = "native")]
+        {
+            backends.insert("vault".to_string(), Arc::new(remote::VaultSecrets));
+            backends.insert("aws".to_string(), Arc::new(remote::AwsSecrets));
+        }
+        RwLock::new(backends)
+    })
+}
+
+/// Serves the references to a backend name with another backend, replacing any built-in one
+pub fn register_secret_backend(name: &str, backend: impl SecretBackend + 'static) {
+    backends().write().unwrap_or_else(|e| e.into_inner()).insert(name.to_string(), Arc::new(backend));
+}
+
+/// Value of a configuration value: the secret it references, or the value itself when it is
+/// not a `secret://` reference. Secrets are read on every call, when connections are made.
+pub fn resolve_secret(value: &str) -> Result<String, ConfigError> {
+    let Some(reference) = SecretReference::parse(value) else {
+        return Ok(value.to_string());
+    };
+    let reference = reference.map_err(|e| ConfigError::SecretError(value.to_string(), e))?;
+    let backend = backends().read().unwrap_or_else(|e| e.into_inner()).get(&reference.backend).cloned()
+        .ok_or_else(|| ConfigError::SecretError(value.to_string(), format!("unknown secrets backend {}", reference.backend)))?;
+    backend.fetch(&reference.path, reference.key.as_deref())
+        .map_err(|e| ConfigError::SecretError(value.to_string(), e))
+}
+
+impl DatabaseConfig {
+    /// This configuration with the secrets of its credentials read from their backends
+    pub fn with_secrets(&self) -> Result<DatabaseConfig, ConfigError> {
+        let mut config = self.clone();
+        config.username = resolve_secret(&self.username)?;
+        config.password = resolve_secret(&self.password)?;
+        config.connection_string = resolve_secret(&self.connection_string)?;
+        Ok(config)
+    }
+}
+
+/// Value of a key of a secret holding a JSON object, or the whole secret without a key
+fn select_key(secret: &str, key: Option<&str>) -> Result<String, String> {
+    let Some(key) = key else {
+        return Ok(secret.trim().to_string());
+    };
+    let object: Value = serde_json::from_str(secret).map_err(|_| format!("the secret is not a JSON object with the key {}", key))?;
+    match object.get(key) {
+        Some(Value::String(value)) => Ok(value.clone()),
+        Some(value) => Ok(value.to_string()),
+        None => Err(format!("the secret has no key {}", key)),
+    }
+}
+
+/// Secrets of environment variables: `secret://env/DB_PASSWORD`
+struct EnvironmentSecrets;
+
+impl SecretBackend for EnvironmentSecrets {
+    fn fetch(&self, path: &str, key: Option<&str>) -> Result<String, String> {
+        let value = std::env::var(path).map_err(|_| format!("environment variable {} is not set", path))?;
+        select_key(&value, key)
+    }
+}
+
+/// Secrets of local files: `secret://file/secrets.json#db_password`, or
+/// `secret://file//etc/rawst/secrets.json#db_password` for absolute paths. Files encrypted like
+/// the exports are decrypted with the key of `RAWST_SECRETS_KEY`.
+struct FileSecrets;
+
+impl SecretBackend for FileSecrets {
+    fn fetch(&self, path: &str, key: Option<&str>) -> Result<String, String> {
+        let data = fs::read(path).map_err(|e| format!("error reading {}: {}", path, e))?;
+        let data = if is_encrypted(&data) {
+            let key = resolve_key(&format!("env:{}", SECRETS_KEY_ENV_VAR)).map_err(|e| e.to_string())?;
+            FileEncryptor::new(&key).and_then(|encryptor| encryptor.decrypt(&data)).map_err(|e| e.to_string())?
+        } else {
+            data
+        };
+        let text = String::from_utf8(data).map_err(|_| format!("{} is not UTF-8 text", path))?;
+        select_key(&text, key)
+    }
+}
+
+/// Backends reached over HTTP
+#[cfg(feature = "native")]
+mod remote {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+    use serde_json::{json, Value};
+    use super::{select_key, SecretBackend};
+    use crate::api::handlers::common::dates::utc_timestamp;
+
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+    const DEFAULT_VAULT_MOUNT: &str = "secret";
+
+    fn env(name: &str) -> Result<String, String> {
+        std::env::var(name).ok().filter(|value| !value.is_empty()).ok_or_else(|| format!("{} is not set", name))
+    }
+
+    /// Sends a request and returns its JSON body. Secrets are resolved by synchronous code that may
+    /// run inside the server runtime, so the request runs on a runtime of its own thread.
+    fn send(request: impl FnOnce(&reqwest::Client) -> reqwest::RequestBuilder + Send) -> Result<Value, String> {
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| e.to_string())?;
+                runtime.block_on(async {
+                    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().map_err(|e| e.to_string())?;
+                    let response = request(&client).send().await.map_err(|e| e.to_string())?;
+                    let status = response.status();
+                    if !status.is_success() {
+                        return Err(format!("the backend answered {}", status));
+                    }
+                    response.json::<Value>().await.map_err(|e| e.to_string())
+                })
+            }).join().unwrap_or_else(|_| Err("the request failed".to_string()))
+        })
+    }
+
+    /// Secrets of the KV version 2 engine of HashiCorp Vault, at `VAULT_ADDR` with `VAULT_TOKEN`.
+    /// `secret://vault/db/password` reads the key `password` of the secret `db`, like
+    /// `secret://vault/db#password`, in the mount of `VAULT_KV_MOUNT`, `secret` by default.
+    pub struct VaultSecrets;
+
+    impl SecretBackend for VaultSecrets {
+        fn fetch(&self, path: &str, key: Option<&str>) -> Result<String, String> {
+            let (path, key) = match key {
+                Some(key) => (path, key),
+                None => path.rsplit_once('/').ok_or_else(|| format!("name the key of the Vault secret {}", path))?,
+            };
+            let address = env("VAULT_ADDR")?;
+            let token = env("VAULT_TOKEN")?;
+            let mount = std::env::var("VAULT_KV_MOUNT").unwrap_or_else(|_| DEFAULT_VAULT_MOUNT.to_string());
+            let namespace = std::env::var("VAULT_NAMESPACE").ok();
+            let url = format!("{}/v1/{}/data/{}", address.trim_end_matches('/'), mount.trim_matches('/'), path);
+
+            let body = send(|client| {
+                let request = client.get(&url).header("X-Vault-Token", &token);
+                match &namespace {
+                    Some(namespace) => request.header("X-Vault-Namespace", namespace),
+                    None => request,
+                }
+            })?;
+            let data = body.pointer("/data/data").ok_or_else(|| format!("Vault has no data for {}", path))?;
+            select_key(&data.to_string(), Some(key))
+        }
+    }
+
+    /// Secrets of AWS Secrets Manager, signed with the credentials of `AWS_ACCESS_KEY_ID`,
+    /// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` for the region of `AWS_REGION`:
+    /// `secret://aws/prod/db#password` reads the key `password` of the secret `prod/db`.
+    pub struct AwsSecrets;
+
+    impl SecretBackend for AwsSecrets {
+        fn fetch(&self, path: &str, key: Option<&str>) -> Result<String, String> {
+            let region = env("AWS_REGION").or_else(|_| env("AWS_DEFAULT_REGION"))?;
+            let access_key = env("AWS_ACCESS_KEY_ID")?;
+            let secret_key = env("AWS_SECRET_ACCESS_KEY")?;
+            let session_token = std::env::var("AWS_SESSION_TOKEN").ok().filter(|token| !token.is_empty());
+
+            let host = format!("secretsmanager.{}.amazonaws.com", region);
+            let body = json!({ "SecretId": path }).to_string();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let amz_date = amz_date(now);
+            let mut headers = vec![
+                ("content-type", "application/x-amz-json-1.1".to_string()),
+                ("host", host.clone()),
+                ("x-amz-date", amz_date.clone()),
+                ("x-amz-target", "secretsmanager.GetSecretValue".to_string()),
+            ];
+            if let Some(token) = session_token {
+                headers.push(("x-amz-security-token", token));
+            }
+            headers.sort();
+            let authorization = sign(&headers, &body, &amz_date, &region, &access_key, &secret_key);
+
+            let response = send(|client| {
+                let request = client.post(format!("https://{}/", host)).header("authorization", &authorization).body(body.clone());
+                headers.iter().filter(|(name, _)| *name != "host").fold(request, |request, (name, value)| request.header(*name, value))
+            })?;
+            let secret = response.get("SecretString").and_then(Value::as_str)
+                .ok_or_else(|| format!("the secret {} has no string value", path))?;
+            select_key(secret, key)
+        }
+    }
+
+    /// Time of a request in the basic ISO 8601 format of Signature Version 4, like `20240102T030405Z`
+    fn amz_date(seconds: u64) -> String {
+        utc_timestamp(seconds).replace(['-', ':'], "")
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(data.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// Authorization header of a request to Secrets Manager, following Signature Version 4.
+    /// The headers must be sorted by name.
+    fn sign(headers: &[(&str, String)], body: &str, amz_date: &str, region: &str, access_key: &str, secret_key: &str) -> String {
+        let date = &amz_date[..8];
+        let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "POST\n/\n\n{}\n{}\n{}", canonical_headers, signed_headers, hex(&Sha256::digest(body.as_bytes()))
+        );
+        let scope = format!("{}/{}/secretsmanager/aws4_request", date, region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(secret_key, date, region, "secretsmanager");
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            access_key, scope, signed_headers, hex(&hmac(&key, &string_to_sign))
+        )
+    }
+
+    /// Key signing the requests to a service of a region on a day, derived from the secret key
+    fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+        let date_key = hmac(format!("AWS4{}", secret_key).as_bytes(), date);
+        hmac(&hmac(&hmac(&date_key, region), service), "aws4_request")
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const SECRET_KEY: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+
+        #[test]
+        fn derives_the_signing_key_of_the_aws_example() {
+            // Example of the Signature Version 4 documentation
+            let key = signing_key(SECRET_KEY, "20120215", "us-east-1", "iam");
+            assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+        }
+
+        #[test]
+        fn signs_get_secret_value_requests() {
+            let headers = vec![
+                ("content-type", "application/x-amz-json-1.1".to_string()),
+                ("host", "secretsmanager.eu-west-1.amazonaws.com".to_string()),
+                ("x-amz-date", "20240102T030405Z".to_string()),
+                ("x-amz-target", "secretsmanager.GetSecretValue".to_string()),
+            ];
+            let authorization = sign(&headers, r#"{"SecretId":"prod/db"}"#, "20240102T030405Z", "eu-west-1", "AKIDEXAMPLE", SECRET_KEY);
+            assert_eq!(authorization, "AWS4-HMAC-SHA256 \
+                Credential=AKIDEXAMPLE/20240102/eu-west-1/secretsmanager/aws4_request, \
+                SignedHeaders=content-type;host;x-amz-date;x-amz-target, \
+                Signature=ff655b0b80735e1cf6c27efa7f431327e3502529d3d8571ed28d6abb5782541b");
+        }
+
+        #[test]
+        fn dates_requests_in_the_basic_format() {
+            assert_eq!(amz_date(1_704_164_645), "20240102T030405Z");
+        }
+    }
+}
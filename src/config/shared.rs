@@ -1,4 +1,6 @@
+use crate::config::validation::ValidationIssue;
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use std::fmt;
 
 #[derive(Debug)]
@@ -6,7 +8,13 @@ pub enum ConfigError {
     FileNotFound(String),
     FileReadError(String, std::io::Error),
     DeserializeError(String, serde_json::Error),
+    /// A YAML or TOML file could not be parsed
+    ParseError(String, String),
     ValidationError(String),
+    /// Every problem found validating a configuration
+    InvalidConfig(Vec<ValidationIssue>),
+    /// A `secret://` reference, and why it could not be resolved
+    SecretError(String, String),
 }
 
 impl fmt::Display for ConfigError {
@@ -15,14 +23,20 @@ impl fmt::Display for ConfigError {
             ConfigError::FileNotFound(path) => write!(f, "Configuration file not found: {}", path),
             ConfigError::FileReadError(path, err) => write!(f, "Error reading file {}: {}", path, err),
             ConfigError::DeserializeError(path, err) => write!(f, "Error deserializing file {}: {}", path, err),
+            ConfigError::ParseError(path, err) => write!(f, "Error parsing file {}: {}", path, err),
             ConfigError::ValidationError(message) => write!(f, "Validation error: {}", message),
+            ConfigError::SecretError(reference, err) => write!(f, "Error resolving secret {}: {}", reference, err),
+            ConfigError::InvalidConfig(issues) => {
+                write!(f, "Invalid configuration:")?;
+                issues.iter().try_for_each(|issue| write!(f, "\n  {}", issue))
+            }
         }
     }
 }
 
 impl std::error::Error for ConfigError {}
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct EntityBasic {
     pub name: String,
     pub table_name: Option<String>,
@@ -30,9 +44,75 @@ pub struct EntityBasic {
     pub authentication: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct FieldBasic {
     pub name: String,
     pub data_type: crate::config::specific::entity_config::DataType,
     pub required: bool,
 }
+
+impl EntityBasic {
+    /// Converts the basic entity set in the GUI into a full entity definition with default settings
+    pub fn to_entity(&self) -> crate::config::specific::entity_config::Entity {
+        use crate::config::specific::entity_config::{Authorization, EndpointConfig, Entity, Field, ImmutableChanges};
+
+        Entity {
+            name: self.name.clone(),
+            table_name: self.table_name.clone(),
+            schema: None,
+            fields: self.fields.iter().map(|f| {
+                Field {
+                    name: f.name.clone(),
+                    column_name: Some(f.name.clone()),
+                    data_type: f.data_type.clone(),
+                    required: f.required,
+                    unique: false,
+                    searchable: true,
+                    default_value: None,
+                    description: None,
+                    generated: false,
+                    insertable: true,
+                    updatable: true,
+                    read_only: false,
+                    computed: None,
+                    immutable: false,
+                    read_roles: Vec::new(),
+                    write_roles: Vec::new(),
+                    password: false,
+                }
+            }).collect(),
+            relationships: Vec::new(),
+            endpoints: EndpointConfig {
+                generate_create: true,
+                generate_read: true,
+                generate_update: true,
+                generate_delete: true,
+                generate_list: true,
+                custom_routes: Vec::new(),
+                upsert_on_put: false,
+                immutable_changes: ImmutableChanges::Reject,
+            },
+            authentication: self.authentication,
+            authorization: Authorization {
+                active: false,
+                roles: Vec::new(),
+                permissions: Vec::new(),
+            },
+            validations: Vec::new(),
+            pagination: None,
+            encryption: None,
+            cors: None,
+            rate_limiting: None,
+            request_timeout_seconds: None,
+            version_field: None,
+            hooks_script: None,
+            id_strategy: None,
+            soft_delete_field: None,
+            owner_field: None,
+            owner_bypass_roles: Vec::new(),
+            cache: None,
+            http_cache: None,
+            webhooks: Vec::new(),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
 use crate::config::specific::auth_config::AuthConfig;
 use crate::config::specific::cors_config::CorsConfig;
 use crate::config::specific::documentation_config::DocumentationConfig;
@@ -7,7 +8,7 @@ use crate::config::specific::server_config::ServerConfig;
 use crate::config::specific::entity_config::Entity;
 
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for the entire API, including database, server, entities, authentication, and more.
 pub struct ApiConfig {
     /// Database configuration settings.
@@ -1,6 +1,11 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use std::collections::HashMap;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Session cookie when `cookie_name` is not set
+const DEFAULT_COOKIE_NAME: &str = "rawst_session";
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for authentication.
 pub struct AuthConfig {
     /// Type of authentication to use.
@@ -11,41 +16,198 @@ pub struct AuthConfig {
     pub oauth_config: Option<OAuthConfig>,
     /// Configuration for API key authentication (optional).
     pub api_key_config: Option<ApiKeyConfig>,
+    /// Configuration for cookie session authentication (optional).
+    #[serde(default)]
+    pub session_config: Option<SessionConfig>,
+    /// Configuration for LDAP / Active Directory sign ins (optional).
+    #[serde(default)]
+    pub ldap_config: Option<LdapConfig>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub enum AuthType {
     JWT,
     OAuth,
+    /// Not implemented by the server yet, configurations using it are refused
     ApiKey,
+    /// Not implemented by the server yet, configurations using it are refused
     Basic,
+    /// Sessions held in an encrypted cookie, for browsers that cannot easily send bearer tokens
+    Session,
     None,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+/// Users entity whose users sign in with the credentials it stores.
+pub struct UsersConfig {
+    /// Entity holding the users (optional, `users` by default).
+    #[serde(default)]
+    pub users_entity: Option<String>,
+    /// Field of the users entity holding the user name (optional, `username` by default).
+    #[serde(default)]
+    pub username_field: Option<String>,
+    /// Field of the users entity holding the password (optional, `password` by default).
+    #[serde(default)]
+    pub password_field: Option<String>,
+    /// Field of the users entity holding the roles of the user, as a list or a comma separated
+    /// string (optional, users have no roles without it).
+    #[serde(default)]
+    pub roles_field: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for JWT authentication. Users sign in at `/api/auth/login` with the credentials
+/// stored in the users entity, for tokens signed with the secret, and sign out at `/api/auth/logout`,
+/// which revokes their tokens. Any token is revoked at `/api/auth/revoke`.
 pub struct JWTConfig {
     pub secret: String,
     pub expiration_hours: u32,
     pub issuer: Option<String>,
+    /// Whether sign ins also get a refresh token, exchanged at `/api/auth/refresh` for new tokens.
+    /// Each refresh token is used once.
     pub refresh_token_enabled: bool,
+    /// Lifetime of the refresh tokens (optional, a week by default).
+    #[serde(default)]
+    pub refresh_expiration_hours: Option<u32>,
+    /// Entity storing the tokens revoked before they expire, so every instance shares them and
+    /// they survive restarts (optional, kept in memory by default). Its key holds the token id and
+    /// its `expires_at` field the expiry of the token, in seconds since the epoch. Its authorization
+    /// must be active, so its endpoints do not let any caller read or delete the revoked tokens.
+    #[serde(default)]
+    pub revocation_entity: Option<String>,
+    /// Users signing in, with their fields.
+    #[serde(flatten)]
+    pub users: UsersConfig,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for cookie session authentication. Users sign in at `/api/auth/login` with the
+/// credentials stored in the users entity, and are given a session cookie encrypted with the
+/// secret, which the browser sends with the following requests until `/api/auth/logout`.
+pub struct SessionConfig {
+    pub secret: String,
+    /// Lifetime of the sessions (optional, 24 hours by default).
+    #[serde(default)]
+    pub ttl_minutes: Option<u32>,
+    /// Name of the session cookie (optional, `rawst_session` by default).
+    #[serde(default)]
+    pub cookie_name: Option<String>,
+    /// Sites the browser sends the session cookie from (optional, `Lax` by default).
+    #[serde(default)]
+    pub same_site: SameSite,
+    /// Whether the session cookie is only sent over HTTPS (optional, true by default).
+    #[serde(default)]
+    pub secure: Option<bool>,
+    /// Path the session cookie is sent to (optional, `/` by default).
+    #[serde(default)]
+    pub cookie_path: Option<String>,
+    /// Users signing in, with their fields.
+    #[serde(flatten)]
+    pub users: UsersConfig,
+}
+
+impl SessionConfig {
+    /// Name of the session cookie
+    pub fn cookie_name(&self) -> &str {
+        self.cookie_name.as_deref().unwrap_or(DEFAULT_COOKIE_NAME)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq)]
+/// `SameSite` policy of the session cookie.
+pub enum SameSite {
+    /// Only sent by the site itself
+    Strict,
+    /// Also sent when following links from other sites
+    #[default]
+    Lax,
+    /// Sent by every site, which requires the cookie to be secure
+    None,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for LDAP / Active Directory sign ins. With it, the JWT or session authentication
+/// verifies the credentials sent to `/api/auth/login` by binding to the directory as the user,
+/// instead of reading the users entity, and gives the user the roles of their groups.
+pub struct LdapConfig {
+    /// URL of the directory, like `ldap://ldap.example.com` or `ldaps://dc.corp.example.com`.
+    pub url: String,
+    /// Name the users bind with, where `{username}` is replaced by their user name, like
+    /// `uid={username},ou=people,dc=example,dc=com` or `{username}@corp.example.com` for Active Directory.
+    pub bind_dn: String,
+    /// DN the entry of the user is searched under (optional). Without it, the entry is read at
+    /// `bind_dn`, which must then be a DN.
+    #[serde(default)]
+    pub search_base: Option<String>,
+    /// Equality filter finding the entry of the user under `search_base`, like
+    /// `(sAMAccountName={username})` (optional, `(uid={username})` by default).
+    #[serde(default)]
+    pub user_filter: Option<String>,
+    /// Attribute of the entry of the user listing their groups (optional, `memberOf` by default).
+    #[serde(default)]
+    pub group_attribute: Option<String>,
+    /// Roles of the API given to the groups, by their name or DN, like `"rawst-admins": "admin"`.
+    /// Groups not listed are kept as roles named like them.
+    #[serde(default)]
+    pub group_mapping: HashMap<String, String>,
+    /// Account reading the groups of the users refreshing their tokens, which sign ins do as the
+    /// user (optional, refresh tokens are not served without it).
+    #[serde(default)]
+    pub service_dn: Option<String>,
+    /// Password of `service_dn` (optional).
+    #[serde(default)]
+    pub service_password: Option<String>,
+    /// PEM file of the certificate authorities trusted for `ldaps` (optional, the ones of the system by default).
+    #[serde(default)]
+    pub ca_file: Option<String>,
+    /// Seconds the directory has to answer (optional, 10 by default).
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for OAuth2 / OpenID Connect authentication. Bearer tokens are validated against
+/// the signing keys of the providers, and users sign in with the authorization-code flow from
+/// `/auth/oauth/<provider>`.
 pub struct OAuthConfig {
     pub providers: Vec<OAuthProvider>,
+    /// Absolute URL the providers redirect to with the authorization code, like
+    /// `https://api.example.com/auth/oauth/callback`. Its path is served by the API.
     pub callback_url: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// An OAuth2 / OpenID Connect provider.
 pub struct OAuthProvider {
     pub name: String,
     pub client_id: String,
     pub client_secret: String,
     pub auth_url: String,
     pub token_url: String,
+    /// Issuer of the tokens, matched against their `iss` claim (optional). The signing keys are
+    /// discovered from its `/.well-known/openid-configuration` when `jwks_url` is not set.
+    #[serde(default)]
+    pub issuer: Option<String>,
+    /// URL of the JSON Web Key Set signing the tokens (optional when `issuer` is set).
+    #[serde(default)]
+    pub jwks_url: Option<String>,
+    /// Audience the tokens must be issued for, like the client id (optional, not checked by default).
+    #[serde(default)]
+    pub audience: Option<String>,
+    /// Scopes asked for when signing in (optional, `openid` by default).
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Claim holding the roles of the user, with dots for nested claims like `realm_access.roles`
+    /// (optional, `roles` by default). Lists and space separated strings are both read.
+    #[serde(default)]
+    pub roles_claim: Option<String>,
+    /// Roles of the API given to the values of the roles claim, like `"rawst-admins": "admin"`.
+    /// Values not listed are kept as they are.
+    #[serde(default)]
+    pub role_mapping: HashMap<String, String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct ApiKeyConfig {
     pub header_name: String,
     pub prefix: Option<String>,
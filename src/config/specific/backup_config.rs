@@ -0,0 +1,39 @@
+use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use crate::config::specific::encryption_config::EncryptionConfig;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for scheduled backups of file datasources and the active configuration.
+pub struct BackupConfig {
+    /// Directory where backups are stored.
+    pub directory: String,
+    /// Interval between automatic backups in minutes.
+    pub interval_minutes: u32,
+    /// Data files included in every backup, such as file datasource files.
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Retention policy for old backups.
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    /// Encryption of the backed up files (optional).
+    #[serde(default)]
+    pub encryption: Option<EncryptionConfig>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Retention policy for backups; backups exceeding any limit are deleted.
+pub struct RetentionPolicy {
+    /// Maximum number of backups to keep.
+    pub max_backups: Option<u32>,
+    /// Maximum age of a backup in days (optional).
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            max_backups: Some(10),
+            max_age_days: None,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
 /// Configuration for Cross-Origin Resource Sharing (CORS).
 pub struct CorsConfig {
     /// List of allowed origins.
@@ -13,4 +14,56 @@ pub struct CorsConfig {
     pub allow_credentials: bool,
     /// Maximum age for CORS preflight requests in seconds (optional).
     pub max_age_seconds: Option<u32>,
+    /// Response headers browsers let scripts read besides the safelisted ones, like `ETag`.
+    #[serde(default)]
+    pub exposed_headers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+/// Per-entity CORS overrides. Every field that is set replaces the global value.
+pub struct EntityCorsConfig {
+    /// List of allowed origins (optional).
+    pub allowed_origins: Option<Vec<String>>,
+    /// List of allowed HTTP methods (optional).
+    pub allowed_methods: Option<Vec<String>>,
+    /// List of allowed HTTP headers (optional).
+    pub allowed_headers: Option<Vec<String>>,
+    /// Whether credentials are allowed in CORS requests (optional).
+    pub allow_credentials: Option<bool>,
+    /// Maximum age for CORS preflight requests in seconds (optional).
+    pub max_age_seconds: Option<u32>,
+}
+
+impl CorsConfig {
+    /// Returns this configuration with the overrides of an entity applied
+    pub fn merged_with(&self, overrides: &EntityCorsConfig) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: overrides.allowed_origins.clone().unwrap_or_else(|| self.allowed_origins.clone()),
+            allowed_methods: overrides.allowed_methods.clone().unwrap_or_else(|| self.allowed_methods.clone()),
+            allowed_headers: overrides.allowed_headers.clone().unwrap_or_else(|| self.allowed_headers.clone()),
+            allow_credentials: overrides.allow_credentials.unwrap_or(self.allow_credentials),
+            max_age_seconds: overrides.max_age_seconds.or(self.max_age_seconds),
+            exposed_headers: self.exposed_headers.clone(),
+        }
+    }
+
+    /// Whether requests from the origin are allowed
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+
+    /// Whether the HTTP method is allowed. An empty list allows every method.
+    pub fn allows_method(&self, method: &str) -> bool {
+        self.allowed_methods.is_empty()
+            || self.allowed_methods.iter().any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(method))
+    }
+
+    /// Whether every header of a comma separated list, as sent in `Access-Control-Request-Headers`,
+    /// is allowed
+    pub fn allows_headers(&self, requested: &str) -> bool {
+        self.allowed_headers.iter().any(|allowed| allowed == "*")
+            || requested.split(',').map(str::trim).filter(|header| !header.is_empty()).all(|header| {
+                self.allowed_headers.iter().any(|allowed| allowed.eq_ignore_ascii_case(header))
+            })
+    }
 }
@@ -1,11 +1,13 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
 use std::{clone, fmt};
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
 /// Configuration for the database connection.
 pub struct DatabaseConfig {
     /// Type of the database (e.g., PostgreSQL, MySQL).
     #[serde(deserialize_with = "deserialize_db_type")]
+    #[schemars(schema_with = "db_type_schema")]
     pub db_type: DatabaseType,
     /// Hostname or IP address of the database server.
     pub host: String,
@@ -27,10 +29,11 @@ pub struct DatabaseConfig {
     pub ssl_enabled: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
 /// Supported database types.
 pub enum DatabaseType {
     /// PostgreSQL database.
+    #[default]
     #[serde(rename = "PostgreSQL")]
     PostgreSQL,
     /// MySQL database.
@@ -42,12 +45,9 @@ pub enum DatabaseType {
     /// MongoDB database.
     #[serde(rename = "MongoDB")]
     MongoDB,
-}
-
-impl Default for DatabaseType {
-    fn default() -> Self {
-        DatabaseType::PostgreSQL
-    }
+    /// SurrealDB database, reached through its HTTP API.
+    #[serde(rename = "SurrealDB")]
+    SurrealDB,
 }
 
 impl fmt::Display for DatabaseType {
@@ -57,6 +57,7 @@ impl fmt::Display for DatabaseType {
             DatabaseType::MySQL => write!(f, "MySQL"),
             DatabaseType::SQLite => write!(f, "SQLite"),
             DatabaseType::MongoDB => write!(f, "MongoDB"),
+            DatabaseType::SurrealDB => write!(f, "SurrealDB"),
         }
     }
 }
@@ -68,6 +69,7 @@ impl DatabaseType {
             DatabaseType::MySQL => 3306,
             DatabaseType::SQLite => 0, // SQLite does not use a port
             DatabaseType::MongoDB => 27017,
+            DatabaseType::SurrealDB => 8000,
         }
     }
 }
@@ -105,10 +107,21 @@ impl DatabaseConfig {
                 "mongodb://{}:{}@{}:{}/{}",
                 self.username, self.password, self.host, self.port.unwrap_or(27017), self.database_name
             ),
+            DatabaseType::SurrealDB => format!(
+                "{}://{}:{}",
+                if self.ssl_enabled { "https" } else { "http" }, self.host, self.port.unwrap_or(8000)
+            ),
         }
     }
 }
 
+/// Schema of the names `deserialize_db_type` accepts, MariaDB included
+fn db_type_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    schemars::json_schema!({
+        "enum": ["PostgreSQL", "MySQL", "MariaDB", "SQLite", "MongoDB", "SurrealDB"]
+    })
+}
+
 fn deserialize_db_type<'de, D>(deserializer: D) -> Result<DatabaseType, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -120,6 +133,7 @@ where
         "MariaDB" => Ok(DatabaseType::MySQL), 
         "SQLite" => Ok(DatabaseType::SQLite),
         "MongoDB" => Ok(DatabaseType::MongoDB),
+        "SurrealDB" => Ok(DatabaseType::SurrealDB),
         _ => Err(serde::de::Error::custom(format!("Invalid database type: {}", s))),
     }
 }
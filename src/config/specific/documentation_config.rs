@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
 /// Configuration for API documentation.
 pub struct DocumentationConfig {
     /// Whether to generate OpenAPI documentation.
@@ -15,4 +16,21 @@ pub struct DocumentationConfig {
     pub contact_email: Option<String>,
     /// License information for the API (optional).
     pub license: Option<String>,
+    /// Viewer of the interactive documentation page (Swagger UI by default).
+    #[serde(default)]
+    pub ui: DocsUi,
+    /// Path of the documentation page, with the OpenAPI document at `<path>/openapi.json`
+    /// (optional, `/docs` by default).
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
+/// Viewers of the OpenAPI document.
+pub enum DocsUi {
+    /// Swagger UI, which can send requests to the API.
+    #[default]
+    SwaggerUi,
+    /// ReDoc, a read-only reference.
+    Redoc,
 }
@@ -0,0 +1,9 @@
+use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for encrypting files produced from an entity (exports, backups).
+pub struct EncryptionConfig {
+    /// Reference to the base64-encoded 256-bit key: `env:VAR`, `file:/path` or `base64:<key>`.
+    pub key: String,
+}
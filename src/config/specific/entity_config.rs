@@ -1,12 +1,19 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use crate::config::specific::cors_config::EntityCorsConfig;
+use crate::config::specific::encryption_config::EncryptionConfig;
+use crate::config::specific::server_config::RateLimitConfig;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for an API entity.
 pub struct Entity {
     /// Name of the entity.
     pub name: String,
     /// Optional table name in the database.
     pub table_name: Option<String>,
+    /// Schema or database holding the table (optional). A prefix in `table_name` takes precedence.
+    #[serde(default)]
+    pub schema: Option<String>,
     /// List of fields in the entity.
     pub fields: Vec<Field>,
     /// Relationships with other entities.
@@ -21,9 +28,68 @@ pub struct Entity {
     pub validations: Vec<Validation>,
     /// Pagination configuration (optional).
     pub pagination: Option<PaginationConfig>,
+    /// Encryption of files produced from this entity, such as exports and backups (optional).
+    #[serde(default)]
+    pub encryption: Option<EncryptionConfig>,
+    /// CORS overrides for the endpoints of this entity (optional).
+    #[serde(default)]
+    pub cors: Option<EntityCorsConfig>,
+    /// Rate limit of the endpoints of this entity, applied on top of the server one (optional).
+    #[serde(default)]
+    pub rate_limiting: Option<RateLimitConfig>,
+    /// Timeout in seconds of the requests to the endpoints of this entity, overriding the server one (optional).
+    #[serde(default)]
+    pub request_timeout_seconds: Option<u32>,
+    /// Field whose value identifies the version of an entity, used as its ETag
+    /// instead of a hash of the entity (optional).
+    #[serde(default)]
+    pub version_field: Option<String>,
+    /// Rhai script of the scripts directory defining lifecycle hooks of the entity (optional).
+    #[serde(default)]
+    pub hooks_script: Option<String>,
+    /// How the create endpoint generates the key of entities sent without one (optional).
+    #[serde(default)]
+    pub id_strategy: Option<IdStrategy>,
+    /// DateTime field set when the entity is deleted, which then keeps it in a trash it can be
+    /// restored from instead of removing it (optional).
+    #[serde(default)]
+    pub soft_delete_field: Option<String>,
+    /// Field holding the subject of the caller that created a row, restricting the endpoints
+    /// to the rows the caller owns (optional).
+    #[serde(default)]
+    pub owner_field: Option<String>,
+    /// Roles reaching the rows of every owner.
+    #[serde(default)]
+    pub owner_bypass_roles: Vec<String>,
+    /// Cache of the responses of the GET endpoints of this entity, cleared when the entity
+    /// changes through the API (optional).
+    #[serde(default)]
+    pub cache: Option<ResponseCacheConfig>,
+    /// Caching headers of the responses of the read and list endpoints of this entity (optional).
+    #[serde(default)]
+    pub http_cache: Option<HttpCachePolicy>,
+    /// Webhooks notified of the changes of this entity made through the API.
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Strategies generating the key of new entities.
+pub enum IdStrategy {
+    /// Random UUID (version 4).
+    UuidV4,
+    /// Time-ordered UUID (version 7).
+    UuidV7,
+    /// Time-ordered ULID.
+    Ulid,
+    /// 21 character URL-safe Nano ID.
+    Nanoid,
+    /// Key generated by the database, like an auto-increment column.
+    DbAuto,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for a field in an entity.
 pub struct Field {
     /// Name of the field.
@@ -42,9 +108,61 @@ pub struct Field {
     pub default_value: Option<String>,
     /// Description of the field (optional).
     pub description: Option<String>,
+    /// Whether the database generates the value (auto-increment IDs, managed timestamps).
+    /// Generated fields are never written.
+    #[serde(default)]
+    pub generated: bool,
+    /// Whether the field is written when inserting.
+    #[serde(default = "default_true")]
+    pub insertable: bool,
+    /// Whether the field is written when updating.
+    #[serde(default = "default_true")]
+    pub updatable: bool,
+    /// Whether clients cannot write the field. Values sent for it are ignored and updates keep the stored value.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Expression computing the field from the other fields, like `price * quantity` or
+    /// `first_name + " " + last_name` (optional). Computed fields are never stored.
+    #[serde(default)]
+    pub computed: Option<String>,
+    /// Whether updates cannot change the field once the entity is created.
+    #[serde(default)]
+    pub immutable: bool,
+    /// Roles allowed to read the field. When set, the field is left out of the responses to
+    /// other callers, who cannot filter or sort by it either.
+    #[serde(default)]
+    pub read_roles: Vec<String>,
+    /// Roles allowed to write the field. When set, writes of the field by other callers are
+    /// answered with 403.
+    #[serde(default)]
+    pub write_roles: Vec<String>,
+    /// Whether the field holds a password. Its values are hashed with Argon2 before they are
+    /// stored and it is never returned.
+    #[serde(default)]
+    pub password: bool,
+}
+
+impl Field {
+    /// Whether the datasource stores the field, which computed fields are not
+    pub fn is_stored(&self) -> bool {
+        self.computed.is_none()
+    }
+}
+
+impl Entity {
+    /// Field identifying the entity: the `id` field when present, otherwise the first field
+    pub fn key_field(&self) -> Option<&Field> {
+        self.fields.iter()
+            .find(|field| field.name == "id")
+            .or_else(|| self.fields.first())
+    }
+}
+
+fn default_true() -> bool {
+    true
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Supported data types for fields.
 pub enum DataType {
     /// String data type.
@@ -65,7 +183,7 @@ pub enum DataType {
     JSON,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for a relationship between entities.
 pub struct Relationship {
     /// Name of the relationship.
@@ -76,11 +194,27 @@ pub struct Relationship {
     pub type_: RelationshipType,
     /// Foreign key for the relationship.
     pub foreign_key: String,
-    /// Whether to include the relationship in responses.
+    /// Whether to include the relationship in responses, as if the `include` parameter named it.
+    /// The related entities are embedded like on their own endpoints, for the callers who may
+    /// read them. Many-to-many relationships cannot be included.
     pub include_in_responses: bool,
+    /// Join table linking both entities, required for many-to-many relationships (optional).
+    #[serde(default)]
+    pub join_table: Option<JoinTable>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Join table of a many-to-many relationship.
+pub struct JoinTable {
+    /// Name of the join table.
+    pub table: String,
+    /// Column referencing the primary key of the entity declaring the relationship.
+    pub local_key: String,
+    /// Column referencing the primary key of the related entity.
+    pub related_key: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Supported types of relationships.
 pub enum RelationshipType {
     /// One-to-one relationship.
@@ -93,7 +227,7 @@ pub enum RelationshipType {
     ManyToMany,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for endpoints related to an entity.
 pub struct EndpointConfig {
     /// Whether to generate a create endpoint.
@@ -108,20 +242,39 @@ pub struct EndpointConfig {
     pub generate_list: bool,
     /// List of custom routes for the entity.
     pub custom_routes: Vec<CustomRoute>,
+    /// Whether the update endpoint creates the entity when the ID does not exist,
+    /// answering 201 instead of 404.
+    #[serde(default)]
+    pub upsert_on_put: bool,
+    /// What the update endpoints do with changes to immutable fields.
+    #[serde(default)]
+    pub immutable_changes: ImmutableChanges,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
+/// Handling of update requests changing immutable fields.
+pub enum ImmutableChanges {
+    /// The request is answered with 400.
+    #[default]
+    Reject,
+    /// The changes are dropped and the rest of the update is applied.
+    Ignore,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for a custom route.
 pub struct CustomRoute {
     /// Path of the custom route.
     pub path: String,
     /// HTTP method for the custom route.
     pub method: HttpMethod,
-    /// Handler for the custom route.
+    /// Handler for the custom route: `list`, `count` or `get`, followed by fixed query
+    /// parameters like `list?status=active&fields=id,name`. Values starting with `:` are
+    /// taken from the path parameters. `script:<file>` runs a Rhai script of the scripts directory instead.
     pub handler: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 /// Supported HTTP methods.
 pub enum HttpMethod {
     /// HTTP GET method.
@@ -136,7 +289,7 @@ pub enum HttpMethod {
     DELETE,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for authorization related to an entity.
 pub struct Authorization {
     /// Whether authorization is active.
@@ -147,7 +300,7 @@ pub struct Authorization {
     pub permissions: Vec<Permission>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for a role in authorization.
 pub struct Role {
     /// Name of the role.
@@ -156,16 +309,36 @@ pub struct Role {
     pub description: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-/// Configuration for a permission in authorization.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for a permission in authorization, written `action:subject` like `create:users`
+/// or `read:*`.
 pub struct Permission {
-    /// Action for the permission.
+    /// CRUD action for the permission: `create`, `read`, `update`, `delete` or `*` for all of them.
+    /// It may carry the subject as well, like `create:users`, leaving `subject` empty.
     pub action: String,
-    /// Subject for the permission.
+    /// Subject for the permission: the name of the entity or `*`. Any other value names the role
+    /// the permission applies to.
+    #[serde(default)]
     pub subject: String,
+    /// Roles the permission applies to (optional, all callers by default).
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Whether the permission allows or denies the action (optional, Allow by default).
+    #[serde(default)]
+    pub effect: PermissionEffect,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
+/// Effect of a permission. Denials take precedence over the permissions allowing an action.
+pub enum PermissionEffect {
+    /// The action is allowed.
+    #[default]
+    Allow,
+    /// The action is denied.
+    Deny,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for a validation related to an entity.
 pub struct Validation {
     /// Field to validate.
@@ -176,7 +349,7 @@ pub struct Validation {
     pub error_message: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Supported types of validations.
 pub enum ValidationType {
     /// Length validation with minimum and optional maximum.
@@ -191,12 +364,105 @@ pub enum ValidationType {
     Range(f64, f64),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration of the response cache of an entity.
+pub struct ResponseCacheConfig {
+    /// Seconds a response is served from the cache.
+    pub ttl_seconds: u64,
+    /// Maximum number of cached responses of the entity (optional, 1000 by default).
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Policy of the caching headers of an entity, telling clients and shared caches how long they
+/// may reuse its responses.
+pub struct HttpCachePolicy {
+    /// Seconds a response may be reused, sent as the `max-age` of `Cache-Control`.
+    pub max_age_seconds: u64,
+    /// Whether shared caches, like proxies and CDNs, may store the responses (`public`) or only
+    /// the client (`private`).
+    #[serde(default)]
+    pub public: bool,
+    /// Whether clients must revalidate responses once stale instead of reusing them (`must-revalidate`).
+    #[serde(default)]
+    pub must_revalidate: bool,
+    /// DateTime field holding the last modification of an entity, sent as `Last-Modified` and
+    /// compared with `If-Modified-Since` (optional).
+    #[serde(default)]
+    pub last_modified_field: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Webhook receiving a JSON payload for each change of an entity, POSTed in the background once
+/// the change is stored.
+pub struct WebhookConfig {
+    /// URL the payloads are POSTed to.
+    pub url: String,
+    /// Changes sent to the webhook. Every change when empty.
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// Secret signing the payloads, sent as the hex HMAC-SHA256 of the body in the
+    /// `X-Rawst-Signature` header, like `sha256=<hex>` (optional).
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Retries of the failed deliveries.
+    #[serde(default)]
+    pub retry: WebhookRetryPolicy,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+/// Changes of an entity a webhook can be notified of.
+pub enum WebhookEvent {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Retries of the deliveries failing with a network error, a timeout, 429 or a 5xx status.
+/// The delay doubles after each attempt.
+pub struct WebhookRetryPolicy {
+    /// Attempts of a delivery, including the first one.
+    #[serde(default = "default_webhook_attempts")]
+    pub max_attempts: u32,
+    /// Seconds before the first retry.
+    #[serde(default = "default_webhook_backoff")]
+    pub backoff_seconds: u64,
+    /// Seconds an attempt waits for the response.
+    #[serde(default = "default_webhook_timeout")]
+    pub timeout_seconds: u64,
+}
+
+impl Default for WebhookRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_webhook_attempts(),
+            backoff_seconds: default_webhook_backoff(),
+            timeout_seconds: default_webhook_timeout(),
+        }
+    }
+}
+
+fn default_webhook_attempts() -> u32 {
+    3
+}
+
+fn default_webhook_backoff() -> u64 {
+    1
+}
+
+fn default_webhook_timeout() -> u64 {
+    10
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for pagination related to an entity.
 pub struct PaginationConfig {
     /// Default page size for pagination.
     pub default_page_size: u32,
-    /// Maximum page size for pagination.
+    /// Maximum page size for pagination. Larger sizes requested by clients are reduced to it.
     pub max_page_size: u32,
     /// Name of the page parameter.
     pub page_param_name: String,
@@ -0,0 +1,82 @@
+use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Publishing of the changes of the entities to a message broker. Changes are first written to an
+/// outbox table of the relational database, then relayed to the broker, so none is lost while the
+/// broker is unreachable or the server restarts. A change may be published more than once.
+pub struct EventsConfig {
+    /// Broker the changes are published to.
+    pub broker: BrokerType,
+    /// Address of the broker: the bootstrap servers for Kafka, like `localhost:9092`, an
+    /// `amqp://` URL for RabbitMQ or a `nats://` URL for NATS.
+    pub url: String,
+    /// Kafka topic, RabbitMQ exchange or NATS subject prefix of the changes. RabbitMQ routing keys
+    /// and NATS subjects end with `<entity>.<action>`, like `users.created`.
+    #[serde(default = "default_topic")]
+    pub topic: String,
+    /// Entities whose changes are published. Every entity when empty.
+    #[serde(default)]
+    pub entities: Vec<String>,
+    /// Outbox table the changes wait in until the broker receives them.
+    #[serde(default)]
+    pub outbox: OutboxConfig,
+}
+
+impl EventsConfig {
+    /// Whether the changes of an entity are published
+    pub fn publishes(&self, entity: &str) -> bool {
+        self.entities.is_empty() || self.entities.iter().any(|name| name.eq_ignore_ascii_case(entity))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+/// Supported message brokers.
+pub enum BrokerType {
+    /// Apache Kafka, with the kafka feature.
+    Kafka,
+    /// RabbitMQ, with the rabbitmq feature.
+    RabbitMQ,
+    /// NATS, with the nats feature.
+    NATS,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Outbox table of the changes to publish, created in the MySQL or MariaDB database when missing.
+pub struct OutboxConfig {
+    /// Name of the table.
+    #[serde(default = "default_outbox_table")]
+    pub table: String,
+    /// Milliseconds between two reads of the table.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Maximum number of changes published per read.
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u32,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            table: default_outbox_table(),
+            poll_interval_ms: default_poll_interval_ms(),
+            batch_size: default_batch_size(),
+        }
+    }
+}
+
+fn default_topic() -> String {
+    "rawst".to_string()
+}
+
+fn default_outbox_table() -> String {
+    "rawst_outbox".to_string()
+}
+
+fn default_poll_interval_ms() -> u64 {
+    1000
+}
+
+fn default_batch_size() -> u32 {
+    100
+}
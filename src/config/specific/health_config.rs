@@ -0,0 +1,42 @@
+use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration of the periodic reachability checks of external integrations
+pub struct HealthConfig {
+    /// Interval between checks in seconds
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u32,
+    /// Timeout of each check in seconds
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u32,
+    /// Integrations to check
+    #[serde(default)]
+    pub targets: Vec<HealthTarget>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// An external integration whose reachability is checked
+pub struct HealthTarget {
+    /// Name shown in the health report
+    pub name: String,
+    /// URL requested by the check
+    pub url: String,
+    /// Kind of integration
+    pub kind: HealthTargetKind,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+/// Kind of integration checked
+pub enum HealthTargetKind {
+    Webhook,
+    Proxy,
+}
+
+fn default_interval_seconds() -> u32 {
+    30
+}
+
+fn default_timeout_seconds() -> u32 {
+    5
+}
@@ -1,37 +1,126 @@
 use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use std::collections::HashMap;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
 /// Configuration for the server.
 pub struct ServerConfig {
     /// Hostname or IP address where the server will run.
     pub host: String,
     /// Port number for the server.
     pub port: u32,
-    /// Timeout for requests in seconds.
+    /// Timeout for requests in seconds, 30 when 0. Slower requests are answered with 504.
     pub request_timeout_seconds: u32,
-    /// Maximum payload size in megabytes.
+    /// Maximum payload size in megabytes, 2 when 0. Larger request bodies are answered with 413.
     pub max_payload_size_mb: u32,
     /// Rate limiting configuration (optional).
     pub rate_limiting: Option<RateLimitConfig>,
     /// Logging level for the server.
     pub logging_level: LogLevel,
+    /// Format of the log lines (text by default).
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// Record-and-replay configuration (optional).
+    #[serde(default)]
+    pub record_replay: Option<RecordReplayConfig>,
+    /// Response schema snapshots used to detect drift (optional).
+    #[serde(default)]
+    pub schema_snapshots: Option<SchemaSnapshotConfig>,
+    /// Directory holding the scripts of custom routes (optional, `scripts` by default).
+    #[serde(default)]
+    pub scripts_dir: Option<String>,
+    /// Directory storing the files uploaded to Binary fields (optional, `uploads` by default).
+    #[serde(default)]
+    pub uploads_dir: Option<String>,
+    /// Shape of the response bodies (bare by default).
+    #[serde(default)]
+    pub response_format: ResponseFormat,
+    /// Shape of the error bodies (simple by default).
+    #[serde(default)]
+    pub error_format: ErrorFormat,
+    /// Seconds the responses of create requests are kept for retries with the same `Idempotency-Key`
+    /// (optional, one day by default).
+    #[serde(default)]
+    pub idempotency_ttl_seconds: Option<u64>,
+    /// Maximum number of entities a list request returns (optional, 1000 by default).
+    #[serde(default)]
+    pub max_results: Option<u64>,
+    /// Web framework serving the API (Rocket by default). Actix-web requires the `actix` feature.
+    #[serde(default)]
+    pub framework: ServerFramework,
+    /// HTTPS configuration (optional). Requests are served over plain HTTP without it.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// OpenTelemetry trace export (optional). Requires the `otel` feature.
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
+    /// GraphQL endpoint at `/graphql` generated from the entities (optional). Requires the `graphql` feature.
+    #[serde(default)]
+    pub graphql: Option<GraphqlConfig>,
+    /// gRPC server generated from the entities, listening on its own port (optional). Requires the `grpc` feature.
+    #[serde(default)]
+    pub grpc: Option<GrpcConfig>,
+    /// Directories served as static files, by URL prefix like `"/assets": "public/assets"` (optional).
+    /// Only supported by the Rocket framework.
+    #[serde(default)]
+    pub static_dirs: HashMap<String, String>,
+    /// Keep-alive, worker and connection limits of the server (optional).
+    #[serde(default)]
+    pub tuning: Option<ServerTuningConfig>,
+    /// Addresses the server listens on from one process, instead of `host` and `port` (optional).
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+    /// Path of a Unix domain socket the server listens on instead of TCP, like behind nginx (optional).
+    /// Only supported by the Actix framework on Unix systems.
+    #[serde(default)]
+    pub socket_path: Option<String>,
+    /// Reverse proxies whose forwarding headers are trusted (optional).
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    /// Whether to serve an HTML admin panel at `/admin`, managing the entities through the API.
+    /// It is an admin route, sending the credentials of the configured authentication.
+    #[serde(default)]
+    pub admin_panel: bool,
+    /// Maintenance mode, answering the requests changing the entities with 503 while reads keep
+    /// being served (optional). It is toggled at runtime through `/admin/maintenance`.
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceConfig>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Directory of the scripts when `scripts_dir` is not set
+pub const DEFAULT_SCRIPTS_DIR: &str = "scripts";
+
+/// Directory of the uploaded files when `uploads_dir` is not set
+pub const DEFAULT_UPLOADS_DIR: &str = "uploads";
+
+/// Timeout for requests in seconds when `request_timeout_seconds` is 0
+pub const DEFAULT_REQUEST_TIMEOUT_SECONDS: u32 = 30;
+
+/// Maximum payload size in megabytes when `max_payload_size_mb` is 0
+pub const DEFAULT_MAX_PAYLOAD_SIZE_MB: u32 = 2;
+
+/// Maximum number of entities a list request returns when `max_results` is not set
+pub const DEFAULT_MAX_RESULTS: u64 = 1000;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 /// Configuration for rate limiting.
 pub struct RateLimitConfig {
     /// Maximum number of requests allowed per minute.
     pub requests_per_minute: u32,
     /// Burst capacity for rate limiting.
     pub burst: u32,
+    /// Whether each client IP address gets its own limit instead of sharing one.
+    #[serde(default)]
+    pub per_ip: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
 /// Logging levels for the server.
 pub enum LogLevel {
     /// Debug level logging.
     Debug,
     /// Informational level logging.
+    #[default]
     Info,
     /// Warning level logging.
     Warning,
@@ -39,8 +128,199 @@ pub enum LogLevel {
     Error,
 }
 
-impl Default for LogLevel {
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
+/// Formats of the log lines.
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields of the event, for log collectors.
+    Json,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for recording request/response pairs and replaying them later.
+pub struct RecordReplayConfig {
+    /// Whether to record live traffic or replay a previous recording.
+    pub mode: RecordMode,
+    /// Path of the recording file (one JSON exchange per line).
+    pub path: String,
+    /// Request and response headers whose values are redacted before being written to disk.
+    #[serde(default = "default_sanitized_headers")]
+    pub sanitize_headers: Vec<String>,
+    /// Body fields whose values are redacted before being written to disk.
+    #[serde(default)]
+    pub sanitize_fields: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
+/// Shapes of the response bodies.
+pub enum ResponseFormat {
+    /// The entity or list of entities itself. Pages hold their items with the pagination fields.
+    #[default]
+    Bare,
+    /// A `{"data": ..., "meta": {...}}` object, with the pagination details in `meta`.
+    Envelope,
+    /// The body tagged with its kind, like `{"Single": {...}}` or `{"List": [...]}`, as in earlier versions.
+    Tagged,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
+/// Web frameworks able to serve the API.
+pub enum ServerFramework {
+    /// Rocket, binding to the address of its own configuration (`ROCKET_ADDRESS` and `ROCKET_PORT`).
+    #[default]
+    Rocket,
+    /// Actix-web, binding to `host` and `port`, or to `socket_path`.
+    Actix,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+/// Configuration for serving the API over HTTPS.
+pub struct TlsConfig {
+    /// Path of the PEM certificate chain.
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    /// Path of the PEM private key of the certificate.
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// Whether to generate a self-signed certificate at startup when no certificate is configured.
+    /// Meant for development, as clients do not trust it.
+    #[serde(default)]
+    pub self_signed: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+/// An address the server listens on.
+pub struct ListenerConfig {
+    /// Hostname or IP address of the listener (`host` of the server when empty).
+    #[serde(default)]
+    pub host: String,
+    /// Port number of the listener.
+    pub port: u32,
+    /// Whether the listener serves the admin routes: `/metrics`, `/health`, `/admin/schema-drift`,
+    /// `/admin/maintenance`, `/_routes`, the admin panel and the documentation. Once a listener is marked admin, the others no longer serve them.
+    #[serde(default)]
+    pub admin: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+/// Trust of the headers set by the reverse proxies and load balancers in front of the server.
+/// `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` are only read from the requests
+/// they send, and give the client address used by rate limiting and logs, and the public URL of the API.
+pub struct ProxyConfig {
+    /// Addresses or CIDR ranges of the proxies, like `10.0.0.0/8` or `::1`, or `*` to trust every peer.
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Maintenance mode of the API, like during deployments and migrations.
+pub struct MaintenanceConfig {
+    /// Whether the server starts in maintenance mode.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds clients are told to wait in the `Retry-After` header of the rejected requests.
+    #[serde(default = "default_retry_after_seconds")]
+    pub retry_after_seconds: u64,
+}
+
+impl Default for MaintenanceConfig {
     fn default() -> Self {
-        LogLevel::Info
+        Self { enabled: false, retry_after_seconds: default_retry_after_seconds() }
     }
 }
+
+fn default_retry_after_seconds() -> u64 {
+    60
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+/// Tuning of the server for high-concurrency deployments. Unset options keep the defaults of the framework.
+pub struct ServerTuningConfig {
+    /// Seconds idle connections are kept open, 0 disabling keep-alive.
+    #[serde(default)]
+    pub keep_alive_seconds: Option<u32>,
+    /// Number of threads running the server and its asynchronous tasks.
+    #[serde(default)]
+    pub workers: Option<usize>,
+    /// Maximum number of threads running the requests to the endpoints, which block on the datasources.
+    #[serde(default)]
+    pub max_blocking_threads: Option<usize>,
+    /// Maximum number of concurrent connections of each worker. Only supported by the Actix framework.
+    #[serde(default)]
+    pub max_connections: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for exporting the spans of the requests and their queries as OpenTelemetry traces.
+pub struct TelemetryConfig {
+    /// OTLP/HTTP endpoint receiving the spans, like `http://localhost:4318/v1/traces`.
+    pub endpoint: String,
+    /// Name of the service in the traces (optional, `rawst` by default).
+    #[serde(default)]
+    pub service_name: Option<String>,
+    /// Share of the traces recorded, from 0 to 1 (optional, every trace by default).
+    /// Traces started by callers keep their sampling decision.
+    #[serde(default)]
+    pub sample_ratio: Option<f64>,
+    /// Headers sent with every export, like the API key of a collector.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+/// Configuration for the GraphQL endpoint.
+pub struct GraphqlConfig {
+    /// Whether `GET /graphql` serves the GraphiQL IDE.
+    #[serde(default)]
+    pub graphiql: bool,
+    /// Maximum nesting of the queries (optional, 10 by default).
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for the gRPC server.
+pub struct GrpcConfig {
+    /// Port the gRPC server listens on, on the same host as the API.
+    pub port: u16,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
+/// Shapes of the error bodies.
+pub enum ErrorFormat {
+    /// A `{"error": "..."}` object.
+    #[default]
+    Simple,
+    /// An RFC 7807 `application/problem+json` object with `type`, `title`, `status`, `detail` and `instance`.
+    Problem,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Record-and-replay modes.
+pub enum RecordMode {
+    /// Forward requests to the datasources and record every exchange.
+    Record,
+    /// Serve previously recorded exchanges without touching the datasources.
+    Replay,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Configuration for snapshotting the JSON shape of responses and reporting when it drifts.
+pub struct SchemaSnapshotConfig {
+    /// Path of the snapshot file, holding the shape of every endpoint response.
+    pub path: String,
+    /// Whether drifted shapes replace the stored snapshots instead of being reported.
+    #[serde(default)]
+    pub update: bool,
+}
+
+fn default_sanitized_headers() -> Vec<String> {
+    vec![
+        "authorization".to_string(),
+        "cookie".to_string(),
+        "set-cookie".to_string(),
+        "x-api-key".to_string(),
+    ]
+}
@@ -0,0 +1,58 @@
+use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+
+/// Header holding the tenant when `header_name` is not set
+const DEFAULT_TENANT_HEADER: &str = "X-Tenant-Id";
+/// Claim holding the tenant when `claim` is not set
+const DEFAULT_TENANT_CLAIM: &str = "tenant_id";
+/// Field holding the tenant of the entities when `field` is not set
+const DEFAULT_TENANT_FIELD: &str = "tenant_id";
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// Multi-tenancy: every request names its tenant, and the entities with the tenant field only
+/// read and write the rows of that tenant. Entities without the field are shared by every tenant.
+pub struct TenancyConfig {
+    /// Where the tenant of the requests comes from.
+    pub source: TenantSource,
+    /// Header holding the tenant, with the Header source (optional, `X-Tenant-Id` by default).
+    #[serde(default)]
+    pub header_name: Option<String>,
+    /// Domain the tenants are subdomains of, with the Subdomain source, like `example.com` for
+    /// tenants reached at `acme.example.com`.
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// Claim of the verified token holding the tenant, with dots for nested claims (optional,
+    /// `tenant_id` by default). With the Header and Subdomain sources, authenticated callers must
+    /// have the tenant they name in this claim, and the ones without it are refused. Users signing
+    /// in with the JWT or session authentication carry the `field` of the users entity in it.
+    #[serde(default)]
+    pub claim: Option<String>,
+    /// Field of the entities holding their tenant (optional, `tenant_id` by default).
+    #[serde(default)]
+    pub field: Option<String>,
+}
+
+impl TenancyConfig {
+    pub fn header_name(&self) -> &str {
+        self.header_name.as_deref().unwrap_or(DEFAULT_TENANT_HEADER)
+    }
+
+    pub fn claim(&self) -> &str {
+        self.claim.as_deref().unwrap_or(DEFAULT_TENANT_CLAIM)
+    }
+
+    pub fn field(&self) -> &str {
+        self.field.as_deref().unwrap_or(DEFAULT_TENANT_FIELD)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+/// Where the tenant of a request comes from.
+pub enum TenantSource {
+    /// A request header, like `X-Tenant-Id: acme`
+    Header,
+    /// The subdomain of the `Host` header, like `acme.example.com`
+    Subdomain,
+    /// A claim of the token the caller authenticated with
+    Claim,
+}
@@ -0,0 +1,18 @@
+use serde::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use crate::config::shared::EntityBasic;
+use crate::config::specific::entity_config::Entity;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+/// A version of the API serving its own set of entities under `/api/<name>`, next to the
+/// entities of the configuration. Entities named like the ones of another set share their data,
+/// so a version can change the fields, validations or endpoints of an entity without a migration.
+/// The GraphQL and gRPC services serve the entities of the configuration only.
+pub struct ApiVersionConfig {
+    /// Segment the entities of the version are served under, like `v2`
+    pub name: String,
+    #[serde(default)]
+    pub entities_basic: Vec<EntityBasic>,
+    #[serde(default)]
+    pub entities_advanced: Vec<Entity>,
+}
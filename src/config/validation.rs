@@ -0,0 +1,301 @@
+use serde::Serialize;
+use crate::config::configuration::Config;
+use crate::config::secrets::SecretReference;
+use crate::config::specific::auth_config::AuthType;
+use crate::config::specific::database_config::DatabaseType;
+use crate::config::specific::entity_config::{Entity, RelationshipType};
+use crate::data::datasource::relational::base::{validate_identifier, validate_qualified_name};
+
+/// A problem of a configuration, at the JSON path of the value causing it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// JSON path of the value, like `entities_advanced[0].fields[1].name`
+    pub path: String,
+    /// What is wrong with the value
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Issues found so far
+#[derive(Default)]
+struct Issues(Vec<ValidationIssue>);
+
+impl Issues {
+    fn push(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.0.push(ValidationIssue { path: path.into(), message: message.into() });
+    }
+}
+
+impl Config {
+    /// Every problem of this configuration, with the JSON path of its value. Configurations
+    /// with any are refused when they are loaded.
+    pub fn validation_issues(&self) -> Vec<ValidationIssue> {
+        let mut issues = Issues::default();
+        validate_server(self, &mut issues);
+        validate_auth(self, &mut issues);
+        validate_secrets(self, &mut issues);
+        validate_entities(self, "", &mut issues);
+        validate_versions(self, &mut issues);
+        issues.0
+    }
+}
+
+/// The port, tuning and listeners of the server must allow it to run
+fn validate_server(config: &Config, issues: &mut Issues) {
+    let valid_port = |port| (1024..=65535).contains(&port);
+    if !valid_port(config.server.port) {
+        issues.push("server.port", "Server port must be between 1024 and 65535");
+    }
+    if let Some(tuning) = &config.server.tuning {
+        let counts = [
+            ("workers", tuning.workers),
+            ("max_blocking_threads", tuning.max_blocking_threads),
+            ("max_connections", tuning.max_connections),
+        ];
+        for (name, _) in counts.into_iter().filter(|(_, count)| *count == Some(0)) {
+            issues.push(format!("server.tuning.{}", name), "must be at least 1");
+        }
+    }
+
+    // Listeners need distinct ports in the same range as the port of the server, and cannot be
+    // combined with a Unix socket
+    if config.server.socket_path.is_some() && !config.server.listeners.is_empty() {
+        issues.push("server.socket_path", "server.socket_path and server.listeners cannot be used together");
+    }
+    let host = |host: &str| if host.is_empty() { config.server.host.clone() } else { host.to_string() };
+    for (index, listener) in config.server.listeners.iter().enumerate() {
+        let path = format!("server.listeners[{}]", index);
+        if !valid_port(listener.port) {
+            issues.push(format!("{}.port", path), format!("Listener port {} must be between 1024 and 65535", listener.port));
+        }
+        if config.server.listeners[..index].iter()
+            .any(|other| other.port == listener.port && host(&other.host) == host(&listener.host)) {
+            issues.push(path, format!("Listener {}:{} is defined twice", host(&listener.host), listener.port));
+        }
+    }
+}
+
+/// The authentication must be one the server implements, and entities restricted to some
+/// callers need one to tell who the callers are
+fn validate_auth(config: &Config, issues: &mut Issues) {
+    let auth_type = config.auth.as_ref().map(|auth| &auth.auth_type);
+    if let Some(auth_type @ (AuthType::ApiKey | AuthType::Basic)) = auth_type {
+        issues.push("auth.auth_type", format!("{:?} authentication is not supported, use JWT, OAuth or Session", auth_type));
+    }
+    if matches!(auth_type, None | Some(AuthType::None)) {
+        for (path, _) in config.served_entities().iter().filter(|(_, entity)| restricts_callers(entity)) {
+            issues.push("auth", format!("Entity {} requires authenticated callers but no authentication is configured", path));
+        }
+    }
+}
+
+/// Whether the endpoints of an entity need to know their callers
+pub fn restricts_callers(entity: &Entity) -> bool {
+    entity.authentication || entity.authorization.active || entity.owner_field.is_some()
+}
+
+/// Credentials given as `secret://` references must name a backend and a path
+fn validate_secrets(config: &Config, issues: &mut Issues) {
+    let mut credentials = vec![
+        ("database.username".to_string(), Some(&config.database.username)),
+        ("database.password".to_string(), Some(&config.database.password)),
+        ("database.connection_string".to_string(), Some(&config.database.connection_string)),
+    ];
+    if let Some(auth) = &config.auth {
+        credentials.push(("auth.jwt_config.secret".to_string(), auth.jwt_config.as_ref().map(|jwt| &jwt.secret)));
+        credentials.push(("auth.session_config.secret".to_string(), auth.session_config.as_ref().map(|session| &session.secret)));
+        credentials.push((
+            "auth.ldap_config.service_password".to_string(),
+            auth.ldap_config.as_ref().and_then(|ldap| ldap.service_password.as_ref()),
+        ));
+        for (index, provider) in auth.oauth_config.iter().flat_map(|oauth| oauth.providers.iter()).enumerate() {
+            credentials.push((format!("auth.oauth_config.providers[{}].client_secret", index), Some(&provider.client_secret)));
+        }
+    }
+    for (path, value) in credentials {
+        if let Some(Err(e)) = value.and_then(|value| SecretReference::parse(value)) {
+            issues.push(path, e);
+        }
+    }
+}
+
+/// Version names must be plain path segments, distinct from each other and from the entities
+/// served next to them. The entities of each version are validated like the others.
+fn validate_versions(config: &Config, issues: &mut Issues) {
+    let entities = config.all_entities();
+    for (index, version) in config.versions.iter().enumerate() {
+        let path = format!("versions[{}]", index);
+        let name = version.name.as_str();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+            issues.push(format!("{}.name", path), format!("Invalid API version name: {:?}", name));
+        }
+        if config.versions[..index].iter().any(|other| other.name.eq_ignore_ascii_case(name)) {
+            issues.push(format!("{}.name", path), format!("API version {} is defined twice", name));
+        }
+        if entities.iter().any(|entity| entity.name.eq_ignore_ascii_case(name)) {
+            issues.push(format!("{}.name", path), format!("API version {} has the name of an entity", name));
+        }
+        validate_entities(&config.version_config(version), &format!("{}.", path), issues);
+    }
+}
+
+/// Validates the entities of a configuration, or of one of its versions, whose paths start with `prefix`
+fn validate_entities(config: &Config, prefix: &str, issues: &mut Issues) {
+    // Entities with their paths, as `all_entities` lists them
+    let mut entities: Vec<(String, Entity)> = config.entities_advanced.iter().enumerate()
+        .map(|(index, entity)| (format!("{}entities_advanced[{}]", prefix, index), entity.clone()))
+        .collect();
+    for (index, entity_basic) in config.entities_basic.iter().enumerate() {
+        let path = format!("{}entities_basic[{}]", prefix, index);
+        if config.entities_basic[..index].iter().any(|other| other.name.eq_ignore_ascii_case(&entity_basic.name)) {
+            issues.push(format!("{}.name", path), format!("Entity {} is defined twice", entity_basic.name));
+        } else if !config.entities_advanced.iter().any(|entity| entity.name.eq_ignore_ascii_case(&entity_basic.name)) {
+            entities.push((path, entity_basic.to_entity()));
+        }
+    }
+    for (index, entity) in config.entities_advanced.iter().enumerate() {
+        if config.entities_advanced[..index].iter().any(|other| other.name.eq_ignore_ascii_case(&entity.name)) {
+            issues.push(format!("{}entities_advanced[{}].name", prefix, index), format!("Entity {} is defined twice", entity.name));
+        }
+    }
+
+    let relational = !matches!(config.database.db_type, DatabaseType::MongoDB | DatabaseType::SurrealDB);
+    for (path, entity) in &entities {
+        validate_entity(entity, path, &entities, issues);
+        if relational {
+            validate_identifiers(entity, path, issues);
+        }
+    }
+}
+
+/// Checks the name, fields, relationships, validations and custom routes of an entity
+fn validate_entity(entity: &Entity, path: &str, entities: &[(String, Entity)], issues: &mut Issues) {
+    if entity.name.is_empty() || !entity.name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')) {
+        issues.push(format!("{}.name", path), format!("Invalid entity name {:?}: expected letters, digits, - and _", entity.name));
+    }
+    if entity.key_field().is_none() {
+        issues.push(format!("{}.fields", path), format!("Entity {} has no field to use as its primary key", entity.name));
+    }
+    for (index, field) in entity.fields.iter().enumerate() {
+        if field.name.is_empty() {
+            issues.push(format!("{}.fields[{}].name", path, index), "A field name cannot be empty");
+        } else if entity.fields[..index].iter().any(|other| other.name == field.name) {
+            issues.push(format!("{}.fields[{}].name", path, index), format!("Field {} is defined twice", field.name));
+        }
+    }
+
+    let has_field = |name: &str| entity.fields.iter().any(|field| field.name == name);
+    let field_options = [
+        ("version_field", &entity.version_field),
+        ("soft_delete_field", &entity.soft_delete_field),
+        ("owner_field", &entity.owner_field),
+    ];
+    for (option, field) in field_options {
+        if let Some(field) = field.as_deref().filter(|field| !has_field(field)) {
+            issues.push(format!("{}.{}", path, option), format!("Unknown field {}", field));
+        }
+    }
+    for (index, validation) in entity.validations.iter().enumerate() {
+        if !has_field(&validation.field) {
+            issues.push(format!("{}.validations[{}].field", path, index), format!("Unknown field {}", validation.field));
+        }
+    }
+
+    for (index, relationship) in entity.relationships.iter().enumerate() {
+        let relationship_path = format!("{}.relationships[{}]", path, index);
+        if entity.relationships[..index].iter().any(|other| other.name == relationship.name) {
+            issues.push(format!("{}.name", relationship_path), format!("Relationship {} is defined twice", relationship.name));
+        }
+        if !entities.iter().any(|(_, other)| other.name.eq_ignore_ascii_case(&relationship.related_entity)) {
+            issues.push(
+                format!("{}.related_entity", relationship_path),
+                format!("Relationship {} points at the unknown entity {}", relationship.name, relationship.related_entity),
+            );
+        }
+        if relationship.include_in_responses && matches!(relationship.type_, RelationshipType::ManyToMany) {
+            issues.push(
+                format!("{}.include_in_responses", relationship_path),
+                format!("Many-to-many relationship {} cannot be included in responses", relationship.name),
+            );
+        }
+    }
+
+    for (index, route) in entity.endpoints.custom_routes.iter().enumerate() {
+        let route_path = format!("{}.endpoints.custom_routes[{}]", path, index);
+        if let Err(e) = validate_route_path(&route.path) {
+            issues.push(format!("{}.path", route_path), e);
+        }
+        if entity.endpoints.custom_routes[..index].iter()
+            .any(|other| other.method == route.method && other.path.trim_matches('/') == route.path.trim_matches('/')) {
+            issues.push(format!("{}.path", route_path), format!("Custom route {:?} {} is defined twice", route.method, route.path));
+        }
+    }
+}
+
+/// Custom route paths are `/` separated segments: literals of letters, digits, `-`, `_`, `.` and
+/// `~`, or path parameters like `:id`, each named once
+fn validate_route_path(path: &str) -> Result<(), String> {
+    let trimmed = path.trim_matches('/');
+    if trimmed.is_empty() {
+        return Err("A custom route path cannot be empty".to_string());
+    }
+    let mut parameters: Vec<&str> = Vec::new();
+    for segment in trimmed.split('/') {
+        match segment.strip_prefix(':') {
+            Some(name) => {
+                let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !valid {
+                    return Err(format!("Invalid path parameter {:?} in {}", segment, path));
+                }
+                if parameters.contains(&name) {
+                    return Err(format!("Path parameter :{} appears twice in {}", name, path));
+                }
+                parameters.push(name);
+            }
+            None if segment.is_empty() => return Err(format!("Empty segment in {}", path)),
+            None if !segment.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~')) => {
+                return Err(format!("Invalid segment {:?} in {}", segment, path));
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// Tables and columns of relational databases are put into the SQL statements, so they must
+/// be plain identifiers
+fn validate_identifiers(entity: &Entity, path: &str, issues: &mut Issues) {
+    let mut check = |path: String, result: Result<(), String>| {
+        if let Err(e) = result {
+            issues.push(path, e);
+        }
+    };
+    match &entity.table_name {
+        Some(table) => check(format!("{}.table_name", path), validate_qualified_name(table)),
+        None => check(format!("{}.name", path), validate_qualified_name(&entity.name)),
+    }
+    if let Some(schema) = &entity.schema {
+        check(format!("{}.schema", path), validate_identifier(schema));
+    }
+    for (index, field) in entity.fields.iter().enumerate().filter(|(_, field)| field.is_stored()) {
+        match &field.column_name {
+            Some(column) => check(format!("{}.fields[{}].column_name", path, index), validate_identifier(column)),
+            None => check(format!("{}.fields[{}].name", path, index), validate_identifier(&field.name)),
+        }
+    }
+    for (index, relationship) in entity.relationships.iter().enumerate() {
+        let relationship_path = format!("{}.relationships[{}]", path, index);
+        check(format!("{}.foreign_key", relationship_path), validate_identifier(&relationship.foreign_key));
+        if let Some(join_table) = &relationship.join_table {
+            check(format!("{}.join_table.table", relationship_path), validate_qualified_name(&join_table.table));
+            check(format!("{}.join_table.local_key", relationship_path), validate_identifier(&join_table.local_key));
+            check(format!("{}.join_table.related_key", relationship_path), validate_identifier(&join_table.related_key));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::config::specific::database_config::{DatabaseConfig, DatabaseType};
 use std::any::Any;
+use tracing::{debug, info};
 // Import necessary crates for each database type (e.g., sqlx, diesel)
 // as suggested previously if using Cargo features.
 
@@ -9,11 +10,11 @@ impl ConnectionManager {
     pub async fn create_connection(
         config: &DatabaseConfig,
     ) -> Result<Box<dyn Any>, Box<dyn std::error::Error>> {
-        let connection_url = config.make_url();
+        let connection_url = config.with_secrets()?.make_url();
 
         match config.db_type {
             DatabaseType::PostgreSQL => {
-                println!("Attempting to connect to PostgreSQL at {}...", connection_url);
+                debug!("Attempting to connect to PostgreSQL at {}...", connection_url);
 
                 use sqlx::postgres::PgPoolOptions;
 
@@ -22,11 +23,11 @@ impl ConnectionManager {
                     .connect(&connection_url) 
                     .await?;
 
-                println!("Successfully connected to PostgreSQL.");
+                info!("Successfully connected to PostgreSQL.");
                 Ok(Box::new(pool))
             }
             DatabaseType::MySQL => {
-                println!("Attempting to connect to MySQL at {}...", connection_url);
+                debug!("Attempting to connect to MySQL at {}...", connection_url);
 
                 use sqlx::mysql::MySqlPoolOptions;
 
@@ -35,13 +36,13 @@ impl ConnectionManager {
                     .connect(&connection_url) 
                     .await?;
 
-                println!("Successfully connected to MySQL.");
+                info!("Successfully connected to MySQL.");
                 Ok(Box::new(pool))
             }
             DatabaseType::SQLite => {
                 
                 let sqlite_path = &config.connection_string; 
-                println!("Attempting to connect to SQLite at {}...", sqlite_path);
+                debug!("Attempting to connect to SQLite at {}...", sqlite_path);
 
 
                 use sqlx::sqlite::SqlitePoolOptions;
@@ -51,18 +52,24 @@ impl ConnectionManager {
                     .connect(sqlite_path) 
                     .await?;
 
-                println!("Successfully connected to SQLite.");
+                info!("Successfully connected to SQLite.");
                 Ok(Box::new(pool))
             }
             DatabaseType::MongoDB => {
-                 println!("Attempting to connect to MongoDB at {}...", connection_url);
+                 debug!("Attempting to connect to MongoDB at {}...", connection_url);
       
 
-                 Err(Box::new(std::io::Error::new(
-                     std::io::ErrorKind::Other,
+                 Err(Box::new(std::io::Error::other(
                      "MongoDB connection using `mongodb` crate not implemented yet",
                  )))
             }
+            DatabaseType::SurrealDB => {
+                debug!("Creating HTTP client for SurrealDB at {}...", connection_url);
+
+                // SurrealDB is reached over HTTP, so the connection is a client without an open socket
+                let client = reqwest::Client::new();
+                Ok(Box::new(client))
+            }
         }
     }
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
 use std::any::Any;
+use std::cmp::Ordering;
 use std::error::Error;
+use serde::Serialize;
+use serde_json::{Map, Value};
 
 /// Core trait for all CRUD operations in a data source
 pub trait DataSource<T>: Send + Sync {
@@ -18,10 +21,396 @@ pub trait DataSource<T>: Send + Sync {
     /// Gets an entity by its ID
     fn get_by_id(&self, id: &str, entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>>;
 
+    /// Gets the entities selected by a list query along with the number of matching entities.
+    /// The default implementation loads every entity and slices them in memory; it does not
+    /// know the primary key, so keyset queries are not supported.
+    fn get_page(&self, query: &ListQuery, entity_name_override: Option<&str>) -> Result<Page<T>, Box<dyn Error>> {
+        if query.order_by_key || query.after.is_some() {
+            return Err(Box::new(DataSourceError::QueryError(
+                "Cursor pagination is not supported by this datasource".to_string()
+            )));
+        }
+        if !query.sort.is_empty() {
+            return Err(Box::new(DataSourceError::QueryError(
+                "Sorting is not supported by this datasource".to_string()
+            )));
+        }
+        if !query.filters.is_empty() || query.expression.is_some() {
+            return Err(Box::new(DataSourceError::QueryError(
+                "Filtering is not supported by this datasource".to_string()
+            )));
+        }
+
+        let items = self.get_all(entity_name_override)?;
+        let total = query.with_total.then_some(items.len() as u64);
+        let items = items.into_iter()
+            .skip(query.offset as usize)
+            .take(query.limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect();
+        Ok(Page { items, total, last_key: None })
+    }
+
+    /// Counts the entities meeting the filters. The default implementation asks `get_page`
+    /// for the total of an empty page.
+    fn count(&self, filters: &[Filter], entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        let query = ListQuery {
+            limit: Some(0),
+            with_total: true,
+            filters: filters.to_vec(),
+            ..ListQuery::default()
+        };
+        Ok(self.get_page(&query, entity_name_override)?.total.unwrap_or(0))
+    }
+
+    /// Gets an entity by its ID, loading at least the given fields, or every field if empty.
+    /// The default implementation loads every field.
+    fn get_fields_by_id(&self, id: &str, _fields: &[String], entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>> {
+        self.get_by_id(id, entity_name_override)
+    }
+
+    /// Applies a partial document to every entity meeting the filters, returning how many
+    /// entities were updated
+    fn update_matching(&self, _filters: &[Filter], _changes: &Map<String, Value>, _entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        Err(Box::new(DataSourceError::QueryError(
+            "Bulk updates are not supported by this datasource".to_string()
+        )))
+    }
+
+    /// Gets the distinct values of a field among the entities meeting the filters, in ascending order,
+    /// along with the number of entities holding each one if `with_counts` is set. At most `limit`
+    /// values are returned. The default implementation loads the matching entities and groups them in memory.
+    fn distinct_values(&self, field: &str, filters: &[Filter], with_counts: bool, limit: u64, entity_name_override: Option<&str>) -> Result<Vec<DistinctValue>, Box<dyn Error>>
+    where
+        T: Serialize,
+    {
+        let query = ListQuery { filters: filters.to_vec(), ..ListQuery::default() };
+        let mut values: Vec<DistinctValue> = Vec::new();
+        for item in self.get_page(&query, entity_name_override)?.items {
+            let value = serde_json::to_value(item)?.get(field).cloned().unwrap_or(Value::Null);
+            match values.iter_mut().find(|distinct| distinct.value == value) {
+                Some(distinct) => distinct.count = distinct.count.map(|count| count + 1),
+                None => values.push(DistinctValue { value, count: with_counts.then_some(1) }),
+            }
+        }
+        values.sort_by(|a, b| compare_values(&a.value, &b.value));
+        values.truncate(limit as usize);
+        Ok(values)
+    }
+
+    /// Gets the connection pool statistics, if the datasource uses a pool
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+
+    /// Links an entity to a related one through a many-to-many relationship.
+    /// Returns false if they were already linked.
+    fn link_related(&self, _id: &str, relationship: &str, _related_id: &str, _entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        Err(Box::new(DataSourceError::QueryError(format!(
+            "Many-to-many relationship '{}' is not supported by this datasource", relationship
+        ))))
+    }
+
+    /// Removes the link between an entity and a related one through a many-to-many relationship.
+    /// Returns false if they were not linked.
+    fn unlink_related(&self, _id: &str, relationship: &str, _related_id: &str, _entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        Err(Box::new(DataSourceError::QueryError(format!(
+            "Many-to-many relationship '{}' is not supported by this datasource", relationship
+        ))))
+    }
+
+    /// Applies the writes of a batch in a single transaction, so either all of them or none are applied.
+    /// Returns the written entities in order, or None for deletions. Deleting a missing entity fails the batch.
+    fn write_batch(&self, _writes: Vec<BatchWrite<T>>) -> Result<Vec<Option<T>>, BatchFailure> {
+        Err(BatchFailure {
+            index: None,
+            error: Box::new(DataSourceError::QueryError("Transactions are not supported by this datasource".to_string())),
+        })
+    }
+
     /// Method to clone a trait object
     fn box_clone(&self) -> Box<dyn DataSource<T>>;
 }
 
+/// A write of a batch, on the entity with the given name
+#[derive(Debug, Clone)]
+pub enum BatchWrite<T> {
+    Create { entity: String, item: T },
+    /// Replaces the entity with the given ID
+    Update { entity: String, id: String, item: T },
+    Delete { entity: String, id: String },
+}
+
+impl<T> BatchWrite<T> {
+    /// Name of the entity written
+    pub fn entity(&self) -> &str {
+        match self {
+            BatchWrite::Create { entity, .. } | BatchWrite::Update { entity, .. } | BatchWrite::Delete { entity, .. } => entity,
+        }
+    }
+}
+
+/// Error of a batch, whose writes were all rolled back
+#[derive(Debug)]
+pub struct BatchFailure {
+    /// Position of the failed write, or None when the transaction itself failed
+    pub index: Option<usize>,
+    pub error: Box<dyn Error>,
+}
+
+/// A distinct value of a field, with the number of entities holding it when it was counted
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DistinctValue {
+    pub value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+}
+
+/// Options of a list query, applied by the datasource
+#[derive(Debug, Clone, Default)]
+pub struct ListQuery {
+    /// Number of entities to skip
+    pub offset: u64,
+    /// Maximum number of entities to return, or every entity if None
+    pub limit: Option<u64>,
+    /// Whether to order the entities by primary key, as keyset pagination requires
+    pub order_by_key: bool,
+    /// Primary key after which the entities start, in primary key order (keyset pagination)
+    pub after: Option<String>,
+    /// Whether to count the entities matching the query
+    pub with_total: bool,
+    /// Fields to order the entities by, in priority order
+    pub sort: Vec<SortField>,
+    /// Conditions every listed entity must meet
+    pub filters: Vec<Filter>,
+    /// Filter expression every listed entity must also meet
+    pub expression: Option<FilterExpression>,
+    /// Fields to load, or every field if empty. Datasources may load more, like the primary key.
+    pub fields: Vec<String>,
+}
+
+/// Ordering of a list query on one entity field
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortField {
+    pub field: String,
+    pub descending: bool,
+}
+
+/// Comparison applied by a list filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOperator {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// SQL LIKE pattern, with `%` matching any text and `_` any character, ignoring case
+    Like,
+    /// Missing or null value; the operand is ignored
+    IsNull,
+    /// Present, non-null value; the operand is ignored
+    NotNull,
+}
+
+impl FilterOperator {
+    /// Operators selected by a query parameter suffix, like `age_gte`
+    pub const SUFFIXED: [FilterOperator; 6] = [
+        FilterOperator::Ne,
+        FilterOperator::Gt,
+        FilterOperator::Gte,
+        FilterOperator::Lt,
+        FilterOperator::Lte,
+        FilterOperator::Like,
+    ];
+
+    /// Query parameter suffix of the operator; equality uses the bare field name.
+    /// Null checks are not read from query parameters.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            FilterOperator::Eq => "",
+            FilterOperator::Ne => "_ne",
+            FilterOperator::Gt => "_gt",
+            FilterOperator::Gte => "_gte",
+            FilterOperator::Lt => "_lt",
+            FilterOperator::Lte => "_lte",
+            FilterOperator::Like => "_like",
+            FilterOperator::IsNull => "_is_null",
+            FilterOperator::NotNull => "_not_null",
+        }
+    }
+
+    /// SQL operator of the comparison. Null checks take no operand.
+    pub fn sql(&self) -> &'static str {
+        match self {
+            FilterOperator::Eq => "=",
+            FilterOperator::Ne => "<>",
+            FilterOperator::Gt => ">",
+            FilterOperator::Gte => ">=",
+            FilterOperator::Lt => "<",
+            FilterOperator::Lte => "<=",
+            FilterOperator::Like => "LIKE",
+            FilterOperator::IsNull => "IS NULL",
+            FilterOperator::NotNull => "IS NOT NULL",
+        }
+    }
+
+    /// Whether the comparison takes no operand
+    pub fn is_null_check(&self) -> bool {
+        matches!(self, FilterOperator::IsNull | FilterOperator::NotNull)
+    }
+}
+
+/// Condition of a list query on one entity field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub field: String,
+    pub operator: FilterOperator,
+    /// Operand, typed like the field
+    pub value: Value,
+}
+
+impl Filter {
+    /// Whether an entity object meets the condition. As in SQL, missing and null values never do,
+    /// except for the `IsNull` check.
+    pub fn matches(&self, object: &Value) -> bool {
+        let value = match object.get(&self.field) {
+            None | Some(Value::Null) => return self.operator == FilterOperator::IsNull,
+            Some(value) => value,
+        };
+
+        let ordering = compare_values(value, &self.value);
+        match self.operator {
+            FilterOperator::Eq => ordering.is_eq(),
+            FilterOperator::Ne => ordering.is_ne(),
+            FilterOperator::Gt => ordering.is_gt(),
+            FilterOperator::Gte => ordering.is_ge(),
+            FilterOperator::Lt => ordering.is_lt(),
+            FilterOperator::Lte => ordering.is_le(),
+            FilterOperator::Like => {
+                let text = match value {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                like_matches(&self.value.as_str().unwrap_or_default().to_lowercase(), &text.to_lowercase())
+            }
+            FilterOperator::IsNull => false,
+            FilterOperator::NotNull => true,
+        }
+    }
+}
+
+/// Boolean combination of filters, as written in a filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpression {
+    Filter(Filter),
+    And(Vec<FilterExpression>),
+    Or(Vec<FilterExpression>),
+    Not(Box<FilterExpression>),
+}
+
+impl FilterExpression {
+    /// Whether an entity object meets the expression
+    pub fn matches(&self, object: &Value) -> bool {
+        match self {
+            FilterExpression::Filter(filter) => filter.matches(object),
+            FilterExpression::And(expressions) => expressions.iter().all(|expression| expression.matches(object)),
+            FilterExpression::Or(expressions) => expressions.iter().any(|expression| expression.matches(object)),
+            FilterExpression::Not(expression) => !expression.matches(object),
+        }
+    }
+}
+
+/// Matches a text against a LIKE pattern
+fn like_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // matched[j] tells whether the pattern read so far matches the first j characters
+    let mut matched = vec![false; text.len() + 1];
+    matched[0] = true;
+    for symbol in pattern {
+        let previous = matched.clone();
+        match symbol {
+            '%' => {
+                for j in 1..=text.len() {
+                    matched[j] = matched[j] || matched[j - 1];
+                }
+            }
+            _ => {
+                matched[0] = false;
+                for j in 1..=text.len() {
+                    matched[j] = previous[j - 1] && (symbol == '_' || symbol == text[j - 1]);
+                }
+            }
+        }
+    }
+    matched[text.len()]
+}
+
+/// Orders JSON values of the same field: nulls first, then booleans, numbers and strings
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Orders two entity objects by the sort fields of a list query
+pub fn compare_by_fields(a: &Value, b: &Value, sort: &[SortField]) -> Ordering {
+    sort.iter()
+        .map(|sort_field| {
+            let ordering = compare_values(
+                a.get(&sort_field.field).unwrap_or(&Value::Null),
+                b.get(&sort_field.field).unwrap_or(&Value::Null),
+            );
+            if sort_field.descending { ordering.reverse() } else { ordering }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Entities selected by a list query
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Number of entities matching the query ignoring offset and limit, if requested
+    pub total: Option<u64>,
+    /// Primary key of the last entity, if the datasource knows it
+    pub last_key: Option<String>,
+}
+
+/// Snapshot of the connection pool statistics of a datasource
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PoolStats {
+    /// Number of connections currently open (idle and in use).
+    pub size: u32,
+    /// Number of idle connections.
+    pub idle: usize,
+    /// Maximum number of connections allowed in the pool.
+    pub max_connections: u32,
+    /// Total number of connection acquisitions.
+    pub acquire_count: u64,
+    /// Number of failed connection acquisitions.
+    pub acquire_errors: u64,
+    /// Average time spent waiting for a connection, in milliseconds.
+    pub average_wait_ms: f64,
+    /// Longest time spent waiting for a connection, in milliseconds.
+    pub max_wait_ms: f64,
+}
+
 /// Implementation for Box<dyn DataSource<T>> to allow direct method use
 impl<T> DataSource<T> for Box<dyn DataSource<T>> {
     fn get_all(&self, entity_name_override: Option<&str>) -> Result<Vec<T>, Box<dyn Error>> {
@@ -44,6 +433,45 @@ impl<T> DataSource<T> for Box<dyn DataSource<T>> {
         (**self).get_by_id(id, entity_name_override)
     }
 
+    fn get_page(&self, query: &ListQuery, entity_name_override: Option<&str>) -> Result<Page<T>, Box<dyn Error>> {
+        (**self).get_page(query, entity_name_override)
+    }
+
+    fn count(&self, filters: &[Filter], entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        (**self).count(filters, entity_name_override)
+    }
+
+    fn get_fields_by_id(&self, id: &str, fields: &[String], entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>> {
+        (**self).get_fields_by_id(id, fields, entity_name_override)
+    }
+
+    fn update_matching(&self, filters: &[Filter], changes: &Map<String, Value>, entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        (**self).update_matching(filters, changes, entity_name_override)
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        (**self).pool_stats()
+    }
+
+    fn link_related(&self, id: &str, relationship: &str, related_id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        (**self).link_related(id, relationship, related_id, entity_name_override)
+    }
+
+    fn unlink_related(&self, id: &str, relationship: &str, related_id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        (**self).unlink_related(id, relationship, related_id, entity_name_override)
+    }
+
+    fn distinct_values(&self, field: &str, filters: &[Filter], with_counts: bool, limit: u64, entity_name_override: Option<&str>) -> Result<Vec<DistinctValue>, Box<dyn Error>>
+    where
+        T: Serialize,
+    {
+        (**self).distinct_values(field, filters, with_counts, limit, entity_name_override)
+    }
+
+    fn write_batch(&self, writes: Vec<BatchWrite<T>>) -> Result<Vec<Option<T>>, BatchFailure> {
+        (**self).write_batch(writes)
+    }
+
     fn box_clone(&self) -> Box<dyn DataSource<T>> {
         (**self).box_clone()
     }
@@ -78,6 +506,8 @@ pub enum DataSourceError {
     ValidationError(String),
     MappingError(String),
     SerializationError(String),
+    /// A write would duplicate the value of a unique field or key
+    Conflict(String),
 }
 
 impl std::fmt::Display for DataSourceError {
@@ -89,6 +519,7 @@ impl std::fmt::Display for DataSourceError {
             DataSourceError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             DataSourceError::MappingError(msg) => write!(f, "Mapping error: {}", msg),
             DataSourceError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
+            DataSourceError::Conflict(msg) => write!(f, "Conflict: {}", msg),
         }
     }
 }
@@ -0,0 +1,334 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use serde_json::{Map, Value};
+use tokio::runtime::Runtime;
+use crate::api::common::api_entity::ApiEntity;
+use crate::config::specific::database_config::DatabaseConfig;
+use crate::config::specific::entity_config::{DataType, Entity};
+use crate::data::datasource::base::{DataSource, DatabaseCommon, DataSourceError};
+use crate::data::datasource::document::base::{CollectionMapping, DocumentSource};
+
+/// Mapping of an entity to a SurrealDB table
+#[derive(Clone)]
+pub struct SurrealTableMapping {
+    /// Table and record ID field of the entity
+    pub collection: CollectionMapping,
+    /// Whether record IDs are integers (`users:1`) instead of strings (`users:⟨abc⟩`)
+    pub integer_id: bool,
+    /// Entity field name to table field name, for renamed fields
+    pub field_columns: HashMap<String, String>,
+}
+
+impl SurrealTableMapping {
+    /// Builds the mapping of an entity. The record ID is the `id` field when present,
+    /// otherwise the first field.
+    pub fn from_entity(entity: &Entity) -> Self {
+        let id_field = entity.fields.iter()
+            .find(|f| f.name == "id")
+            .or_else(|| entity.fields.first());
+
+        SurrealTableMapping {
+            collection: CollectionMapping {
+                collection_name: entity.table_name.clone().unwrap_or_else(|| entity.name.clone()),
+                id_field: id_field.map(|f| f.name.clone()).unwrap_or_else(|| "id".to_string()),
+                indexes: Vec::new(),
+            },
+            integer_id: id_field.map(|f| matches!(f.data_type, DataType::Integer)).unwrap_or(false),
+            field_columns: entity.fields.iter()
+                .filter_map(|f| f.column_name.clone().map(|column| (f.name.clone(), column)))
+                .filter(|(field, column)| field != column)
+                .collect(),
+        }
+    }
+
+    /// SurrealQL literal of the table name
+    fn table_literal(&self) -> String {
+        Value::String(self.collection.collection_name.clone()).to_string()
+    }
+
+    /// SurrealQL expression selecting the record with the given ID
+    fn thing(&self, id: &str) -> String {
+        let id_literal = match id.parse::<i64>() {
+            Ok(number) if self.integer_id => number.to_string(),
+            _ => Value::String(id.to_string()).to_string(),
+        };
+        format!("type::thing({}, {})", self.table_literal(), id_literal)
+    }
+
+    /// Converts a SurrealDB record into an entity object
+    fn record_to_object(&self, record: Value) -> Map<String, Value> {
+        let mut record = match record {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+
+        let mut object = Map::new();
+        if let Some(id) = record.remove("id") {
+            object.insert(self.collection.id_field.clone(), self.record_id_to_value(&id));
+        }
+        for (field, column) in &self.field_columns {
+            if let Some(value) = record.remove(column) {
+                object.insert(field.clone(), value);
+            }
+        }
+        object.extend(record);
+        object
+    }
+
+    /// Converts a record ID (`users:1`, `users:⟨a-b⟩` or `{tb, id}`) into the primary key value
+    fn record_id_to_value(&self, id: &Value) -> Value {
+        let key = match id {
+            Value::String(thing) => {
+                let key = thing.split_once(':').map(|(_, key)| key).unwrap_or(thing);
+                key.trim_start_matches(['⟨', '`']).trim_end_matches(['⟩', '`']).to_string()
+            }
+            Value::Object(thing) => match thing.get("id") {
+                Some(Value::String(key)) => key.clone(),
+                Some(other) => other.to_string(),
+                None => id.to_string(),
+            },
+            other => other.to_string(),
+        };
+
+        match key.parse::<i64>() {
+            Ok(number) if self.integer_id => Value::from(number),
+            _ => Value::String(key),
+        }
+    }
+
+    /// Converts an entity object into the content stored in SurrealDB,
+    /// without the primary key (it is part of the record ID)
+    fn object_to_content(&self, mut object: Map<String, Value>) -> Map<String, Value> {
+        object.remove(&self.collection.id_field);
+        object.into_iter()
+            .map(|(field, value)| (self.field_columns.get(&field).cloned().unwrap_or(field), value))
+            .collect()
+    }
+}
+
+/// SurrealDB datasource using the HTTP `/sql` endpoint.
+///
+/// `database_name` selects the namespace and database as `namespace/database`;
+/// a plain name is used for both. Entity primary keys are stored as SurrealDB record IDs.
+pub struct SurrealDbDatasource {
+    pub config: DatabaseConfig,
+    client: reqwest::Client,
+    entity_mappings: HashMap<String, SurrealTableMapping>,
+    runtime: Arc<Runtime>,
+}
+
+impl SurrealDbDatasource {
+    /// Creates a new SurrealDbDatasource with the provided configuration
+    pub fn new(config: &DatabaseConfig) -> Result<Self, Box<dyn Error>> {
+        let timeout = std::time::Duration::from_secs(config.timeout_seconds.unwrap_or(10) as u64);
+        let client = reqwest::Client::builder().timeout(timeout).build()?;
+
+        Ok(SurrealDbDatasource {
+            config: config.with_secrets()?,
+            client,
+            entity_mappings: HashMap::new(),
+            runtime: Arc::new(Runtime::new()?),
+        })
+    }
+
+    fn normalize_entity_name(name: &str) -> String {
+        name.to_lowercase().trim().to_string()
+    }
+
+    /// Registers the table mappings of the entities and verifies the connection
+    pub fn configure_entity_mappings(&mut self, entities: &[Entity]) -> Result<(), Box<dyn Error>> {
+        for entity in entities {
+            self.entity_mappings.insert(Self::normalize_entity_name(&entity.name), SurrealTableMapping::from_entity(entity));
+        }
+        self.connect()
+    }
+
+    fn find_entity_mapping(&self, entity_name: &str) -> Result<&SurrealTableMapping, Box<dyn Error>> {
+        self.entity_mappings.get(&Self::normalize_entity_name(entity_name)).ok_or_else(|| {
+            Box::new(DataSourceError::NotFound(format!("No SurrealDB table mapping for entity {}", entity_name))) as Box<dyn Error>
+        })
+    }
+
+    fn entity_name<T: ApiEntity>(entity_name_override: Option<&str>) -> String {
+        entity_name_override.map(|s| s.to_string()).unwrap_or_else(T::entity_name)
+    }
+
+    /// Namespace and database selected by `database_name`
+    fn namespace_and_database(&self) -> (String, String) {
+        match self.config.database_name.split_once('/') {
+            Some((namespace, database)) => (namespace.to_string(), database.to_string()),
+            None => (self.config.database_name.clone(), self.config.database_name.clone()),
+        }
+    }
+
+    /// Runs a SurrealQL statement and returns the records of its result
+    fn query(&self, statement: &str) -> Result<Vec<Value>, Box<dyn Error>> {
+        let (namespace, database) = self.namespace_and_database();
+        let mut request = self.client.post(format!("{}/sql", self.config.make_url()))
+            .header("Accept", "application/json")
+            // Version 1 and version 2 header names
+            .header("NS", &namespace)
+            .header("DB", &database)
+            .header("surreal-ns", &namespace)
+            .header("surreal-db", &database)
+            .body(statement.to_string());
+        if !self.config.username.is_empty() {
+            request = request.basic_auth(&self.config.username, Some(&self.config.password));
+        }
+
+        let results: Value = self.runtime.block_on(async {
+            let response = request.send().await.map_err(|e| {
+                DataSourceError::ConnectionError(format!("Error connecting to SurrealDB: {}", e))
+            })?;
+            let status = response.status();
+            let body = response.text().await.map_err(|e| {
+                DataSourceError::QueryError(format!("Error reading SurrealDB response: {}", e))
+            })?;
+            if !status.is_success() {
+                return Err(DataSourceError::QueryError(format!("SurrealDB responded with {}: {}", status, body)));
+            }
+            serde_json::from_str(&body).map_err(|e| {
+                DataSourceError::SerializationError(format!("Invalid SurrealDB response: {}", e))
+            })
+        })?;
+
+        // The response holds one result per statement
+        let result = results.as_array().and_then(|r| r.last()).cloned().unwrap_or(Value::Null);
+        if result.get("status").and_then(Value::as_str) != Some("OK") {
+            let detail = result.get("result").or_else(|| result.get("detail")).cloned().unwrap_or(Value::Null);
+            return Err(Box::new(DataSourceError::QueryError(format!("SurrealDB query failed: {}", detail))));
+        }
+
+        Ok(match result.get("result") {
+            Some(Value::Array(records)) => records.clone(),
+            Some(Value::Null) | None => Vec::new(),
+            Some(record) => vec![record.clone()],
+        })
+    }
+
+    fn to_entity<T: ApiEntity>(mapping: &SurrealTableMapping, record: Value) -> Result<T, Box<dyn Error>> {
+        serde_json::from_value(Value::Object(mapping.record_to_object(record))).map_err(|e| {
+            Box::new(DataSourceError::SerializationError(format!("Error deserializing entity: {}", e))) as Box<dyn Error>
+        })
+    }
+
+    fn to_object<T: ApiEntity>(item: &T) -> Result<Map<String, Value>, Box<dyn Error>> {
+        match serde_json::to_value(item)? {
+            Value::Object(map) => Ok(map),
+            _ => Err(Box::new(DataSourceError::SerializationError(
+                "The entity could not be serialized as a JSON object".to_string()
+            ))),
+        }
+    }
+
+    fn first_entity<T: ApiEntity>(mapping: &SurrealTableMapping, records: Vec<Value>) -> Result<Option<T>, Box<dyn Error>> {
+        records.into_iter().next().map(|record| Self::to_entity(mapping, record)).transpose()
+    }
+}
+
+impl<T> DataSource<T> for SurrealDbDatasource
+where
+    T: ApiEntity,
+{
+    fn get_all(&self, entity_name_override: Option<&str>) -> Result<Vec<T>, Box<dyn Error>> {
+        let mapping = self.find_entity_mapping(&Self::entity_name::<T>(entity_name_override))?;
+        let records = self.query(&format!("SELECT * FROM type::table({});", mapping.table_literal()))?;
+        records.into_iter().map(|record| Self::to_entity(mapping, record)).collect()
+    }
+
+    fn create(&self, item: T, entity_name_override: Option<&str>) -> Result<T, Box<dyn Error>> {
+        let mapping = self.find_entity_mapping(&Self::entity_name::<T>(entity_name_override))?;
+        let object = Self::to_object(&item)?;
+
+        let target = match object.get(&mapping.collection.id_field) {
+            None | Some(Value::Null) => format!("type::table({})", mapping.table_literal()),
+            Some(Value::String(id)) => mapping.thing(id),
+            Some(id) => mapping.thing(&id.to_string()),
+        };
+        let content = Value::Object(mapping.object_to_content(object));
+
+        let records = self.query(&format!("CREATE {} CONTENT {};", target, content))?;
+        Self::first_entity(mapping, records)?.ok_or_else(|| {
+            Box::new(DataSourceError::QueryError("SurrealDB did not return the created record".to_string())) as Box<dyn Error>
+        })
+    }
+
+    fn update(&self, id: &str, item: T, entity_name_override: Option<&str>) -> Result<T, Box<dyn Error>> {
+        let mapping = self.find_entity_mapping(&Self::entity_name::<T>(entity_name_override))?;
+        let content = Value::Object(mapping.object_to_content(Self::to_object(&item)?));
+
+        // UPDATE creates missing records in some SurrealDB versions, so only touch existing ones
+        let statement = format!(
+            "IF (SELECT id FROM {thing}) {{ UPDATE {thing} CONTENT {content} }} ELSE {{ [] }};",
+            thing = mapping.thing(id), content = content
+        );
+        Self::first_entity(mapping, self.query(&statement)?)?.ok_or_else(|| {
+            Box::new(DataSourceError::NotFound(format!("Item with ID {} not found", id))) as Box<dyn Error>
+        })
+    }
+
+    fn delete(&self, id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let mapping = self.find_entity_mapping(&Self::entity_name::<T>(entity_name_override))?;
+        let deleted = self.query(&format!("DELETE {} RETURN BEFORE;", mapping.thing(id)))?;
+        Ok(!deleted.is_empty())
+    }
+
+    fn get_by_id(&self, id: &str, entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>> {
+        let mapping = self.find_entity_mapping(&Self::entity_name::<T>(entity_name_override))?;
+        let records = self.query(&format!("SELECT * FROM {};", mapping.thing(id)))?;
+        Self::first_entity(mapping, records)
+    }
+
+    fn box_clone(&self) -> Box<dyn DataSource<T>> {
+        Box::new(self.clone())
+    }
+}
+
+impl DatabaseCommon for SurrealDbDatasource {
+    /// Gets the HTTP client used to reach SurrealDB
+    fn get_connection(&self) -> Result<Box<dyn Any>, Box<dyn Error>> {
+        Ok(Box::new(self.client.clone()))
+    }
+
+    /// Verifies that SurrealDB is reachable and the credentials are valid
+    fn connect(&self) -> Result<(), Box<dyn Error>> {
+        self.query("INFO FOR DB;").map(|_| ())
+    }
+
+    /// HTTP connections are released by the client, so this is a no-op
+    fn disconnect(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+impl<T: ApiEntity> DocumentSource<T> for SurrealDbDatasource {
+    fn get_db_structure(&self) -> &dyn Any {
+        &self.entity_mappings
+    }
+
+    /// Executes a SurrealQL query. Parameters replace `$1`, `$2`... as string literals.
+    fn execute_query(&self, query: &str, params: &[&str]) -> Result<Vec<T>, Box<dyn Error>> {
+        let mut statement = query.to_string();
+        // Replace from the last parameter so `$1` does not clobber `$10`
+        for (index, param) in params.iter().enumerate().rev() {
+            statement = statement.replace(&format!("${}", index + 1), &Value::String(param.to_string()).to_string());
+        }
+
+        self.query(&statement)?.into_iter()
+            .map(|record| serde_json::from_value(record).map_err(|e| Box::new(e) as Box<dyn Error>))
+            .collect()
+    }
+}
+
+impl Clone for SurrealDbDatasource {
+    fn clone(&self) -> Self {
+        SurrealDbDatasource {
+            config: self.config.clone(),
+            client: self.client.clone(),
+            entity_mappings: self.entity_mappings.clone(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use serde_json::{json, Value};
+use crate::config::specific::entity_config::{DataType, Entity, Field};
+
+const FIRST_NAMES: [&str; 10] = [
+    "Alice", "Bruno", "Carmen", "David", "Elena", "Felix", "Grace", "Hugo", "Irene", "Jorge",
+];
+const LAST_NAMES: [&str; 10] = [
+    "Garcia", "Smith", "Rossi", "Muller", "Dubois", "Silva", "Novak", "Kim", "Ortega", "Brown",
+];
+const WORDS: [&str; 12] = [
+    "alpha", "bravo", "delta", "echo", "orbit", "pixel", "quartz", "river", "solar", "tango", "vector", "zephyr",
+];
+
+/// Deterministic pseudo-random generator, so mock data is stable between runs
+struct FakeRng(u64);
+
+impl FakeRng {
+    fn next(&mut self) -> u64 {
+        // Linear congruential generator (Knuth MMIX constants)
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, max: u64) -> u64 {
+        self.next() % max.max(1)
+    }
+}
+
+/// Generates `count` rows of fake data for an entity, based on field types and names.
+/// The `id` field (or the first integer field) receives sequential values starting at 1.
+pub fn generate_rows(entity: &Entity, count: usize) -> Vec<Value> {
+    let seed = entity.name.bytes().fold(17u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
+    let mut rng = FakeRng(seed);
+    let key_field = entity.fields.iter()
+        .find(|f| f.name == "id")
+        .or_else(|| entity.fields.first())
+        .map(|f| f.name.clone());
+
+    (1..=count)
+        .map(|index| {
+            let mut row = serde_json::Map::new();
+            for field in entity.fields.iter().filter(|field| field.is_stored()) {
+                let value = if Some(&field.name) == key_field.as_ref() {
+                    match field.data_type {
+                        DataType::Integer => json!(index),
+                        _ => json!(index.to_string()),
+                    }
+                } else if entity.soft_delete_field.as_ref() == Some(&field.name) {
+                    // Mock rows start out not deleted
+                    Value::Null
+                } else {
+                    fake_value(field, index, &mut rng)
+                };
+                row.insert(field.name.clone(), value);
+            }
+            Value::Object(row)
+        })
+        .collect()
+}
+
+/// Generates a plausible value for a field, using its name as a hint for strings
+fn fake_value(field: &Field, index: usize, rng: &mut FakeRng) -> Value {
+    let name = field.name.to_lowercase();
+
+    match field.data_type {
+        DataType::String => {
+            let first = FIRST_NAMES[rng.below(FIRST_NAMES.len() as u64) as usize];
+            let last = LAST_NAMES[rng.below(LAST_NAMES.len() as u64) as usize];
+            if name.contains("email") {
+                json!(format!("{}.{}{}@example.com", first.to_lowercase(), last.to_lowercase(), index))
+            } else if name.contains("first") {
+                json!(first)
+            } else if name.contains("last") || name.contains("surname") {
+                json!(last)
+            } else if name.contains("name") {
+                json!(format!("{} {}", first, last))
+            } else if name.contains("phone") {
+                json!(format!("+34 6{:08}", rng.below(100_000_000)))
+            } else if name.contains("url") {
+                json!(format!("https://example.com/{}/{}", name, index))
+            } else {
+                let word = WORDS[rng.below(WORDS.len() as u64) as usize];
+                json!(format!("{} {}", word, index))
+            }
+        }
+        DataType::Integer => {
+            if name.ends_with("_id") {
+                json!(rng.below(10) + 1)
+            } else if name.contains("age") {
+                json!(rng.below(60) + 18)
+            } else {
+                json!(rng.below(1000))
+            }
+        }
+        DataType::Float => json!((rng.below(100_000) as f64) / 100.0),
+        DataType::Boolean => json!(rng.below(2) == 1),
+        DataType::Date => json!(format!("2024-{:02}-{:02}", rng.below(12) + 1, rng.below(28) + 1)),
+        DataType::DateTime => json!(format!(
+            "2024-{:02}-{:02}T{:02}:{:02}:00Z",
+            rng.below(12) + 1, rng.below(28) + 1, rng.below(24), rng.below(60)
+        )),
+        DataType::Binary => json!(""),
+        DataType::JSON => json!({ "index": index }),
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+use serde_json::{Map, Value};
+use crate::api::common::api_entity::ApiEntity;
+use crate::config::specific::entity_config::{DataType, Entity, RelationshipType};
+use crate::data::datasource::base::{BatchFailure, BatchWrite, DataSource, DataSourceError, Filter, ListQuery, Page, compare_by_fields};
+
+/// Rows and key settings of a single entity stored in memory
+#[derive(Clone)]
+struct MemoryCollection {
+    primary_key: String,
+    integer_key: bool,
+    rows: Vec<Value>,
+    /// Links of each many-to-many relationship (lowercase name) as (id, related id) pairs
+    links: HashMap<String, HashSet<(String, String)>>,
+}
+
+impl MemoryCollection {
+    /// Stores a new row, generating its key when missing
+    fn insert(&mut self, mut object: Map<String, Value>) -> Result<Value, Box<dyn Error>> {
+        match object.get(&self.primary_key) {
+            None | Some(Value::Null) => {
+                let key = InMemoryDatasource::next_key(self);
+                object.insert(self.primary_key.clone(), key);
+            }
+            Some(key) => {
+                let id = match key {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                if self.rows.iter().any(|row| InMemoryDatasource::key_matches(row.get(&self.primary_key), &id)) {
+                    return Err(Box::new(DataSourceError::Conflict(format!(
+                        "An item with {} {} already exists", self.primary_key, id
+                    ))));
+                }
+            }
+        }
+
+        let value = Value::Object(object);
+        self.rows.push(value.clone());
+        Ok(value)
+    }
+
+    /// Replaces the row with the given key
+    fn replace(&mut self, id: &str, mut object: Map<String, Value>) -> Result<Value, Box<dyn Error>> {
+        let primary_key = self.primary_key.clone();
+        let row = self.rows.iter_mut()
+            .find(|row| InMemoryDatasource::key_matches(row.get(&primary_key), id))
+            .ok_or_else(|| DataSourceError::NotFound(format!("Item with ID {} not found", id)))?;
+
+        // Keep the stored key, the identifier comes from the path
+        if let Some(key) = row.get(&primary_key) {
+            object.insert(primary_key, key.clone());
+        }
+        *row = Value::Object(object);
+        Ok(row.clone())
+    }
+
+    /// Removes the row with the given key, returning false if there was none
+    fn remove(&mut self, id: &str) -> bool {
+        let before = self.rows.len();
+        let primary_key = self.primary_key.clone();
+        self.rows.retain(|row| !InMemoryDatasource::key_matches(row.get(&primary_key), id));
+        self.rows.len() < before
+    }
+}
+
+/// In-memory datasource storing entities as JSON objects.
+/// Clones share the same storage, so every handler sees the same data.
+/// Useful for mock servers and tests that must run without a database.
+#[derive(Clone, Default)]
+pub struct InMemoryDatasource {
+    collections: Arc<RwLock<HashMap<String, MemoryCollection>>>,
+}
+
+impl InMemoryDatasource {
+    /// Creates an empty in-memory datasource
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Normalizes an entity name so lookups are case insensitive
+    fn normalize_entity_name(name: &str) -> String {
+        name.to_lowercase().trim().to_string()
+    }
+
+    /// Registers the entities served by this datasource.
+    /// The primary key is the `id` field when present, otherwise the first field.
+    pub fn configure_entities(&self, entities: &[Entity]) {
+        let mut collections = self.collections.write().unwrap();
+        for entity in entities {
+            let key_field = entity.key_field();
+            let primary_key = key_field.map(|f| f.name.clone()).unwrap_or_else(|| "id".to_string());
+            let integer_key = key_field.map(|f| matches!(f.data_type, DataType::Integer)).unwrap_or(true);
+
+            let links = entity.relationships.iter()
+                .filter(|r| matches!(r.type_, RelationshipType::ManyToMany))
+                .map(|r| (r.name.to_lowercase(), HashSet::new()))
+                .collect();
+
+            collections.entry(Self::normalize_entity_name(&entity.name)).or_insert(MemoryCollection {
+                primary_key,
+                integer_key,
+                rows: Vec::new(),
+                links,
+            });
+        }
+    }
+
+    /// Replaces the rows stored for an entity
+    pub fn seed(&self, entity_name: &str, rows: Vec<Value>) -> Result<(), Box<dyn Error>> {
+        let mut collections = self.collections.write().unwrap();
+        let collection = collections.get_mut(&Self::normalize_entity_name(entity_name))
+            .ok_or_else(|| DataSourceError::NotFound(format!("Entity {} is not registered", entity_name)))?;
+        collection.rows = rows;
+        Ok(())
+    }
+
+    /// Resolves the entity name from the override or the type name
+    fn entity_name<T: ApiEntity>(entity_name_override: Option<&str>) -> String {
+        Self::normalize_entity_name(&entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name()))
+    }
+
+    /// Compares a stored key with the identifier received in the request
+    fn key_matches(value: Option<&Value>, id: &str) -> bool {
+        match value {
+            Some(Value::String(s)) => s == id,
+            Some(Value::Number(n)) => n.to_string() == id,
+            _ => false,
+        }
+    }
+
+    /// Generates the next primary key value for a collection
+    fn next_key(collection: &MemoryCollection) -> Value {
+        let next = collection.rows.iter()
+            .filter_map(|row| match row.get(&collection.primary_key) {
+                Some(Value::Number(n)) => n.as_i64(),
+                Some(Value::String(s)) => s.parse::<i64>().ok(),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0) + 1;
+
+        if collection.integer_key {
+            Value::from(next)
+        } else {
+            Value::String(next.to_string())
+        }
+    }
+
+    fn to_entity<T: ApiEntity>(value: Value) -> Result<T, Box<dyn Error>> {
+        serde_json::from_value(value).map_err(|e| {
+            Box::new(DataSourceError::SerializationError(format!("Error deserializing entity: {}", e))) as Box<dyn Error>
+        })
+    }
+
+    /// Runs a change on the links of a many-to-many relationship of an entity
+    fn with_links<R>(
+        &self,
+        entity_name: &str,
+        relationship: &str,
+        change: impl FnOnce(&mut HashSet<(String, String)>) -> R,
+    ) -> Result<R, Box<dyn Error>> {
+        let mut collections = self.collections.write().unwrap();
+        let collection = collections.get_mut(entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No collection for entity {}", entity_name)))?;
+        let links = collection.links.get_mut(&relationship.to_lowercase()).ok_or_else(|| {
+            DataSourceError::ValidationError(format!("Relationship {} is not a many-to-many relationship", relationship))
+        })?;
+        Ok(change(links))
+    }
+
+    /// Primary key of a stored row, as used in identifiers and cursors
+    fn row_key(collection: &MemoryCollection, row: &Value) -> Option<String> {
+        match row.get(&collection.primary_key)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Null => None,
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Orders keys numerically for integer keys and lexically otherwise
+    fn key_order(collection: &MemoryCollection, key: &str) -> (i64, String) {
+        if collection.integer_key {
+            (key.parse().unwrap_or(i64::MIN), String::new())
+        } else {
+            (0, key.to_string())
+        }
+    }
+
+    fn to_object<T: ApiEntity>(item: &T) -> Result<serde_json::Map<String, Value>, Box<dyn Error>> {
+        match serde_json::to_value(item)? {
+            Value::Object(map) => Ok(map),
+            _ => Err(Box::new(DataSourceError::SerializationError(
+                "The entity could not be serialized as a JSON object".to_string()
+            ))),
+        }
+    }
+}
+
+impl<T> DataSource<T> for InMemoryDatasource
+where
+    T: ApiEntity,
+{
+    fn get_all(&self, entity_name_override: Option<&str>) -> Result<Vec<T>, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        let collections = self.collections.read().unwrap();
+        let collection = collections.get(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No collection for entity {}", entity_name)))?;
+
+        collection.rows.iter().cloned().map(Self::to_entity).collect()
+    }
+
+    fn get_page(&self, query: &ListQuery, entity_name_override: Option<&str>) -> Result<Page<T>, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        let collections = self.collections.read().unwrap();
+        let collection = collections.get(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No collection for entity {}", entity_name)))?;
+
+        let mut rows: Vec<(Option<String>, &Value)> = collection.rows.iter()
+            .filter(|row| query.filters.iter().all(|filter| filter.matches(row)))
+            .filter(|row| query.expression.as_ref().is_none_or(|expression| expression.matches(row)))
+            .map(|row| (Self::row_key(collection, row), row))
+            .collect();
+        if query.order_by_key || query.after.is_some() {
+            rows.sort_by_cached_key(|(key, _)| key.as_deref().map(|key| Self::key_order(collection, key)));
+        }
+        // The sort is stable, so the key order above only breaks ties between the sort fields
+        if !query.sort.is_empty() {
+            rows.sort_by(|(_, a), (_, b)| compare_by_fields(a, b, &query.sort));
+        }
+        if let Some(after) = &query.after {
+            let after = Self::key_order(collection, after);
+            rows.retain(|(key, _)| key.as_deref().is_some_and(|key| Self::key_order(collection, key) > after));
+        }
+
+        let total = query.with_total.then_some(rows.len() as u64);
+        let rows: Vec<(Option<String>, &Value)> = rows.into_iter()
+            .skip(query.offset as usize)
+            .take(query.limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect();
+        let last_key = rows.last().and_then(|(key, _)| key.clone());
+        let items = rows.into_iter().map(|(_, row)| Self::to_entity(row.clone())).collect::<Result<_, _>>()?;
+        Ok(Page { items, total, last_key })
+    }
+
+    fn create(&self, item: T, entity_name_override: Option<&str>) -> Result<T, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        let object = Self::to_object(&item)?;
+        let mut collections = self.collections.write().unwrap();
+        let collection = collections.get_mut(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No collection for entity {}", entity_name)))?;
+
+        Self::to_entity(collection.insert(object)?)
+    }
+
+    fn update(&self, id: &str, item: T, entity_name_override: Option<&str>) -> Result<T, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        let object = Self::to_object(&item)?;
+        let mut collections = self.collections.write().unwrap();
+        let collection = collections.get_mut(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No collection for entity {}", entity_name)))?;
+
+        Self::to_entity(collection.replace(id, object)?)
+    }
+
+    fn update_matching(&self, filters: &[Filter], changes: &Map<String, Value>, entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        let mut collections = self.collections.write().unwrap();
+        let collection = collections.get_mut(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No collection for entity {}", entity_name)))?;
+
+        let primary_key = collection.primary_key.clone();
+        let mut updated = 0;
+        for row in collection.rows.iter_mut().filter(|row| filters.iter().all(|filter| filter.matches(row))) {
+            if let Value::Object(object) = row {
+                for (field, value) in changes.iter().filter(|(field, _)| **field != primary_key) {
+                    object.insert(field.clone(), value.clone());
+                }
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+
+    fn delete(&self, id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        let mut collections = self.collections.write().unwrap();
+        let collection = collections.get_mut(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No collection for entity {}", entity_name)))?;
+
+        Ok(collection.remove(id))
+    }
+
+    fn get_by_id(&self, id: &str, entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        let collections = self.collections.read().unwrap();
+        let collection = collections.get(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No collection for entity {}", entity_name)))?;
+
+        collection.rows.iter()
+            .find(|row| Self::key_matches(row.get(&collection.primary_key), id))
+            .cloned()
+            .map(Self::to_entity)
+            .transpose()
+    }
+
+    fn link_related(&self, id: &str, relationship: &str, related_id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        self.with_links(&entity_name, relationship, |links| links.insert((id.to_string(), related_id.to_string())))
+    }
+
+    fn unlink_related(&self, id: &str, relationship: &str, related_id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let entity_name = Self::entity_name::<T>(entity_name_override);
+        self.with_links(&entity_name, relationship, |links| links.remove(&(id.to_string(), related_id.to_string())))
+    }
+
+    fn write_batch(&self, writes: Vec<BatchWrite<T>>) -> Result<Vec<Option<T>>, BatchFailure> {
+        let mut collections = self.collections.write().unwrap();
+        // The writes go to a copy, kept only once all of them succeeded
+        let mut staged = collections.clone();
+
+        let mut written = Vec::with_capacity(writes.len());
+        for (index, write) in writes.into_iter().enumerate() {
+            let entity_name = Self::normalize_entity_name(write.entity());
+            let result = staged.get_mut(&entity_name)
+                .ok_or_else(|| Box::new(DataSourceError::NotFound(format!("No collection for entity {}", entity_name))) as Box<dyn Error>)
+                .and_then(|collection| match write {
+                    BatchWrite::Create { item, .. } => collection.insert(Self::to_object(&item)?).and_then(Self::to_entity).map(Some),
+                    BatchWrite::Update { id, item, .. } => collection.replace(&id, Self::to_object(&item)?).and_then(Self::to_entity).map(Some),
+                    BatchWrite::Delete { id, .. } if collection.remove(&id) => Ok(None),
+                    BatchWrite::Delete { id, .. } => Err(Box::new(DataSourceError::NotFound(format!("Item with ID {} not found", id))) as Box<dyn Error>),
+                });
+            written.push(result.map_err(|error| BatchFailure { index: Some(index), error })?);
+        }
+
+        *collections = staged;
+        Ok(written)
+    }
+
+    fn box_clone(&self) -> Box<dyn DataSource<T>> {
+        Box::new(self.clone())
+    }
+}
@@ -1,6 +1,9 @@
 use std::any::Any;
-use crate::config::specific::entity_config::{Entity, DataType};
-use crate::data::datasource::base::{DataSource, DatabaseCommon};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use serde_json::{Map, Value};
+use crate::config::specific::entity_config::{Entity, DataType, IdStrategy, JoinTable, RelationshipType};
+use crate::data::datasource::base::{DataSource, DataSourceError, DatabaseCommon, Filter, FilterExpression, ListQuery, PoolStats};
 
 /// Trait for relational datasources
 pub trait RelationalSource<T>: DataSource<T> + DatabaseCommon {
@@ -14,14 +17,326 @@ pub struct FieldMapping {
     pub field_name: String,
     pub column_name: String,
     pub field_type: String,
+    pub generated: bool,
+    pub insertable: bool,
+    pub updatable: bool,
+}
+
+impl FieldMapping {
+    /// Whether the column is written by INSERT statements
+    pub fn is_insertable(&self) -> bool {
+        self.insertable && !self.generated
+    }
+
+    /// Whether the column is written by UPDATE statements
+    pub fn is_updatable(&self) -> bool {
+        self.updatable && !self.generated
+    }
+}
+
+/// Structure to map an entity relationship to the related table
+#[derive(Clone)]
+pub struct RelationMapping {
+    pub name: String,
+    pub related_entity: String,
+    pub relationship_type: RelationshipType,
+    pub foreign_key: String,
+    pub join_table: Option<JoinTable>,
 }
 
 /// Structure to map entities to tables
 #[derive(Clone)]
 pub struct TableMapping {
+    pub schema: Option<String>,
     pub table_name: String,
     pub primary_key: String,
     pub fields: Vec<FieldMapping>,
+    pub relationships: Vec<RelationMapping>,
+}
+
+impl TableMapping {
+    /// Quoted name of the table, qualified by its schema when one is configured
+    pub fn qualified_name(&self) -> String {
+        self.qualify(&self.table_name)
+    }
+
+    /// Quotes a table name living next to this table, such as a join table. Names without
+    /// a schema prefix are placed in the schema of this table.
+    pub fn qualify(&self, table: &str) -> String {
+        match (&self.schema, table.contains('.')) {
+            (Some(schema), false) => format!("{}.{}", quote_identifier(schema), quote_identifier(table)),
+            _ => quote_qualified_name(table),
+        }
+    }
+
+    /// Finds the field mapped to a column, accepting either the column or the field name
+    pub fn field_for_column(&self, name: &str) -> Option<&FieldMapping> {
+        self.fields.iter()
+            .find(|f| f.column_name == name)
+            .or_else(|| self.fields.iter().find(|f| f.field_name == name))
+    }
+
+    /// Quoted columns of the fields matching a filter, in field order
+    fn quoted_columns(&self, filter: impl Fn(&FieldMapping) -> bool) -> Vec<String> {
+        self.fields.iter()
+            .filter(|field| filter(field))
+            .map(|field| quote_identifier(&field.column_name))
+            .collect()
+    }
+
+    /// Statement selecting every row of the table
+    pub fn select_sql(&self) -> String {
+        self.select_fields_sql(&[])
+    }
+
+    /// Statement selecting the columns of the given fields and the primary key of every row,
+    /// or every column if no field is given
+    pub fn select_fields_sql(&self, fields: &[String]) -> String {
+        let columns = self.quoted_columns(|field| {
+            fields.is_empty() || field.column_name == self.primary_key || field.field_name == self.primary_key
+                || fields.contains(&field.field_name)
+        });
+        format!("SELECT {} FROM {}", columns.join(", "), self.qualified_name())
+    }
+
+    /// Statement selecting the rows of a list query, with its parameters
+    pub fn select_list_sql(&self, query: &ListQuery) -> (String, Vec<Value>) {
+        let mut sql = self.select_fields_sql(&query.fields);
+        let (mut conditions, mut params) = self.query_conditions(query);
+        let primary_key = quote_identifier(&self.primary_key);
+        if let Some(after) = &query.after {
+            conditions.push(format!("{} > ?", primary_key));
+            params.push(Value::String(after.clone()));
+        }
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        let mut order: Vec<String> = query.sort.iter()
+            .filter_map(|sort_field| {
+                let column = quote_identifier(&self.field_for_column(&sort_field.field)?.column_name);
+                Some(if sort_field.descending { format!("{} DESC", column) } else { format!("{} ASC", column) })
+            })
+            .collect();
+        if query.order_by_key || query.after.is_some() {
+            order.push(primary_key);
+        }
+        if !order.is_empty() {
+            sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+        }
+        if query.limit.is_some() || query.offset > 0 {
+            // OFFSET requires a LIMIT, so the largest one is used when there is no limit
+            sql.push_str(" LIMIT ? OFFSET ?");
+            params.push(Value::from(query.limit.unwrap_or(i64::MAX as u64)));
+            params.push(Value::from(query.offset));
+        }
+        (sql, params)
+    }
+
+    /// Primary key of an entity object, as used in identifiers and cursors
+    pub fn key_of(&self, object: &Value) -> Option<String> {
+        let field = self.field_for_column(&self.primary_key)?;
+        object.get(&field.field_name).filter(|key| !key.is_null()).map(value_key)
+    }
+
+    /// Statement counting the rows matching the filters and filter expression of a list query, with its parameters
+    pub fn count_sql(&self, query: &ListQuery) -> (String, Vec<Value>) {
+        let mut sql = format!("SELECT COUNT(*) FROM {}", self.qualified_name());
+        let (conditions, params) = self.query_conditions(query);
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        (sql, params)
+    }
+
+    /// Statement selecting the distinct values of a field among the rows matching the filters, in
+    /// ascending order, along with the number of rows holding each one if `with_counts` is set.
+    /// None if the field has no column.
+    pub fn distinct_sql(&self, field: &str, filters: &[Filter], with_counts: bool, limit: u64) -> Option<(String, Vec<Value>)> {
+        let column = quote_identifier(&self.field_for_column(field)?.column_name);
+        let mut sql = if with_counts {
+            format!("SELECT {}, COUNT(*) FROM {}", column, self.qualified_name())
+        } else {
+            format!("SELECT DISTINCT {} FROM {}", column, self.qualified_name())
+        };
+        let (conditions, mut params) = self.filter_conditions(filters);
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        if with_counts {
+            sql.push_str(&format!(" GROUP BY {}", column));
+        }
+        sql.push_str(&format!(" ORDER BY {} LIMIT ?", column));
+        params.push(Value::from(limit));
+        Some((sql, params))
+    }
+
+    /// Conditions of a set of filters, with their parameters.
+    /// Filters on fields without a column are skipped.
+    fn filter_conditions(&self, filters: &[Filter]) -> (Vec<String>, Vec<Value>) {
+        let mut params = Vec::new();
+        let conditions = filters.iter()
+            .filter_map(|filter| self.filter_condition(filter, &mut params))
+            .collect();
+        (conditions, params)
+    }
+
+    /// Conditions of the filters and the filter expression of a list query, with their parameters
+    fn query_conditions(&self, query: &ListQuery) -> (Vec<String>, Vec<Value>) {
+        let (mut conditions, mut params) = self.filter_conditions(&query.filters);
+        if let Some(expression) = &query.expression {
+            conditions.push(self.expression_condition(expression, &mut params));
+        }
+        (conditions, params)
+    }
+
+    /// Condition of a filter, pushing its parameter. None if the field has no column.
+    fn filter_condition(&self, filter: &Filter, params: &mut Vec<Value>) -> Option<String> {
+        let column = quote_identifier(&self.field_for_column(&filter.field)?.column_name);
+        if filter.operator.is_null_check() {
+            Some(format!("{} {}", column, filter.operator.sql()))
+        } else {
+            params.push(filter.value.clone());
+            Some(format!("{} {} ?", column, filter.operator.sql()))
+        }
+    }
+
+    /// Condition of a filter expression, pushing its parameters in order.
+    /// Filters on fields without a column hold for every row, as they are skipped elsewhere.
+    fn expression_condition(&self, expression: &FilterExpression, params: &mut Vec<Value>) -> String {
+        let join = |expressions: &[FilterExpression], params: &mut Vec<Value>, separator: &str| {
+            let conditions: Vec<String> = expressions.iter()
+                .map(|expression| self.expression_condition(expression, params))
+                .collect();
+            format!("({})", conditions.join(separator))
+        };
+        match expression {
+            FilterExpression::Filter(filter) => self.filter_condition(filter, params).unwrap_or_else(|| "1 = 1".to_string()),
+            FilterExpression::And(expressions) if expressions.is_empty() => "1 = 1".to_string(),
+            FilterExpression::Or(expressions) if expressions.is_empty() => "1 = 0".to_string(),
+            FilterExpression::And(expressions) => join(expressions, params, " AND "),
+            FilterExpression::Or(expressions) => join(expressions, params, " OR "),
+            FilterExpression::Not(expression) => format!("NOT ({})", self.expression_condition(expression, params)),
+        }
+    }
+
+    /// Statement selecting a row by its primary key
+    pub fn select_by_id_sql(&self) -> String {
+        self.select_fields_by_id_sql(&[])
+    }
+
+    /// Statement selecting the columns of the given fields of a row by its primary key,
+    /// as `select_fields_sql` does
+    pub fn select_fields_by_id_sql(&self, fields: &[String]) -> String {
+        format!("{} WHERE {} = ?", self.select_fields_sql(fields), quote_identifier(&self.primary_key))
+    }
+
+    /// Statement inserting a row. Generated and non-insertable columns are left to the database.
+    pub fn insert_sql(&self) -> String {
+        let columns = self.quoted_columns(FieldMapping::is_insertable);
+        let placeholders: Vec<&str> = columns.iter().map(|_| "?").collect();
+        format!("INSERT INTO {} ({}) VALUES ({})", self.qualified_name(), columns.join(", "), placeholders.join(", "))
+    }
+
+    /// Statement updating a row by its primary key, or None if no column is updatable
+    pub fn update_sql(&self) -> Option<String> {
+        let set_clauses: Vec<String> = self.quoted_columns(|field| field.field_name != self.primary_key && field.is_updatable())
+            .into_iter()
+            .map(|column| format!("{} = ?", column))
+            .collect();
+        if set_clauses.is_empty() {
+            return None;
+        }
+        Some(format!("UPDATE {} SET {} WHERE {} = ?", self.qualified_name(), set_clauses.join(", "), quote_identifier(&self.primary_key)))
+    }
+
+    /// Statement applying a partial document to the rows meeting the filters, with its parameters.
+    /// Only updatable fields other than the primary key are written; None if the document has none.
+    pub fn update_matching_sql(&self, filters: &[Filter], changes: &Map<String, Value>) -> Option<(String, Vec<Value>)> {
+        let (set_clauses, mut params): (Vec<String>, Vec<Value>) = self.fields.iter()
+            .filter(|field| field.field_name != self.primary_key && field.is_updatable())
+            .filter_map(|field| {
+                let value = changes.get(&field.field_name)?;
+                Some((format!("{} = ?", quote_identifier(&field.column_name)), value.clone()))
+            })
+            .unzip();
+        if set_clauses.is_empty() {
+            return None;
+        }
+
+        let mut sql = format!("UPDATE {} SET {}", self.qualified_name(), set_clauses.join(", "));
+        let (conditions, condition_params) = self.filter_conditions(filters);
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        params.extend(condition_params);
+        Some((sql, params))
+    }
+
+    /// Statement deleting a row by its primary key
+    pub fn delete_sql(&self) -> String {
+        format!("DELETE FROM {} WHERE {} = ?", self.qualified_name(), quote_identifier(&self.primary_key))
+    }
+
+    /// Parameters of the insert statement taken from an entity object
+    pub fn insert_values(&self, object: &Map<String, Value>) -> Vec<Value> {
+        self.fields.iter()
+            .filter(|field| field.is_insertable())
+            .map(|field| object.get(&field.field_name).cloned().unwrap_or(Value::Null))
+            .collect()
+    }
+
+    /// Parameters of the update statement taken from an entity object, followed by the id
+    pub fn update_values(&self, object: &Map<String, Value>, id: &str) -> Vec<Value> {
+        let mut values: Vec<Value> = self.fields.iter()
+            .filter(|field| field.field_name != self.primary_key && field.is_updatable())
+            .map(|field| object.get(&field.field_name).cloned().unwrap_or(Value::Null))
+            .collect();
+        values.push(Value::String(id.to_string()));
+        values
+    }
+}
+
+/// Counters for connection acquisitions, shared between clones of a pooled datasource
+#[derive(Default)]
+pub struct PoolMetrics {
+    acquire_count: AtomicU64,
+    acquire_errors: AtomicU64,
+    total_wait_micros: AtomicU64,
+    max_wait_micros: AtomicU64,
+}
+
+impl PoolMetrics {
+    /// Records a connection acquisition and how long it waited
+    pub fn record_acquire(&self, wait: Duration, success: bool) {
+        let wait_micros = wait.as_micros() as u64;
+        self.acquire_count.fetch_add(1, Ordering::Relaxed);
+        self.total_wait_micros.fetch_add(wait_micros, Ordering::Relaxed);
+        self.max_wait_micros.fetch_max(wait_micros, Ordering::Relaxed);
+        if !success {
+            self.acquire_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Builds a statistics snapshot combining these counters with the live pool state
+    pub fn snapshot(&self, size: u32, idle: usize, max_connections: u32) -> PoolStats {
+        let acquire_count = self.acquire_count.load(Ordering::Relaxed);
+        let total_wait_micros = self.total_wait_micros.load(Ordering::Relaxed);
+        let average_wait_ms = if acquire_count > 0 {
+            total_wait_micros as f64 / acquire_count as f64 / 1000.0
+        } else {
+            0.0
+        };
+
+        PoolStats {
+            size,
+            idle,
+            max_connections,
+            acquire_count,
+            acquire_errors: self.acquire_errors.load(Ordering::Relaxed),
+            average_wait_ms,
+            max_wait_ms: self.max_wait_micros.load(Ordering::Relaxed) as f64 / 1000.0,
+        }
+    }
 }
 
 /// Converts an entity data type to a relational database type
@@ -47,16 +362,100 @@ pub fn detect_primary_key(entity: &Entity) -> String {
         .unwrap_or_else(|| "id".to_string())
 }
 
-/// Create a table mapping for an entity
-pub fn create_table_mapping(entity: &Entity) -> TableMapping {
+/// Builds a comparable key for a JSON value, so `1` and `"1"` match the same row.
+pub fn value_key(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Longest identifier accepted, the limit of MySQL and MariaDB
+pub const MAX_IDENTIFIER_LEN: usize = 64;
+
+/// Checks that a table, schema or column name is a plain identifier: letters, digits, `_` and
+/// `$`, not only digits, and at most `MAX_IDENTIFIER_LEN` characters. They are quoted anyway, so
+/// reserved words like `order` are accepted.
+pub fn validate_identifier(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("an identifier cannot be empty".to_string());
+    }
+    if name.len() > MAX_IDENTIFIER_LEN {
+        return Err(format!("identifier {} is longer than {} characters", name, MAX_IDENTIFIER_LEN));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+        return Err(format!("identifier {:?} may only contain letters, digits, _ and $", name));
+    }
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("identifier {} cannot be only digits", name));
+    }
+    Ok(())
+}
+
+/// Checks a possibly schema-qualified name such as `analytics.events`, part by part
+pub fn validate_qualified_name(name: &str) -> Result<(), String> {
+    match name.split_once('.') {
+        Some((schema, table)) => validate_identifier(schema).and_then(|_| validate_identifier(table)),
+        None => validate_identifier(name),
+    }
+}
+
+/// Checks the identifiers an entity puts into SQL: its schema, table and columns, and the keys
+/// and join tables of its relationships
+pub fn validate_entity_identifiers(entity: &Entity) -> Result<(), String> {
+    let invalid = |e: String| format!("Entity {}: {}", entity.name, e);
+    validate_qualified_name(entity.table_name.as_deref().unwrap_or(&entity.name)).map_err(invalid)?;
+    if let Some(schema) = &entity.schema {
+        validate_identifier(schema).map_err(invalid)?;
+    }
+    for field in entity.fields.iter().filter(|field| field.is_stored()) {
+        validate_identifier(field.column_name.as_deref().unwrap_or(&field.name)).map_err(invalid)?;
+    }
+    for relationship in &entity.relationships {
+        validate_identifier(&relationship.foreign_key).map_err(invalid)?;
+        if let Some(join_table) = &relationship.join_table {
+            validate_qualified_name(&join_table.table).map_err(invalid)?;
+            validate_identifier(&join_table.local_key).map_err(invalid)?;
+            validate_identifier(&join_table.related_key).map_err(invalid)?;
+        }
+    }
+    Ok(())
+}
+
+/// Quotes an identifier with backticks, doubling any backtick it contains
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
+}
+
+/// Quotes a possibly schema-qualified name such as `analytics.events`, part by part
+pub fn quote_qualified_name(name: &str) -> String {
+    name.split('.').map(quote_identifier).collect::<Vec<_>>().join(".")
+}
+
+/// Create a table mapping for an entity. Entities whose identifiers are not plain ones are
+/// refused, so no configuration can put arbitrary SQL into the statements.
+pub fn create_table_mapping(entity: &Entity) -> Result<TableMapping, DataSourceError> {
+    validate_entity_identifiers(entity).map_err(DataSourceError::ValidationError)?;
+
     // Get the table name from entity config, fallback to entity name if not specified
     let table_name = entity.table_name.clone().unwrap_or_else(|| entity.name.clone());
     
+    // A schema prefix in the table name takes precedence over the entity schema
+    let (schema, table_name) = match table_name.rsplit_once('.') {
+        Some((schema, table)) => (Some(schema.to_string()), table.to_string()),
+        None => (entity.schema.clone(), table_name),
+    };
+    
     // Create field mappings based on entity fields
     let mut fields = Vec::new();
     let mut primary_key = "id".to_string(); // Default primary key
+    // Keys generated by the database are never written
+    let db_key = match entity.id_strategy {
+        Some(IdStrategy::DbAuto) => entity.key_field().map(|field| field.name.as_str()),
+        _ => None,
+    };
     
-    for field in &entity.fields {
+    for field in entity.fields.iter().filter(|field| field.is_stored()) {
         let column_name = field.column_name.clone().unwrap_or_else(|| field.name.clone());
         
         // Add field to mappings
@@ -64,6 +463,9 @@ pub fn create_table_mapping(entity: &Entity) -> TableMapping {
             field_name: field.name.clone(),
             column_name: column_name.clone(),
             field_type: data_type_to_string(&field.data_type),
+            generated: field.generated || db_key == Some(field.name.as_str()),
+            insertable: field.insertable,
+            updatable: field.updatable,
         });
         
         // Use first field as primary key for now (better handling needed)
@@ -72,9 +474,21 @@ pub fn create_table_mapping(entity: &Entity) -> TableMapping {
         }
     }
     
-    TableMapping {
+    let relationships = entity.relationships.iter()
+        .map(|relationship| RelationMapping {
+            name: relationship.name.clone(),
+            related_entity: relationship.related_entity.clone(),
+            relationship_type: relationship.type_.clone(),
+            foreign_key: relationship.foreign_key.clone(),
+            join_table: relationship.join_table.clone(),
+        })
+        .collect();
+    
+    Ok(TableMapping {
+        schema,
         table_name,
         primary_key,
         fields,
-    }
+        relationships,
+    })
 }
\ No newline at end of file
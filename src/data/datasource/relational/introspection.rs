@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+use sqlx::{Connection, MySqlConnection, PgConnection, SqliteConnection};
+use crate::config::shared::{EntityBasic, FieldBasic};
+use crate::config::specific::database_config::{DatabaseConfig, DatabaseType};
+use crate::config::specific::entity_config::{DataType, Entity, JoinTable, Relationship, RelationshipType};
+use crate::data::datasource::base::DataSourceError;
+use tracing::{info, warn};
+
+/// Columns of the tables of the current MySQL database, with literal defaults left unquoted and
+/// `DEFAULT_GENERATED` in the extra information of expressions
+const MYSQL_COLUMNS: &str = "SELECT CAST(c.TABLE_NAME AS CHAR), CAST(c.COLUMN_NAME AS CHAR), CAST(c.COLUMN_TYPE AS CHAR), \
+    CAST(c.IS_NULLABLE AS CHAR), CAST(c.COLUMN_DEFAULT AS CHAR), CAST(c.EXTRA AS CHAR) \
+    FROM information_schema.COLUMNS c JOIN information_schema.TABLES t \
+    ON t.TABLE_SCHEMA = c.TABLE_SCHEMA AND t.TABLE_NAME = c.TABLE_NAME \
+    WHERE c.TABLE_SCHEMA = DATABASE() AND t.TABLE_TYPE = 'BASE TABLE' \
+    ORDER BY c.TABLE_NAME, c.ORDINAL_POSITION";
+
+/// Primary key, unique and foreign key constraints of the current MySQL database, column by column
+const MYSQL_KEYS: &str = "SELECT CAST(k.TABLE_NAME AS CHAR), CAST(k.CONSTRAINT_NAME AS CHAR), CAST(tc.CONSTRAINT_TYPE AS CHAR), \
+    CAST(k.COLUMN_NAME AS CHAR), CAST(k.REFERENCED_TABLE_NAME AS CHAR), CAST(k.REFERENCED_COLUMN_NAME AS CHAR) \
+    FROM information_schema.KEY_COLUMN_USAGE k JOIN information_schema.TABLE_CONSTRAINTS tc \
+    ON tc.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA AND tc.TABLE_NAME = k.TABLE_NAME AND tc.CONSTRAINT_NAME = k.CONSTRAINT_NAME \
+    WHERE k.TABLE_SCHEMA = DATABASE() \
+    ORDER BY k.TABLE_NAME, k.CONSTRAINT_NAME, k.ORDINAL_POSITION";
+
+/// Columns of the tables of the current PostgreSQL schema. Identity, generated and serial
+/// columns are filled in by the database.
+const POSTGRES_COLUMNS: &str = "SELECT c.table_name::text, c.column_name::text, c.udt_name::text, c.is_nullable::text, \
+    c.column_default::text, \
+    (c.is_identity = 'YES' OR c.is_generated = 'ALWAYS' OR COALESCE(c.column_default, '') LIKE 'nextval(%') \
+    FROM information_schema.columns c JOIN information_schema.tables t \
+    ON t.table_schema = c.table_schema AND t.table_name = c.table_name \
+    WHERE c.table_schema = current_schema() AND t.table_type = 'BASE TABLE' \
+    ORDER BY c.table_name, c.ordinal_position";
+
+/// Primary key, unique and foreign key constraints of the current PostgreSQL schema, column by column
+const POSTGRES_KEYS: &str = "SELECT kcu.table_name::text, tc.constraint_name::text, tc.constraint_type::text, \
+    kcu.column_name::text, ccu.table_name::text, ccu.column_name::text \
+    FROM information_schema.table_constraints tc \
+    JOIN information_schema.key_column_usage kcu \
+    ON kcu.constraint_schema = tc.constraint_schema AND kcu.constraint_name = tc.constraint_name \
+    LEFT JOIN information_schema.constraint_column_usage ccu \
+    ON tc.constraint_type = 'FOREIGN KEY' AND ccu.constraint_schema = tc.constraint_schema AND ccu.constraint_name = tc.constraint_name \
+    WHERE tc.table_schema = current_schema() AND tc.constraint_type IN ('PRIMARY KEY', 'UNIQUE', 'FOREIGN KEY') \
+    ORDER BY kcu.table_name, tc.constraint_name, kcu.ordinal_position";
+
+const SQLITE_TABLES: &str = "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name";
+const SQLITE_COLUMNS: &str = "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?1) ORDER BY cid";
+/// Columns with a unique index of their own
+const SQLITE_UNIQUE: &str = "SELECT il.name, MAX(ii.name) FROM pragma_index_list(?1) il, pragma_index_info(il.name) ii \
+    WHERE il.\"unique\" = 1 AND il.origin <> 'pk' GROUP BY il.name HAVING COUNT(*) = 1";
+const SQLITE_FOREIGN_KEYS: &str = "SELECT id, \"from\", \"table\", \"to\" FROM pragma_foreign_key_list(?1) ORDER BY id, seq";
+
+/// A column of a table, as the database describes it
+struct Column {
+    name: String,
+    /// Declared type, like `varchar(255)`, `int4` or `tinyint(1)`
+    type_name: String,
+    nullable: bool,
+    /// Literal default of the column. Defaults computed by the database are left out.
+    default: Option<String>,
+    /// Whether the database fills the column in, as auto increments and identities
+    generated: bool,
+}
+
+/// A foreign key of a single column
+struct ForeignKey {
+    column: String,
+    table: String,
+    /// Referenced column, the primary key of the table when None
+    referenced_column: Option<String>,
+}
+
+#[derive(Default)]
+struct Table {
+    columns: Vec<Column>,
+    primary_key: Vec<String>,
+    /// Columns with a unique constraint of their own
+    unique: Vec<String>,
+    foreign_keys: Vec<ForeignKey>,
+}
+
+impl Table {
+    /// Tables with exactly two columns, both foreign keys, only link the rows of other tables
+    fn join_keys(&self) -> Option<(&ForeignKey, &ForeignKey)> {
+        match self.foreign_keys.as_slice() {
+            [first, second] if self.columns.len() == 2 && first.column != second.column => Some((first, second)),
+            _ => None,
+        }
+    }
+}
+
+/// Table, constraint name and type, column and referenced table and column of a key column
+type KeyRow = (String, String, String, String, Option<String>, Option<String>);
+
+/// A column of a key constraint, as information_schema lists them
+struct KeyColumn {
+    table: String,
+    constraint: String,
+    kind: String,
+    column: String,
+    referenced: Option<(String, String)>,
+}
+
+/// Generates entity definitions from the tables of a database
+pub struct EntityGenerator;
+
+impl EntityGenerator {
+    /// Reads the tables of a MySQL, PostgreSQL or SQLite database and returns an entity for each,
+    /// ready to be added to `entities_advanced`. Fields take the data types inferred from their
+    /// columns, with the primary key first; foreign keys become many-to-one relationships along
+    /// with their one-to-many inverses, and tables linking two others become many-to-many
+    /// relationships instead of entities. Generated relationships are left out of the responses.
+    ///
+    /// Runs its own runtime, like the datasources, so it must not be called from an async context.
+    pub fn from_database(config: &DatabaseConfig) -> Result<Vec<Entity>, DataSourceError> {
+        let runtime = tokio::runtime::Runtime::new()
+            .map_err(|e| DataSourceError::ConnectionError(format!("Error starting the runtime: {}", e)))?;
+        let tables = runtime.block_on(Self::read_tables(config))?;
+        let entities = Self::generate(&tables);
+        info!("Generated {} entities from the {} database {}", entities.len(), config.db_type, config.database_name);
+        Ok(entities)
+    }
+
+    async fn read_tables(config: &DatabaseConfig) -> Result<BTreeMap<String, Table>, DataSourceError> {
+        let url = config.with_secrets().map_err(|e| DataSourceError::ConnectionError(e.to_string()))?.make_url();
+        let connection_error = |e: sqlx::Error| DataSourceError::ConnectionError(format!("Error connecting to {}: {}", config.db_type, e));
+        match config.db_type {
+            DatabaseType::MySQL => {
+                let mut connection = MySqlConnection::connect(&url).await.map_err(connection_error)?;
+                let columns: Vec<(String, String, String, String, Option<String>, String)> =
+                    sqlx::query_as(MYSQL_COLUMNS).fetch_all(&mut connection).await.map_err(query_error)?;
+                let keys: Vec<KeyRow> =
+                    sqlx::query_as(MYSQL_KEYS).fetch_all(&mut connection).await.map_err(query_error)?;
+                let columns = columns.into_iter().map(|(table, name, type_name, nullable, default, extra)| {
+                    let extra = extra.to_lowercase();
+                    let computed_default = extra.contains("default_generated");
+                    let column = Column {
+                        name,
+                        type_name,
+                        nullable: nullable == "YES",
+                        default: default.filter(|_| !computed_default).and_then(|raw| mysql_literal(&raw)),
+                        generated: extra.contains("auto_increment") || (extra.contains("generated") && !computed_default),
+                    };
+                    (table, column)
+                });
+                Ok(assemble(columns, keys.into_iter().map(KeyColumn::from)))
+            }
+            DatabaseType::PostgreSQL => {
+                let mut connection = PgConnection::connect(&url).await.map_err(connection_error)?;
+                let columns: Vec<(String, String, String, String, Option<String>, bool)> =
+                    sqlx::query_as(POSTGRES_COLUMNS).fetch_all(&mut connection).await.map_err(query_error)?;
+                let keys: Vec<KeyRow> =
+                    sqlx::query_as(POSTGRES_KEYS).fetch_all(&mut connection).await.map_err(query_error)?;
+                let columns = columns.into_iter().map(|(table, name, type_name, nullable, default, generated)| {
+                    let column = Column {
+                        name,
+                        type_name,
+                        nullable: nullable == "YES",
+                        default: default.filter(|_| !generated).and_then(|raw| sql_literal(&raw)),
+                        generated,
+                    };
+                    (table, column)
+                });
+                Ok(assemble(columns, keys.into_iter().map(KeyColumn::from)))
+            }
+            DatabaseType::SQLite => {
+                let mut connection = SqliteConnection::connect(&url).await.map_err(connection_error)?;
+                Self::read_sqlite_tables(&mut connection).await
+            }
+            DatabaseType::MongoDB | DatabaseType::SurrealDB => Err(DataSourceError::ValidationError(format!(
+                "Entities can only be generated from relational databases, not from {}", config.db_type
+            ))),
+        }
+    }
+
+    /// SQLite has no information_schema, its tables are described by pragmas instead
+    async fn read_sqlite_tables(connection: &mut SqliteConnection) -> Result<BTreeMap<String, Table>, DataSourceError> {
+        let names: Vec<(String,)> = sqlx::query_as(SQLITE_TABLES).fetch_all(&mut *connection).await.map_err(query_error)?;
+        let mut tables = BTreeMap::new();
+        for (name,) in names {
+            let mut table = Table::default();
+            let columns: Vec<(String, String, i64, Option<String>, i64)> = sqlx::query_as(SQLITE_COLUMNS)
+                .bind(&name).fetch_all(&mut *connection).await.map_err(query_error)?;
+            let mut key: Vec<(i64, String)> = columns.iter()
+                .filter(|(_, _, _, _, pk)| *pk > 0)
+                .map(|(column, _, _, _, pk)| (*pk, column.clone()))
+                .collect();
+            key.sort();
+            table.primary_key = key.into_iter().map(|(_, column)| column).collect();
+            for (column, type_name, not_null, default, pk) in columns {
+                // A single INTEGER primary key is an alias of the rowid, which SQLite assigns
+                let generated = pk > 0 && table.primary_key.len() == 1 && type_name.eq_ignore_ascii_case("integer");
+                table.columns.push(Column {
+                    name: column,
+                    type_name,
+                    nullable: not_null == 0 && pk == 0,
+                    default: default.and_then(|raw| sql_literal(&raw)),
+                    generated,
+                });
+            }
+
+            let unique: Vec<(String, String)> = sqlx::query_as(SQLITE_UNIQUE)
+                .bind(&name).fetch_all(&mut *connection).await.map_err(query_error)?;
+            table.unique = unique.into_iter().map(|(_, column)| column).collect();
+
+            let foreign_keys: Vec<(i64, String, String, Option<String>)> = sqlx::query_as(SQLITE_FOREIGN_KEYS)
+                .bind(&name).fetch_all(&mut *connection).await.map_err(query_error)?;
+            for (id, column, referenced_table, referenced_column) in &foreign_keys {
+                // Foreign keys of several columns have no field to hold them
+                if foreign_keys.iter().filter(|(other, ..)| other == id).count() == 1 {
+                    table.foreign_keys.push(ForeignKey {
+                        column: column.clone(),
+                        table: referenced_table.clone(),
+                        referenced_column: referenced_column.clone(),
+                    });
+                }
+            }
+            tables.insert(name, table);
+        }
+        Ok(tables)
+    }
+
+    /// Entities of the tables, in the order of their names
+    fn generate(tables: &BTreeMap<String, Table>) -> Vec<Entity> {
+        let mut entities: BTreeMap<&str, Entity> = tables.iter()
+            .filter(|(_, table)| table.join_keys().is_none())
+            .map(|(name, table)| (name.as_str(), entity_of_table(name, table)))
+            .collect();
+
+        for (name, table) in tables {
+            if let Some((local, related)) = table.join_keys() {
+                for (from, to) in [(local, related), (related, local)] {
+                    let join_table = JoinTable { table: name.clone(), local_key: from.column.clone(), related_key: to.column.clone() };
+                    add_relationship(&mut entities, &from.table, &to.table, RelationshipType::ManyToMany, &from.column, Some(join_table));
+                }
+                continue;
+            }
+            for foreign_key in &table.foreign_keys {
+                let Some(referenced) = tables.get(&foreign_key.table) else {
+                    warn!("Skipping the foreign key {}.{}: unknown table {}", name, foreign_key.column, foreign_key.table);
+                    continue;
+                };
+                let referenced_column = foreign_key.referenced_column.as_ref().or(referenced.primary_key.first());
+                if referenced.primary_key.len() != 1 || referenced_column != referenced.primary_key.first() {
+                    warn!("Skipping the foreign key {}.{}: it does not reference the primary key of {}", name, foreign_key.column, foreign_key.table);
+                    continue;
+                }
+                add_relationship(&mut entities, name, &foreign_key.table, RelationshipType::ManyToOne, &foreign_key.column, None);
+                add_relationship(&mut entities, &foreign_key.table, name, RelationshipType::OneToMany, &foreign_key.column, None);
+            }
+        }
+        entities.into_values().collect()
+    }
+}
+
+impl From<KeyRow> for KeyColumn {
+    fn from((table, constraint, kind, column, referenced_table, referenced_column): KeyRow) -> Self {
+        Self { table, constraint, kind, column, referenced: referenced_table.zip(referenced_column) }
+    }
+}
+
+fn query_error(e: sqlx::Error) -> DataSourceError {
+    DataSourceError::QueryError(format!("Error reading the database schema: {}", e))
+}
+
+/// Groups the columns and key constraints read from information_schema by table
+fn assemble(columns: impl Iterator<Item = (String, Column)>, keys: impl Iterator<Item = KeyColumn>) -> BTreeMap<String, Table> {
+    let mut tables: BTreeMap<String, Table> = BTreeMap::new();
+    for (table, column) in columns {
+        tables.entry(table).or_default().columns.push(column);
+    }
+
+    let mut constraints: BTreeMap<(String, String), Vec<KeyColumn>> = BTreeMap::new();
+    for key in keys {
+        constraints.entry((key.table.clone(), key.constraint.clone())).or_default().push(key);
+    }
+    for ((name, _), key) in constraints {
+        let Some(table) = tables.get_mut(&name) else {
+            continue;
+        };
+        match (key[0].kind.as_str(), key.as_slice()) {
+            ("PRIMARY KEY", _) => table.primary_key = key.into_iter().map(|key| key.column).collect(),
+            ("UNIQUE", [single]) => table.unique.push(single.column.clone()),
+            ("FOREIGN KEY", [single]) => {
+                if let Some((referenced_table, referenced_column)) = &single.referenced {
+                    table.foreign_keys.push(ForeignKey {
+                        column: single.column.clone(),
+                        table: referenced_table.clone(),
+                        referenced_column: Some(referenced_column.clone()),
+                    });
+                }
+            }
+            // Unique and foreign keys of several columns have no field to hold them
+            _ => {}
+        }
+    }
+    tables
+}
+
+/// Entity of a table, with the fields of its columns and its primary key first
+fn entity_of_table(name: &str, table: &Table) -> Entity {
+    let mut columns: Vec<&Column> = table.columns.iter().collect();
+    if let [key] = table.primary_key.as_slice() {
+        columns.sort_by_key(|column| &column.name != key);
+    }
+    let basic = EntityBasic {
+        name: name.to_string(),
+        table_name: None,
+        fields: columns.iter().map(|column| FieldBasic {
+            name: column.name.clone(),
+            data_type: infer_data_type(&column.type_name),
+            required: !column.nullable && column.default.is_none() && !column.generated,
+        }).collect(),
+        authentication: false,
+    };
+    let mut entity = basic.to_entity();
+    for (field, column) in entity.fields.iter_mut().zip(columns) {
+        field.generated = column.generated;
+        field.unique = table.unique.contains(&column.name);
+        field.default_value = column.default.as_ref().map(|value| match (&field.data_type, value.as_str()) {
+            (DataType::Boolean, "0") => "false".to_string(),
+            (DataType::Boolean, "1") => "true".to_string(),
+            _ => value.clone(),
+        });
+    }
+    entity
+}
+
+/// Adds a relationship to the entity of a table, named after the related table, or after the
+/// foreign key of many-to-one relationships, like `author` for `author_id`
+fn add_relationship(
+    entities: &mut BTreeMap<&str, Entity>,
+    table: &str,
+    related: &str,
+    type_: RelationshipType,
+    foreign_key: &str,
+    join_table: Option<JoinTable>,
+) {
+    let Some(entity) = entities.get_mut(table) else {
+        return;
+    };
+    let base_name = match type_ {
+        RelationshipType::ManyToOne => foreign_key.strip_suffix("_id").filter(|name| !name.is_empty()).unwrap_or(related),
+        _ => related,
+    };
+    let taken = |name: &str| entity.fields.iter().any(|field| field.name == name)
+        || entity.relationships.iter().any(|relationship| relationship.name == name);
+    let mut name = base_name.to_string();
+    if taken(&name) {
+        name = format!("{}_{}", related, foreign_key);
+    }
+    let mut suffix = 2;
+    while taken(&name) {
+        name = format!("{}_{}_{}", related, foreign_key, suffix);
+        suffix += 1;
+    }
+    entity.relationships.push(Relationship {
+        name,
+        related_entity: related.to_string(),
+        type_,
+        foreign_key: foreign_key.to_string(),
+        include_in_responses: false,
+        join_table,
+    });
+}
+
+/// Data type of the fields of a column of some declared type
+fn infer_data_type(type_name: &str) -> DataType {
+    let type_name = type_name.to_lowercase();
+    let base = type_name.split(['(', ' ']).next().unwrap_or_default();
+    match base {
+        _ if type_name == "tinyint(1)" || type_name == "bit(1)" => DataType::Boolean,
+        "bool" | "boolean" => DataType::Boolean,
+        "json" | "jsonb" => DataType::JSON,
+        "date" => DataType::Date,
+        _ if base.starts_with("datetime") || base.starts_with("timestamp") => DataType::DateTime,
+        "bytea" | "binary" | "varbinary" => DataType::Binary,
+        _ if base.ends_with("blob") => DataType::Binary,
+        _ if base.contains("int") && base != "interval" && base != "point" || base.ends_with("serial") => DataType::Integer,
+        "decimal" | "numeric" | "float" | "float4" | "float8" | "double" | "real" | "money" => DataType::Float,
+        _ => DataType::String,
+    }
+}
+
+/// Literal of a SQL default, like `'active'::character varying`, `0` or `true`. Defaults
+/// computed by the database, like `CURRENT_TIMESTAMP`, have none.
+fn sql_literal(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let value = match raw.rfind("::") {
+        Some(index) if raw.starts_with('\'') || raw[..index].parse::<f64>().is_ok() => &raw[..index],
+        _ => raw,
+    };
+    if let Some(quoted) = value.strip_prefix('\'').and_then(|value| value.strip_suffix('\'')) {
+        return Some(quoted.replace("''", "'"));
+    }
+    let value = value.trim_matches(|c| c == '(' || c == ')');
+    if value.parse::<f64>().is_ok() {
+        Some(value.to_string())
+    } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+        Some(value.to_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Literal of a MySQL default, which lists text defaults unquoted while MariaDB quotes them
+fn mysql_literal(raw: &str) -> Option<String> {
+    if raw.starts_with('\'') {
+        return sql_literal(raw);
+    }
+    let computed = raw.eq_ignore_ascii_case("null") || raw.to_uppercase().starts_with("CURRENT_TIMESTAMP") || raw.contains('(');
+    (!computed).then(|| raw.to_string())
+}
@@ -1,18 +1,36 @@
 use std::any::Any;
 use std::collections::HashMap;
 use std::error::Error;
-use sqlx::{MySql, Pool, Row, MySqlExecutor};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+use sqlx::{Connection, MySql, MySqlConnection, Pool, Row, MySqlExecutor};
+use sqlx::pool::PoolConnection;
 use sqlx::mysql::{MySqlRow, MySqlPoolOptions, MySqlArguments};
 use tokio::runtime::Runtime;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use crate::api::common::api_entity::ApiEntity;
 use crate::config::specific::database_config::DatabaseConfig;
-use crate::config::specific::entity_config::Entity;
-use crate::data::datasource::base::{DataSource, DatabaseCommon, DataSourceError};
-use crate::data::datasource::relational::base::{RelationalSource, TableMapping, create_table_mapping};
+use crate::config::specific::entity_config::{Entity, JoinTable, RelationshipType};
+use crate::data::datasource::base::{BatchFailure, BatchWrite, DataSource, DatabaseCommon, DataSourceError, DistinctValue, Filter, ListQuery, Page, PoolStats};
+use crate::data::datasource::relational::base::{PoolMetrics, RelationalSource, TableMapping, create_table_mapping, quote_identifier, value_key};
 use serde::{Serialize, de::DeserializeOwned};
+use tracing::{error, field, info, info_span, instrument, warn, Instrument};
 
 const DEFAULT_QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// Database system reported in the spans of the queries
+const DB_SYSTEM: &str = "mysql";
+
+/// SQL of a write of a batch, prepared before its transaction starts
+struct BatchStatement {
+    sql: String,
+    params: Vec<Value>,
+    /// Query reloading a created row with the values generated by the database, along with
+    /// the key of the row, or None to read it from LAST_INSERT_ID
+    reload: Option<(String, Option<String>)>,
+    /// Whether the write fails when it affects no row, as deleting a missing entity does
+    requires_row: bool,
+}
 
 /// MariaDB datasource implementation that provides CRUD operations 
 /// against MariaDB/MySQL databases, with flexible entity-table mapping.
@@ -20,6 +38,7 @@ pub struct MariaDbDatasource {
     pub config: DatabaseConfig,
     pool: Option<Pool<MySql>>,
     entity_mappings: HashMap<String, TableMapping>,
+    metrics: Arc<PoolMetrics>,
     runtime: Runtime,
 }
 
@@ -36,6 +55,7 @@ impl MariaDbDatasource {
             config: config.clone(),
             pool: None,
             entity_mappings: HashMap::new(),
+            metrics: Arc::new(PoolMetrics::default()),
             runtime: Runtime::new().unwrap(),
         }
     }
@@ -71,7 +91,7 @@ impl MariaDbDatasource {
             });
         
         if result.is_none() {
-            eprintln!("Entity mapping not found for '{}' (normalized: '{}'). Available mappings: {:?}", 
+            error!("Entity mapping not found for '{}' (normalized: '{}'). Available mappings: {:?}", 
                       entity_name, normalized, self.entity_mappings.keys().collect::<Vec<_>>());
         }
         
@@ -93,7 +113,7 @@ impl MariaDbDatasource {
         
         for entity in entities {
             let normalized_name = self.normalize_entity_name(&entity.name);
-            let mapping = create_table_mapping(entity);
+            let mapping = create_table_mapping(entity)?;
             
             self.entity_mappings.insert(normalized_name.clone(), mapping.clone());
             if entity.name != normalized_name { // Store original if different from normalized
@@ -104,7 +124,7 @@ impl MariaDbDatasource {
             }
         }
         
-        println!("Entity mappings configured. Total distinct entities registered: {}", self.entity_mappings.len());
+        info!("Entity mappings configured. Total distinct entities registered: {}", self.entity_mappings.len());
         Ok(())
     }
     
@@ -114,7 +134,7 @@ impl MariaDbDatasource {
     /// # Returns
     /// Result indicating success or containing a connection error
     fn initialize_connection(&mut self) -> Result<(), Box<dyn Error>> {
-        let connection_url = self.config.make_url();
+        let connection_url = self.config.with_secrets()?.make_url();
         
         let pool = self.runtime.block_on(async {
             MySqlPoolOptions::new()
@@ -122,7 +142,7 @@ impl MariaDbDatasource {
                 .connect(&connection_url)
                 .await
                 .map_err(|e| {
-                    eprintln!("Failed to connect to database: {}", e);
+                    error!("Failed to connect to database: {}", e);
                     DataSourceError::ConnectionError(format!("Error connecting to MariaDB: {}", e))
                 })
         })?;
@@ -143,6 +163,34 @@ impl MariaDbDatasource {
         })
     }
 
+    /// Gets the connection pool statistics, combining live pool state with acquisition counters.
+    ///
+    /// # Returns
+    /// Option containing the statistics, or None if no connection has been established
+    pub fn pool_stats(&self) -> Option<PoolStats> {
+        self.pool.as_ref().map(|pool| {
+            self.metrics.snapshot(pool.size(), pool.num_idle(), pool.options().get_max_connections())
+        })
+    }
+
+    /// Acquires a connection from the pool, recording the wait time and failures.
+    ///
+    /// # Parameters
+    /// * `pool`: The connection pool to acquire from
+    /// * `metrics`: The counters to record the acquisition in
+    ///
+    /// # Returns
+    /// Result containing the pooled connection or a connection error
+    async fn acquire_connection(pool: &Pool<MySql>, metrics: &PoolMetrics) -> Result<PoolConnection<MySql>, Box<dyn Error>> {
+        let started = Instant::now();
+        let result = pool.acquire().await;
+        metrics.record_acquire(started.elapsed(), result.is_ok());
+        
+        result.map_err(|e| {
+            Box::new(DataSourceError::ConnectionError(format!("Error acquiring connection: {}", e))) as Box<dyn Error>
+        })
+    }
+
     /// Binds a Serde JSON value to an SQL query parameter with appropriate type conversion.
     ///
     /// # Parameters
@@ -203,13 +251,43 @@ impl MariaDbDatasource {
             sqlx_query = Self::bind_sqlx_value(sqlx_query, p_value)?;
         }
 
-        match tokio::time::timeout(DEFAULT_QUERY_TIMEOUT, sqlx_query.fetch_all(executor)).await {
-            Ok(Ok(rows)) => Ok(rows),
-            Ok(Err(e)) => Err(Box::new(DataSourceError::QueryError(format!("Error executing query: {}", e)))),
+        Self::traced(query_str, sqlx_query.fetch_all(executor)).await
+    }
+    
+    /// Runs a query within its span, failing when it takes longer than the query timeout.
+    /// The span holds the SQL text, with the parameters left out, and the duration of the query.
+    async fn traced<O>(query_str: &str, query: impl Future<Output = Result<O, sqlx::Error>>) -> Result<O, Box<dyn Error>> {
+        let operation = query_str.split_whitespace().next().unwrap_or_default().to_uppercase();
+        let span = info_span!(
+            "query",
+            otel.name = format!("{} {}", operation, DB_SYSTEM),
+            otel.kind = "client",
+            db.system.name = DB_SYSTEM,
+            db.operation.name = operation,
+            db.query.text = query_str,
+            db.duration_ms = field::Empty,
+        );
+        let started = Instant::now();
+        let result = tokio::time::timeout(DEFAULT_QUERY_TIMEOUT, query).instrument(span.clone()).await;
+        span.record("db.duration_ms", started.elapsed().as_secs_f64() * 1000.0);
+
+        match result {
+            Ok(Ok(output)) => Ok(output),
+            Ok(Err(e)) => Err(Self::query_error(e)),
             Err(_) => Err(Box::new(DataSourceError::QueryError(format!("Query timed out after {} seconds", DEFAULT_QUERY_TIMEOUT.as_secs())))),
         }
     }
-    
+
+    /// Converts a failed query into a datasource error. Duplicate values of unique keys become conflicts.
+    fn query_error(e: sqlx::Error) -> Box<dyn Error> {
+        match e.as_database_error() {
+            Some(database_error) if database_error.is_unique_violation() => {
+                Box::new(DataSourceError::Conflict(database_error.message().to_string()))
+            }
+            _ => Box::new(DataSourceError::QueryError(format!("Error executing query: {}", e))),
+        }
+    }
+
     /// Executes an SQL query that returns zero or one row.
     /// Handles parameter binding, execution, and timeout management.
     ///
@@ -233,11 +311,7 @@ impl MariaDbDatasource {
             sqlx_query = Self::bind_sqlx_value(sqlx_query, p_value)?;
         }
     
-        match tokio::time::timeout(DEFAULT_QUERY_TIMEOUT, sqlx_query.fetch_optional(executor)).await {
-            Ok(Ok(row_opt)) => Ok(row_opt),
-            Ok(Err(e)) => Err(Box::new(DataSourceError::QueryError(format!("Error executing query: {}", e)))),
-            Err(_) => Err(Box::new(DataSourceError::QueryError(format!("Query timed out after {} seconds", DEFAULT_QUERY_TIMEOUT.as_secs())))),
-        }
+        Self::traced(query_str, sqlx_query.fetch_optional(executor)).await
     }
 
     /// Executes an SQL command that modifies data (INSERT, UPDATE, DELETE).
@@ -263,11 +337,7 @@ impl MariaDbDatasource {
             sqlx_query = Self::bind_sqlx_value(sqlx_query, p_value)?;
         }
 
-        match tokio::time::timeout(DEFAULT_QUERY_TIMEOUT, sqlx_query.execute(executor)).await {
-            Ok(Ok(result)) => Ok(result.rows_affected()),
-            Ok(Err(e)) => Err(Box::new(DataSourceError::QueryError(format!("Error executing query: {}", e)))),
-            Err(_) => Err(Box::new(DataSourceError::QueryError(format!("Query timed out after {} seconds", DEFAULT_QUERY_TIMEOUT.as_secs())))),
-        }
+        Self::traced(query_str, sqlx_query.execute(executor)).await.map(|result| result.rows_affected())
     }
 
     /// Generates a SQL SELECT query to retrieve all entities of a given type.
@@ -281,34 +351,26 @@ impl MariaDbDatasource {
         let mapping = self.find_entity_mapping(entity_name)
             .ok_or_else(|| {
                 let available = self.entity_mappings.keys().map(|k| k.as_str()).collect::<Vec<_>>().join(", ");
-                eprintln!("ERROR: No mapping found for '{}'. Available: {}", entity_name, available);
+                error!("No mapping found for '{}'. Available: {}", entity_name, available);
                 DataSourceError::NotFound(format!("No mapping found for entity '{}'", entity_name))
             })?;
             
-        let columns: Vec<String> = mapping.fields.iter()
-            .map(|field| format!("`{}`", field.column_name))
-            .collect();
-            
-        Ok(format!("SELECT {} FROM `{}`", columns.join(", "), mapping.table_name))
+        Ok(mapping.select_sql())
     }
     
     /// Generates a SQL SELECT query to retrieve a single entity by its ID.
     ///
     /// # Parameters
     /// * `entity_name`: The name of the entity type to query
+    /// * `fields`: The fields to select along with the primary key, or every field if empty
     ///
     /// # Returns
     /// Result containing the generated SQL query string or an error
-    fn generate_select_by_id_query(&self, entity_name: &str) -> Result<String, Box<dyn Error>> {
+    fn generate_select_by_id_query(&self, entity_name: &str, fields: &[String]) -> Result<String, Box<dyn Error>> {
         let mapping = self.find_entity_mapping(entity_name)
             .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
-            
-        let columns: Vec<String> = mapping.fields.iter()
-            .map(|field| format!("`{}`", field.column_name))
-            .collect();
-            
-        Ok(format!("SELECT {} FROM `{}` WHERE `{}` = ?", 
-            columns.join(", "), mapping.table_name, mapping.primary_key))
+        
+        Ok(mapping.select_fields_by_id_sql(fields))
     }
     
     /// Generates a SQL INSERT query to create a new entity.
@@ -321,15 +383,8 @@ impl MariaDbDatasource {
     fn generate_insert_query(&self, entity_name: &str) -> Result<String, Box<dyn Error>> {
         let mapping = self.find_entity_mapping(entity_name)
             .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
-            
-        let columns: Vec<String> = mapping.fields.iter()
-            .map(|field| format!("`{}`", field.column_name))
-            .collect();
-            
-        let placeholders: Vec<String> = (0..mapping.fields.len()).map(|_| "?".to_string()).collect();
-            
-        Ok(format!("INSERT INTO `{}` ({}) VALUES ({})", 
-            mapping.table_name, columns.join(", "), placeholders.join(", ")))
+        
+        Ok(mapping.insert_sql())
     }
     
     /// Generates a SQL UPDATE query to modify an existing entity.
@@ -342,14 +397,10 @@ impl MariaDbDatasource {
     fn generate_update_query(&self, entity_name: &str) -> Result<String, Box<dyn Error>> {
         let mapping = self.find_entity_mapping(entity_name)
             .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
-            
-        let set_clauses: Vec<String> = mapping.fields.iter()
-            .filter(|field| field.field_name != mapping.primary_key) // PK should not be in SET
-            .map(|field| format!("`{}` = ?", field.column_name))
-            .collect();
-            
-        Ok(format!("UPDATE `{}` SET {} WHERE `{}` = ?", 
-            mapping.table_name, set_clauses.join(", "), mapping.primary_key))
+        
+        mapping.update_sql().ok_or_else(|| Box::new(DataSourceError::ValidationError(format!(
+            "Entity {} has no updatable fields", entity_name
+        ))) as Box<dyn Error>)
     }
     
     /// Generates a SQL DELETE query to remove an entity by its ID.
@@ -362,53 +413,113 @@ impl MariaDbDatasource {
     fn generate_delete_query(&self, entity_name: &str) -> Result<String, Box<dyn Error>> {
         let mapping = self.find_entity_mapping(entity_name)
             .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
-            
-        Ok(format!("DELETE FROM `{}` WHERE `{}` = ?", mapping.table_name, mapping.primary_key))
+        
+        Ok(mapping.delete_sql())
     }
     
-    /// Maps a database row to an entity object using the entity mapping configuration.
+    /// Maps a database row to a JSON object using a table mapping.
     /// Converts database column values to appropriate types based on field mappings.
     ///
     /// # Parameters
     /// * `row`: The database row containing entity data
-    /// * `entity_name`: The name of the entity type to map to
+    /// * `mapping`: The table mapping describing the row's columns
     ///
     /// # Returns
-    /// Result containing the mapped entity object or an error
-    fn map_row_to_entity<T: ApiEntity + DeserializeOwned>(&self, row: MySqlRow, entity_name: &str) -> Result<T, Box<dyn Error>> {
-        let mapping = self.find_entity_mapping(entity_name)
-            .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
-        
+    /// JSON object keyed by entity field names
+    fn map_row_to_json(row: &MySqlRow, mapping: &TableMapping) -> serde_json::Map<String, Value> {
         let mut json_object = serde_json::Map::new();
         
         for field in &mapping.fields {
-            let column_name = field.column_name.as_str();
-            let value: Option<Value> = match field.field_type.as_str() {
-                "string" => row.try_get(column_name).ok().map(Value::String),
-                "integer" => row.try_get::<i64, _>(column_name).ok().map(|v| Value::Number(v.into())),
-                "float" => row.try_get::<f64, _>(column_name).ok().and_then(|v| serde_json::Number::from_f64(v).map(Value::Number)),
-                "boolean" => row.try_get(column_name).ok().map(Value::Bool),
-                _ => row.try_get(column_name).ok().map(Value::String), // Fallback to string
-            };
-            
-            if let Some(v) = value {
+            if let Some(v) = Self::column_value(row, field.column_name.as_str(), &field.field_type) {
                 json_object.insert(field.field_name.clone(), v);
             }
         }
         
-        match serde_json::from_value(Value::Object(json_object.clone())) {
+        json_object
+    }
+
+    /// Reads a column of a row as a JSON value of the given field type.
+    ///
+    /// # Parameters
+    /// * `row`: The database row
+    /// * `column`: The name or position of the column
+    /// * `field_type`: The type of the field mapped to the column
+    ///
+    /// # Returns
+    /// The value, or None if the column is NULL or cannot be read as the type
+    fn column_value<I: sqlx::ColumnIndex<MySqlRow>>(row: &MySqlRow, column: I, field_type: &str) -> Option<Value> {
+        match field_type {
+            "string" => row.try_get(column).ok().map(Value::String),
+            "integer" => row.try_get::<i64, _>(column).ok().map(|v| Value::Number(v.into())),
+            "float" => row.try_get::<f64, _>(column).ok().and_then(|v| serde_json::Number::from_f64(v).map(Value::Number)),
+            "boolean" => row.try_get(column).ok().map(Value::Bool),
+            _ => row.try_get(column).ok().map(Value::String), // Fallback to string
+        }
+    }
+
+    /// Converts a JSON object built from a row into an entity object.
+    ///
+    /// # Parameters
+    /// * `json_object`: The JSON object holding the entity data
+    /// * `entity_name`: The name of the entity type to map to
+    ///
+    /// # Returns
+    /// Result containing the mapped entity object or an error
+    fn json_to_entity<T: ApiEntity + DeserializeOwned>(json_object: serde_json::Map<String, Value>, entity_name: &str) -> Result<T, Box<dyn Error>> {
+        let keys: Vec<String> = json_object.keys().cloned().collect();
+        
+        match serde_json::from_value(Value::Object(json_object)) {
             Ok(entity) => Ok(entity),
             Err(e) => {
-                let keys: Vec<String> = json_object.keys().cloned().collect();
                 let error_msg = format!(
                     "Error deserializing entity '{}': {}. Fields available: {}", 
                     entity_name, e, keys.join(", ")
                 );
-                eprintln!("Deserialization error: {}", error_msg);
+                error!("Deserialization error: {}", error_msg);
                 Err(Box::new(DataSourceError::MappingError(error_msg)))
             }
         }
     }
+
+    /// Maps database rows to entity objects.
+    ///
+    /// # Parameters
+    /// * `rows`: The database rows containing entity data
+    /// * `entity_name`: The name of the entity type to map to
+    ///
+    /// # Returns
+    /// Result containing the mapped entity objects or an error
+    fn map_rows_to_entities<T: ApiEntity + DeserializeOwned>(&self, rows: Vec<MySqlRow>, entity_name: &str) -> Result<Vec<T>, Box<dyn Error>> {
+        let mapping = self.find_entity_mapping(entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
+        
+        rows.iter()
+            .map(|row| Self::json_to_entity(Self::map_row_to_json(row, mapping), entity_name))
+            .collect()
+    }
+    
+    /// Finds the join table of a many-to-many relationship of an entity.
+    ///
+    /// # Parameters
+    /// * `entity_name`: The name of the entity declaring the relationship
+    /// * `relationship`: The name of the relationship
+    ///
+    /// # Returns
+    /// Result containing the entity mapping and the join table, or an error if the relationship is not many-to-many
+    fn find_join_table(&self, entity_name: &str, relationship: &str) -> Result<(&TableMapping, &JoinTable), Box<dyn Error>> {
+        let mapping = self.find_entity_mapping(entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
+        let relation = mapping.relationships.iter()
+            .find(|r| r.name.eq_ignore_ascii_case(relationship))
+            .ok_or_else(|| DataSourceError::NotFound(format!("Relationship {} not found on entity {}", relationship, entity_name)))?;
+        
+        match (&relation.relationship_type, &relation.join_table) {
+            (RelationshipType::ManyToMany, Some(join_table)) => Ok((mapping, join_table)),
+            _ => Err(Box::new(DataSourceError::ValidationError(format!(
+                "Relationship {} is not a many-to-many relationship with a join table", relationship
+            )))),
+        }
+    }
     
     /// Converts an entity object to a vector of values for use in SQL queries.
     /// Orders values according to the entity mapping field order.
@@ -424,17 +535,12 @@ impl MariaDbDatasource {
         let mapping = self.find_entity_mapping(entity_name)
             .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
         
-        let mut values = Vec::new();
-        if let Value::Object(map) = entity_json {
-            for field in &mapping.fields {
-                values.push(map.get(&field.field_name).cloned().unwrap_or(Value::Null));
-            }
-        } else {
-            return Err(Box::new(DataSourceError::SerializationError(
+        match entity_json {
+            Value::Object(map) => Ok(mapping.insert_values(&map)),
+            _ => Err(Box::new(DataSourceError::SerializationError(
                 "The entity could not be serialized as a JSON object".to_string()
-            )));
+            ))),
         }
-        Ok(values)
     }
 
     /// Prepares values for an UPDATE query, excluding the primary key field from SET clause values
@@ -452,21 +558,11 @@ impl MariaDbDatasource {
             .ok_or_else(|| DataSourceError::NotFound(format!("No mapping for entity {}", entity_name)))?;
         let entity_json = serde_json::to_value(item)?;
     
-        let mut values = Vec::new();
-        if let Value::Object(map) = entity_json {
-            for field in &mapping.fields {
-                if field.field_name != mapping.primary_key { 
-                    values.push(map.get(&field.field_name).cloned().unwrap_or(Value::Null));
-                }
-            }
-            // Add the ID for the WHERE clause. Assuming ID is string for simplicity.
-            // This might need adjustment if PKs are not always strings or require specific type handling.
-            values.push(Value::String(id.to_string())); 
-            Ok(values)
-        } else {
-            Err(Box::new(DataSourceError::SerializationError(
+        match entity_json {
+            Value::Object(map) => Ok(mapping.update_values(&map, id)),
+            _ => Err(Box::new(DataSourceError::SerializationError(
                 "Entity could not be serialized as JSON object".to_string()
-            )))
+            ))),
         }
     }
     
@@ -500,7 +596,7 @@ impl MariaDbDatasource {
                         ("boolean", Value::Bool(_)) => {},
                         (_, Value::Null) => {},
                         (expected, actual) => {
-                            eprintln!("Warning: Field '{}' expected type {}, but got {:?} during validation", 
+                            warn!("Field '{}' expected type {}, but got {:?} during validation", 
                                     field.field_name, expected, actual.as_str().unwrap_or("complex type"));
                         }
                     }
@@ -512,6 +608,78 @@ impl MariaDbDatasource {
         }
     }
 
+    /// Prepares the SQL of a write of a batch, like `create`, `update` and `delete` run it.
+    ///
+    /// # Parameters
+    /// * `write`: The write to prepare
+    ///
+    /// # Returns
+    /// Result containing the statement to run in the transaction or an error
+    fn batch_statement<U: ApiEntity + Serialize>(&self, write: &BatchWrite<U>) -> Result<BatchStatement, Box<dyn Error>> {
+        match write {
+            BatchWrite::Create { entity, item } => {
+                self.validate_entity(item, entity)?;
+                let mapping = self.find_entity_mapping(entity)
+                    .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity)))?;
+                let reload = match mapping.field_for_column(&mapping.primary_key) {
+                    _ if !mapping.fields.iter().any(|field| field.generated) => None,
+                    Some(field) if field.generated => Some(None),
+                    Some(field) => serde_json::to_value(item)?.get(&field.field_name).map(|key| Some(value_key(key))),
+                    None => None,
+                };
+                Ok(BatchStatement {
+                    sql: self.generate_insert_query(entity)?,
+                    params: self.entity_to_query_values(item, entity)?,
+                    reload: reload.map(|key| Ok::<_, Box<dyn Error>>((self.generate_select_by_id_query(entity, &[])?, key))).transpose()?,
+                    requires_row: false,
+                })
+            }
+            BatchWrite::Update { entity, id, item } => {
+                self.validate_entity(item, entity)?;
+                Ok(BatchStatement {
+                    sql: self.generate_update_query(entity)?,
+                    params: self.prepare_update_values(item, entity, id)?,
+                    reload: None,
+                    requires_row: false,
+                })
+            }
+            BatchWrite::Delete { entity, id } => Ok(BatchStatement {
+                sql: self.generate_delete_query(entity)?,
+                params: vec![Value::String(id.clone())],
+                reload: None,
+                requires_row: true,
+            }),
+        }
+    }
+
+    /// Runs a write of a batch on the connection of its transaction.
+    ///
+    /// # Parameters
+    /// * `conn`: The connection running the transaction
+    /// * `statement`: The prepared write
+    ///
+    /// # Returns
+    /// Result containing the reloaded row of a created entity, if it has generated values, or an error
+    async fn run_batch_statement(conn: &mut MySqlConnection, statement: BatchStatement) -> Result<Option<MySqlRow>, Box<dyn Error>> {
+        let rows_affected = Self::run_execute_async(&mut *conn, &statement.sql, statement.params).await?;
+        if statement.requires_row && rows_affected == 0 {
+            return Err(Box::new(DataSourceError::NotFound("The entity to write was not found".to_string())));
+        }
+
+        let Some((query_str, key)) = statement.reload else {
+            return Ok(None);
+        };
+        let key = match key {
+            Some(key) => key,
+            // LAST_INSERT_ID is per connection, so it must be read on the one of the transaction
+            None => Self::run_query_optional_async(&mut *conn, "SELECT LAST_INSERT_ID()", Vec::new()).await?
+                .and_then(|row| row.try_get::<u64, _>(0).ok())
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+        };
+        Self::run_query_optional_async(&mut *conn, &query_str, vec![Value::String(key)]).await
+    }
+
     /// Extracts the ID value from an entity object.
     ///
     /// # Parameters
@@ -596,6 +764,7 @@ impl Clone for MariaDbDatasource {
             config: self.config.clone(),
             pool: self.pool.clone(),
             entity_mappings: self.entity_mappings.clone(),
+            metrics: self.metrics.clone(),
             runtime: Runtime::new().unwrap(), // Consider Arc<Runtime> if clones are frequent
         }
     }
@@ -625,20 +794,84 @@ where
     ///
     /// # Returns
     /// Result containing vector of entity objects or an error
+    #[instrument(skip_all, fields(entity = entity_name_override))]
     fn get_all(&self, entity_name_override: Option<&str>) -> Result<Vec<T>, Box<dyn Error>> {
         let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
         let pool = self.get_pool_or_err()?;
         let query_str = self.generate_select_query(&entity_name)?;
         
-        let rows = self.runtime.block_on(Self::run_query_async(pool, &query_str, Vec::new()))?;
+        let rows = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_query_async(&mut *conn, &query_str, Vec::new()).await
+        })?;
         
         if rows.is_empty() {
             return Ok(Vec::new());
         }
         
-        rows.into_iter()
-            .map(|row| self.map_row_to_entity(row, &entity_name))
-            .collect()
+        self.map_rows_to_entities(rows, &entity_name)
+    }
+
+    /// Retrieves a page of entities with LIMIT/OFFSET, or after a primary key, counting the matching
+    /// rows in the same connection when requested.
+    ///
+    /// # Parameters
+    /// * `query`: The offset, limit and starting key of the page
+    /// * `entity_name_override`: Optional explicit entity name to use instead of T::entity_name()
+    ///
+    /// # Returns
+    /// Result containing the page of entity objects and the total number of rows, or an error
+    #[instrument(skip_all, fields(entity = entity_name_override))]
+    fn get_page(&self, query: &ListQuery, entity_name_override: Option<&str>) -> Result<Page<T>, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let pool = self.get_pool_or_err()?;
+        let mapping = self.find_entity_mapping(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
+        let (query_str, params) = mapping.select_list_sql(query);
+        let count = query.with_total.then(|| mapping.count_sql(query));
+        
+        let (total, rows) = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            let total = match count {
+                Some((count_str, count_params)) => {
+                    let count_row = Self::run_query_optional_async(&mut *conn, &count_str, count_params).await?;
+                    Some(count_row.and_then(|row| row.try_get::<i64, _>(0).ok()).unwrap_or(0).max(0) as u64)
+                }
+                None => None,
+            };
+            let rows = Self::run_query_async(&mut *conn, &query_str, params).await?;
+            Ok::<_, Box<dyn Error>>((total, rows))
+        })?;
+        
+        let items: Vec<T> = if rows.is_empty() { Vec::new() } else { self.map_rows_to_entities(rows, &entity_name)? };
+        let last_key = match items.last() {
+            Some(item) => mapping.key_of(&serde_json::to_value(item)?),
+            None => None,
+        };
+        Ok(Page { items, total, last_key })
+    }
+
+    /// Counts the entities meeting the filters with a single COUNT query.
+    ///
+    /// # Parameters
+    /// * `filters`: The conditions selecting the rows to count
+    /// * `entity_name_override`: Optional explicit entity name to use instead of T::entity_name()
+    ///
+    /// # Returns
+    /// Result containing the number of matching rows or an error
+    #[instrument(skip_all, fields(entity = entity_name_override))]
+    fn count(&self, filters: &[Filter], entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let pool = self.get_pool_or_err()?;
+        let mapping = self.find_entity_mapping(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
+        let (query_str, params) = mapping.count_sql(&ListQuery { filters: filters.to_vec(), ..ListQuery::default() });
+
+        let count_row = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_query_optional_async(&mut *conn, &query_str, params).await
+        })?;
+        Ok(count_row.and_then(|row| row.try_get::<i64, _>(0).ok()).unwrap_or(0).max(0) as u64)
     }
 
     /// Retrieves a specific entity of type T by its ID.
@@ -649,16 +882,34 @@ where
     ///
     /// # Returns
     /// Result containing Option with entity if found, or None if not found
+    #[instrument(skip_all, fields(entity = entity_name_override))]
     fn get_by_id(&self, id: &str, entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>> {
+        self.get_fields_by_id(id, &[], entity_name_override)
+    }
+
+    /// Retrieves the given fields of a specific entity by its ID, selecting only their columns.
+    ///
+    /// # Parameters
+    /// * `id`: The entity's unique identifier
+    /// * `fields`: The fields to select along with the primary key, or every field if empty
+    /// * `entity_name_override`: Optional explicit entity name to use instead of T::entity_name()
+    ///
+    /// # Returns
+    /// Result containing Option with entity if found, or None if not found
+    #[instrument(skip_all, fields(entity = entity_name_override))]
+    fn get_fields_by_id(&self, id: &str, fields: &[String], entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>> {
         let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
         let pool = self.get_pool_or_err()?;
-        let query_str = self.generate_select_by_id_query(&entity_name)?;
+        let query_str = self.generate_select_by_id_query(&entity_name, fields)?;
         let params = vec![Value::String(id.to_string())];
         
-        let row_opt = self.runtime.block_on(Self::run_query_optional_async(pool, &query_str, params))?;
+        let row_opt = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_query_optional_async(&mut *conn, &query_str, params).await
+        })?;
         
         match row_opt {
-            Some(r) => self.map_row_to_entity(r, &entity_name).map(Some),
+            Some(r) => Ok(self.map_rows_to_entities(vec![r], &entity_name)?.pop()),
             None => Ok(None),
         }
     }
@@ -671,17 +922,43 @@ where
     ///
     /// # Returns
     /// Result containing the created entity object or an error
+    #[instrument(skip_all, fields(entity = entity_name_override))]
     fn create(&self, item: T, entity_name_override: Option<&str>) -> Result<T, Box<dyn Error>> {
         let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
         self.validate_entity(&item, &entity_name)?;
         let pool = self.get_pool_or_err()?;
         let query_str = self.generate_insert_query(&entity_name)?;
         let values = self.entity_to_query_values(&item, &entity_name)?;
+        let mapping = self.find_entity_mapping(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
+        let has_generated = mapping.fields.iter().any(|field| field.generated);
+        
+        let insert_id = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_execute_async(&mut *conn, &query_str, values).await?;
+            if !has_generated {
+                return Ok::<_, Box<dyn Error>>(None);
+            }
+            // LAST_INSERT_ID is per connection, so it must be read on the same one
+            let row = Self::run_query_optional_async(&mut *conn, "SELECT LAST_INSERT_ID()", Vec::new()).await?;
+            Ok(row.and_then(|row| row.try_get::<u64, _>(0).ok()))
+        })?;
         
-        self.runtime.block_on(Self::run_execute_async(pool, &query_str, values))?;
+        if !has_generated {
+            return Ok(item);
+        }
         
-        // Note: This returns the input item. If DB generates ID/timestamps, this won't reflect them.
-        Ok(item) 
+        // Reload the row so the values generated by the database are returned
+        let primary_key = mapping.field_for_column(&mapping.primary_key);
+        let id = match primary_key {
+            Some(field) if field.generated => insert_id.map(|id| id.to_string()),
+            Some(field) => serde_json::to_value(&item)?.get(&field.field_name).map(value_key),
+            None => None,
+        };
+        match id {
+            Some(id) => Ok(self.get_by_id(&id, Some(&entity_name))?.unwrap_or(item)),
+            None => Ok(item),
+        }
     }
 
     /// Updates an existing entity in the database.
@@ -693,6 +970,7 @@ where
     ///
     /// # Returns
     /// Result containing the updated entity object or an error
+    #[instrument(skip_all, fields(entity = entity_name_override))]
     fn update(&self, id: &str, item: T, entity_name_override: Option<&str>) -> Result<T, Box<dyn Error>> {
         let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
         self.validate_entity(&item, &entity_name)?;
@@ -700,11 +978,73 @@ where
         let query_str = self.generate_update_query(&entity_name)?;
         let values = self.prepare_update_values(&item, &entity_name, id)?;
 
-        self.runtime.block_on(Self::run_execute_async(pool, &query_str, values))?;
+        self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_execute_async(&mut *conn, &query_str, values).await
+        })?;
         
         Ok(item)
     }
 
+    /// Gets the distinct values of a field among the rows meeting the filters with a single
+    /// SELECT DISTINCT query, or a GROUP BY query when the values are counted.
+    ///
+    /// # Parameters
+    /// * `field`: The field whose values are selected
+    /// * `filters`: The conditions selecting the rows
+    /// * `with_counts`: Whether to count the rows holding each value
+    /// * `limit`: The maximum number of values to return
+    /// * `entity_name_override`: Optional explicit entity name to use instead of T::entity_name()
+    ///
+    /// # Returns
+    /// Result containing the values in ascending order or an error
+    #[instrument(skip_all, fields(entity = entity_name_override))]
+    fn distinct_values(&self, field: &str, filters: &[Filter], with_counts: bool, limit: u64, entity_name_override: Option<&str>) -> Result<Vec<DistinctValue>, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let pool = self.get_pool_or_err()?;
+        let mapping = self.find_entity_mapping(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
+        let missing = || DataSourceError::ValidationError(format!("Entity {} has no column for field {}", entity_name, field));
+        let field_type = mapping.field_for_column(field).map(|field| field.field_type.clone()).ok_or_else(missing)?;
+        let (query_str, params) = mapping.distinct_sql(field, filters, with_counts, limit).ok_or_else(missing)?;
+
+        let rows = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_query_async(&mut *conn, &query_str, params).await
+        })?;
+        Ok(rows.iter()
+            .map(|row| DistinctValue {
+                value: Self::column_value(row, 0, &field_type).unwrap_or(Value::Null),
+                count: with_counts.then(|| row.try_get::<i64, _>(1).unwrap_or(0).max(0) as u64),
+            })
+            .collect())
+    }
+
+    /// Applies a partial document to every row meeting the filters in a single UPDATE statement.
+    ///
+    /// # Parameters
+    /// * `filters`: The conditions selecting the rows to update
+    /// * `changes`: The fields to write, keyed by field name
+    /// * `entity_name_override`: Optional explicit entity name to use instead of T::entity_name()
+    ///
+    /// # Returns
+    /// Result containing the number of updated rows or an error
+    #[instrument(skip_all, fields(entity = entity_name_override))]
+    fn update_matching(&self, filters: &[Filter], changes: &Map<String, Value>, entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let pool = self.get_pool_or_err()?;
+        let mapping = self.find_entity_mapping(&entity_name)
+            .ok_or_else(|| DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name)))?;
+        let (query_str, params) = mapping.update_matching_sql(filters, changes).ok_or_else(|| DataSourceError::ValidationError(format!(
+            "The changes hold no updatable field of entity {}", entity_name
+        )))?;
+
+        self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_execute_async(&mut *conn, &query_str, params).await
+        })
+    }
+
     /// Deletes an entity from the database by its ID.
     ///
     /// # Parameters
@@ -713,17 +1053,126 @@ where
     ///
     /// # Returns
     /// Result containing boolean indicating success (true if entity was deleted) or an error
+    #[instrument(skip_all, fields(entity = entity_name_override))]
     fn delete(&self, id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
         let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
         let pool = self.get_pool_or_err()?;
         let query_str = self.generate_delete_query(&entity_name)?;
         let params = vec![Value::String(id.to_string())];
         
-        let rows_affected = self.runtime.block_on(Self::run_execute_async(pool, &query_str, params))?;
+        let rows_affected = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_execute_async(&mut *conn, &query_str, params).await
+        })?;
         
         Ok(rows_affected > 0)
     }
     
+    /// Gets the connection pool statistics of this datasource.
+    ///
+    /// # Returns
+    /// Option containing the statistics, or None if no connection has been established
+    fn pool_stats(&self) -> Option<PoolStats> {
+        MariaDbDatasource::pool_stats(self)
+    }
+
+    /// Inserts a row in the join table of a many-to-many relationship.
+    ///
+    /// # Parameters
+    /// * `id`: The identifier of the entity declaring the relationship
+    /// * `relationship`: The name of the relationship
+    /// * `related_id`: The identifier of the related entity
+    /// * `entity_name_override`: Optional explicit entity name to use instead of T::entity_name()
+    ///
+    /// # Returns
+    /// Result containing true if the link was created, false if it already existed
+    #[instrument(skip_all, fields(entity = entity_name_override))]
+    fn link_related(&self, id: &str, relationship: &str, related_id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let (mapping, join_table) = self.find_join_table(&entity_name, relationship)?;
+        let pool = self.get_pool_or_err()?;
+        let query_str = format!(
+            "INSERT IGNORE INTO {} ({}, {}) VALUES (?, ?)",
+            mapping.qualify(&join_table.table), quote_identifier(&join_table.local_key), quote_identifier(&join_table.related_key)
+        );
+        let params = vec![Value::String(id.to_string()), Value::String(related_id.to_string())];
+        
+        let rows_affected = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_execute_async(&mut *conn, &query_str, params).await
+        })?;
+        
+        Ok(rows_affected > 0)
+    }
+
+    /// Deletes the row of the join table of a many-to-many relationship.
+    ///
+    /// # Parameters
+    /// * `id`: The identifier of the entity declaring the relationship
+    /// * `relationship`: The name of the relationship
+    /// * `related_id`: The identifier of the related entity
+    /// * `entity_name_override`: Optional explicit entity name to use instead of T::entity_name()
+    ///
+    /// # Returns
+    /// Result containing true if the link was removed, false if it did not exist
+    #[instrument(skip_all, fields(entity = entity_name_override))]
+    fn unlink_related(&self, id: &str, relationship: &str, related_id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let (mapping, join_table) = self.find_join_table(&entity_name, relationship)?;
+        let pool = self.get_pool_or_err()?;
+        let query_str = format!(
+            "DELETE FROM {} WHERE {} = ? AND {} = ?",
+            mapping.qualify(&join_table.table), quote_identifier(&join_table.local_key), quote_identifier(&join_table.related_key)
+        );
+        let params = vec![Value::String(id.to_string()), Value::String(related_id.to_string())];
+        
+        let rows_affected = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await?;
+            Self::run_execute_async(&mut *conn, &query_str, params).await
+        })?;
+        
+        Ok(rows_affected > 0)
+    }
+
+    /// Applies the writes of a batch in a single transaction, rolled back when any of them fails.
+    ///
+    /// # Parameters
+    /// * `writes`: The writes to apply, in order
+    ///
+    /// # Returns
+    /// Result containing the written entities, or None for deletions, or the failed write
+    #[instrument(skip_all, fields(writes = writes.len()))]
+    fn write_batch(&self, writes: Vec<BatchWrite<T>>) -> Result<Vec<Option<T>>, BatchFailure> {
+        let failed = |index: Option<usize>| move |error: Box<dyn Error>| BatchFailure { index, error };
+        let statements = writes.iter().enumerate()
+            .map(|(index, write)| self.batch_statement(write).map_err(failed(Some(index))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let pool = self.get_pool_or_err().map_err(failed(None))?;
+
+        let rows = self.runtime.block_on(async {
+            let mut conn = Self::acquire_connection(pool, &self.metrics).await.map_err(failed(None))?;
+            let mut transaction = conn.begin().await.map_err(|e| failed(None)(Self::query_error(e)))?;
+            let mut rows = Vec::with_capacity(statements.len());
+            for (index, statement) in statements.into_iter().enumerate() {
+                // Returning before the commit drops the transaction, which rolls it back
+                rows.push(Self::run_batch_statement(&mut transaction, statement).await.map_err(failed(Some(index)))?);
+            }
+            transaction.commit().await.map_err(|e| failed(None)(Self::query_error(e)))?;
+            Ok(rows)
+        })?;
+
+        // Rows are mapped once the transaction is over
+        Ok(writes.into_iter().zip(rows)
+            .map(|(write, row)| match (write, row) {
+                (BatchWrite::Create { entity, item }, Some(row)) => {
+                    self.map_rows_to_entities(vec![row], &entity).ok().and_then(|mut items| items.pop()).or(Some(item))
+                }
+                (BatchWrite::Create { item, .. } | BatchWrite::Update { item, .. }, _) => Some(item),
+                (BatchWrite::Delete { .. }, _) => None,
+            })
+            .collect())
+    }
+
     /// Creates a clone of this datasource as a boxed DataSource trait object.
     ///
     /// # Returns
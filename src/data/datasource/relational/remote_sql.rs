@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use crate::api::common::api_entity::ApiEntity;
+use crate::config::specific::entity_config::Entity;
+use crate::data::datasource::base::{DataSource, DataSourceError, Filter, ListQuery, Page};
+use crate::data::datasource::relational::base::{TableMapping, create_table_mapping, value_key};
+use tracing::error;
+
+/// Outcome of a statement run through a `SqlTransport`
+#[derive(Debug, Clone, Default)]
+pub struct SqlExecution {
+    /// Number of rows changed by the statement
+    pub rows_affected: u64,
+    /// Id generated for the inserted row, if any
+    pub last_insert_id: Option<u64>,
+}
+
+/// Runs SQL statements on a database reached without a native driver, such as a
+/// Cloudflare D1 binding or an HTTP SQL API. Implemented by the host runtime.
+///
+/// Statements use `?` placeholders and backtick-quoted identifiers. Report duplicate values of unique
+/// keys as `DataSourceError::Conflict` so they are answered with 409.
+pub trait SqlTransport: Send + Sync {
+    /// Runs a query, returning every row as a JSON object keyed by column name
+    fn query(&self, sql: &str, params: &[Value]) -> Result<Vec<Map<String, Value>>, Box<dyn Error>>;
+
+    /// Runs a statement that does not return rows
+    fn execute(&self, sql: &str, params: &[Value]) -> Result<SqlExecution, Box<dyn Error>>;
+}
+
+/// Relational datasource running the generated SQL through a `SqlTransport`.
+/// It needs no connection pool or async runtime, so it builds without the `native` feature.
+#[derive(Clone)]
+pub struct RemoteSqlDatasource {
+    transport: Arc<dyn SqlTransport>,
+    entity_mappings: HashMap<String, TableMapping>,
+}
+
+impl RemoteSqlDatasource {
+    /// Creates a datasource over a transport, without entity mappings
+    pub fn new(transport: Arc<dyn SqlTransport>) -> Self {
+        Self {
+            transport,
+            entity_mappings: HashMap::new(),
+        }
+    }
+
+    /// Registers the table mappings of the entities, keyed by lowercase entity name. Entities
+    /// with invalid identifiers are left unmapped.
+    pub fn configure_entity_mappings(&mut self, entities: &[Entity]) {
+        for entity in entities {
+            match create_table_mapping(entity) {
+                Ok(mapping) => {
+                    self.entity_mappings.insert(entity.name.to_lowercase(), mapping);
+                }
+                Err(e) => error!("Skipping the table mapping of {}: {}", entity.name, e),
+            }
+        }
+    }
+
+    fn find_entity_mapping(&self, entity_name: &str) -> Result<&TableMapping, Box<dyn Error>> {
+        self.entity_mappings.get(&entity_name.to_lowercase())
+            .ok_or_else(|| Box::new(DataSourceError::NotFound(format!("No mapping found for entity {}", entity_name))) as Box<dyn Error>)
+    }
+
+    /// Converts a row keyed by column name into an entity
+    fn row_to_entity<T: DeserializeOwned>(mapping: &TableMapping, mut row: Map<String, Value>, entity_name: &str) -> Result<T, Box<dyn Error>> {
+        let mut object = Map::new();
+        for field in &mapping.fields {
+            if let Some(value) = row.remove(&field.column_name) {
+                object.insert(field.field_name.clone(), value);
+            }
+        }
+
+        serde_json::from_value(Value::Object(object)).map_err(|e| {
+            Box::new(DataSourceError::MappingError(format!("Error deserializing entity '{}': {}", entity_name, e))) as Box<dyn Error>
+        })
+    }
+
+    /// Counts the rows matching the filters of a list query
+    fn count_rows(&self, mapping: &TableMapping, query: &ListQuery) -> Result<u64, Box<dyn Error>> {
+        let (count_str, count_params) = mapping.count_sql(query);
+        let count = self.transport.query(&count_str, &count_params)?
+            .into_iter().next()
+            .and_then(|row| row.into_iter().next())
+            .and_then(|(_, count)| count.as_u64());
+        Ok(count.unwrap_or(0))
+    }
+
+    /// Serializes an entity into a JSON object
+    fn entity_to_object<T: Serialize>(item: &T) -> Result<Map<String, Value>, Box<dyn Error>> {
+        match serde_json::to_value(item)? {
+            Value::Object(map) => Ok(map),
+            _ => Err(Box::new(DataSourceError::SerializationError(
+                "The entity could not be serialized as a JSON object".to_string()
+            ))),
+        }
+    }
+}
+
+impl<T> DataSource<T> for RemoteSqlDatasource
+where
+    T: ApiEntity + DeserializeOwned + Serialize + Send + Sync + Clone + 'static
+{
+    fn get_all(&self, entity_name_override: Option<&str>) -> Result<Vec<T>, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let mapping = self.find_entity_mapping(&entity_name)?;
+
+        self.transport.query(&mapping.select_sql(), &[])?
+            .into_iter()
+            .map(|row| Self::row_to_entity(mapping, row, &entity_name))
+            .collect()
+    }
+
+    fn get_page(&self, query: &ListQuery, entity_name_override: Option<&str>) -> Result<Page<T>, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let mapping = self.find_entity_mapping(&entity_name)?;
+
+        let total = if query.with_total {
+            Some(self.count_rows(mapping, query)?)
+        } else {
+            None
+        };
+        let (query_str, params) = mapping.select_list_sql(query);
+        let items: Vec<T> = self.transport.query(&query_str, &params)?
+            .into_iter()
+            .map(|row| Self::row_to_entity(mapping, row, &entity_name))
+            .collect::<Result<_, _>>()?;
+        let last_key = match items.last() {
+            Some(item) => mapping.key_of(&serde_json::to_value(item)?),
+            None => None,
+        };
+        Ok(Page { items, total, last_key })
+    }
+
+    fn count(&self, filters: &[Filter], entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let mapping = self.find_entity_mapping(&entity_name)?;
+        let query = ListQuery { filters: filters.to_vec(), ..ListQuery::default() };
+        self.count_rows(mapping, &query)
+    }
+
+    fn get_by_id(&self, id: &str, entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>> {
+        self.get_fields_by_id(id, &[], entity_name_override)
+    }
+
+    fn get_fields_by_id(&self, id: &str, fields: &[String], entity_name_override: Option<&str>) -> Result<Option<T>, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let mapping = self.find_entity_mapping(&entity_name)?;
+
+        let rows = self.transport.query(&mapping.select_fields_by_id_sql(fields), &[Value::String(id.to_string())])?;
+        rows.into_iter().next()
+            .map(|row| Self::row_to_entity(mapping, row, &entity_name))
+            .transpose()
+    }
+
+    fn create(&self, item: T, entity_name_override: Option<&str>) -> Result<T, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let mapping = self.find_entity_mapping(&entity_name)?;
+        let object = Self::entity_to_object(&item)?;
+
+        let execution = self.transport.execute(&mapping.insert_sql(), &mapping.insert_values(&object))?;
+        if !mapping.fields.iter().any(|field| field.generated) {
+            return Ok(item);
+        }
+
+        // Reload the row so the values generated by the database are returned
+        let id = match mapping.field_for_column(&mapping.primary_key) {
+            Some(field) if field.generated => execution.last_insert_id.map(|id| id.to_string()),
+            Some(field) => object.get(&field.field_name).map(value_key),
+            None => None,
+        };
+        match id {
+            Some(id) => Ok(self.get_by_id(&id, Some(&entity_name))?.unwrap_or(item)),
+            None => Ok(item),
+        }
+    }
+
+    fn update(&self, id: &str, item: T, entity_name_override: Option<&str>) -> Result<T, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let mapping = self.find_entity_mapping(&entity_name)?;
+        let query_str = mapping.update_sql().ok_or_else(|| DataSourceError::ValidationError(format!(
+            "Entity {} has no updatable fields", entity_name
+        )))?;
+
+        let object = Self::entity_to_object(&item)?;
+        self.transport.execute(&query_str, &mapping.update_values(&object, id))?;
+        Ok(item)
+    }
+
+    fn update_matching(&self, filters: &[Filter], changes: &Map<String, Value>, entity_name_override: Option<&str>) -> Result<u64, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let mapping = self.find_entity_mapping(&entity_name)?;
+        let (query_str, params) = mapping.update_matching_sql(filters, changes).ok_or_else(|| DataSourceError::ValidationError(format!(
+            "The changes hold no updatable field of entity {}", entity_name
+        )))?;
+
+        Ok(self.transport.execute(&query_str, &params)?.rows_affected)
+    }
+
+    fn delete(&self, id: &str, entity_name_override: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let entity_name = entity_name_override.map(|s| s.to_string()).unwrap_or_else(|| T::entity_name());
+        let mapping = self.find_entity_mapping(&entity_name)?;
+
+        let execution = self.transport.execute(&mapping.delete_sql(), &[Value::String(id.to_string())])?;
+        Ok(execution.rows_affected > 0)
+    }
+
+    fn box_clone(&self) -> Box<dyn DataSource<T>> {
+        Box::new(self.clone())
+    }
+}
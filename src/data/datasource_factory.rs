@@ -1,12 +1,23 @@
 use std::collections::HashMap;
-use std::error::Error;
+use std::sync::Arc;
 use serde::{Serialize, de::DeserializeOwned};
 use crate::config::configuration::Config;
-use crate::config::specific::database_config::DatabaseType;
-use crate::config::specific::entity_config::Entity;
 use crate::data::datasource::base::DataSource;
+use crate::data::datasource::memory::fake_data;
+use crate::data::datasource::memory::in_memory::InMemoryDatasource;
+use crate::data::datasource::relational::remote_sql::{RemoteSqlDatasource, SqlTransport};
+#[cfg(feature = "native")]
+use std::error::Error;
+#[cfg(feature = "native")]
+use crate::config::specific::database_config::DatabaseType;
+#[cfg(feature = "native")]
+use crate::data::datasource::document::surrealdb::SurrealDbDatasource;
+#[cfg(feature = "native")]
 use crate::data::datasource::relational::mariadb::MariaDbDatasource;
 use crate::api::common::api_entity::ApiEntity;
+use tracing::{error, info};
+#[cfg(feature = "native")]
+use tracing::debug;
 
 /// Factory responsible for creating and managing datasources for entities
 /// This structure handles the creation and mapping of datasources based on configuration
@@ -15,33 +26,47 @@ pub struct DataSourceFactory;
 impl DataSourceFactory {
     /// Creates datasources for all entities defined in the configuration
     /// Returns a HashMap with entity names as keys and their corresponding datasources as values
+    #[cfg(feature = "native")]
     pub fn create_datasources<T: 'static + ApiEntity + Serialize + DeserializeOwned + Send + Sync>(
         config: &Config
     ) -> HashMap<String, Box<dyn DataSource<T>>> {
         let mut datasources = HashMap::new();
         
-        println!("Initializing datasources...");
-        println!("Advanced entities: {}", config.entities_advanced.len());
-        println!("Basic entities: {}", config.entities_basic.len());
-        println!("Database type: {:?}", config.database.db_type);
+        debug!("Initializing datasources...");
+        debug!("Advanced entities: {}", config.entities_advanced.len());
+        debug!("Basic entities: {}", config.entities_basic.len());
+        debug!("Database type: {:?}", config.database.db_type);
         
         // First, create a single database connection to be shared among all entities
         let db_connection = match config.database.db_type {
             DatabaseType::MySQL => {
-                println!("Creating MariaDB/MySQL connection");
+                debug!("Creating MariaDB/MySQL connection");
                 match Self::create_mariadb_datasource::<T>(config) {
                     Ok(db) => {
-                        println!("Successfully created MariaDB/MySQL connection");
+                        info!("Successfully created MariaDB/MySQL connection");
+                        Some(db)
+                    },
+                    Err(e) => {
+                        error!("Failed to create database connection: {}", e);
+                        None
+                    }
+                }
+            },
+            DatabaseType::SurrealDB => {
+                debug!("Creating SurrealDB connection");
+                match Self::create_surrealdb_datasource::<T>(config) {
+                    Ok(db) => {
+                        info!("Successfully created SurrealDB connection");
                         Some(db)
                     },
                     Err(e) => {
-                        eprintln!("Failed to create database connection: {}", e);
+                        error!("Failed to create database connection: {}", e);
                         None
                     }
                 }
             },
             _ => {
-                eprintln!("Unsupported database type: {:?}", config.database.db_type);
+                error!("Unsupported database type: {:?}", config.database.db_type);
                 None
             }
         };
@@ -52,102 +77,124 @@ impl DataSourceFactory {
             
             // First process advanced entities
             for entity in &config.entities_advanced {
-                println!("Setting up advanced entity: {}", entity.name);
+                debug!("Setting up advanced entity: {}", entity.name);
                 if !processed_entities.contains(&entity.name) {
                     datasources.insert(entity.name.clone(), db.box_clone());
                     processed_entities.insert(entity.name.clone());
-                    println!("Successfully created datasource for advanced entity: {}", entity.name);
+                    info!("Successfully created datasource for advanced entity: {}", entity.name);
                 }
             }
             
             // Then process basic entities
             for entity in &config.entities_basic {
-                println!("Setting up basic entity: {}", entity.name);
+                debug!("Setting up basic entity: {}", entity.name);
                 if !processed_entities.contains(&entity.name) {
                     datasources.insert(entity.name.clone(), db.box_clone());
                     processed_entities.insert(entity.name.clone());
-                    println!("Successfully created datasource for basic entity: {}", entity.name);
+                    info!("Successfully created datasource for basic entity: {}", entity.name);
+                }
+            }
+
+            // Then the entities only the versions of the API define
+            for entity in config.stored_entities() {
+                if !processed_entities.contains(&entity.name) {
+                    debug!("Setting up versioned entity: {}", entity.name);
+                    datasources.insert(entity.name.clone(), db.box_clone());
+                    processed_entities.insert(entity.name.clone());
+                    info!("Successfully created datasource for versioned entity: {}", entity.name);
                 }
             }
         } else {
-            eprintln!("Failed to create database connection, no datasources will be available");
+            error!("Failed to create database connection, no datasources will be available");
         }
         
-        println!("Total datasources created: {}", datasources.len());
-        println!("Datasource keys: {:?}", datasources.keys().collect::<Vec<_>>());
+        info!("Total datasources created: {}", datasources.len());
+        debug!("Datasource keys: {:?}", datasources.keys().collect::<Vec<_>>());
         
         datasources
     }
     
+    /// Creates in-memory datasources for all entities, seeded with generated fake data.
+    /// No database connection is made, which makes it suitable for mock servers.
+    pub fn create_mock_datasources<T: 'static + ApiEntity + Serialize + DeserializeOwned + Send + Sync>(
+        config: &Config,
+        rows_per_entity: usize,
+    ) -> HashMap<String, Box<dyn DataSource<T>>> {
+        let mut datasources = HashMap::new();
+        let all_entities = config.stored_entities();
+        
+        let db = InMemoryDatasource::new();
+        db.configure_entities(&all_entities);
+        
+        for entity in &all_entities {
+            let rows = fake_data::generate_rows(entity, rows_per_entity);
+            if let Err(e) = db.seed(&entity.name, rows) {
+                error!("Failed to seed mock data for entity {}: {}", entity.name, e);
+                continue;
+            }
+            info!("Seeded {} mock rows for entity: {}", rows_per_entity, entity.name);
+            datasources.insert(entity.name.clone(), Box::new(db.clone()) as Box<dyn DataSource<T>>);
+        }
+        
+        datasources
+    }
+    
+    /// Creates datasources for all entities running their SQL through a transport provided
+    /// by the host, such as a D1 binding or an HTTP SQL API on edge runtimes
+    pub fn create_remote_sql_datasources<T: 'static + ApiEntity + Serialize + DeserializeOwned + Send + Sync>(
+        config: &Config,
+        transport: Arc<dyn SqlTransport>,
+    ) -> HashMap<String, Box<dyn DataSource<T>>> {
+        let all_entities = config.stored_entities();
+        let mut db = RemoteSqlDatasource::new(transport);
+        db.configure_entity_mappings(&all_entities);
+        
+        all_entities.iter()
+            .map(|entity| (entity.name.clone(), Box::new(db.clone()) as Box<dyn DataSource<T>>))
+            .collect()
+    }
+    
     /// Creates a MariaDB datasource with entity mappings configured
+    #[cfg(feature = "native")]
     fn create_mariadb_datasource<T: 'static + ApiEntity + Serialize + DeserializeOwned + Send + Sync>(
         config: &Config
     ) -> Result<Box<dyn DataSource<T>>, Box<dyn Error>> {
-        println!("Creating MariaDB datasource with connection string: {}", 
+        debug!("Creating MariaDB datasource with connection string: {}", 
                 &config.database.connection_string);
         
         // Create the MariaDB datasource
         let mut db = MariaDbDatasource::new(&config.database);
         
         // Collect all entities (both advanced and basic) for mapping
-        let mut all_entities = config.entities_advanced.clone();
+        let all_entities = config.stored_entities();
         
-        // Convert basic entities to advanced format and add them
-        let basic_entities: Vec<Entity> = config.entities_basic.iter().map(|e| {
-            Entity {
-                name: e.name.clone(),
-                table_name: e.table_name.clone(),
-                fields: e.fields.iter().map(|f| {
-                    crate::config::specific::entity_config::Field {
-                        name: f.name.clone(),
-                        column_name: Some(f.name.clone()),
-                        data_type: f.data_type.clone(),
-                        required: f.required,
-                        unique: false,
-                        searchable: true,
-                        default_value: None,
-                        description: None,
-                    }
-                }).collect(),
-                relationships: Vec::new(),
-                endpoints: crate::config::specific::entity_config::EndpointConfig {
-                    generate_create: true,
-                    generate_read: true,
-                    generate_update: true,
-                    generate_delete: true,
-                    generate_list: true,
-                    custom_routes: Vec::new(),
-                },
-                authentication: e.authentication,
-                authorization: crate::config::specific::entity_config::Authorization {
-                    active: false,
-                    roles: Vec::new(),
-                    permissions: Vec::new(),
-                },
-                validations: Vec::new(),
-                pagination: None,
-            }
-        }).collect();
-        
-        // Add basic entities to the collection
-        all_entities.extend(basic_entities);
-        
-        println!("Configuring entity mappings for {} entities", all_entities.len());
+        debug!("Configuring entity mappings for {} entities", all_entities.len());
         for entity in &all_entities {
-            println!("  - Entity: {}, Table: {:?}", entity.name, entity.table_name);
+            debug!("  - Entity: {}, Table: {:?}", entity.name, entity.table_name);
         }
         
         // Configure entity mappings for the database
         match db.configure_entity_mappings(&all_entities) {
             Ok(_) => {
-                println!("Entity mappings configured successfully");
+                debug!("Entity mappings configured successfully");
                 Ok(Box::new(db) as Box<dyn DataSource<T>>)
             },
             Err(e) => {
-                eprintln!("Failed to configure entity mappings: {}", e);
+                error!("Failed to configure entity mappings: {}", e);
                 Err(e)
             }
         }
     }
+    
+    /// Creates a SurrealDB datasource with entity mappings configured
+    #[cfg(feature = "native")]
+    fn create_surrealdb_datasource<T: 'static + ApiEntity + Serialize + DeserializeOwned + Send + Sync>(
+        config: &Config
+    ) -> Result<Box<dyn DataSource<T>>, Box<dyn Error>> {
+        debug!("Creating SurrealDB datasource at {}", config.database.make_url());
+        
+        let mut db = SurrealDbDatasource::new(&config.database)?;
+        db.configure_entity_mappings(&config.stored_entities())?;
+        Ok(Box::new(db) as Box<dyn DataSource<T>>)
+    }
 }
-
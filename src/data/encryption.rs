@@ -0,0 +1,110 @@
+use std::error::Error;
+use std::fs;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use crate::config::specific::encryption_config::EncryptionConfig;
+use crate::data::datasource::base::DataSourceError;
+
+/// Header written at the start of every encrypted file
+const MAGIC: &[u8; 4] = b"RWST";
+/// Version of the encrypted file format
+const FORMAT_VERSION: u8 = 1;
+/// Length of the AES-GCM nonce in bytes
+const NONCE_LEN: usize = 12;
+/// Length of an AES-256 key in bytes
+const KEY_LEN: usize = 32;
+
+/// Encrypts and decrypts files with AES-256-GCM.
+///
+/// Encrypted files are laid out as `RWST | version | nonce | ciphertext+tag`,
+/// so they can be recognized and decrypted transparently on import.
+pub struct FileEncryptor {
+    cipher: Aes256Gcm,
+}
+
+impl FileEncryptor {
+    /// Creates an encryptor from a raw 256-bit key
+    pub fn new(key: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if key.len() != KEY_LEN {
+            return Err(Box::new(DataSourceError::ValidationError(format!(
+                "Encryption key must be {} bytes, got {}", KEY_LEN, key.len()
+            ))));
+        }
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        })
+    }
+
+    /// Creates an encryptor resolving the key reference of an encryption configuration
+    pub fn from_config(config: &EncryptionConfig) -> Result<Self, Box<dyn Error>> {
+        Self::new(&resolve_key(&config.key)?)
+    }
+
+    /// Encrypts data with a fresh random nonce
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext).map_err(|_| {
+            DataSourceError::SerializationError("Failed to encrypt data".to_string())
+        })?;
+
+        let mut output = Vec::with_capacity(MAGIC.len() + 1 + NONCE_LEN + ciphertext.len());
+        output.extend_from_slice(MAGIC);
+        output.push(FORMAT_VERSION);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    /// Decrypts data produced by `encrypt`, failing if it was tampered with or the key is wrong
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        if !is_encrypted(data) {
+            return Err(Box::new(DataSourceError::ValidationError("Data is not encrypted by Rawst".to_string())));
+        }
+        if data[MAGIC.len()] != FORMAT_VERSION {
+            return Err(Box::new(DataSourceError::ValidationError(format!(
+                "Unsupported encrypted file version {}", data[MAGIC.len()]
+            ))));
+        }
+
+        let nonce_start = MAGIC.len() + 1;
+        let nonce = Nonce::from_slice(&data[nonce_start..nonce_start + NONCE_LEN]);
+        self.cipher.decrypt(nonce, &data[nonce_start + NONCE_LEN..]).map_err(|_| {
+            Box::new(DataSourceError::ValidationError(
+                "Failed to decrypt data: wrong key or corrupted file".to_string()
+            )) as Box<dyn Error>
+        })
+    }
+}
+
+/// Whether the data starts with the encrypted file header
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() > MAGIC.len() + 1 + NONCE_LEN && data.starts_with(MAGIC)
+}
+
+/// Resolves a key reference into the raw key bytes.
+///
+/// Supported references:
+/// * `env:VAR` - base64 key read from an environment variable
+/// * `file:/path` - base64 key read from a file
+/// * `base64:<key>` - inline base64 key (for development only)
+pub fn resolve_key(reference: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let encoded = if let Some(var) = reference.strip_prefix("env:") {
+        std::env::var(var).map_err(|_| {
+            DataSourceError::ValidationError(format!("Encryption key variable {} is not set", var))
+        })?
+    } else if let Some(path) = reference.strip_prefix("file:") {
+        fs::read_to_string(path)?
+    } else if let Some(inline) = reference.strip_prefix("base64:") {
+        inline.to_string()
+    } else {
+        return Err(Box::new(DataSourceError::ValidationError(format!(
+            "Unsupported encryption key reference: {}", reference
+        ))));
+    };
+
+    BASE64.decode(encoded.trim()).map_err(|e| {
+        Box::new(DataSourceError::ValidationError(format!("Encryption key is not valid base64: {}", e))) as Box<dyn Error>
+    })
+}
@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use crate::api::common::api_entity::ApiEntity;
+use crate::config::specific::encryption_config::EncryptionConfig;
+use crate::config::specific::entity_config::Entity;
+use crate::data::datasource::base::{DataSource, DataSourceError};
+use crate::data::encryption::{is_encrypted, FileEncryptor};
+
+/// Writes a file produced from the server (export, backup), encrypting it when configured
+pub fn write_file(path: &Path, contents: &[u8], encryption: Option<&EncryptionConfig>) -> Result<(), Box<dyn Error>> {
+    match encryption {
+        Some(config) => {
+            let encryptor = FileEncryptor::from_config(config)?;
+            fs::write(path, encryptor.encrypt(contents)?)?;
+        }
+        None => fs::write(path, contents)?,
+    }
+    Ok(())
+}
+
+/// Reads a file written by `write_file`, decrypting it when it is encrypted
+pub fn read_file(path: &Path, encryption: Option<&EncryptionConfig>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let data = fs::read(path)?;
+    if !is_encrypted(&data) {
+        return Ok(data);
+    }
+
+    let config = encryption.ok_or_else(|| {
+        DataSourceError::ValidationError(format!("{} is encrypted but no encryption key is configured", path.display()))
+    })?;
+    FileEncryptor::from_config(config)?.decrypt(&data)
+}
+
+/// Exports every item of an entity to a JSON array file, encrypted with the entity's key when configured.
+/// Returns the number of exported items.
+pub fn export_records<T: ApiEntity>(
+    datasource: &dyn DataSource<T>,
+    entity: &Entity,
+    path: &Path,
+) -> Result<usize, Box<dyn Error>> {
+    let items = datasource.get_all(Some(&entity.name))?;
+    let contents = serde_json::to_vec_pretty(&items)?;
+    write_file(path, &contents, entity.encryption.as_ref())?;
+    Ok(items.len())
+}
+
+/// Imports the items of a JSON array (or newline-delimited JSON) file into an entity,
+/// decrypting the file first when it is encrypted. Returns the number of imported items.
+pub fn import_records<T: ApiEntity>(
+    datasource: &dyn DataSource<T>,
+    entity: &Entity,
+    path: &Path,
+) -> Result<usize, Box<dyn Error>> {
+    let data = read_file(path, entity.encryption.as_ref())?;
+    let text = String::from_utf8(data).map_err(|_| {
+        DataSourceError::SerializationError(format!("{} is not valid UTF-8", path.display()))
+    })?;
+
+    let values: Vec<Value> = match serde_json::from_str::<Value>(&text) {
+        Ok(Value::Array(items)) => items,
+        Ok(item) => vec![item],
+        Err(_) => text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?,
+    };
+
+    let mut imported = 0;
+    for value in values {
+        let item: T = serde_json::from_value(value)?;
+        datasource.create(item, Some(&entity.name))?;
+        imported += 1;
+    }
+    Ok(imported)
+}
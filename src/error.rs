@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +9,7 @@ pub enum RusterApiError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
+    #[cfg(feature = "native")]
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
 
@@ -23,9 +25,36 @@ pub enum RusterApiError {
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Unprocessable entity: {0}")]
+    UnprocessableEntity(String),
+
+    /// A rate limit was exceeded, with the seconds to wait before retrying
+    #[error("Too many requests: retry after {0}s")]
+    TooManyRequests(u64),
+
+    /// Validation rules failed, with the messages of every invalid field
+    #[error("Validation failed: {}", describe_field_errors(.0))]
+    InvalidFields(BTreeMap<String, Vec<String>>),
+
     #[error("Authentication error: {0}")]
     AuthError(String),
 
+    /// The caller is authenticated but not allowed to do what it asked
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -34,6 +63,21 @@ pub enum RusterApiError {
 
     #[error("Server error: {0}")]
     ServerError(String),
+
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
+    /// The API is in maintenance mode, with the seconds to wait before retrying
+    #[error("The API is under maintenance: retry after {0}s")]
+    Maintenance(u64),
+}
+
+/// Joins field errors into a single message, like `email: must be a valid email address`
+fn describe_field_errors(errors: &BTreeMap<String, Vec<String>>) -> String {
+    errors.iter()
+        .flat_map(|(field, messages)| messages.iter().map(move |message| format!("{}: {}", field, message)))
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 pub type Result<T> = std::result::Result<T, RusterApiError>;
@@ -0,0 +1,154 @@
+use crate::config::specific::events_config::{BrokerType, EventsConfig};
+use crate::error::{Result, RusterApiError};
+use crate::events::outbox::{Outbox, OutboxEntry};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Milliseconds Kafka keeps trying to deliver a message before failing it
+#[cfg(feature = "kafka")]
+const KAFKA_MESSAGE_TIMEOUT_MS: &str = "30000";
+
+/// Connection to the message broker of `events`
+pub enum Broker {
+    #[cfg(feature = "kafka")]
+    Kafka(rdkafka::producer::FutureProducer),
+    #[cfg(feature = "rabbitmq")]
+    RabbitMq {
+        /// Kept open for the channel
+        _connection: lapin::Connection,
+        channel: lapin::Channel,
+    },
+    #[cfg(feature = "nats")]
+    Nats(async_nats::Client),
+}
+
+/// Error of a broker, as a server error
+fn broker_error(broker: BrokerType, error: impl std::fmt::Display) -> RusterApiError {
+    RusterApiError::ServerError(format!("{:?} broker error: {}", broker, error))
+}
+
+impl Broker {
+    /// Connects to the broker of `events`. RabbitMQ channels confirm the published messages.
+    pub async fn connect(config: &EventsConfig) -> Result<Self> {
+        match config.broker {
+            #[cfg(feature = "kafka")]
+            BrokerType::Kafka => {
+                let producer = rdkafka::ClientConfig::new()
+                    .set("bootstrap.servers", &config.url)
+                    .set("enable.idempotence", "true")
+                    .set("message.timeout.ms", KAFKA_MESSAGE_TIMEOUT_MS)
+                    .create()
+                    .map_err(|e| broker_error(config.broker, e))?;
+                Ok(Broker::Kafka(producer))
+            }
+            #[cfg(feature = "rabbitmq")]
+            BrokerType::RabbitMQ => {
+                let connection = lapin::Connection::connect(&config.url, lapin::ConnectionProperties::default()).await.map_err(|e| broker_error(config.broker, e))?;
+                let channel = connection.create_channel().await.map_err(|e| broker_error(config.broker, e))?;
+                channel.confirm_select(lapin::options::ConfirmSelectOptions::default()).await.map_err(|e| broker_error(config.broker, e))?;
+                Ok(Broker::RabbitMq { _connection: connection, channel })
+            }
+            #[cfg(feature = "nats")]
+            BrokerType::NATS => Ok(Broker::Nats(async_nats::connect(&config.url).await.map_err(|e| broker_error(config.broker, e))?)),
+            #[allow(unreachable_patterns)]
+            broker => Err(RusterApiError::ConfigError(format!("The {:?} broker is not built in", broker))),
+        }
+    }
+
+    /// Publishes a change of the outbox, returning once the broker received it
+    pub async fn publish(&self, config: &EventsConfig, entry: &OutboxEntry) -> Result<()> {
+        match self {
+            #[cfg(feature = "kafka")]
+            Broker::Kafka(producer) => {
+                let key = entry.entity_id.as_deref().unwrap_or(&entry.entity);
+                let record = rdkafka::producer::FutureRecord::to(&config.topic).key(key).payload(&entry.payload);
+                producer.send(record, Duration::ZERO).await.map_err(|(e, _)| broker_error(config.broker, e))?;
+            }
+            #[cfg(feature = "rabbitmq")]
+            Broker::RabbitMq { channel, .. } => {
+                let properties = lapin::BasicProperties::default()
+                    .with_content_type("application/json".into())
+                    .with_delivery_mode(2);
+                let confirmation = channel.basic_publish(
+                    &config.topic,
+                    &format!("{}.{}", entry.entity, entry.action),
+                    lapin::options::BasicPublishOptions::default(),
+                    entry.payload.as_bytes(),
+                    properties,
+                ).await.map_err(|e| broker_error(config.broker, e))?.await.map_err(|e| broker_error(config.broker, e))?;
+                if confirmation.is_nack() {
+                    return Err(broker_error(config.broker, "the message was not acknowledged"));
+                }
+            }
+            #[cfg(feature = "nats")]
+            Broker::Nats(client) => {
+                let subject = format!("{}.{}.{}", config.topic, entry.entity, entry.action);
+                client.publish(subject, entry.payload.clone().into()).await.map_err(|e| broker_error(config.broker, e))?;
+                client.flush().await.map_err(|e| broker_error(config.broker, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Publishes the changes of the outbox to the broker, every `poll_interval_ms`. Changes are
+/// removed once the broker received them, so they are published again after a failure.
+pub struct OutboxRelay {
+    outbox: Arc<Outbox>,
+}
+
+impl OutboxRelay {
+    pub fn new(outbox: Arc<Outbox>) -> Self {
+        Self { outbox }
+    }
+
+    /// Publishes the oldest changes of the outbox in order, stopping at the first failure.
+    /// Returns the number of published changes.
+    async fn relay(&self, broker: &Broker) -> Result<usize> {
+        let config = &self.outbox.config;
+        let entries = self.outbox.pending(config.outbox.batch_size).await?;
+        for entry in &entries {
+            broker.publish(config, entry).await?;
+            self.outbox.remove(entry.id).await?;
+            debug!("Events: published {}.{} to {}", entry.entity, entry.action, config.topic);
+        }
+        Ok(entries.len())
+    }
+
+    /// Spawns a background task relaying the outbox, connecting to the broker again after failures
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let config = self.outbox.config.clone();
+            let mut broker: Option<Broker> = None;
+            let mut ticker = tokio::time::interval(Duration::from_millis(config.outbox.poll_interval_ms.max(1)));
+            loop {
+                ticker.tick().await;
+                if broker.is_none() {
+                    match Broker::connect(&config).await {
+                        Ok(connected) => broker = Some(connected),
+                        Err(e) => {
+                            warn!("Events: failed to connect to the broker at {}: {}", config.url, e);
+                            continue;
+                        }
+                    }
+                }
+                let Some(connected) = &broker else {
+                    continue;
+                };
+
+                // Full batches are followed by the next one right away
+                let result = loop {
+                    match self.relay(connected).await {
+                        Ok(published) if published >= config.outbox.batch_size.max(1) as usize => continue,
+                        result => break result,
+                    }
+                };
+                if let Err(e) = result {
+                    warn!("Events: failed to publish the outbox: {}", e);
+                    broker = None;
+                }
+            }
+        })
+    }
+}
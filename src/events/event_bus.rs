@@ -0,0 +1,125 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use crate::config::specific::health_config::HealthTargetKind;
+use crate::health::health_monitor::HealthStatus;
+
+/// Number of events kept for slow subscribers before they start missing events
+const EVENT_BUFFER: usize = 256;
+
+/// Events emitted by the server
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum RawstEvent {
+    /// The reachability of an external integration changed
+    HealthChanged {
+        target: String,
+        kind: HealthTargetKind,
+        previous: HealthStatus,
+        current: HealthStatus,
+        error: Option<String>,
+    },
+    /// The JSON shape of an endpoint response no longer matches its snapshot
+    SchemaDrift {
+        endpoint: String,
+        changes: Vec<String>,
+    },
+    /// An entity was created, updated or deleted through the API
+    EntityChanged(EntityChange),
+}
+
+/// Kinds of changes of an entity
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl ChangeAction {
+    /// Name of the action, as serialized
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeAction::Created => "created",
+            ChangeAction::Updated => "updated",
+            ChangeAction::Deleted => "deleted",
+        }
+    }
+}
+
+/// A change of an entity stored through the API
+#[derive(Serialize, Debug, Clone)]
+pub struct EntityChange {
+    /// Name of the entity, as configured
+    pub entity: String,
+    pub action: ChangeAction,
+    /// Key of the entity, when it has one
+    pub id: Option<String>,
+    /// The stored entity, except for deletions
+    pub data: Option<Value>,
+}
+
+impl EntityChange {
+    /// Removes fields from the entity of the change, like the ones its receivers may not read
+    pub fn hide(&mut self, fields: &[String]) {
+        if let Some(Value::Object(data)) = self.data.as_mut() {
+            data.retain(|name, _| !fields.contains(name));
+        }
+    }
+}
+
+/// A change as sent outside of the server, to webhooks and message brokers
+#[derive(Serialize, Debug)]
+pub struct ChangeMessage<'a> {
+    #[serde(flatten)]
+    pub change: &'a EntityChange,
+    /// Time of the message in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl<'a> ChangeMessage<'a> {
+    /// Message of a change, stamped with the current time
+    pub fn now(change: &'a EntityChange) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        Self { change, timestamp }
+    }
+}
+
+/// In-process publish/subscribe bus for server events.
+/// Clones share the same channel.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<RawstEvent>,
+}
+
+impl EventBus {
+    /// Creates a bus without subscribers
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self { sender }
+    }
+
+    /// Publishes an event to every current subscriber.
+    /// Events published without subscribers are dropped.
+    pub fn publish(&self, event: RawstEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Whether anyone is subscribed, so events that are costly to build can be skipped
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Subscribes to the events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<RawstEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,160 @@
+use crate::api::common::api_entity::ApiEntity;
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::api::handlers::hooks::{entity_change, HookRegistry};
+use crate::config::configuration::Config;
+use crate::config::specific::database_config::DatabaseType;
+use crate::config::specific::events_config::EventsConfig;
+use crate::error::{Result, RusterApiError};
+use crate::data::datasource::relational::base::{quote_identifier, validate_identifier};
+use crate::events::event_bus::{ChangeAction, ChangeMessage, EntityChange};
+use sqlx::mysql::MySqlPoolOptions;
+use sqlx::{MySql, Pool, Row};
+use std::sync::Arc;
+use tokio::runtime::Handle;
+use tokio::sync::OnceCell;
+
+/// A change waiting in the outbox to be published
+pub struct OutboxEntry {
+    /// Position of the change in the outbox
+    pub id: u64,
+    pub entity: String,
+    /// Name of the action, like `created`
+    pub action: String,
+    /// Key of the entity, when it has one
+    pub entity_id: Option<String>,
+    /// JSON message of the change
+    pub payload: String,
+}
+
+/// Outbox table of `events` in the MySQL or MariaDB database. The endpoints write the changes
+/// they store to it before answering, and the relay publishes them to the broker in order,
+/// removing them once the broker received them.
+pub struct Outbox {
+    pool: Pool<MySql>,
+    /// Quoted name of the table
+    table: String,
+    created: OnceCell<()>,
+    pub config: EventsConfig,
+}
+
+impl Outbox {
+    /// Checks that the database can hold the outbox table of `events`
+    pub fn validate(config: &Config) -> Result<()> {
+        let Some(events) = &config.events else {
+            return Ok(());
+        };
+        if !matches!(config.database.db_type, DatabaseType::MySQL) {
+            return Err(RusterApiError::ConfigError(format!(
+                "events requires a MySQL or MariaDB database for its outbox table, not {}", config.database.db_type
+            )));
+        }
+        validate_identifier(&events.outbox.table)
+            .map_err(|e| RusterApiError::ConfigError(format!("Invalid events.outbox.table: {}", e)))
+    }
+
+    /// Outbox of `events`, None when the configuration publishes no change. The database is
+    /// connected to on first use, from the current Tokio runtime.
+    pub fn from_config(config: &Config) -> Result<Option<Arc<Self>>> {
+        let Some(events) = &config.events else {
+            return Ok(None);
+        };
+        Self::validate(config)?;
+        if Handle::try_current().is_err() {
+            return Err(RusterApiError::ConfigError("events requires the API to be built on a Tokio runtime".to_string()));
+        }
+        let database = config.database.with_secrets().map_err(|e| RusterApiError::ConfigError(e.to_string()))?;
+        let pool = MySqlPoolOptions::new()
+            .max_connections(database.max_connections.unwrap_or(5))
+            .connect_lazy(&database.make_url())?;
+        Ok(Some(Arc::new(Self {
+            pool,
+            table: quote_identifier(&events.outbox.table),
+            created: OnceCell::new(),
+            config: events.clone(),
+        })))
+    }
+
+    /// Creates the table when missing, once
+    async fn create_table(&self) -> Result<()> {
+        self.created.get_or_try_init(|| async {
+            let sql = format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+                    entity VARCHAR(255) NOT NULL,
+                    action VARCHAR(16) NOT NULL,
+                    entity_id VARCHAR(255) NULL,
+                    payload LONGTEXT NOT NULL,
+                    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+                )",
+                self.table
+            );
+            sqlx::query(&sql).execute(&self.pool).await.map(|_| ())
+        }).await?;
+        Ok(())
+    }
+
+    /// Writes a change to the outbox, blocking until it is stored. Runs off the async runtime,
+    /// like the endpoints.
+    pub fn append(&self, change: &EntityChange) -> Result<()> {
+        let payload = serde_json::to_string(&ChangeMessage::now(change))?;
+        let handle = Handle::try_current()
+            .map_err(|_| RusterApiError::ServerError("The outbox is used outside of a Tokio runtime".to_string()))?;
+        handle.block_on(async {
+            self.create_table().await?;
+            let sql = format!("INSERT INTO {} (entity, action, entity_id, payload) VALUES (?, ?, ?, ?)", self.table);
+            sqlx::query(&sql)
+                .bind(&change.entity)
+                .bind(change.action.name())
+                .bind(change.id.as_deref())
+                .bind(payload)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Oldest changes of the outbox, in the order they were stored
+    pub async fn pending(&self, limit: u32) -> Result<Vec<OutboxEntry>> {
+        self.create_table().await?;
+        let sql = format!("SELECT id, entity, action, entity_id, payload FROM {} ORDER BY id LIMIT ?", self.table);
+        let rows = sqlx::query(&sql).bind(limit.max(1)).fetch_all(&self.pool).await?;
+        rows.iter()
+            .map(|row| Ok(OutboxEntry {
+                id: row.try_get("id")?,
+                entity: row.try_get("entity")?,
+                action: row.try_get("action")?,
+                entity_id: row.try_get("entity_id")?,
+                payload: row.try_get("payload")?,
+            }))
+            .collect()
+    }
+
+    /// Removes a published change
+    pub async fn remove(&self, id: u64) -> Result<()> {
+        let sql = format!("DELETE FROM {} WHERE id = ?", self.table);
+        sqlx::query(&sql).bind(id).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Registers hooks writing the changes of the published entities to the outbox, without the
+    /// passwords and the fields requiring read roles. A change that cannot be written fails its
+    /// request, although it is already stored.
+    pub fn register<T: ApiEntity>(self: &Arc<Self>, mut hooks: HookRegistry<T>, config: &Config) -> HookRegistry<T> {
+        for entity in config.all_entities().into_iter().filter(|entity| self.config.publishes(&entity.name)) {
+            let key = entity.key_field().map(|field| field.name.clone());
+            // Brokers deliver the changes to any consumer, as to a caller without roles
+            let hidden = FieldAccess::new(&entity).hidden(None);
+            let outbox = self.clone();
+            let appender = move |entity: &str, action: ChangeAction, id: Option<&str>, item: Option<&T>| -> Result<()> {
+                outbox.append(&entity_change(entity, key.as_deref(), &hidden, action, id, item)?)
+            };
+
+            let append = appender.clone();
+            hooks.after_create(&entity.name, move |context, item| append(context.entity, ChangeAction::Created, context.id, Some(item)));
+            let append = appender.clone();
+            hooks.after_update(&entity.name, move |context, item| append(context.entity, ChangeAction::Updated, context.id, Some(item)));
+            hooks.after_delete(&entity.name, move |context| appender(context.entity, ChangeAction::Deleted, context.id, None));
+        }
+        hooks
+    }
+}
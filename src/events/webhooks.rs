@@ -0,0 +1,149 @@
+use crate::api::handlers::common::field_access::FieldAccess;
+use crate::config::configuration::Config;
+use crate::config::specific::entity_config::{WebhookConfig, WebhookEvent};
+use crate::events::event_bus::{ChangeAction, ChangeMessage, EventBus, RawstEvent};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, warn};
+
+/// Header holding the HMAC-SHA256 signature of the payload, when the webhook has a secret
+pub const SIGNATURE_HEADER: &str = "X-Rawst-Signature";
+/// Header naming the change, like `users.created`
+pub const EVENT_HEADER: &str = "X-Rawst-Event";
+/// Header identifying a delivery, the same across its retries so receivers can drop duplicates
+pub const DELIVERY_HEADER: &str = "X-Rawst-Delivery";
+
+/// Whether a webhook asked for the changes of an action
+fn subscribed(webhook: &WebhookConfig, action: ChangeAction) -> bool {
+    webhook.events.is_empty() || webhook.events.iter().any(|event| match event {
+        WebhookEvent::Created => action == ChangeAction::Created,
+        WebhookEvent::Updated => action == ChangeAction::Updated,
+        WebhookEvent::Deleted => action == ChangeAction::Deleted,
+    })
+}
+
+/// Hex HMAC-SHA256 of a body
+fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// POSTs the changes of the entities to the webhooks of their configuration, off the requests
+/// storing them. Failed deliveries are retried with the policy of the webhook, then dropped.
+pub struct WebhookDispatcher {
+    /// Webhooks by lowercase entity name
+    webhooks: HashMap<String, Vec<Arc<WebhookConfig>>>,
+    /// Fields left out of the changes by lowercase entity name: the ones requiring read roles,
+    /// as webhooks receive them as callers without roles
+    hidden: HashMap<String, Vec<String>>,
+    events: EventBus,
+}
+
+impl WebhookDispatcher {
+    /// Dispatcher of the webhooks of a configuration, None when no entity has any
+    pub fn new(config: &Config, events: EventBus) -> Option<Self> {
+        let webhooks: HashMap<String, Vec<Arc<WebhookConfig>>> = config.all_entities().into_iter()
+            .filter(|entity| !entity.webhooks.is_empty())
+            .map(|entity| (entity.name.to_lowercase(), entity.webhooks.into_iter().map(Arc::new).collect()))
+            .collect();
+        let hidden = config.all_entities().iter()
+            .filter(|entity| webhooks.contains_key(&entity.name.to_lowercase()))
+            .map(|entity| (entity.name.to_lowercase(), FieldAccess::new(entity).hidden(None)))
+            .collect();
+        (!webhooks.is_empty()).then_some(Self { webhooks, hidden, events })
+    }
+
+    /// Spawns a background task delivering every change published from now on, each in its own
+    /// task so a slow webhook does not hold back the others
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        let mut receiver = self.events.subscribe();
+        tokio::spawn(async move {
+            let client = match reqwest::Client::builder().build() {
+                Ok(client) => client,
+                Err(e) => {
+                    error!("Webhooks: failed to create HTTP client: {}", e);
+                    return;
+                }
+            };
+
+            loop {
+                let mut change = match receiver.recv().await {
+                    Ok(RawstEvent::EntityChanged(change)) => change,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Webhooks: {} changes were not delivered as the dispatcher fell behind", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                let Some(webhooks) = self.webhooks.get(&change.entity.to_lowercase()) else {
+                    continue;
+                };
+                if let Some(hidden) = self.hidden.get(&change.entity.to_lowercase()) {
+                    change.hide(hidden);
+                }
+
+                let body = match serde_json::to_vec(&ChangeMessage::now(&change)) {
+                    Ok(body) => body,
+                    Err(e) => {
+                        error!("Webhooks: failed to serialize a change of {}: {}", change.entity, e);
+                        continue;
+                    }
+                };
+                let event = format!("{}.{}", change.entity, change.action.name());
+                for webhook in webhooks.iter().filter(|webhook| subscribed(webhook, change.action)) {
+                    tokio::spawn(deliver(client.clone(), webhook.clone(), event.clone(), body.clone()));
+                }
+            }
+        })
+    }
+}
+
+/// Delivers a payload to a webhook, retrying the attempts failing with a network error, a
+/// timeout, 429 or a 5xx status. Other statuses are final.
+async fn deliver(client: reqwest::Client, webhook: Arc<WebhookConfig>, event: String, body: Vec<u8>) {
+    let policy = &webhook.retry;
+    let delivery = uuid::Uuid::new_v4().to_string();
+    let signature = webhook.secret.as_deref().map(|secret| format!("sha256={}", signature(secret, &body)));
+    let mut backoff = Duration::from_secs(policy.backoff_seconds);
+
+    for attempt in 1..=policy.max_attempts.max(1) {
+        let mut request = client.post(&webhook.url)
+            .timeout(Duration::from_secs(policy.timeout_seconds.max(1)))
+            .header("Content-Type", "application/json")
+            .header(EVENT_HEADER, &event)
+            .header(DELIVERY_HEADER, &delivery)
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let failure = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Webhooks: delivered {} to {}", event, webhook.url);
+                return;
+            }
+            Ok(response) if response.status().is_server_error() || response.status().as_u16() == 429 => {
+                format!("{} responded with {}", webhook.url, response.status())
+            }
+            Ok(response) => {
+                warn!("Webhooks: {} rejected {} with {}", webhook.url, event, response.status());
+                return;
+            }
+            Err(e) => format!("{} is unreachable: {}", webhook.url, e),
+        };
+
+        if attempt >= policy.max_attempts {
+            error!("Webhooks: dropping {} after {} attempts: {}", event, attempt, failure);
+            return;
+        }
+        warn!("Webhooks: attempt {} of {} failed, retrying in {} seconds: {}", attempt, event, backoff.as_secs(), failure);
+        tokio::time::sleep(backoff).await;
+        backoff = backoff.saturating_mul(2);
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use crate::config::specific::health_config::{HealthConfig, HealthTargetKind};
+#[cfg(feature = "native")]
+use std::time::Duration;
+#[cfg(feature = "native")]
+use crate::config::specific::health_config::HealthTarget;
+#[cfg(feature = "native")]
+use crate::events::event_bus::{EventBus, RawstEvent};
+#[cfg(feature = "native")]
+use tracing::{error, info, warn};
+
+/// Reachability of an integration
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Not checked yet
+    Unknown,
+    Up,
+    Down,
+}
+
+/// Latest check result of an integration
+#[derive(Serialize, Debug, Clone)]
+pub struct TargetHealth {
+    pub name: String,
+    pub kind: HealthTargetKind,
+    pub url: String,
+    pub status: HealthStatus,
+    /// Time of the last check in seconds since the Unix epoch
+    pub last_checked: Option<u64>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+}
+
+/// Shared registry with the health of every checked integration.
+/// Clones share the same state.
+#[derive(Clone, Default)]
+pub struct HealthRegistry {
+    targets: Arc<RwLock<Vec<TargetHealth>>>,
+}
+
+impl HealthRegistry {
+    /// Creates a registry with the configured targets in the unknown state
+    pub fn new(config: Option<&HealthConfig>) -> Self {
+        let targets = config.map(|c| c.targets.iter().map(|target| TargetHealth {
+            name: target.name.clone(),
+            kind: target.kind,
+            url: target.url.clone(),
+            status: HealthStatus::Unknown,
+            last_checked: None,
+            last_error: None,
+            consecutive_failures: 0,
+        }).collect()).unwrap_or_default();
+
+        Self { targets: Arc::new(RwLock::new(targets)) }
+    }
+
+    /// Returns the health of every target
+    pub fn snapshot(&self) -> Vec<TargetHealth> {
+        self.targets.read().unwrap().clone()
+    }
+
+    /// Whether no target is down
+    pub fn is_healthy(&self) -> bool {
+        self.targets.read().unwrap().iter().all(|t| t.status != HealthStatus::Down)
+    }
+
+    /// Records a check result, returning the previous status.
+    /// Hosts without the monitor (such as edge runtimes) can record their own checks.
+    pub fn record(&self, name: &str, error: Option<String>) -> Option<HealthStatus> {
+        let mut targets = self.targets.write().unwrap();
+        let target = targets.iter_mut().find(|t| t.name == name)?;
+        let previous = target.status;
+
+        target.last_checked = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs());
+        match error {
+            Some(error) => {
+                target.status = HealthStatus::Down;
+                target.consecutive_failures += 1;
+                target.last_error = Some(error);
+            }
+            None => {
+                target.status = HealthStatus::Up;
+                target.consecutive_failures = 0;
+                target.last_error = None;
+            }
+        }
+        Some(previous)
+    }
+}
+
+/// Periodically checks the reachability of the configured webhooks and proxies,
+/// updating the registry and publishing status transitions to the event bus
+#[cfg(feature = "native")]
+pub struct HealthMonitor {
+    config: HealthConfig,
+    registry: HealthRegistry,
+    events: EventBus,
+}
+
+#[cfg(feature = "native")]
+impl HealthMonitor {
+    pub fn new(config: HealthConfig, registry: HealthRegistry, events: EventBus) -> Self {
+        Self { config, registry, events }
+    }
+
+    /// Checks a target once. Any response below 500 counts as reachable,
+    /// since webhooks commonly reject requests without a payload.
+    async fn check_target(client: &reqwest::Client, target: &HealthTarget) -> Option<String> {
+        match client.head(&target.url).send().await {
+            Ok(response) if response.status().is_server_error() => {
+                Some(format!("{} responded with {}", target.url, response.status()))
+            }
+            Ok(_) => None,
+            Err(e) => Some(format!("{} is unreachable: {}", target.url, e)),
+        }
+    }
+
+    /// Checks every target once
+    pub async fn check_all(&self, client: &reqwest::Client) {
+        for target in &self.config.targets {
+            let error = Self::check_target(client, target).await;
+            let current = if error.is_some() { HealthStatus::Down } else { HealthStatus::Up };
+
+            if let Some(previous) = self.registry.record(&target.name, error.clone()) {
+                if previous != current {
+                    match &error {
+                        Some(e) => warn!("Health: {} is down: {}", target.name, e),
+                        None => info!("Health: {} is up", target.name),
+                    }
+                    self.events.publish(RawstEvent::HealthChanged {
+                        target: target.name.clone(),
+                        kind: target.kind,
+                        previous,
+                        current,
+                        error,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Spawns a background task running the checks every `interval_seconds`
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let client = match reqwest::Client::builder()
+                .timeout(Duration::from_secs(self.config.timeout_seconds.max(1) as u64))
+                .build()
+            {
+                Ok(client) => client,
+                Err(e) => {
+                    error!("Health: failed to create HTTP client: {}", e);
+                    return;
+                }
+            };
+
+            let mut ticker = tokio::time::interval(Duration::from_secs(self.config.interval_seconds.max(1) as u64));
+            loop {
+                ticker.tick().await;
+                self.check_all(&client).await;
+            }
+        })
+    }
+}
@@ -2,6 +2,8 @@ pub mod data {
     pub mod datasource {
         pub mod document {
             pub mod base;
+            #[cfg(feature = "native")]
+            pub mod surrealdb;
         }
         pub mod file {
             pub mod base;
@@ -9,27 +11,50 @@ pub mod data {
         }
         pub mod relational {
             pub mod base;
+            #[cfg(feature = "native")]
+            pub mod introspection;
+            #[cfg(feature = "native")]
             pub mod mariadb;
+            pub mod remote_sql;
            
         }
+        pub mod memory {
+            pub mod fake_data;
+            pub mod in_memory;
+        }
         pub mod base;
     }
 
     pub mod datasource_factory;
+    pub mod encryption;
+    pub mod transfer;
 }
 
 pub mod config {
+    pub mod compatibility;
     pub mod configuration;
+    pub mod includes;
+    pub mod migration;
+    pub mod profiles;
+    pub mod schema;
+    pub mod secrets;
     pub mod shared;
     pub mod specific {
         pub mod api_config;
         pub mod auth_config;
+        pub mod backup_config;
         pub mod cors_config;
         pub mod database_config;
         pub mod documentation_config;
+        pub mod encryption_config;
         pub mod entity_config;
+        pub mod events_config;
+        pub mod health_config;
         pub mod server_config;
+        pub mod tenancy_config;
+        pub mod version_config;
     }
+    pub mod validation;
 }
 
 pub mod api {
@@ -37,36 +62,144 @@ pub mod api {
 
     pub mod adapters {
         pub mod api_adapter;
+        #[cfg(feature = "native")]
+        pub mod authentication;
+        #[cfg(feature = "native")]
+        pub mod change_access;
+        #[cfg(feature = "native")]
+        pub mod jwt;
+        #[cfg(feature = "native")]
+        pub mod ldap;
+        #[cfg(feature = "native")]
+        pub mod maintenance;
+        pub mod middleware;
+        #[cfg(feature = "native")]
+        pub mod oauth;
+        #[cfg(feature = "native")]
+        pub mod proxy;
+        pub mod record_replay;
+        #[cfg(feature = "native")]
+        pub mod request_timeout;
+        #[cfg(feature = "native")]
+        pub mod response_cache;
+        pub mod router;
+        #[cfg(feature = "native")]
+        pub mod serving;
+        pub mod schema_drift;
+        #[cfg(feature = "native")]
+        pub mod session;
+        #[cfg(feature = "native")]
+        pub mod subscriptions;
+        #[cfg(feature = "native")]
+        pub mod tenancy;
+        #[cfg(feature = "native")]
+        pub mod uploads;
     }
 
     pub mod common {
         pub mod api_entity;
     }
 
+    pub mod docs {
+        pub mod admin_panel;
+        pub mod openapi;
+    }
+
+    #[cfg(feature = "graphql")]
+    pub mod graphql {
+        pub mod schema;
+    }
+
+    pub mod grpc {
+        pub mod proto;
+        #[cfg(feature = "grpc")]
+        pub mod server;
+    }
+
+    #[cfg(feature = "native")]
     pub mod rocket {
         pub mod rocket_adapter;
+        pub mod rate_limit;
        
         pub mod handlers {
-            pub mod catch_all;   
+            pub mod catch_all;
+            pub mod catchers;
+            pub mod docs;
+            pub mod events;
+            pub mod metrics;
+            pub mod health;
+            pub mod maintenance;
+            pub mod oauth;
+            pub mod routes;
+            pub mod schema_drift;
+            pub mod subscriptions;
+            pub mod uploads;
+            #[cfg(feature = "graphql")]
+            pub mod graphql;
+        }
+
+        pub mod fairings {
+            pub mod cors;
         }
     }
 
+    #[cfg(feature = "actix")]
+    pub mod actix {
+        pub mod actix_adapter;
+    }
+
     pub mod handlers {
+        #[cfg(feature = "native")]
+        pub mod auth;
+        pub mod hooks;
         pub mod manager;
         pub mod common {
+            pub mod authorization;
+            pub mod computed;
+            pub mod dates;
+            pub mod defaults;
+            pub mod etag;
+            pub mod field_access;
+            pub mod fieldset;
+            pub mod filter;
+            pub mod filter_expression;
+            pub mod http_cache;
+            pub mod idempotency;
+            pub mod ids;
+            pub mod immutable;
+            pub mod include;
+            pub mod negotiation;
+            pub mod ownership;
+            pub mod password;
+            pub mod policy;
+            pub mod request_id;
+            pub mod soft_delete;
+            pub mod tenancy;
+            pub mod unique;
             pub mod utils;
+            pub mod validation;
         }
 
         pub mod crud {
+            pub mod batch;
+            pub mod bulk_update;
+            pub mod count;
             pub mod create;
             pub mod delete;
+            pub mod distinct;
+            pub mod export;
             pub mod read;
             pub mod update;
             pub mod list;
+            pub mod nested;
+            pub mod relationships;
+            pub mod trash;
         }
 
         pub mod custom {
             pub mod routes;
+            #[cfg(feature = "scripting")]
+            pub mod script;
         }
     }
 }
@@ -74,6 +207,32 @@ pub mod serialization {
     pub mod serialization_service;
 }
 
+pub mod backup {
+    pub mod backup_manager;
+}
+
+pub mod events {
+    #[cfg(any(feature = "kafka", feature = "rabbitmq", feature = "nats"))]
+    pub mod broker;
+    pub mod event_bus;
+    #[cfg(feature = "native")]
+    pub mod outbox;
+    #[cfg(feature = "native")]
+    pub mod webhooks;
+}
+
+pub mod health {
+    pub mod health_monitor;
+}
+
+#[cfg(feature = "native")]
+pub mod logging {
+    pub mod logger;
+    #[cfg(feature = "otel")]
+    pub mod telemetry;
+}
+
+#[cfg(feature = "native")]
 pub mod connection {
     pub mod connection_manager;
 }
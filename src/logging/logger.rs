@@ -0,0 +1,75 @@
+use crate::config::specific::server_config::{LogFormat, LogLevel, ServerConfig};
+use crate::error::Result;
+#[cfg(not(feature = "otel"))]
+use crate::error::RusterApiError;
+use std::net::IpAddr;
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+use tracing::{error, info, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Layer, Registry};
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Warning => LevelFilter::WARN,
+            LogLevel::Error => LevelFilter::ERROR,
+        }
+    }
+}
+
+/// Installs the global subscriber writing the logs to stderr, at the level and in the format of
+/// the server configuration, and exporting the spans when `server.telemetry` is set. Does nothing
+/// when a subscriber is already installed, so embedding applications can install their own.
+pub fn init(server: &ServerConfig) -> Result<()> {
+    let level = LevelFilter::from(server.logging_level);
+    let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = Vec::new();
+    let output = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    layers.push(match server.log_format {
+        LogFormat::Text => output.with_filter(level).boxed(),
+        LogFormat::Json => output.json().flatten_event(true).with_filter(level).boxed(),
+    });
+
+    if let Some(telemetry) = &server.telemetry {
+        #[cfg(feature = "otel")]
+        layers.push(crate::logging::telemetry::layer(telemetry)?.with_filter(level).boxed());
+        #[cfg(not(feature = "otel"))]
+        return Err(RusterApiError::ConfigError(format!(
+            "server.telemetry ({}) requires Rawst to be built with the otel feature",
+            telemetry.endpoint
+        )));
+    }
+
+    // An application subscriber takes precedence
+    let _ = tracing_subscriber::registry().with(layers).try_init();
+    Ok(())
+}
+
+/// Flushes what is still buffered, like the spans waiting to be exported
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    crate::logging::telemetry::shutdown();
+}
+
+/// Logs a served request with the address of its client, when known: server errors at the error
+/// level, client errors at the warning level and the rest at the info level
+pub fn log_request(method: &str, path: &str, route: Option<&str>, request_id: &str, client: Option<IpAddr>, status: u16, latency: Duration) {
+    // The entity is the first segment of the route, like `users` in `GET:users/:id`
+    let entity = route
+        .and_then(|key| key.split_once(':'))
+        .map(|(_, pattern)| pattern.strip_prefix("api/").unwrap_or(pattern))
+        .and_then(|pattern| pattern.split('/').find(|segment| !segment.is_empty()))
+        .unwrap_or_default();
+    let latency_ms = latency.as_secs_f64() * 1000.0;
+    let client = client.map(|ip| ip.to_string());
+    let client = client.as_deref();
+
+    match status {
+        500.. => error!(method, path, route, entity, request_id, client, status, latency_ms, "request failed"),
+        400..=499 => warn!(method, path, route, entity, request_id, client, status, latency_ms, "request rejected"),
+        _ => info!(method, path, route, entity, request_id, client, status, latency_ms, "request served"),
+    }
+}
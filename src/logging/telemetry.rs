@@ -0,0 +1,64 @@
+use crate::config::specific::server_config::TelemetryConfig;
+use crate::error::{Result, RusterApiError};
+use opentelemetry::global;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::{warn, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Name of the service in the traces when `service_name` is not set
+const DEFAULT_SERVICE_NAME: &str = "rawst";
+
+/// Provider exporting the spans, kept to flush them on shutdown
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Builds the layer turning the tracing spans into OpenTelemetry spans exported over OTLP/HTTP,
+/// and installs the W3C trace context propagator so requests continue the traces of their callers
+pub fn layer<S>(config: &TelemetryConfig) -> Result<impl Layer<S>>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(config.endpoint.as_str())
+        .with_headers(config.headers.clone())
+        .build()
+        .map_err(|e| RusterApiError::ConfigError(format!("Failed to create the OTLP exporter: {}", e)))?;
+
+    let service_name = config.service_name.clone().unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string());
+    let ratio = config.sample_ratio.unwrap_or(1.0).clamp(0.0, 1.0);
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio))))
+        .with_resource(Resource::builder().with_service_name(service_name.clone()).build())
+        .build();
+
+    let tracer = provider.tracer(service_name);
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+    let _ = PROVIDER.set(provider);
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Makes a span continue the trace of the `traceparent` header of a request, if any
+pub fn continue_trace(span: &Span, headers: &HashMap<String, String>) {
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(headers));
+    // Without the layer, like when an application installed its own subscriber, spans are not exported
+    let _ = span.set_parent(parent);
+}
+
+/// Exports the spans that are still buffered
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            warn!("Failed to export the remaining spans: {}", e);
+        }
+    }
+}
@@ -1,6 +1,35 @@
+use rawst::api::adapters::api_adapter::ApiAdapter;
+use rawst::api::adapters::serving::server_runtime;
 use rawst::api::common::api_entity::ApiEntity;
+use rawst::api::grpc::proto::ProtoFile;
+use rawst::backup::backup_manager::BackupManager;
+use rawst::config::compatibility::CURRENT_VERSION;
+use rawst::config::migration::{migrate_file, CURRENT_CONFIG_VERSION};
+use rawst::config::configuration::Config;
+use rawst::config::profiles::load_configuration_with_profile;
+use rawst::config::secrets::SECRETS_KEY_ENV_VAR;
+use rawst::data::encryption::{resolve_key, FileEncryptor};
+use rawst::data::datasource_factory::DataSourceFactory;
+use rawst::logging::logger;
 use serde::{Serialize, Deserialize};
 
+const DEFAULT_MOCK_ROWS: usize = 25;
+
+const USAGE: &str = "Usage:
+    rawst mock <config> [--rows <n>]            Serve the configured entities from in-memory mock data
+    rawst backup create <config>                Take a backup of the configuration and data files
+    rawst backup list <config>                  List the available backups
+    rawst backup restore <config> <backup-id>   Restore a backup over the current files
+    rawst check <config> [--target-version <v>] Report config features unsupported by a Rawst version
+    rawst migrate <config>                      Upgrade a configuration to the current config_version
+    rawst proto <config>                        Print the protobuf definitions of the gRPC service
+    rawst schema [<output>]                     Print or write the JSON Schema of the configuration files
+    rawst secrets encrypt <input> <output>      Encrypt a JSON file of secrets with the key of RAWST_SECRETS_KEY
+
+The configuration is read as YAML from .yaml and .yml files, as TOML from .toml files and as JSON otherwise.
+Set RAWST_PROFILE to a profile like prod to merge its overlay over the configuration: <config-name>.prod.<ext>
+next to it, or else prod.<ext>.";
+
 
 // Define a generic entity type that implements ApiEntity
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,8 +44,178 @@ impl ApiEntity for GenericEntity {
     }
 }
 
-#[tokio::main]
-pub async fn main() {
-    
+pub fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(|s| s.as_str()) {
+        Some("mock") => run_mock(&args[1..]),
+        Some("backup") => run_backup(&args[1..]),
+        Some("check") => run_check(&args[1..]),
+        Some("migrate") => run_migrate(&args[1..]),
+        Some("proto") => run_proto(&args[1..]),
+        Some("schema") => run_schema(&args[1..]),
+        Some("secrets") => run_secrets(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
 }
 
+/// Serves CRUD endpoints for every configured entity from an in-memory datasource
+/// seeded with fake data, without connecting to any database. The server runs on a runtime
+/// with the threads of `server.tuning`.
+fn run_mock(args: &[String]) -> Result<(), String> {
+    let config_path = args.first().ok_or_else(|| USAGE.to_string())?;
+
+    let mut rows = DEFAULT_MOCK_ROWS;
+    let mut options = args[1..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--rows" => {
+                rows = options.next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| "--rows expects a positive number".to_string())?;
+            }
+            other => return Err(format!("Unknown option: {}\n{}", other, USAGE)),
+        }
+    }
+
+    let config = load_configuration_with_profile(config_path, None).map_err(|e| e.to_string())?;
+    let runtime = server_runtime(&config).map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        logger::init(&config.server).map_err(|e| e.to_string())?;
+        let datasources = DataSourceFactory::create_mock_datasources::<serde_json::Value>(&config, rows);
+        let adapter = ApiAdapter::new(config, datasources);
+
+        println!("Starting mock server with {} rows per entity", rows);
+        adapter.start_server().await.map_err(|e| e.to_string())
+    })
+}
+
+
+/// Administers the backups configured in `backup`: create, list or restore them
+fn run_backup(args: &[String]) -> Result<(), String> {
+    let (command, config_path) = match args {
+        [command, config_path, ..] => (command.as_str(), config_path),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let config = load_configuration_with_profile(config_path, None).map_err(|e| e.to_string())?;
+    let manager = BackupManager::from_config(&config)
+        .ok_or_else(|| "Backups are not configured: add a \"backup\" section to the configuration".to_string())?;
+
+    match command {
+        "create" => {
+            let manifest = manager.create_backup().map_err(|e| e.to_string())?;
+            println!("Backup {} created with {} data files", manifest.id, manifest.files.len());
+        }
+        "list" => {
+            for backup in manager.list_backups().map_err(|e| e.to_string())? {
+                println!("{}\t{} data files{}", backup.id, backup.files.len(), if backup.encrypted { "\tencrypted" } else { "" });
+            }
+        }
+        "restore" => {
+            let backup_id = args.get(2).ok_or_else(|| USAGE.to_string())?;
+            let manifest = manager.restore(backup_id, config_path).map_err(|e| e.to_string())?;
+            println!("Backup {} restored ({} data files and the configuration)", manifest.id, manifest.files.len());
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
+
+/// Reports the features of a configuration that the targeted Rawst version does not support,
+/// failing when there is any
+fn run_check(args: &[String]) -> Result<(), String> {
+    let config_path = args.first().ok_or_else(|| USAGE.to_string())?;
+
+    let mut target_version = CURRENT_VERSION.to_string();
+    let mut options = args[1..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--target-version" => {
+                target_version = options.next()
+                    .cloned()
+                    .ok_or_else(|| "--target-version expects a version".to_string())?;
+            }
+            other => return Err(format!("Unknown option: {}\n{}", other, USAGE)),
+        }
+    }
+
+    let config = load_configuration_with_profile(config_path, None).map_err(|e| e.to_string())?;
+    let issues = config.check_compatibility(&target_version).map_err(|e| e.to_string())?;
+    if issues.is_empty() {
+        println!("{} is compatible with Rawst {}", config_path, target_version);
+        return Ok(());
+    }
+
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    Err(format!("{} uses {} features not supported by Rawst {}", config_path, issues.len(), target_version))
+}
+
+/// Upgrades a configuration file to the current config_version in place, keeping the original
+/// next to it
+fn run_migrate(args: &[String]) -> Result<(), String> {
+    let [config_path] = args else {
+        return Err(USAGE.to_string());
+    };
+    match migrate_file(config_path).map_err(|e| e.to_string())? {
+        Some(version) => println!(
+            "{} upgraded from config_version {} to {}, the original is kept as {}.v{}.bak",
+            config_path, version, CURRENT_CONFIG_VERSION, config_path, version
+        ),
+        None => println!("{} is already at config_version {}", config_path, CURRENT_CONFIG_VERSION),
+    }
+    Ok(())
+}
+
+/// Prints the `.proto` file of the gRPC service generated from the entities, for clients
+/// to generate their stubs from
+fn run_proto(args: &[String]) -> Result<(), String> {
+    let config_path = args.first().ok_or_else(|| USAGE.to_string())?;
+    let config = load_configuration_with_profile(config_path, None).map_err(|e| e.to_string())?;
+    print!("{}", ProtoFile::from_config(&config).render());
+    Ok(())
+}
+
+/// Prints the JSON Schema of the configuration files, or writes it to a file, for editors to
+/// complete and check them
+fn run_schema(args: &[String]) -> Result<(), String> {
+    let schema = serde_json::to_string_pretty(&Config::json_schema()).map_err(|e| e.to_string())?;
+    match args {
+        [] => println!("{}", schema),
+        [output] => {
+            std::fs::write(output, schema).map_err(|e| format!("Error writing {}: {}", output, e))?;
+            println!("Configuration schema written to {}", output);
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
+
+/// Encrypts a JSON object of secrets for the `secret://file/...` references, with the base64 key
+/// of `RAWST_SECRETS_KEY`
+fn run_secrets(args: &[String]) -> Result<(), String> {
+    let [command, input, output] = args else {
+        return Err(USAGE.to_string());
+    };
+    if command != "encrypt" {
+        return Err(USAGE.to_string());
+    }
+
+    let secrets = std::fs::read(input).map_err(|e| format!("Error reading {}: {}", input, e))?;
+    serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(&secrets)
+        .map_err(|e| format!("{} is not a JSON object of secrets: {}", input, e))?;
+    let key = resolve_key(&format!("env:{}", SECRETS_KEY_ENV_VAR)).map_err(|e| e.to_string())?;
+    let encrypted = FileEncryptor::new(&key)
+        .and_then(|encryptor| encryptor.encrypt(&secrets))
+        .map_err(|e| e.to_string())?;
+    std::fs::write(output, encrypted).map_err(|e| format!("Error writing {}: {}", output, e))?;
+    println!("Secrets of {} encrypted to {}", input, output);
+    Ok(())
+}